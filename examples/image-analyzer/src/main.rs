@@ -3,7 +3,7 @@ use std::io::Cursor;
 use std::sync::Arc;
 
 use async_web::middleware;
-use async_web::web::errors::AppState;
+use async_web::web::guards::RouteGuards;
 use async_web::web::resolution::error_resolution::{Configured, ErrorResolution};
 use async_web::web::{App, Method, Middleware, Request, Resolution, file, middleware, status};
 use local_ip_address::local_ip;
@@ -21,7 +21,7 @@ use crate::loaded_model::LoadedModel;
 use crate::token_output_resolution::TokenOutputResolution;
 
 #[tokio::main]
-async fn main() -> Result<(), AppState> {
+async fn main() -> async_web::Result<()> {
     let mut app = route_app().await;
 
     let _ = app.start()?;
@@ -151,6 +151,17 @@ async fn route_app() -> App {
     )
     .await;
 
+    //only jpeg and png images of up to 10 MB are captioned, others are rejected before the api limit is counted.
+    app.guard_route(
+        "/alt",
+        Method::POST,
+        RouteGuards::new()
+            .max_body(10 * 1024 * 1024)
+            .content_types(&["image/jpeg", "image/png"]),
+    )
+    .await
+    .expect("could not guard the alt route.");
+
     //homepage
     app.add_or_change_route("/", Method::GET, None, |_req| async move {
         file("public/index.html").resolve()
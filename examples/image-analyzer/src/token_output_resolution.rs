@@ -1,10 +1,8 @@
 use crate::loaded_model::LoadedModel;
 use crate::model::{Model, load_image_from_data};
 use async_stream::stream;
-use async_web::web::Resolution;
-use async_web::web::resolution::get_status_header;
+use async_web::web::{HeaderMap, Resolution};
 use candle_core::{Device, Tensor};
-use linked_hash_map::LinkedHashMap;
 use std::io::Cursor;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -30,11 +28,10 @@ impl TokenOutputResolution {
 }
 
 impl Resolution for TokenOutputResolution {
-    fn get_headers(&self) -> LinkedHashMap<String, Option<String>> {
-        let mut hmap = LinkedHashMap::new();
+    fn get_headers(&self) -> HeaderMap {
+        let mut hmap = HeaderMap::new();
 
-        let (k, v ) = get_status_header(200);
-        hmap.insert(k, Some(v));
+        hmap.set_status(200);
 
         hmap
     }
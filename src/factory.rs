@@ -1,7 +1,22 @@
+pub mod autoscale;
+pub mod drain;
+pub mod health;
+pub mod job;
+pub mod metrics;
 pub mod queue;
+pub mod scheduler;
+pub mod supervisor;
 pub mod work_manager;
 pub mod worker;
 
-pub use queue::Queue;
+pub use autoscale::AutoscaleConfig;
+pub use drain::DrainReport;
+pub use tokio_util::sync::{CancellationToken, WaitForCancellationFutureOwned};
+pub use health::WorkerHealth;
+pub use job::{Job, Work, WorkHandle, WorkResult};
+pub use metrics::{Metrics, WorkStats};
+pub use queue::{Priority, Queue, QueuePolicy};
+pub use scheduler::{JobHandle, Schedule, Scheduler};
+pub use supervisor::{Supervisor, WorkerCrash};
 pub use work_manager::WorkManager;
 pub use worker::Worker;
\ No newline at end of file
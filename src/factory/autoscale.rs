@@ -0,0 +1,112 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::{sync::Mutex, task::JoinHandle, time::Instant};
+
+use crate::factory::WorkManager;
+
+/// # Autoscale Config
+///
+/// Settings of the autoscaler that grows and shrinks the workers of a [`WorkManager`] with the work waiting and in flight.
+///
+/// Set on the app with `App::set_autoscale`.
+#[derive(Debug, Clone)]
+pub struct AutoscaleConfig {
+    /// The fewest workers kept, even while idle.
+    ///
+    /// By default (1)
+    pub min_workers: usize,
+
+    /// The most workers started, `scale_workers` also stops growing here.
+    ///
+    /// By default (100)
+    pub max_workers: usize,
+
+    /// How often the queue and workers are sampled.
+    ///
+    /// By default (1 second)
+    pub sample_every: Duration,
+
+    /// The share of workers that should be busy, between 0 and 1, lower keeps more spare workers.
+    ///
+    /// By default (0.75)
+    pub target_utilization: f64,
+
+    /// How long the workers must be more than needed before idle workers are stopped.
+    ///
+    /// By default (30 seconds)
+    pub scale_down_after: Duration,
+}
+
+impl Default for AutoscaleConfig {
+    fn default() -> Self {
+        Self {
+            min_workers: 1,
+            max_workers: 100,
+            sample_every: Duration::from_secs(1),
+            target_utilization: 0.75,
+            scale_down_after: Duration::from_secs(30),
+        }
+    }
+}
+
+impl AutoscaleConfig {
+    /// # Desired Workers
+    ///
+    /// The workers needed for the work in flight and queued at the target utilization, between the min and max.
+    pub fn desired_workers(&self, in_flight: usize, queued: usize) -> usize {
+        let utilization = self.target_utilization.clamp(0.01, 1.0);
+        let demand = (in_flight + queued) as f64 / utilization;
+
+        let max_workers = self.max_workers.max(self.min_workers);
+
+        (demand.ceil() as usize).clamp(self.min_workers, max_workers)
+    }
+}
+
+/// # Spawn
+///
+/// Spawns the autoscaler task, sampling the work manager each `sample_every`.
+///
+/// The workers grow as soon as more are needed, and shrink to the needed workers once more than needed for `scale_down_after`, only idle workers are stopped.
+pub fn spawn<R>(work_manager: Arc<Mutex<WorkManager<R>>>, config: AutoscaleConfig) -> JoinHandle<()>
+where
+    R: Send + 'static,
+{
+    tokio::task::spawn(async move {
+        work_manager
+            .lock()
+            .await
+            .set_max_workers(Some(config.max_workers.max(config.min_workers)));
+
+        //when the workers became more than needed.
+        let mut surplus_since: Option<Instant> = None;
+
+        loop {
+            let mut manager = work_manager.lock().await;
+
+            let in_flight = manager.in_flight().await;
+            let queued = manager.queued().await;
+            let workers = manager.size();
+
+            let desired = config.desired_workers(in_flight, queued);
+
+            if desired > workers {
+                manager.scale_to(desired).await;
+                surplus_since = None;
+            } else if desired < workers {
+                let since = *surplus_since.get_or_insert_with(Instant::now);
+
+                if since.elapsed() >= config.scale_down_after {
+                    manager.scale_to(desired).await;
+                    surplus_since = None;
+                }
+            } else {
+                surplus_since = None;
+            }
+
+            drop(manager);
+
+            tokio::time::sleep(config.sample_every).await;
+        }
+    })
+}
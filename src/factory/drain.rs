@@ -0,0 +1,21 @@
+use serde::Serialize;
+
+/// # Drain Report
+///
+/// What happened to the work of a [`WorkManager`](crate::factory::WorkManager) while it drained, see `WorkManager::drain`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DrainReport {
+    /// Work that finished during the drain.
+    pub completed: u64,
+    /// Work that was still waiting in the queue when the drain timed out, it was dropped.
+    pub abandoned: usize,
+    /// Work that was still running when the drain timed out, it is finished without being waited for.
+    pub running: usize,
+}
+
+impl DrainReport {
+    /// Returns true if every piece of work finished before the timeout.
+    pub fn is_clean(&self) -> bool {
+        self.abandoned == 0 && self.running == 0
+    }
+}
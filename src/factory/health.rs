@@ -0,0 +1,22 @@
+use serde::Serialize;
+
+/// # Worker Health
+///
+/// A snapshot of the state of the workers of a [`WorkManager`](crate::factory::WorkManager).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct WorkerHealth {
+    /// The amount of workers, including overflow workers.
+    pub workers: usize,
+    /// Workers that are working on a piece of work.
+    pub busy: usize,
+    /// Workers that have been working on the same piece of work for too long.
+    pub stalled: usize,
+    /// Work waiting in the queue for a worker.
+    pub queued: usize,
+    /// Panics caught in the workers, see [`Supervisor`](crate::factory::Supervisor).
+    pub panics: u64,
+    /// Emergency workers that were added because all workers were stalled.
+    pub overflow_workers: usize,
+    /// True if every worker is stalled, no new work can make progress until one finishes.
+    pub wedged: bool,
+}
@@ -0,0 +1,188 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use tokio::{sync::oneshot, time::Instant};
+use tokio_util::sync::CancellationToken;
+
+use crate::web::errors::WorkerError;
+
+/// Idiomatic type alias for a piece of work of the workers that returns R.
+pub type Work<R> = Pin<Box<dyn Future<Output = R> + Send + 'static>>;
+
+/// Idiomatic type alias for a closure that is called instead of the work of a job whose deadline passed.
+pub type ExpiredCallback = Box<dyn FnOnce() + Send + 'static>;
+
+/// Idiomatic type alias for the channel the result of a job is sent on.
+pub(crate) type Reply<R> = oneshot::Sender<Result<R, WorkerError>>;
+
+/// # Job
+///
+/// A piece of work queued for the workers, with the channel its result is sent on when the submitter awaits it.
+///
+/// A job that is cancelled is skipped, or dropped at its next await if a worker already runs it.
+///
+/// A job whose deadline passed before a worker took it is skipped, and a job running longer than its budget is dropped, so a stale backlog is not worked on long after its clients gave up.
+pub struct Job<R> {
+    pub(crate) work: Work<R>,
+    pub(crate) reply: Option<Reply<R>>,
+    pub(crate) queued_at: Instant,
+    pub(crate) cancel: CancellationToken,
+    pub(crate) deadline: Option<Instant>,
+    pub(crate) budget: Option<Duration>,
+    pub(crate) on_expired: Option<ExpiredCallback>,
+}
+
+impl<R> Job<R> {
+    /// A job whose result is dropped.
+    pub fn new(work: Work<R>) -> Self {
+        Self {
+            work,
+            reply: None,
+            queued_at: Instant::now(),
+            cancel: CancellationToken::new(),
+            deadline: None,
+            budget: None,
+            on_expired: None,
+        }
+    }
+
+    /// # With Result
+    ///
+    /// A job whose result is sent to the returned [`WorkResult`].
+    pub fn with_result(work: Work<R>) -> (Self, WorkResult<R>) {
+        let (reply, result) = WorkResult::channel();
+        let mut job = Self::new(work);
+        job.reply = Some(reply);
+
+        (job, result)
+    }
+
+    /// Cancels the job with the token instead of its own, see `WorkManager::add_work`.
+    pub fn with_cancel(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// # With Deadline
+    ///
+    /// Skips the job if no worker took it before the deadline, its result is `WorkerError::Expired`.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// # With Budget
+    ///
+    /// Drops the work if it runs longer than the budget, its result is `WorkerError::TimedOut`.
+    pub fn with_budget(mut self, budget: Duration) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// # On Expired
+    ///
+    /// Calls the closure instead of the work when the deadline passed, for example to answer the client with `503`.
+    pub fn on_expired(mut self, on_expired: impl FnOnce() + Send + 'static) -> Self {
+        self.on_expired = Some(Box::new(on_expired));
+        self
+    }
+
+    /// The token that cancels the job.
+    pub fn cancel_token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// The work of the job.
+    pub fn into_work(self) -> Work<R> {
+        self.work
+    }
+
+    /// How long ago the job was created.
+    pub fn waited(&self) -> Duration {
+        self.queued_at.elapsed()
+    }
+
+    /// Returns true if the deadline of the job passed.
+    pub fn is_expired(&self) -> bool {
+        self.deadline.is_some_and(|deadline| deadline <= Instant::now())
+    }
+}
+
+/// # Work Result
+///
+/// The result of a submitted piece of work, see `WorkManager::submit`.
+///
+/// Resolves to `WorkerError::WorkLost` if the work panicked or was dropped before it finished, or to the error of why it was not finished, such as `WorkerError::Expired`.
+pub struct WorkResult<R> {
+    result: oneshot::Receiver<Result<R, WorkerError>>,
+}
+
+impl<R> WorkResult<R> {
+    /// a result and the channel it is sent on, for work that is not queued as a job.
+    pub(crate) fn channel() -> (Reply<R>, Self) {
+        let (reply, result) = oneshot::channel();
+
+        (reply, Self { result })
+    }
+}
+
+impl<R> Future for WorkResult<R> {
+    type Output = Result<R, WorkerError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.result)
+            .poll(cx)
+            .map(|result| result.unwrap_or(Err(WorkerError::WorkLost)))
+    }
+}
+
+/// # Work Handle
+///
+/// The result of work added with `WorkManager::add_work`, which can be cancelled.
+///
+/// The work receives the token of the handle to stop cooperatively, for example between the steps of a long computation, and is dropped at its next await once cancelled.
+///
+/// Resolves to `WorkerError::Cancelled` if the work was cancelled before it finished, dropping the handle does not cancel the work.
+pub struct WorkHandle<R> {
+    cancel: CancellationToken,
+    result: WorkResult<R>,
+}
+
+impl<R> WorkHandle<R> {
+    /// a handle of the result, cancelled by the token.
+    pub(crate) fn new(cancel: CancellationToken, result: WorkResult<R>) -> Self {
+        Self { cancel, result }
+    }
+
+    /// Cancels the work, work that already finished keeps its result.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Returns true if the work was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// The token given to the work.
+    pub fn cancel_token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+}
+
+impl<R> Future for WorkHandle<R> {
+    type Output = Result<R, WorkerError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let cancelled = self.cancel.is_cancelled();
+
+        Pin::new(&mut self.result).poll(cx).map(|result| match result {
+            Err(WorkerError::WorkLost) if cancelled => Err(WorkerError::Cancelled),
+            result => result,
+        })
+    }
+}
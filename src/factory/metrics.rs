@@ -0,0 +1,171 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use serde::Serialize;
+
+/// # Metrics
+///
+/// Receives what the workers of a [`WorkManager`](crate::factory::WorkManager) do, for example to export it to a metrics system.
+///
+/// Set with `Supervisor::set_metrics`, the work manager also keeps its own counters, see `WorkManager::stats`.
+///
+/// ```ignore
+/// struct Prometheus { latency: Histogram }
+///
+/// impl Metrics for Prometheus {
+///     fn completed(&self, latency: Duration) {
+///         self.latency.observe(latency.as_secs_f64());
+///     }
+/// }
+/// ```
+pub trait Metrics: Send + Sync {
+    /// A worker took a piece of work that waited in the queue for `wait`.
+    fn started(&self, wait: Duration) {
+        let _ = wait;
+    }
+
+    /// A piece of work finished after running for `latency`.
+    fn completed(&self, latency: Duration) {
+        let _ = latency;
+    }
+
+    /// A piece of work panicked after running for `latency`.
+    fn panicked(&self, latency: Duration) {
+        let _ = latency;
+    }
+}
+
+/// the upper bounds of the latency buckets, in milliseconds.
+const BUCKETS_MS: [u64; 12] = [1, 5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000];
+
+/// # Latency Histogram
+///
+/// Counts latencies into fixed buckets from 1 millisecond to 10 seconds, and a bucket above.
+#[derive(Default)]
+pub struct LatencyHistogram {
+    buckets: [AtomicU64; BUCKETS_MS.len() + 1],
+    sum_micros: AtomicU64,
+    max_micros: AtomicU64,
+}
+
+impl LatencyHistogram {
+    /// Counts the latency.
+    pub fn record(&self, latency: Duration) {
+        let micros = latency.as_micros().min(u64::MAX as u128) as u64;
+
+        let bucket = BUCKETS_MS
+            .iter()
+            .position(|&le| micros <= le * 1_000)
+            .unwrap_or(BUCKETS_MS.len());
+
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum_micros.fetch_add(micros, Ordering::Relaxed);
+        self.max_micros.fetch_max(micros, Ordering::Relaxed);
+    }
+
+    /// # Snapshot
+    ///
+    /// The counts of the buckets, percentiles are the upper bound of the bucket they fall in.
+    pub fn snapshot(&self) -> LatencySnapshot {
+        let counts: Vec<u64> = self
+            .buckets
+            .iter()
+            .map(|bucket| bucket.load(Ordering::Relaxed))
+            .collect();
+
+        let count: u64 = counts.iter().sum();
+        let max_ms = self.max_micros.load(Ordering::Relaxed) as f64 / 1_000.0;
+
+        //the upper bound of the bucket holding the nearest rank, the max for the last bucket.
+        let percentile = |percent: u64| {
+            if count == 0 {
+                return 0.0;
+            }
+
+            let rank = (count * percent).div_ceil(100).max(1);
+            let mut seen = 0;
+
+            for (i, bucket) in counts.iter().enumerate() {
+                seen += bucket;
+
+                if seen >= rank {
+                    return BUCKETS_MS
+                        .get(i)
+                        .map_or(max_ms, |&le| (le as f64).min(max_ms));
+                }
+            }
+
+            max_ms
+        };
+
+        LatencySnapshot {
+            count,
+            sum_ms: self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000.0,
+            max_ms,
+            p50_ms: percentile(50),
+            p95_ms: percentile(95),
+            p99_ms: percentile(99),
+            buckets: BUCKETS_MS
+                .iter()
+                .map(|&le| Some(le))
+                .chain([None])
+                .zip(counts)
+                .map(|(le_ms, count)| LatencyBucket { le_ms, count })
+                .collect(),
+        }
+    }
+}
+
+/// # Latency Bucket
+///
+/// The latencies up to a bound.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LatencyBucket {
+    /// The upper bound in milliseconds, None for the latencies above every bound.
+    pub le_ms: Option<u64>,
+    pub count: u64,
+}
+
+/// # Latency Snapshot
+///
+/// The counts of a [`LatencyHistogram`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LatencySnapshot {
+    pub count: u64,
+    pub sum_ms: f64,
+    pub max_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    /// The latencies of each bucket, not cumulative.
+    pub buckets: Vec<LatencyBucket>,
+}
+
+/// # Work Stats
+///
+/// A snapshot of the saturation of a [`WorkManager`](crate::factory::WorkManager), see `WorkManager::stats`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WorkStats {
+    /// The amount of workers.
+    pub workers: usize,
+    /// Workers that are working on a piece of work.
+    pub busy: usize,
+    /// Work waiting in the queue for a worker.
+    pub queued: usize,
+    /// Work that finished.
+    pub completed: u64,
+    /// Work that panicked.
+    pub panics: u64,
+    /// Work that was cancelled before it finished.
+    pub cancelled: u64,
+    /// Work skipped because its deadline passed while it was queued.
+    pub expired: u64,
+    /// Work dropped because it ran longer than its budget.
+    pub timed_out: u64,
+    /// How long finished work ran.
+    pub latency: LatencySnapshot,
+    /// How long work waited in the queue for a worker.
+    pub queue_wait: LatencySnapshot,
+}
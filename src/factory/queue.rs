@@ -1,15 +1,85 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
 
+use serde::{Deserialize, Serialize};
 use tokio::sync::{Mutex, Notify};
 
 /// # Queue State
 /// 
-/// The state of the queue, either free or blocked.
+/// The state of the queue, either free, blocked, or full.
 pub enum QueueState<R> {
     /// The queue was free and inserted successfully
     Free, 
     /// The queue was blocked and could not insert
-    Blocked(R)
+    Blocked(R),
+    /// The queue is at its capacity and rejects values, see `Queue::set_capacity`
+    Full(R)
+}
+
+impl<R> QueueState<R> {
+    /// Maps the value given back by a blocked or full queue.
+    pub fn map<T>(self, f: impl FnOnce(R) -> T) -> QueueState<T> {
+        match self {
+            QueueState::Free => QueueState::Free,
+            QueueState::Blocked(value) => QueueState::Blocked(f(value)),
+            QueueState::Full(value) => QueueState::Full(f(value)),
+        }
+    }
+}
+
+/// # Queue Policy
+///
+/// What a bounded queue does with a value queued while it is at its capacity, see `Queue::set_capacity`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueuePolicy {
+    /// The producer waits until a value is dequeued.
+    #[default]
+    Block,
+    /// The oldest waiting value is dropped to make room.
+    DropOldest,
+    /// The value is given back with `QueueState::Full`.
+    Reject,
+}
+
+/// # Priority
+///
+/// The class of a queued value, higher classes are served first, see `Queue::queue_with`.
+///
+/// Classes are served by weight, four `High` values for two `Normal` and one `Low` while all wait, so lower classes are never starved.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    /// Latency sensitive work, such as health checks.
+    High,
+    /// Requests, the default.
+    #[default]
+    Normal,
+    /// Background work, such as scheduled jobs.
+    Low,
+}
+
+impl Priority {
+    /// the classes from the highest.
+    const ALL: [Priority; 3] = [Priority::High, Priority::Normal, Priority::Low];
+
+    /// the values of the class served before the lower classes get a turn.
+    fn weight(self) -> u32 {
+        match self {
+            Priority::High => 4,
+            Priority::Normal => 2,
+            Priority::Low => 1,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
 }
 
 /// ## Queue
@@ -34,8 +104,62 @@ pub enum QueueState<R> {
 /// 
 /// ```
 pub struct Queue<R> {
-    work: Mutex<Vec<R>>,
-    pub deque_lock: Notify
+    work: Mutex<Pending<R>>,
+    fair: AtomicBool,
+    /// false while values are rejected, see `set_accepting`.
+    accepting: AtomicBool,
+    pub deque_lock: Notify,
+    /// notified when a value is dequeued, wakes producers blocked on a full queue.
+    space: Notify
+}
+
+/// the values waiting in a queue.
+struct Pending<R> {
+    /// values of each priority with the key of the client they are for.
+    values: [VecDeque<(Option<String>, R)>; 3],
+    /// the values each priority may still be served before the lower priorities get a turn.
+    credits: [u32; 3],
+    /// when each waiting client was last served, used by fair queuing.
+    last_served: HashMap<String, u64>,
+    /// the amount of values served.
+    served: u64,
+    /// the most values waiting, None is unbounded.
+    capacity: Option<usize>,
+    /// what is done with values over the capacity.
+    policy: QueuePolicy,
+}
+
+impl<R> Pending<R> {
+    /// the values waiting in every priority.
+    fn len(&self) -> usize {
+        self.values.iter().map(VecDeque::len).sum()
+    }
+
+    /// forgets when a client was last served once none of its values wait.
+    fn forget_if_idle(&mut self, key: &str) {
+        if !self.values.iter().flatten().any(|(k, _)| k.as_deref() == Some(key)) {
+            self.last_served.remove(key);
+        }
+    }
+
+    /// the priority served next, spending one of its credits.
+    fn next_priority(&mut self) -> Option<usize> {
+        let waiting = |pending: &Self, class: usize| !pending.values[class].is_empty();
+
+        if !(0..3).any(|class| waiting(self, class)) {
+            return None;
+        }
+
+        //every waiting priority spent its turn, start a new round.
+        if !(0..3).any(|class| waiting(self, class) && self.credits[class] > 0) {
+            self.credits = Priority::ALL.map(Priority::weight);
+        }
+
+        let class = (0..3).find(|&class| waiting(self, class) && self.credits[class] > 0)?;
+        self.credits[class] -= 1;
+
+        Some(class)
+    }
 }
 
 /// Async based Queue
@@ -43,32 +167,195 @@ impl<R> Queue<R> {
 
     /// Create a new queue
     pub fn new() -> Self {
-        Self { work: Mutex::new(Vec::new()), deque_lock: Notify::new() }
+        Self {
+            work: Mutex::new(Pending { values: Default::default(), credits: Priority::ALL.map(Priority::weight), last_served: HashMap::new(), served: 0, capacity: None, policy: QueuePolicy::Block }),
+            fair: AtomicBool::new(false),
+            accepting: AtomicBool::new(true),
+            deque_lock: Notify::new(),
+            space: Notify::new()
+        }
+    }
+
+    /// # Set Fair
+    ///
+    /// Enables or disables fair queuing, off by default.
+    ///
+    /// A fair queue serves the client that was served least recently first instead of the oldest value, so a client that floods the queue does not starve other clients. Values queued without a key are served in order, before keyed values.
+    pub fn set_fair(&self, fair: bool) {
+        self.fair.store(fair, Ordering::Relaxed);
+    }
+
+    /// Returns true if the queue is fair, see `set_fair`.
+    pub fn is_fair(&self) -> bool {
+        self.fair.load(Ordering::Relaxed)
+    }
+
+    /// # Set Accepting
+    ///
+    /// Stops or resumes taking new values, a queue that does not accept gives each value back with `QueueState::Full`. Values already waiting are still dequeued.
+    pub fn set_accepting(&self, accepting: bool) {
+        self.accepting.store(accepting, Ordering::Relaxed);
+
+        //blocked producers are given their value back.
+        self.space.notify_waiters();
+    }
+
+    /// Returns true if the queue takes new values, see `set_accepting`.
+    pub fn is_accepting(&self) -> bool {
+        self.accepting.load(Ordering::Relaxed)
+    }
+
+    /// # Set Capacity
+    ///
+    /// Bounds the values waiting in the queue, with the policy for values queued while it is full. None is unbounded, the default.
+    ///
+    /// An unbounded queue blocks as soon as values wait, so its producer grows the workers, a bounded queue holds values up to its capacity instead.
+    pub async fn set_capacity(&self, capacity: Option<usize>, policy: QueuePolicy) {
+        let mut work = self.work.lock().await;
+
+        work.capacity = capacity.map(|capacity| capacity.max(1));
+        work.policy = policy;
+
+        drop(work);
+
+        //blocked producers check the new capacity.
+        self.space.notify_waiters();
+    }
+
+    /// The capacity and policy of the queue, None if unbounded.
+    pub async fn capacity(&self) -> Option<(usize, QueuePolicy)> {
+        let work = self.work.lock().await;
+        work.capacity.map(|capacity| (capacity, work.policy))
     }
 
     /// Queue a value
     pub async fn queue(&self, value: R) -> QueueState::<R>  {
-        let mut work = self.work.lock().await;
+        self.push(None, Priority::Normal, value).await
+    }
+
+    /// # Queue For
+    ///
+    /// Queue a value for a client, such as its IP, which fair queuing interleaves by.
+    pub async fn queue_for(&self, key: &str, value: R) -> QueueState::<R> {
+        self.push(Some(key.to_string()), Priority::Normal, value).await
+    }
+
+    /// # Queue With
+    ///
+    /// Queue a value with a priority, see [`Priority`].
+    pub async fn queue_with(&self, priority: Priority, value: R) -> QueueState::<R> {
+        self.push(None, priority, value).await
+    }
+
+    async fn push(&self, key: Option<String>, priority: Priority, value: R) -> QueueState::<R> {
+        loop {
+            let space = self.space.notified();
+            tokio::pin!(space);
+
+            let mut work = self.work.lock().await;
+
+            if !self.is_accepting() {
+                return QueueState::Full(value);
+            }
+
+            match work.capacity {
+                //the work has blocked.
+                None if work.len() > 1 => return QueueState::Blocked(value),
+                Some(capacity) if work.len() >= capacity => match work.policy {
+                    QueuePolicy::Block => {
+                        //listen before unlocking, so a value dequeued in between still wakes this.
+                        space.as_mut().enable();
+                        drop(work);
+
+                        space.await;
+                        continue;
+                    }
+                    //the oldest value of the lowest priority is dropped, never one above the new value.
+                    QueuePolicy::DropOldest => {
+                        let Some(class) = (priority.index()..3).rev().find(|&class| !work.values[class].is_empty()) else {
+                            return QueueState::Full(value);
+                        };
+
+                        if let Some((Some(key), _)) = work.values[class].pop_front() {
+                            work.forget_if_idle(&key);
+                        }
+                    }
+                    QueuePolicy::Reject => return QueueState::Full(value),
+                },
+                _ => {}
+            }
+
+            work.values[priority.index()].push_back((key, value));
+            self.deque_lock.notify_one();
 
-        //the work has blocked.
-        if work.len() > 1 {
-            return QueueState::Blocked(value)
+            return QueueState::Free;
         }
+    }
 
-        work.push(value);
-        self.deque_lock.notify_one();
+    /// The amount of values waiting in the queue.
+    pub async fn len(&self) -> usize {
+        self.work.lock().await.len()
+    }
 
-        QueueState::Free
+    /// Returns true if no values are waiting in the queue.
+    pub async fn is_empty(&self) -> bool {
+        self.work.lock().await.len() == 0
     }
 
-    async fn try_deque(&self) -> Option<R> {
+    /// # Clear
+    ///
+    /// Removes every waiting value, from the highest priority.
+    pub async fn clear(&self) -> Vec<R> {
+        let mut work = self.work.lock().await;
+
+        work.last_served.clear();
+
+        let cleared = work
+            .values
+            .iter_mut()
+            .flat_map(|values| values.drain(..).map(|(_, value)| value))
+            .collect();
+
+        drop(work);
+
+        self.space.notify_waiters();
+
+        cleared
+    }
+
+    /// takes the next value, with true if values are still waiting.
+    async fn try_deque(&self) -> Option<(R, bool)> {
         let mut locked_queue = self.work.lock().await;
+        let pending = &mut *locked_queue;
 
-        if locked_queue.is_empty() {
-            return None;
+        let class = pending.next_priority()?;
+
+        if !self.is_fair() {
+            let (_, value) = pending.values[class].pop_front()?;
+            return Some((value, pending.len() > 0));
+        }
+
+        //the oldest value of the client served least recently, a client never served comes first.
+        let index = pending
+            .values[class]
+            .iter()
+            .enumerate()
+            .min_by_key(|(i, (key, _))| {
+                let last = key.as_ref().and_then(|k| pending.last_served.get(k)).copied();
+                (last.unwrap_or(0), *i)
+            })
+            .map(|(i, _)| i)?;
+
+        let (key, value) = pending.values[class].remove(index)?;
+
+        pending.served += 1;
+
+        if let Some(key) = key {
+            pending.last_served.insert(key.clone(), pending.served);
+            pending.forget_if_idle(&key);
         }
 
-        Some(locked_queue.remove(0))
+        Some((value, pending.len() > 0))
     }
 
     /// Deque and wait for a value.
@@ -81,15 +368,23 @@ impl<R> Queue<R> {
 
         loop {
 
+            //listen before checking the closure, so a close between the two still wakes this.
+            fut.as_mut().enable();
+
             if let Some(is_closed_ref) = &closure {
                 if *is_closed_ref.lock().await {
                     return None;
                 }
             }
 
-            fut.as_mut().enable();
+            if let Some((r, more)) = self.try_deque().await {
+                self.space.notify_waiters();
+
+                //a wakeup taken by this worker may have been meant for another value, pass it on.
+                if more {
+                    self.deque_lock.notify_one();
+                }
 
-            if let Some(r) = self.try_deque().await {
                 return Some(r);
             }
 
@@ -0,0 +1,424 @@
+use std::{
+    future::Future,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use aes_gcm::aead::{OsRng, rand_core::RngCore};
+use tokio::{sync::Mutex, task::AbortHandle, time::Instant};
+
+use crate::{
+    factory::{Job, Priority, WorkManager, queue::QueueState},
+    web::errors::ConfigError,
+};
+
+/// # Schedule
+///
+/// When a job of a [`Scheduler`] runs, at a fixed interval or on a cron expression.
+///
+/// ```ignore
+/// let purge = Schedule::every(Duration::from_secs(60)).jitter(Duration::from_secs(5));
+/// let report = Schedule::cron("0 3 * * 1-5")?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    timing: Timing,
+    jitter: Duration,
+    priority: Priority,
+}
+
+/// the times a schedule runs at.
+#[derive(Debug, Clone)]
+enum Timing {
+    Every(Duration),
+    Cron(Cron),
+}
+
+impl Schedule {
+    /// Runs every interval, the first run is one interval after the job is added.
+    pub fn every(interval: Duration) -> Self {
+        Self {
+            timing: Timing::Every(interval.max(Duration::from_millis(1))),
+            jitter: Duration::ZERO,
+            priority: Priority::Low,
+        }
+    }
+
+    /// # Cron
+    ///
+    /// Runs on a cron expression in UTC, `minute hour day-of-month month day-of-week`, see [`Cron`].
+    pub fn cron(expression: &str) -> Result<Self, ConfigError> {
+        Ok(Self {
+            timing: Timing::Cron(Cron::parse(expression)?),
+            jitter: Duration::ZERO,
+            priority: Priority::Low,
+        })
+    }
+
+    /// Delays each run by a random duration up to `max`, so jobs of several instances do not run at once.
+    pub fn jitter(mut self, max: Duration) -> Self {
+        self.jitter = max;
+        self
+    }
+
+    /// The priority of the runs on the workers, by default (low) so jobs wait behind requests.
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// a random delay up to the jitter.
+    fn random_jitter(&self) -> Duration {
+        let max = self.jitter.as_millis() as u64;
+
+        match max {
+            0 => Duration::ZERO,
+            max => Duration::from_millis(OsRng.next_u64() % (max + 1)),
+        }
+    }
+}
+
+/// # Cron
+///
+/// A cron expression of five fields in UTC, `minute hour day-of-month month day-of-week`.
+///
+/// Each field is `*`, a value, a range `1-5`, a step `*/15` or `0-30/10`, or a list of them `1,15,30`. Sunday is `0` or `7`.
+///
+/// As in cron, when both the day of the month and the day of the week are restricted a day matching either runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl Cron {
+    /// # Parse
+    ///
+    /// Parses the five fields of the expression.
+    pub fn parse(expression: &str) -> Result<Self, ConfigError> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return Err(ConfigError::Parse(format!(
+                "'{expression}' does not have five fields"
+            )));
+        };
+
+        let mut days_of_week_bits = parse_field(days_of_week, 0, 7)?;
+
+        //7 is also sunday.
+        if days_of_week_bits & (1 << 7) != 0 {
+            days_of_week_bits = (days_of_week_bits | 1) & !(1 << 7);
+        }
+
+        Ok(Self {
+            minutes: parse_field(minutes, 0, 59)?,
+            hours: parse_field(hours, 0, 23)?,
+            days_of_month: parse_field(days_of_month, 1, 31)?,
+            months: parse_field(months, 1, 12)?,
+            days_of_week: days_of_week_bits,
+            any_day_of_month: days_of_month == "*",
+            any_day_of_week: days_of_week == "*",
+        })
+    }
+
+    /// # Next After
+    ///
+    /// The first minute matching the expression after the time, None if none matches within five years, such as `0 0 31 2 *`.
+    pub fn next_after(&self, time: SystemTime) -> Option<SystemTime> {
+        let seconds = time.duration_since(UNIX_EPOCH).ok()?.as_secs();
+
+        //the next whole minute.
+        let mut minute = seconds / 60 + 1;
+        let last = minute + 5 * 366 * 24 * 60;
+
+        while minute < last {
+            let days = minute / (24 * 60);
+            let (_, month, day) = civil_from_days(days as i64);
+            let weekday = (days + 4) % 7;
+
+            if !self.matches_day(month, day, weekday) {
+                minute = (days + 1) * 24 * 60;
+                continue;
+            }
+
+            let hour = minute / 60 % 24;
+
+            if self.hours & (1 << hour) == 0 {
+                minute = (minute / 60 + 1) * 60;
+                continue;
+            }
+
+            if self.minutes & (1 << (minute % 60)) != 0 {
+                return Some(UNIX_EPOCH + Duration::from_secs(minute * 60));
+            }
+
+            minute += 1;
+        }
+
+        None
+    }
+
+    /// true if the day runs, see the type docs for days of the month and week.
+    fn matches_day(&self, month: u32, day: u32, weekday: u64) -> bool {
+        if self.months & (1 << month) == 0 {
+            return false;
+        }
+
+        let day_of_month = self.days_of_month & (1 << day) != 0;
+        let day_of_week = self.days_of_week & (1 << weekday) != 0;
+
+        match (self.any_day_of_month, self.any_day_of_week) {
+            (false, false) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+}
+
+/// parses a field into a bit per allowed value.
+fn parse_field(field: &str, min: u64, max: u64) -> Result<u64, ConfigError> {
+    let invalid = || ConfigError::Parse(format!("'{field}' is not a cron field of {min}-{max}"));
+
+    let mut bits = 0u64;
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u64>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (
+                    start.parse().map_err(|_| invalid())?,
+                    end.parse().map_err(|_| invalid())?,
+                ),
+                None => {
+                    let value = range.parse().map_err(|_| invalid())?;
+
+                    //`5/15` runs from 5 to the end of the field.
+                    match part.contains('/') {
+                        true => (value, max),
+                        false => (value, value),
+                    }
+                }
+            },
+        };
+
+        if step == 0 || start < min || end > max || start > end {
+            return Err(invalid());
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+
+    Ok(bits)
+}
+
+/// the year, month, and day of days since the unix epoch.
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+/// the days since the unix epoch of a year, month, and day.
+#[cfg(feature = "tls")]
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (i64::from(month) + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146_097 + doe - 719_468
+}
+
+/// # Job Handle
+///
+/// A job added to a [`Scheduler`], cancel it with `cancel`.
+///
+/// Dropping the handle does not cancel the job.
+#[derive(Debug, Clone)]
+pub struct JobHandle {
+    task: AbortHandle,
+    runs: Arc<AtomicU64>,
+}
+
+impl JobHandle {
+    /// Stops the job, a run that was already queued on the workers still finishes.
+    pub fn cancel(&self) {
+        self.task.abort();
+    }
+
+    /// True once the job was cancelled, or its schedule has no further runs.
+    pub fn is_cancelled(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// The times the job was queued on the workers.
+    pub fn runs(&self) -> u64 {
+        self.runs.load(Ordering::Relaxed)
+    }
+}
+
+/// # Scheduler
+///
+/// Runs jobs on a schedule with the workers of a [`WorkManager`], such as purging sessions or refreshing caches.
+///
+/// Each job waits for its next run on a timer, the run itself is queued on the workers like a connection.
+///
+/// Cloning shares the same jobs.
+///
+/// ```ignore
+/// let scheduler = app.scheduler();
+///
+/// let purge = scheduler.add(Schedule::every(Duration::from_secs(60)), move || {
+///     let sessions = sessions.clone();
+///     async move { sessions.purge_expired().await }
+/// });
+///
+/// purge.cancel();
+/// ```
+pub struct Scheduler<R>
+where
+    R: Send + 'static,
+{
+    work_manager: Arc<Mutex<WorkManager<R>>>,
+    jobs: Arc<std::sync::Mutex<Vec<AbortHandle>>>,
+}
+
+impl<R> Clone for Scheduler<R>
+where
+    R: Send + 'static,
+{
+    fn clone(&self) -> Self {
+        Self {
+            work_manager: self.work_manager.clone(),
+            jobs: self.jobs.clone(),
+        }
+    }
+}
+
+impl<R> Scheduler<R>
+where
+    R: Send + 'static,
+{
+    /// Create a scheduler that runs jobs on the workers of the work manager.
+    pub fn new(work_manager: Arc<Mutex<WorkManager<R>>>) -> Self {
+        Self {
+            work_manager,
+            jobs: Arc::new(std::sync::Mutex::new(Vec::new())),
+        }
+    }
+
+    /// # Add
+    ///
+    /// Runs the job on the schedule until it is cancelled.
+    pub fn add<F, Fut>(&self, schedule: Schedule, job: F) -> JobHandle
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = R> + Send + 'static,
+    {
+        let work_manager = self.work_manager.clone();
+        let runs = Arc::new(AtomicU64::new(0));
+        let job_runs = runs.clone();
+
+        let task = tokio::task::spawn(async move {
+            let mut last = SystemTime::now();
+            let mut next_interval = Instant::now();
+
+            loop {
+                //wait for the next run.
+                match &schedule.timing {
+                    Timing::Every(interval) => {
+                        next_interval = (next_interval + *interval).max(Instant::now());
+                        tokio::time::sleep_until(next_interval + schedule.random_jitter()).await;
+                    }
+                    Timing::Cron(cron) => {
+                        //never before the last run, the clock may be behind the timer.
+                        let Some(at) = cron.next_after(SystemTime::now().max(last)) else {
+                            break;
+                        };
+
+                        last = at;
+
+                        let wait = at.duration_since(SystemTime::now()).unwrap_or_default();
+                        tokio::time::sleep(wait + schedule.random_jitter()).await;
+                    }
+                }
+
+                let mut work = Job::new(Box::pin(job()));
+
+                let queue = work_manager.lock().await.work_queue();
+
+                //the queue is blocked while every worker is busy, wait for it to drain.
+                let queued = loop {
+                    match queue.queue_with(schedule.priority, work).await {
+                        QueueState::Free => break true,
+                        QueueState::Blocked(returned_work) => work = returned_work,
+                        //a full queue that rejects work skips the run.
+                        QueueState::Full(_) => break false,
+                    }
+
+                    tokio::task::yield_now().await;
+                };
+
+                if queued {
+                    job_runs.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        });
+
+        let handle = task.abort_handle();
+
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        jobs.retain(|job| !job.is_finished());
+        jobs.push(handle.clone());
+
+        JobHandle { task: handle, runs }
+    }
+
+    /// The jobs that were not cancelled.
+    pub fn len(&self) -> usize {
+        let jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        jobs.iter().filter(|job| !job.is_finished()).count()
+    }
+
+    /// Returns true if every job was cancelled.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Cancels every job, such as when the app is closed.
+    pub fn cancel_all(&self) {
+        for job in self
+            .jobs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .drain(..)
+        {
+            job.abort();
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use std::{
+    any::Any,
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use crate::factory::metrics::{LatencyHistogram, Metrics};
+
+/// Idiomatic type alias for a closure that is called when a worker crashes.
+pub type CrashCallback = dyn Fn(&WorkerCrash) + Send + Sync;
+
+/// # Worker Crash
+///
+/// A panic of a piece of work, given to the crash callback of a [`Supervisor`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerCrash {
+    /// The message of the panic.
+    pub message: String,
+
+    /// The panics of the workers so far, including this one.
+    pub panics: u64,
+}
+
+/// # Supervisor
+///
+/// Watches the workers of a [`WorkManager`](crate::factory::WorkManager), shared by each of its workers.
+///
+/// A panic in a piece of work is caught, counted, and given to the crash callback, the worker then takes the next piece of work. Workers that stopped anyway are replaced by `WorkManager::respawn_dead_workers` while `respawn` is set.
+///
+/// The supervisor also counts finished work and its latency for `WorkManager::stats`, and passes them on to the [`Metrics`] set with `set_metrics`.
+#[derive(Default)]
+pub struct Supervisor {
+    panics: AtomicU64,
+    completed: AtomicU64,
+    cancelled: AtomicU64,
+    expired: AtomicU64,
+    timed_out: AtomicU64,
+    latency: LatencyHistogram,
+    queue_wait: LatencyHistogram,
+    respawn: AtomicBool,
+    on_crash: RwLock<Option<Arc<CrashCallback>>>,
+    metrics: RwLock<Option<Arc<dyn Metrics>>>,
+}
+
+impl Supervisor {
+    /// Create a supervisor without a crash callback that does not respawn workers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The panics caught in the workers.
+    pub fn panics(&self) -> u64 {
+        self.panics.load(Ordering::Relaxed)
+    }
+
+    /// The work that finished without a panic.
+    pub fn completed(&self) -> u64 {
+        self.completed.load(Ordering::Relaxed)
+    }
+
+    /// The work that was cancelled before it finished.
+    pub fn cancelled_work(&self) -> u64 {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// The work that was skipped because its deadline passed before a worker took it.
+    pub fn expired_work(&self) -> u64 {
+        self.expired.load(Ordering::Relaxed)
+    }
+
+    /// The work that was dropped because it ran longer than its budget.
+    pub fn timed_out_work(&self) -> u64 {
+        self.timed_out.load(Ordering::Relaxed)
+    }
+
+    /// How long finished work ran.
+    pub fn latency(&self) -> &LatencyHistogram {
+        &self.latency
+    }
+
+    /// How long work waited in the queue for a worker.
+    pub fn queue_wait(&self) -> &LatencyHistogram {
+        &self.queue_wait
+    }
+
+    /// Sets the metrics the work of the workers is passed on to, None removes them.
+    pub fn set_metrics(&self, metrics: Option<Arc<dyn Metrics>>) {
+        *self.metrics.write().unwrap_or_else(|e| e.into_inner()) = metrics;
+    }
+
+    /// the metrics set with `set_metrics`.
+    fn metrics(&self) -> Option<Arc<dyn Metrics>> {
+        self.metrics.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// records work taken by a worker after waiting in the queue.
+    pub(crate) fn started(&self, wait: Duration) {
+        self.queue_wait.record(wait);
+
+        if let Some(metrics) = self.metrics() {
+            metrics.started(wait);
+        }
+    }
+
+    /// records work that finished.
+    pub(crate) fn finished(&self, latency: Duration) {
+        self.completed.fetch_add(1, Ordering::Relaxed);
+        self.latency.record(latency);
+
+        if let Some(metrics) = self.metrics() {
+            metrics.completed(latency);
+        }
+    }
+
+    /// counts work that was cancelled.
+    pub(crate) fn cancelled(&self) {
+        self.cancelled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// counts work whose deadline passed.
+    pub(crate) fn expired(&self) {
+        self.expired.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// counts work that ran out of budget.
+    pub(crate) fn timed_out(&self) {
+        self.timed_out.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns true if dead workers are replaced.
+    pub fn respawns(&self) -> bool {
+        self.respawn.load(Ordering::Relaxed)
+    }
+
+    /// Replaces workers that stopped without being closed, off by default.
+    pub fn set_respawn(&self, respawn: bool) {
+        self.respawn.store(respawn, Ordering::Relaxed);
+    }
+
+    /// Sets the callback called with each caught panic, None removes it.
+    pub fn set_on_crash(&self, on_crash: Option<Arc<CrashCallback>>) {
+        *self.on_crash.write().unwrap_or_else(|e| e.into_inner()) = on_crash;
+    }
+
+    /// counts the panic and calls the crash callback.
+    pub(crate) fn crashed(&self, payload: Box<dyn Any + Send>, latency: Duration) {
+        let panics = self.panics.fetch_add(1, Ordering::Relaxed) + 1;
+
+        if let Some(metrics) = self.metrics() {
+            metrics.panicked(latency);
+        }
+
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|m| m.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+
+        #[cfg(feature = "tracing")]
+        tracing::error!(panics, message = %message, "a worker panicked");
+
+        let on_crash = self
+            .on_crash
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+
+        if let Some(on_crash) = on_crash {
+            on_crash(&WorkerCrash { message, panics });
+        }
+    }
+}
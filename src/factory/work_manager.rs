@@ -1,12 +1,14 @@
-use std::{pin::Pin, sync::Arc};
+use std::{panic::AssertUnwindSafe, sync::Arc, time::Duration};
 
 use futures::future::join_all;
-use tokio::sync::{
-    Mutex,
-    mpsc::{self, Receiver, Sender},
-};
 
-use crate::factory::{Queue, Worker, queue::QueueState};
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+use crate::factory::{DrainReport, Job, Priority, Queue, QueuePolicy, Supervisor, Work, WorkHandle, WorkResult, WorkStats, Worker, WorkerHealth, queue::QueueState};
+
+/// Idiomatic type alias for the queue of jobs of the workers.
+pub type WorkQueue<R> = Arc<Queue<Job<R>>>;
 
 /// # Work Manager
 ///
@@ -19,17 +21,20 @@ where
     /// The amount of workers started on creation.
     size: usize,
 
-    /// The sender to clone for the receiver
-    pub sender: Sender<R>,
-
-    ///The receiver, used to get incoming data from workers.
-    pub receiver: Arc<Mutex<Receiver<R>>>,
-
     /// Vec of created workers
     workers: Vec<Worker<R>>,
 
     /// Work to complete. Async work that returns the R type given
-    work: Arc<Queue<Pin<Box<dyn Future<Output = R> + Send + 'static>>>>,
+    work: WorkQueue<R>,
+
+    /// The amount of emergency workers added with `add_overflow_workers`.
+    overflow: usize,
+
+    /// The most workers `scale_workers` grows to, None grows without a bound.
+    max_workers: Option<usize>,
+
+    /// Catches the panics of the workers, shared by each worker.
+    supervisor: Arc<Supervisor>,
 }
 
 impl<R> WorkManager<R>
@@ -40,51 +45,45 @@ where
     ///
     /// Creates a new work manager that has N amount of workers.
     ///
-    /// The amount of workers also sets the size of the channel buffer size.
-    ///
     /// For example this allows us to distribute a batch of work.
     ///
     /// Assume that we make a WorkManager of 100 workers and 200 task come in, each worker will assume a task, run, finish, and take another task.
     pub async fn new(init_size: usize) -> Self {
-        let (tx, rx) = mpsc::channel(init_size);
-
-        let receiver = Arc::new(Mutex::new(rx));
-
         let work = Arc::new(Queue::new());
 
-        let workers = Self::create_workers(init_size, &tx, &work).await;
+        let supervisor = Arc::new(Supervisor::new());
+
+        let workers = Self::create_workers(init_size, &work, &supervisor).await;
 
         Self {
             size: init_size,
-            sender: tx,
-            receiver,
             workers,
             work,
+            overflow: 0,
+            max_workers: None,
+            supervisor,
         }
     }
 
     /// # create workers
     ///
-    /// Creates a batch of workers Of the size, cloning both the sender and the work load references.
+    /// Creates a batch of workers Of the size, cloning the work load and supervisor references.
     ///
     /// It is important to note that if the worker upon creation experiences an error it is not captured. And the reference is dropped.
     async fn create_workers(
         worker_count: usize,
-        data_send: &Sender<R>,
-        work_load: &Arc<Queue<Pin<Box<dyn Future<Output = R> + Send + 'static>>>>,
+        work_load: &WorkQueue<R>,
+        supervisor: &Arc<Supervisor>,
     ) -> Vec<Worker<R>> {
         // work start futures
         let mut work_futs = vec![];
 
         // for the size of workers
         for _ in 0..worker_count {
-            //clone the sender
-            let data_sender = data_send.clone();
-
             //clone the work queue
             let work_queue = work_load.clone();
 
-            let mut worker = Worker::new(data_sender, work_queue);
+            let mut worker = Worker::supervised(work_queue, supervisor.clone());
 
             //push each worker future and map the result to return the Worker that was created.
             work_futs.push(async move {
@@ -104,9 +103,149 @@ where
 
     /// # queue work
     /// 
-    /// Queues work with the given future.
-    pub async fn queue_work(&self, work: Pin<Box<dyn Future<Output = R> + Send + 'static>>) -> QueueState::<Pin<Box<dyn Future<Output = R> + Send + 'static>>> {
-        self.work.queue(work).await
+    /// Queues work with the given future, its result is dropped.
+    pub async fn queue_work(&self, work: Work<R>) -> QueueState::<Work<R>> {
+        self.work.queue(Job::new(work)).await.map(Job::into_work)
+    }
+
+    /// # submit
+    ///
+    /// Queues work and returns its result to await, see [`WorkResult`].
+    ///
+    /// Gives the work back as `QueueState::Blocked` or `QueueState::Full` if it was not queued.
+    pub async fn submit(&self, work: Work<R>) -> Result<WorkResult<R>, QueueState<Work<R>>> {
+        self.submit_with(Priority::Normal, work).await
+    }
+
+    /// # submit with
+    ///
+    /// Queues work with a priority and returns its result to await, see `submit`.
+    pub async fn submit_with(&self, priority: Priority, work: Work<R>) -> Result<WorkResult<R>, QueueState<Work<R>>> {
+        let (job, result) = Job::with_result(work);
+
+        match self.work.queue_with(priority, job).await {
+            QueueState::Free => Ok(result),
+            state => Err(state.map(Job::into_work)),
+        }
+    }
+
+    /// # submit with deadline
+    ///
+    /// Queues work that is skipped if no worker took it before the deadline, and dropped if it runs longer than the budget, and returns its result to await.
+    ///
+    /// The result is `WorkerError::Expired` or `WorkerError::TimedOut` when the work was not finished, see [`Job`].
+    pub async fn submit_with_deadline(
+        &self,
+        deadline: Instant,
+        budget: Option<Duration>,
+        work: Work<R>,
+    ) -> Result<WorkResult<R>, QueueState<Work<R>>> {
+        let (mut job, result) = Job::with_result(work);
+        job = job.with_deadline(deadline);
+
+        if let Some(budget) = budget {
+            job = job.with_budget(budget);
+        }
+
+        match self.work.queue(job).await {
+            QueueState::Free => Ok(result),
+            state => Err(state.map(Job::into_work)),
+        }
+    }
+
+    /// # add work
+    ///
+    /// Queues work that can be cancelled, the closure receives the token of the returned [`WorkHandle`] to stop cooperatively.
+    ///
+    /// Gives the work back as `QueueState::Blocked` or `QueueState::Full` if it was not queued.
+    ///
+    /// ```ignore
+    /// let handle = work_manager.add_work(|cancel| Box::pin(async move {
+    ///     while !cancel.is_cancelled() {
+    ///         step().await;
+    ///     }
+    /// })).await.ok().unwrap();
+    ///
+    /// handle.cancel();
+    /// ```
+    pub async fn add_work<F>(&self, work: F) -> Result<WorkHandle<R>, QueueState<Work<R>>>
+    where
+        F: FnOnce(CancellationToken) -> Work<R>,
+    {
+        let cancel = CancellationToken::new();
+        let (job, result) = Job::with_result(work(cancel.clone()));
+
+        match self.work.queue(job.with_cancel(cancel.clone())).await {
+            QueueState::Free => Ok(WorkHandle::new(cancel, result)),
+            state => Err(state.map(Job::into_work)),
+        }
+    }
+
+    /// # queue work with
+    ///
+    /// Queues work with a priority, such as `Priority::High` for health checks, its result is dropped.
+    pub async fn queue_work_with(&self, priority: Priority, work: Work<R>) -> QueueState::<Work<R>> {
+        self.work.queue_with(priority, Job::new(work)).await.map(Job::into_work)
+    }
+
+    /// # queue work for
+    ///
+    /// Queues work for a client, such as its IP, so fair queuing can interleave the work of clients.
+    pub async fn queue_work_for(&self, key: &str, work: Work<R>) -> QueueState::<Work<R>> {
+        self.work.queue_for(key, Job::new(work)).await.map(Job::into_work)
+    }
+
+    /// # add blocking work
+    ///
+    /// Runs CPU heavy or blocking work on the blocking pool of tokio instead of the workers, so it does not starve the workers serving IO.
+    ///
+    /// The work is counted in `stats` and its panics are given to the supervisor, a panic resolves the result to `WorkerError::WorkLost`.
+    ///
+    /// ```ignore
+    /// let embedding = work_manager.add_blocking_work(move || model.embed(&text)).await?;
+    /// ```
+    pub fn add_blocking_work<F, T>(&self, work: F) -> WorkResult<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (reply, result) = WorkResult::channel();
+        let supervisor = self.supervisor.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let started = Instant::now();
+
+            match std::panic::catch_unwind(AssertUnwindSafe(work)) {
+                Ok(value) => {
+                    supervisor.finished(started.elapsed());
+                    let _ = reply.send(Ok(value));
+                }
+                Err(payload) => supervisor.crashed(payload, started.elapsed()),
+            }
+        });
+
+        result
+    }
+
+    /// # work queue
+    ///
+    /// The queue the workers take work from, work can be queued on it without holding the work manager.
+    pub fn work_queue(&self) -> WorkQueue<R> {
+        self.work.clone()
+    }
+
+    /// # set queue capacity
+    ///
+    /// Bounds the work waiting for a worker, see `Queue::set_capacity`.
+    pub async fn set_queue_capacity(&self, capacity: Option<usize>, policy: QueuePolicy) {
+        self.work.set_capacity(capacity, policy).await;
+    }
+
+    /// # set fair queuing
+    ///
+    /// Enables or disables fair queuing of work by client, see `Queue::set_fair`.
+    pub fn set_fair_queuing(&self, fair: bool) {
+        self.work.set_fair(fair);
     }
 
 
@@ -121,10 +260,14 @@ where
 
         //sizes and scalers.
         let current_size = self.size;
-        let new_size = current_size * scale_factor;
+        let mut new_size = current_size * scale_factor;
+
+        if let Some(max_workers) = self.max_workers {
+            new_size = new_size.min(max_workers.max(current_size));
+        }
 
         //create new workers with the difference.
-        let mut new_workers = Self::create_workers(new_size - current_size, &self.sender, &self.work).await;
+        let mut new_workers = Self::create_workers(new_size - current_size, &self.work, &self.supervisor).await;
 
         //move the workers from one container to another.
         let mut worker_container = Vec::with_capacity(new_size);
@@ -136,6 +279,238 @@ where
         self.workers = worker_container;
     }
 
+    /// # set max workers
+    ///
+    /// The most workers `scale_workers` grows to, None grows without a bound. Overflow workers are not bounded.
+    pub fn set_max_workers(&mut self, max_workers: Option<usize>) {
+        self.max_workers = max_workers;
+    }
+
+    /// # scale to
+    ///
+    /// Grows or shrinks the workers to the target, only idle workers are stopped when shrinking.
+    ///
+    /// Returns the amount of workers after scaling.
+    pub async fn scale_to(&mut self, target: usize) -> usize {
+        if target > self.workers.len() {
+            let mut new_workers = Self::create_workers(target - self.workers.len(), &self.work, &self.supervisor).await;
+            self.workers.append(&mut new_workers);
+        } else if target < self.workers.len() {
+            self.retire_idle_workers(self.workers.len() - target).await;
+        }
+
+        self.size = self.workers.len();
+        self.size
+    }
+
+    /// # retire idle workers
+    ///
+    /// Stops up to the count of idle workers, newest first, and removes them.
+    ///
+    /// Returns the amount of workers that were stopped.
+    pub async fn retire_idle_workers(&mut self, count: usize) -> usize {
+        let mut retired = 0;
+        let mut index = self.workers.len();
+
+        while retired < count && index > 0 {
+            index -= 1;
+
+            if !self.workers[index].is_idle().await {
+                continue;
+            }
+
+            let mut worker = self.workers.remove(index);
+
+            if worker.retire().await {
+                retired += 1;
+            }
+        }
+
+        self.size = self.workers.len();
+        self.overflow = self.overflow.min(self.size);
+
+        retired
+    }
+
+    /// # supervisor
+    ///
+    /// The supervisor of the workers, set the crash callback and respawning on it.
+    pub fn supervisor(&self) -> Arc<Supervisor> {
+        self.supervisor.clone()
+    }
+
+    /// # healthy workers
+    ///
+    /// The amount of workers whose task is still running.
+    pub fn healthy_workers(&self) -> usize {
+        self.workers.iter().filter(|worker| worker.is_running()).count()
+    }
+
+    /// # respawn dead workers
+    ///
+    /// Replaces the workers whose task stopped without being closed, only while the supervisor respawns workers.
+    ///
+    /// Returns the amount of workers that were replaced.
+    pub async fn respawn_dead_workers(&mut self) -> usize {
+        if !self.supervisor.respawns() {
+            return 0;
+        }
+
+        let mut dead = 0;
+        let mut index = 0;
+
+        while index < self.workers.len() {
+            if self.workers[index].is_dead().await {
+                self.workers.remove(index);
+                dead += 1;
+            } else {
+                index += 1;
+            }
+        }
+
+        let mut new_workers = Self::create_workers(dead, &self.work, &self.supervisor).await;
+        self.workers.append(&mut new_workers);
+        self.size = self.workers.len();
+
+        dead
+    }
+
+    /// # in flight
+    ///
+    /// The amount of workers that are working on a piece of work.
+    pub async fn in_flight(&self) -> usize {
+        let mut busy = 0;
+
+        for worker in &self.workers {
+            if !worker.is_idle().await {
+                busy += 1;
+            }
+        }
+
+        busy
+    }
+
+    /// # stats
+    ///
+    /// A snapshot of the workers, queue, finished work, and latencies, see [`WorkStats`].
+    pub async fn stats(&self) -> WorkStats {
+        WorkStats {
+            workers: self.workers.len(),
+            busy: self.in_flight().await,
+            queued: self.work.len().await,
+            completed: self.supervisor.completed(),
+            panics: self.supervisor.panics(),
+            cancelled: self.supervisor.cancelled_work(),
+            expired: self.supervisor.expired_work(),
+            timed_out: self.supervisor.timed_out_work(),
+            latency: self.supervisor.latency().snapshot(),
+            queue_wait: self.supervisor.queue_wait().snapshot(),
+        }
+    }
+
+    /// # queued
+    ///
+    /// The amount of work waiting in the queue for a worker.
+    pub async fn queued(&self) -> usize {
+        self.work.len().await
+    }
+
+    /// # add overflow workers
+    ///
+    /// Adds emergency workers, used when every worker is stalled and no work can make progress.
+    ///
+    /// Returns the amount of workers that were started.
+    pub async fn add_overflow_workers(&mut self, count: usize) -> usize {
+        let mut new_workers = Self::create_workers(count, &self.work, &self.supervisor).await;
+        let added = new_workers.len();
+
+        self.workers.append(&mut new_workers);
+        self.size = self.workers.len();
+        self.overflow += added;
+
+        added
+    }
+
+    /// # retire overflow workers
+    ///
+    /// Stops the idle overflow workers, used once the workers are no longer stalled.
+    ///
+    /// Returns the amount of workers that were stopped.
+    pub async fn retire_overflow_workers(&mut self) -> usize {
+        let overflow = self.overflow;
+        let retired = self.retire_idle_workers(overflow).await;
+
+        self.overflow = (overflow - retired).min(self.size);
+
+        retired
+    }
+
+    /// # health
+    ///
+    /// Takes a snapshot of the workers, a worker busy for longer than `stall_after` is counted as stalled.
+    pub async fn health(&self, stall_after: Duration) -> WorkerHealth {
+        let mut busy = 0;
+        let mut stalled = 0;
+
+        for worker in &self.workers {
+            if let Some(busy_for) = worker.busy_for().await {
+                busy += 1;
+
+                if busy_for >= stall_after {
+                    stalled += 1;
+                }
+            }
+        }
+
+        WorkerHealth {
+            workers: self.workers.len(),
+            busy,
+            stalled,
+            queued: self.work.len().await,
+            panics: self.supervisor.panics(),
+            overflow_workers: self.overflow,
+            wedged: !self.workers.is_empty() && stalled == self.workers.len(),
+        }
+    }
+
+    /// # drain
+    ///
+    /// Stops accepting new work, waits for the queued work to finish, then stops the workers, for at most the timeout.
+    ///
+    /// Work still queued when the timeout passes is dropped, work still running is finished without being waited for, see [`DrainReport`].
+    ///
+    /// The queue keeps rejecting work with `QueueState::Full` after the drain, add workers with `scale_to` and resume it with `Queue::set_accepting` to reuse the manager.
+    pub async fn drain(&mut self, timeout: Duration) -> DrainReport {
+        let deadline = Instant::now() + timeout;
+        let completed = self.supervisor.completed();
+
+        self.work.set_accepting(false);
+
+        //the queue empties first, each worker then finishes the work it took and stops.
+        let _ = tokio::time::timeout_at(deadline, async {
+            while !self.work.is_empty().await && !self.workers.is_empty() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await;
+
+        let close_futs = self.workers.iter_mut().map(|worker| worker.close());
+        let _ = tokio::time::timeout_at(deadline, join_all(close_futs)).await;
+
+        let abandoned = self.work.clear().await.len();
+        let running = self.workers.iter().filter(|worker| worker.is_running()).count();
+
+        self.workers.clear();
+        self.size = 0;
+        self.overflow = 0;
+
+        DrainReport {
+            completed: self.supervisor.completed() - completed,
+            abandoned,
+            running,
+        }
+    }
+
     /// Close all workers, the queue, and wait for them to finish
     pub async fn close_and_finish_work(&mut self) -> () {
         let mut close_futs = vec![];
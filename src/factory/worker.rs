@@ -1,26 +1,32 @@
-use std::future::Future;
-use std::pin::Pin;
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
+use std::time::Duration;
 
+use futures::FutureExt;
 use tokio::{
-    sync::{Mutex, mpsc::Sender},
+    sync::Mutex,
     task::JoinHandle,
+    time::Instant,
 };
 
 
-use crate::{factory::Queue, web::errors::WorkerError};
+use crate::{
+    factory::{Job, Queue, Supervisor},
+    web::errors::WorkerError,
+};
 
 /// # Worker <R>
 ///
-/// A worker that dequeues a piece of work in asynchronous manner, calling, finishing the task, and sends the data back to the sender.
+/// A worker that dequeues a piece of work in asynchronous manner, calling, finishing the task, and sends the result to the submitter of the job if it awaits one.
 pub struct Worker<R>
 where
     R: Send + 'static,
 {
-    work: Arc<Queue<Pin<Box<dyn Future<Output = R> + 'static + Send>>>>,
+    work: Arc<Queue<Job<R>>>,
     task: Option<JoinHandle<()>>,
-    sender: Sender<R>,
     closed: Arc<Mutex<bool>>,
+    busy_since: Arc<Mutex<Option<Instant>>>,
+    supervisor: Arc<Supervisor>,
 }
 
 impl<R> Worker<R>
@@ -29,16 +35,21 @@ where
 {
     /// # New
     ///
-    /// Creates a new worker with an output (Sender<R> of some R data) and queue of work that contains functions that output R
-    pub fn new(
-        sender: Sender<R>,
-        work: Arc<Queue<Pin<Box<dyn Future<Output = R> + 'static + Send>>>>,
-    ) -> Self {
+    /// Creates a new worker with a queue of jobs whose work outputs R
+    pub fn new(work: Arc<Queue<Job<R>>>) -> Self {
+        Self::supervised(work, Arc::new(Supervisor::new()))
+    }
+
+    /// # Supervised
+    ///
+    /// Creates a worker whose panics are reported to the supervisor, see [`Supervisor`].
+    pub fn supervised(work: Arc<Queue<Job<R>>>, supervisor: Arc<Supervisor>) -> Self {
         Self {
-            sender,
             work,
             task: None,
             closed: Arc::new(Mutex::new(false)),
+            busy_since: Arc::new(Mutex::new(None)),
+            supervisor,
         }
     }
 
@@ -55,21 +66,80 @@ where
 
         //refs to send
         let work = self.work.clone();
-        let sender = self.sender.clone();
         let closed = self.closed.clone();
+        let busy_since = self.busy_since.clone();
+        let supervisor = self.supervisor.clone();
 
         //spawn a new task
         let task = tokio::task::spawn(async move {
             // while some work, send the "closed" flag into the work so we can ensure concurrency in ensuring workers do not keep working.
             //pass the closed ref to the deque func
-            while let Some(func) = work.deque(Some(closed.clone())).await {
-                //call and await the future, then send the result
-                let func_result = func.await;
-                let send_result = sender.send(func_result).await;
-
-                //the channel was closed.
-                if send_result.is_err() {
-                    break;
+            while let Some(job) = work.deque(Some(closed.clone())).await {
+                let queued_for = job.waited();
+                let expired = job.is_expired();
+                let Job { work: func, reply, cancel, budget, on_expired, .. } = job;
+
+                //work cancelled or past its deadline while it was queued is skipped.
+                let skipped = if cancel.is_cancelled() {
+                    supervisor.cancelled();
+                    Some(WorkerError::Cancelled)
+                } else if expired {
+                    supervisor.expired();
+
+                    if let Some(on_expired) = on_expired {
+                        on_expired();
+                    }
+
+                    Some(WorkerError::Expired)
+                } else {
+                    None
+                };
+
+                if let Some(error) = skipped {
+                    if let Some(reply) = reply {
+                        let _ = reply.send(Err(error));
+                    }
+
+                    continue;
+                }
+
+                supervisor.started(queued_for);
+
+                //call and await the future unless it is cancelled or runs out of budget first, then send the result
+                let started = Instant::now();
+                *busy_since.lock().await = Some(started);
+                let func_result = tokio::select! {
+                    biased;
+
+                    _ = cancel.cancelled() => Err(WorkerError::Cancelled),
+                    _ = over_budget(budget) => Err(WorkerError::TimedOut),
+                    func_result = AssertUnwindSafe(func).catch_unwind() => Ok(func_result),
+                };
+                *busy_since.lock().await = None;
+
+                //a panic is reported and the worker takes the next piece of work, the submitter sees the work as lost.
+                let func_result = match func_result {
+                    Ok(Ok(func_result)) => {
+                        supervisor.finished(started.elapsed());
+                        Ok(func_result)
+                    }
+                    Ok(Err(payload)) => {
+                        supervisor.crashed(payload, started.elapsed());
+                        continue;
+                    }
+                    Err(WorkerError::Cancelled) => {
+                        supervisor.cancelled();
+                        Err(WorkerError::Cancelled)
+                    }
+                    Err(error) => {
+                        supervisor.timed_out();
+                        Err(error)
+                    }
+                };
+
+                //a submitter that stopped waiting is ignored.
+                if let Some(reply) = reply {
+                    let _ = reply.send(func_result);
                 }
             }
         });
@@ -79,6 +149,53 @@ where
         Ok(())
     }
 
+    /// # Busy For
+    ///
+    /// How long the worker has been working on its current piece of work, None if it is idle.
+    pub async fn busy_for(&self) -> Option<Duration> {
+        self.busy_since.lock().await.map(|since| since.elapsed())
+    }
+
+    /// Returns true if the worker was started and its task has not stopped.
+    pub fn is_running(&self) -> bool {
+        self.task.as_ref().is_some_and(|task| !task.is_finished())
+    }
+
+    /// Returns true if the task of the worker stopped without the worker being closed.
+    pub async fn is_dead(&self) -> bool {
+        self.task.as_ref().is_some_and(|task| task.is_finished()) && !*self.closed.lock().await
+    }
+
+    /// Returns true if the worker is not working on a piece of work.
+    pub async fn is_idle(&self) -> bool {
+        self.busy_since.lock().await.is_none()
+    }
+
+    /// # Retire
+    ///
+    /// Stops the worker without waiting for it, a piece of work it already took is still finished.
+    ///
+    /// Returns false if the worker was not running or was already closed.
+    pub async fn retire(&mut self) -> bool {
+        if self.task.is_none() {
+            return false;
+        }
+
+        let mut closed = self.closed.lock().await;
+
+        if *closed {
+            return false;
+        }
+
+        *closed = true;
+        drop(closed);
+
+        self.work.deque_lock.notify_waiters();
+        self.task = None;
+
+        true
+    }
+
     /// # Close
     ///
     /// Closes the worker, it does so by setting the closed flag to true, then joining the ongoing task.
@@ -102,7 +219,8 @@ where
         *running_guard = true;
         drop(running_guard);
 
-        self.work.deque_lock.notify_one();
+        //wake every waiting worker, one of them is this worker.
+        self.work.deque_lock.notify_waiters();
 
         let task = self.task.as_mut();
 
@@ -117,3 +235,11 @@ where
         Ok(())
     }
 }
+
+/// waits out the budget of a job, forever without one.
+async fn over_budget(budget: Option<Duration>) {
+    match budget {
+        Some(budget) => tokio::time::sleep(budget).await,
+        None => std::future::pending().await,
+    }
+}
@@ -1,6 +1,8 @@
 pub mod factory;
 pub mod web;
 
+pub use web::errors::{Error, Result};
+
 #[cfg(test)]
 mod tests {
 
@@ -11,10 +13,13 @@ mod tests {
     use crate::{
         resolve,
         web::{
-            App, EndPoint, Method, Resolution,
+            App, EndPoint, HeaderMap, Method, Resolution, StatusCode,
             resolution::{
-                empty_resolution::EmptyResolution, file_resolution::FileResolution,
-                json_resolution::JsonResolution, merged_resolution::and,
+                archive_resolution::{ArchiveFormat, ArchiveResolution},
+                empty_resolution::EmptyResolution,
+                file_resolution::FileResolution,
+                json_resolution::JsonResolution,
+                merged_resolution::and,
             },
             routing::router::route_tree::RouteTree,
         },
@@ -121,4 +126,4236 @@ mod tests {
 
         drop(closure_guard);
     }
+
+    //ensures that merged content is sent in order.
+    #[tokio::test]
+    async fn test_chain() {
+        use crate::web::resolution::{merged_resolution::chain, text_resolution::TextResolution};
+        use futures::StreamExt;
+
+        let text = |body: &str| TextResolution::new(body).resolve();
+
+        let merged = chain([text("one "), text("two ")]).and(JsonResolution::serialize(1).unwrap());
+        assert_eq!(merged.content_length(), Some(9));
+        assert_eq!(merged.get_headers().get("Content-Type"), Some("text/plain; charset=utf-8"));
+        assert_eq!(merged.get_content().concat().await, b"one two 1");
+
+        //the content is only served once.
+        assert!(merged.get_content().concat().await.is_empty());
+    }
+
+    //ensures that archives are streamed with valid framing.
+    #[tokio::test]
+    async fn test_archive() {
+        use futures::StreamExt;
+
+        let mut archive = ArchiveResolution::new("test.tar", ArchiveFormat::Tar);
+        archive.add_bytes("hello.txt", b"hello world".to_vec());
+        archive.add_file("missing.txt", "does/not/exist.txt");
+
+        let tar: Vec<u8> = archive.get_content().concat().await;
+
+        //one header, one data block, two end blocks. the missing file is skipped.
+        assert_eq!(tar.len(), 512 * 4, "tar was not block aligned");
+        assert_eq!(&tar[512..523], b"hello world");
+
+        //a long name that can not be split is cut at a character, not inside one.
+        let mut archive = ArchiveResolution::new("test.tar", ArchiveFormat::Tar);
+        archive.add_bytes(&format!("a{}", "é".repeat(60)), b"x".to_vec());
+
+        let tar: Vec<u8> = archive.get_content().concat().await;
+        let name = &tar[..100];
+        let name = &name[..name.iter().position(|b| *b == 0).unwrap_or(100)];
+        assert_eq!(name.len(), 99);
+        assert!(std::str::from_utf8(name).is_ok());
+
+        let mut archive = ArchiveResolution::new("test.zip", ArchiveFormat::Zip);
+        archive.add_bytes("hello.txt", b"hello world".to_vec());
+
+        let zip: Vec<u8> = archive.get_content().concat().await;
+
+        assert_eq!(&zip[..4], &0x04034b50u32.to_le_bytes());
+        assert_eq!(&zip[zip.len() - 22..zip.len() - 18], &0x06054b50u32.to_le_bytes());
+    }
+
+    //ensures that a signed jwt is accepted and a tampered one is not.
+    #[test]
+    fn test_jwt() {
+        use crate::web::{
+            auth::{Hs256, decode_jwt},
+            errors::AuthError,
+        };
+        use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+
+        let verifier = Hs256::new(b"secret");
+
+        let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"HS256","typ":"JWT"}"#);
+        let payload = URL_SAFE_NO_PAD.encode(r#"{"sub":"42"}"#);
+        let signing_input = format!("{header}.{payload}");
+        let signature = URL_SAFE_NO_PAD.encode(verifier.sign(signing_input.as_bytes()));
+
+        let claims = decode_jwt(&format!("{signing_input}.{signature}"), &verifier);
+        assert!(claims.is_ok(), "valid token was rejected");
+        assert_eq!(claims.unwrap()["sub"], "42");
+
+        let forged = URL_SAFE_NO_PAD.encode(r#"{"sub":"1"}"#);
+        let tampered = decode_jwt(&format!("{header}.{forged}.{signature}"), &verifier);
+        assert!(tampered.is_err(), "tampered token was accepted");
+        let signed = |claims: &str| {
+            let signing_input = format!("{header}.{}", URL_SAFE_NO_PAD.encode(claims));
+            let signature = URL_SAFE_NO_PAD.encode(verifier.sign(signing_input.as_bytes()));
+            decode_jwt(&format!("{signing_input}.{signature}"), &verifier)
+        };
+
+        //NumericDates may have a fraction or be negative, any other value is rejected instead of ignored.
+        assert!(matches!(signed(r#"{"exp":1000000000.5}"#), Err(AuthError::Expired)));
+        assert!(matches!(signed(r#"{"exp":-1}"#), Err(AuthError::Expired)));
+        assert!(matches!(signed(r#"{"nbf":99999999999.5}"#), Err(AuthError::Expired)));
+        assert!(signed(r#"{"exp":99999999999.5,"nbf":1000000000.5}"#).is_ok());
+        assert!(matches!(signed(r#"{"exp":"1000000000"}"#), Err(AuthError::Malformed(_))));
+        assert!(matches!(signed(r#"{"nbf":null}"#), Err(AuthError::Malformed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_bearer() {
+        use crate::web::{
+            Middleware,
+            auth::{Principal, bearer, quoted_string},
+            routing::request::Request,
+        };
+
+        let middleware = bearer(vec!["first".to_string(), "second".to_string()]);
+
+        let mut req = Request::builder().header("Authorization", "Bearer second").build();
+        assert!(matches!(middleware(&mut req).await, Middleware::Next));
+        assert_eq!(req.extensions.get::<Principal>().map(|p| p.id.as_str()), Some("1"));
+
+        let mut prefix = Request::builder().header("Authorization", "Bearer secon").build();
+        assert!(matches!(middleware(&mut prefix).await, Middleware::InvalidEmpty(_)));
+        assert!(prefix.extensions.get::<Principal>().is_none());
+
+        //error text is escaped before it is put in the challenge.
+        assert_eq!(quoted_string("a \"b\" \\ c\r\n"), r#""a \"b\" \\ c""#);
+    }
+
+    #[test]
+    fn test_cookie_keyring() {
+        use crate::web::cookies::Keyring;
+
+        let mut keyring = Keyring::new(b"new secret");
+
+        let signed = keyring.sign("flash", "saved");
+        assert_eq!(keyring.verify("flash", &signed).as_deref(), Some("saved"));
+        assert!(keyring.verify("other", &signed).is_none(), "signature was moved");
+        assert!(keyring.verify("flash", &signed.replacen("saved", "owned", 1)).is_none());
+
+        //the split between name and value is part of the signature.
+        let shifted = keyring.sign("a=b", "c").replacen('c', "b=c", 1);
+        assert!(keyring.verify("a", &shifted).is_none(), "signature was moved across the name");
+
+        let encrypted = keyring.encrypt("session", "42");
+        assert_eq!(keyring.decrypt("session", &encrypted).as_deref(), Some("42"));
+
+        //values issued with a rotated secret are still accepted.
+        let old = Keyring::new(b"old secret").sign("flash", "saved");
+        assert!(keyring.verify("flash", &old).is_none());
+        keyring.add_previous(b"old secret");
+        assert_eq!(keyring.verify("flash", &old).as_deref(), Some("saved"));
+    }
+
+    #[test]
+    fn test_cookie_header_value() {
+        use crate::web::{cookies::Cookie, routing::request::Request};
+
+        let cookie = Cookie::new("session", "abc.123");
+        assert_eq!(cookie.header_value().unwrap(), "session=abc.123; Path=/; HttpOnly; Secure; SameSite=Lax");
+
+        //characters that would add attributes or headers are rejected.
+        assert!(Cookie::new("session", "a; Domain=evil.com").header_value().is_err());
+        assert!(Cookie::new("session", "a\r\nSet-Cookie: b=c").header_value().is_err());
+        assert!(Cookie::new("a=b", "c").header_value().is_err());
+        assert!(Cookie::new("", "c").header_value().is_err());
+
+        let mut path = Cookie::new("session", "a");
+        path.path = Some("/; Secure=false".to_string());
+        assert!(path.header_value().is_err());
+
+        let mut request = Request::builder().build();
+        assert!(request.set_cookie(Cookie::new("bad name", "a")).is_err());
+        assert!(request.set_cookie(Cookie::new("good", "\"quoted\"")).is_ok());
+        assert_eq!(request.take_cookies().len(), 1);
+    }
+
+    #[test]
+    fn test_common_log_format() {
+        use crate::web::access_log::AccessRecord;
+
+        let record = AccessRecord {
+            timestamp: 971_186_136,
+            client: "127.0.0.1".parse().unwrap(),
+            method: "GET".to_string(),
+            path: "/index.html".to_string(),
+            status: 200,
+            bytes: 2326,
+            latency_ms: 4,
+            request_id: None,
+        };
+
+        assert_eq!(
+            record.to_common(),
+            "127.0.0.1 - - [10/Oct/2000:13:55:36 +0000] \"GET /index.html HTTP/1.1\" 200 2326 4ms"
+        );
+    }
+
+    #[test]
+    fn test_route_table() {
+        use crate::web::config::{ConfigRegistry, RouteTable};
+
+        let table = RouteTable::from_toml(
+            r#"
+            [[routes]]
+            path = "/api/users/{id}"
+            method = "post"
+            status = 201
+            cors = "permissive"
+            cache = "no-store"
+            rate_limit = { requests = 10, per_seconds = 60 }
+
+            [[routes]]
+            path = "/admin"
+            status = 200
+            auth = "admin"
+            "#,
+        )
+        .expect("the table did not parse");
+
+        let registry = ConfigRegistry::new();
+
+        assert_eq!(table.routes[0].method, "post");
+        assert_eq!(table.routes[0].middleware(&registry).map(|m| m.len()).ok(), Some(3));
+        assert!(table.routes[1].middleware(&registry).is_err(), "unknown auth was accepted");
+    }
+
+    #[test]
+    fn test_scope() {
+        use crate::web::routing::Scope;
+
+        let scope = Scope::new().exclude("/health").exclude("/public/{*}");
+
+        assert!(scope.contains("/admin", &Method::GET));
+        assert!(!scope.contains("/health", &Method::GET));
+        assert!(!scope.contains("/public/css/site.css", &Method::GET));
+
+        let posts = Scope::new().include("/users/{id}").method(Method::POST);
+
+        assert!(posts.contains("/users/1", &Method::POST));
+        assert!(!posts.contains("/users/1", &Method::GET));
+        assert!(!posts.contains("/users/1/delete", &Method::POST));
+    }
+
+    #[tokio::test]
+    async fn test_route_stats() {
+        use crate::web::stats::RouteStats;
+        use std::time::Duration;
+
+        let stats = RouteStats::new(100);
+
+        for ms in 1..=100 {
+            stats
+                .record("/users/{id}", "200 OK", Duration::from_millis(ms), None)
+                .await;
+        }
+
+        stats
+            .record(
+                "/users/{id}",
+                "500 Internal Server Error",
+                Duration::from_millis(1),
+                Some("db down".to_string()),
+            )
+            .await;
+
+        let users = stats.route("/users/{id}").await.unwrap();
+
+        assert_eq!(users.requests, 101);
+        assert_eq!(users.success, 100);
+        assert_eq!(users.server_errors, 1);
+        assert_eq!(users.p50_ms, 50.0);
+        assert_eq!(users.p95_ms, 95.0);
+        assert_eq!(users.last_error.unwrap().message, "db down");
+        assert!(stats.route("/missing").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_body_limits() {
+        use crate::web::{Request, routing::request::BodyLimits};
+
+        let limits = BodyLimits {
+            max_size: 4,
+            ..BodyLimits::default()
+        };
+        let client = "127.0.0.1:1".parse().unwrap();
+
+        let mut small: &[u8] = b"POST /upload HTTP/1.1\r\nContent-Length: 4\r\n\r\nabcd";
+        let request = Request::from_stream_limited(&mut small, client, limits)
+            .await
+            .unwrap();
+
+        assert!(!request.body_too_large());
+        assert_eq!(request.body.as_deref(), Some(&b"abcd"[..]));
+
+        let mut large: &[u8] = b"POST /upload HTTP/1.1\r\nContent-Length: 5\r\n\r\nabcde";
+        let request = Request::from_stream_limited(&mut large, client, limits)
+            .await
+            .unwrap();
+
+        assert!(request.body_too_large());
+        assert!(request.body.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_idempotency() {
+        use crate::web::{
+            Middleware, Request,
+            idempotency::{Idempotency, IdempotencyClaim, idempotency},
+        };
+        use std::time::Duration;
+
+        let store = Idempotency::new(Duration::from_secs(60));
+        let middleware = idempotency(store.clone());
+        let client = "127.0.0.1:1".parse().unwrap();
+
+        let request = |body: &'static [u8]| async move {
+            let raw = format!(
+                "POST /payments HTTP/1.1\r\nIdempotency-Key: abc\r\nContent-Length: {}\r\n\r\n",
+                body.len()
+            );
+            let raw = [raw.as_bytes(), body].concat();
+            Request::from_stream(&mut raw.as_slice(), client).await.unwrap()
+        };
+
+        let mut first = request(b"{}").await;
+        assert!(matches!(middleware(&mut first).await, Middleware::Next));
+
+        //a retry while the first is running
+        let mut retry = request(b"{}").await;
+        assert!(matches!(middleware(&mut retry).await, Middleware::InvalidEmpty(StatusCode::CONFLICT)));
+
+        let claim = first.extensions.remove::<IdempotencyClaim>().unwrap();
+        claim.complete("201 Created", HeaderMap::new(), Some(b"{}".to_vec()));
+
+        let mut retry = request(b"{}").await;
+        assert!(matches!(middleware(&mut retry).await, Middleware::Invalid(_)));
+
+        let mut changed = request(b"{\"amount\":1}").await;
+        assert!(matches!(middleware(&mut changed).await, Middleware::InvalidEmpty(StatusCode::UNPROCESSABLE_ENTITY)));
+
+        //a ttl too large for an instant keeps the response instead of panicking.
+        let forever = Idempotency::new(Duration::MAX);
+        let middleware = idempotency(forever);
+
+        let mut first = request(b"{}").await;
+        assert!(matches!(middleware(&mut first).await, Middleware::Next));
+        let claim = first.extensions.remove::<IdempotencyClaim>().unwrap();
+        claim.complete("201 Created", HeaderMap::new(), Some(b"{}".to_vec()));
+
+        let mut retry = request(b"{}").await;
+        assert!(matches!(middleware(&mut retry).await, Middleware::Invalid(_)));
+    }
+
+    #[tokio::test]
+    async fn test_extractors() {
+        use crate::web::{
+            Request,
+            extract::{IntoHandler, Json, Path, State, StateMap},
+        };
+
+        #[derive(serde::Deserialize)]
+        struct CreateUser {
+            name: String,
+        }
+
+        let handler = (|Path(id): Path<u64>, Json(body): Json<CreateUser>, State(prefix): State<String>| async move {
+            let status = if id == 7 && body.name == "ada" && prefix == "v1" { 201 } else { 500 };
+            EmptyResolution::status(status).resolve()
+        })
+        .into_handler();
+
+        let mut state = StateMap::new();
+        state.insert("v1".to_string());
+
+        let raw = b"POST /users/7 HTTP/1.1\r\nContent-Length: 14\r\n\r\n{\"name\":\"ada\"}";
+        let mut request = Request::from_stream(&mut &raw[..], "127.0.0.1:1".parse().unwrap())
+            .await
+            .unwrap();
+        request.variables.insert("id".to_string(), "7".to_string());
+        request.extensions.insert(Arc::new(state));
+
+        let status = async |req: &mut Request| {
+            handler(req).await.get_headers().status().map(str::to_string)
+        };
+
+        assert_eq!(status(&mut request).await.as_deref(), Some("201 Created"));
+
+        request.variables.insert("id".to_string(), "seven".to_string());
+        assert_eq!(status(&mut request).await.as_deref(), Some("400 Bad Request"));
+    }
+
+    #[tokio::test]
+    async fn test_range() {
+        use crate::web::range::{ByteRange, RangeRequest, parse_range, take_bytes};
+        use futures::StreamExt;
+
+        let partial = |start, end| RangeRequest::Partial(ByteRange { start, end });
+
+        assert_eq!(parse_range("bytes=0-9", 100), partial(0, 9));
+        assert_eq!(parse_range("bytes=90-", 100), partial(90, 99));
+        assert_eq!(parse_range("bytes=-10", 100), partial(90, 99));
+        assert_eq!(parse_range("bytes=50-500", 100), partial(50, 99));
+        assert_eq!(parse_range("bytes=100-", 100), RangeRequest::Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-1,5-9", 100), RangeRequest::Full);
+        assert_eq!(parse_range("items=0-9", 100), RangeRequest::Full);
+
+        let content = futures::stream::iter(vec![b"hello ".to_vec(), b"world".to_vec()]);
+        let taken: Vec<Vec<u8>> = take_bytes(Box::pin(content), 8).collect().await;
+
+        assert_eq!(taken.concat(), b"hello wo");
+    }
+
+    #[test]
+    fn test_into_resolution() {
+        use crate::web::resolution::IntoResolution;
+
+        let status = |resolution: Box<dyn Resolution + Send>| {
+            resolution.get_headers().status().map(str::to_string)
+        };
+
+        let text = "hello".into_resolution();
+        assert_eq!(status(text).as_deref(), Some("200 OK"));
+
+        let missing = (404, "not found").into_resolution();
+        let headers = missing.get_headers();
+        assert_eq!(status(missing).as_deref(), Some("404 Not Found"));
+        assert_eq!(
+            headers.get("Content-Type"),
+            Some("text/plain; charset=utf-8")
+        );
+
+        let json = serde_json::json!({ "ok": true }).into_resolution();
+        assert_eq!(status(json).as_deref(), Some("200 OK"));
+    }
+
+    #[test]
+    fn test_pass_resolution() {
+        use crate::web::resolution::IntoResolution;
+
+        let passed = None::<&'static str>.into_resolution();
+        assert!(passed.is_pass());
+        assert_eq!(
+            passed.get_headers().status(),
+            Some("404 Not Found")
+        );
+
+        assert!(!Some("cached").into_resolution().is_pass());
+        assert!(!(404, "not found").into_resolution().is_pass());
+    }
+
+    #[test]
+    fn test_header_map() {
+        let mut headers = HeaderMap::new();
+
+        headers.set_status(StatusCode::OK);
+        headers.insert("content-type", Some("text/plain".to_string()));
+        headers.set_content_type("application/json");
+        headers.append_set_cookie("a=1");
+        headers.append_set_cookie("b=2");
+
+        assert_eq!(headers.status(), Some("200 OK"));
+        assert_eq!(headers.get("CONTENT-TYPE"), Some("application/json"));
+        assert_eq!(headers.get_all("set-cookie"), vec!["a=1", "b=2"]);
+        assert_eq!(headers.len(), 4);
+
+        let mut other = HeaderMap::new();
+        other.append_set_cookie("c=3");
+        headers.merge(other);
+
+        assert_eq!(headers.get_all("Set-Cookie"), vec!["c=3"]);
+    }
+
+    #[test]
+    fn test_negotiation() {
+        use crate::web::{negotiate::Accepts, resolution::negotiated_resolution::NegotiatedResolution};
+
+        let accepts = Accepts::parse("text/*;q=0.5, application/json, text/html;q=0");
+        assert_eq!(accepts.quality("text/plain; charset=utf-8"), 0.5);
+        assert!(!accepts.accepts("text/html"));
+        assert_eq!(accepts.preferred(&["text/plain", "application/json"]), Some("application/json"));
+
+        let negotiated = |accept: &str| {
+            NegotiatedResolution::new(Accepts::parse(accept))
+                .json(&serde_json::json!({ "name": "ada" }))
+                .html("<h1>ada</h1>")
+                .text("ada")
+        };
+
+        let html = negotiated("text/html,application/xhtml+xml,*/*;q=0.8");
+        assert_eq!(html.selected_type(), Some("text/html"));
+        assert_eq!(html.get_headers().get("Vary"), Some("Accept"));
+
+        assert_eq!(negotiated("*/*").selected_type(), Some("application/json"));
+        assert_eq!(
+            negotiated("image/png").get_headers().status(),
+            Some("406 Not Acceptable")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_static_files() {
+        use crate::web::static_files::StaticFiles;
+        use futures::StreamExt;
+
+        let root = std::env::temp_dir().join(format!("async-web-static-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::write(root.join("index.html"), "<h1>home</h1>").unwrap();
+        std::fs::write(root.join("docs/a.txt"), "a").unwrap();
+
+        let files = StaticFiles::new(&root).listing(true);
+        let status = |resolution: Box<dyn Resolution + Send>| {
+            resolution.get_headers().status().map(str::to_string)
+        };
+
+        let index = files.resolution("", "/assets", None).await;
+        assert_eq!(index.get_headers().get("Content-Type"), Some("text/html"));
+        assert_eq!(status(index).as_deref(), Some("200 OK"));
+
+        let text = files.resolution("docs/a.txt", "/assets/docs/a.txt", None).await;
+        let etag = text.get_headers().get("ETag").unwrap().to_string();
+        let cached = files.resolution("docs/a.txt", "/assets/docs/a.txt", Some(&etag)).await;
+        assert_eq!(status(cached).as_deref(), Some("304 Not Modified"));
+
+        let listing = files.resolution("docs", "/assets/docs", None).await;
+        let body = listing.get_content().concat().await;
+        assert!(String::from_utf8(body).unwrap().contains("href=\"/assets/docs/a.txt\""));
+
+        let escaped = files.resolution("docs/../../etc/passwd", "/", None).await;
+        assert_eq!(status(escaped).as_deref(), Some("400 Bad Request"));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_static_bundle() {
+        let bundle = crate::static_bundle!(".", ["README.md"])
+            .add("/docs/index.html", "<h1>docs</h1>".repeat(100).as_bytes())
+            .gzip(64);
+
+        assert_eq!(bundle.len(), 2);
+        assert_eq!(bundle.get("README.md").unwrap().content_type, "text/markdown");
+
+        let docs = bundle.get("docs").unwrap();
+        assert_eq!(docs.encodings(), vec!["gzip"]);
+
+        let gzipped = bundle.resolution("docs/", None, Some("br;q=1, gzip;q=0.5"));
+        let headers = gzipped.get_headers();
+        assert_eq!(headers.get("Content-Encoding"), Some("gzip"));
+        assert_eq!(headers.get("Content-Type"), Some("text/html"));
+
+        let plain = bundle.resolution("docs/index.html", None, Some("gzip;q=0"));
+        assert_eq!(plain.seekable_len(), Some(docs.bytes().len() as u64));
+
+        let cached = bundle.resolution("docs", Some(&docs.etag), None);
+        assert_eq!(cached.get_headers().status(), Some("304 Not Modified"));
+
+        let missing = bundle.resolution("missing.js", None, None);
+        assert_eq!(missing.get_headers().status(), Some("404 Not Found"));
+    }
+
+    #[tokio::test]
+    async fn test_file_resolution() {
+        use futures::StreamExt;
+
+        let path = std::env::temp_dir().join(format!("async-web-file-{}.bin", std::process::id()));
+        let bytes: Vec<u8> = (0..=255).cycle().take(100_000).collect();
+        std::fs::write(&path, &bytes).unwrap();
+
+        let file = FileResolution::new(&path.to_string_lossy());
+        let headers = file.get_headers();
+
+        assert_eq!(file.content_length(), Some(100_000));
+        assert_eq!(headers.get("Content-Type"), Some("application/octet-stream"));
+        assert_eq!(file.get_content().concat().await, bytes);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_attachment() {
+        use crate::web::resolution::{
+            Response,
+            attachment_resolution::{AttachmentResolution, content_disposition},
+        };
+
+        assert_eq!(content_disposition("report.csv"), "attachment; filename=\"report.csv\"");
+        assert_eq!(
+            content_disposition("naïve \"q\".csv"),
+            "attachment; filename=\"na_ve _q_.csv\"; filename*=UTF-8''na%C3%AFve%20%22q%22.csv"
+        );
+
+        let export = AttachmentResolution::new("../export.json", JsonResolution::serialize(1).unwrap().resolve());
+        assert_eq!(
+            export.get_headers().get("Content-Disposition"),
+            Some("attachment; filename=\".._export.json\"")
+        );
+
+        let csv = Response::builder().attachment("a.csv").text("a,b");
+        assert!(csv.get_headers().contains("content-disposition"));
+    }
+
+    #[tokio::test]
+    async fn test_redirect() {
+        use crate::web::resolution::redirect::Redirect;
+        use futures::StreamExt;
+
+        let id = 7;
+        let created = Redirect::see_other(format!("/users/{id}"));
+        let headers = created.get_headers();
+        assert_eq!(headers.status(), Some("303 See Other"));
+        assert_eq!(headers.get("Location"), Some("/users/7"));
+
+        let choices = Redirect::multiple_choices(["/doc.html", "/doc.pdf"]);
+        assert_eq!(choices.get_headers().get("Location"), Some("/doc.html"));
+        assert_eq!(choices.get_content().concat().await, b"/doc.html\r\n/doc.pdf\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_error_map() {
+        use crate::web::{
+            error_map::ErrorMap,
+            errors::RoutingError,
+            resolution::error_resolution::{Configured, ErrorResolution},
+        };
+        use futures::StreamExt;
+
+        let mut map = ErrorMap::new();
+        map.map::<serde_json::Error>(400);
+        map.map::<RoutingError>(StatusCode::NOT_FOUND);
+        map.set_debug(true);
+
+        let (invalid, missing, other, overridden) = Arc::new(map)
+            .scope(async {
+                let invalid = serde_json::from_str::<u8>("x").unwrap_err();
+
+                (
+                    ErrorResolution::from_error(invalid, Configured::Json),
+                    ErrorResolution::from_error(RoutingError::NoRouteExist, None),
+                    ErrorResolution::from_error(std::fmt::Error, None),
+                    ErrorResolution::from_error(RoutingError::Exist, None).with_status(409),
+                )
+            })
+            .await;
+
+        assert_eq!(invalid.code, StatusCode::BAD_REQUEST);
+        assert_eq!(missing.code, StatusCode::NOT_FOUND);
+        assert_eq!(other.code, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(overridden.get_headers().status(), Some("409 Conflict"));
+
+        let json: serde_json::Value =
+            serde_json::from_slice(&invalid.get_content().concat().await).unwrap();
+        assert_eq!(json["code"], 400);
+        assert!(json["backtrace"].is_string());
+
+        //errors that are not Send are formatted immediately.
+        let local = ErrorResolution::from_non_send(std::rc::Rc::new("rc").to_string(), None);
+        assert_eq!(local.get_content().concat().await, b"rc");
+
+        //outside of an app nothing is mapped.
+        let unmapped = ErrorResolution::from_error(RoutingError::NoRouteExist, None);
+        assert_eq!(unmapped.code, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn test_problem_details() {
+        use crate::web::{
+            error_map::ErrorMap,
+            errors::RoutingError,
+            resolution::{
+                error_resolution::{Configured, ErrorResolution},
+                problem_details::ProblemDetails,
+            },
+        };
+        use futures::StreamExt;
+
+        let problem = ProblemDetails::new(403)
+            .with_type("https://example.com/probs/out-of-credit")
+            .detail("Your current balance is 30, but that costs 50.")
+            .extension("balance", 30)
+            .extension("status", 200);
+
+        assert_eq!(problem.get_headers().get("Content-Type"), Some("application/problem+json"));
+
+        let json: serde_json::Value = serde_json::from_str(&problem.to_json()).unwrap();
+        assert_eq!(json["title"], "Forbidden");
+        assert_eq!(json["status"], 403);
+        assert_eq!(json["balance"], 30);
+
+        let mut map = ErrorMap::new();
+        map.map::<RoutingError>(404);
+        map.set_problem_details(true);
+
+        let error = Arc::new(map)
+            .scope(async { ErrorResolution::from_error(RoutingError::NoRouteExist, Configured::Json) })
+            .await;
+
+        assert_eq!(error.get_headers().get("Content-Type"), Some("application/problem+json"));
+
+        let json: serde_json::Value =
+            serde_json::from_slice(&error.get_content().concat().await).unwrap();
+        assert_eq!(json["type"], "about:blank");
+        assert_eq!(json["status"], 404);
+    }
+
+    #[test]
+    fn test_semantic_resolutions() {
+        use crate::web::resolution::semantic::{Accepted, Created, NoContent};
+
+        let id = 42;
+        let created = Created::at(format!("/users/{id}"))
+            .with_body(JsonResolution::serialize(serde_json::json!({ "id": id })).unwrap().resolve());
+        let headers = created.get_headers();
+
+        assert_eq!(headers.status(), Some("201 Created"));
+        assert_eq!(headers.get("Location"), Some("/users/42"));
+        assert_eq!(headers.get("Content-Type"), Some("application/json"));
+
+        assert_eq!(NoContent.get_headers().status(), Some("204 No Content"));
+
+        let accepted = Accepted::with_status_url("/jobs/7").get_headers();
+        assert_eq!(accepted.status(), Some("202 Accepted"));
+        assert_eq!(accepted.get("Location"), Some("/jobs/7"));
+    }
+
+    #[tokio::test]
+    async fn test_content_length() {
+        use crate::web::{
+            errors::RoutingError,
+            resolution::{
+                error_resolution::{Configured, ErrorResolution},
+                redirect::Redirect,
+                semantic::Created,
+            },
+        };
+        use futures::StreamExt;
+
+        let json = JsonResolution::serialize(serde_json::json!({ "id": 7 })).unwrap();
+        let length = json.get_content().concat().await.len() as u64;
+        assert_eq!(json.content_length(), Some(length));
+
+        let created = Created::at("/users/7").with_body(json.resolve());
+        assert_eq!(created.content_length(), Some(length));
+
+        let choices = Redirect::multiple_choices(["/a", "/b"]);
+        assert_eq!(choices.content_length(), Some(8));
+
+        let error = ErrorResolution::from_error(RoutingError::NoRouteExist, Configured::PlainText);
+        assert_eq!(error.content_length(), Some(error.get_content().concat().await.len() as u64));
+    }
+
+    #[tokio::test]
+    async fn test_app_builder() {
+        use crate::web::app_builder::AppConfig;
+        use std::time::Duration;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let config = AppConfig::from_toml("addr = \"127.0.0.1:0\"\nworkers = 2\nkeep_alive = 5").unwrap();
+        assert_eq!(config.workers, 2);
+        assert_eq!(config.max_body_size, 16 * 1024 * 1024);
+
+        let vars = [("APP_WORKERS", "3"), ("APP_ADDR", "127.0.0.1:0"), ("OTHER", "1")];
+        let env = AppConfig::from_vars("APP_", vars.map(|(k, v)| (k.to_string(), v.to_string()))).unwrap();
+        assert_eq!((env.workers, env.addr.as_str()), (3, "127.0.0.1:0"));
+
+        let mut app = App::builder().config(&config).build().await.unwrap();
+        let addr = app.local_addr().unwrap();
+
+        app.add_or_panic("/kept", Method::GET, None, |_req| async move {
+            JsonResolution::serialize(1).unwrap().resolve()
+        })
+        .await;
+        app.start().unwrap();
+
+        //both requests are answered on the same connection.
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+
+        for _ in 0..2 {
+            client.write_all(b"GET /kept HTTP/1.1\r\nHost: test\r\n\r\n").await.unwrap();
+
+            let mut response = Vec::new();
+            while !response.ends_with(b"\r\n\r\n1") {
+                let mut buffer = [0u8; 256];
+                let read = tokio::time::timeout(Duration::from_secs(5), client.read(&mut buffer))
+                    .await
+                    .unwrap()
+                    .unwrap();
+                assert!(read > 0, "the connection was closed");
+                response.extend_from_slice(&buffer[..read]);
+            }
+
+            assert!(response.starts_with(b"HTTP/1.1 200 OK"));
+        }
+
+        app.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_client_ip() {
+        use crate::web::{Request, forwarded::TrustedProxies, routing::request::{BodyLimits, HeadLimits}};
+        use std::net::IpAddr;
+
+        let proxies = TrustedProxies::parse(&["10.0.0.0/8", "::1"]).unwrap();
+        assert!(TrustedProxies::parse(&["10.0.0.0/33"]).is_err());
+        assert!(proxies.contains("::ffff:10.1.2.3".parse().unwrap()));
+
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        let proxy = "10.0.0.1:443".parse().unwrap();
+
+        //the nearest address that is not a proxy is the client, headers of other peers are ignored.
+        assert_eq!(proxies.client_ip(proxy, None, Some("1.1.1.1, 2.2.2.2, 10.0.0.2")), ip("2.2.2.2"));
+        assert_eq!(proxies.client_ip(proxy, Some("for=\"[2001:db8::1]:80\";proto=https"), Some("3.3.3.3")), ip("2001:db8::1"));
+        assert_eq!(proxies.client_ip("4.4.4.4:1".parse().unwrap(), None, Some("1.1.1.1")), ip("4.4.4.4"));
+
+        let raw = b"GET / HTTP/1.1\r\nX-Forwarded-For: 5.5.5.5\r\n\r\n";
+        let mut request = Request::from_reader(&mut &raw[..], proxy, BodyLimits::default(), HeadLimits::default()).await.unwrap();
+        assert_eq!(request.client_ip(), ip("10.0.0.1"));
+
+        request.extensions.insert(proxies);
+        assert_eq!(request.client_ip(), ip("5.5.5.5"));
+    }
+
+    #[cfg(feature = "proxy")]
+    #[tokio::test]
+    async fn test_proxy_resolution() {
+        use crate::web::resolution::proxy_resolution::{ProxyResolution, Upstream};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let upstream_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream_addr = upstream_listener.local_addr().unwrap();
+
+        //answers one request with a chunked body, returning the head it received.
+        let upstream_task = tokio::spawn(async move {
+            let (mut stream, _) = upstream_listener.accept().await.unwrap();
+
+            let mut head = Vec::new();
+            while !head.ends_with(b"\r\n\r\n") {
+                let mut byte = [0u8; 1];
+                stream.read_exact(&mut byte).await.unwrap();
+                head.push(byte[0]);
+            }
+
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nKeep-Alive: timeout=5\r\nX-Upstream: yes\r\n\r\n5\r\nhello\r\n0\r\n\r\n")
+                .await
+                .unwrap();
+
+            String::from_utf8(head).unwrap()
+        });
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+
+        let upstream = Upstream::new(&format!("http://{upstream_addr}/v2")).unwrap().strip_prefix("/api");
+        app.add_or_panic("/api/{*}", Method::GET, None, move |req| {
+            let upstream = upstream.clone();
+            async move { ProxyResolution::forward(&upstream, &*req.lock().await).await.resolve() }
+        })
+        .await;
+
+        let down = Upstream::new("http://127.0.0.1:1").unwrap();
+        app.add_or_panic("/down", Method::GET, None, move |req| {
+            let down = down.clone();
+            async move { ProxyResolution::forward(&down, &*req.lock().await).await.resolve() }
+        })
+        .await;
+        app.start().unwrap();
+
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        client
+            .write_all(b"GET /api/hello?x=1 HTTP/1.1\r\nHost: gateway\r\nConnection: close, X-Secret\r\nX-Secret: 1\r\nX-Forwarded-For: 6.6.6.6\r\n\r\n")
+            .await
+            .unwrap();
+
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).await.unwrap();
+        let response = String::from_utf8_lossy(&response).to_string();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(response.contains("X-Upstream:yes"), "{response}");
+        assert!(!response.contains("Keep-Alive"));
+        assert!(response.contains("hello"));
+
+        //hop-by-hop headers are stripped, an untrusted forwarding chain is replaced.
+        let head = upstream_task.await.unwrap();
+        assert!(head.starts_with("GET /v2/hello?x=1 HTTP/1.1\r\n"), "{head}");
+        assert!(head.contains(&format!("Host: {upstream_addr}\r\n")));
+        assert!(head.contains("X-Forwarded-For: 127.0.0.1\r\n"));
+        assert!(head.contains("X-Forwarded-Host: gateway\r\n"));
+        assert!(!head.contains("X-Secret") && !head.contains("6.6.6.6"));
+
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        client.write_all(b"GET /down HTTP/1.1\r\n\r\n").await.unwrap();
+
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).await.unwrap();
+        assert!(response.starts_with(b"HTTP/1.1 502 Bad Gateway"));
+
+        app.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_from_config() {
+        use crate::web::app_builder::{AppConfig, LogLevel};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let dir = std::env::temp_dir().join(format!("async-web-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("hello.txt"), "hello").unwrap();
+
+        let path = dir.join("app.toml");
+        let source = format!(
+            "addr = \"127.0.0.1:8080\"\nlog_level = \"error\"\n\n[[static_mounts]]\nprefix = \"/assets\"\nroot = {:?}\n",
+            dir.to_string_lossy()
+        );
+        std::fs::write(&path, source).unwrap();
+
+        //variables replace the settings of the file and keep the rest.
+        let config = AppConfig::from_file(path.to_str().unwrap()).await.unwrap();
+        let vars = [("ASYNC_WEB_WORKERS".to_string(), "4".to_string())];
+        let config = config.override_vars("ASYNC_WEB_", vars).unwrap();
+        assert_eq!((config.workers, config.log_level), (4, LogLevel::Error));
+        assert_eq!(config.static_mounts[0].prefix, "/assets");
+
+        std::fs::write(&path, std::fs::read_to_string(&path).unwrap().replace("8080", "0")).unwrap();
+        let mut app = App::from_config(path.to_str().unwrap()).await.unwrap();
+        let addr = app.local_addr().unwrap();
+        app.start().unwrap();
+
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        client.write_all(b"GET /assets/hello.txt HTTP/1.1\r\n\r\n").await.unwrap();
+
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).await.unwrap();
+        assert!(response.starts_with(b"HTTP/1.1 200 OK"));
+        assert!(response.ends_with(b"hello"));
+
+        app.close().await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_connection_limit() {
+        use crate::web::app_builder::AppConfig;
+        use crate::web::connection_limit::{ConnectionLimit, Overflow};
+        use std::time::Duration;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let config = AppConfig::from_toml("max_connections = 1\nconnection_overflow = \"reject\"").unwrap();
+        assert_eq!(config.connection_limit().unwrap().max(), 1);
+
+        let limit = ConnectionLimit::new(1).overflow(Overflow::Reject);
+        let mut app = App::builder()
+            .addr("127.0.0.1:0")
+            .keep_alive(Duration::from_secs(5))
+            .connection_limit(limit.clone())
+            .build()
+            .await
+            .unwrap();
+        let addr = app.local_addr().unwrap();
+
+        app.add_or_panic("/held", Method::GET, None, |_req| async move {
+            JsonResolution::serialize(1).unwrap().resolve()
+        })
+        .await;
+        app.start().unwrap();
+
+        //the first connection is kept alive and holds the only permit.
+        let mut held = tokio::net::TcpStream::connect(addr).await.unwrap();
+        held.write_all(b"GET /held HTTP/1.1\r\nHost: test\r\n\r\n").await.unwrap();
+
+        let mut response = Vec::new();
+        while !response.ends_with(b"\r\n\r\n1") {
+            let mut buffer = [0u8; 256];
+            let read = held.read(&mut buffer).await.unwrap();
+            assert!(read > 0, "the connection was closed");
+            response.extend_from_slice(&buffer[..read]);
+        }
+        assert_eq!(limit.active(), 1);
+
+        let mut rejected = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let mut response = Vec::new();
+        rejected.read_to_end(&mut response).await.unwrap();
+        assert!(response.starts_with(b"HTTP/1.1 503 Service Unavailable"));
+        assert_eq!(limit.rejected(), 1);
+
+        //the permit is released once the connection is closed.
+        drop(held);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while limit.active() > 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        app.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_socket_options() {
+        use crate::web::{app_builder::AppConfig, socket_options::SocketOptions};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let config = AppConfig::from_toml("[socket]\nreuse_port = true\nnodelay = true\nkeepalive = 60").unwrap();
+        assert!(config.socket.reuse_port && config.socket.nodelay);
+        assert_eq!((config.socket.keepalive, config.socket.backlog), (Some(60), 1024));
+
+        //both apps bind the same port with SO_REUSEPORT.
+        let options = SocketOptions { reuse_port: true, ..config.socket };
+        let mut first = App::bind_with("127.0.0.1:0", options.clone()).await.unwrap();
+        let addr = first.local_addr().unwrap();
+        let second = App::bind_with(addr, options).await.unwrap();
+        assert_eq!(second.local_addr().unwrap(), addr);
+        drop(second);
+
+        first.add_or_panic("/tuned", Method::GET, None, |_req| async move {
+            JsonResolution::serialize(1).unwrap().resolve()
+        })
+        .await;
+        first.start().unwrap();
+
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        client.write_all(b"GET /tuned HTTP/1.1\r\n\r\n").await.unwrap();
+
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).await.unwrap();
+        assert!(response.starts_with(b"HTTP/1.1 200 OK"));
+
+        first.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_swap_router() {
+        use crate::web::route_log::RouteChangeKind;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+
+        app.add_or_panic("/blue", Method::GET, None, |_req| async move {
+            EmptyResolution::status(200).resolve()
+        })
+        .await;
+        app.start().unwrap();
+
+        let mut green = RouteTree::new(None);
+        green
+            .add_route(
+                "/green",
+                Some((Method::GET, EndPoint::new(resolve!(_req, moves[], {EmptyResolution::status(200).resolve()}), None))),
+            )
+            .await
+            .unwrap();
+
+        let blue = app.swap_router(green).await;
+        assert!(blue.get_route("/blue").await.is_some());
+
+        //only the routes of the new tree are served.
+        for (path, served) in [("/green", true), ("/blue", false)] {
+            let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+            client.write_all(format!("GET {path} HTTP/1.1\r\n\r\n").as_bytes()).await.unwrap();
+
+            let mut response = Vec::new();
+            client.read_to_end(&mut response).await.unwrap();
+            assert_eq!(response.starts_with(b"HTTP/1.1 200 OK"), served, "{path}");
+        }
+
+        let changes = app.route_changes().changes().await;
+        let swap = changes.last().unwrap();
+        assert_eq!(swap.kind, RouteChangeKind::Swap);
+        assert_eq!((swap.added.as_slice(), swap.removed.as_slice()), (&["GET /green".to_string()][..], &["GET /blue".to_string()][..]));
+
+        app.close().await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_acceptors() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut app = App::builder().addr("127.0.0.1:0").acceptors(4).build().await.unwrap();
+        let addr = app.local_addr().unwrap();
+
+        app.add_or_panic("/accepted", Method::GET, None, |_req| async move {
+            JsonResolution::serialize(1).unwrap().resolve()
+        })
+        .await;
+        let mut handle = app.start().unwrap();
+
+        //every loop accepts from the same listener.
+        let clients = (0..8).map(|_| async move {
+            let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+            client.write_all(b"GET /accepted HTTP/1.1\r\n\r\n").await.unwrap();
+
+            let mut response = Vec::new();
+            client.read_to_end(&mut response).await.unwrap();
+            response.starts_with(b"HTTP/1.1 200 OK")
+        });
+        assert!(futures::future::join_all(clients).await.into_iter().all(|ok| ok));
+
+        app.close().await.unwrap();
+        handle.join().await;
+        assert!(handle.is_finished());
+    }
+
+    #[tokio::test]
+    async fn test_scheduler() {
+        use crate::factory::{Schedule, scheduler::Cron};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+        //2023-11-14 22:13:20 UTC, a tuesday.
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let weekdays = Cron::parse("30 9 * * 1-5").unwrap();
+        assert_eq!(weekdays.next_after(now), Some(UNIX_EPOCH + Duration::from_secs(1_700_040_600)));
+        let new_year = Cron::parse("0 0 1 1 *").unwrap();
+        assert_eq!(new_year.next_after(now), Some(UNIX_EPOCH + Duration::from_secs(1_704_067_200)));
+        assert_eq!(Cron::parse("0 0 31 2 *").unwrap().next_after(now), None);
+        assert!(Cron::parse("61 * * * *").is_err());
+        assert!(Cron::parse("* * *").is_err());
+        assert!(Schedule::cron("*/15 9-17 * * mon").is_err());
+        assert!(SystemTime::now() > now);
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let count = Arc::new(AtomicUsize::new(0));
+        let job_count = count.clone();
+
+        let job = app.scheduler().add(Schedule::every(Duration::from_millis(20)).jitter(Duration::from_millis(5)), move || {
+            let count = job_count.clone();
+            async move {
+                count.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+        assert_eq!(app.scheduler().len(), 1);
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(job.runs() >= 2);
+        assert!(count.load(Ordering::Relaxed) >= 2);
+
+        job.cancel();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(job.is_cancelled());
+        assert!(app.scheduler().is_empty());
+
+        //cancelled jobs no longer run.
+        let runs = count.load(Ordering::Relaxed);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(count.load(Ordering::Relaxed), runs);
+
+        app.start().unwrap();
+        app.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_autoscale() {
+        use crate::factory::{AutoscaleConfig, WorkManager, autoscale, queue::QueueState};
+        use std::time::Duration;
+
+        let config = AutoscaleConfig {
+            min_workers: 2,
+            max_workers: 6,
+            sample_every: Duration::from_millis(10),
+            target_utilization: 1.0,
+            scale_down_after: Duration::from_millis(50),
+        };
+        assert_eq!(config.desired_workers(0, 0), 2);
+        assert_eq!(config.desired_workers(3, 1), 4);
+        assert_eq!(config.desired_workers(40, 0), 6);
+
+        let work_manager = Arc::new(Mutex::new(WorkManager::<()>::new(1).await));
+
+        let task = autoscale::spawn(work_manager.clone(), config);
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(work_manager.lock().await.size(), 2);
+
+        //busy workers grow the pool up to the max.
+        for _ in 0..10 {
+            let mut work: std::pin::Pin<Box<dyn Future<Output = ()> + Send>> = Box::pin(tokio::time::sleep(Duration::from_millis(150)));
+
+            while let QueueState::Blocked(returned) = work_manager.lock().await.queue_work(work).await {
+                work = returned;
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        }
+        assert!(work_manager.lock().await.in_flight().await > 2);
+        assert!(work_manager.lock().await.size() <= 6);
+
+        //idle workers are stopped once the work is done.
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        assert_eq!(work_manager.lock().await.size(), 2);
+
+        //manual scaling stops at the max.
+        task.abort();
+        work_manager.lock().await.scale_workers(10).await;
+        assert_eq!(work_manager.lock().await.size(), 6);
+        assert_eq!(work_manager.lock().await.scale_to(3).await, 3);
+    }
+
+    #[tokio::test]
+    async fn test_queue_capacity() {
+        use crate::factory::{Queue, QueuePolicy, queue::QueueState};
+        use std::time::Duration;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let queue = Arc::new(Queue::new());
+        queue.set_capacity(Some(2), QueuePolicy::Reject).await;
+        assert!(matches!(queue.queue(1).await, QueueState::Free));
+        assert!(matches!(queue.queue(2).await, QueueState::Free));
+        assert!(matches!(queue.queue(3).await, QueueState::Full(3)));
+
+        //the oldest value makes room.
+        queue.set_capacity(Some(2), QueuePolicy::DropOldest).await;
+        assert!(matches!(queue.queue(3).await, QueueState::Free));
+        assert_eq!(queue.deque(None).await, Some(2));
+
+        //the producer waits for a value to be dequeued.
+        queue.set_capacity(Some(1), QueuePolicy::Block).await;
+        let producer = tokio::spawn({
+            let queue = queue.clone();
+            async move { matches!(queue.queue(4).await, QueueState::Free) }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!producer.is_finished());
+        assert_eq!(queue.deque(None).await, Some(3));
+        assert!(producer.await.unwrap());
+        assert_eq!(queue.len().await, 1);
+
+        //connections over the capacity are answered with 503.
+        let mut app = App::builder().addr("127.0.0.1:0").queue_capacity(1, QueuePolicy::Reject).build().await.unwrap();
+        let addr = app.local_addr().unwrap();
+
+        app.add_or_panic("/slow", Method::GET, None, |_req| async move {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            JsonResolution::serialize(1).unwrap().resolve()
+        })
+        .await;
+        app.start().unwrap();
+
+        let mut clients = Vec::new();
+
+        for _ in 0..3 {
+            let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+            client.write_all(b"GET /slow HTTP/1.1\r\n\r\n").await.unwrap();
+            clients.push(client);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        let mut statuses = Vec::new();
+
+        for mut client in clients {
+            let mut response = Vec::new();
+            client.read_to_end(&mut response).await.unwrap();
+            statuses.push(String::from_utf8_lossy(&response).lines().next().unwrap_or_default().to_string());
+        }
+        assert_eq!(statuses, ["HTTP/1.1 200 OK", "HTTP/1.1 200 OK", "HTTP/1.1 503 Service Unavailable"]);
+
+        app.close().await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_queue_wakeups() {
+        use crate::factory::{Queue, QueuePolicy};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        let queue = Arc::new(Queue::new());
+        queue.set_capacity(Some(1000), QueuePolicy::Block).await;
+        let taken = Arc::new(AtomicUsize::new(0));
+
+        //every consumer waits before the values are queued at once.
+        let consumers: Vec<_> = (0..4)
+            .map(|_| {
+                let queue = queue.clone();
+                let taken = taken.clone();
+
+                tokio::spawn(async move {
+                    while queue.deque(None).await.is_some() {
+                        taken.fetch_add(1, Ordering::Relaxed);
+                        tokio::time::sleep(Duration::from_millis(1)).await;
+                    }
+                })
+            })
+            .collect();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        for value in 0..500 {
+            queue.queue(value).await;
+        }
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while taken.load(Ordering::Relaxed) < 500 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert!(queue.is_empty().await);
+
+        consumers.iter().for_each(|consumer| consumer.abort());
+    }
+
+    #[tokio::test]
+    async fn test_worker_supervision() {
+        use crate::factory::{WorkManager, WorkerCrash, queue::QueueState};
+        use std::time::Duration;
+
+        let work_manager = WorkManager::<u32>::new(2).await;
+        let crashes = Arc::new(std::sync::Mutex::new(Vec::<WorkerCrash>::new()));
+        let crash_log = crashes.clone();
+        work_manager.supervisor().set_on_crash(Some(Arc::new(move |crash: &WorkerCrash| crash_log.lock().unwrap().push(crash.clone()))));
+
+        assert!(matches!(work_manager.queue_work(Box::pin(async { panic!("work failed") })).await, QueueState::Free));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        //the worker that panicked keeps working.
+        assert_eq!(work_manager.healthy_workers(), 2);
+        assert_eq!(*crashes.lock().unwrap(), [WorkerCrash { message: "work failed".to_string(), panics: 1 }]);
+        assert_eq!(work_manager.health(Duration::from_secs(1)).await.panics, 1);
+
+        let result = work_manager.submit(Box::pin(async { 7 })).await.ok().unwrap();
+        assert_eq!(result.await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_work_results() {
+        use crate::{factory::{WorkManager, queue::QueueState}, web::errors::WorkerError};
+
+        let work_manager = WorkManager::<u32>::new(2).await;
+
+        //each submitter awaits the result of its own work.
+        let first = work_manager.submit(Box::pin(async { 1 })).await.ok().unwrap();
+        let second = work_manager.submit(Box::pin(async { 2 })).await.ok().unwrap();
+        assert_eq!((second.await.unwrap(), first.await.unwrap()), (2, 1));
+
+        //work queued without a result is not held anywhere.
+        for value in 0..100 {
+            while let QueueState::Blocked(_) = work_manager.queue_work(Box::pin(async move { value })).await {
+                tokio::task::yield_now().await;
+            }
+        }
+
+        let lost = loop {
+            match work_manager.submit(Box::pin(async { panic!("lost") })).await {
+                Ok(result) => break result,
+                Err(_) => tokio::task::yield_now().await,
+            }
+        };
+        assert!(matches!(lost.await, Err(WorkerError::WorkLost)));
+    }
+
+    #[tokio::test]
+    async fn test_priorities() {
+        use crate::factory::{Priority, Queue, QueuePolicy, queue::QueueState};
+
+        let queue = Queue::new();
+        queue.set_capacity(Some(18), QueuePolicy::Block).await;
+
+        for priority in [Priority::Low, Priority::Normal, Priority::High] {
+            for _ in 0..6 {
+                queue.queue_with(priority, priority).await;
+            }
+        }
+
+        //higher priorities are served first, lower ones still get a turn each round.
+        let mut order = String::new();
+
+        while !queue.is_empty().await {
+            order.push(match queue.deque(None).await.unwrap() {
+                Priority::High => 'H',
+                Priority::Normal => 'N',
+                Priority::Low => 'L',
+            });
+        }
+        assert_eq!(order, "HHHHNNLHHNNLNNLLLL");
+
+        //a full queue drops lower priorities for higher ones, never the other way.
+        queue.set_capacity(Some(1), QueuePolicy::DropOldest).await;
+        queue.queue_with(Priority::High, Priority::High).await;
+        assert!(matches!(queue.queue_with(Priority::Low, Priority::Low).await, QueueState::Full(Priority::Low)));
+        assert!(matches!(queue.queue_with(Priority::High, Priority::Normal).await, QueueState::Free));
+        assert_eq!(queue.deque(None).await, Some(Priority::Normal));
+    }
+
+    #[tokio::test]
+    async fn test_work_stats() {
+        use crate::factory::{Metrics, WorkManager};
+        use std::{sync::atomic::{AtomicU64, Ordering}, time::Duration};
+
+        #[derive(Default)]
+        struct Counter(AtomicU64);
+
+        impl Metrics for Counter {
+            fn completed(&self, _: Duration) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let work_manager = WorkManager::<u32>::new(2).await;
+        let counter = Arc::new(Counter::default());
+        work_manager.supervisor().set_metrics(Some(counter.clone()));
+
+        for value in 0..3 {
+            let result = work_manager.submit(Box::pin(async move {
+                tokio::time::sleep(Duration::from_millis(2)).await;
+                value
+            })).await.ok().unwrap();
+            assert_eq!(result.await.unwrap(), value);
+        }
+        assert!(work_manager.submit(Box::pin(async { panic!("stats") })).await.ok().unwrap().await.is_err());
+
+        let stats = work_manager.stats().await;
+        assert_eq!((stats.workers, stats.completed, stats.panics), (2, 3, 1));
+        assert_eq!(stats.latency.count, 3);
+        assert_eq!(stats.queue_wait.count, 4);
+        assert!(stats.latency.p50_ms >= 1.0 && stats.latency.max_ms >= 2.0);
+        assert_eq!(stats.latency.buckets.iter().map(|bucket| bucket.count).sum::<u64>(), 3);
+        assert_eq!(counter.0.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn test_blocking_work() {
+        use crate::{
+            factory::WorkManager,
+            web::{Resolution, errors::WorkerError, resolution::blocking_resolution::BlockingResolution},
+        };
+        use futures::StreamExt;
+
+        let work_manager = WorkManager::<()>::new(1).await;
+
+        //blocking work runs beside the workers and is counted with their work.
+        let sum = work_manager.add_blocking_work(|| (1..=1_000u64).sum::<u64>());
+        assert_eq!(sum.await.unwrap(), 500_500);
+        assert!(matches!(work_manager.add_blocking_work(|| panic!("blocking")).await, Err(WorkerError::WorkLost)));
+
+        let stats = work_manager.stats().await;
+        assert_eq!((stats.completed, stats.panics), (1, 1));
+
+        let resolution = BlockingResolution::run(|| "computed").await;
+        assert_eq!(resolution.get_headers().status(), Some("200 OK"));
+        assert_eq!(resolution.get_content().concat().await, b"computed");
+
+        let failed = BlockingResolution::run(|| -> &'static str { panic!("blocking") }).await;
+        assert_eq!(failed.get_headers().status(), Some("500 Internal Server Error"));
+    }
+
+    #[tokio::test]
+    async fn test_cancellation() {
+        use crate::{factory::WorkManager, web::errors::WorkerError};
+        use std::{sync::atomic::{AtomicBool, Ordering}, time::Duration};
+        use tokio::io::AsyncWriteExt;
+
+        let work_manager = WorkManager::<u32>::new(1).await;
+
+        //the work stops at its next await once cancelled.
+        let handle = work_manager.add_work(|cancel| Box::pin(async move {
+            while !cancel.is_cancelled() {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+            1
+        })).await.ok().unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        handle.cancel();
+        assert!(matches!(handle.await, Err(WorkerError::Cancelled)));
+
+        let finished = work_manager.add_work(|_| Box::pin(async { 2 })).await.ok().unwrap();
+        assert_eq!(finished.await.unwrap(), 2);
+        assert_eq!(work_manager.stats().await.cancelled, 1);
+
+        //the app cancels the request of a client that disconnects.
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let seen = cancelled.clone();
+
+        app.add_or_panic("/slow", Method::GET, None, move |req| {
+            let seen = seen.clone();
+
+            async move {
+                let cancel = req.lock().await.cancel_token();
+                tokio::spawn(async move {
+                    cancel.cancelled().await;
+                    seen.store(true, Ordering::Relaxed);
+                });
+
+                tokio::time::sleep(Duration::from_secs(30)).await;
+                JsonResolution::serialize(1).unwrap().resolve()
+            }
+        })
+        .await;
+        app.start().unwrap();
+
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        client.write_all(b"GET /slow HTTP/1.1\r\nHost: test\r\n\r\n").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        drop(client);
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !cancelled.load(Ordering::Relaxed) {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_deadlines() {
+        use crate::{factory::{QueuePolicy, WorkManager}, web::errors::WorkerError};
+        use std::time::Duration;
+        use tokio::{io::{AsyncReadExt, AsyncWriteExt}, time::Instant};
+
+        let work_manager = WorkManager::<u32>::new(1).await;
+
+        //work that waited past its deadline is skipped, work over its budget is dropped.
+        let busy = work_manager.submit(Box::pin(async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            1
+        })).await.ok().unwrap();
+        let stale = work_manager.submit_with_deadline(Instant::now() + Duration::from_millis(10), None, Box::pin(async { 2 })).await.ok().unwrap();
+        assert_eq!(busy.await.unwrap(), 1);
+        assert!(matches!(stale.await, Err(WorkerError::Expired)));
+
+        let slow = work_manager.submit_with_deadline(Instant::now() + Duration::from_secs(5), Some(Duration::from_millis(10)), Box::pin(async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            3
+        })).await.ok().unwrap();
+        assert!(matches!(slow.await, Err(WorkerError::TimedOut)));
+
+        let stats = work_manager.stats().await;
+        assert_eq!((stats.completed, stats.expired, stats.timed_out), (1, 1, 1));
+
+        //the app answers connections that waited too long with 503.
+        let mut app = App::builder()
+            .addr("127.0.0.1:0")
+            .workers(1)
+            .queue_capacity(8, QueuePolicy::Block)
+            .queue_timeout(Duration::from_millis(50))
+            .build()
+            .await
+            .unwrap();
+        let addr = app.local_addr().unwrap();
+
+        app.add_or_panic("/slow", Method::GET, None, |_req| async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            JsonResolution::serialize(1).unwrap().resolve()
+        })
+        .await;
+        app.start().unwrap();
+
+        let mut served = tokio::net::TcpStream::connect(addr).await.unwrap();
+        served.write_all(b"GET /slow HTTP/1.1\r\nHost: test\r\n\r\n").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let mut stale = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stale.write_all(b"GET /slow HTTP/1.1\r\nHost: test\r\n\r\n").await.unwrap();
+
+        let mut response = Vec::new();
+        served.read_to_end(&mut response).await.unwrap();
+        assert!(response.starts_with(b"HTTP/1.1 200 OK"));
+
+        let mut response = Vec::new();
+        stale.read_to_end(&mut response).await.unwrap();
+        assert!(response.starts_with(b"HTTP/1.1 503 Service Unavailable"));
+    }
+
+    #[tokio::test]
+    async fn test_drain() {
+        use crate::factory::{DrainReport, QueuePolicy, WorkManager, queue::QueueState};
+        use std::time::Duration;
+
+        let mut work_manager = WorkManager::<()>::new(2).await;
+        work_manager.set_queue_capacity(Some(16), QueuePolicy::Reject).await;
+
+        for _ in 0..6 {
+            work_manager.queue_work(Box::pin(async { tokio::time::sleep(Duration::from_millis(20)).await })).await;
+        }
+
+        //the queued work finishes before the workers stop, new work is rejected.
+        let report = work_manager.drain(Duration::from_secs(5)).await;
+        assert_eq!(report, DrainReport { completed: 6, abandoned: 0, running: 0 });
+        assert!(report.is_clean());
+        assert!(matches!(work_manager.queue_work(Box::pin(async {})).await, QueueState::Full(_)));
+
+        //work left when the timeout passes is reported.
+        let mut work_manager = WorkManager::<()>::new(1).await;
+        work_manager.set_queue_capacity(Some(16), QueuePolicy::Reject).await;
+
+        for _ in 0..3 {
+            work_manager.queue_work(Box::pin(async { tokio::time::sleep(Duration::from_secs(5)).await })).await;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        let report = work_manager.drain(Duration::from_millis(50)).await;
+        assert_eq!(report, DrainReport { completed: 0, abandoned: 2, running: 1 });
+    }
+
+    #[tokio::test]
+    async fn test_scale_running_app() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+
+        app.add_or_panic("/", Method::GET, None, |_req| async move {
+            JsonResolution::serialize(1).unwrap().resolve()
+        })
+        .await;
+        app.start().unwrap();
+
+        //the workers grow and shrink while connections are served.
+        assert_eq!(app.scale_workers(8).await, 8);
+        assert_eq!(app.work_stats().await.workers, 8);
+
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        client.write_all(b"GET / HTTP/1.1\r\nHost: test\r\n\r\n").await.unwrap();
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).await.unwrap();
+        assert!(response.starts_with(b"HTTP/1.1 200 OK"));
+
+        //only idle workers are stopped, the worker may still be recording the connection.
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert_eq!(app.scale_workers(0).await, 1);
+        assert_eq!(app.work_stats().await.workers, 1);
+
+        app.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_spawn_per_connection() {
+        use crate::web::{app_builder::AppConfig, execution::ExecutionStrategy};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let config = AppConfig::from_toml("addr = \"127.0.0.1:0\"\n[execution.spawn_per_connection]\nmax_concurrent = 1").unwrap();
+        assert_eq!(config.execution, ExecutionStrategy::SpawnPerConnection { max_concurrent: Some(1) });
+
+        let mut app = App::builder().config(&config).build().await.unwrap();
+        let addr = app.local_addr().unwrap();
+
+        app.add_or_panic("/", Method::GET, None, |_req| async move {
+            JsonResolution::serialize(1).unwrap().resolve()
+        })
+        .await;
+        app.start().unwrap();
+
+        //connections over the bound wait for a running one to close.
+        let clients = (0..3).map(|_| async move {
+            let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+            client.write_all(b"GET / HTTP/1.1\r\nHost: test\r\n\r\n").await.unwrap();
+            let mut response = Vec::new();
+            client.read_to_end(&mut response).await.unwrap();
+            response
+        });
+
+        for response in futures::future::join_all(clients).await {
+            assert!(response.starts_with(b"HTTP/1.1 200 OK"));
+        }
+
+        //the workers did not run the connections.
+        assert_eq!(app.work_stats().await.completed, 0);
+    }
+
+    #[tokio::test]
+    async fn test_buffer_pool() {
+        use crate::web::buffer_pool::BufferPool;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let pool = BufferPool::new(1, 1024);
+
+        //a buffer is given back empty and reused.
+        let mut buffer = pool.take();
+        buffer.extend_from_slice(b"head");
+        let capacity = buffer.capacity();
+        drop(buffer);
+        assert_eq!(pool.idle(), 1);
+
+        let buffer = pool.take();
+        assert!(buffer.is_empty() && buffer.capacity() == capacity);
+        assert_eq!(pool.idle(), 0);
+        drop(buffer);
+
+        //buffers grown past the capacity are not kept.
+        pool.take().resize(4096, 0);
+        assert_eq!(pool.idle(), 0);
+
+        //the app writes its responses with the pool.
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+        let pool = BufferPool::default();
+        app.set_buffer_pool(pool.clone());
+
+        app.add_or_panic("/", Method::GET, None, |_req| async move {
+            JsonResolution::serialize(1).unwrap().resolve()
+        })
+        .await;
+        app.start().unwrap();
+
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        client.write_all(b"GET / HTTP/1.1\r\nHost: test\r\nAccept: */*\r\n\r\n").await.unwrap();
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).await.unwrap();
+        assert!(response.starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with(b"\r\n\r\n1"));
+        assert_eq!(pool.idle(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_streamed_response_framing() {
+        use crate::web::{HeaderMap, resolution::semantic::NoContent};
+        use futures::Stream;
+        use std::pin::Pin;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        struct Streamed;
+
+        impl Resolution for Streamed {
+            fn get_headers(&self) -> HeaderMap {
+                let mut headers = HeaderMap::new();
+                headers.set_status(200);
+                headers
+            }
+
+            fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+                Box::pin(futures::stream::iter([b"hello".to_vec(), Vec::new(), b" world".to_vec()]))
+            }
+
+            fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+                Box::new(self)
+            }
+        }
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+
+        app.add_or_panic("/streamed", Method::GET, None, |_req| async move { Streamed.resolve() }).await;
+        app.add_or_panic("/empty", Method::GET, None, |_req| async move { NoContent.resolve() }).await;
+        app.start().unwrap();
+
+        let request = |path: &'static str| async move {
+            let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+            client.write_all(format!("GET {path} HTTP/1.1\r\nHost: test\r\n\r\n").as_bytes()).await.unwrap();
+            let mut response = Vec::new();
+            client.read_to_end(&mut response).await.unwrap();
+            String::from_utf8(response).unwrap()
+        };
+
+        //the head, the chunk frames, and the chunks arrive in order, empty chunks are skipped.
+        let streamed = request("/streamed").await;
+        assert!(streamed.contains("Transfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n"));
+        assert!(streamed.ends_with("0\r\n\r\n"));
+
+        //a response without a body is only its head.
+        let empty = request("/empty").await;
+        assert!(empty.starts_with("HTTP/1.1 204 No Content\r\n"));
+        assert!(empty.ends_with("\r\n\r\n") && !empty.contains("chunked"));
+    }
+
+    #[tokio::test]
+    async fn test_borrowed_request() {
+        use crate::web::{Middleware, Request, middleware, middleware_fn, routing::resolution_fn};
+        use futures::FutureExt;
+        use std::panic::AssertUnwindSafe;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+
+        //middleware on the shared request runs before middleware borrowing it.
+        let shared = middleware(|req| async move {
+            req.lock().await.variables.insert("from".to_string(), "shared".to_string());
+            Middleware::Next
+        });
+
+        let borrowed = middleware_fn(|req| Box::pin(async move {
+            req.add_header("X-From".to_string(), req.variables.get("from").cloned());
+            Middleware::Next
+        }));
+
+        let handler = resolution_fn(|req: &mut Request| Box::pin(async move {
+            JsonResolution::serialize(req.variables.get("from").cloned()).unwrap().resolve()
+        }));
+
+        app.add_route_fn("/", Method::GET, crate::middleware!(shared, borrowed), handler)
+            .await
+            .unwrap();
+        app.start().unwrap();
+
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        client.write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n").await.unwrap();
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).await.unwrap();
+        let response = String::from_utf8_lossy(&response);
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("X-From:shared"));
+        assert!(response.contains("\"shared\""));
+
+        //the request is given back when the shared closure panics.
+        let mut request = Request::from_stream(&mut &b"GET /a HTTP/1.1\r\n\r\n"[..], addr).await.unwrap();
+        let panicked = AssertUnwindSafe(request.shared(|_| async { panic!("lost") })).catch_unwind().await;
+        assert!(panicked.is_err());
+        assert_eq!(request.route.init_route, "/a");
+
+        //a clone still locked when the closure completes is waited for.
+        let mut request = Request::from_stream(&mut &b"GET /b HTTP/1.1\r\n\r\n"[..], addr).await.unwrap();
+        request
+            .shared(|shared| async move {
+                let guard = shared.lock_owned().await;
+                tokio::spawn(async move {
+                    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                    drop(guard);
+                });
+            })
+            .await;
+        assert_eq!(request.route.init_route, "/b");
+    }
+
+    #[tokio::test]
+    async fn test_large_file_copy() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let path = std::env::temp_dir().join(format!("async-web-large-{}.bin", std::process::id()));
+        let bytes: Vec<u8> = (0..=255).cycle().take(3 * 1024 * 1024).collect();
+        std::fs::write(&path, &bytes).unwrap();
+
+        let file = FileResolution::new(&path.to_string_lossy());
+        assert_eq!(file.source_file(), Some(path.clone()));
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+        let served = path.to_string_lossy().to_string();
+
+        app.add_or_panic("/", Method::GET, None, move |_req| {
+            let served = served.clone();
+            async move { FileResolution::new(&served).resolve() }
+        })
+        .await;
+        app.start().unwrap();
+
+        let get = async |range: &str| {
+            let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+            let raw = format!("GET / HTTP/1.1\r\nConnection: close\r\n{range}\r\n");
+            client.write_all(raw.as_bytes()).await.unwrap();
+            let mut response = Vec::new();
+            client.read_to_end(&mut response).await.unwrap();
+
+            let split = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+            let body = response.split_off(split);
+            (String::from_utf8(response).unwrap(), body)
+        };
+
+        let (head, body) = get("").await;
+        assert!(head.starts_with("HTTP/1.1 200 OK"));
+        assert!(!head.contains("chunked"));
+        assert_eq!(body, bytes);
+
+        //ranges are copied from their offset.
+        let (head, body) = get("Range: bytes=1048576-2097151\r\n").await;
+        assert!(head.starts_with("HTTP/1.1 206"));
+        assert_eq!(body, &bytes[1048576..2097152]);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_head_limits() {
+        use crate::web::routing::request::HeadLimits;
+        use std::time::Duration;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+
+        app.set_keep_alive(Some(Duration::from_secs(5)));
+        app.set_max_requests_per_connection(Some(2));
+        app.set_head_limits(HeadLimits { line_timeout: Duration::from_millis(100), max_size: 1024, ..HeadLimits::default() });
+        app.add_or_panic("/", Method::GET, None, |_req| async move { "ok" }).await;
+        app.start().unwrap();
+
+        let read_all = async |client: &mut tokio::net::TcpStream| {
+            let mut response = Vec::new();
+            let _ = client.read_to_end(&mut response).await;
+            String::from_utf8_lossy(&response).to_string()
+        };
+
+        //a head larger than the limit is answered with 431.
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let large = format!("GET / HTTP/1.1\r\nX-Large: {}\r\n\r\n", "a".repeat(2048));
+        client.write_all(large.as_bytes()).await.unwrap();
+        assert!(read_all(&mut client).await.starts_with("HTTP/1.1 431"));
+
+        //a client that stops sending its head is closed.
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        client.write_all(b"GET / HTTP/1.1\r\nHost: slow").await.unwrap();
+        assert_eq!(read_all(&mut client).await, "");
+
+        //the connection is closed after its second request.
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        client.write_all(b"GET / HTTP/1.1\r\n\r\nGET / HTTP/1.1\r\n\r\n").await.unwrap();
+        let responses = read_all(&mut client).await;
+        assert_eq!(responses.matches("HTTP/1.1 200 OK").count(), 2);
+        assert!(responses.contains("Connection:close"));
+
+        let rejected = app.rejections().stats();
+        assert_eq!((rejected.large_head, rejected.slow_head, rejected.request_limit), (1, 1, 1));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn test_tracing_spans() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tracing::{
+            Event, Metadata,
+            field::{Field, Visit},
+            span::{Attributes, Id, Record},
+        };
+
+        //records the names of the spans and the statuses recorded on them.
+        #[derive(Default)]
+        struct Recorder {
+            next: AtomicU64,
+            seen: std::sync::Mutex<Vec<String>>,
+        }
+
+        struct Status<'a>(&'a std::sync::Mutex<Vec<String>>);
+
+        impl Visit for Status<'_> {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                if field.name() == "status" {
+                    self.0.lock().unwrap().push(format!("{value:?}"));
+                }
+            }
+
+            fn record_str(&mut self, field: &Field, value: &str) {
+                if field.name() == "status" {
+                    self.0.lock().unwrap().push(value.to_string());
+                }
+            }
+        }
+
+        impl tracing::Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                self.seen.lock().unwrap().push(span.metadata().name().to_string());
+                Id::from_u64(self.next.fetch_add(1, Ordering::Relaxed) + 1)
+            }
+
+            fn record(&self, _: &Id, values: &Record<'_>) {
+                values.record(&mut Status(&self.seen));
+            }
+
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+            fn event(&self, _: &Event<'_>) {}
+
+            fn enter(&self, _: &Id) {}
+
+            fn exit(&self, _: &Id) {}
+        }
+
+        let recorder = Arc::new(Recorder::default());
+        let _default = tracing::subscriber::set_default(recorder.clone());
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+
+        app.set_execution(crate::web::execution::ExecutionStrategy::SpawnPerConnection { max_concurrent: None });
+        app.add_or_panic("/", Method::GET, None, |_req| async move { "ok" }).await;
+        app.start().unwrap();
+
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        client.write_all(b"GET / HTTP/1.1\r\n\r\n").await.unwrap();
+        client.read_to_end(&mut Vec::new()).await.unwrap();
+
+        let seen = recorder.seen.lock().unwrap().clone();
+        assert_eq!(seen, ["connection", "request", "200 OK"]);
+    }
+
+    #[tokio::test]
+    async fn test_trace_context() {
+        use crate::web::{Middleware, Request, trace_context::{TraceContext, trace_context}};
+
+        let parent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let context = TraceContext::from_incoming(parent, Some("vendor=abc")).unwrap();
+        assert_eq!(context.trace_id_hex(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert!(context.sampled());
+        assert_eq!(context.state.as_deref(), Some("vendor=abc"));
+
+        //the server span becomes the parent of upstream requests.
+        let outgoing = context.traceparent();
+        assert!(outgoing.starts_with("00-4bf92f3577b34da6a3ce929d0e0e4736-"));
+        assert!(!outgoing.contains("00f067aa0ba902b7") && outgoing.ends_with("-01"));
+        assert_eq!(TraceContext::from_incoming(&outgoing, None).unwrap().trace_id, context.trace_id);
+
+        //malformed or zeroed ids are rejected, later versions may carry more fields.
+        assert!(TraceContext::from_incoming("00-00000000000000000000000000000000-00f067aa0ba902b7-01", None).is_none());
+        assert!(TraceContext::from_incoming("00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01", None).is_none());
+        assert!(TraceContext::from_incoming(&format!("{parent}-extra"), None).is_none());
+        assert!(TraceContext::from_incoming(&format!("01{}-extra", &parent[2..]), None).is_some());
+        assert!(TraceContext::from_incoming("ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01", None).is_none());
+
+        let middleware = trace_context();
+        let raw = format!("GET / HTTP/1.1\r\ntraceparent: {parent}\r\ntracestate: vendor=abc\r\n\r\n");
+        let mut request = Request::from_stream(&mut raw.as_bytes(), "127.0.0.1:1".parse().unwrap()).await.unwrap();
+        assert!(matches!(middleware(&mut request).await, Middleware::Next));
+        assert_eq!(request.trace_context().unwrap().parent_id, context.parent_id);
+
+        //a new trace is started without a valid traceparent.
+        let mut request = Request::from_stream(&mut &b"GET / HTTP/1.1\r\ntraceparent: junk\r\n\r\n"[..], "127.0.0.1:1".parse().unwrap()).await.unwrap();
+        middleware(&mut request).await;
+        let started = request.trace_context().unwrap();
+        assert!(started.parent_id.is_none() && started.state.is_none() && started.sampled());
+    }
+
+    #[tokio::test]
+    async fn test_test_client() {
+        use crate::web::{Middleware, middleware_fn};
+
+        let app = App::bind("127.0.0.1:0").await.unwrap();
+
+        let auth = middleware_fn(|req| Box::pin(async move {
+            match req.request_header("Authorization") {
+                Some(_) => Middleware::Next,
+                None => Middleware::InvalidEmpty(StatusCode::UNAUTHORIZED),
+            }
+        }));
+
+        app.add_or_panic("/tasks", Method::POST, crate::middleware!(auth), |req| async move {
+            let body = req.lock().await.body.clone().unwrap_or_default();
+            let task: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            JsonResolution::serialize(task["name"].clone()).unwrap().resolve()
+        })
+        .await;
+
+        //routes added after the client is created are seen by it.
+        let client = app.test_client();
+
+        app.add_or_panic("/client", Method::GET, None, |req| async move {
+            let ip = req.lock().await.client_socket.ip().to_string();
+            JsonResolution::serialize(ip).unwrap().resolve()
+        })
+        .await;
+
+        let response = client
+            .post("/tasks")
+            .header("Authorization", "Bearer token")
+            .json(&serde_json::json!({ "name": "write tests" }))
+            .unwrap()
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.header("content-type").unwrap().contains("json"));
+        assert_eq!(response.json::<String>().unwrap(), "write tests");
+
+        let response = client.post("/tasks").body("{}").send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        //without a missing route the app closes the connection, as it does for real clients.
+        assert!(client.get("/missing").send().await.is_err());
+
+        //other methods are sent by name.
+        let purge = Method::Other("PURGE".to_string());
+        app.add_or_panic("/cache", purge.clone(), None, |_| async move {
+            JsonResolution::serialize("purged").unwrap().resolve()
+        })
+        .await;
+
+        let response = client.request(purge, "/cache").send().await.unwrap();
+        assert_eq!(response.json::<String>().unwrap(), "purged");
+
+        let client = client.with_client_socket("10.1.2.3:5000".parse().unwrap());
+        let response = client.get("/client").send().await.unwrap();
+        assert_eq!(response.json::<String>().unwrap(), "10.1.2.3");
+    }
+
+    #[tokio::test]
+    async fn test_request_builder() {
+        use crate::web::{Middleware, Request, request_id::{RequestId, request_id}, routing::resolution_fn};
+        use futures::StreamExt;
+
+        let mut req = Request::builder()
+            .method(Method::POST)
+            .path("/tasks/7?notify=true")
+            .header("authorization", "Bearer one")
+            .header("Authorization", "Bearer two")
+            .variable("id", "7")
+            .extension(RequestId("given".to_string()))
+            .json(&serde_json::json!({ "name": "write tests" }))
+            .unwrap()
+            .build();
+
+        assert_eq!(req.method, Method::POST);
+        assert_eq!(req.route.cleaned_route, "/tasks/7");
+        assert_eq!(req.route.get_params().get("notify").map(String::as_str), Some("true"));
+        assert_eq!(req.request_header("AUTHORIZATION").map(String::as_str), Some("Bearer two"));
+        assert_eq!(req.request_header("Content-Length").map(String::as_str), Some("22"));
+        assert_eq!(req.request_id(), Some("given"));
+
+        //middleware and handlers are called directly with the request.
+        let middleware = request_id();
+        req.headers.insert("X-Request-Id".to_string(), "incoming".to_string());
+        assert!(matches!(middleware(&mut req).await, Middleware::Next));
+        assert_eq!(req.get_header("X-Request-Id"), Some("incoming"));
+
+        let handler = resolution_fn(|req: &mut Request| Box::pin(async move {
+            let body: serde_json::Value = serde_json::from_slice(req.body.as_deref().unwrap_or_default()).unwrap();
+            JsonResolution::serialize(format!("{} {}", req.variables["id"], body["name"])).unwrap().resolve()
+        }));
+
+        let resolution = handler(&mut req).await;
+        let body: String = serde_json::from_slice(&resolution.get_content().concat().await).unwrap();
+        assert_eq!(body, "7 \"write tests\"");
+
+        let req = Request::builder().build();
+        assert_eq!(req.method, Method::GET);
+        assert!(req.body.is_none() && req.keep_alive());
+    }
+
+    #[tokio::test]
+    async fn test_match_route() {
+        use crate::web::routing::router::route_tree::MatchKind;
+
+        let app = App::bind("127.0.0.1:0").await.unwrap();
+
+        for (route, method) in [("/users/{id}", Method::GET), ("/users/{id}", Method::DELETE), ("/files/{*}", Method::GET)] {
+            app.add_or_panic(route, method, None, |_| async move { JsonResolution::serialize(()).unwrap().resolve() })
+                .await;
+        }
+
+        let router = app.get_router().await;
+
+        let result = router.match_route("/users/7?full=true", &Method::POST).await;
+        assert_eq!(result.kind, MatchKind::Route);
+        assert_eq!(result.pattern.as_deref(), Some("/users/{id}"));
+        assert_eq!(result.variables.get("id").map(String::as_str), Some("7"));
+        assert_eq!(result.methods, vec![Method::DELETE, Method::GET]);
+        assert!(!result.handled);
+
+        let result = router.match_route("/files/a/b.txt", &Method::GET).await;
+        assert_eq!(result.kind, MatchKind::Wildcard);
+        assert_eq!(result.variables.get("*").map(String::as_str), Some("a/b.txt"));
+        assert!(result.handled);
+
+        let result = router.match_route("/nowhere", &Method::GET).await;
+        assert_eq!(result.kind, MatchKind::None);
+        assert!(result.pattern.is_none() && !result.handled);
+    }
+
+    #[tokio::test]
+    async fn test_error_sources() {
+        use crate::web::errors::{AppState, ConfigError, RoutingError};
+        use std::error::Error as _;
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+
+        let handler = |_| async move { JsonResolution::serialize(()).unwrap().resolve() };
+        app.add_route("/", Method::GET, None, handler).await.unwrap();
+
+        let added = app.add_route("/", Method::GET, None, handler).await;
+        assert!(matches!(added, Err(crate::Error::Routing(RoutingError::Exist))));
+
+        app.start().unwrap();
+        let started = app.start().unwrap_err();
+        assert!(matches!(started, crate::Error::State(AppState::Running)));
+        assert_eq!(started.source().unwrap().to_string(), "already running");
+
+        app.close().await.unwrap();
+        assert!(matches!(app.close().await, Err(crate::Error::State(AppState::Closed))));
+
+        //the chain reaches the error that caused it.
+        let error = crate::Error::from(ConfigError::from(std::io::Error::other("disk on fire")));
+        let config = error.source().unwrap();
+        assert!(config.is::<ConfigError>());
+        assert_eq!(config.source().unwrap().to_string(), "disk on fire");
+    }
+
+    #[tokio::test]
+    async fn test_handler_panic() {
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+
+        app.add_or_panic("/boom/{id}", Method::GET, None, |req| async move {
+            let id = req.lock().await.variables["id"].clone();
+
+            if !id.is_empty() {
+                panic!("handler {id} failed");
+            }
+
+            "unreachable"
+        })
+        .await;
+        app.add_or_panic("/ok", Method::GET, None, |_| async move { "ok" }).await;
+
+        //by default an empty 500 is sent, and the app keeps serving.
+        let client = app.test_client();
+        assert_eq!(client.get("/boom/1").send().await.unwrap().status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(client.get("/ok").send().await.unwrap().status(), StatusCode::OK);
+
+        app.set_on_panic(|message| JsonResolution::serialize(message).unwrap().resolve());
+        let client = app.test_client();
+
+        let response = client.get("/boom/2").send().await.unwrap();
+        assert_eq!(response.json::<String>().unwrap(), "handler 2 failed");
+    }
+
+    #[tokio::test]
+    async fn test_middleware_panic() {
+        use crate::web::middleware;
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+
+        let explode = middleware(|req| async move {
+            let path = req.lock().await.route.cleaned_route.clone();
+            panic!("middleware failed on {path}");
+        });
+
+        app.add_or_panic("/guarded", Method::GET, Some(vec![explode]), |_| async move { "unreachable" }).await;
+        app.add_or_panic("/ok", Method::GET, None, |_| async move { "ok" }).await;
+
+        //a panic in middleware is answered with an empty 500 before the handler, and the app keeps serving.
+        let client = app.test_client();
+        assert_eq!(client.get("/guarded").send().await.unwrap().status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(client.get("/ok").send().await.unwrap().text(), "ok");
+
+        //the hook is given the panic message of the middleware.
+        app.set_on_panic(|message| JsonResolution::serialize(message).unwrap().resolve());
+        let client = app.test_client();
+
+        let response = client.get("/guarded").send().await.unwrap();
+        assert_eq!(response.json::<String>().unwrap(), "middleware failed on /guarded");
+        assert_eq!(client.get("/ok").send().await.unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_error_pages() {
+        use crate::web::{
+            Middleware,
+            error_pages::{ErrorPages, template},
+            middleware_fn,
+        };
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+
+        let locked = middleware_fn(|_req| Box::pin(async move { Middleware::InvalidEmpty(StatusCode::UNAUTHORIZED) }));
+
+        app.add_or_panic("/items", Method::GET, None, |_| async move { "items" }).await;
+        app.add_or_panic("/locked", Method::GET, crate::middleware!(locked), |_| async move { "locked" }).await;
+        app.add_or_panic("/boom", Method::GET, None, |req| async move {
+            if req.lock().await.variables.is_empty() {
+                panic!("boom");
+            }
+
+            "unreachable"
+        })
+        .await;
+
+        //without pages the errors keep their empty body, a missing route closes the connection.
+        let client = app.test_client();
+        assert!(client.get("/missing").send().await.is_err());
+        assert!(client.get("/boom").send().await.unwrap().bytes().is_empty());
+
+        app.set_error_pages(
+            ErrorPages::new()
+                .status(404, template("<h1>{status} {reason}</h1>"))
+                .client_errors(template("client {status}"))
+                .server_errors(template("server {status}")),
+        );
+        let client = app.test_client();
+
+        let response = client.get("/missing").send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.header("Content-Type"), Some("text/html; charset=utf-8"));
+        assert_eq!(response.text(), "<h1>404 Not Found</h1>");
+
+        //a route without a handler for the method is a 405, the status falls back to the page of its class.
+        let response = client.delete("/items").send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.text(), "client 405");
+
+        let response = client.get("/locked").send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.text(), "client 401");
+
+        let response = client.get("/boom").send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(response.text(), "server 500");
+
+        //a request line that can not be parsed is answered with the 400 page.
+        let handle = app.start().unwrap();
+        let mut stream = tokio::net::TcpStream::connect(handle.local_addr().unwrap()).await.unwrap();
+        stream.write_all(b"GARBAGE\r\n\r\n").await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 400"));
+        assert!(response.ends_with("client 400"));
+
+        app.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_locale() {
+        use crate::web::{
+            Request,
+            locale::{locale, negotiate_language, parse_accept_language},
+        };
+
+        assert_eq!(parse_accept_language("fr;q=0.5, en-US, de;q=0, , x y, es;q=0.5"), vec!["en-US", "fr", "es"]);
+
+        let supported = ["en-GB", "fr", "de"];
+        assert_eq!(negotiate_language(&["DE"], &supported), Some("de"));
+        assert_eq!(negotiate_language(&["en"], &supported), Some("en-GB"));
+        assert_eq!(negotiate_language(&["fr-CA", "en"], &supported), Some("fr"));
+        assert_eq!(negotiate_language(&["ja", "*"], &supported), Some("en-GB"));
+        assert_eq!(negotiate_language(&["ja"], &supported), None);
+
+        let mut req = Request::builder().header("Accept-Language", "ja, de-AT;q=0.9, fr;q=0.8").build();
+        assert_eq!(req.accept_language(), vec!["ja", "de-AT", "fr"]);
+        assert_eq!(req.negotiate_language(&supported), Some("de"));
+
+        //the middleware stores the choice, or the default when nothing is accepted.
+        let middleware = locale(&supported, "en-GB");
+        middleware(&mut req).await;
+        assert_eq!(req.locale(), Some("de"));
+
+        let mut req = Request::builder().build();
+        assert_eq!(req.locale(), None);
+        middleware(&mut req).await;
+        assert_eq!(req.locale(), Some("en-GB"));
+    }
+
+    #[tokio::test]
+    async fn test_full_url() {
+        use crate::web::{Request, forwarded::TrustedProxies};
+
+        let proxies = TrustedProxies::parse(&["10.0.0.0/8"]).unwrap();
+        let proxy = "10.0.0.1:443".parse().unwrap();
+
+        let request = |peer, headers: &[(&str, &str)]| {
+            let mut builder = Request::builder().path("/tasks?page=2").client_socket(peer);
+
+            for (name, value) in headers {
+                builder = builder.header(name, value);
+            }
+
+            let mut request = builder.build();
+            request.extensions.insert(proxies.clone());
+            request
+        };
+
+        //the headers of untrusted peers are ignored.
+        let direct = request("4.4.4.4:1".parse().unwrap(), &[("Host", "app.local"), ("X-Forwarded-Proto", "https"), ("X-Forwarded-Host", "evil.test")]);
+        assert_eq!(direct.scheme(), "http");
+        assert_eq!(direct.full_url().as_deref(), Some("http://app.local/tasks?page=2"));
+
+        let forwarded = request(proxy, &[("Host", "10.0.0.5"), ("X-Forwarded-Proto", "https"), ("X-Forwarded-Host", "example.com")]);
+        assert_eq!(forwarded.scheme(), "https");
+        assert_eq!(forwarded.host().as_deref(), Some("example.com"));
+        assert_eq!(forwarded.full_url().as_deref(), Some("https://example.com/tasks?page=2"));
+
+        //the element of the client is used, over those added between trusted proxies.
+        let chained = request(proxy, &[("Forwarded", "for=1.1.1.1;proto=https;host=\"shop.example\", for=10.0.0.9;proto=http;host=internal")]);
+        assert_eq!(chained.full_url().as_deref(), Some("https://shop.example/tasks?page=2"));
+
+        let invalid = request(proxy, &[("Host", "app.local"), ("X-Forwarded-Proto", "gopher"), ("X-Forwarded-Host", "a b")]);
+        assert_eq!(invalid.full_url().as_deref(), Some("http://app.local/tasks?page=2"));
+
+        assert_eq!(Request::builder().secure(true).header("Host", "app.local").build().full_url().as_deref(), Some("https://app.local/"));
+        assert_eq!(Request::builder().build().full_url(), None);
+    }
+
+    #[tokio::test]
+    async fn test_url_for() {
+        use crate::web::{errors::RoutingError, resolution::redirect::Redirect};
+
+        let app = App::bind("127.0.0.1:0").await.unwrap();
+
+        app.add_route("/users/{id}", Method::GET, None, |_| async move { "user" })
+            .await
+            .unwrap()
+            .name("user_detail")
+            .unwrap();
+        app.add_route("/files/{*}", Method::GET, None, |_| async move { "file" })
+            .await
+            .unwrap()
+            .name("file")
+            .unwrap();
+        app.add_route("/me", Method::GET, None, |req| async move {
+            let location = req.lock().await.url_for("user_detail", &[("id", "7")]).unwrap();
+            Redirect::see_other(location)
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(app.url_for("user_detail", &[("id", "42")]).unwrap(), "/users/42");
+        assert_eq!(app.url_for("user_detail", &[("id", "a b"), ("tab", "posts & likes")]).unwrap(), "/users/a%20b?tab=posts%20%26%20likes");
+        assert_eq!(app.url_for("file", &[("*", "docs/read me.txt")]).unwrap(), "/files/docs/read%20me.txt");
+
+        assert!(matches!(app.url_for("user_detail", &[]), Err(crate::Error::Routing(RoutingError::MissingVariable(v))) if v == "id"));
+        assert!(matches!(app.url_for("missing", &[]), Err(crate::Error::Routing(RoutingError::UnknownName(_)))));
+        assert!(matches!(app.name_route("user_detail", "/people/{id}"), Err(crate::Error::Routing(RoutingError::NameTaken(_)))));
+
+        //handlers build paths from the same names.
+        let response = app.test_client().get("/me").send().await.unwrap();
+        assert_eq!(response.header("Location"), Some("/users/7"));
+    }
+
+    #[cfg(feature = "openapi")]
+    #[tokio::test]
+    async fn test_openapi() {
+        use crate::web::{
+            errors::RoutingError,
+            openapi::{OpenApi, RouteDoc},
+        };
+
+        #[derive(serde::Deserialize, schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct Task {
+            name: String,
+            done: bool,
+        }
+
+        let app = App::bind("127.0.0.1:0").await.unwrap();
+
+        app.add_or_panic("/tasks", Method::GET, None, |_| async move { "tasks" }).await;
+        app.add_or_panic("/tasks/{id}", Method::PUT, None, |_| async move { "task" }).await;
+        app.add_or_panic("/files/{*}", Method::GET, None, |_| async move { "file" }).await;
+
+        let doc = RouteDoc::new()
+            .summary("Replace a task")
+            .tag("tasks")
+            .request::<Task>()
+            .response::<Task>(200, "The replaced task")
+            .empty_response(404, "No task has the id");
+        app.document_route("/tasks/{id}", Method::PUT, doc).await.unwrap();
+
+        let missing = app.document_route("/tasks/{id}", Method::GET, RouteDoc::new()).await;
+        assert!(matches!(missing, Err(crate::Error::Routing(RoutingError::MethodMissing))));
+
+        let api = OpenApi::new("Tasks", "1.0.0").server("https://tasks.example.com");
+        app.serve_openapi("/docs", api.clone()).await.unwrap();
+
+        let document = app.openapi(&api).await;
+        assert_eq!(document["openapi"], "3.1.0");
+        assert_eq!(document["info"]["title"], "Tasks");
+        assert_eq!(document["servers"][0]["url"], "https://tasks.example.com");
+
+        //undocumented routes are listed with a 200 response, the docs themselves are not.
+        assert_eq!(document["paths"]["/tasks"]["get"]["responses"]["200"]["description"], "OK");
+        assert_eq!(document["paths"]["/files/{path}"]["get"]["parameters"][0]["name"], "path");
+        assert!(document["paths"].get("/docs").is_none());
+
+        let put = &document["paths"]["/tasks/{id}"]["put"];
+        assert_eq!(put["summary"], "Replace a task");
+        assert_eq!(put["parameters"][0]["name"], "id");
+        assert_eq!(put["requestBody"]["content"]["application/json"]["schema"]["$ref"], "#/components/schemas/Task");
+        assert_eq!(put["responses"]["404"]["description"], "No task has the id");
+        assert_eq!(document["components"]["schemas"]["Task"]["properties"]["done"]["type"], "boolean");
+
+        let client = app.test_client();
+        assert_eq!(client.get("/docs/openapi.json").send().await.unwrap().json::<serde_json::Value>().unwrap(), document);
+        assert!(client.get("/docs").send().await.unwrap().text().contains("\"/docs/openapi.json\""));
+    }
+
+    #[cfg(feature = "hyper")]
+    #[tokio::test]
+    async fn test_hyper_engine() {
+        use crate::web::engine::Engine;
+        use std::time::Duration;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        app.set_engine(Engine::Hyper);
+        app.set_keep_alive(Some(Duration::from_secs(5)));
+
+        app.add_or_panic("/hello/{name}", Method::GET, None, |req| async move {
+            format!("hello {}", req.lock().await.variables["name"])
+        })
+        .await;
+        app.add_or_panic("/echo", Method::POST, None, |req| async move {
+            String::from_utf8(req.lock().await.body.clone().unwrap_or_default()).unwrap()
+        })
+        .await;
+
+        let handle = app.start().unwrap();
+        let addr = handle.local_addr().unwrap();
+
+        //both requests are answered over the same connection.
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /hello/hyper HTTP/1.1\r\nHost: test\r\n\r\nPOST /echo HTTP/1.1\r\nHost: test\r\nContent-Length: 4\r\nConnection: close\r\n\r\nping")
+            .await
+            .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert_eq!(response.matches("HTTP/1.1 200 OK").count(), 2);
+        assert!(response.contains("hello hyper"));
+        assert!(response.ends_with("ping"));
+
+        //hyper rejects what it can not parse before the app sees it.
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream.write_all(b"GARBAGE\r\n\r\n").await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 400"));
+
+        app.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stream_disconnect() {
+        use futures::{Stream, StreamExt};
+        use std::{
+            pin::Pin,
+            sync::atomic::{AtomicBool, Ordering},
+            time::Duration,
+        };
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        //sets the flag once the body stream is dropped.
+        struct Dropped(Arc<AtomicBool>);
+
+        impl Drop for Dropped {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::Relaxed);
+            }
+        }
+
+        //sends one chunk, then never ends.
+        struct Endless(Arc<AtomicBool>);
+
+        impl Resolution for Endless {
+            fn get_headers(&self) -> HeaderMap {
+                let mut headers = HeaderMap::new();
+                headers.set_status(200);
+                headers
+            }
+
+            fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+                let dropped = Dropped(self.0.clone());
+
+                Box::pin(
+                    futures::stream::iter([b"first".to_vec()])
+                        .chain(futures::stream::pending())
+                        .map(move |chunk| {
+                            let _ = &dropped;
+                            chunk
+                        }),
+                )
+            }
+
+            fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+                Box::new(self)
+            }
+        }
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+        let (dropped, disconnected) = (Arc::new(AtomicBool::new(false)), Arc::new(AtomicBool::new(false)));
+        let (stream_dropped, seen) = (dropped.clone(), disconnected.clone());
+
+        app.add_or_panic("/events", Method::GET, None, move |req| {
+            let (stream_dropped, seen) = (stream_dropped.clone(), seen.clone());
+
+            async move {
+                let on_disconnect = req.lock().await.on_disconnect();
+                tokio::spawn(async move {
+                    on_disconnect.await;
+                    seen.store(true, Ordering::Relaxed);
+                });
+
+                Endless(stream_dropped).resolve()
+            }
+        })
+        .await;
+        app.start().unwrap();
+
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        client.write_all(b"GET /events HTTP/1.1\r\nHost: test\r\n\r\n").await.unwrap();
+
+        //the first chunk arrives, then the client leaves mid stream.
+        let mut response = Vec::new();
+        while !String::from_utf8_lossy(&response).contains("first") {
+            let mut buffer = [0u8; 1024];
+            let read = client.read(&mut buffer).await.unwrap();
+            assert!(read > 0);
+            response.extend_from_slice(&buffer[..read]);
+        }
+        drop(client);
+
+        //the body stream is dropped and handlers selecting on the disconnect are told.
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !dropped.load(Ordering::Relaxed) || !disconnected.load(Ordering::Relaxed) {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_date_and_server_headers() {
+        use crate::web::http_date::{self, http_date};
+        use std::time::{Duration, UNIX_EPOCH};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        assert_eq!(http_date(UNIX_EPOCH + Duration::from_secs(784_111_777)), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(http_date(UNIX_EPOCH), "Thu, 01 Jan 1970 00:00:00 GMT");
+        assert_eq!(http_date::now().len(), 29);
+
+        let request = |server: Option<&'static str>| async move {
+            let mut app = App::bind("127.0.0.1:0").await.unwrap();
+            let addr = app.local_addr().unwrap();
+            app.set_server_header(server);
+            app.add_or_panic("/", Method::GET, None, |_req| async move { "hello" }).await;
+            app.start().unwrap();
+
+            let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+            client.write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n").await.unwrap();
+            let mut response = String::new();
+            client.read_to_string(&mut response).await.unwrap();
+            app.close().await.unwrap();
+            response
+        };
+
+        //every response is dated, and identifies the server unless it is turned off.
+        let response = request(Some("async-web")).await;
+        assert!(response.contains("\r\nDate:") && response.contains(" GMT\r\n"));
+        assert!(response.contains("\r\nServer:async-web\r\n"));
+
+        let response = request(None).await;
+        assert!(response.contains("\r\nDate:"));
+        assert!(!response.contains("Server:"));
+    }
+
+    #[tokio::test]
+    async fn test_request_target_validation() {
+        use crate::web::routing::{
+            Request,
+            request::{BodyLimits, HeadLimits},
+            route::normalize_path,
+        };
+        use std::net::SocketAddr;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        assert_eq!(normalize_path("/a/./b/../c"), "/a/c");
+        assert_eq!(normalize_path("/../../etc/passwd"), "/etc/passwd");
+        assert_eq!(normalize_path("/static/%2e%2E/secret"), "/secret");
+        assert_eq!(normalize_path("/a/b/.."), "/a/");
+
+        let client: SocketAddr = "127.0.0.1:1".parse().unwrap();
+        let parse = |raw: &'static str, head: HeadLimits| async move {
+            Request::from_reader(&mut raw.as_bytes(), client, BodyLimits::default(), head).await
+        };
+
+        //dot segments are removed before routing, the query is kept.
+        let request = parse("GET /files/../admin/./users?page=2 HTTP/1.1\r\n\r\n", HeadLimits::default()).await.unwrap();
+        assert_eq!(request.route.init_route, "/admin/users?page=2");
+        assert_eq!(request.route.cleaned_route, "/admin/users");
+
+        //absolute-form and CONNECT are rejected unless allowed.
+        let absolute = "GET http://example.com/tasks HTTP/1.1\r\nHost: other\r\n\r\n";
+        assert!(parse(absolute, HeadLimits::default()).await.is_err());
+        assert!(parse("CONNECT example.com:443 HTTP/1.1\r\n\r\n", HeadLimits::default()).await.is_err());
+
+        let allowed = HeadLimits { allow_absolute_form: true, allow_connect: true, ..HeadLimits::default() };
+        let request = parse(absolute, allowed).await.unwrap();
+        assert_eq!(request.route.cleaned_route, "/tasks");
+        assert_eq!(request.host().as_deref(), Some("example.com"));
+        assert!(parse("CONNECT example.com:443 HTTP/1.1\r\n\r\n", allowed).await.is_ok());
+
+        //control characters and too many segments are rejected, * only for OPTIONS.
+        assert!(parse("GET /a\x01b HTTP/1.1\r\n\r\n", HeadLimits::default()).await.is_err());
+        assert!(parse("GET /a/b/c HTTP/1.1\r\n\r\n", HeadLimits { max_segments: 2, ..HeadLimits::default() }).await.is_err());
+        assert!(parse("OPTIONS * HTTP/1.1\r\n\r\n", HeadLimits::default()).await.is_ok());
+        assert!(parse("GET * HTTP/1.1\r\n\r\n", HeadLimits::default()).await.is_err());
+
+        //the app answers an invalid target with 400.
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+        app.start().unwrap();
+
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream.write_all(b"GET http://example.com/ HTTP/1.1\r\nHost: test\r\n\r\n").await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+
+        app.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_query() {
+        use crate::web::{
+            Request,
+            extract::{IntoHandler, Query},
+        };
+
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        #[serde(rename_all = "lowercase")]
+        enum Sort {
+            Asc,
+            Desc,
+        }
+
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        #[serde(default)]
+        struct Pagination {
+            page: u32,
+            per_page: u32,
+            sort: Sort,
+        }
+
+        impl Default for Pagination {
+            fn default() -> Self {
+                Self { page: 1, per_page: 20, sort: Sort::Asc }
+            }
+        }
+
+        let parse = async |raw: &'static str| {
+            Request::from_stream(&mut raw.as_bytes(), "127.0.0.1:1".parse().unwrap()).await.unwrap()
+        };
+
+        let request = parse("GET /tasks?page=2&per_page=50&sort=desc HTTP/1.1\r\n\r\n").await;
+        assert_eq!(request.query::<Pagination>().unwrap(), Pagination { page: 2, per_page: 50, sort: Sort::Desc });
+
+        //missing fields take their defaults, values are decoded.
+        let request = parse("GET /tasks HTTP/1.1\r\n\r\n").await;
+        assert_eq!(request.query::<Pagination>().unwrap(), Pagination::default());
+
+        let request = parse("GET /search?q=hello+world%21 HTTP/1.1\r\n\r\n").await;
+        assert_eq!(request.route.query::<std::collections::HashMap<String, String>>().unwrap()["q"], "hello world!");
+
+        //a query that does not fit is answered with 400.
+        let handler = (|Query(pagination): Query<Pagination>| async move {
+            EmptyResolution::status(if pagination.page == 2 { 200 } else { 500 }).resolve()
+        })
+        .into_handler();
+
+        let status = async |raw: &'static str| {
+            let mut request = parse(raw).await;
+            handler(&mut request).await.get_headers().status().map(str::to_string)
+        };
+
+        assert_eq!(status("GET /tasks?page=2 HTTP/1.1\r\n\r\n").await.as_deref(), Some("200 OK"));
+        assert_eq!(status("GET /tasks?page=two HTTP/1.1\r\n\r\n").await.as_deref(), Some("400 Bad Request"));
+        assert_eq!(status("GET /tasks?sort=sideways HTTP/1.1\r\n\r\n").await.as_deref(), Some("400 Bad Request"));
+    }
+
+    #[test]
+    fn test_request_text() {
+        use crate::web::{Request, StatusCode, media_type::MediaType};
+
+        let media_type = MediaType::parse("Text/HTML; Charset=\"UTF-8\"").unwrap();
+        assert_eq!(media_type.essence(), "text/html");
+        assert!(media_type.is("text/html"));
+        assert_eq!(media_type.charset(), Some("UTF-8"));
+        assert!(MediaType::parse("not a type").is_none());
+
+        let request = |content_type: Option<&str>, body: &[u8]| {
+            let mut builder = Request::builder().body(body.to_vec());
+
+            if let Some(content_type) = content_type {
+                builder = builder.header("Content-Type", content_type);
+            }
+
+            builder.build()
+        };
+
+        //the charset of the content type is used, UTF-8 by default.
+        assert_eq!(request(Some("text/plain; charset=utf-8"), "héllo".as_bytes()).text().unwrap(), "héllo");
+        assert_eq!(request(Some("text/plain; charset=ISO-8859-1"), b"h\xe9llo").text().unwrap(), "héllo");
+        assert_eq!(request(None, "héllo".as_bytes()).text().unwrap(), "héllo");
+        assert_eq!(request(Some("text/plain; charset=utf-8"), b"").content_type().unwrap().essence(), "text/plain");
+
+        //without a charset, a body that is not UTF-8 falls back to latin-1.
+        assert_eq!(request(Some("text/plain"), b"h\xe9llo").text().unwrap(), "héllo");
+
+        //invalid text and unsupported charsets are errors.
+        let invalid = request(Some("text/plain; charset=utf-8"), b"h\xe9llo").text().unwrap_err();
+        assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+
+        let unsupported = request(Some("text/plain; charset=shift_jis"), b"hello").text().unwrap_err();
+        assert_eq!(unsupported.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[tokio::test]
+    async fn test_route_guards() {
+        use crate::web::guards::RouteGuards;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+
+        app.add_or_panic("/upload", Method::POST, None, |_req| async move { "stored" }).await;
+        app.add_or_panic("/tasks", Method::PUT, None, |_req| async move { "updated" }).await;
+
+        app.guard_route("/upload", Method::POST, RouteGuards::new().max_body(8).content_types(&["image/jpeg", "image/png"]))
+            .await
+            .unwrap();
+        app.guard_route("/tasks", Method::PUT, RouteGuards::new().require_header("If-Match")).await.unwrap();
+        assert!(app.guard_route("/missing", Method::GET, RouteGuards::new()).await.is_err());
+
+        //guarding a route keeps its other settings and is recorded as a replaced route.
+        app.add_or_panic("/slow", Method::POST, None, |_req| async move {
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            "late"
+        })
+        .await;
+        app.timeout_route("/slow", Method::POST, std::time::Duration::from_millis(50), 504).await.unwrap();
+        app.guard_route("/slow", Method::POST, RouteGuards::new().max_body(8)).await.unwrap();
+
+        let changes = app.route_changes().changes().await;
+        let replaced: Vec<_> = changes.iter().flat_map(|c| c.replaced.clone()).collect();
+        assert_eq!(replaced, ["POST /upload", "PUT /tasks", "POST /slow", "POST /slow"]);
+
+        app.start().unwrap();
+
+        let request = |head: &'static str, body: &'static str| async move {
+            let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+            let raw = format!("{head}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}", body.len());
+            client.write_all(raw.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            client.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        //requests failing a guard are answered before the handler.
+        let png = request("POST /upload HTTP/1.1\r\nContent-Type: image/png", "png").await;
+        assert!(png.starts_with("HTTP/1.1 200 OK") && png.ends_with("stored"));
+
+        let large = request("POST /upload HTTP/1.1\r\nContent-Type: image/png", "too large to upload").await;
+        assert!(large.starts_with("HTTP/1.1 413"));
+
+        let gif = request("POST /upload HTTP/1.1\r\nContent-Type: image/gif", "gif").await;
+        assert!(gif.starts_with("HTTP/1.1 415"));
+
+        let unconditional = request("PUT /tasks HTTP/1.1", "").await;
+        assert!(unconditional.starts_with("HTTP/1.1 428"));
+
+        let conditional = request("PUT /tasks HTTP/1.1\r\nIf-Match: \"v1\"", "").await;
+        assert!(conditional.ends_with("updated"));
+
+        assert!(request("POST /slow HTTP/1.1", "too large to wait for").await.starts_with("HTTP/1.1 413"));
+        assert!(request("POST /slow HTTP/1.1", "small").await.starts_with("HTTP/1.1 504"));
+
+        app.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_body_framing() {
+        use crate::web::{
+            Request,
+            routing::request::{BodyLimits, HeadLimits},
+        };
+        use futures::Stream;
+        use std::{pin::Pin, time::Duration};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let parse = async |raw: &'static str| {
+            Request::from_reader(&mut raw.as_bytes(), "127.0.0.1:1".parse().unwrap(), BodyLimits::default(), HeadLimits::default()).await
+        };
+
+        //chunked bodies are decoded, extensions and trailers are dropped.
+        let request = parse("POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5;ext=1\r\nhello\r\n6\r\n world\r\n0\r\nX-Trailer: 1\r\n\r\n").await.unwrap();
+        assert_eq!(request.body.as_deref(), Some(&b"hello world"[..]));
+        assert_eq!(request.request_header("Content-Length").map(String::as_str), Some("11"));
+
+        //ambiguous framing is rejected instead of guessed.
+        assert!(parse("POST / HTTP/1.1\r\nContent-Length: 1\r\nContent-Length: 2\r\n\r\nab").await.is_err());
+        assert!(parse("POST / HTTP/1.1\r\nContent-Length: +2\r\n\r\nab").await.is_err());
+        assert!(parse("POST / HTTP/1.1\r\nContent-Length: 2\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n").await.is_err());
+        assert!(parse("POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello world\r\n0\r\n\r\n").await.is_err());
+        assert!(parse("POST / HTTP/1.1\r\nContent-Length: 2\r\nContent-Length: 2\r\n\r\nab").await.is_ok());
+
+        //header lines each server would read differently are rejected, RFC 9112 5.1.
+        for raw in [
+            "POST / HTTP/1.1\r\nContent-Length : 2\r\n\r\nab",
+            "POST / HTTP/1.1\r\n Content-Length: 2\r\n\r\nab",
+            "POST / HTTP/1.1\r\nContent-Length 2\r\n\r\nab",
+            "POST / HTTP/1.1\r\n: 2\r\n\r\nab",
+            "GET / HTTP/1.1\r\nHost: a.example\r\nhost: b.example\r\n\r\n",
+        ] {
+            let error = parse(raw).await.err().unwrap_or_else(|| panic!("{raw:?} was parsed"));
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidData, "{raw:?}");
+        }
+
+        //a body sent too slowly times out like the head.
+        let head = HeadLimits { line_timeout: Duration::from_millis(50), ..HeadLimits::default() };
+
+        for raw in ["POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\nab", "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nab"] {
+            let (mut client, server) = tokio::io::duplex(1024);
+            client.write_all(raw.as_bytes()).await.unwrap();
+
+            let mut reader = tokio::io::BufReader::new(server);
+            let parsed = Request::from_reader(&mut reader, "127.0.0.1:1".parse().unwrap(), BodyLimits::default(), head).await;
+            assert_eq!(parsed.err().map(|e| e.kind()), Some(std::io::ErrorKind::TimedOut), "{raw:?}");
+        }
+
+        //sends less content than its length.
+        struct Short;
+
+        impl Resolution for Short {
+            fn get_headers(&self) -> HeaderMap {
+                let mut headers = HeaderMap::new();
+                headers.set_status(200);
+                headers
+            }
+
+            fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+                Box::pin(futures::stream::iter([b"short".to_vec()]))
+            }
+
+            fn content_length(&self) -> Option<u64> {
+                Some(10)
+            }
+
+            fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+                Box::new(self)
+            }
+        }
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+        app.set_keep_alive(Some(Duration::from_secs(5)));
+
+        app.add_or_panic("/echo", Method::POST, None, |req| async move {
+            String::from_utf8(req.lock().await.body.clone().unwrap_or_default()).unwrap()
+        })
+        .await;
+        app.add_or_panic("/page", Method::Other("HEAD".to_string()), None, |_req| async move { "a body" }).await;
+        app.add_or_panic("/short", Method::GET, None, |_req| async move { Short.resolve() }).await;
+        app.start().unwrap();
+
+        let exchange = |raw: &'static str| async move {
+            let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+            client.write_all(raw.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            let _ = tokio::time::timeout(Duration::from_secs(5), client.read_to_string(&mut response)).await.unwrap();
+            response
+        };
+
+        //the chunked body is read before the next request on the connection.
+        let response = exchange("POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nping\r\n0\r\n\r\nPOST /echo HTTP/1.1\r\nContent-Length: 4\r\nConnection: close\r\n\r\npong").await;
+        assert_eq!(response.matches("HTTP/1.1 200 OK").count(), 2);
+        assert!(response.contains("ping") && response.ends_with("pong"));
+
+        //a body behind a length with whitespace before the colon is never read as the next request.
+        let response = exchange("POST /echo HTTP/1.1\r\nContent-Length : 40\r\n\r\nGET /page HTTP/1.1\r\nConnection: close\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 400"));
+        assert_eq!(response.matches("HTTP/1.1").count(), 1);
+
+        //other transfer codings are not implemented, the connection is closed.
+        let response = exchange("POST /echo HTTP/1.1\r\nTransfer-Encoding: gzip, chunked\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 501"));
+
+        //a HEAD response has the headers without the body.
+        let response = exchange("HEAD /page HTTP/1.1\r\nConnection: close\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK") && response.ends_with("\r\n\r\n"));
+        assert!(!response.contains("a body"));
+
+        //content shorter than its length closes the kept alive connection.
+        let response = exchange("GET /short HTTP/1.1\r\n\r\n").await;
+        assert!(response.contains("Content-Length:10") && response.ends_with("short"));
+
+        app.close().await.unwrap();
+    }
+
+    #[cfg(feature = "image")]
+    #[tokio::test]
+    async fn test_image_cache() {
+        use crate::web::resolution::image_resolution::{ImageOptions, ImageResolution};
+
+        let root = std::env::temp_dir().join(format!("async-web-image-{}", std::process::id()));
+        let cache = root.join("cache");
+        std::fs::create_dir_all(&root).unwrap();
+
+        let source = root.join("cat.png");
+        image::RgbImage::new(8, 8).save(&source).unwrap();
+
+        let options = ImageOptions {
+            width: Some(4),
+            ..ImageOptions::default()
+        };
+
+        for _ in 0..2 {
+            let resolution = ImageResolution::transform(source.to_str().unwrap(), options.clone(), cache.to_str()).await;
+            assert_eq!(resolution.get_headers().status(), Some("200 OK"));
+        }
+
+        let names: Vec<String> = std::fs::read_dir(&cache)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+
+        //one cached image keyed by a sha256, and no temp files left behind.
+        assert_eq!(names.len(), 1);
+        let (key, ext) = names[0].split_once('.').unwrap();
+        assert_eq!(ext, "png");
+        assert_eq!(key.len(), 64);
+        assert!(key.chars().all(|c| c.is_ascii_hexdigit()));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn test_certificate_validity() {
+        use crate::web::acme::Validity;
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let certificate = |not_after: (i32, u8, u8)| {
+            let key = rcgen::KeyPair::generate().unwrap();
+            let mut params = rcgen::CertificateParams::new(vec!["localhost".to_string()]).unwrap();
+            params.not_before = rcgen::date_time_ymd(2025, 1, 1);
+            params.not_after = rcgen::date_time_ymd(not_after.0, not_after.1, not_after.2);
+            params.self_signed(&key).unwrap().pem()
+        };
+
+        let at = |seconds: u64| UNIX_EPOCH + Duration::from_secs(seconds);
+
+        //a 90 day certificate is renewed with 30 days left.
+        let validity = Validity::from_pem(certificate((2025, 4, 1)).as_bytes()).unwrap();
+        assert_eq!(validity.not_before, at(1_735_689_600));
+        assert_eq!(validity.not_after, at(1_743_465_600));
+        assert_eq!(validity.renew_at(), at(1_743_465_600 - 30 * 86_400));
+
+        //dates from 2050 are a GeneralizedTime.
+        let long = Validity::from_pem(certificate((2051, 1, 1)).as_bytes()).unwrap();
+        assert_eq!(long.not_after, at(2_556_144_000));
+
+        assert!(Validity::from_pem(b"not a certificate").is_none());
+        assert!(Validity::from_der(&[0x30, 0x82, 0xff]).is_none());
+    }
+
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn test_acme_challenge_app() {
+        use crate::web::acme::{AcmeChallenges, challenge_app};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let challenges = AcmeChallenges::new();
+        challenges.insert("token", "token.thumbprint").await;
+
+        let mut http = challenge_app("127.0.0.1:0", challenges.clone()).await.unwrap();
+        let addr = http.local_addr().unwrap();
+        http.start().unwrap();
+
+        let get = |path: &'static str| async move {
+            let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+            let raw = format!("GET {path} HTTP/1.1\r\nConnection: close\r\n\r\n");
+            client.write_all(raw.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            client.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        //served over plain HTTP, as the ACME server validates them.
+        let found = get("/.well-known/acme-challenge/token").await;
+        assert!(found.starts_with("HTTP/1.1 200 OK") && found.ends_with("token.thumbprint"));
+
+        challenges.remove("token").await;
+        let removed = get("/.well-known/acme-challenge/token").await;
+        assert!(removed.starts_with("HTTP/1.1 404"));
+    }
+
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn test_tls_handshake_timeout() {
+        use crate::web::tls::CertificateStore;
+        use std::time::Duration;
+        use tokio::io::AsyncReadExt;
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+
+        app.use_tls(Arc::new(CertificateStore::new()));
+        app.set_tls_handshake_timeout(Duration::from_millis(100));
+        app.start().unwrap();
+
+        //a client that never sends a hello is closed once the handshake times out.
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let mut buf = [0u8; 16];
+        let read = tokio::time::timeout(Duration::from_secs(5), client.read(&mut buf)).await;
+
+        assert!(matches!(read, Ok(Ok(0)) | Ok(Err(_))));
+    }
+
+    #[tokio::test]
+    async fn test_watchdog() {
+        use crate::{
+            factory::{QueuePolicy, WorkManager, WorkerHealth},
+            web::watchdog::{self, WatchdogConfig},
+        };
+        use std::time::Duration;
+
+        let work_manager = Arc::new(Mutex::new(WorkManager::<()>::new(1).await));
+        work_manager.lock().await.set_queue_capacity(Some(100), QueuePolicy::Reject).await;
+        let health = Arc::new(Mutex::new(WorkerHealth::default()));
+
+        let config = WatchdogConfig {
+            check_every: Duration::from_millis(10),
+            stall_after: Duration::from_millis(30),
+            max_overflow_workers: 1,
+            queue_growth_checks: 0,
+            ..Default::default()
+        };
+        let task = watchdog::spawn(work_manager.clone(), config, health.clone());
+
+        //a stalled worker gets an overflow worker while it is stuck.
+        let _ = work_manager.lock().await.queue_work(Box::pin(tokio::time::sleep(Duration::from_millis(250)))).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(work_manager.lock().await.size(), 2);
+        assert_eq!(health.lock().await.overflow_workers, 1);
+
+        //and the overflow worker is retired once the worker is free.
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(work_manager.lock().await.size(), 1);
+        assert_eq!(health.lock().await.overflow_workers, 0);
+        task.abort();
+
+        //a queue that only grows is wedged, even before any worker is stalled.
+        let work_manager = Arc::new(Mutex::new(WorkManager::<()>::new(1).await));
+        work_manager.lock().await.set_queue_capacity(Some(100), QueuePolicy::Reject).await;
+        let health = Arc::new(Mutex::new(WorkerHealth::default()));
+
+        let config = WatchdogConfig {
+            check_every: Duration::from_millis(20),
+            stall_after: Duration::from_secs(60),
+            queue_growth_checks: 2,
+            ..Default::default()
+        };
+        let task = watchdog::spawn(work_manager.clone(), config, health.clone());
+
+        for _ in 0..20 {
+            let _ = work_manager.lock().await.queue_work(Box::pin(tokio::time::sleep(Duration::from_millis(500)))).await;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let snapshot = health.lock().await.clone();
+        assert!(snapshot.wedged && snapshot.stalled == 0, "{snapshot:?}");
+        task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_readiness_while_wedged() {
+        use crate::web::watchdog::WatchdogConfig;
+        use std::time::Duration;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+
+        app.set_watchdog(WatchdogConfig {
+            check_every: Duration::from_millis(10),
+            stall_after: Duration::from_millis(30),
+            ..Default::default()
+        });
+        app.serve_readiness("/ready").await.unwrap();
+        app.add_or_panic("/slow", Method::GET, None, |_req| async move {
+            tokio::time::sleep(Duration::from_millis(800)).await;
+            "done"
+        })
+        .await;
+        app.start().unwrap();
+
+        let ready = || async move {
+            let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+            client.write_all(b"GET /ready HTTP/1.1\r\nConnection: close\r\n\r\n").await.unwrap();
+            let mut response = String::new();
+            tokio::time::timeout(Duration::from_millis(500), client.read_to_string(&mut response))
+                .await
+                .expect("readiness was not answered")
+                .unwrap();
+            response
+        };
+
+        assert!(ready().await.starts_with("HTTP/1.1 200 OK"));
+
+        //the only worker is stuck, readiness is still answered.
+        let mut slow = tokio::net::TcpStream::connect(addr).await.unwrap();
+        slow.write_all(b"GET /slow HTTP/1.1\r\nConnection: close\r\n\r\n").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(150)).await;
+
+        let wedged = ready().await;
+        assert!(wedged.starts_with("HTTP/1.1 503"), "{wedged}");
+        assert!(wedged.contains("\"wedged\":true"));
+
+        //silent clients do not hold the accept loop, and a request line sent in parts is still recognized.
+        let mut silent = Vec::new();
+        for _ in 0..5 {
+            silent.push(tokio::net::TcpStream::connect(addr).await.unwrap());
+        }
+
+        let started = std::time::Instant::now();
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        client.write_all(b"GET /rea").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        client.write_all(b"dy HTTP/1.1\r\nConnection: close\r\n\r\n").await.unwrap();
+
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 503"), "{response}");
+        assert!(started.elapsed() < Duration::from_millis(90), "{:?}", started.elapsed());
+    }
+
+    #[tokio::test]
+    async fn test_audit_log() {
+        use crate::web::audit::{AuditConfig, AuditEvent, AuditKind, AuditLog, AuditSink};
+
+        let client = "10.0.0.1".parse().unwrap();
+
+        //a burst of 4xx responses is recorded once.
+        let config = AuditConfig {
+            burst_threshold: 3,
+            ..AuditConfig::default()
+        };
+        let audit = AuditLog::new(AuditSink::Memory, config);
+        for _ in 0..4 {
+            audit.client_error(client, "/login", "401 Unauthorized").await;
+        }
+        let events = audit.events().await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, AuditKind::ClientErrorBurst);
+
+        //a log file removed from under the log is recreated instead of failing every later write.
+        let dir = std::env::temp_dir().join(format!("async-web-audit-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("audit.log");
+        let event = || AuditEvent::new(AuditKind::AuthFailure, client, "/login", "bearer");
+        let line = serde_json::to_vec(&event()).unwrap().len() as u64 + 1;
+
+        let audit = AuditLog::new(
+            AuditSink::File {
+                path: path.clone(),
+                max_bytes: line * 2 + 1,
+                max_files: 2,
+            },
+            AuditConfig::default(),
+        );
+
+        audit.record(event()).await;
+        audit.record(event()).await;
+        std::fs::remove_file(&path).unwrap();
+
+        audit.record(event()).await;
+        audit.record(event()).await;
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+
+        //a full file is rotated.
+        audit.record(event()).await;
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 1);
+        assert_eq!(std::fs::read_to_string(dir.join("audit.log.1")).unwrap().lines().count(), 2);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cache_ttl() {
+        use crate::web::cache::{Cache, MemoryStore};
+        use std::time::Duration;
+
+        let cache = Cache::new(MemoryStore::new(2));
+
+        //a ttl past what an instant can hold never expires instead of panicking.
+        cache.insert("forever", &1, Duration::MAX).await;
+        cache.insert("short", &2, Duration::from_millis(20)).await;
+        assert_eq!(cache.get::<i32>("forever").await, Some(1));
+        assert_eq!(cache.get::<i32>("short").await, Some(2));
+
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert_eq!(cache.get::<i32>("short").await, None);
+
+        //the entry expiring soonest is evicted first, never the one that does not expire.
+        cache.insert("a", &3, Duration::from_secs(60)).await;
+        cache.insert("b", &4, Duration::from_secs(120)).await;
+        assert_eq!(cache.get::<i32>("forever").await, Some(1));
+        assert_eq!(cache.get::<i32>("a").await, None);
+        assert_eq!(cache.get::<i32>("b").await, Some(4));
+    }
+
+    #[tokio::test]
+    async fn test_route_timeout() {
+        use crate::web::{Middleware, middleware, timeout};
+        use std::time::Duration;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+
+        let slow_middleware = middleware(|_req| async move {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Middleware::Next
+        });
+
+        app.add_or_panic("/handler", Method::GET, None, timeout(Duration::from_millis(50), |_req| async move {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            EmptyResolution::status(200).resolve()
+        }))
+        .await;
+        app.add_or_panic("/middleware", Method::GET, Some(vec![slow_middleware]), |_req| async move { "late" }).await;
+        app.add_or_panic("/fast", Method::GET, None, |_req| async move { "fast" }).await;
+
+        app.timeout_route("/middleware", Method::GET, Duration::from_millis(50), 503).await.unwrap();
+        app.timeout_route("/fast", Method::GET, Duration::from_secs(5), 504).await.unwrap();
+        assert!(app.timeout_route("/missing", Method::GET, Duration::from_secs(1), 504).await.is_err());
+
+        //each timeout is recorded as a replaced route.
+        let changes = app.route_changes().changes().await;
+        let replaced: Vec<_> = changes.iter().flat_map(|c| c.replaced.clone()).collect();
+        assert_eq!(replaced, ["GET /middleware", "GET /fast"]);
+
+        app.start().unwrap();
+
+        let request = |path: &'static str| async move {
+            let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+            let raw = format!("GET {path} HTTP/1.1\r\nConnection: close\r\n\r\n");
+            client.write_all(raw.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            client.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        //a slow handler is cut off by the wrapper, slow middleware only by the route timeout.
+        let started = std::time::Instant::now();
+        assert!(request("/handler").await.starts_with("HTTP/1.1 504"));
+        assert!(request("/middleware").await.starts_with("HTTP/1.1 503"));
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        let fast = request("/fast").await;
+        assert!(fast.starts_with("HTTP/1.1 200 OK") && fast.ends_with("fast"));
+
+        app.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_request_id() {
+        use crate::web::{Middleware, middleware, request_id::request_id};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+
+        app.use_middleware(request_id()).await;
+
+        let deny = middleware(|_req| async move { Middleware::InvalidEmpty(403.into()) });
+
+        app.add_or_panic("/id", Method::GET, None, |req| async move {
+            req.lock().await.request_id().unwrap_or_default().to_string()
+        })
+        .await;
+        app.add_or_panic("/denied", Method::GET, Some(vec![deny]), |_req| async move { "unreachable" }).await;
+
+        app.start().unwrap();
+
+        let request = |head: String| async move {
+            let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+            client.write_all(format!("{head}\r\nConnection: close\r\n\r\n").as_bytes()).await.unwrap();
+            let mut response = String::new();
+            client.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        //the id echoed in the response header, found case insensitively.
+        let echoed = |response: &str| {
+            response
+                .lines()
+                .find_map(|line| line.split_once(':').filter(|(name, _)| name.eq_ignore_ascii_case("X-Request-Id")))
+                .map(|(_, id)| id.trim().to_string())
+        };
+
+        //an incoming id reaches the handler and is echoed back.
+        let given = request("GET /id HTTP/1.1\r\nX-Request-Id: trace-42".to_string()).await;
+        assert_eq!(echoed(&given).as_deref(), Some("trace-42"));
+        assert!(given.ends_with("trace-42"));
+
+        //an invalid id is replaced with a generated one, unique per request.
+        let long = format!("GET /id HTTP/1.1\r\nX-Request-Id: {}", "a".repeat(200));
+        let first = echoed(&request(long).await).unwrap();
+        let second = echoed(&request("GET /id HTTP/1.1".to_string()).await).unwrap();
+        assert!(uuid::Uuid::parse_str(&first).is_ok() && uuid::Uuid::parse_str(&second).is_ok());
+        assert_ne!(first, second);
+
+        //responses rejected by later middleware still carry the id.
+        let denied = request("GET /denied HTTP/1.1\r\nX-Request-Id: rejected-1".to_string()).await;
+        assert!(denied.starts_with("HTTP/1.1 403"));
+        assert_eq!(echoed(&denied).as_deref(), Some("rejected-1"));
+
+        app.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_security_headers() {
+        use crate::web::{
+            Middleware, Request,
+            security::{SecurityHeaders, security_headers},
+        };
+        use std::collections::HashSet;
+
+        let middleware = security_headers(SecurityHeaders::default());
+        let mut nonces = HashSet::new();
+
+        //each request is given a fresh nonce, reflected in every slot of its policy.
+        for _ in 0..32 {
+            let mut req = Request::builder().build();
+            assert!(matches!(middleware(&mut req).await, Middleware::Next));
+
+            let nonce = req.csp_nonce().unwrap().to_string();
+            let policy = req.get_header("Content-Security-Policy").unwrap();
+
+            assert_eq!(policy.matches(&format!("'nonce-{nonce}'")).count(), 2);
+            assert!(!policy.contains("{nonce}"));
+            assert!(nonces.insert(nonce));
+        }
+
+        let mut req = Request::builder().build();
+        middleware(&mut req).await;
+        assert_eq!(req.get_header("X-Content-Type-Options"), Some("nosniff"));
+        assert_eq!(req.get_header("X-Frame-Options"), Some("DENY"));
+        assert_eq!(req.get_header("Strict-Transport-Security"), None);
+
+        let middleware = security_headers(SecurityHeaders::default().content_security_policy("script-src 'nonce-{nonce}'").hsts(600));
+        let mut req = Request::builder().build();
+        middleware(&mut req).await;
+
+        let expected = format!("script-src 'nonce-{}'", req.csp_nonce().unwrap());
+        assert_eq!(req.get_header("Content-Security-Policy"), Some(expected.as_str()));
+        assert_eq!(req.get_header("Strict-Transport-Security"), Some("max-age=600; includeSubDomains"));
+    }
+
+    #[tokio::test]
+    async fn test_debug_tap() {
+        use crate::web::{
+            debug_tap::{DebugTap, TapSink},
+            routing::Scope,
+        };
+        use std::time::Duration;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let sink = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let sink_addr = sink.local_addr().unwrap();
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+
+        app.set_debug_tap(DebugTap::new(TapSink::Socket(sink_addr)).scope(Scope::new().include("/hooks/{*}")).max_bytes(512));
+        app.add_or_panic("/hooks/{name}", Method::POST, None, |_req| async move { "received" }).await;
+        app.add_or_panic("/other", Method::GET, None, |_req| async move { "other" }).await;
+
+        app.start().unwrap();
+
+        let request = |raw: String| async move {
+            let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+            client.write_all(raw.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            client.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        //the next recording sent to the sink, None if nothing is sent.
+        let recording = || async {
+            let (mut socket, _) = tokio::time::timeout(Duration::from_millis(300), sink.accept()).await.ok()?.unwrap();
+            let mut text = String::new();
+            socket.read_to_string(&mut text).await.unwrap();
+            Some(text)
+        };
+
+        //the bytes of both directions are mirrored, with credential headers redacted.
+        let raw = "POST /hooks/github HTTP/1.1\r\nAuthorization: Bearer secret\r\nContent-Length: 7\r\nConnection: close\r\n\r\npayload";
+        let response = request(raw.to_string()).await;
+        let tapped = recording().await.unwrap();
+
+        assert!(tapped.contains(&format!(">>> {} bytes\n", raw.len())));
+        assert!(tapped.contains(&raw.replace("Bearer secret", "[redacted]")));
+        assert!(!tapped.contains("secret"));
+        assert!(tapped.contains(&format!("<<< {} bytes\n{response}", response.len())));
+        assert!(response.ends_with("received"));
+
+        //requests out of the scope are not written.
+        request("GET /other HTTP/1.1\r\nConnection: close\r\n\r\n".to_string()).await;
+        assert!(recording().await.is_none());
+
+        //at most max_bytes are kept in each direction.
+        let body = "x".repeat(1000);
+        let raw = format!("POST /hooks/large HTTP/1.1\r\nContent-Length: 1000\r\nConnection: close\r\n\r\n{body}");
+        request(raw.clone()).await;
+        let tapped = recording().await.unwrap();
+
+        assert!(tapped.contains(&format!(">>> {} bytes, {} not kept\n{}", raw.len(), raw.len() - 512, &raw[..512])));
+
+        app.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_route_change_actor() {
+        use crate::web::route_log::{RouteChangeKind, RouteChangeLog};
+
+        let app = App::bind("127.0.0.1:0").await.unwrap();
+
+        //changes outside of an actor are made by the app.
+        app.add_or_panic("/a", Method::GET, None, |_req| async move { "a" }).await;
+
+        RouteChangeLog::as_actor("deploy 4f2a1c", app.add_or_change_route("/a", Method::GET, None, |_req| async move { "a2" }))
+            .await
+            .unwrap();
+
+        //concurrent changes are each attributed to their own actor.
+        let (first, second) = tokio::join!(
+            RouteChangeLog::as_actor("alice", app.add_or_change_route("/b", Method::GET, None, |_req| async move { "b" })),
+            RouteChangeLog::as_actor("bob", app.add_or_change_route("/c", Method::POST, None, |_req| async move { "c" })),
+        );
+        first.unwrap();
+        second.unwrap();
+
+        let changes = app.route_changes().changes().await;
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|c| (c.actor.as_str(), c.added.clone(), c.replaced.clone()))
+            .collect();
+
+        assert_eq!(
+            summary,
+            [
+                ("app", vec!["GET /a".to_string()], vec![]),
+                ("deploy 4f2a1c", vec![], vec!["GET /a".to_string()]),
+                ("alice", vec!["GET /b".to_string()], vec![]),
+                ("bob", vec!["POST /c".to_string()], vec![]),
+            ]
+        );
+        assert!(changes.iter().all(|c| c.kind == RouteChangeKind::Route));
+
+        //the served trail carries the actor of each change.
+        RouteChangeLog::as_actor("admin", app.serve_route_changes("/changes")).await.unwrap();
+
+        let served: Vec<serde_json::Value> = app.test_client().get("/changes").send().await.unwrap().json().unwrap();
+        assert_eq!(served.len(), 5);
+        assert_eq!(served[1]["actor"], "deploy 4f2a1c");
+        assert_eq!(served[4]["actor"], "admin");
+        assert_eq!(served[4]["added"][0], "GET /changes");
+
+        //only the last changes are kept.
+        let log = RouteChangeLog::new(2);
+        for route in ["/x", "/y", "/z"] {
+            log.record(RouteChangeKind::Route, vec![route.to_string()], Vec::new(), Vec::new()).await;
+        }
+        let kept: Vec<_> = log.changes().await.into_iter().map(|c| c.added[0].clone()).collect();
+        assert_eq!(kept, ["/y", "/z"]);
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limit() {
+        use crate::{
+            factory::Supervisor,
+            web::{
+                Middleware, Request,
+                concurrency::{ConcurrencyLimit, concurrency_limit},
+            },
+        };
+        use futures::StreamExt;
+        use std::time::Duration;
+
+        let limit = ConcurrencyLimit::new(1, 1);
+        let middleware = concurrency_limit(limit.clone());
+
+        let mut running = Request::builder().build();
+        assert!(matches!(middleware(&mut running).await, Middleware::Next));
+        assert_eq!(limit.in_flight(), 1);
+
+        //requests dropped while waiting, such as on a client disconnect, give their spot back.
+        for _ in 0..3 {
+            let mut abandoned = Request::builder().build();
+            assert!(tokio::time::timeout(Duration::from_millis(10), middleware(&mut abandoned)).await.is_err());
+            assert_eq!(limit.waiting(), 0);
+        }
+
+        let waiter = tokio::spawn({
+            let middleware = middleware.clone();
+
+            async move {
+                let mut req = Request::builder().build();
+                matches!(middleware(&mut req).await, Middleware::Next)
+            }
+        });
+
+        while limit.waiting() == 0 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+
+        //a full queue is rejected with an empty 503.
+        let mut rejected = Request::builder().build();
+        let Middleware::Invalid(rejection) = middleware(&mut rejected).await else { panic!("the full queue was joined") };
+        assert_eq!(rejection.get_headers().status(), Some("503 Service Unavailable"));
+        assert!(rejection.get_content().concat().await.is_empty());
+        assert_eq!(rejected.get_header("Retry-After"), None);
+
+        //the waiter runs once the slot is released.
+        drop(running);
+        assert!(waiter.await.unwrap());
+        assert_eq!(limit.waiting(), 0);
+
+        //a soft rejection estimates the wait from the latency of finished work.
+        let supervisor = Arc::new(Supervisor::new());
+        supervisor.latency().record(Duration::from_millis(1000));
+        supervisor.latency().record(Duration::from_millis(2000));
+
+        let limit = ConcurrencyLimit::new(2, 0).soft(202);
+        let middleware = concurrency_limit(limit.clone());
+        let latency = supervisor.latency().snapshot();
+        assert_eq!(limit.estimated_wait(3, &latency), Duration::from_millis(3000));
+
+        let mut first = Request::builder().build();
+        let mut second = Request::builder().build();
+        middleware(&mut first).await;
+        middleware(&mut second).await;
+
+        let mut rejected = Request::builder().extension(supervisor.clone()).build();
+        let Middleware::Invalid(rejection) = middleware(&mut rejected).await else { panic!("the full limit was joined") };
+        assert_eq!(rejection.get_headers().status(), Some("202 Accepted"));
+
+        let busy: serde_json::Value = serde_json::from_slice(&rejection.get_content().concat().await).unwrap();
+        assert_eq!((busy["queue_position"].as_u64(), busy["estimated_wait_ms"].as_u64(), busy["retry_after"].as_u64()), (Some(1), Some(1500), Some(1)));
+        assert_eq!(rejected.get_header("Retry-After"), Some("1"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_asset_manifest() {
+        use crate::web::{manifest::AssetManifest, static_files::StaticFiles};
+
+        let root = std::env::temp_dir().join(format!("async-web-manifest-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("css")).unwrap();
+        std::fs::write(root.join("app.js"), "console.log(1)").unwrap();
+        std::fs::write(root.join("css/site.css"), "body {}").unwrap();
+
+        //a link to a file is listed, a link to a parent directory or nothing is skipped.
+        std::os::unix::fs::symlink(root.join("app.js"), root.join("alias.js")).unwrap();
+        std::os::unix::fs::symlink(&root, root.join("css/loop")).unwrap();
+        std::os::unix::fs::symlink(root.join("missing.js"), root.join("broken.js")).unwrap();
+
+        let manifest = AssetManifest::new(root.to_str().unwrap(), "/assets/");
+
+        //builds never run at once and see the same files.
+        let (first, second) = tokio::join!(manifest.build(), manifest.data());
+        let (first, second) = (first.unwrap(), second.unwrap());
+        assert_eq!(first.version, second.version);
+        assert_eq!(first.assets.keys().collect::<Vec<_>>(), ["/assets/alias.js", "/assets/app.js", "/assets/css/site.css"]);
+
+        //the ETag of an asset is the one static files serve it with.
+        let app_js = &first.assets["/assets/app.js"];
+        let served = StaticFiles::new(&root).resolution("app.js", "/assets/app.js", None).await;
+        assert_eq!(served.get_headers().get("ETag"), Some(app_js.etag.as_str()));
+        assert_eq!(app_js.versioned_url, format!("/assets/app.js?v={}", app_js.hash));
+
+        //If-None-Match lists and weak tags are matched like static files.
+        let status = async |if_none_match: Option<&str>| {
+            manifest.resolution(if_none_match).await.get_headers().status().map(str::to_string)
+        };
+        let etag = format!("\"{}\"", first.version);
+
+        assert_eq!(status(None).await.as_deref(), Some("200 OK"));
+        assert_eq!(status(Some("\"stale\"")).await.as_deref(), Some("200 OK"));
+        assert_eq!(status(Some(&format!("\"stale\", W/{etag}"))).await.as_deref(), Some("304 Not Modified"));
+        assert_eq!(status(Some("*")).await.as_deref(), Some("304 Not Modified"));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_background_spawn() {
+        use crate::web::{Request, background, errors::WorkerError, request_id::request_id};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        //a request not handled by an app has no background tasks to spawn into.
+        let detached = Request::builder().build().spawn(async {});
+        assert!(matches!(detached, Err(WorkerError::NoBackground)));
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+
+        app.use_middleware(request_id()).await;
+
+        let (context_tx, mut context_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (handle_tx, mut handle_rx) = tokio::sync::mpsc::unbounded_channel();
+
+        app.add_or_panic("/jobs", Method::GET, None, move |req| {
+            let context_tx = context_tx.clone();
+            let handle_tx = handle_tx.clone();
+
+            async move {
+                let handle = req
+                    .lock()
+                    .await
+                    .spawn(async move {
+                        let _ = context_tx.send(background::current());
+                        std::future::pending::<()>().await
+                    })
+                    .unwrap();
+                let _ = handle_tx.send(handle);
+                "spawned"
+            }
+        })
+        .await;
+
+        app.start().unwrap();
+
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        client
+            .write_all(b"GET /jobs HTTP/1.1\r\nX-Request-Id: job-7\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        assert!(response.ends_with("spawned"));
+
+        //the request id and route reach the task, which outlives the response.
+        let context = context_rx.recv().await.unwrap().unwrap();
+        assert_eq!(context.request_id.as_deref(), Some("job-7"));
+        assert_eq!(context.route, "/jobs");
+        assert_eq!(app.background_tasks(), 1);
+
+        //closing the app cancels the task and waits for it.
+        let handle = handle_rx.recv().await.unwrap();
+        app.close().await.unwrap();
+        assert!(context.cancellation().is_cancelled());
+        assert_eq!(app.background_tasks(), 0);
+        assert_eq!(handle.await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_throttle() {
+        use crate::web::{Request, throttle::{Throttle, throttle, throttle_per_client}};
+        use std::time::{Duration, Instant};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        //the burst is spent at once, the rest at the rate.
+        let bucket = Throttle::new(4000, 400);
+        assert_eq!(bucket.chunk_size(), 400);
+
+        let started = Instant::now();
+        for _ in 0..3 {
+            bucket.consume(400).await;
+        }
+        assert!(started.elapsed() >= Duration::from_millis(180));
+
+        //responses are written no faster than the throttle allows.
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+
+        app.add_or_panic("/download", Method::GET, Some(vec![throttle(8000)]), |_req| async move { "x".repeat(6000) }).await;
+        app.add_or_panic("/free", Method::GET, None, |_req| async move { "x".repeat(6000) }).await;
+
+        app.start().unwrap();
+
+        let download = |path: &'static str| async move {
+            let started = Instant::now();
+            let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+            let raw = format!("GET {path} HTTP/1.1\r\nConnection: close\r\n\r\n");
+            client.write_all(raw.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            client.read_to_string(&mut response).await.unwrap();
+            assert!(response.ends_with(&"x".repeat(6000)));
+            started.elapsed()
+        };
+
+        assert!(download("/download").await >= Duration::from_millis(400));
+        assert!(download("/free").await < Duration::from_millis(400));
+
+        app.close().await.unwrap();
+
+        //a throttle of one byte per second, so a spent bucket is seen as a consume that does not finish.
+        let middleware = throttle_per_client(1);
+
+        let request = async |ip: &str| {
+            let mut req = Request::builder().client_socket(format!("{ip}:80").parse().unwrap()).build();
+            middleware(&mut req).await;
+            req
+        };
+        let spend = async |req: &Request| {
+            tokio::time::timeout(Duration::from_millis(100), req.throttle().unwrap().consume(1))
+                .await
+                .is_ok()
+        };
+
+        //the connections of one client share a bucket, other clients have their own.
+        let first = request("10.0.0.1").await;
+        let second = request("10.0.0.1").await;
+        let other = request("10.0.0.2").await;
+
+        assert!(spend(&first).await);
+        assert!(!spend(&second).await);
+        assert!(spend(&other).await);
+
+        //past the idle limit, clients without a response in flight are forgotten.
+        drop((first, second));
+
+        for i in 0..1024 {
+            request(&format!("10.1.{}.{}", i / 256, i % 256)).await;
+        }
+
+        assert!(spend(&request("10.0.0.1").await).await);
+        assert!(!spend(&other).await);
+    }
+
+    #[tokio::test]
+    async fn test_middleware_registry() {
+        use crate::web::{
+            Middleware,
+            errors::{Error, RoutingError},
+            middleware,
+            routing::middleware::Priority,
+        };
+
+        let app = App::bind("127.0.0.1:0").await.unwrap();
+        let order = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let record = |name: &'static str| {
+            let order = order.clone();
+            middleware(move |_req| {
+                let order = order.clone();
+                async move {
+                    order.lock().unwrap().push(name);
+                    Middleware::Next
+                }
+            })
+        };
+
+        app.register_middleware("auth", record("auth"), Priority(10)).await;
+        app.register_middleware("request_id", record("request_id"), Priority(0)).await;
+        app.register_middleware("audit", record("audit"), Priority(20)).await;
+
+        //registered middleware is called by priority, not in the order it is named.
+        app.add_route_named("/admin", Method::GET, &["audit", "auth", "request_id"], |_req| async move { "admin" })
+            .await
+            .unwrap();
+        app.add_route_named("/public", Method::GET, &["request_id"], |_req| async move { "public" })
+            .await
+            .unwrap();
+
+        let client = app.test_client();
+        assert_eq!(client.get("/admin").send().await.unwrap().text(), "admin");
+        assert_eq!(*order.lock().unwrap(), ["request_id", "auth", "audit"]);
+
+        //registering a name again replaces it for every route using it.
+        app.register_middleware("request_id", middleware(|_req| async move { Middleware::InvalidEmpty(403.into()) }), Priority(0))
+            .await;
+
+        order.lock().unwrap().clear();
+        assert_eq!(client.get("/admin").send().await.unwrap().status(), 403);
+        assert_eq!(client.get("/public").send().await.unwrap().status(), 403);
+        assert!(order.lock().unwrap().is_empty());
+
+        //routes may only name registered middleware.
+        let unknown = app.add_route_named("/other", Method::GET, &["auth", "missing"], |_req| async move { "other" }).await;
+        assert!(matches!(unknown, Err(Error::Routing(RoutingError::UnknownMiddleware(name))) if name == "missing"));
+        assert!(client.get("/other").send().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_route_pattern() {
+        use crate::web::{Middleware, Request, middleware_fn, rate_limit::rate_limit_per_route, routing::MatchedRoute};
+        use std::time::Duration;
+
+        //a request that was not routed falls back to its cleaned route.
+        assert_eq!(Request::builder().path("/users/7").build().route_pattern(), "/users/7");
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = seen.clone();
+
+        //middleware sees the pattern the request matched, not its url.
+        app.use_middleware(middleware_fn(move |req| {
+            let recorded = recorded.clone();
+            Box::pin(async move {
+                recorded.lock().unwrap().push((req.route_pattern().to_string(), req.matched.clone()));
+                Middleware::Next
+            })
+        }))
+        .await;
+        app.use_middleware(rate_limit_per_route(2, Duration::from_secs(60))).await;
+
+        app.add_or_panic("/users/{id}", Method::GET, None, |_req| async move { "user" }).await;
+        app.add_or_panic("/posts/{id}", Method::GET, None, |_req| async move { "post" }).await;
+
+        let client = app.test_client();
+        assert_eq!(client.get("/users/1").send().await.unwrap().status(), 200);
+
+        let (pattern, matched) = seen.lock().unwrap()[0].clone();
+        assert_eq!(pattern, "/users/{id}");
+        assert_eq!(
+            matched,
+            Some(MatchedRoute {
+                pattern: "/users/{id}".to_string(),
+                method: Method::GET,
+                named_middleware: Vec::new(),
+                fallback: false,
+            })
+        );
+
+        //requests to different urls of one pattern count towards the same limit, other patterns do not.
+        assert_eq!(client.get("/users/2").send().await.unwrap().status(), 200);
+
+        let limited = client.get("/users/3").send().await.unwrap();
+        assert_eq!(limited.status(), 429);
+        assert!(limited.header("Retry-After").is_some());
+
+        assert_eq!(client.get("/posts/1").send().await.unwrap().status(), 200);
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn test_sni_resolver() {
+        use crate::web::tls::{CertificateStore, SniResolver, certified_key};
+
+        //a loaded store and the certificate it serves, to tell which store was selected.
+        let certificate = |host: &str| {
+            let generated = rcgen::generate_simple_self_signed(vec![host.to_string()]).unwrap();
+            let key = certified_key(generated.cert.pem().as_bytes(), generated.signing_key.serialize_pem().as_bytes()).unwrap();
+            Arc::new(key)
+        };
+        let store = |key: &Arc<_>| {
+            let store = CertificateStore::new();
+            store.set(Arc::clone(key));
+            Arc::new(store)
+        };
+
+        let (exact, wildcard, runtime, default) =
+            (certificate("api.example.com"), certificate("*.example.com"), certificate("tenant.example.net"), certificate("localhost"));
+
+        let mut sni = SniResolver::new();
+        sni.add_host("API.example.com", store(&exact));
+        sni.add_host("*.example.com", store(&wildcard));
+        sni.add_host("pending.example.com", Arc::new(CertificateStore::new()));
+        sni.add_host("pending.example.net", Arc::new(CertificateStore::new()));
+
+        let callback_key = runtime.clone();
+        sni.set_callback(move |host| (host == "tenant.example.net").then(|| callback_key.clone()));
+        sni.set_default(store(&default));
+
+        let selected = |host: Option<&str>| sni.resolve_host(host).map(|key| key.cert[0].clone());
+        let served = |key: &Arc<rustls::sign::CertifiedKey>| Some(key.cert[0].clone());
+
+        //exact hosts are matched case insensitively before wildcards.
+        assert_eq!(selected(Some("api.example.com")), served(&exact));
+        assert_eq!(selected(Some("Api.Example.Com")), served(&exact));
+
+        //a wildcard matches a single label.
+        assert_eq!(selected(Some("www.example.com")), served(&wildcard));
+        assert_eq!(selected(Some("a.b.example.com")), served(&default));
+        assert_eq!(selected(Some("example.com")), served(&default));
+
+        //a host whose store is not loaded yet falls through to the wildcard, callback, and default.
+        assert_eq!(selected(Some("pending.example.com")), served(&wildcard));
+        assert_eq!(selected(Some("pending.example.net")), served(&default));
+
+        //the callback is asked for hosts that were not added.
+        assert_eq!(selected(Some("tenant.example.net")), served(&runtime));
+
+        //without a match or SNI, the default is served.
+        assert_eq!(selected(Some("unknown.org")), served(&default));
+        assert_eq!(selected(None), served(&default));
+
+        sni.remove_host("api.example.com");
+        assert_eq!(selected(Some("api.example.com")), served(&wildcard));
+    }
+
+    #[tokio::test]
+    async fn test_local_addr() {
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+        assert_ne!(addr.port(), 0);
+
+        //the address is kept once the app task owns the listener.
+        let handle = app.start().unwrap();
+        assert_eq!(handle.local_addr(), Some(addr));
+        assert_eq!(app.local_addr().unwrap(), addr);
+
+        app.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_also_bind() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        app.also_bind("127.0.0.1:0").await.unwrap();
+
+        app.add_or_panic("/both", Method::GET, None, |_req| async move {
+            JsonResolution::serialize(1).unwrap().resolve()
+        })
+        .await;
+
+        let mut handle = app.start().unwrap();
+        assert_eq!(handle.local_addrs().len(), 2);
+
+        //every listener is served by the same routes.
+        for addr in handle.local_addrs() {
+            let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+            client.write_all(b"GET /both HTTP/1.1\r\n\r\n").await.unwrap();
+
+            let mut response = Vec::new();
+            client.read_to_end(&mut response).await.unwrap();
+            assert!(response.starts_with(b"HTTP/1.1 200 OK"));
+        }
+
+        assert!(!handle.is_finished());
+        app.close().await.unwrap();
+        handle.join().await;
+        assert!(handle.is_finished());
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle() {
+        use std::{
+            net::SocketAddr,
+            sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+        };
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut app = App::bind("127.0.0.1:0").await.unwrap();
+        let addr = app.local_addr().unwrap();
+
+        let (started, rejected, stopped) = (
+            Arc::new(Mutex::new(None::<SocketAddr>)),
+            Arc::new(AtomicUsize::new(0)),
+            Arc::new(AtomicBool::new(false)),
+        );
+
+        let on_start = started.clone();
+        app.on_start(move |addr| {
+            let on_start = on_start.clone();
+            async move { *on_start.lock().await = Some(addr) }
+        });
+
+        let on_connection = rejected.clone();
+        app.on_connection(move |_peer| {
+            on_connection.fetch_add(1, Ordering::SeqCst);
+            async { false }
+        });
+
+        let on_shutdown = stopped.clone();
+        app.on_shutdown(move || {
+            on_shutdown.store(true, Ordering::SeqCst);
+            async {}
+        });
+
+        app.start().unwrap();
+
+        //the client is closed without a response.
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        client.write_all(b"GET / HTTP/1.1\r\n\r\n").await.unwrap();
+        let mut response = Vec::new();
+        let _ = client.read_to_end(&mut response).await;
+        assert!(response.is_empty());
+        assert_eq!(rejected.load(Ordering::SeqCst), 1);
+        assert_eq!(*started.lock().await, Some(addr));
+
+        app.close().await.unwrap();
+        assert!(stopped.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_early_hints() {
+        use crate::web::resolution::{Response, early_hints::EarlyHints};
+
+        let hints = EarlyHints::new(EmptyResolution::status(200).resolve())
+            .preload("/style.css", "style")
+            .link("</app.js>; rel=preload; as=script");
+
+        let informational = hints.informational();
+        assert_eq!(informational.len(), 1);
+        assert_eq!(informational[0].status(), Some("103 Early Hints"));
+        assert_eq!(
+            informational[0].get_all("Link"),
+            ["</style.css>; rel=preload; as=style", "</app.js>; rel=preload; as=script"]
+        );
+        assert_eq!(hints.get_headers().status(), Some("200 OK"));
+
+        assert_eq!(StatusCode::UNPROCESSABLE_CONTENT.to_string(), "422 Unprocessable Content");
+        assert_eq!(StatusCode::OK.with_reason("Fine\r\nX-Injected: 1"), "200 FineX-Injected: 1");
+
+        let response = Response::builder().status(200).reason("Fine").empty();
+        assert_eq!(response.get_headers().status(), Some("200 Fine"));
+    }
+
+    #[tokio::test]
+    async fn test_response_cache() {
+        use crate::web::{Request, response_cache::ResponseCache};
+        use futures::StreamExt;
+        use std::time::Duration;
+
+        let cache = ResponseCache::new(Duration::from_secs(60)).max_entries(1).vary(&["Accept-Language"]);
+        let client = "127.0.0.1:1".parse().unwrap();
+
+        let request = |language: &str| {
+            format!("GET /alt?id=1 HTTP/1.1\r\nAccept-Language: {language}\r\n\r\n").into_bytes()
+        };
+
+        let en = Request::from_stream(&mut request("en").as_slice(), client).await.unwrap();
+        let fr = Request::from_stream(&mut request("fr").as_slice(), client).await.unwrap();
+        let (en, fr) = (cache.key(&en), cache.key(&fr));
+        assert_ne!(en, fr);
+        assert!(cache.get(&en).is_none());
+
+        let mut headers = HeaderMap::new();
+        headers.set_content_type("text/plain");
+        cache.claim(en.clone()).complete("200 OK", headers.clone(), Some(b"a cat".to_vec()));
+
+        let hit = cache.get(&en).unwrap();
+        assert_eq!(hit.get_headers().status(), Some("200 OK"));
+        assert_eq!(hit.get_headers().get("Age"), Some("0"));
+        assert_eq!(hit.get_content().concat().await, b"a cat");
+
+        //only one entry is kept
+        cache.claim(fr.clone()).complete("200 OK", headers.clone(), Some(b"un chat".to_vec()));
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get(&en).is_none());
+
+        //errors and private responses are not kept
+        cache.clear();
+        cache.claim(en.clone()).complete("500 Internal Server Error", headers.clone(), Some(Vec::new()));
+        headers.insert("Cache-Control", Some("private".to_string()));
+        cache.claim(fr.clone()).complete("200 OK", headers, Some(Vec::new()));
+        assert!(cache.is_empty());
+
+        //caching a route keeps its other settings and is recorded as a replaced route.
+        let app = App::bind("127.0.0.1:0").await.unwrap();
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counted = calls.clone();
+
+        app.add_or_panic("/alt", Method::GET, None, move |_req| {
+            counted.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            async move { "a cat" }
+        })
+        .await;
+        app.add_or_panic("/slow", Method::GET, None, |_req| async move {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            "late"
+        })
+        .await;
+        app.timeout_route("/slow", Method::GET, Duration::from_millis(50), 504).await.unwrap();
+
+        app.cache_route("/alt", Method::GET, ResponseCache::new(Duration::from_secs(60))).await.unwrap();
+        app.cache_route("/slow", Method::GET, ResponseCache::new(Duration::from_secs(60))).await.unwrap();
+
+        let client = app.test_client();
+        assert_eq!(client.get("/alt").send().await.unwrap().text(), "a cat");
+        assert_eq!(client.get("/alt").send().await.unwrap().text(), "a cat");
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert_eq!(client.get("/slow").send().await.unwrap().status(), 504);
+
+        let changes = app.route_changes().changes().await;
+        let replaced: Vec<_> = changes.iter().flat_map(|c| c.replaced.clone()).collect();
+        assert_eq!(replaced, ["GET /slow", "GET /alt", "GET /slow"]);
+    }
+
+    #[test]
+    fn test_response_builder() {
+        use crate::web::resolution::Response;
+
+        let created = Response::builder()
+            .status(201)
+            .header("Location", "/users/7")
+            .json(&serde_json::json!({ "id": 7 }));
+
+        let headers = created.get_headers();
+        let header = |name: &str| headers.get(name).map(str::to_string);
+
+        assert_eq!(header("HTTP/1.1").as_deref(), Some("201 Created"));
+        assert_eq!(header("Location").as_deref(), Some("/users/7"));
+        assert_eq!(header("Content-Type").as_deref(), Some("application/json"));
+        assert_eq!(created.body(), br#"{"id":7}"#);
+
+        let html = Response::builder()
+            .header("Content-Type", "text/html")
+            .text("<p>hi</p>");
+
+        assert_eq!(
+            html.get_headers().get("Content-Type"),
+            Some("text/html")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fair_queue() {
+        use crate::factory::Queue;
+
+        let queue = Queue::new();
+        queue.set_fair(true);
+
+        queue.queue_for("10.0.0.1", "flood 1").await;
+        queue.queue_for("10.0.0.1", "flood 2").await;
+
+        //the flooding client is served once, then the other client goes first.
+        assert_eq!(queue.deque(None).await, Some("flood 1"));
+        queue.queue_for("10.0.0.2", "other").await;
+
+        assert_eq!(queue.deque(None).await, Some("other"));
+        assert_eq!(queue.deque(None).await, Some("flood 2"));
+    }
+
+    #[test]
+    fn test_status_code() {
+        let status = StatusCode::new(429).unwrap();
+
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert!(status.is_client_error() && !status.is_server_error());
+        assert_eq!(status.to_string(), "429 Too Many Requests");
+
+        assert!(StatusCode::new(42).is_err());
+        assert_eq!(StatusCode::from(299).to_string(), "299 Unknown Status Code");
+        assert_eq!(StatusCode::from(-1), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(i32::from(StatusCode::NO_CONTENT), 204);
+    }
+
+    #[tokio::test]
+    async fn test_banner() {
+        use crate::web::banner::Banner;
+
+        let endpoint = || {
+            EndPoint::new(
+                Arc::new(|_req| Box::pin(async { EmptyResolution::status(200).resolve() })),
+                None,
+            )
+        };
+
+        let mut tree = RouteTree::new(None);
+        tree.add_route("/users/{id}", Some((Method::GET, endpoint()))).await.unwrap();
+        tree.add_route("/users/{id}", Some((Method::DELETE, endpoint()))).await.unwrap();
+        tree.add_route("/users/me", Some((Method::GET, endpoint()))).await.unwrap();
+
+        let routes = Banner::routes(&tree).await;
+        let listed: Vec<String> = routes.iter().map(|r| format!("{} {}", r.method, r.pattern)).collect();
+        assert_eq!(listed, ["GET /users/me", "DELETE /users/{id}", "GET /users/{id}"]);
+
+        let warnings = Banner::warnings(&tree).await;
+        assert_eq!(warnings, ["/users/me shadows /users/{id}, DELETE /users/me is not found"]);
+
+        let banner = Banner { routes, warnings, ..Banner::default() };
+        assert!(banner.to_string().contains("warning: /users/me shadows"));
+    }
 }
@@ -1,10 +1,63 @@
+pub mod access_log;
+#[cfg(feature = "tls")]
+pub mod acme;
 pub mod app;
+pub mod app_builder;
+pub mod app_handle;
+pub mod audit;
+pub mod auth;
+pub mod background;
+pub mod banner;
+pub mod buffer_pool;
+pub mod bundle;
+pub mod cache;
+pub mod concurrency;
+pub mod config;
+pub mod connection_limit;
+pub mod cookies;
+pub mod cors;
+pub mod debug_tap;
+pub mod engine;
+pub mod error_map;
+pub mod error_pages;
 pub mod errors;
+pub mod execution;
+pub mod extract;
+pub mod forwarded;
+pub mod guards;
+pub mod headers;
+pub mod http_date;
+pub mod idempotency;
+pub mod lifecycle;
+pub mod locale;
+pub mod manifest;
+pub mod media_type;
+pub mod negotiate;
+#[cfg(feature = "openapi")]
+pub mod openapi;
+pub mod range;
+pub mod rate_limit;
+pub mod rejections;
+pub mod request_id;
+pub mod response_cache;
 pub mod resolution;
+pub mod route_log;
 pub mod routing;
+pub mod security;
+pub mod socket_options;
+pub mod stats;
+pub mod static_files;
+pub mod status;
 pub mod streams;
+pub mod telemetry;
+pub mod testing;
+pub mod throttle;
+pub mod trace_context;
+#[cfg(feature = "tls")]
+pub mod tls;
+pub mod watchdog;
 
-use std::sync::Arc;
+use std::{pin::Pin, sync::Arc, time::Duration};
 
 use serde::Serialize;
 use tokio::sync::Mutex;
@@ -17,14 +70,18 @@ use crate::web::{
         json_resolution::JsonResolution,
         redirect::{Redirect, RedirectType},
     },
-    routing::middleware::MiddlewareClosure,
+    routing::{RequestFuture, ResolutionFuture, middleware::MiddlewareClosure},
 };
 
 pub use self::{
     app::App, resolution::Resolution, routing::method::Method, routing::middleware::Middleware,
     routing::request::Request, routing::route::Route, routing::router::endpoint::EndPoint,
+    headers::HeaderMap, status::StatusCode,
 };
 
+//the app runs its connections on the workers of the factory, the only implementation of them.
+pub use crate::factory::{Queue, WorkManager, Worker};
+
 /// ## resolve!
 ///
 /// Shorthand for writing a route resolver.
@@ -135,19 +192,8 @@ macro_rules! middleware {
 
     // collection
     ( $( $items:ident ),* ) => {{
-        let mut collection: ::std::vec::Vec<
-            ::std::sync::Arc<
-                dyn Fn(
-                    ::std::sync::Arc<::tokio::sync::Mutex<$crate::web::Request>>
-                ) -> ::std::pin::Pin<
-                    ::std::boxed::Box<
-                        dyn ::std::future::Future<Output = $crate::web::Middleware> + Send
-                    >
-                > + Send + Sync
-            >
-        > = ::std::vec::Vec::new();
-
-        $( collection.push($items.clone()); )*
+        let collection: ::std::vec::Vec<$crate::web::routing::middleware::MiddlewareClosure> =
+            ::std::vec![$( $items.clone() ),*];
 
         ::std::option::Option::Some(collection)
     }};
@@ -161,7 +207,7 @@ macro_rules! middleware {
 ///
 /// ```
 ///     let mw_1 = middleware(|req| async move {
-///         Middleware::InvalidEmpty(403)
+///         Middleware::InvalidEmpty(StatusCode::FORBIDDEN)
 ///     });
 ///
 ///     //or moving some value
@@ -175,12 +221,37 @@ macro_rules! middleware {
 ///         }
 ///     });
 /// ```
+///
+/// The closure is given the request as an `Arc<Mutex<Request>>`, see `middleware_fn` to borrow it instead.
 pub fn middleware<F, Fut>(f: F) -> MiddlewareClosure
 where
     F: Fn(Arc<Mutex<Request>>) -> Fut + Send + Sync + 'static,
     Fut: Future<Output = Middleware> + Send + Sync + 'static, //middleware future
 {
-    Arc::new(move |req: Arc<Mutex<Request>>| Box::pin(f(req)))
+    let f = Arc::new(f);
+
+    Arc::new(move |req: &mut Request| {
+        let f = f.clone();
+
+        Box::pin(async move { req.shared(|req| f(req)).await })
+    })
+}
+
+/// # Middleware Fn
+///
+/// Allows for the creation of middleware closures that borrow the request, no lock is needed.
+///
+/// ```ignore
+/// let tag = middleware_fn(|req| Box::pin(async move {
+///     req.add_header("X-Tag".to_string(), Some("1".to_string()));
+///     Middleware::Next
+/// }));
+/// ```
+pub fn middleware_fn<F>(f: F) -> MiddlewareClosure
+where
+    F: for<'a> Fn(&'a mut Request) -> RequestFuture<'a, Middleware> + Send + Sync + 'static,
+{
+    Arc::new(f)
 }
 
 pub type Resolved = Box<dyn Resolution + Send + 'static>;
@@ -188,7 +259,7 @@ pub type Resolved = Box<dyn Resolution + Send + 'static>;
 /// # Status
 ///
 /// Short for `EmptyResolution::status(code)`
-pub fn status(code: i32) -> impl Resolution {
+pub fn status(code: impl Into<StatusCode>) -> impl Resolution {
     EmptyResolution::status(code)
 }
 
@@ -213,7 +284,7 @@ where
 /// Note: Code is 500 by default, see `error_status`
 pub fn error<E, C>(error: E, configured: C) -> ErrorResolution
 where
-    E: std::error::Error + 'static,
+    E: std::error::Error + Send + 'static,
     C: Into<Option<Configured>>,
 {
     ErrorResolution::from_error(error, configured)
@@ -226,17 +297,17 @@ where
 /// ```
 /// let mut err = ErrorResolution::from_error(error, configured);
 ///
-/// err.code = code;
+/// err.code = code.into();
 ///
 /// err
 /// ```
-pub fn error_status<E, C>(err: E, configured: C, code: i32) -> impl Resolution
+pub fn error_status<E, C>(err: E, configured: C, code: impl Into<StatusCode>) -> impl Resolution
 where
-    E: std::error::Error + 'static,
+    E: std::error::Error + Send + 'static,
     C: Into<Option<Configured>>,
 {
     let mut res = error(err, configured);
-    res.code = code;
+    res.code = code.into();
 
     res
 }
@@ -261,3 +332,56 @@ pub fn file(file: &str) -> impl Resolution {
 pub fn redirect(redir_type: RedirectType) -> impl Resolution {
     Redirect::new(redir_type)
 }
+
+/// # Timeout
+///
+/// Wraps a resolution function, racing it against the `duration`.
+///
+/// If the resolution does not complete in time it is dropped and a `504` is served instead.
+///
+/// Only the resolution is raced, middleware of the route runs before the clock starts, see `App::timeout_route` to cut off the middleware as well.
+///
+/// ```ignore
+/// app.add_or_panic("/slow", Method::GET, None, timeout(Duration::from_secs(5), |req| async move {
+///     expensive_work().await;
+///     status(200).resolve()
+/// }))
+/// .await;
+/// ```
+///
+/// See `timeout_status` to serve a different status.
+pub fn timeout<F, Fut>(
+    duration: Duration,
+    resolution: F,
+) -> impl Fn(Arc<Mutex<Request>>) -> Pin<Box<ResolutionFuture>> + Send + Sync + 'static
+where
+    F: Fn(Arc<Mutex<Request>>) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Resolved> + Send + 'static,
+{
+    timeout_status(duration, 504, resolution)
+}
+
+/// # Timeout Status
+///
+/// Same as `timeout` but serves `code` when the resolution does not complete in time, for example `408`.
+pub fn timeout_status<F, Fut>(
+    duration: Duration,
+    code: impl Into<StatusCode>,
+    resolution: F,
+) -> impl Fn(Arc<Mutex<Request>>) -> Pin<Box<ResolutionFuture>> + Send + Sync + 'static
+where
+    F: Fn(Arc<Mutex<Request>>) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Resolved> + Send + 'static,
+{
+    let code = code.into();
+
+    move |req: Arc<Mutex<Request>>| {
+        let fut = resolution(req);
+
+        Box::pin(async move {
+            tokio::time::timeout(duration, fut)
+                .await
+                .unwrap_or_else(|_| EmptyResolution::status(code).resolve())
+        })
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    net::IpAddr,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use tokio::{
+    fs::File,
+    io::AsyncWriteExt,
+    sync::{Mutex, mpsc::Sender},
+};
+
+use crate::web::{Middleware, middleware_fn, routing::middleware::MiddlewareClosure};
+
+/// # Access Record
+///
+/// A single request written to the access log.
+#[derive(Debug, Clone, Serialize)]
+pub struct AccessRecord {
+    /// Seconds since the unix epoch.
+    pub timestamp: u64,
+    /// The address of the client.
+    pub client: IpAddr,
+    /// The method of the request.
+    pub method: String,
+    /// The path and query that was requested.
+    pub path: String,
+    /// The status code of the response, 0 if the status could not be read.
+    pub status: u16,
+    /// The size of the response body in bytes.
+    pub bytes: usize,
+    /// Milliseconds from reading the request until the response was written.
+    pub latency_ms: u128,
+    /// The id of the request, when the request id middleware is used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl AccessRecord {
+    /// Create a record stamped with the current time, the status is the status line, for example "404 Not Found".
+    pub fn new(
+        client: IpAddr,
+        method: &str,
+        path: &str,
+        status: &str,
+        bytes: usize,
+        latency: Duration,
+    ) -> Self {
+        let status = status
+            .split_whitespace()
+            .next()
+            .and_then(|code| code.parse().ok())
+            .unwrap_or(0);
+
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            client,
+            method: method.to_string(),
+            path: path.to_string(),
+            status,
+            bytes,
+            latency_ms: latency.as_millis(),
+            request_id: None,
+        }
+    }
+
+    /// # Common Log Format
+    ///
+    /// Formats the record as a line of the Common Log Format, with the latency appended.
+    ///
+    /// `127.0.0.1 - - [10/Oct/2000:13:55:36 +0000] "GET /index.html HTTP/1.1" 200 2326 4ms`
+    pub fn to_common(&self) -> String {
+        format!(
+            "{} - - [{}] \"{} {} HTTP/1.1\" {} {} {}ms",
+            self.client,
+            clf_time(self.timestamp),
+            self.method,
+            self.path,
+            self.status,
+            self.bytes,
+            self.latency_ms
+        )
+    }
+}
+
+/// formats unix seconds as `10/Oct/2000:13:55:36 +0000`.
+fn clf_time(timestamp: u64) -> String {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let days = (timestamp / 86_400) as i64;
+    let secs = timestamp % 86_400;
+
+    //civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{day:02}/{}/{year}:{:02}:{:02}:{:02} +0000",
+        MONTHS[(month - 1) as usize],
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+/// # Access Log Format
+///
+/// How each record is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessLogFormat {
+    /// The Common Log Format with the latency appended.
+    Common,
+    /// One JSON object per line.
+    Json,
+}
+
+/// # Access Log Sink
+///
+/// Where the access log is written to.
+pub enum AccessLogSink {
+    /// Lines are printed to stdout.
+    Stdout,
+    /// Lines are appended to a file.
+    File(PathBuf),
+    /// Records are sent through a channel, records are dropped if the channel is full.
+    Channel(Sender<AccessRecord>),
+}
+
+/// # Access Log
+///
+/// Records the method, path, status, response size, latency, and client of each request.
+///
+/// Used with the [`access_log`] middleware, cloning the log shares the same sink.
+#[derive(Clone)]
+pub struct AccessLog {
+    sink: Arc<AccessLogSink>,
+    format: AccessLogFormat,
+    file: Arc<Mutex<Option<File>>>,
+}
+
+impl AccessLog {
+    /// Create a new access log that writes to the sink in the format.
+    pub fn new(sink: AccessLogSink, format: AccessLogFormat) -> Self {
+        Self {
+            sink: Arc::new(sink),
+            format,
+            file: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Formats the record in the format of the log.
+    pub fn format(&self, record: &AccessRecord) -> String {
+        match self.format {
+            AccessLogFormat::Common => record.to_common(),
+            AccessLogFormat::Json => serde_json::to_string(record).unwrap_or_default(),
+        }
+    }
+
+    /// # Record
+    ///
+    /// Writes the record to the sink.
+    ///
+    /// Failing to write does not fail the request.
+    pub async fn record(&self, record: AccessRecord) {
+        match self.sink.as_ref() {
+            AccessLogSink::Stdout => println!("{}", self.format(&record)),
+            AccessLogSink::Channel(sender) => {
+                let _ = sender.try_send(record);
+            }
+            AccessLogSink::File(path) => {
+                let mut line = self.format(&record);
+                line.push('\n');
+
+                let mut file = self.file.lock().await;
+
+                if file.is_none() {
+                    *file = tokio::fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(path)
+                        .await
+                        .ok();
+                }
+
+                if let Some(f) = file.as_mut()
+                    && f.write_all(line.as_bytes()).await.is_err()
+                {
+                    //reopen on the next record.
+                    *file = None;
+                }
+            }
+        }
+    }
+}
+
+/// # Access Log
+///
+/// Middleware that writes each request to the access log once the response is written.
+///
+/// Add it with `App::use_middleware` before any other middleware, so requests rejected by later middleware are logged too.
+///
+/// ```ignore
+/// let log = AccessLog::new(AccessLogSink::File("logs/access.log".into()), AccessLogFormat::Common);
+///
+/// app.use_middleware(access_log(log)).await;
+/// ```
+pub fn access_log(log: AccessLog) -> MiddlewareClosure {
+    middleware_fn(move |req| {
+        let log = log.clone();
+
+        Box::pin(async move {
+            req.extensions.insert(log);
+
+            Middleware::Next
+        })
+    })
+}
@@ -0,0 +1,544 @@
+use std::{
+    collections::HashMap,
+    io::BufReader,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use futures::{Stream, stream};
+use tokio::{net::ToSocketAddrs, sync::Mutex, task::JoinHandle};
+
+use crate::{
+    factory::scheduler::days_from_civil,
+    web::{App, Resolution, headers::HeaderMap, tls::CertificateStore},
+};
+
+/// The path ACME servers fetch http-01 challenges from.
+pub const CHALLENGE_PATH: &str = "/.well-known/acme-challenge";
+
+/// # Acme Challenges
+///
+/// The pending http-01 challenges, a map of token to key authorization.
+///
+/// Served by the app after calling `App::serve_acme_challenges`, an [`AcmeProvider`] publishes into it while an order is being validated.
+///
+/// Cloning shares the same challenges.
+#[derive(Clone, Default)]
+pub struct AcmeChallenges {
+    tokens: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl AcmeChallenges {
+    /// Create an empty challenge map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Publish the key authorization for a token.
+    pub async fn insert(&self, token: &str, key_authorization: &str) {
+        self.tokens
+            .lock()
+            .await
+            .insert(token.to_string(), key_authorization.to_string());
+    }
+
+    /// Remove a token once the challenge is complete.
+    pub async fn remove(&self, token: &str) -> Option<String> {
+        self.tokens.lock().await.remove(token)
+    }
+
+    /// Get the key authorization of a token.
+    pub async fn get(&self, token: &str) -> Option<String> {
+        self.tokens.lock().await.get(token).cloned()
+    }
+
+    /// # Resolution
+    ///
+    /// The resolution served for a token, `200` with the key authorization or `404`.
+    pub async fn resolution(&self, token: &str) -> Box<dyn Resolution + Send + 'static> {
+        KeyAuthorization {
+            value: self.get(token).await,
+        }
+        .resolve()
+    }
+}
+
+/// # Challenge App
+///
+/// Binds a plain HTTP app that only serves the challenges, ACME servers validate http-01 over port 80 and can not reach a TLS listener without a certificate.
+///
+/// The app is returned unstarted, start it before ordering a certificate.
+///
+/// ```ignore
+/// let challenges = AcmeChallenges::new();
+///
+/// let mut http = challenge_app("0.0.0.0:80", challenges.clone()).await?;
+/// http.start()?;
+/// ```
+pub async fn challenge_app<A>(addr: A, challenges: AcmeChallenges) -> crate::Result<App>
+where
+    A: ToSocketAddrs,
+{
+    let app = App::bind(addr).await?;
+    app.serve_acme_challenges(challenges).await?;
+
+    Ok(app)
+}
+
+/// serves the key authorization of a challenge as plain text.
+struct KeyAuthorization {
+    value: Option<String>,
+}
+
+impl Resolution for KeyAuthorization {
+    fn get_headers(&self) -> HeaderMap {
+        let mut hmap = HeaderMap::new();
+
+        let code = if self.value.is_some() { 200 } else { 404 };
+        hmap.set_status(code);
+        hmap.set_content_type("text/plain");
+
+        hmap
+    }
+
+    fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        let value = self.value.clone().unwrap_or_default();
+
+        Box::pin(stream::once(async move { value.into_bytes() }))
+    }
+
+    fn content_length(&self) -> Option<u64> {
+        Some(self.value.as_ref().map_or(0, String::len) as u64)
+    }
+
+    fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+        Box::new(self)
+    }
+}
+
+/// # Certificate PEM
+///
+/// A certificate chain and private key, both PEM encoded.
+pub struct CertificatePem {
+    pub cert_chain: Vec<u8>,
+    pub private_key: Vec<u8>,
+}
+
+/// # Validity
+///
+/// When a certificate starts and stops being valid, read from its `notBefore` and `notAfter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Validity {
+    pub not_before: SystemTime,
+    pub not_after: SystemTime,
+}
+
+impl Validity {
+    /// The validity of the first certificate of a PEM chain, None if it can not be read.
+    pub fn from_pem(cert_pem: &[u8]) -> Option<Self> {
+        let cert = rustls_pemfile::certs(&mut BufReader::new(cert_pem))
+            .next()?
+            .ok()?;
+
+        Self::from_der(&cert)
+    }
+
+    /// The validity of a DER encoded certificate, None if it can not be read.
+    pub fn from_der(cert_der: &[u8]) -> Option<Self> {
+        //Certificate -> TBSCertificate -> [version], serial, signature, issuer, validity
+        let (_, cert, _) = der_element(cert_der, SEQUENCE)?;
+        let (_, tbs, _) = der_element(cert, SEQUENCE)?;
+
+        let mut rest = tbs;
+        if rest.first() == Some(&EXPLICIT_VERSION) {
+            rest = der_element(rest, EXPLICIT_VERSION)?.2;
+        }
+
+        for _ in 0..3 {
+            rest = der_any(rest)?.2;
+        }
+
+        let (_, validity, _) = der_element(rest, SEQUENCE)?;
+        let (not_before, rest) = der_time(validity)?;
+        let (not_after, _) = der_time(rest)?;
+
+        Some(Self {
+            not_before,
+            not_after,
+        })
+    }
+
+    /// # Renew At
+    ///
+    /// When the certificate should be renewed, once two thirds of its lifetime have passed.
+    pub fn renew_at(&self) -> SystemTime {
+        let lifetime = self
+            .not_after
+            .duration_since(self.not_before)
+            .unwrap_or_default();
+
+        self.not_after - lifetime / 3
+    }
+}
+
+const SEQUENCE: u8 = 0x30;
+const EXPLICIT_VERSION: u8 = 0xa0;
+const UTC_TIME: u8 = 0x17;
+const GENERALIZED_TIME: u8 = 0x18;
+
+/// splits the next DER element into its tag, content, and the bytes after it.
+fn der_any(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, input) = input.split_first()?;
+    let (&first, mut input) = input.split_first()?;
+
+    let len = match first {
+        0..=0x7f => first as usize,
+        0x81..=0x84 => {
+            let count = (first & 0x7f) as usize;
+            let (bytes, rest) = input.split_at_checked(count)?;
+            input = rest;
+
+            bytes.iter().fold(0usize, |len, b| (len << 8) | *b as usize)
+        }
+        _ => return None,
+    };
+
+    let (content, rest) = input.split_at_checked(len)?;
+
+    Some((tag, content, rest))
+}
+
+/// the next DER element, None if it does not have the tag.
+fn der_element(input: &[u8], tag: u8) -> Option<(u8, &[u8], &[u8])> {
+    der_any(input).filter(|(t, _, _)| *t == tag)
+}
+
+/// reads a UTCTime or GeneralizedTime in UTC.
+fn der_time(input: &[u8]) -> Option<(SystemTime, &[u8])> {
+    let (tag, content, rest) = der_any(input)?;
+    let text = std::str::from_utf8(content).ok()?.strip_suffix('Z')?;
+
+    let (year, text) = match tag {
+        //two digit years are 1950 to 2049.
+        UTC_TIME => {
+            let year: i64 = text.get(..2)?.parse().ok()?;
+            (
+                if year < 50 { 2000 + year } else { 1900 + year },
+                text.get(2..)?,
+            )
+        }
+        GENERALIZED_TIME => (text.get(..4)?.parse().ok()?, text.get(4..)?),
+        _ => return None,
+    };
+
+    if text.len() != 10 || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let field = |at: usize| text[at..at + 2].parse::<u32>().ok();
+    let (month, day) = (field(0)?, field(2)?);
+    let (hour, minute, second) = (field(4)?, field(6)?, field(8)?);
+
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    let seconds =
+        days_from_civil(year, month, day) * 86_400 + i64::from(hour * 3_600 + minute * 60 + second);
+
+    let time = match u64::try_from(seconds) {
+        Ok(seconds) => UNIX_EPOCH + Duration::from_secs(seconds),
+        Err(_) => UNIX_EPOCH - Duration::from_secs(seconds.unsigned_abs()),
+    };
+
+    Some((time, rest))
+}
+
+/// Idiomatic type alias for the error an [`AcmeProvider`] may fail with.
+pub type AcmeError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Idiomatic type alias for the future returned by [`AcmeProvider::obtain`].
+pub type AcmeFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<CertificatePem, AcmeError>> + Send + 'a>>;
+
+/// # Acme Provider
+///
+/// The ACME client used to order certificates, see [`AcmeClient`] with the `acme` feature.
+///
+/// While an order is validated the provider must publish each http-01 token into the `challenges`, and remove it afterwards.
+pub trait AcmeProvider: Send + Sync + 'static {
+    /// Orders a certificate for the domains.
+    fn obtain<'a>(
+        &'a self,
+        domains: &'a [String],
+        challenges: &'a AcmeChallenges,
+    ) -> AcmeFuture<'a>;
+}
+
+/// # Acme Manager
+///
+/// Obtains a certificate on start and renews it once two thirds of its lifetime have passed, hot swapping it into the [`CertificateStore`] used by the TLS listener.
+///
+/// ```ignore
+/// let store = Arc::new(CertificateStore::new());
+/// let challenges = AcmeChallenges::new();
+///
+/// app.use_tls(store.clone());
+///
+/// //the challenges are validated over plain HTTP.
+/// let mut http = challenge_app("0.0.0.0:80", challenges.clone()).await?;
+/// http.start()?;
+///
+/// let client = AcmeClient::lets_encrypt().contact("admin@example.com");
+/// let manager = AcmeManager::new(client, vec!["example.com".into()], store, challenges);
+/// manager.spawn(|e| eprintln!("renewal failed {e}"));
+/// ```
+pub struct AcmeManager {
+    provider: Arc<dyn AcmeProvider>,
+    domains: Vec<String>,
+    store: Arc<CertificateStore>,
+    challenges: AcmeChallenges,
+
+    /// How long to wait before trying again after a failed renewal.
+    ///
+    /// By default (1 hour)
+    pub retry_after: Duration,
+}
+
+impl AcmeManager {
+    /// Create a new manager for the domains.
+    pub fn new(
+        provider: impl AcmeProvider,
+        domains: Vec<String>,
+        store: Arc<CertificateStore>,
+        challenges: AcmeChallenges,
+    ) -> Self {
+        Self {
+            provider: Arc::new(provider),
+            domains,
+            store,
+            challenges,
+            retry_after: Duration::from_secs(60 * 60),
+        }
+    }
+
+    /// # Renew
+    ///
+    /// Orders a new certificate and loads it into the store, returning its validity.
+    pub async fn renew(&self) -> Result<Validity, AcmeError> {
+        let pem = self
+            .provider
+            .obtain(&self.domains, &self.challenges)
+            .await?;
+
+        let validity = Validity::from_pem(&pem.cert_chain)
+            .ok_or("the certificate validity can not be read")?;
+
+        self.store.load_pem(&pem.cert_chain, &pem.private_key)?;
+
+        Ok(validity)
+    }
+
+    /// # Spawn
+    ///
+    /// Spawns a task that renews the certificate immediately, then again at [`Validity::renew_at`].
+    ///
+    /// Failures are passed to `on_error` and retried after `retry_after`.
+    pub fn spawn(self, on_error: impl Fn(String) + Send + Sync + 'static) -> JoinHandle<()> {
+        tokio::task::spawn(async move {
+            loop {
+                let wait = match self.renew().await {
+                    //never sooner than a retry, a short lived certificate would be ordered in a loop.
+                    Ok(validity) => validity
+                        .renew_at()
+                        .duration_since(SystemTime::now())
+                        .unwrap_or_default()
+                        .max(self.retry_after),
+                    Err(e) => {
+                        on_error(e.to_string());
+                        self.retry_after
+                    }
+                };
+
+                tokio::time::sleep(wait).await;
+            }
+        })
+    }
+}
+
+/// # Acme Client
+///
+/// An [`AcmeProvider`] speaking the ACME protocol with `instant-acme`, validating the domains with http-01 challenges.
+///
+/// The account is created on the first order, or restored from `credentials_file`.
+///
+/// Requires the `acme` feature.
+///
+/// ```ignore
+/// let client = AcmeClient::lets_encrypt()
+///     .contact("admin@example.com")
+///     .credentials_file("acme-account.json");
+/// ```
+#[cfg(feature = "acme")]
+pub struct AcmeClient {
+    directory_url: String,
+    contact: Vec<String>,
+    credentials_file: Option<std::path::PathBuf>,
+    account: tokio::sync::OnceCell<instant_acme::Account>,
+}
+
+#[cfg(feature = "acme")]
+impl AcmeClient {
+    /// Create a client for the directory of an ACME server.
+    pub fn new(directory_url: &str) -> Self {
+        Self {
+            directory_url: directory_url.to_string(),
+            contact: Vec::new(),
+            credentials_file: None,
+            account: tokio::sync::OnceCell::new(),
+        }
+    }
+
+    /// A client for the production Let's Encrypt directory.
+    pub fn lets_encrypt() -> Self {
+        Self::new(instant_acme::LetsEncrypt::Production.url())
+    }
+
+    /// A client for the staging Let's Encrypt directory, its certificates are not trusted but its rate limits are higher.
+    pub fn lets_encrypt_staging() -> Self {
+        Self::new(instant_acme::LetsEncrypt::Staging.url())
+    }
+
+    /// Adds an email the ACME server may contact about the account.
+    pub fn contact(mut self, email: &str) -> Self {
+        self.contact.push(format!("mailto:{email}"));
+        self
+    }
+
+    /// # Credentials File
+    ///
+    /// Where the account credentials are kept, so restarts reuse the account instead of creating another.
+    ///
+    /// By default (None) a new account is created every time the app starts.
+    pub fn credentials_file(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.credentials_file = Some(path.into());
+        self
+    }
+
+    /// the account, created or restored on first use.
+    async fn account(&self) -> Result<&instant_acme::Account, AcmeError> {
+        self.account
+            .get_or_try_init(|| async {
+                if let Some(path) = &self.credentials_file
+                    && let Ok(saved) = tokio::fs::read(path).await
+                {
+                    let credentials = serde_json::from_slice(&saved)?;
+                    return Ok(instant_acme::Account::builder()?
+                        .from_credentials(credentials)
+                        .await?);
+                }
+
+                let contact: Vec<&str> = self.contact.iter().map(String::as_str).collect();
+                let (account, credentials) = instant_acme::Account::builder()?
+                    .create(
+                        &instant_acme::NewAccount {
+                            contact: &contact,
+                            terms_of_service_agreed: true,
+                            only_return_existing: false,
+                        },
+                        self.directory_url.clone(),
+                        None,
+                    )
+                    .await?;
+
+                if let Some(path) = &self.credentials_file {
+                    tokio::fs::write(path, serde_json::to_vec(&credentials)?).await?;
+                }
+
+                Ok(account)
+            })
+            .await
+    }
+
+    /// places an order, publishing its http-01 challenges until it is validated.
+    async fn order(
+        &self,
+        domains: &[String],
+        challenges: &AcmeChallenges,
+    ) -> Result<CertificatePem, AcmeError> {
+        use instant_acme::{
+            AuthorizationStatus, ChallengeType, Identifier, NewOrder, OrderStatus, RetryPolicy,
+        };
+
+        let identifiers: Vec<Identifier> = domains.iter().cloned().map(Identifier::Dns).collect();
+
+        let mut order = self
+            .account()
+            .await?
+            .new_order(&NewOrder::new(&identifiers))
+            .await?;
+
+        let mut tokens = Vec::new();
+
+        let validated: Result<OrderStatus, AcmeError> = async {
+            let mut authorizations = order.authorizations();
+
+            while let Some(authorization) = authorizations.next().await {
+                let mut authorization = authorization?;
+
+                if authorization.status == AuthorizationStatus::Valid {
+                    continue;
+                }
+
+                let mut challenge = authorization
+                    .challenge(ChallengeType::Http01)
+                    .ok_or("the ACME server offered no http-01 challenge")?;
+
+                challenges
+                    .insert(&challenge.token, challenge.key_authorization().as_str())
+                    .await;
+                tokens.push(challenge.token.clone());
+
+                challenge.set_ready().await?;
+            }
+
+            Ok(order.poll_ready(&RetryPolicy::default()).await?)
+        }
+        .await;
+
+        //the tokens are removed whether or not the order was validated.
+        for token in tokens {
+            challenges.remove(&token).await;
+        }
+
+        let status = validated?;
+        if status != OrderStatus::Ready {
+            return Err(format!("the order was not validated, it is {status:?}").into());
+        }
+
+        let private_key = order.finalize().await?;
+        let cert_chain = order.poll_certificate(&RetryPolicy::default()).await?;
+
+        Ok(CertificatePem {
+            cert_chain: cert_chain.into_bytes(),
+            private_key: private_key.into_bytes(),
+        })
+    }
+}
+
+#[cfg(feature = "acme")]
+impl AcmeProvider for AcmeClient {
+    fn obtain<'a>(
+        &'a self,
+        domains: &'a [String],
+        challenges: &'a AcmeChallenges,
+    ) -> AcmeFuture<'a> {
+        Box::pin(self.order(domains, challenges))
+    }
+}
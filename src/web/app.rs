@@ -1,24 +1,73 @@
-use std::{net::SocketAddr, pin::Pin, sync::Arc};
+use std::{any::Any, io::{IoSlice, SeekFrom, Write}, net::SocketAddr, panic::AssertUnwindSafe, path::Path, pin::Pin, sync::Arc, time::Duration};
 
-use futures::StreamExt;
+#[cfg(feature = "tls")]
+use tokio_rustls::TlsAcceptor;
+
+use futures::{FutureExt, StreamExt};
 use tokio::{
-    io::AsyncWriteExt,
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt, BufReader},
     net::{TcpListener, TcpStream, ToSocketAddrs},
-    sync::{Mutex, MutexGuard, broadcast},
+    sync::{Mutex, MutexGuard, OwnedSemaphorePermit, broadcast, watch},
     task::{self, JoinHandle},
+    time::Instant,
 };
 
-use crate::{factory::WorkManager, web::errors::AppState};
+use crate::{
+    factory::{
+        AutoscaleConfig, CancellationToken, DrainReport, Job, Metrics, QueuePolicy, Scheduler, Supervisor, WorkManager, WorkStats, WorkerCrash, WorkerHealth,
+        autoscale,
+    },
+    web::errors::AppState,
+};
 
 use crate::web::{
-    EndPoint, Method, Middleware, Request, Resolution,
+    EndPoint, Method, Middleware, Request, Resolution, StatusCode,
+    access_log::{AccessLog, AccessRecord},
+    app_builder::{AppBuilder, AppConfig},
+    app_handle::AppHandle,
+    audit::AuditLog,
+    background::Background,
+    banner::{Banner, RouteSummary},
+    buffer_pool::BufferPool,
+    bundle::StaticBundle,
+    headers::{HeaderMap, STATUS_KEY},
+    cache::Cache,
+    debug_tap::DebugTap,
+    engine::Engine,
+    error_map::ErrorMap,
+    error_pages::ErrorPages,
+    guards::RouteGuards,
+    http_date::{self, DATE_HEADER, DEFAULT_SERVER, SERVER_HEADER},
     errors::RoutingError,
-    resolution::empty_resolution::EmptyResolution,
+    execution::{ExecutionStrategy, Executor},
+    range::{self, RangeRequest},
+    rejections::Rejections,
+    response_cache::{ResponseCache, ResponseCacheClaim},
+    route_log::{RouteChangeKind, RouteChangeLog, describe_route},
+    extract::{IntoHandler, StateMap},
+    idempotency::IdempotencyClaim,
+    lifecycle::Lifecycle,
+    connection_limit::ConnectionLimit,
+    socket_options::SocketOptions,
+    forwarded::TrustedProxies,
+    watchdog::{self, ReadinessProbe, WatchdogConfig},
+    resolution::{
+        IntoResolution, empty_resolution::EmptyResolution, get_status_header, json_resolution::JsonResolution,
+        pass_resolution::PassResolution, semantic::NoContent,
+    },
     routing::{
-        ResolutionFnRef, RouteNodeRef,
-        middleware::{MiddlewareClosure, MiddlewareCollection},
-        router::route_tree::RouteTree,
+        MatchedRoute, ResolutionFnRef, RouteNodeRef,
+        resolution_fn_ref,
+        request::{BodyLimits, HeadLimits, HeadTooLarge, UnsupportedTransferEncoding},
+        route_names::{AddedRoute, RouteNames},
+        middleware::{MiddlewareClosure, MiddlewareCollection, MiddlewareRegistry, Priority},
+        router::{route_node::RouteNode, route_tree::RouteTree},
+        scope::{Scope, scoped},
     },
+    static_files::StaticFiles,
+    stats::{RouteStats, UNMATCHED_ROUTE},
+    telemetry::{self, Span},
+    testing::TestClient,
 };
 
 /// # App
@@ -34,32 +83,164 @@ use crate::web::{
 /// // Check if app was created successfully
 /// ```
 pub struct App {
-    /// The listener used for binding.
-    listener: Option<TcpListener>,
+    /// The listeners used for binding, each accepted by its own loop.
+    listeners: Vec<TcpListener>,
+
+    /// The addresses of the listeners once they are taken by the app task.
+    local_addrs: Vec<SocketAddr>,
 
     /// The router that controls all routes in the App
     router: Arc<Mutex<RouteTree>>,
     //middleware that is applied to all routes called
     global_middleware: Arc<Mutex<Vec<MiddlewareClosure>>>,
 
+    //middleware that routes reference by name
+    middleware_registry: Arc<Mutex<MiddlewareRegistry>>,
+
     //handle to the spawned task
     app_task: Option<JoinHandle<()>>,
 
     // callback to handle errors
-    error_callback: Option<Arc<Pin<Box<dyn Fn(String) -> () + Send + Sync + 'static>>>>,
+    error_callback: Option<ErrorCallback>,
 
     /// Broadcast channel sender to kill the app task
     shutdown: Option<broadcast::Sender<()>>,
 
+    /// The audit log that security relevant events are recorded to.
+    audit_log: Option<AuditLog>,
+
+    /// The cache shared by every request, see `Request::cache`.
+    cache: Cache,
+
+    /// Background tasks spawned by handlers with `Request::spawn`.
+    background: Background,
+
+    /// Converts a panic in middleware or a handler into a resolution.
+    on_panic: Option<Arc<PanicHandler>>,
+
+    /// Counters of each route pattern.
+    route_stats: RouteStats,
+
+    /// Limits on the body of each request.
+    body_limits: BodyLimits,
+
+    /// Limits on the request line and headers of each request.
+    head_limits: HeadLimits,
+
+    /// How long a connection may idle between requests, None closes it after each request.
+    keep_alive: Option<Duration>,
+
+    /// The most requests one connection may make, None for no limit.
+    max_requests_per_connection: Option<usize>,
+
+    /// The connections closed to protect the app.
+    rejections: Rejections,
+
+    /// Cancels the handling of a request when its client disconnects.
+    cancel_on_disconnect: bool,
+
+    /// The start, connection, and shutdown hooks.
+    lifecycle: Lifecycle,
+
+    /// Caps the connections served at once, None accepts every connection.
+    connection_limit: Option<ConnectionLimit>,
+
+    /// The options of the listeners and accepted connections.
+    socket_options: SocketOptions,
+
+    /// The accept loops of each listener.
+    acceptors: usize,
+
+    /// The proxies whose forwarding headers are believed, None ignores the headers.
+    trusted_proxies: Option<TrustedProxies>,
+
+    /// State handlers take with the `State` extractor.
+    state: Arc<StateMap>,
+
+    /// The statuses of error types, see `map_error`.
+    error_map: Arc<ErrorMap>,
+
+    /// The pages of the errors the app generates, see `set_error_pages`.
+    error_pages: Arc<ErrorPages>,
+
+    /// The `Server` header of responses, None sends none, see `set_server_header`.
+    server_header: Option<Arc<str>>,
+
+    /// Mirrors the bytes of connections for debugging, None disables it.
+    debug_tap: Option<DebugTap>,
+
+    /// The audit trail of changes to the routes.
+    route_changes: RouteChangeLog,
+
+    /// The names of routes, see `url_for`.
+    route_names: RouteNames,
+
+    /// The OpenAPI metadata of routes, see `document_route`.
+    #[cfg(feature = "openapi")]
+    route_docs: crate::web::openapi::RouteDocs,
+
+    /// The acceptor used to accept TLS connections, None serves plain HTTP.
+    #[cfg(feature = "tls")]
+    tls: Option<TlsAcceptor>,
+
+    /// How long a client has to complete the TLS handshake, see `set_tls_handshake_timeout`.
+    #[cfg(feature = "tls")]
+    tls_handshake_timeout: Duration,
+
     /// reference to the work manager to control workers.
     work_manager: Arc<Mutex<WorkManager<()>>>,
 
+    /// Catches the panics of the workers, see `set_on_worker_crash`.
+    supervisor: Arc<Supervisor>,
+
+    /// The jobs run on the workers on a schedule, cancelled when the app is closed.
+    scheduler: Scheduler<()>,
+
+    /// Interleaves queued connections by client IP, see `set_fair_queuing`.
+    fair_queuing: bool,
+
+    /// The most connections waiting for a worker and what is done over it, None is unbounded, see `set_queue_capacity`.
+    queue_capacity: Option<(usize, QueuePolicy)>,
+
+    /// How long a connection may wait for a worker before it is answered with `503`, None waits without a bound.
+    queue_timeout: Option<Duration>,
+
+    /// Runs connections on the workers or spawns each of them.
+    execution: ExecutionStrategy,
+
+    /// Reads the requests and writes the responses of connections.
+    engine: Engine,
+
+    /// The buffers responses are written with.
+    buffer_pool: BufferPool,
+
+    /// Writes the startup banner when the app starts, None disables it.
+    startup_banner: Option<Arc<dyn Fn(String) + Send + Sync + 'static>>,
+
     /// Worker Scale Factor
     ///
     /// The factor at which the workers will scale when the workload becomes too intense.
     ///
     /// By default (10)
     pub worker_scale_factor: Arc<Mutex<usize>>,
+
+    /// Grows and shrinks the workers with the load, None disables it.
+    autoscale: Option<AutoscaleConfig>,
+
+    /// handle to the spawned autoscaler task
+    autoscale_task: Option<JoinHandle<()>>,
+
+    /// The watchdog that detects wedged workers, None disables it.
+    watchdog: Option<WatchdogConfig>,
+
+    /// handle to the spawned watchdog task
+    watchdog_task: Option<JoinHandle<()>>,
+
+    /// The latest health snapshot written by the watchdog.
+    worker_health: Arc<Mutex<WorkerHealth>>,
+
+    /// Answers the readiness endpoint without a worker while the workers are wedged, see `serve_readiness`.
+    readiness: ReadinessProbe,
 }
 
 /// Represents a web application where you can bind, route, and do other web server related activities.
@@ -73,6 +254,39 @@ impl App {
         self.global_middleware.lock().await.push(closure);
     }
 
+    /// ## Use Middleware Scoped
+    ///
+    /// Adds middleware that is used for each request in the scope, for example auth that skips `/health` and `/public/{*}`.
+    ///
+    /// See `web::routing::Scope`.
+    pub async fn use_middleware_scoped(&mut self, closure: MiddlewareClosure, scope: Scope) {
+        self.use_middleware(scoped(closure, scope)).await;
+    }
+
+    /// ## Register Middleware
+    ///
+    /// Registers middleware under a name so routes can reference it with `add_route_named`.
+    ///
+    /// Middleware of a route is called in order of priority, lower first. Registering a name again replaces it for every route.
+    ///
+    /// ```ignore
+    /// app.register_middleware("request_id", request_id(), Priority(0)).await;
+    /// app.register_middleware("auth", bearer(tokens), Priority(10)).await;
+    ///
+    /// app.add_route_named("/admin", Method::GET, &["auth", "request_id"], admin).await?;
+    /// ```
+    pub async fn register_middleware(
+        &self,
+        name: &str,
+        closure: MiddlewareClosure,
+        priority: Priority,
+    ) {
+        self.middleware_registry
+            .lock()
+            .await
+            .register(name, closure, priority);
+    }
+
     /// ## Bind
     ///
     /// Binds the program to a Socket via TCP.
@@ -87,54 +301,192 @@ impl App {
     /////try bind socket.
     ///let app_bind = App::bind(SocketAddrV4::new(addr, port)).await;
     /// ```
-    pub async fn bind<A>(addr: A) -> Result<Self, std::io::Error>
+    pub async fn bind<A>(addr: A) -> crate::Result<Self>
     where
         A: ToSocketAddrs,
     {
         //bind our tcp listener to handle request.
         let bind_result = TcpListener::bind(addr).await?;
 
-        let initial_workers_size: usize = 1;
-        let work_manager = Arc::new(Mutex::new(WorkManager::new(initial_workers_size).await));
+        Ok(Self::from_listener(bind_result, 1, SocketOptions::default()).await)
+    }
 
-        let listener = Some(bind_result);
-        let router = Arc::new(Mutex::new(RouteTree::new(None)));
+    /// # From Config
+    ///
+    /// Builds an app from a `.toml` or `.json` config file, see `web::app_builder::AppConfig`, so deployments do not hardcode the address.
+    ///
+    /// Environment variables starting with `ASYNC_WEB_` override the file, `ASYNC_WEB_ADDR=0.0.0.0:80` binds every interface.
+    ///
+    /// ```ignore
+    /// let mut app = App::from_config("app.toml").await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Config` if the file can not be read or parsed, or the error of building the app.
+    pub async fn from_config(path: &str) -> crate::Result<Self> {
+        let config = AppConfig::from_file(path)
+            .await?
+            .override_env(crate::web::app_builder::ENV_PREFIX)?;
 
-        let bind = Self {
-            work_manager,
-            listener,
-            router,
-            global_middleware: Arc::new(Mutex::new(Vec::new())),
-            app_task: None,
-            error_callback: None,
-            shutdown: None,
-            worker_scale_factor: Arc::new(Mutex::new(10)),
-        };
+        Self::builder().config(&config).build().await
+    }
 
-        bind.consume().await;
+    /// # Bind With
+    ///
+    /// Binds the program to a Socket via TCP with the socket options, such as `SO_REUSEPORT` to balance connections between several processes.
+    ///
+    /// The options are also used by `also_bind` and set on every accepted connection.
+    ///
+    /// ```ignore
+    /// let options = SocketOptions { reuse_port: true, nodelay: true, ..Default::default() };
+    /// let mut app = App::bind_with("0.0.0.0:80", options).await?;
+    /// ```
+    pub async fn bind_with<A>(addr: A, options: SocketOptions) -> crate::Result<Self>
+    where
+        A: ToSocketAddrs,
+    {
+        let listener = options.bind(addr).await?;
 
-        Ok(bind)
+        Ok(Self::from_listener(listener, 1, options).await)
     }
 
-    ///  consume
+    /// # Also Bind
     ///
-    /// Spawns a background task that continuously consumes messages from the work manager receiver.
+    /// Binds another address, its connections are served by the same workers and routes, such as both HTTP ports or IPv4 and IPv6.
     ///
-    /// Prevents the internal work channel from filling and blocking producers.
+    /// IPv6 addresses bound with this only accept IPv6, so `[::]:80` may be bound next to `0.0.0.0:80`.
     ///
-    /// Runs until the receiver channel is closed.
-    async fn consume(&self) -> JoinHandle<()> {
-        let receiver = {
-            let guard = self.work_manager.lock().await;
+    /// ```ignore
+    /// let mut app = App::bind("0.0.0.0:80").await?;
+    /// app.also_bind("[::]:80").await?;
+    /// ```
+    ///
+    /// This MUST be set before you start the app.
+    pub async fn also_bind<A>(&mut self, addr: A) -> crate::Result<()>
+    where
+        A: ToSocketAddrs,
+    {
+        for addr in tokio::net::lookup_host(addr).await? {
+            self.listeners.push(self.socket_options.listen(addr)?);
+        }
 
-            guard.receiver.clone()
-        };
+        Ok(())
+    }
+
+    /// # Local Addr
+    ///
+    /// The address the app is bound to, the port the system assigned when binding to port `0`.
+    ///
+    /// ```ignore
+    /// let app = App::bind("127.0.0.1:0").await?;
+    /// let port = app.local_addr()?.port();
+    /// ```
+    pub fn local_addr(&self) -> Result<SocketAddr, std::io::Error> {
+        self.local_addrs().first().copied().ok_or(std::io::Error::new(
+            std::io::ErrorKind::NotConnected,
+            "the app is not bound to an address",
+        ))
+    }
 
-        task::spawn(async move {
-            let mut rx = receiver.lock().await;
+    /// The addresses of every listener of the app, see `also_bind`.
+    pub fn local_addrs(&self) -> Vec<SocketAddr> {
+        match self.listeners.is_empty() {
+            true => self.local_addrs.clone(),
+            false => self
+                .listeners
+                .iter()
+                .filter_map(|listener| listener.local_addr().ok())
+                .collect(),
+        }
+    }
 
-            while let Some(_) = rx.recv().await {}
-        })
+    /// # Builder
+    ///
+    /// Configures an app before binding it, see [`AppBuilder`].
+    ///
+    /// ```ignore
+    /// let mut app = App::builder()
+    ///     .addr("0.0.0.0:8080")
+    ///     .workers(100)
+    ///     .keep_alive(Duration::from_secs(5))
+    ///     .build()
+    ///     .await?;
+    /// ```
+    pub fn builder() -> AppBuilder {
+        AppBuilder::new()
+    }
+
+    /// creates the app serving the listener, starting with the workers.
+    pub(crate) async fn from_listener(
+        listener: TcpListener,
+        workers: usize,
+        socket_options: SocketOptions,
+    ) -> Self {
+        let work_manager = WorkManager::new(workers).await;
+        let supervisor = work_manager.supervisor();
+        let work_manager = Arc::new(Mutex::new(work_manager));
+
+        let listeners = vec![listener];
+        let router = Arc::new(Mutex::new(RouteTree::new(None)));
+        let worker_health = Arc::new(Mutex::new(WorkerHealth::default()));
+
+        Self {
+            supervisor,
+            scheduler: Scheduler::new(work_manager.clone()),
+            work_manager,
+            fair_queuing: false,
+            queue_capacity: None,
+            queue_timeout: None,
+            execution: ExecutionStrategy::WorkerPool,
+            engine: Engine::Native,
+            buffer_pool: BufferPool::default(),
+            startup_banner: None,
+            listeners,
+            local_addrs: Vec::new(),
+            router,
+            global_middleware: Arc::new(Mutex::new(Vec::new())),
+            middleware_registry: Arc::new(Mutex::new(MiddlewareRegistry::new())),
+            app_task: None,
+            error_callback: None,
+            shutdown: None,
+            audit_log: None,
+            cache: Cache::default(),
+            background: Background::new(),
+            on_panic: None,
+            route_stats: RouteStats::default(),
+            body_limits: BodyLimits::default(),
+            head_limits: HeadLimits::default(),
+            keep_alive: None,
+            max_requests_per_connection: Some(1000),
+            rejections: Rejections::new(),
+            cancel_on_disconnect: true,
+            lifecycle: Lifecycle::new(),
+            connection_limit: None,
+            socket_options,
+            acceptors: 1,
+            trusted_proxies: None,
+            state: Arc::new(StateMap::new()),
+            error_map: Arc::new(ErrorMap::new()),
+            error_pages: Arc::new(ErrorPages::new()),
+            server_header: Some(Arc::from(DEFAULT_SERVER)),
+            debug_tap: None,
+            route_changes: RouteChangeLog::default(),
+            route_names: RouteNames::new(),
+            #[cfg(feature = "openapi")]
+            route_docs: crate::web::openapi::RouteDocs::new(),
+            #[cfg(feature = "tls")]
+            tls: None,
+            #[cfg(feature = "tls")]
+            tls_handshake_timeout: Duration::from_secs(10),
+            worker_scale_factor: Arc::new(Mutex::new(10)),
+            autoscale: None,
+            autoscale_task: None,
+            watchdog: None,
+            watchdog_task: None,
+            readiness: ReadinessProbe::new(worker_health.clone()),
+            worker_health,
+        }
     }
 
     /// # Start
@@ -145,108 +497,123 @@ impl App {
     ///
     /// This function returns:
     ///
-    /// Err(AppState::Running) if the application was already running
-    /// Err(AppState::Closed) if the application was closed
+    /// Err(Error::State(AppState::Running)) if the application was already running
+    /// Err(Error::State(AppState::Closed)) if the application was closed
     /// or
     ///
-    /// Ok(AppState::Running) if the application was started successfully.
-    pub fn start(&mut self) -> Result<AppState, AppState> {
+    /// Ok(AppHandle) if the application was started successfully, the handle joins the accept loops of every listener.
+    pub fn start(&mut self) -> crate::Result<AppHandle> {
         if self.app_task.is_some() {
-            return Err(AppState::Running);
+            return Err(AppState::Running.into());
         }
 
         //err cannot start.
-        if self.listener.is_none() {
-            return Err(AppState::Closed);
+        if self.listeners.is_empty() {
+            return Err(AppState::Closed.into());
         }
 
         // create reference clones to each thing passed to the opened task
         let work_manager = self.work_manager.clone();
-        let router = self.router.clone();
-        let global_middleware = self.global_middleware.clone();
+        let context = self.context();
 
         //error call back clone
         let error_callback = self.error_callback.as_ref().map(|cb| cb.clone());
 
-        //listener
-        let listener = self.listener.take().unwrap();
+        //listeners
+        let addresses = self.local_addrs();
+        let listeners = std::mem::take(&mut self.listeners);
+        self.local_addrs = addresses.clone();
 
         //shutdown sender/receiver.
-        let (shutdown_tx, mut shutdown_rx) = broadcast::channel(1);
-        self.shutdown = Some(shutdown_tx);
+        let (shutdown_tx, _) = broadcast::channel(1);
+        let (finished_tx, finished_rx) = watch::channel(false);
 
         //scaling
         let scale_factor_clone = self.worker_scale_factor.clone();
+        let fair_queuing = self.fair_queuing;
+        let queue_capacity = self.queue_capacity;
 
-        //add the app_task
-        self.app_task = Some(task::spawn(async move {
-            //create a default callback if none.
-            let error_callback = error_callback.unwrap_or(Arc::new(Box::pin(|_| {})));
+        //hooks
+        let lifecycle = self.lifecycle.clone();
 
-            loop {
-                tokio::select! {
-                    _ = shutdown_rx.recv() => {
-                        break;
-                    },
-                    accepted_client = listener.accept() => {
+        //banner
+        let banner = self.startup_banner.clone().map(|logger| {
+            let router = self.router.clone();
+            let global_middleware = self.global_middleware.clone();
 
-                        //failed to accept the client send the error to the callback
-                        if let Err(e) = accepted_client {
-                            error_callback(e.to_string());
-                            continue;
-                        }
+            (logger, addresses.clone(), router, global_middleware)
+        });
+
+        if let Some(config) = &self.autoscale {
+            self.autoscale_task = Some(autoscale::spawn(work_manager.clone(), config.clone()));
+        }
 
-                        //get refs for the worker.
-                        let router_ref = router.clone();
-                        let middleware_ref = global_middleware.clone();
-                        let error_callback = error_callback.clone();
+        if let Some(config) = &self.watchdog {
+            self.watchdog_task = Some(watchdog::spawn(
+                work_manager.clone(),
+                config.clone(),
+                self.worker_health.clone(),
+            ));
+        }
 
-                        //get work that needs to be completed.
-                        let mut current_work = Box::pin(
-                            async move {
+        //each listener is accepted by its own loops, all of them feed the same workers.
+        let mut accept_loops = Vec::with_capacity(listeners.len() * self.acceptors);
+
+        for listener in listeners.into_iter().map(Arc::new) {
+            for _ in 0..self.acceptors {
+                accept_loops.push(accept_loop(
+                    listener.clone(),
+                    shutdown_tx.subscribe(),
+                    context.clone(),
+                    work_manager.clone(),
+                    scale_factor_clone.clone(),
+                    error_callback.clone(),
+                ));
+            }
+        }
 
-                                //handle the client request
-                                let completed_work =
-                                    handle_client_request(accepted_client.unwrap(), middleware_ref, router_ref).await;
+        self.shutdown = Some(shutdown_tx);
 
-                                //handle any errors
-                                if let Err(e) = completed_work {
-                                    error_callback(e.to_string());
-                                }
-                            }
-                        ) as Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+        let started_addresses = addresses.clone();
 
-                        //loop, needed to ensure that work is queued properly. please see below
-                        loop {
+        //add the app_task
+        self.app_task = Some(task::spawn(async move {
+            work_manager.lock().await.set_fair_queuing(fair_queuing);
 
-                            //lock the work managet
-                            let mut work_manager = work_manager.lock().await;
+            if let Some((capacity, policy)) = queue_capacity {
+                work_manager.lock().await.set_queue_capacity(Some(capacity), policy).await;
+            }
 
-                            //queue some work
-                            match work_manager.queue_work(current_work).await {
-                                crate::factory::queue::QueueState::Free => break, //work was successfully added to the queue (enough workers)
-                                crate::factory::queue::QueueState::Blocked(returned_work) => { //the queue was blocked (no workers) this gives us back the work that was not queued.
-                                    current_work = returned_work;
+            if let Some((logger, addresses, router, global_middleware)) = banner {
+                let banner = collect_banner(
+                    addresses,
+                    &router,
+                    &global_middleware,
+                    &work_manager,
+                    *scale_factor_clone.lock().await,
+                    fair_queuing,
+                )
+                .await;
+
+                logger(banner.to_string());
+            }
 
-                                    //scale our worker count.
-                                    let scale_factor = *scale_factor_clone.lock().await;
-                                    work_manager.scale_workers(scale_factor).await;
+            for local_addr in started_addresses {
+                lifecycle.started(local_addr).await;
+            }
 
-                                    drop(work_manager);
+            //spawned so the loops accept on every thread of the runtime.
+            let accept_loops = accept_loops.into_iter().map(task::spawn);
 
-                                    //hand control back to the async controller.
-                                    tokio::task::yield_now().await;
-                                }
-                            };
+            //the listeners are closed once their loops end, no connection is accepted during the hooks.
+            futures::future::join_all(accept_loops).await;
 
+            lifecycle.shutting_down().await;
 
-                        }
-                    }
-                }
-            }
+            let _ = finished_tx.send(true);
         }));
 
-        Ok(AppState::Running)
+        Ok(AppHandle::new(addresses, finished_rx))
     }
 
     /// # close
@@ -259,26 +626,59 @@ impl App {
     ///
     /// This function returns:
     ///
-    /// `Err(AppState::Closed)` if the application was already closed
+    /// `Err(Error::State(AppState::Closed))` if the application was already closed
     ///
     /// or
     ///
     /// `Ok(AppState::Closed)` if the application was closed.
-    pub async fn close(&mut self) -> Result<AppState, AppState> {
+    pub async fn close(&mut self) -> crate::Result<AppState> {
         if self.app_task.is_none() {
-            return Err(AppState::Closed);
+            return Err(AppState::Closed.into());
         }
 
         let task = self.app_task.take().unwrap();
 
+        if let Some(watchdog_task) = self.watchdog_task.take() {
+            watchdog_task.abort();
+        }
+
+        if let Some(autoscale_task) = self.autoscale_task.take() {
+            autoscale_task.abort();
+        }
+
         let closure = self.shutdown.take().unwrap();
         let _ = closure.send(());
 
         let _ = task.await;
 
+        self.scheduler.cancel_all();
+
+        //cancel the tasks spawned by handlers and wait for them to stop.
+        self.background.cancel();
+        self.background.wait().await;
+
         Ok(AppState::Closed)
     }
 
+    /// # Close And Drain
+    ///
+    /// Closes the web app, then lets the workers finish the connections that were accepted for at most the timeout, see `WorkManager::drain`.
+    ///
+    /// ```ignore
+    /// let report = app.close_and_drain(Duration::from_secs(30)).await?;
+    ///
+    /// if !report.is_clean() {
+    ///     eprintln!("{} connections were abandoned", report.abandoned + report.running);
+    /// }
+    /// ```
+    ///
+    /// Returns `Err(Error::State(AppState::Closed))` if the application was already closed.
+    pub async fn close_and_drain(&mut self, timeout: Duration) -> crate::Result<DrainReport> {
+        self.close().await?;
+
+        Ok(self.work_manager.lock().await.drain(timeout).await)
+    }
+
     /// # close
     ///
     /// Closes the web app but does not join with the app handle to ensure that the app was closed.
@@ -293,13 +693,25 @@ impl App {
     ///
     /// Ok(()) if the app successfully sent a notification to the app thread to stop.
     ///
-    /// Err(AppState) if the app was already closed OR if the app failed to send a notification to stop the app thread.
-    pub fn close_unchecked(&mut self) -> Result<(), AppState> {
+    /// Err(Error::State) if the app was already closed OR if the app failed to send a notification to stop the app thread.
+    pub fn close_unchecked(&mut self) -> crate::Result<()> {
         if self.app_task.is_none() {
-            return Err(AppState::Closed);
+            return Err(AppState::Closed.into());
         }
 
         let _ = self.app_task.take();
+
+        self.scheduler.cancel_all();
+        self.background.cancel();
+
+        if let Some(watchdog_task) = self.watchdog_task.take() {
+            watchdog_task.abort();
+        }
+
+        if let Some(autoscale_task) = self.autoscale_task.take() {
+            autoscale_task.abort();
+        }
+
         let _ = self
             .shutdown
             .take()
@@ -316,243 +728,2125 @@ impl App {
     ///
     /// # Errors
     ///
-    /// Returns a `RoutingError` if the route cannot be added.
-
+    /// Returns `Error::Routing` if the route cannot be added.
     pub async fn add_or_change_route<F, Fut>(
         &self,
         route: &str,
         method: Method,
         middleware: Option<MiddlewareCollection>,
         resolution: F,
-    ) -> Result<(), RoutingError>
+    ) -> crate::Result<()>
     where
         F: Fn(Arc<Mutex<Request>>) -> Fut + Send + Sync + 'static,
-        Fut: Future<Output = Box<dyn Resolution + Send + 'static>> + Send + 'static,
+        Fut: Future<Output: IntoResolution> + Send + 'static,
     {
-        let resolution: ResolutionFnRef =
-            Arc::new(move |req: Arc<Mutex<Request>>| Box::pin(resolution(req)));
+        let resolution = resolution_fn_ref(resolution);
 
         let endpoint = EndPoint::new(resolution, middleware);
 
         let mut router = self.router.lock().await;
-        router.add_route(route, Some((method, endpoint))).await
+
+        let existed = has_resolution(&router, route, &method).await;
+        let change = describe_route(&method, route);
+
+        router.add_route(route, Some((method, endpoint))).await?;
+        drop(router);
+
+        let (added, replaced) = match existed {
+            true => (Vec::new(), vec![change]),
+            false => (vec![change], Vec::new()),
+        };
+
+        self.route_changes
+            .record(RouteChangeKind::Route, added, replaced, Vec::new())
+            .await;
+
+        Ok(())
     }
 
-    /// Adds a new route or replaces an existing route’s resolution for the given method.
+    /// Adds a new route for the given method.
     ///
-    /// If the route already exists, its resolution for the specified method is overwritten.
+    /// The added route is returned so it may be named, see `AddedRoute::name`.
+    ///
+    /// ```ignore
+    /// app.add_route("/users/{id}", Method::GET, None, user_detail)
+    ///     .await?
+    ///     .name("user_detail")?;
+    /// ```
     ///
     /// # Errors
     ///
-    /// Returns a `RoutingError` if the route cannot be added.
+    /// Returns `Error::Routing` if the route already exists or cannot be added.
     pub async fn add_route<F, Fut>(
         &self,
         route: &str,
         method: Method,
         middleware: Option<MiddlewareCollection>,
         resolution: F,
-    ) -> Result<(), RoutingError>
+    ) -> crate::Result<AddedRoute>
     where
         F: Fn(Arc<Mutex<Request>>) -> Fut + Send + Sync + 'static,
-        Fut: Future<Output = Box<dyn Resolution + Send + 'static>> + Send + 'static,
+        Fut: Future<Output: IntoResolution> + Send + 'static,
     {
+        self.add_route_fn(route, method, middleware, resolution_fn_ref(resolution))
+            .await
+    }
+
+    /// # Add Route Fn
+    ///
+    /// Adds a route whose resolution borrows the request instead of locking it, see `routing::resolution_fn`.
+    ///
+    /// ```ignore
+    /// app.add_route_fn("/hello", Method::GET, None, resolution_fn(|req| Box::pin(async move {
+    ///     let name = req.variables.get("name").cloned().unwrap_or_default();
+    ///     format!("hello {name}")
+    /// })))
+    /// .await?;
+    /// ```
+    ///
+    /// The added route is returned so it may be named, see `AddedRoute::name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Routing` if the route already exists or cannot be added.
+    pub async fn add_route_fn(
+        &self,
+        route: &str,
+        method: Method,
+        middleware: Option<MiddlewareCollection>,
+        resolution: ResolutionFnRef,
+    ) -> crate::Result<AddedRoute> {
         let mut router = self.router.lock().await;
 
-        if let Some(rte) = router.get_route(route).await {
-            if rte.lock().await.brw_resolution(&method).is_some() {
-                return Err(RoutingError::Exist);
-            }
+        if has_resolution(&router, route, &method).await {
+            return Err(RoutingError::Exist.into());
         }
 
-        let resolution: ResolutionFnRef =
-            Arc::new(move |req: Arc<Mutex<Request>>| Box::pin(resolution(req)));
-
+        let change = describe_route(&method, route);
         let endpoint = EndPoint::new(resolution, middleware);
         let route_res = Some((method, endpoint));
 
-        router.add_route(route, route_res).await
+        router.add_route(route, route_res).await?;
+        drop(router);
+
+        self.route_changes
+            .record(RouteChangeKind::Route, vec![change], Vec::new(), Vec::new())
+            .await;
+
+        Ok(AddedRoute::new(self.route_names.clone(), route))
     }
 
-    /// Adds a route and method combination to the router.
+    /// Adds a route whose middleware is referenced by name, see `register_middleware`.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the route already exists or cannot be added.
-    /// Intended for use during application initialization.
-
-    pub async fn add_or_panic<F, Fut>(
+    /// Returns `Error::Routing` if the route already exists, a name is not registered, or the route cannot be added.
+    pub async fn add_route_named<F, Fut>(
         &self,
         route: &str,
         method: Method,
-        middleware: Option<MiddlewareCollection>,
+        middleware_names: &[&str],
         resolution: F,
-    ) -> ()
+    ) -> crate::Result<AddedRoute>
     where
         F: Fn(Arc<Mutex<Request>>) -> Fut + Send + Sync + 'static,
-        Fut: Future<Output = Box<dyn Resolution + Send + 'static>> + Send + 'static,
+        Fut: Future<Output: IntoResolution> + Send + 'static,
     {
-        let result = self.add_route(route, method, middleware, resolution).await;
+        let names = self.registered_names(middleware_names).await?;
 
-        if let Err(e) = result {
-            panic!("When adding route '{route}' an error occurred because '{e}'");
+        let mut router = self.router.lock().await;
+
+        if has_resolution(&router, route, &method).await {
+            return Err(RoutingError::Exist.into());
         }
-    }
 
-    /// Provides exclusive access to the internal route tree.
-    ///
-    /// Returns a locked guard allowing inspection or modification of routing state.
-    /// This call blocks until the router mutex becomes available.
+        let resolution = resolution_fn_ref(resolution);
 
-    pub async fn get_router(&self) -> MutexGuard<'_, RouteTree> {
-        self.router.lock().await
+        let change = describe_route(&method, route);
+        let endpoint = EndPoint::new(resolution, None).with_named_middleware(names);
+
+        router.add_route(route, Some((method, endpoint))).await?;
+        drop(router);
+
+        self.route_changes
+            .record(RouteChangeKind::Route, vec![change], Vec::new(), Vec::new())
+            .await;
+
+        Ok(AddedRoute::new(self.route_names.clone(), route))
     }
 
-    /// # Set Error callback
+    /// Adds a route whose handler takes extractors as arguments instead of the request, see `web::extract`.
     ///
-    /// Sets the error callback using a FN closure.
+    /// ```ignore
+    /// app.add_handler("/users/{id}", Method::GET, None, |Path(id): Path<u64>, State(db): State<Db>| async move {
+    ///     JsonResolution::serialize(db.user(id).await).unwrap().resolve()
+    /// })
+    /// .await?;
+    /// ```
     ///
-    /// This error callback is used for the App task handle. Allowing you to control how errors are displayed to the user.
+    /// # Errors
     ///
-    /// This MUST be set before you start the app.
-    pub fn set_error_callback(&mut self, callback: impl Fn(String) -> () + Send + Sync + 'static) {
-        //pin the callback for the error.
-        let callback: Arc<Pin<Box<dyn Fn(String) -> () + Send + Sync>>> =
-            Arc::new(Box::pin(callback));
-        self.error_callback = Some(callback);
+    /// Returns `Error::Routing` if the route already exists or cannot be added.
+    pub async fn add_handler<H, Args>(
+        &self,
+        route: &str,
+        method: Method,
+        middleware: Option<MiddlewareCollection>,
+        handler: H,
+    ) -> crate::Result<AddedRoute>
+    where
+        H: IntoHandler<Args>,
+    {
+        self.add_route_fn(route, method, middleware, handler.into_handler())
+            .await
     }
 
-    /// # state
+    /// # Add Route Chain
     ///
-    /// Get the state of the application.
-    pub fn state(&self) -> AppState {
-        match &self.app_task {
-            None => AppState::Closed,
-            _ => AppState::Running,
+    /// Adds a route whose resolutions are tried in order, the next is called when the previous passes by returning a `PassResolution` (or None, see `IntoResolution`).
+    ///
+    /// The client receives a `404` if every resolution passes.
+    ///
+    /// ```ignore
+    /// app.add_route_chain("/assets/{*}", Method::GET, None, vec![
+    ///     resolution_fn_ref(from_disk_cache),
+    ///     resolution_fn_ref(generate),
+    ///     resolution_fn_ref(proxy_upstream),
+    /// ])
+    /// .await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Routing` if the chain is empty, the route already exists, or the route cannot be added.
+    pub async fn add_route_chain(
+        &self,
+        route: &str,
+        method: Method,
+        middleware: Option<MiddlewareCollection>,
+        resolutions: Vec<ResolutionFnRef>,
+    ) -> crate::Result<AddedRoute> {
+        let mut resolutions = resolutions.into_iter();
+
+        let Some(first) = resolutions.next() else {
+            return Err(RoutingError::InvalidRoute(
+                "a route chain needs at least one resolution".to_string(),
+            )
+            .into());
+        };
+
+        let mut router = self.router.lock().await;
+
+        if has_resolution(&router, route, &method).await {
+            return Err(RoutingError::Exist.into());
         }
-    }
-}
 
-impl Drop for App {
-    /// Drops when the application goes out of scope. This is equivalent to calling (&mut self).close
-    fn drop(&mut self) {
-        let _ = self.close();
-    }
-}
+        let change = describe_route(&method, route);
+        let endpoint = EndPoint::new(first, middleware).with_fallbacks(resolutions.collect());
 
-/// Extracts dynamic route parameters from the matched route tree.
+        router.add_route(route, Some((method, endpoint))).await?;
+        drop(router);
+
+        self.route_changes
+            .record(RouteChangeKind::Route, vec![change], Vec::new(), Vec::new())
+            .await;
+
+        Ok(AddedRoute::new(self.route_names.clone(), route))
+    }
+
+    /// # Cache Route
+    ///
+    /// Sends fresh responses of an existing route and method from the cache, before its middleware and handler are called, see `web::response_cache::ResponseCache`.
+    ///
+    /// ```ignore
+    /// app.cache_route("/alt", Method::GET, ResponseCache::new(Duration::from_secs(300))).await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `RoutingError::Missing` if the route does not exist and `RoutingError::MethodMissing` if it has no resolution for the method.
+    pub async fn cache_route(
+        &self,
+        route: &str,
+        method: Method,
+        cache: ResponseCache,
+    ) -> crate::Result<()> {
+        self.change_endpoint(route, method, |endpoint| endpoint.with_response_cache(cache))
+            .await
+    }
+
+    /// # Guard Route
+    ///
+    /// Checks the requests of an existing route and method with the guards before its middleware and handler are called, see `web::guards::RouteGuards`. Requests failing a check are answered with `413`, `415`, or `428`.
+    ///
+    /// ```ignore
+    /// app.guard_route("/upload", Method::POST, RouteGuards::new()
+    ///     .max_body(10 * 1024 * 1024)
+    ///     .content_types(&["image/jpeg", "image/png"]))
+    ///     .await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `RoutingError::Missing` if the route does not exist and `RoutingError::MethodMissing` if it has no resolution for the method.
+    pub async fn guard_route(
+        &self,
+        route: &str,
+        method: Method,
+        guards: RouteGuards,
+    ) -> crate::Result<()> {
+        self.change_endpoint(route, method, |endpoint| endpoint.with_guards(guards))
+            .await
+    }
+
+    /// # Timeout Route
+    ///
+    /// Serves `status` when the middleware and the handler of an existing route and method do not complete within `duration`, for example `504` or `503`.
+    ///
+    /// Unlike `web::timeout`, which only races the handler, slow middleware is cut off as well.
+    ///
+    /// ```ignore
+    /// app.timeout_route("/report", Method::GET, Duration::from_secs(5), 504).await?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `RoutingError::Missing` if the route does not exist and `RoutingError::MethodMissing` if it has no resolution for the method.
+    pub async fn timeout_route(
+        &self,
+        route: &str,
+        method: Method,
+        duration: Duration,
+        status: impl Into<StatusCode>,
+    ) -> crate::Result<()> {
+        let status = status.into();
+
+        self.change_endpoint(route, method, |endpoint| endpoint.with_timeout(duration, status))
+            .await
+    }
+
+    /// replaces the endpoint of an existing route and method with a changed copy, recorded as a replaced route.
+    async fn change_endpoint(
+        &self,
+        route: &str,
+        method: Method,
+        change: impl FnOnce(EndPoint) -> EndPoint,
+    ) -> crate::Result<()> {
+        let node = self
+            .router
+            .lock()
+            .await
+            .get_route(route)
+            .await
+            .ok_or(RoutingError::Missing)?;
+
+        let mut node = node.lock().await;
+
+        let endpoint = node
+            .brw_resolution(&method)
+            .ok_or(RoutingError::MethodMissing)?;
+
+        let changed = change(EndPoint::clone(&endpoint));
+        let replaced = describe_route(&method, route);
+
+        node.insert_resolution(method, changed);
+        drop(node);
+
+        self.route_changes
+            .record(RouteChangeKind::Route, Vec::new(), vec![replaced], Vec::new())
+            .await;
+
+        Ok(())
+    }
+
+    /// checks that each name is registered.
+    async fn registered_names(&self, names: &[&str]) -> Result<Vec<String>, RoutingError> {
+        let registry = self.middleware_registry.lock().await;
+
+        names
+            .iter()
+            .map(|name| match registry.contains(name) {
+                true => Ok(name.to_string()),
+                false => Err(RoutingError::UnknownMiddleware(name.to_string())),
+            })
+            .collect()
+    }
+
+    /// Adds a route and method combination to the router.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the route already exists or cannot be added.
+    /// Intended for use during application initialization.
+    pub async fn add_or_panic<F, Fut>(
+        &self,
+        route: &str,
+        method: Method,
+        middleware: Option<MiddlewareCollection>,
+        resolution: F,
+    ) where
+        F: Fn(Arc<Mutex<Request>>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output: IntoResolution> + Send + 'static,
+    {
+        let result = self.add_route(route, method, middleware, resolution).await;
+
+        if let Err(e) = result {
+            panic!("When adding route '{route}' an error occurred because '{e}'");
+        }
+    }
+
+    /// # Name Route
+    ///
+    /// Names a route pattern, so its path is built with `url_for`, for routes added by functions that do not return an `AddedRoute`.
+    ///
+    /// ```ignore
+    /// app.add_or_change_route("/users/{id}", Method::GET, None, user_detail).await?;
+    /// app.name_route("user_detail", "/users/{id}")?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Routing` if the name is given to another route.
+    pub fn name_route(&self, name: &str, route: &str) -> crate::Result<()> {
+        Ok(self.route_names.insert(name, route)?)
+    }
+
+    /// # Url For
+    ///
+    /// Builds the path of a named route from its pattern, so redirects and links follow the router, see `RouteNames::url_for`.
+    ///
+    /// Handlers build paths with `Request::url_for`.
+    ///
+    /// ```ignore
+    /// app.add_route("/users/{id}", Method::GET, None, user_detail).await?.name("user_detail")?;
+    ///
+    /// assert_eq!(app.url_for("user_detail", &[("id", "42")])?, "/users/42");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Routing` if no route has the name or a variable of its pattern has no param.
+    pub fn url_for(&self, name: &str, params: &[(&str, &str)]) -> crate::Result<String> {
+        Ok(self.route_names.url_for(name, params)?)
+    }
+
+    /// Provides exclusive access to the internal route tree.
+    ///
+    /// Returns a locked guard allowing inspection or modification of routing state.
+    /// This call blocks until the router mutex becomes available.
+
+    pub async fn get_router(&self) -> MutexGuard<'_, RouteTree> {
+        self.router.lock().await
+    }
+
+    /// # Swap Router
+    ///
+    /// Replaces every route at once with the routes of the tree, including its missing route, returning the replaced tree.
+    ///
+    /// Requests that were already routed finish with their old endpoint, later requests only see the new routes, so a reloaded config or a blue/green deploy never serves a partial set of routes.
+    ///
+    /// ```ignore
+    /// let mut green = RouteTree::new(None);
+    /// green.add_route("/users", Some((Method::GET, EndPoint::new(list_users, None)))).await?;
+    ///
+    /// let blue = app.swap_router(green).await;
+    /// ```
+    pub async fn swap_router(&self, router: RouteTree) -> RouteTree {
+        let describe = |routes: Vec<RouteSummary>| -> Vec<String> {
+            routes
+                .iter()
+                .map(|r| describe_route(&r.method, &r.pattern))
+                .collect()
+        };
+
+        let incoming = describe(Banner::routes(&router).await);
+
+        let mut current = self.router.lock().await;
+        let outgoing = describe(Banner::routes(&current).await);
+        let replaced_tree = std::mem::replace(&mut *current, router);
+        drop(current);
+
+        let (replaced, added) = incoming
+            .into_iter()
+            .partition::<Vec<_>, _>(|route| outgoing.contains(route));
+        let removed = outgoing
+            .into_iter()
+            .filter(|route| !replaced.contains(route))
+            .collect();
+
+        self.route_changes
+            .record(RouteChangeKind::Swap, added, replaced, removed)
+            .await;
+
+        replaced_tree
+    }
+
+    /// # Set Error callback
+    ///
+    /// Sets the error callback using a FN closure.
+    ///
+    /// This error callback is used for the App task handle. Allowing you to control how errors are displayed to the user.
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_error_callback(&mut self, callback: impl Fn(String) -> () + Send + Sync + 'static) {
+        //pin the callback for the error.
+        let callback: Arc<Pin<Box<dyn Fn(String) -> () + Send + Sync>>> =
+            Arc::new(Box::pin(callback));
+        self.error_callback = Some(callback);
+    }
+
+    /// # Set Audit Log
+    ///
+    /// Sets the audit log that security relevant events are recorded to, see `web::audit`.
+    ///
+    /// The log is placed in each request's extensions so middleware may record to it, and 4xx bursts per client are recorded automatically.
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_audit_log(&mut self, audit_log: AuditLog) {
+        self.audit_log = Some(audit_log);
+    }
+
+    /// # Set On Panic
+    ///
+    /// Sets the handler that converts a panic in middleware or a handler into the response, by default an empty `500` is sent.
+    ///
+    /// The handler receives the panic message, the worker keeps serving requests after a panic. Each panic is also logged with the method and route it happened in (as a `tracing` error with the feature, to stderr without it).
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_on_panic(
+        &mut self,
+        handler: impl Fn(String) -> Box<dyn Resolution + Send> + Send + Sync + 'static,
+    ) {
+        self.on_panic = Some(Arc::new(handler));
+    }
+
+    /// # Add State
+    ///
+    /// Adds state handlers take with the `web::extract::State` extractor, such as a database pool. State is cloned for each handler, share it with an `Arc`.
+    ///
+    /// Adding state of the same type again replaces it.
+    ///
+    /// This MUST be set before you start the app.
+    pub fn add_state<T: Clone + Send + Sync + 'static>(&mut self, value: T) {
+        Arc::make_mut(&mut self.state).insert(value);
+    }
+
+    /// # Map Error
+    ///
+    /// Sends error resolutions of errors of type `E` with the status instead of `500`, see `web::error_map::ErrorMap`.
+    ///
+    /// ```ignore
+    /// app.map_error::<serde_json::Error>(400);
+    /// app.map_error::<RoutingError>(StatusCode::NOT_FOUND);
+    /// ```
+    ///
+    /// This MUST be set before you start the app.
+    pub fn map_error<E: std::error::Error + 'static>(&mut self, status: impl Into<StatusCode>) {
+        Arc::make_mut(&mut self.error_map).map::<E>(status);
+    }
+
+    /// # Set Error Debug
+    ///
+    /// Includes the debug output, sources, and a backtrace of the error in JSON error resolutions. Do not turn it on in production.
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_error_debug(&mut self, debug: bool) {
+        Arc::make_mut(&mut self.error_map).set_debug(debug);
+    }
+
+    /// # Set Problem Details
+    ///
+    /// Sends JSON error resolutions as `application/problem+json` (RFC 9457), see `resolution::problem_details::ProblemDetails`.
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_problem_details(&mut self, problem_details: bool) {
+        Arc::make_mut(&mut self.error_map).set_problem_details(problem_details);
+    }
+
+    /// # Set Error Pages
+    ///
+    /// Sets the pages sent for the errors the app generates itself, such as `404` and `413`, see [`ErrorPages`].
+    ///
+    /// By default (no pages) these errors are sent with an empty body.
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_error_pages(&mut self, pages: ErrorPages) {
+        self.error_pages = Arc::new(pages);
+    }
+
+    /// # Set Server Header
+    ///
+    /// Sets the `Server` header of responses, None sends no `Server` header so the software is not revealed. A `Server` set by a resolution or middleware is kept.
+    ///
+    /// By default (`async-web`) responses identify the crate without its version.
+    ///
+    /// Every response has a `Date` header either way, see `http_date::now`.
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_server_header(&mut self, server: Option<&str>) {
+        self.server_header = server.map(Arc::from);
+    }
+
+    /// # Set Debug Tap
+    ///
+    /// Mirrors the raw bytes of requests and responses to the sink of the tap, see `web::debug_tap`.
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_debug_tap(&mut self, tap: DebugTap) {
+        self.debug_tap = Some(tap);
+    }
+
+    /// # Set Fair Queuing
+    ///
+    /// Interleaves queued connections by client IP, so a client flooding the server does not occupy the whole queue and other clients keep being served.
+    ///
+    /// By default connections are served in the order they were accepted.
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_fair_queuing(&mut self, fair: bool) {
+        self.fair_queuing = fair;
+    }
+
+    /// # Set Queue Capacity
+    ///
+    /// Bounds the connections waiting for a worker, so a flood of connections is not buffered forever.
+    ///
+    /// A full queue blocks accepting with `QueuePolicy::Block`, drops the connection that waited longest with `QueuePolicy::DropOldest`, or answers the new connection with `503` with `QueuePolicy::Reject`.
+    ///
+    /// A bounded queue holds connections instead of growing the workers by the worker scale factor, grow them with `set_autoscale`.
+    ///
+    /// ```ignore
+    /// app.set_queue_capacity(Some(1024), QueuePolicy::Reject);
+    /// ```
+    ///
+    /// By default (None) the queue is unbounded.
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_queue_capacity(&mut self, capacity: Option<usize>, policy: QueuePolicy) {
+        self.queue_capacity = capacity.map(|capacity| (capacity, policy));
+    }
+
+    /// # Set Queue Timeout
+    ///
+    /// Answers connections that waited longer than the timeout for a worker with `503` instead of serving them, so a stale backlog is not served long after its clients gave up. TLS clients are closed without a response.
+    ///
+    /// ```ignore
+    /// app.set_queue_timeout(Some(Duration::from_secs(5)));
+    /// ```
+    ///
+    /// By default (None) connections wait until a worker takes them.
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_queue_timeout(&mut self, timeout: Option<Duration>) {
+        self.queue_timeout = timeout;
+    }
+
+    /// # Set Execution
+    ///
+    /// Runs connections on the workers, or spawns a task for each connection for lower latency, optionally bounded, see [`ExecutionStrategy`].
+    ///
+    /// Scheduled jobs run on the workers either way.
+    ///
+    /// ```ignore
+    /// app.set_execution(ExecutionStrategy::SpawnPerConnection { max_concurrent: Some(1024) });
+    /// ```
+    ///
+    /// By default (WorkerPool)
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_execution(&mut self, execution: ExecutionStrategy) {
+        self.execution = execution;
+    }
+
+    /// # Set Engine
+    ///
+    /// Sets what reads requests from and writes responses to connections, routing, middleware, and resolutions stay the same, see [`Engine`].
+    ///
+    /// ```ignore
+    /// app.set_engine(Engine::Hyper);
+    /// ```
+    ///
+    /// By default (Native)
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_engine(&mut self, engine: Engine) {
+        self.engine = engine;
+    }
+
+    /// # Set Buffer Pool
+    ///
+    /// The pool of the buffers responses are written with, see [`BufferPool`].
+    ///
+    /// By default (256 buffers of up to 64 KiB)
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_buffer_pool(&mut self, pool: BufferPool) {
+        self.buffer_pool = pool;
+    }
+
+    /// # Set Body Limits
+    ///
+    /// Sets the largest request body that is read, larger bodies are discarded and answered with `413` before middleware or the handler is called.
+    ///
+    /// By default bodies up to 16 MiB are read.
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_body_limits(&mut self, limits: BodyLimits) {
+        self.body_limits = limits;
+    }
+
+    /// # Set Keep Alive
+    ///
+    /// Reuses connections for further requests of the client, closing a connection once it idles for the duration between requests.
+    ///
+    /// By default (None) each connection is closed after its request.
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_keep_alive(&mut self, idle: Option<Duration>) {
+        self.keep_alive = idle;
+    }
+
+    /// # Set Head Limits
+    ///
+    /// Sets how long a client may take to send each line of the request line and headers, and how large they may be, see [`HeadLimits`].
+    ///
+    /// Connections sending a line too slowly are closed, heads that are too large are answered with `431`, both are counted in `rejections`.
+    ///
+    /// By default lines are sent within 10 seconds and heads are up to 64 KiB.
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_head_limits(&mut self, limits: HeadLimits) {
+        self.head_limits = limits;
+    }
+
+    /// # Set Max Requests Per Connection
+    ///
+    /// Closes a kept alive connection after the response to its last allowed request, so one client can not hold a connection forever. Connections closed by the limit are counted in `rejections`.
+    ///
+    /// By default (1000)
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_max_requests_per_connection(&mut self, max: Option<usize>) {
+        self.max_requests_per_connection = max;
+    }
+
+    /// # Rejections
+    ///
+    /// The connections closed to protect the app, for heads sent too slowly, heads that are too large, and connections that reached the request limit.
+    ///
+    /// ```ignore
+    /// let rejected = app.rejections().stats();
+    ///
+    /// if rejected.slow_head > 100 {
+    ///     eprintln!("slow clients are holding connections");
+    /// }
+    /// ```
+    pub fn rejections(&self) -> Rejections {
+        self.rejections.clone()
+    }
+
+    /// # Set Cancel On Disconnect
+    ///
+    /// Stops handling a request when its client disconnects before the response, the middleware or handler is dropped at its next await and the token of `Request::cancel_token` is cancelled, so abandoned requests stop consuming CPU. The route records such requests as `499`.
+    ///
+    /// A streamed response is dropped the same way once the client disconnects while it is sent, so streams such as server-sent events stop producing chunks. A response that fails to write because the client is gone, such as a broken pipe, always cancels the token and is recorded as `499`.
+    ///
+    /// Clients that close their half of the connection after sending a request are seen as disconnected.
+    ///
+    /// By default (true)
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_cancel_on_disconnect(&mut self, cancel: bool) {
+        self.cancel_on_disconnect = cancel;
+    }
+
+    /// # Set Connection Limit
+    ///
+    /// Caps the connections served at once, further clients wait in the backlog or are answered with `503`, see [`ConnectionLimit`].
+    ///
+    /// ```ignore
+    /// app.set_connection_limit(Some(ConnectionLimit::new(10_000).overflow(Overflow::Reject)));
+    /// ```
+    ///
+    /// By default (None) every connection is accepted.
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_connection_limit(&mut self, limit: Option<ConnectionLimit>) {
+        self.connection_limit = limit;
+    }
+
+    /// # Set Acceptors
+    ///
+    /// Accepts the connections of each listener with several loops, spawned on the threads of the runtime, so a single accept loop is not the bottleneck on large machines, such as one per core.
+    ///
+    /// The loops share the listener, for a listener per loop bind the address again with `also_bind` and `SocketOptions::reuse_port`, the system then balances connections between them.
+    ///
+    /// ```ignore
+    /// app.set_acceptors(std::thread::available_parallelism().map_or(1, |n| n.get()));
+    /// ```
+    ///
+    /// By default (1), 0 is treated as 1.
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_acceptors(&mut self, acceptors: usize) {
+        self.acceptors = acceptors.max(1);
+    }
+
+    /// # Set Trusted Proxies
+    ///
+    /// Reads the address of the client from the `Forwarded` or `X-Forwarded-For` header of connections from the proxies, see `Request::client_ip`.
+    ///
+    /// The address is used by the access log, audit log, rate limiter, and throttle.
+    ///
+    /// The scheme and host the client requested are also read from the `Forwarded`, `X-Forwarded-Proto`, and `X-Forwarded-Host` headers of the proxies, see `Request::full_url`.
+    ///
+    /// ```ignore
+    /// app.set_trusted_proxies(Some(TrustedProxies::parse(&["10.0.0.0/8"])?));
+    /// ```
+    ///
+    /// By default (None) the headers are ignored, the client is the connected socket.
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_trusted_proxies(&mut self, proxies: Option<TrustedProxies>) {
+        self.trusted_proxies = proxies;
+    }
+
+    /// # On Start
+    ///
+    /// Adds a hook awaited with the bound address when the app starts, before the first connection is accepted, such as warming a cache.
+    ///
+    /// This MUST be set before you start the app.
+    pub fn on_start<F, Fut>(&mut self, hook: F)
+    where
+        F: Fn(SocketAddr) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.lifecycle.on_start(hook);
+    }
+
+    /// # On Connection
+    ///
+    /// Adds a hook called with the address of each accepted client before its request is read, returning false closes the connection, such as an allowlist.
+    ///
+    /// ```ignore
+    /// app.on_connection(move |peer| {
+    ///     let allowed = allowed.clone();
+    ///     async move { allowed.contains(&peer.ip()) }
+    /// });
+    /// ```
+    ///
+    /// This MUST be set before you start the app.
+    pub fn on_connection<F, Fut>(&mut self, hook: F)
+    where
+        F: Fn(SocketAddr) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = bool> + Send + 'static,
+    {
+        self.lifecycle.on_connection(hook);
+    }
+
+    /// # On Shutdown
+    ///
+    /// Adds a hook awaited when the app is closed, after it stops accepting connections, such as flushing buffers.
+    ///
+    /// This MUST be set before you start the app.
+    pub fn on_shutdown<F, Fut>(&mut self, hook: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.lifecycle.on_shutdown(hook);
+    }
+
+    /// # Set Cache
+    ///
+    /// Sets the cache handlers reach with `Request::cache`, for example a cache backed by a shared store.
+    ///
+    /// By default an in-memory cache of 10,000 entries is used.
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_cache(&mut self, cache: Cache) {
+        self.cache = cache;
+    }
+
+    /// # Set Watchdog
+    ///
+    /// Watches for the state where every worker is stuck on a request, for example a deadlocked handler.
+    ///
+    /// While wedged the `on_wedged` callback is called, emergency workers are added up to the cap, and the readiness endpoint reports `503`.
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_watchdog(&mut self, config: WatchdogConfig) {
+        self.watchdog = Some(config);
+    }
+
+    /// # Set Autoscale
+    ///
+    /// Grows and shrinks the workers between `min_workers` and `max_workers` with the connections waiting and in flight, stopping idle workers once fewer are needed.
+    ///
+    /// Growing by `worker_scale_factor` when every worker is busy stops at `max_workers`.
+    ///
+    /// ```ignore
+    /// app.set_autoscale(Some(AutoscaleConfig { min_workers: 4, max_workers: 256, ..Default::default() }));
+    /// ```
+    ///
+    /// By default (None) the workers only grow.
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_autoscale(&mut self, config: Option<AutoscaleConfig>) {
+        self.autoscale = config;
+    }
+
+    /// # Set On Worker Crash
+    ///
+    /// Calls the callback with each panic caught in a worker, such as a panic outside of a handler, which `set_on_panic` does not see.
+    ///
+    /// The worker that panicked takes the next connection, the pool does not shrink.
+    ///
+    /// ```ignore
+    /// app.set_on_worker_crash(|crash| eprintln!("worker panicked ({} so far): {}", crash.panics, crash.message));
+    /// ```
+    pub fn set_on_worker_crash(&mut self, callback: impl Fn(&WorkerCrash) + Send + Sync + 'static) {
+        self.supervisor.set_on_crash(Some(Arc::new(callback)));
+    }
+
+    /// # Set Respawn Workers
+    ///
+    /// Replaces workers whose task stopped without being closed, checked by the watchdog, see `set_watchdog`.
+    ///
+    /// By default (false)
+    pub fn set_respawn_workers(&mut self, respawn: bool) {
+        self.supervisor.set_respawn(respawn);
+    }
+
+    /// # Worker Panics
+    ///
+    /// The panics caught in the workers since the app was bound.
+    pub fn worker_panics(&self) -> u64 {
+        self.supervisor.panics()
+    }
+
+    /// # Worker Health
+    ///
+    /// The latest health of the workers as seen by the watchdog.
+    ///
+    /// Without a watchdog the snapshot is never updated.
+    pub async fn worker_health(&self) -> WorkerHealth {
+        self.worker_health.lock().await.clone()
+    }
+
+    /// # Work Stats
+    ///
+    /// The saturation of the workers, busy workers, queued connections, finished work, panics, and latency histograms, see [`WorkStats`].
+    pub async fn work_stats(&self) -> WorkStats {
+        self.work_manager.lock().await.stats().await
+    }
+
+    /// # Scale Workers
+    ///
+    /// Grows or shrinks the workers of the app to the target while it runs, only idle workers are stopped when shrinking, see `WorkManager::scale_to`.
+    ///
+    /// The app keeps at least one worker. With autoscaling the autoscaler moves the workers back into its bounds on its next check, see `set_autoscale`.
+    ///
+    /// ```ignore
+    /// let workers = app.scale_workers(64).await;
+    /// ```
+    ///
+    /// Returns the amount of workers after scaling.
+    pub async fn scale_workers(&self, target: usize) -> usize {
+        self.work_manager.lock().await.scale_to(target.max(1)).await
+    }
+
+    /// # Set Work Metrics
+    ///
+    /// Passes the work of the workers on to the metrics, for example to export it, see [`Metrics`].
+    ///
+    /// The counters of `work_stats` are kept either way.
+    pub fn set_work_metrics(&mut self, metrics: impl Metrics + 'static) {
+        self.supervisor.set_metrics(Some(Arc::new(metrics)));
+    }
+
+    /// # Serve Work Stats
+    ///
+    /// Routes the path to a JSON snapshot of the work stats.
+    ///
+    /// ```ignore
+    /// app.serve_work_stats("/admin/workers").await?;
+    /// ```
+    pub async fn serve_work_stats(&self, path: &str) -> crate::Result<()> {
+        let work_manager = self.work_manager.clone();
+
+        self.add_or_change_route(path, Method::GET, None, move |_| {
+            let work_manager = work_manager.clone();
+
+            async move {
+                let stats = work_manager.lock().await.stats().await;
+
+                match JsonResolution::serialize(stats) {
+                    Ok(json) => json.resolve(),
+                    Err(e) => e.resolve(),
+                }
+            }
+        })
+        .await
+    }
+
+    /// # Route Stats
+    ///
+    /// The counters of each route pattern, responses, latency percentiles, and the last server error.
+    ///
+    /// Requests that match no route are counted under `web::stats::UNMATCHED_ROUTE`.
+    pub fn route_stats(&self) -> RouteStats {
+        self.route_stats.clone()
+    }
+
+    /// # Serve Route Stats
+    ///
+    /// Routes the path to a JSON snapshot of the route stats, protect it with middleware as it exposes error messages.
+    ///
+    /// ```ignore
+    /// app.serve_route_stats("/admin/routes").await?;
+    /// ```
+    pub async fn serve_route_stats(&self, path: &str) -> crate::Result<()> {
+        let stats = self.route_stats.clone();
+
+        self.add_or_change_route(path, Method::GET, None, move |_| {
+            let stats = stats.clone();
+
+            async move { stats.resolution().await }
+        })
+        .await
+    }
+
+    /// # Document Route
+    ///
+    /// Sets the OpenAPI metadata of an existing route and method, its summary and the schemas of its request and responses, see `web::openapi::RouteDoc`.
+    ///
+    /// ```ignore
+    /// app.document_route("/users/{id}", Method::GET, RouteDoc::new().summary("Get a user").response::<User>(200, "The user")).await?;
+    /// ```
+    ///
+    /// Requires the `openapi` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RoutingError::Missing` if the route does not exist and `RoutingError::MethodMissing` if it has no resolution for the method.
+    #[cfg(feature = "openapi")]
+    pub async fn document_route(
+        &self,
+        route: &str,
+        method: Method,
+        doc: crate::web::openapi::RouteDoc,
+    ) -> crate::Result<()> {
+        let node = self
+            .router
+            .lock()
+            .await
+            .get_route(route)
+            .await
+            .ok_or(RoutingError::Missing)?;
+
+        if node.lock().await.brw_resolution(&method).is_none() {
+            return Err(RoutingError::MethodMissing.into());
+        }
+
+        let pattern = RouteNode::pattern(&node).await;
+        self.route_docs.insert(&pattern, method, doc);
+
+        Ok(())
+    }
+
+    /// # OpenAPI
+    ///
+    /// Generates the OpenAPI 3.1 document of every route of the app, with the metadata of `document_route`, see `web::openapi::OpenApi::document`.
+    ///
+    /// Requires the `openapi` feature.
+    #[cfg(feature = "openapi")]
+    pub async fn openapi(&self, api: &crate::web::openapi::OpenApi) -> serde_json::Value {
+        let routes = Banner::routes(&*self.router.lock().await).await;
+
+        api.document(&routes, &self.route_docs)
+    }
+
+    /// # Serve OpenAPI
+    ///
+    /// Routes the path to a Swagger UI page and `{path}/openapi.json` to the OpenAPI document of the app, generated when it is requested so it follows changes to the routes.
+    ///
+    /// Neither route is listed in the document, protect them with middleware if the API is private.
+    ///
+    /// ```ignore
+    /// app.serve_openapi("/docs", OpenApi::new("Tasks", "1.0.0")).await?;
+    /// ```
+    ///
+    /// Requires the `openapi` feature.
+    #[cfg(feature = "openapi")]
+    pub async fn serve_openapi(
+        &self,
+        path: &str,
+        api: crate::web::openapi::OpenApi,
+    ) -> crate::Result<()> {
+        use crate::web::openapi::{RouteDoc, swagger_ui};
+
+        let document_path = format!("{}/openapi.json", path.trim_end_matches('/'));
+        let router = self.router.clone();
+        let docs = self.route_docs.clone();
+        let api = Arc::new(api);
+
+        self.add_or_change_route(&document_path, Method::GET, None, move |_| {
+            let router = router.clone();
+            let docs = docs.clone();
+            let api = api.clone();
+
+            async move {
+                let routes = Banner::routes(&*router.lock().await).await;
+
+                match JsonResolution::serialize(api.document(&routes, &docs)) {
+                    Ok(json) => json.resolve(),
+                    Err(e) => e.resolve(),
+                }
+            }
+        })
+        .await?;
+
+        let url = document_path.clone();
+
+        self.add_or_change_route(path, Method::GET, None, move |_| {
+            let url = url.clone();
+
+            async move { swagger_ui(&url, "API docs") }
+        })
+        .await?;
+
+        self.document_route(&document_path, Method::GET, RouteDoc::new().hidden())
+            .await?;
+        self.document_route(path, Method::GET, RouteDoc::new().hidden())
+            .await
+    }
+
+    /// # Banner
+    ///
+    /// A summary of the addresses, workers, and routes of the app, with warnings about routes that can not be reached, see `set_startup_banner`.
+    pub async fn banner(&self) -> Banner {
+        let addresses = self.local_addrs();
+
+        collect_banner(
+            addresses,
+            &self.router,
+            &self.global_middleware,
+            &self.work_manager,
+            *self.worker_scale_factor.lock().await,
+            self.fair_queuing,
+        )
+        .await
+    }
+
+    /// # Set Startup Banner
+    ///
+    /// Writes the banner to the logger when the app starts, a table of the bound addresses, workers, and every route with its methods and middleware count, followed by warnings about routes that can not be reached.
+    ///
+    /// ```ignore
+    /// app.set_startup_banner(|banner| println!("{banner}"));
+    /// ```
+    ///
+    /// This MUST be set before you start the app.
+    pub fn set_startup_banner(&mut self, logger: impl Fn(String) + Send + Sync + 'static) {
+        self.startup_banner = Some(Arc::new(logger));
+    }
+
+    /// # Route Changes
+    ///
+    /// The audit trail of changes to the routes, who changed what and when, see `web::route_log`.
+    pub fn route_changes(&self) -> RouteChangeLog {
+        self.route_changes.clone()
+    }
+
+    /// # Serve Route Changes
+    ///
+    /// Routes the path to a JSON list of the changes to the routes, protect it with middleware.
+    pub async fn serve_route_changes(&self, path: &str) -> crate::Result<()> {
+        let changes = self.route_changes.clone();
+
+        self.add_or_change_route(path, Method::GET, None, move |_| {
+            let changes = changes.clone();
+
+            async move { changes.resolution().await }
+        })
+        .await
+    }
+
+    /// # Serve Readiness
+    ///
+    /// Routes the path to a readiness endpoint for load balancers, `200` while workers can take work and `503` while they are wedged.
+    ///
+    /// While the workers are wedged, `GET` requests for the path are answered by a task of their own since no worker is free, except over TLS.
+    pub async fn serve_readiness(&self, path: &str) -> crate::Result<()> {
+        let health = self.worker_health.clone();
+        self.readiness.set_path(path);
+
+        self.add_or_change_route(path, Method::GET, None, move |_| {
+            let health = health.clone();
+
+            async move { watchdog::readiness(&*health.lock().await) }
+        })
+        .await
+    }
+
+    /// # Load Route Table
+    ///
+    /// Adds each route of the table with its middleware presets, replacing routes that already exist.
+    ///
+    /// Routes with a CORS policy also answer preflight (`OPTIONS`) requests, and `middleware = ["name"]` references middleware registered with `register_middleware`.
+    ///
+    /// ```ignore
+    /// let mut registry = ConfigRegistry::new();
+    /// registry.add_handler("get_user", get_user);
+    /// registry.add_middleware("admin", bearer(admin_tokens));
+    ///
+    /// let table = RouteTable::from_file("routes.toml").await?;
+    /// app.load_route_table(&table, &registry).await?;
+    /// ```
+    pub async fn load_route_table(
+        &self,
+        table: &crate::web::config::RouteTable,
+        registry: &crate::web::config::ConfigRegistry,
+    ) -> crate::Result<()> {
+        //build every endpoint first so an invalid table adds no routes.
+        let mut endpoints = Vec::with_capacity(table.routes.len());
+
+        for entry in &table.routes {
+            let resolution = entry.resolution(registry)?;
+            let middleware = entry.middleware(registry)?;
+
+            if let Some(policy) = entry.cors_policy()? {
+                let preflight: ResolutionFnRef = Arc::new(|_| {
+                    Box::pin(async move { NoContent.resolve() })
+                });
+
+                let options = Method::Other("OPTIONS".to_string());
+                let cors = crate::web::cors::cors(policy);
+
+                let endpoint = EndPoint::new(preflight, Some(vec![cors]));
+
+                endpoints.push((entry.path.clone(), options, endpoint));
+            }
+
+            let method = Method::from(entry.method.as_str());
+
+            let names: Vec<&str> = entry.middleware.iter().map(|n| n.as_str()).collect();
+            let names = self.registered_names(&names).await?;
+
+            let endpoint =
+                EndPoint::new(resolution, Some(middleware)).with_named_middleware(names);
+
+            endpoints.push((entry.path.clone(), method, endpoint));
+        }
+
+        let mut router = self.router.lock().await;
+        let (mut added, mut replaced) = (Vec::new(), Vec::new());
+
+        for (path, method, endpoint) in endpoints {
+            match has_resolution(&router, &path, &method).await {
+                true => replaced.push(describe_route(&method, &path)),
+                false => added.push(describe_route(&method, &path)),
+            }
+
+            router.add_route(&path, Some((method, endpoint))).await?;
+        }
+
+        drop(router);
+
+        self.route_changes
+            .record(RouteChangeKind::Table, added, replaced, Vec::new())
+            .await;
+
+        Ok(())
+    }
+
+    /// # Serve Manifest
+    ///
+    /// Routes the path to the JSON of the asset manifest, see `web::manifest::AssetManifest`.
+    ///
+    /// The manifest is served with an ETag of its version, clients that send a matching `If-None-Match` receive `304`.
+    pub async fn serve_manifest(
+        &self,
+        path: &str,
+        manifest: crate::web::manifest::AssetManifest,
+    ) -> crate::Result<()> {
+        self.add_or_change_route(path, Method::GET, None, move |req| {
+            let manifest = manifest.clone();
+
+            async move {
+                let if_none_match = req.lock().await.request_header("If-None-Match").cloned();
+
+                manifest.resolution(if_none_match.as_deref()).await
+            }
+        })
+        .await
+    }
+
+    /// # Serve Static
+    ///
+    /// Routes everything under the prefix to the files of the directory, `/assets/css/site.css` serves `./public/css/site.css`.
+    ///
+    /// ```ignore
+    /// app.serve_static("/assets", "./public").await?;
+    /// ```
+    ///
+    /// Paths leaving the directory are rejected, directories are served by their `index.html`, see `web::static_files::StaticFiles` for listings and cache headers.
+    pub async fn serve_static(&self, prefix: &str, root: &str) -> crate::Result<()> {
+        self.serve_static_with(prefix, StaticFiles::new(root)).await
+    }
+
+    /// # Serve Static With
+    ///
+    /// Routes everything under the prefix to the configured static files, see `serve_static`.
+    pub async fn serve_static_with(
+        &self,
+        prefix: &str,
+        files: StaticFiles,
+    ) -> crate::Result<()> {
+        let files = Arc::new(files);
+
+        for route in prefix_routes(prefix) {
+            let files = files.clone();
+
+            self.add_or_change_route(&route, Method::GET, None, move |req| {
+                let files = files.clone();
+
+                async move {
+                    let (path, url, if_none_match) = {
+                        let guard = req.lock().await;
+
+                        (
+                            guard.variables.get("*").cloned().unwrap_or_default(),
+                            guard.route.cleaned_route.clone(),
+                            guard.request_header("If-None-Match").cloned(),
+                        )
+                    };
+
+                    files.resolution(&path, &url, if_none_match.as_deref()).await
+                }
+            })
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// # Serve Bundle
+    ///
+    /// Routes everything under the prefix to the assets of a bundle compiled into the executable, see `web::bundle::StaticBundle`.
+    ///
+    /// ```ignore
+    /// app.serve_bundle("/", static_bundle!("public", ["index.html", "app.js"])).await?;
+    /// ```
+    pub async fn serve_bundle(
+        &self,
+        prefix: &str,
+        bundle: StaticBundle,
+    ) -> crate::Result<()> {
+        let bundle = Arc::new(bundle);
+
+        for route in prefix_routes(prefix) {
+            let bundle = bundle.clone();
+
+            self.add_or_change_route(&route, Method::GET, None, move |req| {
+                let bundle = bundle.clone();
+
+                async move {
+                    let guard = req.lock().await;
+
+                    bundle.resolution(
+                        guard.variables.get("*").map(String::as_str).unwrap_or_default(),
+                        guard.request_header("If-None-Match").map(String::as_str),
+                        guard.request_header("Accept-Encoding").map(String::as_str),
+                    )
+                }
+            })
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// # Use TLS
+    ///
+    /// Serves HTTPS instead of HTTP, selecting the certificate of each connection with the `resolver`.
+    ///
+    /// See `web::tls::CertificateStore` for a certificate that may be replaced while the app is running.
+    ///
+    /// Requires the `tls` feature. This MUST be set before you start the app.
+    #[cfg(feature = "tls")]
+    pub fn use_tls(&mut self, resolver: Arc<dyn crate::web::tls::ResolvesServerCert>) {
+        self.tls = Some(crate::web::tls::acceptor(resolver));
+    }
+
+    /// # Set TLS Handshake Timeout
+    ///
+    /// How long a client has to complete the TLS handshake before the connection is closed.
+    ///
+    /// By default (10 seconds)
+    ///
+    /// Requires the `tls` feature. This MUST be set before you start the app.
+    #[cfg(feature = "tls")]
+    pub fn set_tls_handshake_timeout(&mut self, timeout: Duration) {
+        self.tls_handshake_timeout = timeout;
+    }
+
+    /// # Serve Acme Challenges
+    ///
+    /// Routes `/.well-known/acme-challenge/{token}` to the challenges, allowing an ACME server to validate http-01 challenges.
+    ///
+    /// ACME servers validate http-01 over plain HTTP on port 80, so this is usually called on a second app without TLS, see `web::acme::challenge_app`.
+    ///
+    /// Requires the `tls` feature.
+    #[cfg(feature = "tls")]
+    pub async fn serve_acme_challenges(
+        &self,
+        challenges: crate::web::acme::AcmeChallenges,
+    ) -> crate::Result<()> {
+        let route = format!("{}/{{token}}", crate::web::acme::CHALLENGE_PATH);
+
+        self.add_or_change_route(&route, Method::GET, None, move |req| {
+            let challenges = challenges.clone();
+
+            async move {
+                let token = req
+                    .lock()
+                    .await
+                    .variables
+                    .get("token")
+                    .cloned()
+                    .unwrap_or_default();
+
+                challenges.resolution(&token).await
+            }
+        })
+        .await
+    }
+
+    /// # Scheduler
+    ///
+    /// Runs jobs on the workers of the app at fixed intervals or on cron expressions, such as purging sessions or refreshing caches, see `factory::Scheduler`.
+    ///
+    /// The jobs are cancelled when the app is closed.
+    ///
+    /// ```ignore
+    /// let job = app.scheduler().add(Schedule::cron("*/5 * * * *")?.jitter(Duration::from_secs(10)), move || {
+    ///     let cache = cache.clone();
+    ///     async move { cache.clear().await }
+    /// });
+    /// ```
+    pub fn scheduler(&self) -> Scheduler<()> {
+        self.scheduler.clone()
+    }
+
+    /// # Background Tasks
+    ///
+    /// The amount of tasks spawned with `Request::spawn` that are still running.
+    pub fn background_tasks(&self) -> usize {
+        self.background.len()
+    }
+
+    /// # Test Client
+    ///
+    /// Creates a client that sends requests through the routing, middleware, and resolutions of the app in memory, see `web::testing::TestClient`.
+    ///
+    /// The app does not have to be started. Routes added later are seen by the client, the other settings are those of the app when the client is created.
+    pub fn test_client(&self) -> TestClient {
+        TestClient::new(self.context())
+    }
+
+    /// the references shared by every connection of the app.
+    fn context(&self) -> AppContext {
+        AppContext {
+            router: self.router.clone(),
+            global_middleware: self.global_middleware.clone(),
+            middleware_registry: self.middleware_registry.clone(),
+            audit_log: self.audit_log.clone(),
+            cache: self.cache.clone(),
+            background: self.background.clone(),
+            supervisor: self.supervisor.clone(),
+            on_panic: self.on_panic.clone(),
+            route_stats: self.route_stats.clone(),
+            body_limits: self.body_limits,
+            head_limits: self.head_limits,
+            keep_alive: self.keep_alive,
+            max_requests_per_connection: self.max_requests_per_connection,
+            rejections: self.rejections.clone(),
+            queue_timeout: self.queue_timeout,
+            executor: self.execution.executor(),
+            buffers: self.buffer_pool.clone(),
+            cancel_on_disconnect: self.cancel_on_disconnect,
+            lifecycle: self.lifecycle.clone(),
+            connection_limit: self.connection_limit.clone(),
+            socket_options: self.socket_options.clone(),
+            trusted_proxies: self.trusted_proxies.clone(),
+            state: self.state.clone(),
+            error_map: self.error_map.clone(),
+            error_pages: self.error_pages.clone(),
+            server_header: self.server_header.clone(),
+            route_names: self.route_names.clone(),
+            debug_tap: self.debug_tap.clone(),
+            engine: self.engine,
+            readiness: self.readiness.clone(),
+            #[cfg(feature = "tls")]
+            tls: self.tls.clone(),
+            #[cfg(feature = "tls")]
+            tls_handshake_timeout: self.tls_handshake_timeout,
+        }
+    }
+
+    /// # state
+    ///
+    /// Get the state of the application.
+    pub fn state(&self) -> AppState {
+        match &self.app_task {
+            None => AppState::Closed,
+            _ => AppState::Running,
+        }
+    }
+}
+
+impl Drop for App {
+    /// Drops when the application goes out of scope. This is equivalent to calling (&mut self).close
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+/// # App Context
+///
+/// References shared by every connection the app task accepts.
+#[derive(Clone)]
+pub(crate) struct AppContext {
+    router: Arc<Mutex<RouteTree>>,
+    global_middleware: Arc<Mutex<Vec<MiddlewareClosure>>>,
+    middleware_registry: Arc<Mutex<MiddlewareRegistry>>,
+    audit_log: Option<AuditLog>,
+    cache: Cache,
+    background: Background,
+    supervisor: Arc<Supervisor>,
+    on_panic: Option<Arc<PanicHandler>>,
+    route_stats: RouteStats,
+    body_limits: BodyLimits,
+    head_limits: HeadLimits,
+    keep_alive: Option<Duration>,
+    max_requests_per_connection: Option<usize>,
+    rejections: Rejections,
+    queue_timeout: Option<Duration>,
+    executor: Executor,
+    buffers: BufferPool,
+    cancel_on_disconnect: bool,
+    lifecycle: Lifecycle,
+    connection_limit: Option<ConnectionLimit>,
+    socket_options: SocketOptions,
+    trusted_proxies: Option<TrustedProxies>,
+    state: Arc<StateMap>,
+    error_map: Arc<ErrorMap>,
+    error_pages: Arc<ErrorPages>,
+    server_header: Option<Arc<str>>,
+    route_names: RouteNames,
+    debug_tap: Option<DebugTap>,
+    engine: Engine,
+    readiness: ReadinessProbe,
+    #[cfg(feature = "tls")]
+    tls: Option<TlsAcceptor>,
+    #[cfg(feature = "tls")]
+    tls_handshake_timeout: Duration,
+}
+
+/// # Collect Banner
+///
+/// Builds the banner of an app.
+async fn collect_banner(
+    addresses: Vec<SocketAddr>,
+    router: &Mutex<RouteTree>,
+    global_middleware: &Mutex<Vec<MiddlewareClosure>>,
+    work_manager: &Mutex<WorkManager<()>>,
+    worker_scale_factor: usize,
+    fair_queuing: bool,
+) -> Banner {
+    let (routes, warnings) = {
+        let router = router.lock().await;
+        (Banner::routes(&router).await, Banner::warnings(&router).await)
+    };
+
+    Banner {
+        addresses,
+        workers: work_manager.lock().await.size(),
+        worker_scale_factor,
+        fair_queuing,
+        global_middleware: global_middleware.lock().await.len(),
+        routes,
+        warnings,
+    }
+}
+
+/// # Has Resolution
+///
+/// Returns true if the route of the router has a resolution for the method.
+async fn has_resolution(router: &RouteTree, route: &str, method: &Method) -> bool {
+    match router.get_route(route).await {
+        Some(rte) => rte.lock().await.brw_resolution(method).is_some(),
+        None => false,
+    }
+}
+
+/// # Accept Loop
+///
+/// Accepts the clients of the listener and queues their connections on the workers, until the app is shut down.
+async fn accept_loop(
+    listener: Arc<TcpListener>,
+    mut shutdown_rx: broadcast::Receiver<()>,
+    context: AppContext,
+    work_manager: Arc<Mutex<WorkManager<()>>>,
+    scale_factor_clone: Arc<Mutex<usize>>,
+    error_callback: Option<ErrorCallback>,
+) {
+    //create a default callback if none.
+    let error_callback = error_callback.unwrap_or(Arc::new(Box::pin(|_| {})));
+
+    loop {
+        //with backpressure no client is accepted until a connection is free, new clients wait in the backlog.
+        let mut permit = match &context.connection_limit {
+            Some(limit) if limit.backpressure() => tokio::select! {
+                _ = shutdown_rx.recv() => {
+                    break;
+                },
+                permit = limit.acquire() => permit,
+            },
+            _ => None,
+        };
+
+        tokio::select! {
+            _ = shutdown_rx.recv() => {
+                break;
+            },
+            accepted_client = listener.accept() => {
+
+                //failed to accept the client send the error to the callback
+                if let Err(e) = accepted_client {
+                    error_callback(e.to_string());
+                    continue;
+                }
+
+                let (client_stream, client_socket) = accepted_client.unwrap();
+
+                //connections the options can not be set on are still served.
+                if context.socket_options.applies_to_connections()
+                    && let Err(e) = context.socket_options.apply(&client_stream)
+                {
+                    error_callback(e.to_string());
+                }
+
+                //without backpressure clients over the limit are rejected.
+                if let Some(limit) = &context.connection_limit
+                    && permit.is_none()
+                {
+                    permit = limit.try_acquire();
+
+                    if permit.is_none() {
+                        reject_connection(client_stream, &context);
+                        continue;
+                    }
+                }
+
+                //a wedged pool can not answer readiness, peeking for it waits on the client so it is done outside of the accept loop.
+                #[cfg(feature = "tls")]
+                let probe = context.tls.is_none();
+                #[cfg(not(feature = "tls"))]
+                let probe = true;
+
+                if probe && context.readiness.probes().await {
+                    let context = context.clone();
+                    let work_manager = work_manager.clone();
+                    let scale_factor = scale_factor_clone.clone();
+                    let error_callback = error_callback.clone();
+
+                    tokio::spawn(async move {
+                        if let Some(client_stream) = context.readiness.intercept(client_stream).await {
+                            queue_connection(client_stream, client_socket, permit, &context, &work_manager, &scale_factor, error_callback).await;
+                        }
+                    });
+
+                    continue;
+                }
+
+                queue_connection(client_stream, client_socket, permit, &context, &work_manager, &scale_factor_clone, error_callback.clone()).await;
+            }
+        }
+    }
+}
+
+/// # Queue Connection
+///
+/// Queues the connection of the client on the workers, or spawns it with the spawn execution strategy. Clients are answered with `503` if the queue is full or they wait longer than the queue timeout.
+async fn queue_connection(
+    client_stream: TcpStream,
+    client_socket: SocketAddr,
+    permit: Option<OwnedSemaphorePermit>,
+    context: &AppContext,
+    work_manager: &Mutex<WorkManager<()>>,
+    scale_factor: &Mutex<usize>,
+    error_callback: ErrorCallback,
+) {
+    //get refs for the worker.
+    let work_context = context.clone();
+    let client_key = client_socket.ip().to_string();
+
+    //the stream is taken back to reject the client if the queue is full.
+    let client_stream = Arc::new(std::sync::Mutex::new(Some(client_stream)));
+    let work_stream = client_stream.clone();
+
+    //get work that needs to be completed.
+    let mut current_work = Job::new(Box::pin(
+        async move {
+            //the connection counts against the limit until it is closed.
+            let _permit = permit;
+
+            let Some(client_stream) = work_stream.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+                return;
+            };
+
+            //handle the client request
+            let completed_work =
+                handle_connection(client_stream, client_socket, work_context).await;
+
+            //handle any errors
+            if let Err(e) = completed_work {
+                error_callback(e.to_string());
+            }
+        }
+    ));
+
+    //spawned connections do not wait for a worker.
+    if let Executor::Spawn(permits) = &context.executor {
+        let permits = permits.clone();
+        let work = current_work.into_work();
+
+        tokio::spawn(async move {
+            //the permit is held until the connection is closed.
+            let _permit = match permits {
+                Some(permits) => permits.acquire_owned().await.ok(),
+                None => None,
+            };
+
+            work.await;
+        });
+
+        return;
+    }
+
+    //connections that waited too long for a worker are answered with 503 instead.
+    if let Some(queue_timeout) = context.queue_timeout {
+        let expired_stream = client_stream.clone();
+        let expired_context = context.clone();
+
+        current_work = current_work
+            .with_deadline(Instant::now() + queue_timeout)
+            .on_expired(move || {
+                if let Some(client_stream) = expired_stream.lock().unwrap_or_else(|e| e.into_inner()).take() {
+                    reject_connection(client_stream, &expired_context);
+                }
+            });
+    }
+
+    //queued without holding the work manager, a bounded queue may block until a worker is free.
+    let work_queue = work_manager.lock().await.work_queue();
+
+    //loop, needed to ensure that work is queued properly. please see below
+    loop {
+
+        //queue some work
+        match work_queue.queue_for(&client_key, current_work).await {
+            crate::factory::queue::QueueState::Free => break, //work was successfully added to the queue (enough workers)
+            crate::factory::queue::QueueState::Blocked(returned_work) => { //the queue was blocked (no workers) this gives us back the work that was not queued.
+                current_work = returned_work;
+
+                //scale our worker count.
+                let scale_factor = *scale_factor.lock().await;
+                work_manager.lock().await.scale_workers(scale_factor).await;
+
+                //hand control back to the async controller.
+                tokio::task::yield_now().await;
+            }
+            crate::factory::queue::QueueState::Full(_) => { //the bounded queue rejects work, the client is answered with 503.
+                if let Some(client_stream) = client_stream.lock().unwrap_or_else(|e| e.into_inner()).take() {
+                    reject_connection(client_stream, context);
+                }
+
+                break;
+            }
+        };
+    }
+}
+
+/// # Reject Connection
 ///
-/// Traverses parent route nodes and assigns variable values into the request.
-/// This is executed after routing but before middleware and resolution execution.
+/// Answers a client over the connection limit with `503` and closes it, TLS clients are closed without a response.
+fn reject_connection(mut stream: TcpStream, context: &AppContext) {
+    #[cfg(feature = "tls")]
+    if context.tls.is_some() {
+        return;
+    }
 
-async fn set_request_variables(req_ref: Arc<Mutex<Request>>, route_ref: RouteNodeRef) -> () {
-    //the given route by the user, cleaned.
-    let given_route: String = {
-        let req_lock = req_ref.lock().await;
+    #[cfg(not(feature = "tls"))]
+    let _ = context;
 
-        req_lock.route.cleaned_route.clone()
-    };
+    //written outside of the accept loop, so a slow client does not hold it.
+    tokio::spawn(async move {
+        let _ = tokio::time::timeout(Duration::from_secs(1), async {
+            stream
+                .write_all(
+                    b"HTTP/1.1 503 Service Unavailable\r\nRetry-After: 1\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                )
+                .await?;
+            stream.shutdown().await?;
+
+            //closing with the unread request of the client resets the connection and loses the response.
+            let mut discard = [0u8; 1024];
+            while stream.read(&mut discard).await? > 0 {}
+
+            Ok::<(), std::io::Error>(())
+        })
+        .await;
+    });
+}
 
-    let mut given_route_parts: Vec<&str> = given_route.split('/').collect();
+/// # Handle Connection
+///
+/// Performs the TLS handshake when TLS is used, then handles the request of the client.
+async fn handle_connection(
+    stream: TcpStream,
+    client_socket: SocketAddr,
+    context: AppContext,
+) -> Result<(), Box<dyn std::error::Error>> {
+    //rejected clients are closed before the handshake.
+    if !context.lifecycle.accepts(client_socket).await {
+        return Ok(());
+    }
 
-    let mut current_ref = Some(route_ref.clone());
+    #[cfg(feature = "tls")]
+    if let Some(acceptor) = context.tls.clone() {
+        //a client that never finishes the handshake would hold the connection slot forever.
+        let stream = tokio::time::timeout(context.tls_handshake_timeout, acceptor.accept(stream))
+            .await
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "tls handshake timed out"))??;
+        return serve_engine(stream, client_socket, context).await;
+    }
 
-    let wild_card_skip = {
-        let mut current = Some(route_ref.clone());
-        let mut wild_skip = 0;
+    serve_engine(stream, client_socket, context).await
+}
 
-        while let Some(node) = current {
-            let guard = node.lock().await;
-            current = guard.parent.clone();
-            wild_skip += 1;
+/// # Serve Engine
+///
+/// Handles the requests of an established connection with the engine of the app.
+async fn serve_engine<S>(
+    stream: S,
+    client_socket: SocketAddr,
+    context: AppContext,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    match context.engine {
+        Engine::Native => serve_stream(stream, client_socket, context).await,
+        #[cfg(feature = "hyper")]
+        Engine::Hyper => {
+            let (keep_alive, head_limits) = (context.keep_alive, context.head_limits);
+            crate::web::engine::serve_hyper(stream, client_socket, context, keep_alive, head_limits).await
         }
+    }
+}
+
+/// # Serve Stream
+///
+/// Handles the requests of an established connection, a TCP or TLS stream, or the in memory stream of a `TestClient`.
+pub(crate) async fn serve_stream<S>(
+    stream: S,
+    client_socket: SocketAddr,
+    context: AppContext,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    //error resolutions of the request take their status from the error map.
+    let error_map = context.error_map.clone();
+
+    error_map
+        .scope(handle_tapped(stream, client_socket, context))
+        .await
+}
 
-        //skip for the WILDCARD {*} and SKIP for the beginning "/" route.
-        wild_skip - 1
+/// # Handle Tapped
+///
+/// Records the bytes of the connection when the debug tap taps the client, then handles the request of the client.
+async fn handle_tapped<S>(
+    stream: S,
+    client_socket: SocketAddr,
+    context: AppContext,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let Some(tap) = context
+        .debug_tap
+        .clone()
+        .filter(|tap| tap.taps_client(client_socket.ip()))
+    else {
+        return serve_requests(stream, client_socket, context).await;
     };
 
-    while let Some(c_ref) = current_ref {
-        //pop a route part
-        let route_part = given_route_parts.pop();
+    let (stream, recording) = tap.wrap(stream);
 
-        //if none, something is wrong, break out
-        if route_part.is_none() {
-            break;
-        }
+    //the error is not Send, it may not be held while the tap is written.
+    let result = serve_requests(stream, client_socket, context)
+        .await
+        .map_err(|e| e.to_string());
+
+    tap.emit(client_socket, &recording).await;
 
-        //unwrap the route part
-        let route_part = route_part.unwrap();
+    result.map_err(|e| e.into())
+}
+
+/// # Serve Requests
+///
+/// Handles the requests of the connection, while the app keeps connections alive and the client allows it, until the connection idles too long.
+async fn serve_requests<S>(
+    stream: S,
+    client_socket: SocketAddr,
+    context: AppContext,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    //the buffer is kept between requests, it may hold the start of the next request.
+    let mut stream = BufReader::new(stream);
+    let idle = context.keep_alive;
+    let max_requests = context.max_requests_per_connection;
+    let mut served = 0;
+    let span = Span::connection(client_socket);
+
+    loop {
+        served += 1;
+
+        //the last request the connection may make is answered, then the connection is closed.
+        let last = max_requests.is_some_and(|max| served >= max);
+
+        let reuse = span
+            .instrument(handle_client_request(&mut stream, client_socket, context.clone(), last))
+            .await?;
+
+        let Some(idle) = idle.filter(|_| reuse) else {
+            //the client sees the end of the response right away.
+            let _ = stream.shutdown().await;
+            return Ok(());
+        };
 
-        //check if the route part is empty, we are allowed to continue from this
-        if route_part.is_empty() {
-            //since we own c_ref and have not locked, we can just reuse.
-            //we need to pass into some for ownership
-            current_ref = Some(c_ref);
-            continue;
+        //wait for the next request, the client closing the connection or idling ends it.
+        match tokio::time::timeout(idle, stream.fill_buf()).await {
+            Ok(Ok(buffered)) if !buffered.is_empty() => continue,
+            _ => return Ok(()),
         }
+    }
+}
+
+/// Extracts dynamic route parameters from the matched route tree.
+///
+/// Traverses parent route nodes and assigns variable values into the request.
+/// This is executed after routing but before middleware and resolution execution.
 
-        //lock for checks
-        let c_ref_lock = c_ref.lock().await;
+async fn set_request_variables(request: &mut Request, route_ref: RouteNodeRef) -> () {
+    let variables = RouteNode::variables(&route_ref, &request.route.cleaned_route).await;
+    request.variables.extend(variables);
+}
 
-        if c_ref_lock.is_var {
-            //clean the ID from {name} -> name
-            let mut id = c_ref_lock.id.clone();
-            id.remove(0);
-            id.remove(id.len() - 1);
+/// the routes serving everything under a prefix, the prefix itself and its wildcard.
+fn prefix_routes(prefix: &str) -> [String; 2] {
+    let prefix = prefix.trim_end_matches('/');
 
-            let is_wild = id.eq("*");
+    let base = match prefix.is_empty() {
+        true => "/".to_string(),
+        false => prefix.to_string(),
+    };
 
-            let value = if is_wild {
-                given_route_parts.push(route_part);
+    [base, format!("{prefix}/{{*}}")]
+}
 
-                given_route_parts
-                    .iter()
-                    .skip(wild_card_skip)
-                    .copied()
-                    .collect::<Vec<&str>>()
-                    .join("/")
-            } else {
-                route_part.to_string()
-            };
+/// Idiomatic type alias for the closure errors of the app are sent to.
+type ErrorCallback = Arc<Pin<Box<dyn Fn(String) + Send + Sync + 'static>>>;
 
-            req_ref.lock().await.variables.insert(id, value);
+/// Idiomatic type alias for a closure that converts the message of a panic into a resolution.
+pub type PanicHandler = dyn Fn(String) -> Box<dyn Resolution + Send> + Send + Sync;
 
-            if is_wild {
-                break;
-            }
-        }
+/// # Panic Message
+///
+/// The message of a caught panic.
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|m| m.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
 
-        current_ref = c_ref_lock.parent.clone();
+/// # Panic Resolution
+///
+/// Logs a caught panic with the route it happened in, then converts its message into a resolution with the `on_panic` handler, by default an empty `500`.
+fn panic_resolution(
+    on_panic: &Option<Arc<PanicHandler>>,
+    error_pages: &ErrorPages,
+    method: &Method,
+    route: &str,
+    message: String,
+) -> Box<dyn Resolution + Send> {
+    telemetry::error(&format!("{} {route} panicked: {message}", method.as_str()));
+
+    match on_panic {
+        Some(on_panic) => on_panic(message),
+        None => error_page(error_pages, 500),
     }
 }
 
+/// # Error Page
+///
+/// The page of a status the app generates, an empty resolution with the status without a page.
+fn error_page(error_pages: &ErrorPages, status: impl Into<StatusCode> + Copy) -> Box<dyn Resolution + Send> {
+    error_pages
+        .page(status)
+        .unwrap_or_else(|| EmptyResolution::status(status).resolve())
+}
+
 /// # Handle Client Request
 ///
 /// This function is called whenever a client is accepted from the tcp listener.
 ///
 /// Each time a client is accepted, the request is parsed, a route is found, middleware is called, and a endpoint is resolved.
 
-async fn handle_client_request(
-    client: (TcpStream, SocketAddr),
-    global_middleware: Arc<Mutex<Vec<MiddlewareClosure>>>,
-    router_ref: Arc<Mutex<RouteTree>>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let (mut stream, client_socket) = client;
+async fn handle_client_request<S>(
+    stream: &mut S,
+    client_socket: SocketAddr,
+    context: AppContext,
+    last: bool,
+) -> Result<bool, Box<dyn std::error::Error>>
+where
+    S: AsyncBufRead + AsyncWrite + Unpin,
+{
+    let AppContext {
+        router: router_ref,
+        global_middleware,
+        middleware_registry,
+        audit_log,
+        cache,
+        background,
+        supervisor,
+        on_panic,
+        route_stats,
+        body_limits,
+        head_limits,
+        keep_alive,
+        rejections,
+        cancel_on_disconnect,
+        trusted_proxies,
+        state,
+        buffers,
+        error_pages,
+        server_header,
+        route_names,
+        #[cfg(feature = "tls")]
+        tls,
+        ..
+    } = context;
+
+    let started = Instant::now();
 
     //process the acception and get the result from the stream
-    let request = Arc::new(Mutex::new(
-        Request::from_stream(&mut stream, client_socket).await?,
-    ));
+    let mut request = match Request::from_reader(stream, client_socket, body_limits, head_limits).await {
+        Ok(request) => request,
+        Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+            rejections.slow_head();
+            return Err(e.into());
+        }
+        Err(e) if HeadTooLarge::is(&e) => {
+            rejections.large_head();
 
-    //get the function to handle the resolution, backs up to a 404 if existant
-    let (cleaned_route, method) = {
-        let request_lock = request.lock().await;
-        (
-            request_lock.route.cleaned_route.clone(),
-            request_lock.method.clone(),
-        )
+            stream
+                .write_all(b"HTTP/1.1 431 Request Header Fields Too Large\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .await?;
+            stream.flush().await?;
+
+            return Err(e.into());
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
+            //a request that can not be parsed, such as one with an invalid target or length, is answered with 400 and the connection is closed.
+            let status = match UnsupportedTransferEncoding::is(&e) {
+                true => 501,
+                false => 400,
+            };
+
+            let mut request = Request::builder().client_socket(client_socket).build();
+            request.add_header("Connection".to_string(), Some("close".to_string()));
+
+            if let Some(server) = &server_header {
+                request.add_header(SERVER_HEADER.to_string(), Some(server.to_string()));
+            }
+
+            resolve(stream, &mut request, error_page(&error_pages, status), &buffers, false).await?;
+
+            return Err(e.into());
+        }
+        Err(e) => return Err(e.into()),
     };
 
-    let endpoint = {
+    //the rest of a discarded body is still unread, the connection can not be reused.
+    let wants_reuse = keep_alive.is_some() && request.keep_alive() && !request.body_too_large();
+    let reuse = wants_reuse && !last;
+
+    if wants_reuse && last {
+        rejections.request_limit();
+    }
+
+    if !reuse {
+        request.add_header("Connection".to_string(), Some("close".to_string()));
+    } else if request.version() == "HTTP/1.0" {
+        request.add_header("Connection".to_string(), Some("keep-alive".to_string()));
+    }
+
+    if let Some(server) = &server_header {
+        request.add_header(SERVER_HEADER.to_string(), Some(server.to_string()));
+    }
+
+    if let Some(audit_log) = &audit_log {
+        request.extensions.insert(audit_log.clone());
+    }
+
+    if let Some(trusted_proxies) = trusted_proxies {
+        request.extensions.insert(trusted_proxies);
+    }
+
+    #[cfg(feature = "tls")]
+    request.set_secure(tls.is_some());
+
+    request.extensions.insert(cache);
+    request.extensions.insert(background);
+    request.extensions.insert(supervisor);
+    request.extensions.insert(route_names);
+    request.extensions.insert(state);
+
+    //cancelled when the client disconnects while the request is handled.
+    let cancel = CancellationToken::new();
+    request.extensions.insert(cancel.clone());
+
+    //get the function to handle the resolution, backs up to a 404 if existant
+    let (cleaned_route, request_target, method) = (
+        request.route.cleaned_route.clone(),
+        request.route.init_route.clone(),
+        request.method.clone(),
+    );
+
+    let span = Span::request(&method, &cleaned_route);
+
+    let (endpoint, route_pattern, routed) = {
         let binding = router_ref.lock().await;
 
         let route = binding.get_route(&cleaned_route).await;
@@ -560,177 +2854,691 @@ async fn handle_client_request(
         match route {
             Some(r) => {
                 // This no longer deadlocks because the lock was dropped above
-                set_request_variables(request.clone(), r.clone()).await;
+                set_request_variables(&mut request, r.clone()).await;
+                let pattern = RouteNode::pattern(&r).await;
                 let route_lock = r.lock().await;
-                route_lock.brw_resolution(&method)
+                (route_lock.brw_resolution(&method), pattern, !route_lock.resolutions.is_empty())
             }
-            None => binding
-                .missing_route
-                .as_ref()
-                .and_then(|mr| mr.brw_resolution(&Method::GET)),
+            None => (
+                binding
+                    .missing_route
+                    .as_ref()
+                    .and_then(|mr| mr.brw_resolution(&Method::GET)),
+                UNMATCHED_ROUTE.to_string(),
+                false,
+            ),
         }
-        .and_then(|end_point_ref| Some(end_point_ref.clone()))
-    }
-    .ok_or(RoutingError::NoRouteExist)?;
+    };
+
+    let Some(endpoint) = endpoint else {
+        //a route without a handler for the method is a 405, otherwise a 404, the connection is closed without a page.
+        let status = if routed { 405 } else { 404 };
+        let page = error_pages.page(status).ok_or(RoutingError::NoRouteExist)?;
+
+        let (status, _) = span
+            .instrument(resolve(stream, &mut request, page, &buffers, false))
+            .await?;
 
-    //find any middleware function that when called, returns an Invalid or InvalidEmpty
-    let middleware_failed_resolution = {
-        //the given back final middleware.
-        let mut invalid_middleware = None;
+        span.finish(&status, started.elapsed());
+        route_stats.record(&route_pattern, &status, started.elapsed(), None).await;
 
-        let global_mw_guard = global_middleware.lock().await;
+        return Ok(reuse);
+    };
+
+    //expose the matched route to middleware before it is called.
+    request.matched = Some(MatchedRoute {
+        fallback: route_pattern == UNMATCHED_ROUTE,
+        pattern: route_pattern.clone(),
+        method: method.clone(),
+        named_middleware: endpoint.named_middleware.clone(),
+    });
 
-        //size of all middleware included
-        let mware_col_size =
-            global_mw_guard.len() + endpoint.middleware.as_ref().map(|mw| mw.len()).unwrap_or(0);
+    //the message of a panic in middleware or the handler, kept as the last error of the route.
+    let mut panicked = None;
 
-        let mut test_middleware = Vec::with_capacity(mware_col_size);
+    let body_too_large = request.body_too_large();
 
-        test_middleware.extend_from_slice(&global_mw_guard);
+    //the status of the first guard of the route the request fails.
+    let guarded = endpoint.guards.as_ref().and_then(|guards| guards.check(&request));
 
-        // ! Drop reference once we have all the function refs.
-        drop(global_mw_guard);
+    //a fresh cached response is sent before middleware, a miss is kept once it is written.
+    let cached = match (&endpoint.response_cache, &method) {
+        (Some(cache), Method::GET) if !body_too_large && guarded.is_none() => {
+            let key = cache.key(&request);
 
-        if let Some(route_middleware) = &endpoint.middleware {
-            test_middleware.extend_from_slice(route_middleware);
+            match cache.get(&key) {
+                Some(response) => Some(response.resolve()),
+                None => {
+                    request.extensions.insert(cache.claim(key));
+                    None
+                }
+            }
         }
+        _ => None,
+    };
 
-        for middleware_closure in test_middleware {
-            //call each middleware and map it out
-            match middleware_closure(request.clone()).await {
-                Middleware::Invalid(res) => {
-                    invalid_middleware = Some(res);
-                    break;
+    //the middleware and the handler, dropped if the client disconnects first.
+    let handled = async {
+        //find any middleware function that when called, returns an Invalid or InvalidEmpty
+        let middleware_failed_resolution = if body_too_large {
+            Some(error_page(&error_pages, 413))
+        } else if let Some(status) = guarded {
+            Some(error_page(&error_pages, status))
+        } else if cached.is_some() {
+            cached
+        } else {
+            //the given back final middleware.
+            let mut invalid_middleware = None;
+
+            let global_mw_guard = global_middleware.lock().await;
+
+            //size of all middleware included
+            let mware_col_size =
+                global_mw_guard.len() + endpoint.middleware.as_ref().map(|mw| mw.len()).unwrap_or(0);
+
+            let mut test_middleware = Vec::with_capacity(mware_col_size);
+
+            test_middleware.extend_from_slice(&global_mw_guard);
+
+            // ! Drop reference once we have all the function refs.
+            drop(global_mw_guard);
+
+            //registered middleware referenced by name, ordered by priority.
+            if !endpoint.named_middleware.is_empty() {
+                let registry = middleware_registry.lock().await;
+                test_middleware.extend(registry.resolve(&endpoint.named_middleware));
+            }
+
+            if let Some(route_middleware) = &endpoint.middleware {
+                test_middleware.extend_from_slice(route_middleware);
+            }
+
+            for middleware_closure in test_middleware {
+                //call each middleware and map it out, a panic is treated as a rejection.
+                let called = AssertUnwindSafe(middleware_closure(&mut request))
+                    .catch_unwind()
+                    .await;
+
+                let called = match called {
+                    Ok(called) => called,
+                    Err(payload) => {
+                        let message = panic_message(payload);
+                        panicked = Some(message.clone());
+                        Middleware::Invalid(panic_resolution(&on_panic, &error_pages, &method, &route_pattern, message))
+                    }
+                };
+
+                match called {
+                    Middleware::Invalid(res) => {
+                        invalid_middleware = Some(res);
+                        break;
+                    }
+                    Middleware::InvalidEmpty(status_code) => {
+                        invalid_middleware = Some(error_page(&error_pages, status_code));
+                        break;
+                    }
+                    Middleware::Next => continue,
+                };
+            }
+
+            invalid_middleware
+        };
+
+        //get either the failed middleware, or the endpoint resolution
+        match middleware_failed_resolution {
+            Some(resolved) => resolved,
+            None => {
+                let chain = std::iter::once(&endpoint.resolution).chain(&endpoint.fallbacks);
+                let mut resolved = None;
+
+                //try each resolution of the chain until one does not pass.
+                for resolution in chain {
+                    let attempt = AssertUnwindSafe(resolution(&mut request))
+                        .catch_unwind()
+                        .await
+                        .unwrap_or_else(|payload| {
+                            let message = panic_message(payload);
+                            panicked = Some(message.clone());
+                            panic_resolution(&on_panic, &error_pages, &method, &route_pattern, message)
+                        });
+
+                    let pass = attempt.is_pass();
+                    resolved = Some(attempt);
+
+                    if !pass {
+                        break;
+                    }
                 }
-                Middleware::InvalidEmpty(status_code) => {
-                    invalid_middleware = Some(EmptyResolution::status(status_code).resolve());
-                    break;
+
+                //every resolution passed, answered with the 404 page or the empty 404 of the pass resolution.
+                match resolved {
+                    Some(resolved) if !resolved.is_pass() => resolved,
+                    _ => error_pages.page(404).unwrap_or_else(|| PassResolution.resolve()),
                 }
-                Middleware::Next => continue,
-            };
+            }
+        }
+    };
+
+    //the middleware and the handler are dropped together once the timeout of the route runs out.
+    let timeout = endpoint.timeout;
+
+    let handled = async {
+        match timeout {
+            Some((duration, status)) => tokio::time::timeout(duration, handled)
+                .await
+                .unwrap_or_else(|_| error_page(&error_pages, status)),
+            None => handled.await,
+        }
+    };
+
+    let resolved = tokio::select! {
+        resolved = span.instrument(handled) => resolved,
+        _ = client_closed(stream), if cancel_on_disconnect => {
+            cancel.cancel();
+            client_closed_request(&span, &route_stats, &route_pattern, started).await;
+
+            return Ok(false);
         }
+    };
+
+    //finally resolve this and send the request, the body stream is dropped if the client leaves while it is sent.
+    let (status, body_size) = match span
+        .instrument(resolve(stream, &mut request, resolved, &buffers, cancel_on_disconnect))
+        .await
+    {
+        Ok(sent) => sent,
+        Err(e) if is_disconnect(&e) => {
+            cancel.cancel();
+            client_closed_request(&span, &route_stats, &route_pattern, started).await;
 
-        invalid_middleware
+            return Ok(false);
+        }
+        Err(e) => return Err(e.into()),
     };
 
-    //get either the failed middleware, or the endpoint resolution
-    let resolved =
-        middleware_failed_resolution.unwrap_or((endpoint.resolution)(request.clone()).await);
+    span.finish(&status, started.elapsed());
+
+    route_stats
+        .record(&route_pattern, &status, started.elapsed(), panicked)
+        .await;
+
+    //write the access log if the access log middleware ran.
+    let (access_log, request_id, client_ip) = (
+        request.extensions.get::<AccessLog>().cloned(),
+        request.request_id().map(|id| id.to_string()),
+        request.client_ip(),
+    );
+
+    if let Some(access_log) = access_log {
+        let mut record = AccessRecord::new(
+            client_ip,
+            &method.to_string(),
+            &request_target,
+            &status,
+            body_size,
+            started.elapsed(),
+        );
+
+        record.request_id = request_id;
+
+        access_log.record(record).await;
+    }
+
+    //count client errors towards a burst.
+    if let Some(audit_log) = audit_log
+        && status.starts_with('4')
+    {
+        audit_log
+            .client_error(client_socket.ip(), &cleaned_route, &status)
+            .await;
+    }
+
+    Ok(reuse)
+}
+
+/// writes every slice, in as few writes as the stream allows.
+async fn write_all_vectored<S>(stream: &mut S, mut slices: &mut [IoSlice<'_>]) -> std::io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    //empty slices are skipped.
+    IoSlice::advance_slices(&mut slices, 0);
 
-    //finally resolve this and send the request
-    resolve(&mut stream, request, resolved).await?;
+    while !slices.is_empty() {
+        let written = stream.write_vectored(slices).await?;
+
+        if written == 0 {
+            return Err(std::io::ErrorKind::WriteZero.into());
+        }
+
+        IoSlice::advance_slices(&mut slices, written);
+    }
 
     Ok(())
 }
 
+/// files at least this large are copied to the client by `copy_file`.
+const LARGE_FILE: u64 = 1024 * 1024;
+
+/// the size of the reads of `copy_file`.
+const FILE_COPY_BUFFER: usize = 256 * 1024;
+
+/// writes the head then copies `length` bytes of the file from `offset`, without a chunk allocated per read.
+///
+/// None if the file could not be opened, nothing is written.
+async fn copy_file<S>(
+    stream: &mut S,
+    head: &[u8],
+    path: &Path,
+    offset: u64,
+    length: u64,
+) -> std::io::Result<Option<u64>>
+where
+    S: AsyncWrite + Unpin,
+{
+    let Ok(mut file) = tokio::fs::File::open(path).await else {
+        return Ok(None);
+    };
+
+    if offset > 0 {
+        file.seek(SeekFrom::Start(offset)).await?;
+    }
+
+    stream.write_all(head).await?;
+
+    let mut reader = BufReader::with_capacity(FILE_COPY_BUFFER, file.take(length));
+    let copied = tokio::io::copy_buf(&mut reader, stream).await?;
+
+    //the file shrank, the client can not be given the length that was sent.
+    if copied < length {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+
+    Ok(Some(copied))
+}
+
+/// finishes the span and records the route of a request whose client disconnected as `499`.
+async fn client_closed_request(
+    span: &Span,
+    route_stats: &RouteStats,
+    route_pattern: &str,
+    started: Instant,
+) {
+    span.finish("499 Client Closed Request", started.elapsed());
+
+    route_stats
+        .record(route_pattern, "499 Client Closed Request", started.elapsed(), None)
+        .await;
+}
+
+/// true if the error means the client is gone, such as a broken pipe.
+fn is_disconnect(error: &std::io::Error) -> bool {
+    matches!(
+        error.kind(),
+        std::io::ErrorKind::BrokenPipe
+            | std::io::ErrorKind::ConnectionReset
+            | std::io::ErrorKind::ConnectionAborted
+            | std::io::ErrorKind::NotConnected
+            | std::io::ErrorKind::WriteZero
+    )
+}
+
+/// waits until the client closes the connection, forever once it sends more data instead.
+async fn client_closed<S>(stream: &mut S)
+where
+    S: AsyncBufRead + Unpin,
+{
+    //the data is left in the buffer for the next request.
+    if let Ok(buffered) = stream.fill_buf().await
+        && !buffered.is_empty()
+    {
+        std::future::pending::<()>().await;
+    }
+}
+
+/// the head of a 1xx response, None if the status is not 1xx or switches protocols.
+fn informational_head(mut headers: HeaderMap) -> Option<String> {
+    let status = headers.remove(STATUS_KEY).flatten()?;
+
+    if !status.starts_with('1') || status.starts_with("101") {
+        return None;
+    }
+
+    let mut head = format!("{STATUS_KEY} {status}\r\n");
+
+    for (key, value) in headers {
+        match value {
+            Some(value) => head.push_str(&format!("{key}:{value}\r\n")),
+            None => head.push_str(&format!("{key}\r\n")),
+        }
+    }
+
+    head.push_str("\r\n");
+
+    Some(head)
+}
+
 /// # Resolve
 ///
-/// Takes a boxed resolution and TcpStream(client)
+/// Takes a boxed resolution and the stream of the client
 ///
 /// The function does the following:
 ///
-/// i. push the transfer encoding header
+/// i. push the transfer encoding header, unless the resolution set a `Content-Length`
 ///
-/// ii. write all headers required to the stream
+/// ii. write all headers required into a buffer of the pool
 ///
 /// iii. retrieves the content stream
 ///
-/// iv. loops over the content stream chunk by chunk, writing to the client
+/// iv. loops over the content stream chunk by chunk, writing to the client with the headers before the first, as chunks or as is when the length is known
+///
+/// v. writes the termination of the stream when stream ends, then flushes the stream
 ///
-/// v. writes the termination of the stream when stream ends
-async fn resolve(
-    stream: &mut TcpStream,
-    request: Arc<Mutex<Request>>,
+/// The headers, chunk frames, and chunks are written together with vectored writes, without copying them into one buffer.
+///
+/// A large file, see `Resolution::source_file`, is copied from the file after the headers instead of streamed.
+///
+/// When `watch_disconnect` is set, waiting on the content stream ends with a `BrokenPipe` error once the client closes the connection, so the stream is dropped instead of producing chunks for no one.
+///
+/// Returns the status that was written, for example "404 Not Found", and the size of the body in bytes.
+async fn resolve<S>(
+    stream: &mut S,
+    request: &mut Request,
     resolved: Box<dyn Resolution + Send>,
-) -> Result<(), std::io::Error> {
+    buffers: &BufferPool,
+    watch_disconnect: bool,
+) -> Result<(String, usize), std::io::Error>
+where
+    S: AsyncBufRead + AsyncWrite + Unpin,
+{
     //maps the header from a k,v to a String
 
     // collect all of our headers from the resolution and the middleware
     let headers = resolved.get_headers();
 
-    let mut req_guard = request.lock().await;
-
-    let mut response_headers = req_guard.take_headers().ok_or(std::io::Error::new(
+    let mut response_headers = request.take_headers().ok_or(std::io::Error::new(
         std::io::ErrorKind::InvalidData,
         "the headers were already taken",
     ))?;
 
-    // ! no need for the request guard.
-    drop(req_guard);
+    let throttle = request.throttle().cloned();
+    let cookies = request.take_cookies();
+    let idempotency_claim = request.extensions.remove::<IdempotencyClaim>();
+    let cache_claim = request.extensions.remove::<ResponseCacheClaim>();
+    let head_request = matches!(&request.method, Method::Other(m) if m.eq_ignore_ascii_case("HEAD"));
+    let range_header = match request.method {
+        Method::GET => request.request_header("Range").cloned(),
+        _ => None,
+    };
+
+    //responses setting cookies are never cached.
+    let cache_claim = cache_claim.filter(|_| cookies.is_empty());
+
+    //the resolution headers and body are kept for replays of the idempotency key, and for the response cache.
+    let max_body = [
+        idempotency_claim.as_ref().map(IdempotencyClaim::max_body),
+        cache_claim.as_ref().map(ResponseCacheClaim::max_body),
+    ]
+    .into_iter()
+    .flatten()
+    .max();
+
+    let mut replay = max_body.map(|max_body| {
+        let mut headers = headers.clone();
+        headers.remove(STATUS_KEY);
+
+        (headers, Some(Vec::new()), max_body)
+    });
 
     //insert our headers from the resolution onto our
-    for (key, val) in headers {
-        response_headers.insert(key, val);
+    response_headers.merge(headers);
+
+    //every response is dated, unless the resolution dated it.
+    if !response_headers.contains(DATE_HEADER) {
+        response_headers.insert(DATE_HEADER, Some(http_date::now()));
+    }
+
+    //cookies can repeat, each is its own header, they were validated by `set_cookie`.
+    for value in cookies.iter().filter_map(|cookie| cookie.header_value().ok()) {
+        response_headers.append_set_cookie(&value);
     }
 
-    let first_rep_key = "HTTP/1.1";
-    let status = response_headers
-        .remove(first_rep_key)
+    let mut status = response_headers
+        .remove(STATUS_KEY)
         .map(|s| s.expect("you must include a status"))
         .unwrap_or_else(|| "200 OK".to_string());
 
-    //the header string to convert to bytes
-    let mut header_str = String::new();
+    //seekable content answers range requests.
+    let mut ranged_content = None;
+    let mut content_offset = 0;
 
-    let status_header = format!("{first_rep_key} {status}\r\n");
-    header_str.push_str(&status_header);
+    //a length in the headers is used over the length of the content.
+    if !response_headers.contains("Content-Length")
+        && let Some(length) = resolved.content_length()
+    {
+        response_headers.set_content_length(length);
+    }
 
-    //Fn to format the headers into a single string
-    let format_headers = |(key, val): (String, Option<String>)| {
-        let value = match val {
-            None => "".to_string(),
-            Some(v) => format!(":{v}"),
-        };
+    if let Some(total) = resolved.seekable_len() {
+        response_headers.insert("Accept-Ranges".to_string(), Some("bytes".to_string()));
+
+        if let Some(range_header) = range_header.filter(|_| status.starts_with("200")) {
+            match range::parse_range(&range_header, total) {
+                RangeRequest::Full => {}
+                RangeRequest::Partial(byte_range) => {
+                    status = get_status_header(206).1;
+                    response_headers.insert(
+                        "Content-Range".to_string(),
+                        Some(byte_range.content_range(total)),
+                    );
+                    response_headers.set_content_length(byte_range.len());
+                    content_offset = byte_range.start;
+                    ranged_content = Some(range::take_bytes(
+                        resolved.seek(byte_range.start),
+                        byte_range.len(),
+                    ));
+                }
+                RangeRequest::Unsatisfiable => {
+                    status = get_status_header(416).1;
+                    response_headers
+                        .insert("Content-Range".to_string(), Some(format!("bytes */{total}")));
+                    response_headers.set_content_length(0);
+                    ranged_content = Some(Box::pin(futures::stream::empty()) as _);
+                }
+            }
+        }
+    }
 
-        format!("{key}{value}")
-    };
+    //204, 304, and 1xx responses never have a body, nor a length.
+    let bodiless = status.starts_with('1') || status.starts_with("204") || status.starts_with("304");
 
-    //pushes the formatted header into the header_str
-    let push_to_str = |s: String| {
-        header_str.push_str(&s);
-        header_str.push_str("\r\n");
-    };
+    if bodiless {
+        response_headers.remove("Content-Length");
+    }
+
+    //a HEAD response has the headers of the response without its body.
+    let no_body = bodiless || head_request;
+
+    //content of a known length is written as is, the rest is chunked.
+    let mut remaining = response_headers
+        .get("Content-Length")
+        .and_then(|length| length.trim().parse::<u64>().ok());
+
+    if remaining.is_none() {
+        response_headers.remove("Content-Length");
+    }
+
+    //1xx responses, such as early hints, are sent before the response.
+    for informational in resolved.informational() {
+        if let Some(head) = informational_head(informational) {
+            stream.write_all(head.as_bytes()).await?;
+        }
+    }
+
+    //the head is written into the buffer of the pool, it is sent with the first chunk of the body.
+    let mut scratch = buffers.take();
+
+    write!(scratch, "{STATUS_KEY} {status}\r\n")?;
 
-    //converts all the headers into a single string.
-    response_headers
-        .into_iter()
-        .map(format_headers) // map these items to an appropriate format.
-        .for_each(push_to_str); //foreach string push onto the string.
+    for (key, value) in response_headers {
+        scratch.extend_from_slice(key.as_bytes());
+
+        if let Some(value) = value {
+            scratch.push(b':');
+            scratch.extend_from_slice(value.as_bytes());
+        }
+
+        scratch.extend_from_slice(b"\r\n");
+    }
 
     // ? tell the client this is streamed
-    header_str.push_str("Transfer-Encoding: chunked\r\n\r\n");
+    if remaining.is_none() && !bodiless {
+        scratch.extend_from_slice(b"Transfer-Encoding: chunked\r\n");
+    }
+
+    scratch.extend_from_slice(b"\r\n");
+
+    //large files are copied from the file to the client, unless the body is throttled or kept.
+    let source_file = match (remaining, &throttle, &replay) {
+        (Some(length), None, None) if length >= LARGE_FILE && !no_body => resolved.source_file(),
+        _ => None,
+    };
+
+    if let Some(path) = source_file
+        && let Some(copied) =
+            copy_file(stream, &scratch, &path, content_offset, remaining.unwrap_or_default()).await?
+    {
+        stream.flush().await?;
+
+        return Ok((status, copied as usize));
+    }
 
-    // ! write the headers to the stream.
-    stream.write_all(header_str.as_bytes()).await?;
+    let mut head_written = false;
 
-    let mut content_stream = resolved.get_content();
+    let mut content_stream = match no_body {
+        true => Box::pin(futures::stream::empty()),
+        false => ranged_content.unwrap_or_else(|| resolved.get_content()),
+    };
+    let mut body_size = 0;
+
+    //retrieve the next chunk of the body, unless the client left while it is produced.
+    loop {
+        let next = tokio::select! {
+            next = content_stream.next() => next,
+            _ = client_closed(stream), if watch_disconnect => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::BrokenPipe,
+                    "the client disconnected during the response",
+                ));
+            }
+        };
+
+        let Some(mut chunk) = next else {
+            break;
+        };
+
+        //never write past the length that was sent, the file may have grown.
+        if let Some(remaining) = &mut remaining {
+            chunk.truncate((*remaining).min(chunk.len() as u64) as usize);
+            *remaining -= chunk.len() as u64;
+        }
 
-    //retrieve the next chunk of the body
-    while let Some(chunk) = content_stream.next().await {
         let size = chunk.len();
 
         if size <= 0 {
             continue; //nothing to write 
         }
 
-        //create the size header for the stream chunk
-        let size_header = format!("{size:X}\r\n");
-        let size_header = size_header.as_bytes();
+        body_size += size;
+
+        if let Some((_, captured, max_body)) = &mut replay {
+            match captured {
+                Some(body) if body.len() + size <= *max_body => body.extend_from_slice(&chunk),
+                _ => *captured = None,
+            }
+        }
+
+        //a chunk of a streamed body is framed by its size in hex and a terminator.
+        let mut size_header = [0u8; 18];
+
+        let (size_header, terminator): (&[u8], &[u8]) = match remaining {
+            Some(_) => (&[], &[]),
+            None => {
+                let len = {
+                    let mut cursor = &mut size_header[..];
+                    write!(cursor, "{size:X}\r\n")?;
+                    18 - cursor.len()
+                };
+
+                (&size_header[..len], b"\r\n")
+            }
+        };
+
+        //write ONCE without copying the chunk, unless throttled, then write as the throttle allows.
+        match &throttle {
+            None => {
+                let head: &[u8] = if head_written { &[] } else { &scratch };
+
+                write_all_vectored(
+                    stream,
+                    &mut [
+                        IoSlice::new(head),
+                        IoSlice::new(size_header),
+                        IoSlice::new(&chunk),
+                        IoSlice::new(terminator),
+                    ],
+                )
+                .await?;
+            }
+            Some(throttle) => {
+                if !head_written {
+                    stream.write_all(&scratch).await?;
+                }
 
-        //create a buffer that will hold this chunk data
-        let mut buffer = Vec::with_capacity(size_header.len() + chunk.len() + 2);
+                //the throttle writes the frame in pieces, so it is copied into one buffer.
+                scratch.clear();
+                scratch.extend_from_slice(size_header);
+                scratch.extend_from_slice(&chunk);
+                scratch.extend_from_slice(terminator);
 
-        //the buffer is comprised of the size header, the data chunk, the terminator for the chunk.
-        buffer.extend_from_slice(size_header);
-        buffer.extend_from_slice(&chunk);
-        buffer.extend_from_slice(b"\r\n");
+                for piece in scratch.chunks(throttle.chunk_size()) {
+                    throttle.consume(piece.len()).await;
+                    stream.write_all(piece).await?;
+                }
+            }
+        }
 
-        //write ONCE
-        stream.write_all(&buffer).await?;
+        head_written = true;
     }
 
-    //indicate end of stream
-    stream.write_all(b"0\r\n\r\n").await?;
+    //the content ended before the length that was sent, the client would wait for the rest, so the connection is closed.
+    if !no_body && remaining.is_some_and(|remaining| remaining > 0) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "the content was shorter than its Content-Length",
+        ));
+    }
 
-    Ok(())
+    //indicate end of stream, with the head of a response without a body.
+    let head: &[u8] = if head_written { &[] } else { &scratch };
+    let end: &[u8] = if remaining.is_none() && !no_body { b"0\r\n\r\n" } else { &[] };
+
+    write_all_vectored(stream, &mut [IoSlice::new(head), IoSlice::new(end)]).await?;
+
+    //short responses are sent now instead of waiting in a buffer of the stream.
+    stream.flush().await?;
+
+    if let Some((headers, body, _)) = replay {
+        if let Some(claim) = cache_claim {
+            let body = body.clone().filter(|body| body.len() <= claim.max_body());
+            claim.complete(&status, headers.clone(), body);
+        }
+
+        if let Some(claim) = idempotency_claim {
+            let body = body.filter(|body| body.len() <= claim.max_body());
+            claim.complete(&status, headers, body);
+        }
+    }
+
+    Ok((status, body_size))
 }
@@ -0,0 +1,710 @@
+use std::{path::Path, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    factory::{AutoscaleConfig, QueuePolicy},
+    web::{
+        App,
+        connection_limit::{ConnectionLimit, Overflow},
+        errors::ConfigError,
+        execution::ExecutionStrategy,
+        routing::request::{BodyLimits, HeadLimits},
+        socket_options::SocketOptions,
+        static_files::StaticFiles,
+        telemetry,
+    },
+};
+
+/// The prefix of the environment variables that override the file of `App::from_config`, `ASYNC_WEB_WORKERS=100`.
+pub const ENV_PREFIX: &str = "ASYNC_WEB_";
+
+/// # Log Level
+///
+/// What an app built from an [`AppConfig`] writes, see `App::set_error_callback` and `App::set_startup_banner`.
+///
+/// With the `tracing` feature they are written as `tracing` events instead of to stderr and stdout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    /// Nothing is written.
+    #[default]
+    Off,
+    /// Errors of the app are written to stderr.
+    Error,
+    /// Errors are written to stderr and the startup banner to stdout.
+    Info,
+}
+
+/// # Static Mount
+///
+/// A directory served under a prefix, see `App::serve_static_with`.
+///
+/// ```toml
+/// [[static_mounts]]
+/// prefix = "/assets"
+/// root = "./public"
+/// max_age = 3600
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaticMount {
+    /// The route prefix, `/assets`.
+    pub prefix: String,
+
+    /// The directory of the files, `./public`.
+    pub root: String,
+
+    /// Lists the files of directories without an index.
+    #[serde(default)]
+    pub listing: bool,
+
+    /// How long clients may cache files without revalidating, in seconds.
+    pub max_age: Option<u64>,
+}
+
+impl StaticMount {
+    /// The static files of the mount.
+    pub fn files(&self) -> StaticFiles {
+        let files = StaticFiles::new(&self.root).listing(self.listing);
+
+        match self.max_age {
+            Some(max_age) => files.max_age(Duration::from_secs(max_age)),
+            None => files,
+        }
+    }
+}
+
+/// # App Config
+///
+/// The settings of an app, read from TOML, JSON, or the environment and applied with [`AppBuilder::config`], or loaded with `App::from_config`.
+///
+/// Settings that are missing keep their default.
+///
+/// ```toml
+/// addr = "0.0.0.0:8080"
+/// workers = 100
+/// max_body_size = 1048576
+/// keep_alive = 5
+/// log_level = "info"
+///
+/// [[static_mounts]]
+/// prefix = "/assets"
+/// root = "./public"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    /// The address the app binds to.
+    ///
+    /// By default (127.0.0.1:8080)
+    pub addr: String,
+
+    /// More addresses the app binds to, see `App::also_bind`.
+    ///
+    /// By default (none)
+    pub also_bind: Vec<String>,
+
+    /// The workers started with the app.
+    ///
+    /// By default (1)
+    pub workers: usize,
+
+    /// The factor at which the workers scale when every worker is busy.
+    ///
+    /// By default (10)
+    pub worker_scale_factor: usize,
+
+    /// The accept loops of each listener, see `App::set_acceptors`.
+    ///
+    /// By default (1)
+    pub acceptors: usize,
+
+    /// The fewest workers kept by the autoscaler, see `App::set_autoscale`, used with `max_workers`.
+    ///
+    /// By default (None) the workers are not autoscaled.
+    pub min_workers: Option<usize>,
+
+    /// The most workers started by the autoscaler, see `App::set_autoscale`.
+    ///
+    /// By default (None) the workers are not autoscaled.
+    pub max_workers: Option<usize>,
+
+    /// Interleaves queued connections by client IP, see `App::set_fair_queuing`.
+    ///
+    /// By default (false)
+    pub fair_queuing: bool,
+
+    /// The most connections waiting for a worker, see `App::set_queue_capacity`.
+    ///
+    /// By default (None) the queue is unbounded.
+    pub queue_capacity: Option<usize>,
+
+    /// What is done with connections over `queue_capacity`, `block`, `drop_oldest`, or `reject`.
+    ///
+    /// By default (block)
+    pub queue_policy: QueuePolicy,
+
+    /// How long a connection may wait for a worker, in milliseconds, see `App::set_queue_timeout`.
+    ///
+    /// By default (None) connections wait until a worker takes them.
+    pub queue_timeout_ms: Option<u64>,
+
+    /// Runs connections on the workers or spawns each of them, see `App::set_execution`.
+    ///
+    /// By default (worker_pool)
+    pub execution: ExecutionStrategy,
+
+    /// The largest request body that is read, in bytes.
+    ///
+    /// By default (16 MiB)
+    pub max_body_size: usize,
+
+    /// The most bytes of a rejected body that are discarded, in bytes.
+    ///
+    /// By default (64 KiB)
+    pub max_body_drain: usize,
+
+    /// How long discarding a rejected body may take, in milliseconds.
+    ///
+    /// By default (1000)
+    pub body_drain_timeout_ms: u64,
+
+    /// How long a client may take to send each line of the head, in milliseconds, see `App::set_head_limits`.
+    ///
+    /// By default (10000)
+    pub head_line_timeout_ms: u64,
+
+    /// The most bytes of the request line and headers, in bytes.
+    ///
+    /// By default (64 KiB)
+    pub max_head_size: usize,
+
+    /// The most segments of the path of a request target.
+    ///
+    /// By default (128)
+    pub max_path_segments: usize,
+
+    /// Accepts request targets in absolute-form, as sent to proxies.
+    ///
+    /// By default (false)
+    pub allow_absolute_form: bool,
+
+    /// Accepts `CONNECT` requests.
+    ///
+    /// By default (false)
+    pub allow_connect: bool,
+
+    /// How long a connection may idle between requests, in seconds, see `App::set_keep_alive`.
+    ///
+    /// By default (None) each connection is closed after its request.
+    pub keep_alive: Option<u64>,
+
+    /// The most requests one connection may make, see `App::set_max_requests_per_connection`.
+    ///
+    /// By default (1000)
+    pub max_requests_per_connection: Option<usize>,
+
+    /// The most connections served at once, see `App::set_connection_limit`.
+    ///
+    /// By default (None) every connection is accepted.
+    pub max_connections: Option<usize>,
+
+    /// What is done with connections over `max_connections`, `backpressure` or `reject`.
+    ///
+    /// By default (backpressure)
+    pub connection_overflow: Overflow,
+
+    /// The options of the listeners and accepted connections, the `[socket]` table.
+    ///
+    /// By default see [`SocketOptions`].
+    pub socket: SocketOptions,
+
+    /// Directories served under a prefix.
+    ///
+    /// By default (none)
+    pub static_mounts: Vec<StaticMount>,
+
+    /// What the app writes, `off`, `error`, or `info`.
+    ///
+    /// By default (off)
+    pub log_level: LogLevel,
+
+    /// The PEM certificate chain served over TLS, used with `tls_key`.
+    ///
+    /// Requires the `tls` feature.
+    #[cfg(feature = "tls")]
+    pub tls_cert: Option<String>,
+
+    /// The PEM private key of the `tls_cert`.
+    ///
+    /// Requires the `tls` feature.
+    #[cfg(feature = "tls")]
+    pub tls_key: Option<String>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        let body_limits = BodyLimits::default();
+        let head_limits = HeadLimits::default();
+
+        Self {
+            addr: "127.0.0.1:8080".to_string(),
+            also_bind: Vec::new(),
+            workers: 1,
+            worker_scale_factor: 10,
+            acceptors: 1,
+            min_workers: None,
+            max_workers: None,
+            fair_queuing: false,
+            queue_capacity: None,
+            queue_policy: QueuePolicy::Block,
+            queue_timeout_ms: None,
+            execution: ExecutionStrategy::WorkerPool,
+            max_body_size: body_limits.max_size,
+            max_body_drain: body_limits.max_drain,
+            body_drain_timeout_ms: body_limits.drain_timeout.as_millis() as u64,
+            head_line_timeout_ms: head_limits.line_timeout.as_millis() as u64,
+            max_head_size: head_limits.max_size,
+            max_path_segments: head_limits.max_segments,
+            allow_absolute_form: head_limits.allow_absolute_form,
+            allow_connect: head_limits.allow_connect,
+            keep_alive: None,
+            max_requests_per_connection: Some(1000),
+            max_connections: None,
+            connection_overflow: Overflow::Backpressure,
+            socket: SocketOptions::default(),
+            static_mounts: Vec::new(),
+            log_level: LogLevel::Off,
+            #[cfg(feature = "tls")]
+            tls_cert: None,
+            #[cfg(feature = "tls")]
+            tls_key: None,
+        }
+    }
+}
+
+impl AppConfig {
+    /// Parses a config from TOML.
+    pub fn from_toml(source: &str) -> Result<Self, ConfigError> {
+        toml::from_str(source).map_err(|e| ConfigError::Parse(e.to_string()))
+    }
+
+    /// Parses a config from JSON.
+    pub fn from_json(source: &str) -> Result<Self, ConfigError> {
+        serde_json::from_str(source).map_err(|e| ConfigError::Parse(e.to_string()))
+    }
+
+    /// # From File
+    ///
+    /// Reads a config from a `.toml` or `.json` file.
+    pub async fn from_file(path: &str) -> Result<Self, ConfigError> {
+        let source = tokio::fs::read_to_string(path).await?;
+
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("json") => Self::from_json(&source),
+            Some("toml") => Self::from_toml(&source),
+            _ => Err(ConfigError::Parse(format!(
+                "'{path}' is not a .toml or .json file"
+            ))),
+        }
+    }
+
+    /// # From Env
+    ///
+    /// Reads a config from the environment variables starting with the prefix, `APP_WORKERS=100` sets `workers` for the prefix `APP_`.
+    pub fn from_env(prefix: &str) -> Result<Self, ConfigError> {
+        Self::from_vars(prefix, std::env::vars())
+    }
+
+    /// # From Vars
+    ///
+    /// Reads a config from variables named like the environment of `from_env`, such as the lines of a `.env` file.
+    ///
+    /// Values are read as TOML values, `100` is a number and `true` a boolean, values that are not valid TOML are strings.
+    pub fn from_vars<I>(prefix: &str, vars: I) -> Result<Self, ConfigError>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        Self::default().override_vars(prefix, vars)
+    }
+
+    /// # Override Env
+    ///
+    /// Replaces the settings that are set by environment variables starting with the prefix, see `from_env`.
+    pub fn override_env(self, prefix: &str) -> Result<Self, ConfigError> {
+        self.override_vars(prefix, std::env::vars())
+    }
+
+    /// # Override Vars
+    ///
+    /// Replaces the settings that are set by the variables, named and read like the environment of `from_env`.
+    pub fn override_vars<I>(self, prefix: &str, vars: I) -> Result<Self, ConfigError>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut table = match toml::Value::try_from(self) {
+            Ok(toml::Value::Table(table)) => table,
+            Ok(_) => toml::Table::new(),
+            Err(e) => return Err(ConfigError::Parse(e.to_string())),
+        };
+
+        for (name, value) in vars {
+            let Some(key) = name.strip_prefix(prefix) else {
+                continue;
+            };
+
+            let value = toml::from_str::<toml::Table>(&format!("value = {value}"))
+                .ok()
+                .and_then(|mut parsed| parsed.remove("value"))
+                .unwrap_or(toml::Value::String(value));
+
+            table.insert(key.to_lowercase(), value);
+        }
+
+        table
+            .try_into()
+            .map_err(|e: toml::de::Error| ConfigError::Parse(e.to_string()))
+    }
+
+    /// The connection limit of the config, None without `max_connections`.
+    pub fn connection_limit(&self) -> Option<ConnectionLimit> {
+        self.max_connections
+            .map(|max| ConnectionLimit::new(max).overflow(self.connection_overflow))
+    }
+
+    /// # Autoscale
+    ///
+    /// The autoscaler of the config, None without `min_workers` or `max_workers`.
+    ///
+    /// A missing bound is `workers` for the min and the default for the max.
+    pub fn autoscale(&self) -> Option<AutoscaleConfig> {
+        if self.min_workers.is_none() && self.max_workers.is_none() {
+            return None;
+        }
+
+        let default = AutoscaleConfig::default();
+
+        Some(AutoscaleConfig {
+            min_workers: self.min_workers.unwrap_or(self.workers),
+            max_workers: self.max_workers.unwrap_or(default.max_workers),
+            ..default
+        })
+    }
+
+    /// The body limits of the config.
+    pub fn body_limits(&self) -> BodyLimits {
+        BodyLimits {
+            max_size: self.max_body_size,
+            max_drain: self.max_body_drain,
+            drain_timeout: Duration::from_millis(self.body_drain_timeout_ms),
+        }
+    }
+
+    /// The head limits of the config.
+    pub fn head_limits(&self) -> HeadLimits {
+        HeadLimits {
+            line_timeout: Duration::from_millis(self.head_line_timeout_ms),
+            max_size: self.max_head_size,
+            max_segments: self.max_path_segments,
+            allow_absolute_form: self.allow_absolute_form,
+            allow_connect: self.allow_connect,
+        }
+    }
+}
+
+/// # App Builder
+///
+/// Configures an app before it is bound, so settings that must be set before the app starts are given in one place.
+///
+/// Created with `App::builder`, settings that are not given keep the defaults of [`AppConfig`].
+///
+/// ```ignore
+/// let config = AppConfig::from_file("app.toml").await?;
+///
+/// let mut app = App::builder()
+///     .config(&config)
+///     .workers(100)
+///     .request_limits(BodyLimits { max_size: 1024 * 1024, ..Default::default() })
+///     .build()
+///     .await?;
+/// ```
+pub struct AppBuilder {
+    addr: String,
+    also_bind: Vec<String>,
+    workers: usize,
+    worker_scale_factor: usize,
+    acceptors: usize,
+    autoscale: Option<AutoscaleConfig>,
+    fair_queuing: bool,
+    queue_capacity: Option<usize>,
+    queue_policy: QueuePolicy,
+    queue_timeout: Option<Duration>,
+    execution: ExecutionStrategy,
+    body_limits: BodyLimits,
+    head_limits: HeadLimits,
+    keep_alive: Option<Duration>,
+    max_requests_per_connection: Option<usize>,
+    connection_limit: Option<ConnectionLimit>,
+    socket_options: SocketOptions,
+    static_mounts: Vec<StaticMount>,
+    log_level: LogLevel,
+    #[cfg(feature = "tls")]
+    tls: Option<std::sync::Arc<dyn crate::web::tls::ResolvesServerCert>>,
+    #[cfg(feature = "tls")]
+    tls_files: Option<(String, String)>,
+}
+
+impl AppBuilder {
+    /// Create a builder with the default settings.
+    pub fn new() -> Self {
+        let config = AppConfig::default();
+
+        Self {
+            addr: config.addr.clone(),
+            also_bind: config.also_bind.clone(),
+            workers: config.workers,
+            worker_scale_factor: config.worker_scale_factor,
+            acceptors: config.acceptors,
+            autoscale: None,
+            fair_queuing: config.fair_queuing,
+            queue_capacity: config.queue_capacity,
+            queue_policy: config.queue_policy,
+            queue_timeout: config.queue_timeout_ms.map(Duration::from_millis),
+            execution: config.execution,
+            body_limits: config.body_limits(),
+            head_limits: config.head_limits(),
+            keep_alive: None,
+            max_requests_per_connection: config.max_requests_per_connection,
+            connection_limit: None,
+            socket_options: config.socket.clone(),
+            static_mounts: Vec::new(),
+            log_level: LogLevel::Off,
+            #[cfg(feature = "tls")]
+            tls: None,
+            #[cfg(feature = "tls")]
+            tls_files: None,
+        }
+    }
+
+    /// # Config
+    ///
+    /// Uses every setting of the config, settings given after replace them.
+    pub fn config(mut self, config: &AppConfig) -> Self {
+        self.addr = config.addr.clone();
+        self.also_bind = config.also_bind.clone();
+        self.workers = config.workers;
+        self.worker_scale_factor = config.worker_scale_factor;
+        self.acceptors = config.acceptors;
+        self.autoscale = config.autoscale();
+        self.fair_queuing = config.fair_queuing;
+        self.queue_capacity = config.queue_capacity;
+        self.queue_policy = config.queue_policy;
+        self.queue_timeout = config.queue_timeout_ms.map(Duration::from_millis);
+        self.execution = config.execution;
+        self.body_limits = config.body_limits();
+        self.head_limits = config.head_limits();
+        self.keep_alive = config.keep_alive.map(Duration::from_secs);
+        self.max_requests_per_connection = config.max_requests_per_connection;
+        self.connection_limit = config.connection_limit();
+        self.socket_options = config.socket.clone();
+        self.static_mounts = config.static_mounts.clone();
+        self.log_level = config.log_level;
+
+        #[cfg(feature = "tls")]
+        if let (Some(cert), Some(key)) = (&config.tls_cert, &config.tls_key) {
+            self.tls_files = Some((cert.clone(), key.clone()));
+        }
+
+        self
+    }
+
+    /// The address the app binds to, `0.0.0.0:8080`.
+    pub fn addr(mut self, addr: &str) -> Self {
+        self.addr = addr.to_string();
+        self
+    }
+
+    /// Binds another address, see `App::also_bind`.
+    pub fn also_bind(mut self, addr: &str) -> Self {
+        self.also_bind.push(addr.to_string());
+        self
+    }
+
+    /// The workers started with the app.
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers;
+        self
+    }
+
+    /// The factor at which the workers scale when every worker is busy.
+    pub fn worker_scale_factor(mut self, worker_scale_factor: usize) -> Self {
+        self.worker_scale_factor = worker_scale_factor;
+        self
+    }
+
+    /// Accepts each listener with several loops, such as one per core, see `App::set_acceptors`.
+    pub fn acceptors(mut self, acceptors: usize) -> Self {
+        self.acceptors = acceptors;
+        self
+    }
+
+    /// Grows and shrinks the workers with the load, see `App::set_autoscale`.
+    pub fn autoscale(mut self, config: AutoscaleConfig) -> Self {
+        self.autoscale = Some(config);
+        self
+    }
+
+    /// Interleaves queued connections by client IP, see `App::set_fair_queuing`.
+    pub fn fair_queuing(mut self, fair: bool) -> Self {
+        self.fair_queuing = fair;
+        self
+    }
+
+    /// Bounds the connections waiting for a worker, see `App::set_queue_capacity`.
+    pub fn queue_capacity(mut self, capacity: usize, policy: QueuePolicy) -> Self {
+        self.queue_capacity = Some(capacity);
+        self.queue_policy = policy;
+        self
+    }
+
+    /// Answers connections that waited longer than the timeout for a worker with `503`, see `App::set_queue_timeout`.
+    pub fn queue_timeout(mut self, timeout: Duration) -> Self {
+        self.queue_timeout = Some(timeout);
+        self
+    }
+
+    /// Runs connections on the workers or spawns each of them, see `App::set_execution`.
+    pub fn execution(mut self, execution: ExecutionStrategy) -> Self {
+        self.execution = execution;
+        self
+    }
+
+    /// Limits on the body of each request, see `App::set_body_limits`.
+    pub fn request_limits(mut self, limits: BodyLimits) -> Self {
+        self.body_limits = limits;
+        self
+    }
+
+    /// Limits on the request line and headers of each request, see `App::set_head_limits`.
+    pub fn head_limits(mut self, limits: HeadLimits) -> Self {
+        self.head_limits = limits;
+        self
+    }
+
+    /// Reuses connections until they idle for the duration, see `App::set_keep_alive`.
+    pub fn keep_alive(mut self, idle: Duration) -> Self {
+        self.keep_alive = Some(idle);
+        self
+    }
+
+    /// Closes connections after the most requests they may make, see `App::set_max_requests_per_connection`.
+    pub fn max_requests_per_connection(mut self, max: Option<usize>) -> Self {
+        self.max_requests_per_connection = max;
+        self
+    }
+
+    /// Caps the connections served at once, see `App::set_connection_limit`.
+    pub fn connection_limit(mut self, limit: ConnectionLimit) -> Self {
+        self.connection_limit = Some(limit);
+        self
+    }
+
+    /// # Socket Options
+    ///
+    /// The options of the listeners and accepted connections, such as `SO_REUSEPORT` or `TCP_NODELAY`, see `App::bind_with`.
+    ///
+    /// ```ignore
+    /// let app = App::builder()
+    ///     .socket_options(SocketOptions { reuse_port: true, backlog: 4096, ..Default::default() })
+    ///     .build()
+    ///     .await?;
+    /// ```
+    pub fn socket_options(mut self, options: SocketOptions) -> Self {
+        self.socket_options = options;
+        self
+    }
+
+    /// Serves the directory under the prefix, see `App::serve_static_with`.
+    pub fn static_mount(mut self, mount: StaticMount) -> Self {
+        self.static_mounts.push(mount);
+        self
+    }
+
+    /// What the app writes, see [`LogLevel`].
+    pub fn log_level(mut self, level: LogLevel) -> Self {
+        self.log_level = level;
+        self
+    }
+
+    /// Serves HTTPS, selecting the certificate of each connection with the resolver, see `App::use_tls`.
+    ///
+    /// Requires the `tls` feature.
+    #[cfg(feature = "tls")]
+    pub fn tls(mut self, resolver: std::sync::Arc<dyn crate::web::tls::ResolvesServerCert>) -> Self {
+        self.tls = Some(resolver);
+        self
+    }
+
+    /// # Build
+    ///
+    /// Binds the app with the settings, the app is started with `App::start`.
+    ///
+    /// Fails if the address can not be bound, the TLS files of the config can not be loaded, or a static mount can not be routed.
+    pub async fn build(self) -> crate::Result<App> {
+        #[cfg(feature = "tls")]
+        let tls = match (self.tls, &self.tls_files) {
+            (Some(resolver), _) => Some(resolver),
+            (None, Some((cert, key))) => {
+                let store = crate::web::tls::CertificateStore::from_files(cert, key)?;
+
+                Some(std::sync::Arc::new(store) as _)
+            }
+            (None, None) => None,
+        };
+
+        let listener = self.socket_options.bind(self.addr.as_str()).await?;
+        let mut app = App::from_listener(listener, self.workers, self.socket_options).await;
+
+        for addr in &self.also_bind {
+            app.also_bind(addr.as_str()).await?;
+        }
+
+        *app.worker_scale_factor.lock().await = self.worker_scale_factor;
+        app.set_acceptors(self.acceptors);
+        app.set_autoscale(self.autoscale);
+        app.set_fair_queuing(self.fair_queuing);
+        app.set_queue_capacity(self.queue_capacity, self.queue_policy);
+        app.set_queue_timeout(self.queue_timeout);
+        app.set_execution(self.execution);
+        app.set_body_limits(self.body_limits);
+        app.set_head_limits(self.head_limits);
+        app.set_keep_alive(self.keep_alive);
+        app.set_max_requests_per_connection(self.max_requests_per_connection);
+        app.set_connection_limit(self.connection_limit);
+
+        for mount in &self.static_mounts {
+            app.serve_static_with(&mount.prefix, mount.files()).await?;
+        }
+
+        if self.log_level != LogLevel::Off {
+            app.set_error_callback(|e| telemetry::error(&e));
+        }
+
+        if self.log_level == LogLevel::Info {
+            app.set_startup_banner(|banner| telemetry::info(&banner.to_string()));
+        }
+
+        #[cfg(feature = "tls")]
+        if let Some(resolver) = tls {
+            app.use_tls(resolver);
+        }
+
+        Ok(app)
+    }
+}
+
+impl Default for AppBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,53 @@
+use std::net::SocketAddr;
+
+use tokio::sync::watch;
+
+/// # App Handle
+///
+/// Returned by `App::start`, joins the accept loops of every listener of the app.
+///
+/// ```ignore
+/// let mut handle = app.start()?;
+///
+/// println!("listening on {:?}", handle.local_addr());
+///
+/// //waits until the app is closed, for example by a shutdown signal.
+/// handle.join().await;
+/// ```
+#[derive(Debug, Clone)]
+pub struct AppHandle {
+    local_addrs: Vec<SocketAddr>,
+    finished: watch::Receiver<bool>,
+}
+
+impl AppHandle {
+    pub(crate) fn new(local_addrs: Vec<SocketAddr>, finished: watch::Receiver<bool>) -> Self {
+        Self {
+            local_addrs,
+            finished,
+        }
+    }
+
+    /// The address the app is listening on, the first when it listens on several, see `local_addrs`.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addrs.first().copied()
+    }
+
+    /// The addresses the app is listening on.
+    pub fn local_addrs(&self) -> &[SocketAddr] {
+        &self.local_addrs
+    }
+
+    /// True once every accept loop stopped and the shutdown hooks ran.
+    pub fn is_finished(&self) -> bool {
+        *self.finished.borrow()
+    }
+
+    /// # Join
+    ///
+    /// Waits until every accept loop stopped and the shutdown hooks ran, after the app is closed.
+    pub async fn join(&mut self) {
+        //the app task ending without a signal is finished as well.
+        let _ = self.finished.wait_for(|finished| *finished).await;
+    }
+}
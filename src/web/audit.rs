@@ -0,0 +1,366 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    net::IpAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use tokio::{io::AsyncWriteExt, sync::Mutex, time::Instant};
+
+use crate::web::{Middleware, middleware_fn, routing::middleware::MiddlewareClosure};
+
+/// # Audit Kind
+///
+/// The kind of security relevant event that was recorded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum AuditKind {
+    /// A client authenticated successfully.
+    AuthSuccess,
+    /// A client failed to authenticate.
+    AuthFailure,
+    /// A client received many 4xx responses in a short window.
+    ClientErrorBurst,
+    /// An admin route was accessed.
+    AdminAccess,
+    /// An application defined event.
+    Custom(String),
+}
+
+/// # Audit Event
+///
+/// A single event in the audit log.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEvent {
+    /// Seconds since the unix epoch.
+    pub timestamp: u64,
+    /// The kind of event.
+    pub kind: AuditKind,
+    /// The address of the client that caused the event.
+    pub client: IpAddr,
+    /// The route that was requested.
+    pub route: String,
+    /// Extra information about the event.
+    pub detail: String,
+}
+
+impl AuditEvent {
+    /// Create a new event stamped with the current time.
+    pub fn new(kind: AuditKind, client: IpAddr, route: &str, detail: &str) -> Self {
+        Self {
+            timestamp: unix_now(),
+            kind,
+            client,
+            route: route.to_string(),
+            detail: detail.to_string(),
+        }
+    }
+}
+
+/// Idiomatic type alias for a closure that receives each audit event.
+pub type AuditCallback = dyn Fn(&AuditEvent) + Send + Sync;
+
+/// # Audit Sink
+///
+/// Where audit events are written to, in addition to the in-memory history.
+pub enum AuditSink {
+    /// Events are only kept in memory.
+    Memory,
+
+    /// Events are appended as JSON lines to a file.
+    ///
+    /// Once the file reaches `max_bytes` it is rotated to `path.1`, `path.2`, ... keeping `max_files` old files.
+    File {
+        path: PathBuf,
+        max_bytes: u64,
+        max_files: usize,
+    },
+
+    /// Events are passed to a callback.
+    Callback(Arc<AuditCallback>),
+}
+
+/// # Audit Config
+///
+/// Retention and detection settings of an [`AuditLog`].
+#[derive(Debug, Clone)]
+pub struct AuditConfig {
+    /// Max events kept in memory.
+    pub max_events: usize,
+    /// Max age of an event kept in memory.
+    pub max_age: Duration,
+    /// Amount of 4xx responses from one client within `burst_window` that is recorded as a burst.
+    pub burst_threshold: usize,
+    /// The window 4xx responses are counted in.
+    pub burst_window: Duration,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            max_events: 1000,
+            max_age: Duration::from_secs(60 * 60 * 24),
+            burst_threshold: 20,
+            burst_window: Duration::from_secs(60),
+        }
+    }
+}
+
+struct AuditState {
+    events: VecDeque<AuditEvent>,
+    /// 4xx response times per client.
+    client_errors: HashMap<IpAddr, VecDeque<Instant>>,
+    /// when clients without recent errors were last forgotten.
+    clients_pruned: Instant,
+    /// bytes written to the current file.
+    file_size: Option<u64>,
+}
+
+/// # Audit Log
+///
+/// Records security relevant events separately from access logs, with bounded retention.
+///
+/// Set on the app with `App::set_audit_log`, the app then:
+///
+/// * records bursts of 4xx responses per client
+/// * hands the log to middleware through the request extensions, which the `web::auth` middleware uses to record successes and failures
+///
+/// Cloning the log shares the same history.
+///
+/// ```ignore
+/// let audit = AuditLog::new(AuditSink::File {
+///     path: "logs/audit.log".into(),
+///     max_bytes: 10 * 1024 * 1024,
+///     max_files: 5,
+/// }, AuditConfig::default());
+///
+/// app.set_audit_log(audit.clone());
+///
+/// app.add_or_panic("/admin", Method::GET, middleware!(audit_admin), ...).await;
+/// ```
+#[derive(Clone)]
+pub struct AuditLog {
+    sink: Arc<AuditSink>,
+    config: AuditConfig,
+    state: Arc<Mutex<AuditState>>,
+}
+
+impl AuditLog {
+    /// Create a new audit log that writes to the sink.
+    pub fn new(sink: AuditSink, config: AuditConfig) -> Self {
+        Self {
+            sink: Arc::new(sink),
+            config,
+            state: Arc::new(Mutex::new(AuditState {
+                events: VecDeque::new(),
+                client_errors: HashMap::new(),
+                clients_pruned: Instant::now(),
+                file_size: None,
+            })),
+        }
+    }
+
+    /// # Record
+    ///
+    /// Records an event into the history and the sink.
+    ///
+    /// Failing to write to a file sink does not fail the request, the event is still kept in memory.
+    pub async fn record(&self, event: AuditEvent) {
+        let mut state = self.state.lock().await;
+
+        match self.sink.as_ref() {
+            AuditSink::Memory => {}
+            AuditSink::Callback(callback) => callback(&event),
+            AuditSink::File {
+                path,
+                max_bytes,
+                max_files,
+            } => {
+                let _ = write_rotating(&mut state, path, *max_bytes, *max_files, &event).await;
+            }
+        }
+
+        state.events.push_back(event);
+        self.prune(&mut state);
+    }
+
+    /// # Client Error
+    ///
+    /// Counts a 4xx response for the client, recording a [`AuditKind::ClientErrorBurst`] when the threshold is reached.
+    pub async fn client_error(&self, client: IpAddr, route: &str, status: &str) {
+        let burst = {
+            let mut state = self.state.lock().await;
+            let now = Instant::now();
+            let window = self.config.burst_window;
+
+            let times = state.client_errors.entry(client).or_default();
+            times.push_back(now);
+
+            while times
+                .front()
+                .is_some_and(|t| now.duration_since(*t) > window)
+            {
+                times.pop_front();
+            }
+
+            let burst = times.len() >= self.config.burst_threshold;
+
+            //reset after a burst so one burst is recorded once.
+            if burst {
+                times.clear();
+            }
+
+            //forget clients without recent errors, once per window rather than on every error.
+            if now.duration_since(state.clients_pruned) > window {
+                state.client_errors.retain(|_, t| {
+                    t.back()
+                        .is_some_and(|last| now.duration_since(*last) <= window)
+                });
+                state.clients_pruned = now;
+            }
+
+            burst
+        };
+
+        if burst {
+            let detail = format!(
+                "{} client errors within {:?}, last status {status}",
+                self.config.burst_threshold, self.config.burst_window
+            );
+
+            self.record(AuditEvent::new(
+                AuditKind::ClientErrorBurst,
+                client,
+                route,
+                &detail,
+            ))
+            .await;
+        }
+    }
+
+    /// # Events
+    ///
+    /// Returns the retained events, oldest first.
+    pub async fn events(&self) -> Vec<AuditEvent> {
+        let mut state = self.state.lock().await;
+        self.prune(&mut state);
+
+        state.events.iter().cloned().collect()
+    }
+
+    /// drops events past the retention.
+    fn prune(&self, state: &mut AuditState) {
+        let oldest = unix_now().saturating_sub(self.config.max_age.as_secs());
+
+        while state.events.len() > self.config.max_events
+            || state.events.front().is_some_and(|e| e.timestamp < oldest)
+        {
+            state.events.pop_front();
+        }
+    }
+}
+
+/// appends the event to the file, rotating when the file is full.
+async fn write_rotating(
+    state: &mut AuditState,
+    path: &Path,
+    max_bytes: u64,
+    max_files: usize,
+    event: &AuditEvent,
+) -> Result<(), std::io::Error> {
+    let mut line = serde_json::to_vec(event)?;
+    line.push(b'\n');
+
+    let size = match state.file_size {
+        Some(size) => size,
+        None => tokio::fs::metadata(path)
+            .await
+            .map(|m| m.len())
+            .unwrap_or(0),
+    };
+
+    let size = if size + line.len() as u64 > max_bytes && size > 0 {
+        match rotate(path, max_files).await {
+            Ok(_) => 0,
+            //the file was moved or deleted, the size is read again from what is on disk.
+            Err(_) => tokio::fs::metadata(path)
+                .await
+                .map(|m| m.len())
+                .unwrap_or(0),
+        }
+    } else {
+        size
+    };
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+
+    file.write_all(&line).await?;
+    //tokio writes in the background, the line is on disk before the next rotation looks at the file.
+    file.flush().await?;
+
+    state.file_size = Some(size + line.len() as u64);
+
+    Ok(())
+}
+
+/// shifts path.N-1 -> path.N ... path -> path.1, removing the file when no rotated files are kept.
+async fn rotate(path: &Path, max_files: usize) -> Result<(), std::io::Error> {
+    for i in (1..max_files).rev() {
+        let from = rotated(path, i);
+        if tokio::fs::try_exists(&from).await.unwrap_or(false) {
+            tokio::fs::rename(&from, rotated(path, i + 1)).await?;
+        }
+    }
+
+    if max_files > 0 {
+        tokio::fs::rename(path, rotated(path, 1)).await
+    } else {
+        tokio::fs::remove_file(path).await
+    }
+}
+
+/// the path of a rotated file.
+fn rotated(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{index}"));
+
+    PathBuf::from(name)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// # Audit Admin
+///
+/// Middleware that records an [`AuditKind::AdminAccess`] event for each request of the route.
+///
+/// The event is recorded to the audit log set on the app, if there is none nothing is recorded.
+pub fn audit_admin() -> MiddlewareClosure {
+    middleware_fn(|req| Box::pin(async move {
+        if let Some(audit) = req.extensions.get::<AuditLog>() {
+            let event = AuditEvent::new(
+                AuditKind::AdminAccess,
+                req.client_ip(),
+                &req.route.cleaned_route,
+                &req.method.to_string(),
+            );
+
+            audit.record(event).await;
+        }
+
+        Middleware::Next
+    }))
+}
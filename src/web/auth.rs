@@ -0,0 +1,405 @@
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use base64::{
+    Engine,
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+};
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::web::{
+    Middleware, Request, StatusCode,
+    audit::{AuditEvent, AuditKind, AuditLog},
+    errors::AuthError,
+    middleware_fn,
+    routing::middleware::MiddlewareClosure,
+};
+
+/// # Auth Scheme
+///
+/// The scheme a [`Principal`] was authenticated with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthScheme {
+    /// HTTP Basic, username and password
+    Basic,
+    /// A static bearer token
+    Bearer,
+    /// A validated JSON Web Token
+    Jwt,
+}
+
+/// # Principal
+///
+/// The authenticated identity of a request.
+///
+/// The auth middleware inserts this into the request's extensions, handlers can read it with:
+///
+/// ```ignore
+/// let principal = req.lock().await.extensions.get::<Principal>().cloned();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Principal {
+    /// The username (Basic), the token index (Bearer), or the `sub` claim (JWT).
+    pub id: String,
+
+    /// How the principal was authenticated.
+    pub scheme: AuthScheme,
+
+    /// The claims of the token, only set for JWT.
+    pub claims: Option<Value>,
+}
+
+/// Idiomatic type alias for a basic credential check, taking the username and password.
+pub type BasicVerifier = dyn Fn(&str, &str) -> bool + Send + Sync;
+
+/// # Basic
+///
+/// Middleware for HTTP Basic authentication.
+///
+/// The `verify` closure receives the username and password and returns true when they are valid.
+///
+/// If the credentials are missing or rejected a `401` is served with a `WWW-Authenticate` header for the `realm`.
+///
+/// ```ignore
+/// let admin = basic("admin area", |user, pass| user == "admin" && pass == "hunter2");
+///
+/// app.add_or_panic("/admin", Method::GET, middleware!(admin), |req| async move { ... }).await;
+/// ```
+pub fn basic<F>(realm: &str, verify: F) -> MiddlewareClosure
+where
+    F: Fn(&str, &str) -> bool + Send + Sync + 'static,
+{
+    let challenge = format!(
+        "Basic realm=\"{}\", charset=\"UTF-8\"",
+        realm.replace('"', "")
+    );
+    let verify: Arc<BasicVerifier> = Arc::new(verify);
+
+    middleware_fn(move |req| {
+        let challenge = challenge.clone();
+        let verify = verify.clone();
+
+        Box::pin(async move {
+            match decode_basic(req) {
+                Ok((user, pass)) if verify(&user, &pass) => {
+                    audit(
+                        req,
+                        AuditKind::AuthSuccess,
+                        &format!("basic user {user}"),
+                    )
+                    .await;
+
+                    req.extensions.insert(Principal {
+                        id: user,
+                        scheme: AuthScheme::Basic,
+                        claims: None,
+                    });
+
+                    Middleware::Next
+                }
+                _ => {
+                    audit(req, AuditKind::AuthFailure, "basic").await;
+                    unauthorized(req, challenge)
+                }
+            }
+        })
+    })
+}
+
+/// # Bearer
+///
+/// Middleware that accepts any of the static `tokens` in the `Authorization: Bearer` header.
+///
+/// The principal id is the index of the matched token.
+///
+/// Every token is compared, so the time taken does not tell which token matched or how long the tokens are.
+pub fn bearer(tokens: Vec<String>) -> MiddlewareClosure {
+    //compared as digests, so every comparison is the same length.
+    let tokens: Arc<Vec<_>> = Arc::new(tokens.iter().map(Sha256::digest).collect());
+
+    middleware_fn(move |req| {
+        let tokens = tokens.clone();
+
+        Box::pin(async move {
+            let matched = bearer_token(req).ok().and_then(|given| {
+                let given = Sha256::digest(given);
+
+                tokens.iter().enumerate().fold(None, |matched, (index, token)| {
+                    let equal = constant_time_eq(token, &given);
+                    matched.or(equal.then_some(index))
+                })
+            });
+
+            match matched {
+                Some(index) => {
+                    audit(
+                        req,
+                        AuditKind::AuthSuccess,
+                        &format!("bearer token {index}"),
+                    )
+                    .await;
+
+                    req.extensions.insert(Principal {
+                        id: index.to_string(),
+                        scheme: AuthScheme::Bearer,
+                        claims: None,
+                    });
+
+                    Middleware::Next
+                }
+                None => {
+                    audit(req, AuditKind::AuthFailure, "bearer").await;
+                    unauthorized(req, "Bearer".to_string())
+                }
+            }
+        })
+    })
+}
+
+/// # JWT Verifier
+///
+/// Verifies the signature of a JSON Web Token.
+///
+/// `HS256` is provided by [`Hs256`], other algorithms such as `RS256` can be plugged in by implementing this trait with the crypto library of your choice.
+pub trait JwtVerifier: Send + Sync {
+    /// The `alg` this verifier accepts, for example "RS256".
+    fn algorithm(&self) -> &str;
+
+    /// Returns true if the `signature` is valid for the `signing_input` (`header.payload`).
+    fn verify(&self, signing_input: &[u8], signature: &[u8]) -> bool;
+}
+
+/// # HS256
+///
+/// HMAC SHA-256 verifier using a shared secret.
+pub struct Hs256 {
+    secret: Vec<u8>,
+}
+
+impl Hs256 {
+    /// Create a new verifier with the shared secret.
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            secret: secret.to_vec(),
+        }
+    }
+
+    /// Signs the `signing_input`, useful for issuing tokens.
+    pub fn sign(&self, signing_input: &[u8]) -> Vec<u8> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.secret).expect("hmac takes any key size");
+        mac.update(signing_input);
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+
+impl JwtVerifier for Hs256 {
+    fn algorithm(&self) -> &str {
+        "HS256"
+    }
+
+    fn verify(&self, signing_input: &[u8], signature: &[u8]) -> bool {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.secret).expect("hmac takes any key size");
+        mac.update(signing_input);
+        mac.verify_slice(signature).is_ok()
+    }
+}
+
+/// # JWT
+///
+/// Middleware that validates a JSON Web Token from the `Authorization: Bearer` header.
+///
+/// The signature is checked with the `verifier`, and the `exp` and `nbf` claims are checked against the current time.
+///
+/// The principal id is the `sub` claim, and the claims are available on the principal.
+pub fn jwt(verifier: impl JwtVerifier + 'static) -> MiddlewareClosure {
+    let verifier: Arc<dyn JwtVerifier> = Arc::new(verifier);
+
+    middleware_fn(move |req| {
+        let verifier = verifier.clone();
+
+        Box::pin(async move {
+            let claims =
+                bearer_token(req).and_then(|token| decode_jwt(&token, verifier.as_ref()));
+
+            match claims {
+                Ok(claims) => {
+                    let id = claims
+                        .get("sub")
+                        .and_then(|s| s.as_str())
+                        .unwrap_or_default()
+                        .to_string();
+
+                    audit(req, AuditKind::AuthSuccess, &format!("jwt sub {id}")).await;
+
+                    req.extensions.insert(Principal {
+                        id,
+                        scheme: AuthScheme::Jwt,
+                        claims: Some(claims),
+                    });
+
+                    Middleware::Next
+                }
+                Err(e) => {
+                    audit(req, AuditKind::AuthFailure, &format!("jwt {e}")).await;
+
+                    unauthorized(
+                        req,
+                        format!(
+                            "Bearer error=\"invalid_token\", error_description={}",
+                            quoted_string(&e.to_string())
+                        ),
+                    )
+                }
+            }
+        })
+    })
+}
+
+/// # Decode JWT
+///
+/// Validates a compact JWT and returns its claims.
+pub fn decode_jwt(token: &str, verifier: &dyn JwtVerifier) -> Result<Value, AuthError> {
+    let mut parts = token.split('.');
+
+    let (header, payload, signature) =
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(h), Some(p), Some(s), None) => (h, p, s),
+            _ => return Err(AuthError::Malformed("a jwt has three parts".to_string())),
+        };
+
+    let decode_json = |part: &str| -> Result<Value, AuthError> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(part)
+            .map_err(|e| AuthError::Malformed(e.to_string()))?;
+
+        serde_json::from_slice(&bytes).map_err(|e| AuthError::Malformed(e.to_string()))
+    };
+
+    let header_json = decode_json(header)?;
+
+    let alg = header_json.get("alg").and_then(|a| a.as_str());
+    if alg != Some(verifier.algorithm()) {
+        return Err(AuthError::Rejected);
+    }
+
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature)
+        .map_err(|e| AuthError::Malformed(e.to_string()))?;
+
+    let signing_input = &token[..header.len() + 1 + payload.len()];
+
+    if !verifier.verify(signing_input.as_bytes(), &signature) {
+        return Err(AuthError::Rejected);
+    }
+
+    let claims = decode_json(payload)?;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0);
+
+    //a NumericDate may have a fraction or be negative, a claim that is present but not a number is never ignored.
+    let claim_time = |key: &str| match claims.get(key) {
+        None => Ok(None),
+        Some(value) => value
+            .as_f64()
+            .filter(|time| time.is_finite())
+            .map(Some)
+            .ok_or_else(|| AuthError::Malformed(format!("the {key} claim is not a NumericDate"))),
+    };
+
+    if claim_time("exp")?.is_some_and(|exp| exp <= now)
+        || claim_time("nbf")?.is_some_and(|nbf| nbf > now)
+    {
+        return Err(AuthError::Expired);
+    }
+
+    Ok(claims)
+}
+
+/// reads the `Authorization` header for the scheme, returning the credentials.
+fn credentials(req: &Request, scheme: &str) -> Result<String, AuthError> {
+    let header = req
+        .request_header("Authorization")
+        .ok_or(AuthError::MissingCredentials)?;
+
+    match header.split_once(' ') {
+        Some((given, value)) if given.eq_ignore_ascii_case(scheme) => Ok(value.trim().to_string()),
+        _ => Err(AuthError::MissingCredentials),
+    }
+}
+
+/// the token from a `Authorization: Bearer` header.
+fn bearer_token(req: &Request) -> Result<String, AuthError> {
+    credentials(req, "Bearer")
+}
+
+/// the username and password from a `Authorization: Basic` header.
+fn decode_basic(req: &Request) -> Result<(String, String), AuthError> {
+    let encoded = credentials(req, "Basic")?;
+
+    let decoded = STANDARD
+        .decode(encoded)
+        .map_err(|e| AuthError::Malformed(e.to_string()))?;
+
+    let decoded = String::from_utf8(decoded).map_err(|e| AuthError::Malformed(e.to_string()))?;
+
+    decoded
+        .split_once(':')
+        .map(|(user, pass)| (user.to_string(), pass.to_string()))
+        .ok_or(AuthError::Malformed("missing ':' separator".to_string()))
+}
+
+/// records the event to the audit log of the app, if one is set.
+async fn audit(req: &Request, kind: AuditKind, detail: &str) {
+    if let Some(log) = req.extensions.get::<AuditLog>() {
+        let event = AuditEvent::new(
+            kind,
+            req.client_ip(),
+            &req.route.cleaned_route,
+            detail,
+        );
+
+        log.record(event).await;
+    }
+}
+
+/// serves a 401 with the challenge header.
+fn unauthorized(req: &mut Request, challenge: String) -> Middleware {
+    req.add_header("WWW-Authenticate".to_string(), Some(challenge));
+
+    Middleware::InvalidEmpty(StatusCode::UNAUTHORIZED)
+}
+
+/// a quoted-string of RFC 9110, quotes and backslashes are escaped and control characters dropped.
+pub(crate) fn quoted_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+
+    for c in value.chars().filter(|c| !c.is_control()) {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+
+        quoted.push(c);
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+/// compares two byte slices without exiting early.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
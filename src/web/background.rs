@@ -0,0 +1,109 @@
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+
+use crate::web::telemetry::Span;
+
+tokio::task_local! {
+    static TASK_CONTEXT: TaskContext;
+}
+
+/// # Task Context
+///
+/// The context of the request that spawned a background task with `Request::spawn`.
+///
+/// Read from inside of the task with [`current`].
+#[derive(Debug, Clone)]
+pub struct TaskContext {
+    /// The id of the request, when the request id middleware is used.
+    pub request_id: Option<String>,
+    /// The route of the request.
+    pub route: String,
+    cancellation: CancellationToken,
+}
+
+impl TaskContext {
+    /// The token that is cancelled when the app closes, long running tasks may check it to stop early.
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+}
+
+/// # Current
+///
+/// The context of the background task being run, None when called outside of a task spawned by `Request::spawn`.
+pub fn current() -> Option<TaskContext> {
+    TASK_CONTEXT.try_with(|ctx| ctx.clone()).ok()
+}
+
+/// # Background
+///
+/// The background tasks spawned by handlers, cancelled when the app closes.
+///
+/// Cloning shares the same tasks.
+#[derive(Clone, Default)]
+pub struct Background {
+    token: CancellationToken,
+    tracker: TaskTracker,
+}
+
+impl Background {
+    /// Create an empty set of tasks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The amount of background tasks still running.
+    pub fn len(&self) -> usize {
+        self.tracker.len()
+    }
+
+    /// Returns true if no background task is running.
+    pub fn is_empty(&self) -> bool {
+        self.tracker.is_empty()
+    }
+
+    /// # Spawn
+    ///
+    /// Spawns the future with the context, the output is None if the task was cancelled.
+    ///
+    /// With the `tracing` feature the task runs in the span that is current when spawning, the span of the request for `Request::spawn`.
+    pub fn spawn<F>(
+        &self,
+        request_id: Option<String>,
+        route: String,
+        future: F,
+    ) -> tokio::task::JoinHandle<Option<F::Output>>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let cancellation = self.token.child_token();
+
+        let context = TaskContext {
+            request_id,
+            route,
+            cancellation: cancellation.clone(),
+        };
+
+        let task = Span::current().instrument(async move {
+            tokio::select! {
+                _ = cancellation.cancelled() => None,
+                output = future => Some(output),
+            }
+        });
+
+        self.tracker.spawn(TASK_CONTEXT.scope(context, task))
+    }
+
+    /// # Cancel
+    ///
+    /// Cancels every running task and every task spawned afterwards.
+    pub fn cancel(&self) {
+        self.token.cancel();
+        self.tracker.close();
+    }
+
+    /// Waits for every task to finish, call after `cancel`.
+    pub async fn wait(&self) {
+        self.tracker.wait().await;
+    }
+}
@@ -0,0 +1,226 @@
+use std::{fmt::Display, net::SocketAddr};
+
+use crate::web::{
+    Method,
+    routing::{
+        RouteNodeRef,
+        router::{route_node::RouteNode, route_tree::RouteTree},
+    },
+};
+
+/// # Route Summary
+///
+/// A registered route and method, listed by the [`Banner`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteSummary {
+    pub method: Method,
+    /// The route pattern, `/users/{id}`.
+    pub pattern: String,
+    /// The amount of middleware called for the route, not counting global middleware.
+    pub middleware: usize,
+    /// The amount of fallback resolutions, see `App::add_route_chain`.
+    pub fallbacks: usize,
+}
+
+/// # Banner
+///
+/// A summary of how an app is configured, written when the app starts so misconfiguration is visible immediately, see `App::set_startup_banner`.
+///
+/// Lists the bound addresses, the workers, every route with its methods and middleware, and warnings about routes that can not be reached.
+#[derive(Debug, Clone, Default)]
+pub struct Banner {
+    pub addresses: Vec<SocketAddr>,
+    /// The amount of workers when the app started.
+    pub workers: usize,
+    pub worker_scale_factor: usize,
+    pub fair_queuing: bool,
+    pub global_middleware: usize,
+    /// The routes, sorted by pattern then method.
+    pub routes: Vec<RouteSummary>,
+    pub warnings: Vec<String>,
+}
+
+impl Banner {
+    /// # Routes
+    ///
+    /// Every route and method of the tree, sorted by pattern then method.
+    pub async fn routes(tree: &RouteTree) -> Vec<RouteSummary> {
+        let mut routes = Vec::new();
+
+        for node_ref in nodes(tree).await {
+            let pattern = RouteNode::pattern(&node_ref).await;
+            let node = node_ref.lock().await;
+
+            for (method, endpoint) in &node.resolutions {
+                routes.push(RouteSummary {
+                    method: method.clone(),
+                    pattern: pattern.clone(),
+                    middleware: endpoint.middleware.as_ref().map(|mw| mw.len()).unwrap_or(0)
+                        + endpoint.named_middleware.len(),
+                    fallbacks: endpoint.fallbacks.len(),
+                });
+            }
+        }
+
+        routes.sort_by(|a, b| {
+            (&a.pattern, a.method.to_string()).cmp(&(&b.pattern, b.method.to_string()))
+        });
+
+        routes
+    }
+
+    /// # Warnings
+    ///
+    /// Routes of the tree that can not be reached:
+    ///
+    /// * a segment such as `/users/me` shadows a variable `/users/{id}`, methods of `{id}` that `me` lacks are not found for `/users/me`
+    /// * routes below a wildcard `/files/{*}` are never matched, the wildcard matches first
+    pub async fn warnings(tree: &RouteTree) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        for node_ref in nodes(tree).await {
+            //patterns lock parents, so nodes are not held while they are found.
+            let (var_child, static_children) = {
+                let node = node_ref.lock().await;
+                (node.var_child.clone(), node.children.values().cloned().collect::<Vec<_>>())
+            };
+
+            let Some(var_child) = var_child else {
+                continue;
+            };
+
+            let var_pattern = RouteNode::pattern(&var_child).await;
+
+            let (is_wildcard, var_methods, below) = {
+                let var_node = var_child.lock().await;
+
+                let below: Vec<RouteNodeRef> = var_node
+                    .children
+                    .values()
+                    .chain(var_node.var_child.iter())
+                    .cloned()
+                    .collect();
+
+                (var_node.id == "{*}", var_node.resolutions.keys().cloned().collect::<Vec<_>>(), below)
+            };
+
+            if is_wildcard {
+                for child in below {
+                    warnings.push(format!(
+                        "routes below {var_pattern} are never matched, starting with {}",
+                        RouteNode::pattern(&child).await
+                    ));
+                }
+            }
+
+            for static_child in static_children {
+                let static_pattern = RouteNode::pattern(&static_child).await;
+                let static_node = static_child.lock().await;
+
+                let mut shadowed: Vec<String> = var_methods
+                    .iter()
+                    .filter(|method| !static_node.resolutions.contains_key(method))
+                    .map(|method| method.to_string())
+                    .collect();
+
+                if shadowed.is_empty() {
+                    continue;
+                }
+
+                shadowed.sort();
+
+                warnings.push(format!(
+                    "{static_pattern} shadows {var_pattern}, {} {static_pattern} is not found",
+                    shadowed.join(", ")
+                ));
+            }
+        }
+
+        warnings.sort();
+        warnings
+    }
+}
+
+/// every node of the tree, parents before children.
+async fn nodes(tree: &RouteTree) -> Vec<RouteNodeRef> {
+    let mut nodes = Vec::new();
+    let mut stack = vec![tree.root.clone()];
+
+    while let Some(node_ref) = stack.pop() {
+        {
+            let node = node_ref.lock().await;
+
+            stack.extend(node.children.values().cloned());
+            stack.extend(node.var_child.iter().cloned());
+        }
+
+        nodes.push(node_ref);
+    }
+
+    nodes
+}
+
+impl Display for Banner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for address in &self.addresses {
+            writeln!(f, "listening on {address}")?;
+        }
+
+        writeln!(
+            f,
+            "workers: {} (scale factor {}), fair queuing: {}, global middleware: {}",
+            self.workers,
+            self.worker_scale_factor,
+            if self.fair_queuing { "on" } else { "off" },
+            self.global_middleware
+        )?;
+
+        let method_width = self
+            .routes
+            .iter()
+            .map(|r| r.method.to_string().len())
+            .chain(["METHOD".len()])
+            .max()
+            .unwrap_or(0);
+
+        let route_width = self
+            .routes
+            .iter()
+            .map(|r| r.pattern.len())
+            .chain(["ROUTE".len()])
+            .max()
+            .unwrap_or(0);
+
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:method_width$}  {:route_width$}  MIDDLEWARE  FALLBACKS",
+            "METHOD", "ROUTE"
+        )?;
+
+        for route in &self.routes {
+            writeln!(
+                f,
+                "{:method_width$}  {:route_width$}  {:<10}  {}",
+                route.method.to_string(),
+                route.pattern,
+                route.middleware,
+                route.fallbacks
+            )?;
+        }
+
+        if self.routes.is_empty() {
+            writeln!(f, "(no routes)")?;
+        }
+
+        if !self.warnings.is_empty() {
+            writeln!(f)?;
+
+            for warning in &self.warnings {
+                writeln!(f, "warning: {warning}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{Arc, Mutex},
+};
+
+/// # Buffer Pool
+///
+/// Reuses the buffers responses are written with, so each request does not allocate new ones under load, see `App::set_buffer_pool`.
+///
+/// A buffer grown past `max_capacity` by a large response is dropped instead of being kept.
+///
+/// Cloning shares the same pool.
+///
+/// ```ignore
+/// app.set_buffer_pool(BufferPool::new(1024, 16 * 1024));
+/// ```
+#[derive(Debug, Clone)]
+pub struct BufferPool {
+    buffers: Arc<Mutex<Vec<Vec<u8>>>>,
+    max_buffers: usize,
+    max_capacity: usize,
+}
+
+impl BufferPool {
+    /// Create a pool keeping at most `max_buffers` idle buffers of at most `max_capacity` bytes each.
+    pub fn new(max_buffers: usize, max_capacity: usize) -> Self {
+        Self {
+            buffers: Arc::new(Mutex::new(Vec::new())),
+            max_buffers,
+            max_capacity,
+        }
+    }
+
+    /// # Take
+    ///
+    /// An empty buffer, reused from the pool if one is idle, which goes back to the pool once dropped.
+    pub fn take(&self) -> PooledBuffer {
+        let buffer = self
+            .buffers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .pop()
+            .unwrap_or_default();
+
+        PooledBuffer {
+            buffer,
+            pool: self.clone(),
+        }
+    }
+
+    /// The amount of idle buffers in the pool.
+    pub fn idle(&self) -> usize {
+        self.buffers.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// keeps the buffer for the next take, unless the pool is full or the buffer grew too large.
+    fn give_back(&self, mut buffer: Vec<u8>) {
+        if buffer.capacity() == 0 || buffer.capacity() > self.max_capacity {
+            return;
+        }
+
+        buffer.clear();
+
+        let mut buffers = self.buffers.lock().unwrap_or_else(|e| e.into_inner());
+
+        if buffers.len() < self.max_buffers {
+            buffers.push(buffer);
+        }
+    }
+}
+
+impl Default for BufferPool {
+    /// 256 buffers of up to 64 KiB.
+    fn default() -> Self {
+        Self::new(256, 64 * 1024)
+    }
+}
+
+/// # Pooled Buffer
+///
+/// A buffer taken from a [`BufferPool`], it is given back to the pool once dropped.
+pub struct PooledBuffer {
+    buffer: Vec<u8>,
+    pool: BufferPool,
+}
+
+impl Deref for PooledBuffer {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.buffer
+    }
+}
+
+impl DerefMut for PooledBuffer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.buffer
+    }
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        self.pool.give_back(std::mem::take(&mut self.buffer));
+    }
+}
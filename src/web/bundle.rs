@@ -0,0 +1,294 @@
+use std::{collections::HashMap, io::Write, pin::Pin, sync::Arc, time::Duration};
+
+use flate2::{Compression, write::GzEncoder};
+use futures::{Stream, stream};
+use sha2::{Digest, Sha256};
+
+use crate::web::{
+    Resolution, StatusCode,
+    headers::HeaderMap,
+    resolution::{empty_resolution::EmptyResolution, file_resolution::content_type_of},
+};
+
+/// # Bundled Asset
+///
+/// A file of a [`StaticBundle`], held in memory with its ETag and encoded variants.
+#[derive(Debug, Clone)]
+pub struct BundledAsset {
+    pub content_type: &'static str,
+    /// The strong ETag of the file, the quoted first 16 hex characters of its SHA-256.
+    pub etag: String,
+    bytes: Arc<[u8]>,
+    /// `gzip` or `br` variants, preferred in the order they were added.
+    encodings: Vec<(String, Arc<[u8]>)>,
+}
+
+impl BundledAsset {
+    /// The bytes of the file, not encoded.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// The encodings the file is available in, `gzip` or `br`.
+    pub fn encodings(&self) -> Vec<&str> {
+        self.encodings.iter().map(|(encoding, _)| encoding.as_str()).collect()
+    }
+}
+
+/// # Static Bundle
+///
+/// Assets compiled into the executable and served from memory, so tools built on async-web ship as one file, see `App::serve_bundle`.
+///
+/// Create one with the [`static_bundle!`](crate::static_bundle) macro, which includes each file with `include_bytes!`.
+///
+/// * each asset is sent with a precomputed ETag, a matching `If-None-Match` receives `304`
+/// * pre-compressed variants, built at compile time with `with_encoding` or at startup with `gzip`, are sent to clients that accept them
+/// * a path of a directory serves its `index.html`
+///
+/// ```ignore
+/// let bundle = static_bundle!("public", ["index.html", "app.js", "style.css"])
+///     .with_encoding("app.js", "br", include_bytes!("../public/app.js.br"))
+///     .gzip(1024)
+///     .max_age(Duration::from_secs(86400));
+///
+/// app.serve_bundle("/", bundle).await?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StaticBundle {
+    assets: HashMap<String, BundledAsset>,
+    max_age: Option<Duration>,
+}
+
+impl StaticBundle {
+    /// Create an empty bundle.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Add
+    ///
+    /// Adds a file at `path`, `css/site.css`, hashing it for its ETag.
+    pub fn add(mut self, path: &str, bytes: &[u8]) -> Self {
+        let path = clean(path);
+
+        let asset = BundledAsset {
+            content_type: content_type_of(&path),
+            etag: format!("\"{}\"", short_hash(bytes)),
+            bytes: Arc::from(bytes),
+            encodings: Vec::new(),
+        };
+
+        self.assets.insert(path, asset);
+        self
+    }
+
+    /// # With Encoding
+    ///
+    /// Adds a pre-compressed variant of a file, `gzip` or `br`, ignored if the file is not in the bundle.
+    pub fn with_encoding(mut self, path: &str, encoding: &str, bytes: &[u8]) -> Self {
+        if let Some(asset) = self.assets.get_mut(&clean(path)) {
+            asset.encodings.retain(|(e, _)| !e.eq_ignore_ascii_case(encoding));
+            asset
+                .encodings
+                .push((encoding.to_ascii_lowercase(), Arc::from(bytes)));
+        }
+
+        self
+    }
+
+    /// # Gzip
+    ///
+    /// Compresses text, JSON, JavaScript, and SVG files of at least `min_size` bytes that have no gzip variant, keeping the variant only if it is smaller.
+    pub fn gzip(mut self, min_size: usize) -> Self {
+        for asset in self.assets.values_mut() {
+            let compressible = asset.content_type.starts_with("text/")
+                || asset.content_type.contains("json")
+                || asset.content_type.contains("javascript")
+                || asset.content_type.contains("xml");
+
+            if !compressible
+                || asset.bytes.len() < min_size
+                || asset.encodings.iter().any(|(e, _)| e == "gzip")
+            {
+                continue;
+            }
+
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+
+            let Ok(compressed) = encoder.write_all(&asset.bytes).and_then(|_| encoder.finish()) else {
+                continue;
+            };
+
+            if compressed.len() < asset.bytes.len() {
+                asset.encodings.push(("gzip".to_string(), Arc::from(compressed)));
+            }
+        }
+
+        self
+    }
+
+    /// How long clients may cache assets without revalidating, sent as `Cache-Control: public, max-age`.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// The asset at `path`, or the `index.html` of the directory.
+    pub fn get(&self, path: &str) -> Option<&BundledAsset> {
+        let path = clean(path);
+
+        self.assets.get(&path).or_else(|| {
+            let index = match path.is_empty() {
+                true => "index.html".to_string(),
+                false => format!("{path}/index.html"),
+            };
+
+            self.assets.get(&index)
+        })
+    }
+
+    /// The amount of assets.
+    pub fn len(&self) -> usize {
+        self.assets.len()
+    }
+
+    /// True if the bundle has no assets.
+    pub fn is_empty(&self) -> bool {
+        self.assets.is_empty()
+    }
+
+    /// # Resolution
+    ///
+    /// The asset at `path`, encoded with the first variant the `Accept-Encoding` of the client allows, `404` if it is not bundled.
+    pub fn resolution(
+        &self,
+        path: &str,
+        if_none_match: Option<&str>,
+        accept_encoding: Option<&str>,
+    ) -> Box<dyn Resolution + Send + 'static> {
+        let Some(asset) = self.get(path) else {
+            return EmptyResolution::status(StatusCode::NOT_FOUND).resolve();
+        };
+
+        let mut headers = HeaderMap::new();
+
+        headers.insert("ETag", Some(asset.etag.clone()));
+        headers.insert(
+            "Cache-Control",
+            Some(match self.max_age {
+                Some(max_age) => format!("public, max-age={}", max_age.as_secs()),
+                None => "no-cache".to_string(),
+            }),
+        );
+
+        if !asset.encodings.is_empty() {
+            headers.insert("Vary", Some("Accept-Encoding".to_string()));
+        }
+
+        if if_none_match.is_some_and(|tags| tags.split(',').any(|tag| tag.trim() == asset.etag)) {
+            headers.set_status(StatusCode::NOT_MODIFIED);
+
+            return BundleResolution {
+                headers,
+                bytes: Arc::from([]),
+            }
+            .resolve();
+        }
+
+        let encoded = asset
+            .encodings
+            .iter()
+            .find(|(encoding, _)| accept_encoding.is_some_and(|accepted| accepts(accepted, encoding)));
+
+        headers.set_status(StatusCode::OK);
+        headers.set_content_type(asset.content_type);
+
+        let bytes = match encoded {
+            Some((encoding, bytes)) => {
+                headers.insert("Content-Encoding", Some(encoding.clone()));
+                bytes.clone()
+            }
+            None => asset.bytes.clone(),
+        };
+
+        BundleResolution { headers, bytes }.resolve()
+    }
+}
+
+/// # static_bundle!
+///
+/// Builds a [`StaticBundle`](crate::web::bundle::StaticBundle) of files compiled into the executable with `include_bytes!`.
+///
+/// The directory is relative to the `Cargo.toml` of the crate using the macro, and each file is bundled at its path below the directory.
+///
+/// ```ignore
+/// let bundle = static_bundle!("public", ["index.html", "css/site.css"]);
+/// ```
+#[macro_export]
+macro_rules! static_bundle {
+    ($dir:literal, [$($file:literal),* $(,)?]) => {
+        $crate::web::bundle::StaticBundle::new()
+            $(.add(
+                $file,
+                include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/", $dir, "/", $file)),
+            ))*
+    };
+}
+
+/// the path of an asset without leading or trailing slashes.
+fn clean(path: &str) -> String {
+    path.trim_matches('/').to_string()
+}
+
+/// the first 16 hex characters of the SHA-256.
+fn short_hash(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)[..8]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// true if the `Accept-Encoding` allows the encoding.
+fn accepts(accept_encoding: &str, encoding: &str) -> bool {
+    accept_encoding.split(',').any(|coding| {
+        let mut parts = coding.split(';');
+        let name = parts.next().unwrap_or_default().trim();
+
+        let quality = parts
+            .filter_map(|param| param.trim().strip_prefix("q="))
+            .find_map(|q| q.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+
+        (name.eq_ignore_ascii_case(encoding) || name == "*") && quality > 0.0
+    })
+}
+
+/// an asset served from memory.
+struct BundleResolution {
+    headers: HeaderMap,
+    bytes: Arc<[u8]>,
+}
+
+impl Resolution for BundleResolution {
+    fn get_headers(&self) -> HeaderMap {
+        self.headers.clone()
+    }
+
+    fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        self.seek(0)
+    }
+
+    fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+        Box::new(self)
+    }
+
+    fn seekable_len(&self) -> Option<u64> {
+        Some(self.bytes.len() as u64)
+    }
+
+    fn seek(&self, offset: u64) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        let body = self.bytes[(offset as usize).min(self.bytes.len())..].to_vec();
+
+        Box::pin(stream::once(async move { body }))
+    }
+}
@@ -0,0 +1,250 @@
+use std::{collections::HashMap, pin::Pin, sync::Arc, time::Duration};
+
+use serde::{Serialize, de::DeserializeOwned};
+use tokio::{sync::Mutex, time::Instant};
+
+use crate::web::{Middleware, middleware_fn, routing::middleware::MiddlewareClosure};
+
+/// Idiomatic type alias for the future returned by a [`CacheStore`].
+pub type CacheFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// # Cache Store
+///
+/// The storage behind a [`Cache`], values are stored as bytes so the store may live outside of the process, for example in Redis.
+pub trait CacheStore: Send + Sync + 'static {
+    /// Gets the value of a key, None if it is missing or expired.
+    fn get<'a>(&'a self, key: &'a str) -> CacheFuture<'a, Option<Vec<u8>>>;
+
+    /// Sets the value of a key, expiring after the ttl.
+    fn set<'a>(&'a self, key: &'a str, value: Vec<u8>, ttl: Duration) -> CacheFuture<'a, ()>;
+
+    /// Removes a key.
+    fn remove<'a>(&'a self, key: &'a str) -> CacheFuture<'a, ()>;
+}
+
+/// # Memory Store
+///
+/// An in-memory [`CacheStore`] holding at most `max_entries` values.
+///
+/// Expired values are dropped when they are read, when the store is full expired values are dropped first then the value closest to expiring.
+pub struct MemoryStore {
+    max_entries: usize,
+    entries: Mutex<Entries>,
+}
+
+/// values by key, with when they expire, None never expires.
+type Entries = HashMap<String, (Option<Instant>, Vec<u8>)>;
+
+impl MemoryStore {
+    /// Create a store holding at most `max_entries` values.
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            max_entries,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl CacheStore for MemoryStore {
+    fn get<'a>(&'a self, key: &'a str) -> CacheFuture<'a, Option<Vec<u8>>> {
+        Box::pin(async move {
+            let mut entries = self.entries.lock().await;
+
+            match entries.get(key) {
+                Some((expires, value)) if expires.is_none_or(|e| e > Instant::now()) => {
+                    Some(value.clone())
+                }
+                Some(_) => {
+                    entries.remove(key);
+                    None
+                }
+                None => None,
+            }
+        })
+    }
+
+    fn set<'a>(&'a self, key: &'a str, value: Vec<u8>, ttl: Duration) -> CacheFuture<'a, ()> {
+        Box::pin(async move {
+            if self.max_entries == 0 {
+                return;
+            }
+
+            let mut entries = self.entries.lock().await;
+            let now = Instant::now();
+
+            if entries.len() >= self.max_entries && !entries.contains_key(key) {
+                entries.retain(|_, (expires, _)| expires.is_none_or(|e| e > now));
+            }
+
+            if entries.len() >= self.max_entries && !entries.contains_key(key) {
+                let soonest = entries
+                    .iter()
+                    .min_by_key(|(_, (expires, _))| (expires.is_none(), *expires))
+                    .map(|(k, _)| k.clone());
+
+                if let Some(soonest) = soonest {
+                    entries.remove(&soonest);
+                }
+            }
+
+            //a ttl too large for an instant never expires.
+            entries.insert(key.to_string(), (now.checked_add(ttl), value));
+        })
+    }
+
+    fn remove<'a>(&'a self, key: &'a str) -> CacheFuture<'a, ()> {
+        Box::pin(async move {
+            self.entries.lock().await.remove(key);
+        })
+    }
+}
+
+/// # Cache
+///
+/// A typed cache for expensive sub-computations of a handler, such as database queries.
+///
+/// Values are stored as JSON in the [`CacheStore`], so any type that is `Serialize` and `DeserializeOwned` may be cached.
+///
+/// The app places its cache in each request, see `App::set_cache` and `Request::cache`.
+///
+/// Cloning shares the same store.
+///
+/// ```ignore
+/// app.add_or_panic("/users/{id}", Method::GET, None, |req| async move {
+///     let (cache, id) = {
+///         let guard = req.lock().await;
+///         (guard.cache(), guard.variables.get("id").cloned().unwrap_or_default())
+///     };
+///
+///     let user: User = cache
+///         .get_or_compute(&format!("user:{id}"), Duration::from_secs(60), async {
+///             db.find_user(&id).await
+///         })
+///         .await;
+///
+///     JsonResolution::serialize(user).unwrap().resolve()
+/// })
+/// .await;
+/// ```
+#[derive(Clone)]
+pub struct Cache {
+    store: Arc<dyn CacheStore>,
+}
+
+impl Cache {
+    /// Create a cache backed by the store.
+    pub fn new(store: impl CacheStore) -> Self {
+        Self {
+            store: Arc::new(store),
+        }
+    }
+
+    /// # Get
+    ///
+    /// Gets a value, None if it is missing, expired, or is not a `T`.
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let bytes = self.store.get(key).await?;
+
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    /// # Insert
+    ///
+    /// Caches a value for the ttl, values that fail to serialize are not cached.
+    pub async fn insert<T: Serialize>(&self, key: &str, value: &T, ttl: Duration) {
+        if let Ok(bytes) = serde_json::to_vec(value) {
+            self.store.set(key, bytes, ttl).await;
+        }
+    }
+
+    /// # Invalidate
+    ///
+    /// Removes a value so it is computed again.
+    pub async fn invalidate(&self, key: &str) {
+        self.store.remove(key).await;
+    }
+
+    /// # Get or Compute
+    ///
+    /// Gets a cached value, or awaits `compute` and caches its output for the ttl.
+    pub async fn get_or_compute<T, F>(&self, key: &str, ttl: Duration, compute: F) -> T
+    where
+        T: Serialize + DeserializeOwned,
+        F: Future<Output = T>,
+    {
+        if let Some(value) = self.get(key).await {
+            return value;
+        }
+
+        let value = compute.await;
+        self.insert(key, &value, ttl).await;
+
+        value
+    }
+}
+
+impl Default for Cache {
+    /// A cache backed by a [`MemoryStore`] of 10,000 entries.
+    fn default() -> Self {
+        Self::new(MemoryStore::new(10_000))
+    }
+}
+
+/// # Cache Preset
+///
+/// Named `Cache-Control` policies, used by name in route tables (see `web::config`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CachePreset {
+    /// `no-store`, never cached.
+    NoStore,
+    /// `private, no-cache`, cached by the browser but revalidated.
+    Private,
+    /// `public, max-age=60`
+    Short,
+    /// `public, max-age=86400`
+    Long,
+    /// `public, max-age=31536000, immutable`, for hashed assets.
+    Immutable,
+}
+
+impl CachePreset {
+    /// Gets a preset by name, `no-store`, `private`, `short`, `long`, or `immutable`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "no-store" => Some(Self::NoStore),
+            "private" => Some(Self::Private),
+            "short" => Some(Self::Short),
+            "long" => Some(Self::Long),
+            "immutable" => Some(Self::Immutable),
+            _ => None,
+        }
+    }
+
+    /// The value of the `Cache-Control` header.
+    pub fn header_value(&self) -> &'static str {
+        match self {
+            Self::NoStore => "no-store",
+            Self::Private => "private, no-cache",
+            Self::Short => "public, max-age=60",
+            Self::Long => "public, max-age=86400",
+            Self::Immutable => "public, max-age=31536000, immutable",
+        }
+    }
+}
+
+/// # Cache Control
+///
+/// Middleware that sets the `Cache-Control` header of responses, a resolution that sets its own header takes precedence.
+pub fn cache_control(value: &str) -> MiddlewareClosure {
+    let value = value.to_string();
+
+    middleware_fn(move |req| {
+        let value = value.clone();
+
+        Box::pin(async move {
+            req.add_header("Cache-Control".to_string(), Some(value));
+
+            Middleware::Next
+        })
+    })
+}
@@ -0,0 +1,210 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use serde::Serialize;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::{
+    factory::{Supervisor, metrics::LatencySnapshot},
+    web::{
+        Middleware, Resolution, StatusCode, middleware_fn,
+        resolution::{empty_resolution::EmptyResolution, json_resolution::JsonResolution},
+        routing::middleware::MiddlewareClosure,
+    },
+};
+
+/// # Busy
+///
+/// The body of a soft rejection, letting clients show progress and retry politely.
+#[derive(Debug, Clone, Serialize)]
+pub struct Busy {
+    /// The position the request would have had in the queue.
+    pub queue_position: usize,
+    /// The estimated time until a request at that position would run.
+    pub estimated_wait_ms: u64,
+    /// Seconds the client should wait before retrying, also sent as `Retry-After`.
+    pub retry_after: u64,
+}
+
+/// # Concurrency Limit
+///
+/// Caps the amount of requests of a route that run at once, for expensive endpoints like model inference.
+///
+/// Up to `max_in_flight` requests run, up to `max_waiting` more wait for a slot, and the rest are rejected.
+///
+/// Rejections are an empty `503` unless `soft` is used, which sends a JSON [`Busy`] body with the wait estimated from the latency of the work of the app, see `WorkManager::stats`.
+///
+/// Cloning shares the same limit.
+///
+/// ```ignore
+/// let limit = ConcurrencyLimit::new(4, 16).soft(503);
+///
+/// app.add_or_panic("/infer", Method::POST, middleware!(concurrency_limit(limit)), infer).await;
+/// ```
+#[derive(Clone)]
+pub struct ConcurrencyLimit {
+    semaphore: Arc<Semaphore>,
+    max_in_flight: usize,
+    max_waiting: usize,
+    waiting: Arc<AtomicUsize>,
+    soft_status: Option<StatusCode>,
+}
+
+impl ConcurrencyLimit {
+    /// Create a limit of `max_in_flight` running requests (at minimum 1) and `max_waiting` queued requests.
+    pub fn new(max_in_flight: usize, max_waiting: usize) -> Self {
+        let max_in_flight = max_in_flight.max(1);
+
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_in_flight)),
+            max_in_flight,
+            max_waiting,
+            waiting: Arc::new(AtomicUsize::new(0)),
+            soft_status: None,
+        }
+    }
+
+    /// # Soft
+    ///
+    /// Rejects with the status (usually `503` or `202`) and a JSON [`Busy`] body instead of an empty `503`.
+    pub fn soft(mut self, status: impl Into<StatusCode>) -> Self {
+        self.soft_status = Some(status.into());
+        self
+    }
+
+    /// The amount of requests running.
+    pub fn in_flight(&self) -> usize {
+        self.max_in_flight - self.semaphore.available_permits()
+    }
+
+    /// The amount of requests waiting for a slot.
+    pub fn waiting(&self) -> usize {
+        self.waiting.load(Ordering::Relaxed)
+    }
+
+    /// # Estimated Wait
+    ///
+    /// The estimated time until the request at the queue position runs, from the mean latency of finished work, see `WorkStats::latency`.
+    pub fn estimated_wait(&self, queue_position: usize, latency: &LatencySnapshot) -> Duration {
+        if latency.count == 0 {
+            return Duration::ZERO;
+        }
+
+        let rounds = queue_position.div_ceil(self.max_in_flight) as f64;
+
+        Duration::from_secs_f64(rounds * latency.sum_ms / latency.count as f64 / 1_000.0)
+    }
+
+    /// # Acquire
+    ///
+    /// Waits for a slot, None if the queue is full.
+    pub async fn acquire(&self) -> Option<ConcurrencyPermit> {
+        let permit = match self.semaphore.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                //reserve a spot in the queue.
+                let reserved = self
+                    .waiting
+                    .fetch_update(Ordering::AcqRel, Ordering::Acquire, |w| {
+                        (w < self.max_waiting).then_some(w + 1)
+                    })
+                    .is_ok();
+
+                if !reserved {
+                    return None;
+                }
+
+                //the spot is given back even if the waiting request is dropped, such as when its client disconnects.
+                let _waiting = Waiting(&self.waiting);
+
+                self.semaphore.clone().acquire_owned().await.ok()?
+            }
+        };
+
+        Some(ConcurrencyPermit { _permit: permit })
+    }
+
+    /// the body of a soft rejection, None when rejections are not soft.
+    fn busy(&self, supervisor: Option<&Supervisor>) -> Option<Busy> {
+        self.soft_status?;
+
+        let latency = supervisor.map(|supervisor| supervisor.latency().snapshot());
+
+        let queue_position = self.waiting() + 1;
+        let estimated_wait = latency
+            .map(|latency| self.estimated_wait(queue_position, &latency))
+            .unwrap_or_default();
+
+        Some(Busy {
+            queue_position,
+            estimated_wait_ms: estimated_wait.as_millis() as u64,
+            retry_after: estimated_wait.as_secs().max(1),
+        })
+    }
+
+    /// the rejection sent when the queue is full.
+    fn rejection(&self, busy: Option<Busy>) -> Box<dyn Resolution + Send> {
+        let (Some(status), Some(busy)) = (self.soft_status, busy) else {
+            return EmptyResolution::status(503).resolve();
+        };
+
+        match JsonResolution::serialize(busy) {
+            Ok(mut json) => {
+                json.set_status(status);
+                json.resolve()
+            }
+            Err(e) => e.resolve(),
+        }
+    }
+}
+
+/// # Concurrency Permit
+///
+/// A slot of a [`ConcurrencyLimit`], released when dropped.
+pub struct ConcurrencyPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+/// a reserved spot in the queue of a limit, given back when dropped.
+struct Waiting<'a>(&'a AtomicUsize);
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// # Concurrency Limit
+///
+/// Middleware that holds a slot of the limit until the response is written, rejecting when the queue is full.
+pub fn concurrency_limit(limit: ConcurrencyLimit) -> MiddlewareClosure {
+    middleware_fn(move |req| {
+        let limit = limit.clone();
+
+        Box::pin(async move {
+            let Some(permit) = limit.acquire().await else {
+                let supervisor = req.extensions.get::<Arc<Supervisor>>().cloned();
+                let busy = limit.busy(supervisor.as_deref());
+
+                if let Some(busy) = &busy {
+                    req.add_header(
+                        "Retry-After".to_string(),
+                        Some(busy.retry_after.to_string()),
+                    );
+                }
+
+                return Middleware::Invalid(limit.rejection(busy));
+            };
+
+            //released when the request is dropped, after the response is written.
+            req.extensions.insert(permit);
+
+            Middleware::Next
+        })
+    })
+}
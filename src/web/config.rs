@@ -0,0 +1,261 @@
+use std::{collections::HashMap, path::Path, sync::Arc, time::Duration};
+
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use crate::web::{
+    Request, Resolution,
+    cache::{CachePreset, cache_control},
+    cors::{Cors, cors},
+    errors::ConfigError,
+    rate_limit::rate_limit,
+    resolution::{
+        IntoResolution, empty_resolution::EmptyResolution, file_resolution::FileResolution,
+    },
+    routing::{ResolutionFnRef, middleware::MiddlewareClosure, resolution_fn_ref},
+};
+
+/// # Route Table
+///
+/// A table of routes read from a TOML or JSON file, with middleware presets chosen by name.
+///
+/// ```toml
+/// [[routes]]
+/// path = "/api/users/{id}"
+/// handler = "get_user"
+/// cors = "permissive"
+/// cache = "short"
+/// auth = "admin"
+/// rate_limit = { requests = 100, per_seconds = 60 }
+///
+/// [[routes]]
+/// path = "/"
+/// file = "public/index.html"
+/// cache = "long"
+///
+/// [[routes]]
+/// path = "/old"
+/// redirect = "/new"
+/// ```
+///
+/// Loaded into an app with `App::load_route_table`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RouteTable {
+    pub routes: Vec<RouteEntry>,
+}
+
+/// # Route Entry
+///
+/// A single route of a [`RouteTable`].
+///
+/// Exactly one of `handler`, `file`, `redirect`, or `status` is used, in that order.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RouteEntry {
+    /// The route, for example `/users/{id}`.
+    pub path: String,
+
+    /// The method of the route.
+    ///
+    /// By default (GET)
+    #[serde(default = "default_method")]
+    pub method: String,
+
+    /// The name of a handler added to the [`ConfigRegistry`].
+    pub handler: Option<String>,
+    /// A file to serve.
+    pub file: Option<String>,
+    /// A url to permanently redirect to.
+    pub redirect: Option<String>,
+    /// A status to respond with.
+    pub status: Option<i32>,
+
+    /// `"permissive"` or a CORS policy.
+    pub cors: Option<CorsSpec>,
+    /// The name of a `Cache-Control` preset, see `web::cache::CachePreset`.
+    pub cache: Option<String>,
+    /// The name of middleware added to the [`ConfigRegistry`] that authenticates the request.
+    pub auth: Option<String>,
+    /// A per client rate limit.
+    pub rate_limit: Option<RateLimitSpec>,
+    /// Names of middleware registered with `App::register_middleware`, called after the presets.
+    #[serde(default)]
+    pub middleware: Vec<String>,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+/// # Cors Spec
+///
+/// The CORS policy of a route, either a preset name or a policy.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum CorsSpec {
+    /// `"permissive"`
+    Preset(String),
+    /// A full policy.
+    Policy(Cors),
+}
+
+/// # Rate Limit Spec
+///
+/// Allows each client `requests` requests every `per_seconds` seconds.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitSpec {
+    pub requests: u32,
+    pub per_seconds: u64,
+}
+
+impl RouteTable {
+    /// Parses a table from TOML.
+    pub fn from_toml(source: &str) -> Result<Self, ConfigError> {
+        toml::from_str(source).map_err(|e| ConfigError::Parse(e.to_string()))
+    }
+
+    /// Parses a table from JSON.
+    pub fn from_json(source: &str) -> Result<Self, ConfigError> {
+        serde_json::from_str(source).map_err(|e| ConfigError::Parse(e.to_string()))
+    }
+
+    /// # From File
+    ///
+    /// Reads a table from a `.toml` or `.json` file.
+    pub async fn from_file(path: &str) -> Result<Self, ConfigError> {
+        let source = tokio::fs::read_to_string(path).await?;
+
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("json") => Self::from_json(&source),
+            Some("toml") => Self::from_toml(&source),
+            _ => Err(ConfigError::Parse(format!(
+                "'{path}' is not a .toml or .json file"
+            ))),
+        }
+    }
+}
+
+impl RouteEntry {
+    /// # Middleware
+    ///
+    /// Builds the middleware of the entry in the order rate limit, CORS, auth, then cache.
+    pub fn middleware(
+        &self,
+        registry: &ConfigRegistry,
+    ) -> Result<Vec<MiddlewareClosure>, ConfigError> {
+        let mut middleware = Vec::new();
+
+        if let Some(limit) = &self.rate_limit {
+            middleware.push(rate_limit(
+                limit.requests,
+                Duration::from_secs(limit.per_seconds),
+            ));
+        }
+
+        if let Some(policy) = self.cors_policy()? {
+            middleware.push(cors(policy));
+        }
+
+        if let Some(auth) = &self.auth {
+            let auth = registry
+                .middleware
+                .get(auth)
+                .ok_or_else(|| ConfigError::UnknownPreset("auth", auth.clone()))?;
+
+            middleware.push(auth.clone());
+        }
+
+        if let Some(cache) = &self.cache {
+            let preset = CachePreset::from_name(cache)
+                .ok_or_else(|| ConfigError::UnknownPreset("cache", cache.clone()))?;
+
+            middleware.push(cache_control(preset.header_value()));
+        }
+
+        Ok(middleware)
+    }
+
+    /// The CORS policy of the entry, None if it has none.
+    pub fn cors_policy(&self) -> Result<Option<Cors>, ConfigError> {
+        match &self.cors {
+            None => Ok(None),
+            Some(CorsSpec::Policy(policy)) => Ok(Some(policy.clone())),
+            Some(CorsSpec::Preset(name)) if name == "permissive" => Ok(Some(Cors::permissive())),
+            Some(CorsSpec::Preset(name)) => Err(ConfigError::UnknownPreset("cors", name.clone())),
+        }
+    }
+
+    /// # Resolution
+    ///
+    /// The resolution function of the entry.
+    pub fn resolution(&self, registry: &ConfigRegistry) -> Result<ResolutionFnRef, ConfigError> {
+        if let Some(name) = &self.handler {
+            return registry
+                .handlers
+                .get(name)
+                .cloned()
+                .ok_or_else(|| ConfigError::UnknownHandler(name.clone()));
+        }
+
+        if let Some(file) = &self.file {
+            let file = file.clone();
+
+            return Ok(Arc::new(move |_| {
+                let file = file.clone();
+                Box::pin(async move { FileResolution::new(&file).resolve() })
+            }));
+        }
+
+        if let Some(location) = &self.redirect {
+            let location = location.clone();
+
+            return Ok(Arc::new(move |req: &mut Request| {
+                req.add_header("Location".to_string(), Some(location.clone()));
+
+                Box::pin(async move { EmptyResolution::status(308).resolve() })
+            }));
+        }
+
+        if let Some(status) = self.status {
+            return Ok(Arc::new(move |_| {
+                Box::pin(async move { EmptyResolution::status(status).resolve() })
+            }));
+        }
+
+        Err(ConfigError::Parse(format!(
+            "the route '{}' has no handler, file, redirect, or status",
+            self.path
+        )))
+    }
+}
+
+/// # Config Registry
+///
+/// The handlers and middleware a [`RouteTable`] may reference by name.
+#[derive(Default, Clone)]
+pub struct ConfigRegistry {
+    handlers: HashMap<String, ResolutionFnRef>,
+    middleware: HashMap<String, MiddlewareClosure>,
+}
+
+impl ConfigRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a handler that routes reference with `handler = "name"`.
+    pub fn add_handler<F, Fut>(&mut self, name: &str, handler: F)
+    where
+        F: Fn(Arc<Mutex<Request>>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output: IntoResolution> + Send + 'static,
+    {
+        let handler = resolution_fn_ref(handler);
+
+        self.handlers.insert(name.to_string(), handler);
+    }
+
+    /// Adds auth middleware that routes reference with `auth = "name"`, for example `web::auth::bearer(tokens)`.
+    pub fn add_middleware(&mut self, name: &str, middleware: MiddlewareClosure) {
+        self.middleware.insert(name.to_string(), middleware);
+    }
+}
@@ -0,0 +1,95 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// # Overflow
+///
+/// What the app does with connections over a [`ConnectionLimit`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Overflow {
+    /// Stops accepting until a connection closes, new clients wait in the TCP backlog of the system.
+    #[default]
+    Backpressure,
+    /// Accepts the connection and answers `503` with `Retry-After`, then closes it. TLS connections are closed without a response.
+    Reject,
+}
+
+/// # Connection Limit
+///
+/// Caps the connections the app serves at once, see `App::set_connection_limit`. Without a limit every accepted connection is queued for the workers, so memory grows with the load.
+///
+/// Cloning shares the same limit.
+///
+/// ```ignore
+/// app.set_connection_limit(Some(ConnectionLimit::new(10_000).overflow(Overflow::Reject)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConnectionLimit {
+    semaphore: Arc<Semaphore>,
+    max: usize,
+    overflow: Overflow,
+    rejected: Arc<AtomicU64>,
+}
+
+impl ConnectionLimit {
+    /// Create a limit of `max` connections, further clients wait in the backlog.
+    pub fn new(max: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max)),
+            max,
+            overflow: Overflow::Backpressure,
+            rejected: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// What is done with connections over the limit.
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// The most connections that are served at once.
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// The connections being served.
+    pub fn active(&self) -> usize {
+        self.max - self.semaphore.available_permits()
+    }
+
+    /// The connections that were rejected with `503`.
+    pub fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+
+    /// True if the app stops accepting while the limit is reached.
+    pub fn backpressure(&self) -> bool {
+        self.overflow == Overflow::Backpressure
+    }
+
+    /// # Acquire
+    ///
+    /// Waits for a free connection, the connection is served until the permit is dropped.
+    pub async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        self.semaphore.clone().acquire_owned().await.ok()
+    }
+
+    /// # Try Acquire
+    ///
+    /// A free connection, None if the limit is reached, counting the connection as rejected.
+    pub fn try_acquire(&self) -> Option<OwnedSemaphorePermit> {
+        let permit = self.semaphore.clone().try_acquire_owned().ok();
+
+        if permit.is_none() {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+        }
+
+        permit
+    }
+}
@@ -0,0 +1,302 @@
+use std::time::Duration;
+
+use aes_gcm::{
+    Aes256Gcm, Key, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::web::errors::CookieError;
+
+/// # Same Site
+///
+/// The `SameSite` attribute of a cookie.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+/// # Cookie
+///
+/// A cookie that is sent to the client with a `Set-Cookie` header, see `Request::set_cookie`.
+///
+/// ```ignore
+/// let mut cookie = Cookie::new("session", &keyring.encrypt("session", &session_id));
+/// cookie.max_age = Some(Duration::from_secs(60 * 60));
+///
+/// req.lock().await.set_cookie(cookie)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    /// By default ("/")
+    pub path: Option<String>,
+    pub domain: Option<String>,
+    pub max_age: Option<Duration>,
+    /// By default (true)
+    pub http_only: bool,
+    /// By default (true)
+    pub secure: bool,
+    /// By default (Lax)
+    pub same_site: Option<SameSite>,
+}
+
+impl Cookie {
+    /// Create a new cookie with secure defaults.
+    pub fn new(name: &str, value: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            value: value.to_string(),
+            path: Some("/".to_string()),
+            domain: None,
+            max_age: None,
+            http_only: true,
+            secure: true,
+            same_site: Some(SameSite::Lax),
+        }
+    }
+
+    /// Create a cookie that removes the cookie with the name from the client.
+    pub fn removal(name: &str) -> Self {
+        let mut cookie = Self::new(name, "");
+        cookie.max_age = Some(Duration::ZERO);
+
+        cookie
+    }
+
+    /// # Header Value
+    ///
+    /// Formats the cookie as the value of a `Set-Cookie` header.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CookieError` if the name is not a token, the value has a character that is not a cookie-octet, or the path or domain has `;` or a control character.
+    /// These would otherwise inject attributes or headers into the response.
+    pub fn header_value(&self) -> Result<String, CookieError> {
+        if self.name.is_empty() || !self.name.bytes().all(is_token) {
+            return Err(CookieError::InvalidName(self.name.clone()));
+        }
+
+        //the value may be quoted, the quotes are not part of it.
+        let unquoted = self
+            .value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(&self.value);
+
+        if !unquoted.bytes().all(is_cookie_octet) {
+            return Err(CookieError::InvalidValue(self.name.clone()));
+        }
+
+        let mut value = format!("{}={}", self.name, self.value);
+
+        if let Some(path) = &self.path {
+            if !path.bytes().all(is_attribute_octet) {
+                return Err(CookieError::InvalidAttribute("Path"));
+            }
+
+            value.push_str(&format!("; Path={path}"));
+        }
+
+        if let Some(domain) = &self.domain {
+            if !domain.bytes().all(is_attribute_octet) {
+                return Err(CookieError::InvalidAttribute("Domain"));
+            }
+
+            value.push_str(&format!("; Domain={domain}"));
+        }
+
+        if let Some(max_age) = &self.max_age {
+            value.push_str(&format!("; Max-Age={}", max_age.as_secs()));
+        }
+
+        if self.http_only {
+            value.push_str("; HttpOnly");
+        }
+
+        if self.secure {
+            value.push_str("; Secure");
+        }
+
+        if let Some(same_site) = &self.same_site {
+            let same_site = match same_site {
+                SameSite::Strict => "Strict",
+                SameSite::Lax => "Lax",
+                SameSite::None => "None",
+            };
+
+            value.push_str(&format!("; SameSite={same_site}"));
+        }
+
+        Ok(value)
+    }
+}
+
+/// a tchar of RFC 9110, the characters of a cookie name.
+pub(crate) fn is_token(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+/// a cookie-octet of RFC 6265, visible ASCII except `"`, `,`, `;`, and `\`.
+fn is_cookie_octet(b: u8) -> bool {
+    matches!(b, 0x21 | 0x23..=0x2b | 0x2d..=0x3a | 0x3c..=0x5b | 0x5d..=0x7e)
+}
+
+/// an attribute value of RFC 6265, any character except control characters and `;`.
+fn is_attribute_octet(b: u8) -> bool {
+    matches!(b, 0x20..=0x7e) && b != b';'
+}
+
+/// # Parse Cookies
+///
+/// Parses the value of a `Cookie` header into name and value pairs.
+pub fn parse_cookies(header: &str) -> Vec<(String, String)> {
+    header
+        .split(';')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+/// keys derived from a single secret.
+struct KeyPair {
+    signing: Vec<u8>,
+    encryption: Key<Aes256Gcm>,
+}
+
+impl KeyPair {
+    fn derive(secret: &[u8]) -> Self {
+        let derive = |purpose: &[u8]| {
+            let mut mac =
+                <Hmac<Sha256> as Mac>::new_from_slice(secret).expect("hmac takes any key size");
+            mac.update(purpose);
+            mac.finalize().into_bytes()
+        };
+
+        Self {
+            signing: derive(b"async-web cookie signing").to_vec(),
+            encryption: derive(b"async-web cookie encryption"),
+        }
+    }
+
+    fn mac(&self, name: &str, value: &str) -> Hmac<Sha256> {
+        let mut mac =
+            <Hmac<Sha256> as Mac>::new_from_slice(&self.signing).expect("hmac takes any key size");
+        //the name is length prefixed, so no other name and value sign the same bytes.
+        mac.update(&(name.len() as u64).to_be_bytes());
+        mac.update(name.as_bytes());
+        mac.update(value.as_bytes());
+
+        mac
+    }
+}
+
+/// # Keyring
+///
+/// Signs and encrypts cookie values so they can safely round trip through the client.
+///
+/// * signed values can be read by the client but not changed
+/// * encrypted values can be neither read nor changed
+///
+/// The name of the cookie is bound to the value, so a value cannot be moved to another cookie.
+///
+/// Keys can be rotated by adding the old secret with `add_previous`, values are issued with the newest secret and verified with all of them.
+///
+/// ```ignore
+/// let keyring = Keyring::new(b"a long random secret of at least 32 bytes");
+///
+/// let signed = keyring.sign("flash", "saved!");
+/// assert_eq!(keyring.verify("flash", &signed), Some("saved!".to_string()));
+/// ```
+pub struct Keyring {
+    keys: Vec<KeyPair>,
+}
+
+impl Keyring {
+    /// Create a keyring from a secret, the secret should be at least 32 random bytes.
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            keys: vec![KeyPair::derive(secret)],
+        }
+    }
+
+    /// Adds a previously used secret, values issued with it are still accepted.
+    pub fn add_previous(&mut self, secret: &[u8]) {
+        self.keys.push(KeyPair::derive(secret));
+    }
+
+    /// # Sign
+    ///
+    /// Returns `value.signature` for the cookie `name`.
+    pub fn sign(&self, name: &str, value: &str) -> String {
+        let signature = self.keys[0].mac(name, value).finalize().into_bytes();
+
+        format!("{value}.{}", URL_SAFE_NO_PAD.encode(signature))
+    }
+
+    /// # Verify
+    ///
+    /// Returns the value of a signed cookie if the signature is valid.
+    pub fn verify(&self, name: &str, signed: &str) -> Option<String> {
+        let (value, signature) = signed.rsplit_once('.')?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+
+        self.keys
+            .iter()
+            .any(|key| key.mac(name, value).verify_slice(&signature).is_ok())
+            .then(|| value.to_string())
+    }
+
+    /// # Encrypt
+    ///
+    /// Encrypts the value for the cookie `name` with AES-256-GCM.
+    pub fn encrypt(&self, name: &str, value: &str) -> String {
+        let cipher = Aes256Gcm::new(&self.keys[0].encryption);
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+        let payload = Payload {
+            msg: value.as_bytes(),
+            aad: name.as_bytes(),
+        };
+
+        let encrypted = cipher
+            .encrypt(&nonce, payload)
+            .expect("encrypting into a vec does not fail");
+
+        let mut combined = nonce.to_vec();
+        combined.extend_from_slice(&encrypted);
+
+        URL_SAFE_NO_PAD.encode(combined)
+    }
+
+    /// # Decrypt
+    ///
+    /// Returns the value of an encrypted cookie if it was encrypted by this keyring and not changed.
+    pub fn decrypt(&self, name: &str, encrypted: &str) -> Option<String> {
+        let combined = URL_SAFE_NO_PAD.decode(encrypted).ok()?;
+
+        if combined.len() < 12 {
+            return None;
+        }
+
+        let (nonce, encrypted) = combined.split_at(12);
+        let nonce = Nonce::from_slice(nonce);
+
+        self.keys.iter().find_map(|key| {
+            let payload = Payload {
+                msg: encrypted,
+                aad: name.as_bytes(),
+            };
+
+            Aes256Gcm::new(&key.encryption)
+                .decrypt(nonce, payload)
+                .ok()
+                .and_then(|value| String::from_utf8(value).ok())
+        })
+    }
+}
@@ -0,0 +1,154 @@
+use serde::Deserialize;
+
+use crate::web::{
+    Method, Middleware, Request, Resolution, StatusCode, middleware_fn,
+    resolution::semantic::NoContent, routing::middleware::MiddlewareClosure,
+};
+
+/// # Cors
+///
+/// The Cross-Origin Resource Sharing policy of a route.
+///
+/// May be read from a route table, see `web::config`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Cors {
+    /// Allowed origins, `*` allows any origin.
+    ///
+    /// By default (["*"])
+    pub origins: Vec<String>,
+    /// Allowed methods of a preflight.
+    ///
+    /// By default (["GET", "POST", "PUT", "PATCH", "DELETE"])
+    pub methods: Vec<String>,
+    /// Allowed request headers of a preflight.
+    ///
+    /// By default (["Content-Type", "Authorization"])
+    pub headers: Vec<String>,
+    /// Whether cookies and credentials may be sent, never combined with a `*` origin.
+    ///
+    /// By default (false)
+    pub credentials: bool,
+    /// How long a browser may cache a preflight, in seconds.
+    ///
+    /// By default (600)
+    pub max_age: u64,
+}
+
+impl Default for Cors {
+    fn default() -> Self {
+        Self {
+            origins: vec!["*".to_string()],
+            methods: ["GET", "POST", "PUT", "PATCH", "DELETE"]
+                .map(String::from)
+                .to_vec(),
+            headers: ["Content-Type", "Authorization"].map(String::from).to_vec(),
+            credentials: false,
+            max_age: 600,
+        }
+    }
+}
+
+impl Cors {
+    /// # Permissive
+    ///
+    /// Allows any origin without credentials.
+    pub fn permissive() -> Self {
+        Self::default()
+    }
+
+    /// # Allow Origins
+    ///
+    /// Allows only the origins, for example `https://example.com`.
+    pub fn allow_origins(origins: &[&str]) -> Self {
+        Self {
+            origins: origins.iter().map(|o| o.to_string()).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// the value of `Access-Control-Allow-Origin` for the origin, None if it is not allowed.
+    fn allowed_origin(&self, origin: &str) -> Option<String> {
+        if self.origins.iter().any(|o| o == "*") {
+            //credentials may not be combined with a wildcard, echo the origin instead.
+            return Some(if self.credentials {
+                origin.to_string()
+            } else {
+                "*".to_string()
+            });
+        }
+
+        self.origins
+            .iter()
+            .find(|o| o.eq_ignore_ascii_case(origin))
+            .map(|_| origin.to_string())
+    }
+
+    /// adds the CORS headers for the origin of the request.
+    fn apply(&self, req: &mut Request, preflight: bool) -> bool {
+        let Some(origin) = req.request_header("Origin").cloned() else {
+            return true;
+        };
+
+        let Some(allowed) = self.allowed_origin(&origin) else {
+            return false;
+        };
+
+        if allowed != "*" {
+            req.add_header("Vary".to_string(), Some("Origin".to_string()));
+        }
+
+        req.add_header("Access-Control-Allow-Origin".to_string(), Some(allowed));
+
+        if self.credentials {
+            req.add_header(
+                "Access-Control-Allow-Credentials".to_string(),
+                Some("true".to_string()),
+            );
+        }
+
+        if preflight {
+            req.add_header(
+                "Access-Control-Allow-Methods".to_string(),
+                Some(self.methods.join(", ")),
+            );
+            req.add_header(
+                "Access-Control-Allow-Headers".to_string(),
+                Some(self.headers.join(", ")),
+            );
+            req.add_header(
+                "Access-Control-Max-Age".to_string(),
+                Some(self.max_age.to_string()),
+            );
+        }
+
+        true
+    }
+}
+
+/// # Cors
+///
+/// Middleware that adds the CORS headers of the policy to responses, rejecting origins that are not allowed with `403`.
+///
+/// Requests without an `Origin` header are not changed.
+///
+/// Preflight requests (`OPTIONS`) are answered with `204` by this middleware, route `OPTIONS` to any resolution and add this middleware to it, see `App::load_route_table` which does so automatically.
+pub fn cors(policy: Cors) -> MiddlewareClosure {
+    middleware_fn(move |req| {
+        let policy = policy.clone();
+
+        Box::pin(async move {
+            let preflight = matches!(&req.method, Method::Other(m) if m == "OPTIONS");
+
+            if !policy.apply(req, preflight) {
+                return Middleware::InvalidEmpty(StatusCode::FORBIDDEN);
+            }
+
+            if preflight {
+                return Middleware::Invalid(NoContent.resolve());
+            }
+
+            Middleware::Next
+        })
+    })
+}
@@ -0,0 +1,309 @@
+use std::{
+    io,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    pin::Pin,
+    sync::{Arc, Mutex as StdMutex},
+    task::{Context, Poll},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use tokio::{
+    fs::File,
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadBuf},
+    net::TcpStream,
+    sync::Mutex,
+};
+
+use crate::web::{Method, routing::Scope};
+
+/// headers whose values are never written to a tap.
+const REDACTED_HEADERS: [&str; 5] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-api-key",
+];
+
+/// # Tap Sink
+///
+/// Where tapped connections are written to.
+#[derive(Debug, Clone)]
+pub enum TapSink {
+    /// Connections are appended to a file.
+    File(PathBuf),
+    /// Each connection is sent to a TCP listener, for example `nc -lk 9999`.
+    Socket(SocketAddr),
+}
+
+/// # Debug Tap
+///
+/// Mirrors the raw bytes of requests and responses to a sink, for diagnosing protocol-level issues with odd clients.
+///
+/// Only connections of the listed clients and requests in the scope are written, at most `max_bytes` in each direction, with credential headers redacted. TLS connections are tapped after decryption.
+///
+/// Tapping copies every byte of a connection, only enable it while debugging.
+///
+/// ```ignore
+/// let tap = DebugTap::new(TapSink::File("tap.log".into()))
+///     .client("10.0.0.7".parse()?)
+///     .scope(Scope::new().include("/webhooks/{*}"));
+///
+/// app.set_debug_tap(tap);
+/// ```
+#[derive(Clone)]
+pub struct DebugTap {
+    sink: TapSink,
+    clients: Vec<IpAddr>,
+    scope: Scope,
+    max_bytes: usize,
+    file: Arc<Mutex<Option<File>>>,
+}
+
+impl DebugTap {
+    /// Create a tap of every connection that writes to the sink, up to 64 KiB in each direction.
+    pub fn new(sink: TapSink) -> Self {
+        Self {
+            sink,
+            clients: Vec::new(),
+            scope: Scope::new(),
+            max_bytes: 64 * 1024,
+            file: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Only taps connections of the client, may be called for several clients.
+    pub fn client(mut self, client: IpAddr) -> Self {
+        self.clients.push(client);
+        self
+    }
+
+    /// Only taps requests in the scope.
+    pub fn scope(mut self, scope: Scope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Sets the most bytes kept in each direction.
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// True if connections of the client are tapped.
+    pub fn taps_client(&self, client: IpAddr) -> bool {
+        self.clients.is_empty() || self.clients.contains(&client)
+    }
+
+    /// # Wrap
+    ///
+    /// Wraps the stream of a connection so its bytes are recorded, write the recording with `emit` once the connection is handled.
+    pub(crate) fn wrap<S>(&self, stream: S) -> (TapStream<S>, Arc<StdMutex<Recording>>) {
+        let recording = Arc::new(StdMutex::new(Recording {
+            max_bytes: self.max_bytes,
+            ..Recording::default()
+        }));
+
+        let stream = TapStream {
+            inner: stream,
+            recording: recording.clone(),
+        };
+
+        (stream, recording)
+    }
+
+    /// # Emit
+    ///
+    /// Writes a recording to the sink if its request is in the scope, failing to write is ignored.
+    pub(crate) async fn emit(&self, client: SocketAddr, recording: &StdMutex<Recording>) {
+        let text = {
+            let recording = recording.lock().unwrap_or_else(|e| e.into_inner());
+
+            if !recording.in_scope(&self.scope) {
+                return;
+            }
+
+            recording.format(client)
+        };
+
+        match &self.sink {
+            TapSink::File(path) => {
+                let mut file = self.file.lock().await;
+
+                if file.is_none() {
+                    *file = tokio::fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(path)
+                        .await
+                        .ok();
+                }
+
+                if let Some(f) = file.as_mut()
+                    && f.write_all(text.as_bytes()).await.is_err()
+                {
+                    //reopen the file on the next connection.
+                    *file = None;
+                }
+            }
+            TapSink::Socket(addr) => {
+                if let Ok(mut socket) = TcpStream::connect(addr).await {
+                    let _ = socket.write_all(text.as_bytes()).await;
+                }
+            }
+        }
+    }
+}
+
+/// # Recording
+///
+/// The bytes of a tapped connection.
+#[derive(Default)]
+pub(crate) struct Recording {
+    max_bytes: usize,
+    inbound: Vec<u8>,
+    inbound_total: usize,
+    outbound: Vec<u8>,
+    outbound_total: usize,
+}
+
+impl Recording {
+    fn push(buffer: &mut Vec<u8>, total: &mut usize, max: usize, bytes: &[u8]) {
+        *total += bytes.len();
+
+        let room = max.saturating_sub(buffer.len());
+        buffer.extend_from_slice(&bytes[..bytes.len().min(room)]);
+    }
+
+    /// true if the request line of the inbound bytes is in the scope.
+    fn in_scope(&self, scope: &Scope) -> bool {
+        let line = self.inbound.split(|b| *b == b'\n').next().unwrap_or_default();
+        let line = String::from_utf8_lossy(line);
+        let mut parts = line.split_whitespace();
+
+        let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+            //an unparsable request is what a tap is for.
+            return true;
+        };
+
+        let path = target.split('?').next().unwrap_or_default();
+
+        scope.contains(path, &Method::from(method))
+    }
+
+    fn format(&self, client: SocketAddr) -> String {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let section = |arrow: &str, bytes: &[u8], total: usize| {
+            let truncated = if total > bytes.len() {
+                format!(", {} not kept", total - bytes.len())
+            } else {
+                String::new()
+            };
+
+            format!(
+                "{arrow} {total} bytes{truncated}\n{}\n",
+                redact(&String::from_utf8_lossy(bytes))
+            )
+        };
+
+        format!(
+            "=== {timestamp} {client} ===\n{}{}\n",
+            section(">>>", &self.inbound, self.inbound_total),
+            section("<<<", &self.outbound, self.outbound_total)
+        )
+    }
+}
+
+/// replaces the values of credential headers in the head of a message.
+fn redact(message: &str) -> String {
+    let (head, body) = match message.split_once("\r\n\r\n") {
+        Some((head, body)) => (head, Some(body)),
+        None => (message, None),
+    };
+
+    let head = head
+        .split("\r\n")
+        .map(|line| match line.split_once(':') {
+            Some((name, _)) if REDACTED_HEADERS.contains(&name.trim().to_lowercase().as_str()) => {
+                format!("{name}: [redacted]")
+            }
+            _ => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\r\n");
+
+    match body {
+        Some(body) => format!("{head}\r\n\r\n{body}"),
+        None => head,
+    }
+}
+
+/// # Tap Stream
+///
+/// A stream that records the bytes read from and written to it.
+pub(crate) struct TapStream<S> {
+    inner: S,
+    recording: Arc<StdMutex<Recording>>,
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for TapStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+
+        if let Poll::Ready(Ok(())) = poll {
+            let mut recording = self.recording.lock().unwrap_or_else(|e| e.into_inner());
+            let recording = &mut *recording;
+
+            Recording::push(
+                &mut recording.inbound,
+                &mut recording.inbound_total,
+                recording.max_bytes,
+                &buf.filled()[before..],
+            );
+        }
+
+        poll
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for TapStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+
+        if let Poll::Ready(Ok(written)) = poll {
+            let mut recording = self.recording.lock().unwrap_or_else(|e| e.into_inner());
+            let recording = &mut *recording;
+
+            Recording::push(
+                &mut recording.outbound,
+                &mut recording.outbound_total,
+                recording.max_bytes,
+                &buf[..written],
+            );
+        }
+
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
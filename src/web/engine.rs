@@ -0,0 +1,86 @@
+/// # Engine
+///
+/// What reads requests from and writes responses to the connections of the app, see `App::set_engine`.
+///
+/// Routing, middleware, and resolutions are the same with every engine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Engine {
+    /// The HTTP/1.1 parser and writer of this crate.
+    #[default]
+    Native,
+
+    /// Connections are served by hyper's HTTP/1.1 server, for its battle-tested parsing and connection handling.
+    ///
+    /// Each request hyper accepts is passed to the app as a well-formed request and the response of the app is streamed back through hyper. The keep-alive of the app decides if hyper keeps connections open, and the head limits bound the heads hyper reads.
+    ///
+    /// Requires the `hyper` feature.
+    #[cfg(feature = "hyper")]
+    Hyper,
+}
+
+#[cfg(feature = "hyper")]
+pub(crate) use hyper_engine::serve_hyper;
+
+#[cfg(feature = "hyper")]
+mod hyper_engine {
+    use std::{net::SocketAddr, time::Duration};
+
+    use hyper::{body::Incoming, header::EXPECT, server::conn::http1, service::service_fn};
+    use hyper_util::rt::{TokioIo, TokioTimer};
+    use tokio::io::{AsyncRead, AsyncWrite};
+
+    use crate::web::{
+        app::{AppContext, serve_stream},
+        routing::request::HeadLimits,
+    };
+
+    /// the size of the in memory connection each request is passed to the app over.
+    const CONNECTION_BUFFER: usize = 64 * 1024;
+
+    /// the smallest read buffer hyper allows.
+    const MIN_BUFFER: usize = 8 * 1024;
+
+    /// serves the connection with hyper, each request is passed to the app over an in memory connection by hyper's client.
+    pub(crate) async fn serve_hyper<S>(
+        stream: S,
+        client_socket: SocketAddr,
+        context: AppContext,
+        keep_alive: Option<Duration>,
+        head_limits: HeadLimits,
+    ) -> Result<(), Box<dyn std::error::Error>>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let service = service_fn(move |mut request: hyper::Request<Incoming>| {
+            let context = context.clone();
+
+            async move {
+                //hyper answers the expectation once the body is read.
+                request.headers_mut().remove(EXPECT);
+
+                let (client, server) = tokio::io::duplex(CONNECTION_BUFFER);
+
+                tokio::spawn(async move {
+                    let _ = serve_stream(server, client_socket, context).await;
+                });
+
+                let (mut sender, connection) =
+                    hyper::client::conn::http1::handshake(TokioIo::new(client)).await?;
+
+                tokio::spawn(connection);
+
+                sender.send_request(request).await
+            }
+        });
+
+        http1::Builder::new()
+            .timer(TokioTimer::new())
+            .keep_alive(keep_alive.is_some())
+            .header_read_timeout(keep_alive.unwrap_or(head_limits.line_timeout))
+            .max_buf_size(head_limits.max_size.max(MIN_BUFFER))
+            .serve_connection(TokioIo::new(stream), service)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use std::{error::Error, sync::Arc};
+
+use crate::web::StatusCode;
+
+tokio::task_local! {
+    static ERROR_MAP: Arc<ErrorMap>;
+}
+
+/// Idiomatic type alias for a check of the type of an error.
+type ErrorCheck = dyn Fn(&(dyn Error + 'static)) -> bool + Send + Sync;
+
+/// # Error Map
+///
+/// The statuses of error types, so an `ErrorResolution` of a `serde_json::Error` is sent as `400` instead of `500`, see `App::map_error`.
+///
+/// Errors of types that are not mapped keep `500`. A status given with `ErrorResolution::with_status` replaces the mapped one.
+///
+/// With debug on, JSON errors include the debug output of the error, its sources, and a backtrace, see `App::set_error_debug`. Do not turn it on in production.
+#[derive(Clone, Default)]
+pub struct ErrorMap {
+    mappings: Vec<(Arc<ErrorCheck>, StatusCode)>,
+    debug: bool,
+    problem_details: bool,
+}
+
+impl ErrorMap {
+    /// Create a map without mappings, with debug off.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Map
+    ///
+    /// Sends errors of type `E` with the status, replacing an earlier mapping of `E`.
+    pub fn map<E: Error + 'static>(&mut self, status: impl Into<StatusCode>) {
+        self.mappings.insert(0, (Arc::new(|error| error.is::<E>()), status.into()));
+    }
+
+    /// Turns the detail of JSON errors on or off.
+    pub fn set_debug(&mut self, debug: bool) {
+        self.debug = debug;
+    }
+
+    /// True if JSON errors include their detail.
+    pub fn debug(&self) -> bool {
+        self.debug
+    }
+
+    /// Sends JSON errors as `application/problem+json`, see `resolution::problem_details::ProblemDetails`.
+    pub fn set_problem_details(&mut self, problem_details: bool) {
+        self.problem_details = problem_details;
+    }
+
+    /// True if JSON errors are sent as problem details.
+    pub fn problem_details(&self) -> bool {
+        self.problem_details
+    }
+
+    /// The status errors of the type are mapped to, None if the type is not mapped.
+    pub fn status_of(&self, error: &(dyn Error + 'static)) -> Option<StatusCode> {
+        self.mappings
+            .iter()
+            .find(|(is, _)| is(error))
+            .map(|(_, status)| *status)
+    }
+
+    /// # Scope
+    ///
+    /// Uses the map for error resolutions created by the future, the app scopes each request with its map.
+    pub async fn scope<F: Future>(self: Arc<Self>, future: F) -> F::Output {
+        ERROR_MAP.scope(self, future).await
+    }
+
+    /// The map of the current request, None outside of a request.
+    pub fn current() -> Option<Arc<ErrorMap>> {
+        ERROR_MAP.try_with(|map| map.clone()).ok()
+    }
+}
+
+impl std::fmt::Debug for ErrorMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ErrorMap")
+            .field("mappings", &self.mappings.len())
+            .field("debug", &self.debug)
+            .field("problem_details", &self.problem_details)
+            .finish()
+    }
+}
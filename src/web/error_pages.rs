@@ -0,0 +1,102 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::web::{Resolution, StatusCode, resolution::response::Response};
+
+/// Idiomatic type alias for a closure that produces the page of an error status.
+pub type ErrorPage = dyn Fn(StatusCode) -> Box<dyn Resolution + Send> + Send + Sync;
+
+/// # Error Pages
+///
+/// The pages sent for the errors the app generates itself, instead of an empty body, see `App::set_error_pages`.
+///
+/// The app generates `400` for a request it can not parse, `501` for a body sent with a transfer coding other than chunked, `404` when no route matches, `405` when the route has no handler for the method, `413` for a body over the `BodyLimits`, `500` for a panic without an `on_panic` handler, and the status of `Middleware::InvalidEmpty`.
+///
+/// A page of the exact status is used first, then the page of its class (`4xx` or `5xx`), an error without a page keeps its empty body. A `404` or `405` without a page closes the connection as before.
+///
+/// ```ignore
+/// let pages = ErrorPages::new()
+///     .status(404, template("<h1>Nothing here</h1><p>{status} {reason}</p>"))
+///     .client_errors(template("<h1>{status}</h1><p>{reason}</p>"))
+///     .server_errors(|status| Response::builder().status(status).json(&json!({ "error": "try again later" })).resolve());
+///
+/// app.set_error_pages(pages);
+/// ```
+#[derive(Clone, Default)]
+pub struct ErrorPages {
+    statuses: HashMap<u16, Arc<ErrorPage>>,
+    client_errors: Option<Arc<ErrorPage>>,
+    server_errors: Option<Arc<ErrorPage>>,
+}
+
+impl ErrorPages {
+    /// Create error pages without any page.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the page of a status, the page should answer with the status it is given.
+    pub fn status(
+        mut self,
+        status: impl Into<StatusCode>,
+        page: impl Fn(StatusCode) -> Box<dyn Resolution + Send> + Send + Sync + 'static,
+    ) -> Self {
+        self.statuses.insert(status.into().as_u16(), Arc::new(page));
+        self
+    }
+
+    /// Sets the page of every `4xx` status without a page of its own.
+    pub fn client_errors(
+        mut self,
+        page: impl Fn(StatusCode) -> Box<dyn Resolution + Send> + Send + Sync + 'static,
+    ) -> Self {
+        self.client_errors = Some(Arc::new(page));
+        self
+    }
+
+    /// Sets the page of every `5xx` status without a page of its own.
+    pub fn server_errors(
+        mut self,
+        page: impl Fn(StatusCode) -> Box<dyn Resolution + Send> + Send + Sync + 'static,
+    ) -> Self {
+        self.server_errors = Some(Arc::new(page));
+        self
+    }
+
+    /// The page of the status, None if neither the status nor its class has a page.
+    pub fn page(&self, status: impl Into<StatusCode>) -> Option<Box<dyn Resolution + Send>> {
+        let status = status.into();
+
+        let page = self.statuses.get(&status.as_u16()).or(match status {
+            s if s.is_client_error() => self.client_errors.as_ref(),
+            s if s.is_server_error() => self.server_errors.as_ref(),
+            _ => None,
+        })?;
+
+        Some(page(status))
+    }
+}
+
+/// # Template
+///
+/// A page sending the HTML template with the status, `{status}` is replaced with the code and `{reason}` with the reason phrase.
+///
+/// ```ignore
+/// ErrorPages::new().client_errors(template("<h1>{status}</h1><p>{reason}</p>"));
+/// ```
+pub fn template(
+    template: &str,
+) -> impl Fn(StatusCode) -> Box<dyn Resolution + Send> + Send + Sync + 'static {
+    let template = template.to_string();
+
+    move |status| {
+        let html = template
+            .replace("{status}", &status.as_u16().to_string())
+            .replace("{reason}", status.reason().unwrap_or_default());
+
+        Response::builder()
+            .status(status)
+            .header("Content-Type", "text/html; charset=utf-8")
+            .text(&html)
+            .resolve()
+    }
+}
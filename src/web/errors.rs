@@ -1,5 +1,17 @@
 pub mod app_state;
+pub mod auth_error;
+pub mod config_error;
+pub mod cookie_error;
+pub mod error;
+pub mod extract_error;
 pub mod routing_error;
+pub mod status_code_error;
+pub mod tls_error;
 pub mod worker_error;
 
-pub use self::{app_state::AppState, routing_error::RoutingError, worker_error::WorkerError};
+pub use self::{
+    app_state::AppState, auth_error::AuthError, config_error::ConfigError, cookie_error::CookieError,
+    error::{Error, Result}, extract_error::ExtractError,
+    routing_error::RoutingError, status_code_error::StatusCodeError, tls_error::TlsError,
+    worker_error::WorkerError,
+};
@@ -0,0 +1,31 @@
+/// # auth error
+///
+/// An error that represents why a request could not be authenticated.
+#[derive(Debug)]
+pub enum AuthError {
+    /// No credentials were provided for the expected scheme.
+    MissingCredentials,
+    /// The credentials were not formatted correctly.
+    Malformed(String),
+    /// The credentials were well formed but were not accepted.
+    Rejected,
+    /// The token has expired or is not valid yet.
+    Expired,
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let err = match &self {
+            AuthError::MissingCredentials => "no credentials were provided",
+            AuthError::Malformed(reason) => {
+                &format!("the credentials were malformed because {reason}")
+            }
+            AuthError::Rejected => "the credentials were rejected",
+            AuthError::Expired => "the token is expired or not yet valid",
+        };
+
+        write!(f, "{err}")
+    }
+}
+
+impl std::error::Error for AuthError {}
@@ -0,0 +1,58 @@
+use crate::web::errors::RoutingError;
+
+/// # config error
+///
+/// An error that represents when loading a route table or the configuration of an app fails.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// Reading the file failed.
+    Io(std::io::Error),
+    /// The file is not a valid route table or configuration.
+    Parse(String),
+    /// A route references a handler that was not registered.
+    UnknownHandler(String),
+    /// A route references a preset that does not exist, the kind and name.
+    UnknownPreset(&'static str, String),
+    /// The route could not be added.
+    Routing(RoutingError),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let err = match &self {
+            ConfigError::Io(e) => &format!("the file could not be read because {e}"),
+            ConfigError::Parse(reason) => &format!("the configuration is invalid because {reason}"),
+            ConfigError::UnknownHandler(name) => {
+                &format!("no handler named '{name}' was registered")
+            }
+            ConfigError::UnknownPreset(kind, name) => {
+                &format!("no {kind} preset named '{name}' exist")
+            }
+            ConfigError::Routing(e) => &format!("the route could not be added because {e}"),
+        };
+
+        write!(f, "{err}")
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self {
+            ConfigError::Io(e) => Some(e),
+            ConfigError::Routing(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(value: std::io::Error) -> Self {
+        ConfigError::Io(value)
+    }
+}
+
+impl From<RoutingError> for ConfigError {
+    fn from(value: RoutingError) -> Self {
+        ConfigError::Routing(value)
+    }
+}
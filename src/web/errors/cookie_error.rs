@@ -0,0 +1,30 @@
+/// # cookie error
+///
+/// An error that represents why a cookie can not be written into a `Set-Cookie` header.
+#[derive(Debug)]
+pub enum CookieError {
+    /// The name is empty or is not a token.
+    InvalidName(String),
+    /// The value contains a character that is not a cookie-octet, such as `;`, `,`, a space, or a control character.
+    InvalidValue(String),
+    /// The attribute contains `;` or a control character.
+    InvalidAttribute(&'static str),
+}
+
+impl std::fmt::Display for CookieError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let err = match &self {
+            CookieError::InvalidName(name) => &format!("{name:?} is not a valid cookie name"),
+            CookieError::InvalidValue(name) => {
+                &format!("the value of the cookie {name:?} contains characters a cookie may not")
+            }
+            CookieError::InvalidAttribute(attribute) => {
+                &format!("the {attribute} attribute contains `;` or a control character")
+            }
+        };
+
+        write!(f, "{err}")
+    }
+}
+
+impl std::error::Error for CookieError {}
@@ -0,0 +1,128 @@
+use crate::web::errors::{
+    AppState, AuthError, ConfigError, ExtractError, RoutingError, StatusCodeError, TlsError,
+    WorkerError,
+};
+
+/// # error
+///
+/// The error of the crate, wrapping the error of the part of the app that failed, returned by `App::bind`, `App::start`, `App::close`, and the functions that add routes.
+///
+/// The wrapped error is the `source` of the error, so the whole chain can be reported.
+///
+/// ```ignore
+/// if let Err(Error::Routing(RoutingError::InvalidRoute(reason))) = app.add_route("", Method::GET, None, handler).await {
+///     eprintln!("{reason}");
+/// }
+/// ```
+#[derive(Debug)]
+pub enum Error {
+    /// Binding or serving a socket failed.
+    Io(std::io::Error),
+    /// A route could not be added or changed.
+    Routing(RoutingError),
+    /// The app could not be started or closed in its state.
+    State(AppState),
+    /// The configuration could not be loaded.
+    Config(ConfigError),
+    /// A worker or its work failed.
+    Worker(WorkerError),
+    /// TLS could not be configured.
+    Tls(TlsError),
+    /// A request could not be authenticated.
+    Auth(AuthError),
+    /// A value could not be extracted from a request.
+    Extract(ExtractError),
+    /// A status code was invalid.
+    StatusCode(StatusCodeError),
+}
+
+/// Idiomatic type alias for a result of the crate.
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let err = match &self {
+            Error::Io(_) => "an io operation of the app failed",
+            Error::Routing(_) => "a route of the app could not be changed",
+            Error::State(_) => "the app was not in a state to do that",
+            Error::Config(_) => "the configuration of the app could not be loaded",
+            Error::Worker(_) => "a worker of the app failed",
+            Error::Tls(_) => "tls could not be configured",
+            Error::Auth(_) => "the request could not be authenticated",
+            Error::Extract(_) => "a value could not be extracted from the request",
+            Error::StatusCode(_) => "the status code was invalid",
+        };
+
+        write!(f, "{err}")
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(match &self {
+            Error::Io(e) => e,
+            Error::Routing(e) => e,
+            Error::State(e) => e,
+            Error::Config(e) => e,
+            Error::Worker(e) => e,
+            Error::Tls(e) => e,
+            Error::Auth(e) => e,
+            Error::Extract(e) => e,
+            Error::StatusCode(e) => e,
+        })
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Error::Io(value)
+    }
+}
+
+impl From<RoutingError> for Error {
+    fn from(value: RoutingError) -> Self {
+        Error::Routing(value)
+    }
+}
+
+impl From<AppState> for Error {
+    fn from(value: AppState) -> Self {
+        Error::State(value)
+    }
+}
+
+impl From<ConfigError> for Error {
+    fn from(value: ConfigError) -> Self {
+        Error::Config(value)
+    }
+}
+
+impl From<WorkerError> for Error {
+    fn from(value: WorkerError) -> Self {
+        Error::Worker(value)
+    }
+}
+
+impl From<TlsError> for Error {
+    fn from(value: TlsError) -> Self {
+        Error::Tls(value)
+    }
+}
+
+impl From<AuthError> for Error {
+    fn from(value: AuthError) -> Self {
+        Error::Auth(value)
+    }
+}
+
+impl From<ExtractError> for Error {
+    fn from(value: ExtractError) -> Self {
+        Error::Extract(value)
+    }
+}
+
+impl From<StatusCodeError> for Error {
+    fn from(value: StatusCodeError) -> Self {
+        Error::StatusCode(value)
+    }
+}
@@ -0,0 +1,60 @@
+use crate::web::StatusCode;
+
+/// # extract error
+///
+/// An error that represents when an extractor could not be created from a request.
+#[derive(Debug)]
+pub enum ExtractError {
+    /// The route variables could not be deserialized.
+    Path(String),
+    /// The query could not be deserialized.
+    Query(String),
+    /// The body is missing or is not valid JSON of the type.
+    Json(String),
+    /// The body is not valid text in its charset, the charset.
+    Text(String),
+    /// The charset of the body is not supported, the charset.
+    UnsupportedCharset(String),
+    /// No state of the type was added to the app, the type name.
+    MissingState(&'static str),
+    /// No extension of the type was added by middleware, the type name.
+    MissingExtension(&'static str),
+}
+
+impl ExtractError {
+    /// The status of the response, `400` for invalid input, `415` for an unsupported charset, and `500` for missing state.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ExtractError::Path(_)
+            | ExtractError::Query(_)
+            | ExtractError::Json(_)
+            | ExtractError::Text(_) => StatusCode::BAD_REQUEST,
+            ExtractError::UnsupportedCharset(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ExtractError::MissingState(_) | ExtractError::MissingExtension(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ExtractError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let err = match &self {
+            ExtractError::Path(reason) => &format!("the route variables are invalid because {reason}"),
+            ExtractError::Query(reason) => &format!("the query is invalid because {reason}"),
+            ExtractError::Json(reason) => &format!("the body is invalid because {reason}"),
+            ExtractError::Text(charset) => &format!("the body is not valid {charset} text"),
+            ExtractError::UnsupportedCharset(charset) => {
+                &format!("the charset '{charset}' of the body is not supported")
+            }
+            ExtractError::MissingState(name) => &format!("no state of type '{name}' was added"),
+            ExtractError::MissingExtension(name) => {
+                &format!("no extension of type '{name}' was added")
+            }
+        };
+
+        write!(f, "{err}")
+    }
+}
+
+impl std::error::Error for ExtractError {}
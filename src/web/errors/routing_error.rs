@@ -7,7 +7,11 @@ pub enum RoutingError {
     Missing,
     MethodMissing,
     InvalidRoute(String),
-    NoRouteExist
+    NoRouteExist,
+    UnknownMiddleware(String),
+    NameTaken(String),
+    UnknownName(String),
+    MissingVariable(String)
 }
 
 impl std::fmt::Display for RoutingError {
@@ -17,7 +21,11 @@ impl std::fmt::Display for RoutingError {
             RoutingError::Missing => "the route does not exist",
             RoutingError::MethodMissing => "the route exist, however the requested method for the route does not.",
             RoutingError::InvalidRoute(reason) => &format!("the route provided was invalid because {reason}"),
-            RoutingError::NoRouteExist => "no route exist"
+            RoutingError::NoRouteExist => "no route exist",
+            RoutingError::UnknownMiddleware(name) => &format!("no middleware named '{name}' was registered"),
+            RoutingError::NameTaken(name) => &format!("the route name '{name}' is given to another route"),
+            RoutingError::UnknownName(name) => &format!("no route is named '{name}'"),
+            RoutingError::MissingVariable(variable) => &format!("no value was given for the route variable '{variable}'")
         };
         write!(f, "{err}")
     }
@@ -0,0 +1,20 @@
+/// # status code error
+///
+/// An error that represents when a number is not a valid status code.
+#[derive(Debug)]
+pub enum StatusCodeError {
+    /// The number is not between 100 and 999.
+    OutOfRange(u16),
+}
+
+impl std::fmt::Display for StatusCodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let err = match &self {
+            StatusCodeError::OutOfRange(code) => &format!("{code} is not a status code, status codes are between 100 and 999"),
+        };
+
+        write!(f, "{err}")
+    }
+}
+
+impl std::error::Error for StatusCodeError {}
@@ -0,0 +1,42 @@
+/// # tls error
+///
+/// An error that represents when loading certificates or configuring TLS fails.
+#[derive(Debug)]
+pub enum TlsError {
+    /// Reading the certificate or key failed.
+    Io(std::io::Error),
+    /// No certificates were found in the certificate PEM.
+    NoCertificates,
+    /// No private key was found in the key PEM.
+    NoPrivateKey,
+    /// The private key is not supported.
+    InvalidKey(String),
+}
+
+impl std::fmt::Display for TlsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let err = match &self {
+            TlsError::Io(e) => &format!("the certificate could not be read because {e}"),
+            TlsError::NoCertificates => "no certificates were found",
+            TlsError::NoPrivateKey => "no private key was found",
+            TlsError::InvalidKey(reason) => &format!("the private key is invalid because {reason}"),
+        };
+
+        write!(f, "{err}")
+    }
+}
+
+impl std::error::Error for TlsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self {
+            TlsError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for TlsError {
+    fn from(value: std::io::Error) -> Self {
+        TlsError::Io(value)
+    }
+}
@@ -19,6 +19,21 @@ pub enum WorkerError {
 
     /// When joining incoming task, the join result failed
     TaskJoinFailure,
+
+    /// The work panicked or was dropped before it finished, so it has no result
+    WorkLost,
+
+    /// The work was cancelled before it finished, see `WorkHandle::cancel`
+    Cancelled,
+
+    /// The deadline of the work passed before a worker took it, see `Job::with_deadline`
+    Expired,
+
+    /// The work ran longer than its budget, see `Job::with_budget`
+    TimedOut,
+
+    /// The request was not handled by an app, so there are no background tasks to spawn into, see `Request::spawn`
+    NoBackground,
 }
 
 impl<T> Into<Result<T, WorkerError>> for WorkerError {
@@ -35,7 +50,12 @@ impl std::fmt::Display for WorkerError {
             WorkerError::AlreadyRunning => "the worker was running",
             WorkerError::AlreadyClosed => "the worker was closed",
             WorkerError::NoTaskRunning => "no task running",
-            WorkerError::TaskJoinFailure => "when joining task, join result failed"
+            WorkerError::TaskJoinFailure => "when joining task, join result failed",
+            WorkerError::WorkLost => "the work panicked or was dropped before it finished",
+            WorkerError::Cancelled => "the work was cancelled before it finished",
+            WorkerError::Expired => "the deadline of the work passed before a worker took it",
+            WorkerError::TimedOut => "the work ran longer than its budget",
+            WorkerError::NoBackground => "the request was not handled by an app",
         };
 
         write!(f, "{err}")
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+
+/// # Execution Strategy
+///
+/// How the app runs its connections, see `App::set_execution`.
+///
+/// ```toml
+/// [execution.spawn_per_connection]
+/// max_concurrent = 1024
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionStrategy {
+    /// Connections are queued for the workers, bounding the connections handled at once by the amount of workers.
+    ///
+    /// The queue settings, fair queuing, autoscaling, and the watchdog apply to the worker pool.
+    #[default]
+    WorkerPool,
+
+    /// Each connection is spawned as its own task, which skips the queue for lower latency.
+    ///
+    /// With `max_concurrent` connections over it wait for a running one to close before they are handled, without it every connection is handled at once.
+    SpawnPerConnection { max_concurrent: Option<usize> },
+}
+
+impl ExecutionStrategy {
+    /// the executor of a running app, with the permits of the spawned connections.
+    pub(crate) fn executor(self) -> Executor {
+        match self {
+            ExecutionStrategy::WorkerPool => Executor::WorkerPool,
+            ExecutionStrategy::SpawnPerConnection { max_concurrent } => {
+                Executor::Spawn(max_concurrent.map(|max| Arc::new(Semaphore::new(max.max(1)))))
+            }
+        }
+    }
+}
+
+/// the strategy of a running app, with the permits shared by its accept loops.
+#[derive(Debug, Clone)]
+pub(crate) enum Executor {
+    WorkerPool,
+    Spawn(Option<Arc<Semaphore>>),
+}
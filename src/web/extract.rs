@@ -0,0 +1,255 @@
+use std::{
+    any::{Any, TypeId, type_name},
+    collections::HashMap,
+    sync::Arc,
+};
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::web::{
+    Request, Resolution,
+    errors::ExtractError,
+    resolution::{IntoResolution, error_resolution::ErrorResolution},
+    routing::ResolutionFnRef,
+};
+
+/// # From Request
+///
+/// A value a handler may take as an argument, created from the request before the handler is called.
+///
+/// The request is locked once for all arguments of a handler.
+pub trait FromRequest: Sized + Send + 'static {
+    fn from_request(req: &Request) -> Result<Self, ExtractError>;
+}
+
+/// # Path
+///
+/// The route variables, deserialized into a type.
+///
+/// A single variable may be taken directly, `Path(id): Path<u64>` for `/users/{id}`, several are taken as a struct with a field per variable.
+#[derive(Debug, Clone)]
+pub struct Path<T>(pub T);
+
+impl<T: DeserializeOwned + Send + 'static> FromRequest for Path<T> {
+    fn from_request(req: &Request) -> Result<Self, ExtractError> {
+        let as_strings: Map<String, Value> = req
+            .variables
+            .iter()
+            .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+            .collect();
+
+        //numbers and booleans are given their json type when a string does not fit.
+        let as_guessed: Map<String, Value> = req
+            .variables
+            .iter()
+            .map(|(k, v)| (k.clone(), guess(v)))
+            .collect();
+
+        let mut candidates = vec![Value::Object(as_strings), Value::Object(as_guessed.clone())];
+
+        if let Some(single) = as_guessed.values().next().filter(|_| as_guessed.len() == 1) {
+            candidates.push(single.clone());
+            candidates.push(Value::String(
+                req.variables.values().next().cloned().unwrap_or_default(),
+            ));
+        }
+
+        let mut last_error = String::new();
+
+        for candidate in candidates {
+            match serde_json::from_value(candidate) {
+                Ok(value) => return Ok(Path(value)),
+                Err(e) => last_error = e.to_string(),
+            }
+        }
+
+        Err(ExtractError::Path(last_error))
+    }
+}
+
+/// the json type of a route variable.
+fn guess(value: &str) -> Value {
+    match serde_json::from_str::<Value>(value) {
+        Ok(value @ (Value::Number(_) | Value::Bool(_))) => value,
+        _ => Value::String(value.to_string()),
+    }
+}
+
+/// # Query
+///
+/// The query of the route, deserialized into a type, see `Route::query`.
+///
+/// Fields the client may leave out take `#[serde(default)]`, a query that does not fit the type is answered with `400`.
+#[derive(Debug, Clone)]
+pub struct Query<T>(pub T);
+
+impl<T: DeserializeOwned + Send + 'static> FromRequest for Query<T> {
+    fn from_request(req: &Request) -> Result<Self, ExtractError> {
+        req.route.query().map(Query)
+    }
+}
+
+/// # Json
+///
+/// The body, deserialized from JSON.
+#[derive(Debug, Clone)]
+pub struct Json<T>(pub T);
+
+impl<T: DeserializeOwned + Send + 'static> FromRequest for Json<T> {
+    fn from_request(req: &Request) -> Result<Self, ExtractError> {
+        let body = req
+            .body
+            .as_deref()
+            .ok_or_else(|| ExtractError::Json("there is no body".to_string()))?;
+
+        serde_json::from_slice(body)
+            .map(Json)
+            .map_err(|e| ExtractError::Json(e.to_string()))
+    }
+}
+
+/// # State
+///
+/// A clone of state added to the app with `App::add_state`, such as a database pool.
+#[derive(Debug, Clone)]
+pub struct State<T>(pub T);
+
+impl<T: Clone + Send + Sync + 'static> FromRequest for State<T> {
+    fn from_request(req: &Request) -> Result<Self, ExtractError> {
+        req.extensions
+            .get::<Arc<StateMap>>()
+            .and_then(|state| state.get::<T>())
+            .map(State)
+            .ok_or(ExtractError::MissingState(type_name::<T>()))
+    }
+}
+
+/// # Extension
+///
+/// A clone of a value placed in the request extensions by middleware, such as the authenticated principal.
+#[derive(Debug, Clone)]
+pub struct Extension<T>(pub T);
+
+impl<T: Clone + Send + Sync + 'static> FromRequest for Extension<T> {
+    fn from_request(req: &Request) -> Result<Self, ExtractError> {
+        req.extensions
+            .get::<T>()
+            .cloned()
+            .map(Extension)
+            .ok_or(ExtractError::MissingExtension(type_name::<T>()))
+    }
+}
+
+impl<T: FromRequest> FromRequest for Option<T> {
+    fn from_request(req: &Request) -> Result<Self, ExtractError> {
+        Ok(T::from_request(req).ok())
+    }
+}
+
+/// # State Map
+///
+/// The state of an app by type, placed in each request so it can be taken with [`State`].
+#[derive(Default, Clone)]
+pub struct StateMap {
+    map: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+}
+
+impl StateMap {
+    /// Create an empty state map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts state, replacing state of the same type.
+    pub fn insert<T: Clone + Send + Sync + 'static>(&mut self, value: T) {
+        self.map.insert(TypeId::of::<T>(), Arc::new(value));
+    }
+
+    /// Get a clone of the state of type T.
+    pub fn get<T: Clone + Send + Sync + 'static>(&self) -> Option<T> {
+        self.map
+            .get(&TypeId::of::<T>())
+            .and_then(|v| v.downcast_ref::<T>())
+            .cloned()
+    }
+}
+
+/// # Rejection
+///
+/// The response sent when an argument of a handler could not be extracted.
+pub fn rejection(error: ExtractError) -> Box<dyn Resolution + Send + 'static> {
+    let status = error.status();
+
+    let mut resolution = ErrorResolution::from_error(error, None);
+    resolution.code = status;
+
+    resolution.resolve()
+}
+
+/// # Into Handler
+///
+/// Converts a function taking extractors into a resolution function, see `App::add_handler`.
+///
+/// Implemented for async functions of up to eight [`FromRequest`] arguments, returning any `IntoResolution`.
+///
+/// ```ignore
+/// app.add_handler("/users/{id}", Method::POST, None,
+///     |Path(id): Path<u64>, Json(body): Json<CreateUser>, State(db): State<Db>| async move {
+///         db.create(id, body).await;
+///         Created::at(format!("/users/{id}")).resolve()
+///     },
+/// )
+/// .await?;
+/// ```
+pub trait IntoHandler<Args>: Send + Sync + 'static {
+    fn into_handler(self) -> ResolutionFnRef;
+}
+
+macro_rules! impl_into_handler {
+    ($($arg:ident),*) => {
+        #[allow(non_snake_case, unused_variables)]
+        impl<$($arg: FromRequest,)*> FromRequest for ($($arg,)*) {
+            fn from_request(req: &Request) -> Result<Self, ExtractError> {
+                Ok(($($arg::from_request(req)?,)*))
+            }
+        }
+
+        impl<F, Fut, $($arg,)*> IntoHandler<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> Fut + Send + Sync + 'static,
+            Fut: Future<Output: IntoResolution> + Send + 'static,
+            $($arg: FromRequest,)*
+        {
+            #[allow(non_snake_case, unused_variables)]
+            fn into_handler(self) -> ResolutionFnRef {
+                let handler = Arc::new(self);
+
+                Arc::new(move |req: &mut Request| {
+                    let handler = handler.clone();
+
+                    //extract every argument before the handler runs, so its future does not borrow the request.
+                    let extracted = <($($arg,)*)>::from_request(req);
+
+                    Box::pin(async move {
+
+                        match extracted {
+                            Ok(($($arg,)*)) => handler($($arg),*).await.into_resolution(),
+                            Err(e) => rejection(e),
+                        }
+                    })
+                })
+            }
+        }
+    };
+}
+
+impl_into_handler!();
+impl_into_handler!(A);
+impl_into_handler!(A, B);
+impl_into_handler!(A, B, C);
+impl_into_handler!(A, B, C, D);
+impl_into_handler!(A, B, C, D, E);
+impl_into_handler!(A, B, C, D, E, G);
+impl_into_handler!(A, B, C, D, E, G, H);
+impl_into_handler!(A, B, C, D, E, G, H, I);
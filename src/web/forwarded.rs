@@ -0,0 +1,238 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+    sync::Arc,
+};
+
+use crate::web::errors::ConfigError;
+
+/// # Trusted Proxies
+///
+/// The proxies whose `Forwarded` and `X-Forwarded-*` headers are believed, see `App::set_trusted_proxies`, `Request::client_ip`, and `Request::full_url`.
+///
+/// Each entry is an address, `10.0.0.1`, or a CIDR range, `10.0.0.0/8` and `fd00::/8`.
+///
+/// Cloning shares the same list.
+///
+/// ```ignore
+/// app.set_trusted_proxies(Some(TrustedProxies::parse(&["127.0.0.1", "10.0.0.0/8"])?));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies {
+    ranges: Arc<Vec<(IpAddr, u8)>>,
+}
+
+impl TrustedProxies {
+    /// # Parse
+    ///
+    /// Parses the addresses and CIDR ranges, fails on the first entry that is neither.
+    pub fn parse(entries: &[&str]) -> Result<Self, ConfigError> {
+        let ranges = entries
+            .iter()
+            .map(|entry| {
+                parse_range(entry).ok_or(ConfigError::Parse(format!(
+                    "'{entry}' is not an address or CIDR range"
+                )))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            ranges: Arc::new(ranges),
+        })
+    }
+
+    /// True if the address is one of the proxies.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = canonical(ip);
+
+        self.ranges
+            .iter()
+            .any(|(network, prefix)| in_range(ip, *network, *prefix))
+    }
+
+    /// # Client Ip
+    ///
+    /// The address of the client of a connection from `peer` with the forwarding headers.
+    ///
+    /// When the peer is a trusted proxy the forwarded addresses are walked from the nearest hop, the first address that is not a trusted proxy is the client. `Forwarded` is used over `X-Forwarded-For` when both are sent.
+    ///
+    /// Headers of untrusted peers are ignored, as any client may send them.
+    pub fn client_ip(
+        &self,
+        peer: SocketAddr,
+        forwarded: Option<&str>,
+        x_forwarded_for: Option<&str>,
+    ) -> IpAddr {
+        let peer = canonical(peer.ip());
+
+        if !self.contains(peer) {
+            return peer;
+        }
+
+        let hops: Vec<IpAddr> = match (forwarded, x_forwarded_for) {
+            (Some(forwarded), _) => forwarded_for(forwarded),
+            (None, Some(x_forwarded_for)) => x_forwarded_for
+                .split(',')
+                .filter_map(|hop| parse_node(hop.trim()))
+                .collect(),
+            (None, None) => Vec::new(),
+        };
+
+        let mut client = peer;
+
+        for hop in hops.into_iter().rev() {
+            client = canonical(hop);
+
+            if !self.contains(client) {
+                break;
+            }
+        }
+
+        client
+    }
+
+    /// # Forwarded Proto
+    ///
+    /// The scheme the client used, `http` or `https`, from the `Forwarded` or `X-Forwarded-Proto` header of a trusted `peer`.
+    ///
+    /// The `Forwarded` element of the client is used, found as in `client_ip`, otherwise the nearest `X-Forwarded-Proto`. None for untrusted peers and missing or invalid values.
+    pub fn forwarded_proto(
+        &self,
+        peer: SocketAddr,
+        forwarded: Option<&str>,
+        x_forwarded_proto: Option<&str>,
+    ) -> Option<String> {
+        self.forwarded_param(peer, forwarded, x_forwarded_proto, "proto")
+            .map(|proto| proto.to_ascii_lowercase())
+            .filter(|proto| proto == "http" || proto == "https")
+    }
+
+    /// # Forwarded Host
+    ///
+    /// The host the client requested, from the `Forwarded` or `X-Forwarded-Host` header of a trusted `peer`.
+    ///
+    /// The `Forwarded` element of the client is used, found as in `client_ip`, otherwise the nearest `X-Forwarded-Host`. None for untrusted peers and missing or invalid values.
+    pub fn forwarded_host(
+        &self,
+        peer: SocketAddr,
+        forwarded: Option<&str>,
+        x_forwarded_host: Option<&str>,
+    ) -> Option<String> {
+        self.forwarded_param(peer, forwarded, x_forwarded_host, "host")
+            .filter(|host| valid_host(host))
+    }
+
+    /// a parameter of the `Forwarded` element of the client, or the nearest value of the X-Forwarded header.
+    fn forwarded_param(
+        &self,
+        peer: SocketAddr,
+        forwarded: Option<&str>,
+        x_forwarded: Option<&str>,
+        name: &str,
+    ) -> Option<String> {
+        if !self.contains(canonical(peer.ip())) {
+            return None;
+        }
+
+        let value = match (forwarded, x_forwarded) {
+            (Some(forwarded), _) => {
+                let elements: Vec<&str> = forwarded.split(',').collect();
+
+                //walked from the nearest hop like client_ip, the element added for the client describes its request.
+                let client = elements
+                    .iter()
+                    .rev()
+                    .find(|element| {
+                        forwarded_param(element, "for")
+                            .and_then(|node| parse_node(&node))
+                            .is_none_or(|ip| !self.contains(canonical(ip)))
+                    })
+                    .or(elements.first())?;
+
+                forwarded_param(client, name)?
+            }
+            (None, Some(x_forwarded)) => x_forwarded.rsplit(',').next()?.trim().to_string(),
+            (None, None) => return None,
+        };
+
+        (!value.is_empty()).then_some(value)
+    }
+}
+
+/// a parameter of a `Forwarded` element, unquoted.
+fn forwarded_param(element: &str, name: &str) -> Option<String> {
+    element.split(';').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+/// true if the host is a name or address with an optional port, so it is safe to build URLs with.
+fn valid_host(host: &str) -> bool {
+    host.bytes()
+        .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'-' | b':' | b'[' | b']'))
+}
+
+/// the `for=` addresses of a `Forwarded` header, in the order they were added.
+fn forwarded_for(header: &str) -> Vec<IpAddr> {
+    header
+        .split(',')
+        .filter_map(|element| forwarded_param(element, "for").and_then(|node| parse_node(&node)))
+        .collect()
+}
+
+/// parses a node, `192.0.2.1`, `192.0.2.1:8080`, `[2001:db8::1]`, or `[2001:db8::1]:8080`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    if let Ok(ip) = IpAddr::from_str(node) {
+        return Some(ip);
+    }
+
+    if let Ok(socket) = SocketAddr::from_str(node) {
+        return Some(socket.ip());
+    }
+
+    node.strip_prefix('[')
+        .and_then(|node| node.strip_suffix(']'))
+        .and_then(|ip| IpAddr::from_str(ip).ok())
+}
+
+/// parses `10.0.0.0/8`, an address without a prefix is a single address.
+fn parse_range(entry: &str) -> Option<(IpAddr, u8)> {
+    let (ip, prefix) = match entry.trim().split_once('/') {
+        Some((ip, prefix)) => (IpAddr::from_str(ip).ok()?, prefix.parse().ok()?),
+        None => {
+            let ip = IpAddr::from_str(entry.trim()).ok()?;
+            (ip, if ip.is_ipv4() { 32 } else { 128 })
+        }
+    };
+
+    let max = if ip.is_ipv4() { 32 } else { 128 };
+
+    (prefix <= max).then_some((canonical(ip), prefix))
+}
+
+/// IPv4 clients of an IPv6 listener are IPv4-mapped, `::ffff:10.0.0.1` is `10.0.0.1`.
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+        IpAddr::V4(_) => ip,
+    }
+}
+
+/// true if the address is in the network, both of the same family.
+fn in_range(ip: IpAddr, network: IpAddr, prefix: u8) -> bool {
+    match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(network)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,91 @@
+use crate::web::{Request, StatusCode};
+
+/// # Route Guards
+///
+/// Checks on the requests of a route and method, made before any middleware is called, see `App::guard_route`.
+///
+/// A request that fails a check is answered with its status (or its error page, see `App::set_error_pages`) and the middleware and handler are not called:
+///
+/// | Check | Status |
+/// |-------|--------|
+/// | `max_body` | `413 Content Too Large` |
+/// | `content_types` | `415 Unsupported Media Type` |
+/// | `require_header` | `428 Precondition Required` |
+///
+/// The body is read before the guards are checked, up to the `BodyLimits` of the app.
+///
+/// ```ignore
+/// app.guard_route("/alt", Method::POST, RouteGuards::new()
+///     .max_body(10 * 1024 * 1024)
+///     .content_types(&["image/jpeg", "image/png"]))
+///     .await?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RouteGuards {
+    max_body: Option<usize>,
+    content_types: Vec<String>,
+    required_headers: Vec<String>,
+}
+
+impl RouteGuards {
+    /// Create guards without any check.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rejects bodies larger than the bytes.
+    pub fn max_body(mut self, bytes: usize) -> Self {
+        self.max_body = Some(bytes);
+        self
+    }
+
+    /// Rejects bodies whose `Content-Type` is not one of the media types, a type may end in `/*` to allow all of its subtypes.
+    ///
+    /// Parameters such as the charset are ignored, requests without a body are not checked.
+    pub fn content_types(mut self, media_types: &[&str]) -> Self {
+        self.content_types
+            .extend(media_types.iter().map(|t| t.trim().to_ascii_lowercase()));
+        self
+    }
+
+    /// Rejects requests without the header, such as `If-Match` for updates that must not be lost.
+    pub fn require_header(mut self, name: &str) -> Self {
+        self.required_headers.push(name.to_string());
+        self
+    }
+
+    /// # Check
+    ///
+    /// The status of the first check the request fails, in the order of the table above, None if it passes every check.
+    pub fn check(&self, request: &Request) -> Option<StatusCode> {
+        let body = request.body.as_deref().filter(|body| !body.is_empty());
+
+        if let (Some(max_body), Some(body)) = (self.max_body, body)
+            && body.len() > max_body
+        {
+            return Some(StatusCode::CONTENT_TOO_LARGE);
+        }
+
+        if !self.content_types.is_empty() && body.is_some() {
+            let allowed = request.content_type().is_some_and(|media_type| {
+                self.content_types.iter().any(|allowed| {
+                    allowed.strip_suffix("/*") == Some(media_type.kind()) || media_type.is(allowed)
+                })
+            });
+
+            if !allowed {
+                return Some(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+            }
+        }
+
+        if self
+            .required_headers
+            .iter()
+            .any(|name| request.request_header(name).is_none())
+        {
+            return Some(StatusCode::PRECONDITION_REQUIRED);
+        }
+
+        None
+    }
+}
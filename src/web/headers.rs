@@ -0,0 +1,200 @@
+use crate::web::StatusCode;
+
+/// The key the status of a response is kept under, the value is the status line, `200 OK`.
+pub const STATUS_KEY: &str = "HTTP/1.1";
+
+/// # Header Map
+///
+/// The headers of a response, returned by `Resolution::get_headers` and added to by middleware.
+///
+/// Names are not case sensitive, `content-type` and `Content-Type` are the same header, and keep the order they were first inserted in.
+///
+/// A header may hold several values with `append`, each is written as its own line, as needed for `Set-Cookie`.
+///
+/// The status of the response is kept under [`STATUS_KEY`], see `set_status`.
+///
+/// ```ignore
+/// let mut headers = HeaderMap::new();
+///
+/// headers.set_status(StatusCode::CREATED);
+/// headers.set_content_type("application/json");
+/// headers.insert("Location", Some("/users/7".to_string()));
+/// headers.append("Set-Cookie", Some("a=1".to_string()));
+/// headers.append("Set-Cookie", Some("b=2".to_string()));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeaderMap {
+    entries: Vec<(String, Option<String>)>,
+}
+
+impl HeaderMap {
+    /// Create an empty header map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty header map with room for `capacity` values.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// # Insert
+    ///
+    /// Sets a header, replacing every value of the same name, a None value writes the name alone.
+    ///
+    /// Returns the first value that was replaced.
+    pub fn insert(&mut self, name: impl Into<String>, value: Option<String>) -> Option<Option<String>> {
+        let name = name.into();
+
+        let position = self.position(&name);
+        let previous = self.remove(&name);
+
+        let index = position.unwrap_or(self.entries.len()).min(self.entries.len());
+        self.entries.insert(index, (name, value));
+
+        previous
+    }
+
+    /// Adds a value to a header, keeping its other values.
+    pub fn append(&mut self, name: impl Into<String>, value: Option<String>) {
+        self.entries.push((name.into(), value));
+    }
+
+    /// # Get
+    ///
+    /// The first value of a header, None if the header is missing or has no value.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    /// Every value of a header, in the order they were added.
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.entries
+            .iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+            .filter_map(|(_, value)| value.as_deref())
+            .collect()
+    }
+
+    /// True if the header is set.
+    pub fn contains(&self, name: &str) -> bool {
+        self.position(name).is_some()
+    }
+
+    /// # Remove
+    ///
+    /// Removes every value of a header, returning the first.
+    pub fn remove(&mut self, name: &str) -> Option<Option<String>> {
+        let mut removed = None;
+
+        self.entries.retain_mut(|(key, value)| {
+            if !key.eq_ignore_ascii_case(name) {
+                return true;
+            }
+
+            if removed.is_none() {
+                removed = Some(value.take());
+            }
+
+            false
+        });
+
+        removed
+    }
+
+    /// # Merge
+    ///
+    /// Sets the headers of `other`, replacing headers of the same name while keeping every value of `other`.
+    pub fn merge(&mut self, other: HeaderMap) {
+        for (name, _) in &other.entries {
+            self.remove(name);
+        }
+
+        self.entries.extend(other.entries);
+    }
+
+    /// The amount of values.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// True if there are no headers.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterates the names and values, a header with several values is given once per value.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_deref()))
+    }
+
+    /// Sets the status of the response, see [`STATUS_KEY`].
+    pub fn set_status(&mut self, status: impl Into<StatusCode>) {
+        self.insert(STATUS_KEY, Some(status.into().to_string()));
+    }
+
+    /// Sets the status of the response with a custom reason phrase, `200 Fine` instead of `200 OK`.
+    pub fn set_status_reason(&mut self, status: impl Into<StatusCode>, reason: &str) {
+        self.insert(STATUS_KEY, Some(status.into().with_reason(reason)));
+    }
+
+    /// The status line of the response, `200 OK`.
+    pub fn status(&self) -> Option<&str> {
+        self.get(STATUS_KEY)
+    }
+
+    /// Sets the `Content-Type`.
+    pub fn set_content_type(&mut self, content_type: &str) {
+        self.insert("Content-Type", Some(content_type.to_string()));
+    }
+
+    /// Sets the `Content-Length`.
+    pub fn set_content_length(&mut self, length: u64) {
+        self.insert("Content-Length", Some(length.to_string()));
+    }
+
+    /// Sets the `Location` of a redirect or a created resource.
+    pub fn set_location(&mut self, location: &str) {
+        self.insert("Location", Some(location.to_string()));
+    }
+
+    /// Adds a `Set-Cookie`, keeping the cookies already set.
+    pub fn append_set_cookie(&mut self, cookie: &str) {
+        self.append("Set-Cookie", Some(cookie.to_string()));
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|(key, _)| key.eq_ignore_ascii_case(name))
+    }
+}
+
+impl IntoIterator for HeaderMap {
+    type Item = (String, Option<String>);
+    type IntoIter = std::vec::IntoIter<(String, Option<String>)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<K: Into<String>> FromIterator<(K, Option<String>)> for HeaderMap {
+    /// Collects headers, later values of a name are appended.
+    fn from_iter<T: IntoIterator<Item = (K, Option<String>)>>(iter: T) -> Self {
+        let mut headers = HeaderMap::new();
+
+        for (name, value) in iter {
+            headers.append(name, value);
+        }
+
+        headers
+    }
+}
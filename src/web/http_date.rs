@@ -0,0 +1,76 @@
+use std::{
+    sync::RwLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::factory::scheduler::civil_from_days;
+
+/// The header holding the time a response was sent.
+pub const DATE_HEADER: &str = "Date";
+
+/// The header identifying the software of the server.
+pub const SERVER_HEADER: &str = "Server";
+
+/// The `Server` header sent by default, see `App::set_server_header`.
+pub const DEFAULT_SERVER: &str = "async-web";
+
+/// the second of the cached date and the date, formatted once a second.
+static CLOCK: RwLock<(u64, String)> = RwLock::new((u64::MAX, String::new()));
+
+/// # Http Date
+///
+/// Formats the time as an HTTP date (the IMF-fixdate of RFC 9110), `Sun, 06 Nov 1994 08:49:37 GMT`.
+///
+/// Times before the unix epoch are formatted as the epoch.
+pub fn http_date(time: SystemTime) -> String {
+    const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let timestamp = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let days = (timestamp / 86_400) as i64;
+    let secs = timestamp % 86_400;
+    let (year, month, day) = civil_from_days(days);
+
+    //the epoch was a thursday.
+    let weekday = WEEKDAYS[(days + 4).rem_euclid(7) as usize];
+
+    format!(
+        "{weekday}, {day:02} {} {year} {:02}:{:02}:{:02} GMT",
+        MONTHS[(month - 1) as usize],
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+/// # Now
+///
+/// The current time as an HTTP date, the `Date` of the responses of the app.
+///
+/// The date only changes once a second, so it is formatted at most once a second and shared by every response sent in that second.
+pub fn now() -> String {
+    let time = SystemTime::now();
+    let second = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    {
+        let clock = CLOCK.read().unwrap_or_else(|e| e.into_inner());
+
+        if clock.0 == second {
+            return clock.1.clone();
+        }
+    }
+
+    let date = http_date(time);
+    *CLOCK.write().unwrap_or_else(|e| e.into_inner()) = (second, date.clone());
+
+    date
+}
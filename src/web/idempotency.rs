@@ -0,0 +1,280 @@
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::{Stream, stream};
+use sha2::{Digest, Sha256};
+use tokio::time::Instant;
+
+use crate::web::{
+    Middleware, Resolution, StatusCode,
+    headers::{HeaderMap, STATUS_KEY},
+    middleware_fn,
+    routing::middleware::MiddlewareClosure,
+};
+
+/// The header a client sends the key of a request in.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// The header set on responses that are replayed.
+pub const REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+/// the longest key that is honored.
+const MAX_KEY_LENGTH: usize = 255;
+
+/// # Stored Response
+///
+/// A response kept for an idempotency key, replayed for retries of the same key.
+#[derive(Debug, Clone)]
+pub struct StoredResponse {
+    status: String,
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+impl Resolution for StoredResponse {
+    fn get_headers(&self) -> HeaderMap {
+        let mut headers = self.headers.clone();
+
+        headers.insert(STATUS_KEY, Some(self.status.clone()));
+        headers.insert(REPLAYED_HEADER, Some("true".to_string()));
+
+        headers
+    }
+
+    fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send + 'static>> {
+        Box::pin(stream::once(std::future::ready(self.body.clone())))
+    }
+
+    fn content_length(&self) -> Option<u64> {
+        Some(self.body.len() as u64)
+    }
+
+    fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+        Box::new(self)
+    }
+}
+
+/// the state of a key.
+enum Entry {
+    /// the first request of the key is still running.
+    InFlight,
+    /// the first request completed.
+    Done {
+        fingerprint: [u8; 32],
+        /// None never expires, the ttl is too large for an instant.
+        expires: Option<Instant>,
+        response: StoredResponse,
+    },
+}
+
+/// # Idempotency
+///
+/// A bounded store of responses by `Idempotency-Key`, used by the [`idempotency`] middleware.
+///
+/// Responses are kept for `ttl`, at most `max_entries` keys are kept, and bodies larger than `max_body` are not kept.
+///
+/// Cloning shares the same store.
+#[derive(Clone)]
+pub struct Idempotency {
+    ttl: Duration,
+    max_entries: usize,
+    max_body: usize,
+    //a std mutex, claims release their key when dropped.
+    entries: Arc<Mutex<HashMap<String, Entry>>>,
+}
+
+impl Idempotency {
+    /// Create a store keeping responses for the ttl, up to 10,000 keys with bodies of up to 1 MiB.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            max_entries: 10_000,
+            max_body: 1024 * 1024,
+            entries: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Sets the most keys that are kept.
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Sets the largest body that is kept, responses with larger bodies are not replayed.
+    pub fn max_body(mut self, max_body: usize) -> Self {
+        self.max_body = max_body;
+        self
+    }
+
+    /// The amount of keys kept, including requests still running.
+    pub fn len(&self) -> usize {
+        self.entries.lock().map(|e| e.len()).unwrap_or(0)
+    }
+
+    /// True if no keys are kept.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// checks a key, claiming it if it is new.
+    fn begin(&self, key: &str, fingerprint: [u8; 32]) -> Result<IdempotencyClaim, Middleware> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+
+        if entries.len() >= self.max_entries {
+            entries.retain(|_, entry| match entry {
+                Entry::Done { expires, .. } => expires.is_none_or(|e| e > now),
+                Entry::InFlight => true,
+            });
+        }
+
+        match entries.get(key) {
+            Some(Entry::InFlight) => return Err(Middleware::InvalidEmpty(StatusCode::CONFLICT)),
+            Some(Entry::Done {
+                fingerprint: stored,
+                expires,
+                response,
+            }) if expires.is_none_or(|e| e > now) => {
+                //the same key reused for a different request.
+                if *stored != fingerprint {
+                    return Err(Middleware::InvalidEmpty(StatusCode::UNPROCESSABLE_ENTITY));
+                }
+
+                return Err(Middleware::Invalid(response.clone().resolve()));
+            }
+            _ => {}
+        }
+
+        if entries.len() >= self.max_entries {
+            //full of running requests, run it without a claim.
+            return Err(Middleware::Next);
+        }
+
+        entries.insert(key.to_string(), Entry::InFlight);
+
+        Ok(IdempotencyClaim {
+            store: self.clone(),
+            key: key.to_string(),
+            fingerprint,
+            completed: false,
+        })
+    }
+}
+
+/// # Idempotency Claim
+///
+/// Marks a key as running, placed in the request extensions by the [`idempotency`] middleware.
+///
+/// The app completes the claim with the response once it is written, a claim that is dropped first (for example, the client disconnected) releases the key so the request may be retried.
+pub struct IdempotencyClaim {
+    store: Idempotency,
+    key: String,
+    fingerprint: [u8; 32],
+    completed: bool,
+}
+
+impl IdempotencyClaim {
+    /// The largest body that is kept for the key.
+    pub fn max_body(&self) -> usize {
+        self.store.max_body
+    }
+
+    /// # Complete
+    ///
+    /// Keeps the response for the key, server errors (`5xx`) and bodies over the limit release the key instead.
+    pub fn complete(
+        mut self,
+        status: &str,
+        headers: HeaderMap,
+        body: Option<Vec<u8>>,
+    ) {
+        let (Some(body), false) = (body, status.starts_with('5')) else {
+            return;
+        };
+
+        let mut entries = self.store.entries.lock().unwrap_or_else(|e| e.into_inner());
+
+        entries.insert(
+            self.key.clone(),
+            Entry::Done {
+                fingerprint: self.fingerprint,
+                expires: Instant::now().checked_add(self.store.ttl),
+                response: StoredResponse {
+                    status: status.to_string(),
+                    headers,
+                    body,
+                },
+            },
+        );
+
+        self.completed = true;
+    }
+}
+
+impl Drop for IdempotencyClaim {
+    fn drop(&mut self) {
+        if self.completed {
+            return;
+        }
+
+        let mut entries = self.store.entries.lock().unwrap_or_else(|e| e.into_inner());
+
+        if matches!(entries.get(&self.key), Some(Entry::InFlight)) {
+            entries.remove(&self.key);
+        }
+    }
+}
+
+/// # Idempotency
+///
+/// Middleware that makes retries of a request safe, for payment-style APIs.
+///
+/// The first request with an `Idempotency-Key` header runs and its response is kept, retries with the same key receive the kept response (with `Idempotent-Replayed: true`) without running the handler.
+///
+/// * a retry while the first request is running is rejected with `409`
+/// * a key reused with a different method, route, or body is rejected with `422`
+/// * requests without the header are not changed
+///
+/// Keys are scoped to the method and route pattern.
+///
+/// ```ignore
+/// let store = Idempotency::new(Duration::from_secs(24 * 60 * 60));
+///
+/// app.add_or_panic("/payments", Method::POST, middleware!(idempotency(store)), create_payment).await;
+/// ```
+pub fn idempotency(store: Idempotency) -> MiddlewareClosure {
+    middleware_fn(move |req| {
+        let store = store.clone();
+
+        Box::pin(async move {
+            let Some(key) = req.request_header(IDEMPOTENCY_KEY_HEADER).cloned() else {
+                return Middleware::Next;
+            };
+
+            if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+                return Middleware::InvalidEmpty(StatusCode::BAD_REQUEST);
+            }
+
+            let scoped_key = format!("{} {} {key}", req.method, req.route_pattern());
+
+            let mut hasher = Sha256::new();
+            hasher.update(req.method.to_string());
+            hasher.update([0]);
+            hasher.update(&req.route.cleaned_route);
+            hasher.update([0]);
+            hasher.update(req.body.as_deref().unwrap_or_default());
+
+            match store.begin(&scoped_key, hasher.finalize().into()) {
+                Ok(claim) => {
+                    req.extensions.insert(claim);
+                    Middleware::Next
+                }
+                Err(response) => response,
+            }
+        })
+    })
+}
@@ -0,0 +1,86 @@
+use std::{net::SocketAddr, pin::Pin, sync::Arc};
+
+/// Idiomatic type alias for the future of a lifecycle hook.
+pub type HookFuture<T> = Pin<Box<dyn Future<Output = T> + Send + 'static>>;
+
+/// Idiomatic type alias for a hook called with the bound address when the app starts.
+pub type StartHook = dyn Fn(SocketAddr) -> HookFuture<()> + Send + Sync;
+
+/// Idiomatic type alias for a hook called with the address of each accepted client, false rejects it.
+pub type ConnectionHook = dyn Fn(SocketAddr) -> HookFuture<bool> + Send + Sync;
+
+/// Idiomatic type alias for a hook called when the app shuts down.
+pub type ShutdownHook = dyn Fn() -> HookFuture<()> + Send + Sync;
+
+/// # Lifecycle
+///
+/// The hooks of an app, see `App::on_start`, `App::on_connection`, and `App::on_shutdown`.
+///
+/// Hooks are called in the order they were added.
+#[derive(Clone, Default)]
+pub struct Lifecycle {
+    on_start: Vec<Arc<StartHook>>,
+    on_connection: Vec<Arc<ConnectionHook>>,
+    on_shutdown: Vec<Arc<ShutdownHook>>,
+}
+
+impl Lifecycle {
+    /// Create a lifecycle without hooks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a hook called with the bound address when the app starts.
+    pub fn on_start<F, Fut>(&mut self, hook: F)
+    where
+        F: Fn(SocketAddr) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.on_start.push(Arc::new(move |addr| Box::pin(hook(addr))));
+    }
+
+    /// Adds a hook called with the address of each accepted client, returning false rejects the client.
+    pub fn on_connection<F, Fut>(&mut self, hook: F)
+    where
+        F: Fn(SocketAddr) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = bool> + Send + 'static,
+    {
+        self.on_connection.push(Arc::new(move |peer| Box::pin(hook(peer))));
+    }
+
+    /// Adds a hook called when the app shuts down.
+    pub fn on_shutdown<F, Fut>(&mut self, hook: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.on_shutdown.push(Arc::new(move || Box::pin(hook())));
+    }
+
+    /// Calls the start hooks with the bound address.
+    pub async fn started(&self, addr: SocketAddr) {
+        for hook in &self.on_start {
+            hook(addr).await;
+        }
+    }
+
+    /// # Accepts
+    ///
+    /// Calls the connection hooks with the address of the client, false once a hook rejects it, the later hooks are not called.
+    pub async fn accepts(&self, peer: SocketAddr) -> bool {
+        for hook in &self.on_connection {
+            if !hook(peer).await {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Calls the shutdown hooks.
+    pub async fn shutting_down(&self) {
+        for hook in &self.on_shutdown {
+            hook().await;
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use crate::web::{Middleware, middleware_fn, routing::middleware::MiddlewareClosure};
+
+/// The header listing the languages a client prefers.
+pub const ACCEPT_LANGUAGE_HEADER: &str = "Accept-Language";
+
+/// # Locale
+///
+/// The language chosen for a request, placed in the request extensions by the [`locale`] middleware.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Locale(pub String);
+
+impl Locale {
+    /// The language tag as a str, `en-US`.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for Locale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// # Parse Accept Language
+///
+/// Parses the value of an `Accept-Language` header into its language ranges, most preferred first.
+///
+/// Ranges keep their order on a tie, ranges without a valid `q` are given `1`, and ranges with `q=0` are left out.
+///
+/// ```ignore
+/// assert_eq!(parse_accept_language("fr;q=0.5, en-US, de;q=0"), vec!["en-US", "fr"]);
+/// ```
+pub fn parse_accept_language(header: &str) -> Vec<String> {
+    let mut ranges: Vec<(String, f32)> = header
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';');
+            let tag = parts.next()?.trim();
+
+            let valid = !tag.is_empty()
+                && (tag == "*" || tag.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-'));
+
+            if !valid {
+                return None;
+            }
+
+            let quality = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0)
+                .clamp(0.0, 1.0);
+
+            (quality > 0.0).then(|| (tag.to_string(), quality))
+        })
+        .collect();
+
+    // a stable sort, so ranges of the same quality keep the order the client sent.
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranges.into_iter().map(|(tag, _)| tag).collect()
+}
+
+/// # Negotiate Language
+///
+/// The supported language the client prefers the most, None if the client accepts none of them.
+///
+/// Each range is tried in order: a supported language equal to it, then one it is a prefix of (`en` accepts `en-GB`), then the range with its last subtags removed (`en-US` falls back to `en`). `*` accepts the first supported language. Tags are compared without case.
+///
+/// ```ignore
+/// let accepted = parse_accept_language("de-AT, en;q=0.8");
+///
+/// assert_eq!(negotiate_language(&accepted, &["en-GB", "de"]), Some("de"));
+/// ```
+pub fn negotiate_language<'a, S: AsRef<str>>(
+    accepted: &[S],
+    supported: &[&'a str],
+) -> Option<&'a str> {
+    accepted.iter().find_map(|range| {
+        let range = range.as_ref();
+
+        if range == "*" {
+            return supported.first().copied();
+        }
+
+        let find = |tag: &str| {
+            supported
+                .iter()
+                .find(|s| s.eq_ignore_ascii_case(tag))
+                .copied()
+        };
+
+        find(range)
+            .or_else(|| {
+                supported.iter().copied().find(|s| {
+                    s.len() > range.len()
+                        && s.as_bytes()[range.len()] == b'-'
+                        && s[..range.len()].eq_ignore_ascii_case(range)
+                })
+            })
+            .or_else(|| {
+                let mut prefix = range;
+
+                while let Some((shorter, _)) = prefix.rsplit_once('-') {
+                    prefix = shorter;
+
+                    if let Some(tag) = find(prefix) {
+                        return Some(tag);
+                    }
+                }
+
+                None
+            })
+    })
+}
+
+/// # Locale
+///
+/// Middleware that chooses the language of each request from the supported languages, the first supported language the client accepts, `default` if it accepts none or sent no `Accept-Language`.
+///
+/// The choice is stored in the request extensions (see `Request::locale`), for handlers and templates to render in.
+///
+/// Responses that depend on the language should be cached per language, see `ResponseCache::vary`.
+///
+/// ```ignore
+/// app.use_middleware(locale(&["en", "fr", "de"], "en")).await;
+///
+/// app.add_or_panic("/", Method::GET, None, |req| async move {
+///     let locale = req.lock().await.locale().unwrap_or("en").to_string();
+///     IndexPage { greeting: greeting(&locale) }.resolve()
+/// })
+/// .await;
+/// ```
+pub fn locale(supported: &[&str], default: &str) -> MiddlewareClosure {
+    let supported: Arc<Vec<String>> = Arc::new(supported.iter().map(|s| s.to_string()).collect());
+    let default = default.to_string();
+
+    middleware_fn(move |req| {
+        let supported = supported.clone();
+        let default = default.clone();
+
+        Box::pin(async move {
+            let supported: Vec<&str> = supported.iter().map(String::as_str).collect();
+
+            let chosen = req
+                .negotiate_language(&supported)
+                .map(str::to_string)
+                .unwrap_or(default);
+
+            req.extensions.insert(Locale(chosen));
+
+            Middleware::Next
+        })
+    })
+}
@@ -0,0 +1,273 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+};
+
+use futures::{Stream, stream};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tokio::{io::AsyncReadExt, sync::Mutex};
+
+use crate::web::{
+    Resolution,
+    headers::HeaderMap,
+    resolution::empty_resolution::EmptyResolution,
+    static_files::{etag_matches, file_etag},
+};
+
+/// # Asset
+///
+/// A single file listed in the [`AssetManifest`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Asset {
+    /// The size of the file in bytes.
+    pub size: u64,
+    /// The first 16 hex characters of the SHA-256 of the file.
+    pub hash: String,
+    /// The ETag the file is served with by `App::serve_static`, see `static_files::file_etag`.
+    pub etag: String,
+    /// The url of the file with the hash as a version, `/assets/app.js?v={hash}`
+    pub versioned_url: String,
+}
+
+/// # Manifest Data
+///
+/// The contents of a built [`AssetManifest`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestData {
+    /// A hash of every asset hash, changes when any asset changes.
+    pub version: String,
+    /// The assets keyed by url, sorted.
+    pub assets: BTreeMap<String, Asset>,
+}
+
+/// the built manifest and its serialized form.
+struct BuiltManifest {
+    data: ManifestData,
+    json: Arc<String>,
+}
+
+/// # Asset Manifest
+///
+/// A manifest of the static assets in a directory with their sizes, hashes, and ETags, so frontends and service workers can precache deterministically.
+///
+/// The manifest is built with `build`, usually at startup, otherwise it is built on the first request that needs it.
+///
+/// Cloning shares the same manifest.
+///
+/// ```ignore
+/// let manifest = AssetManifest::new("public/assets", "/assets");
+/// manifest.build().await?;
+///
+/// app.serve_manifest("/assets/manifest.json", manifest.clone()).await?;
+///
+/// //after a deploy
+/// manifest.build().await?;
+/// ```
+#[derive(Clone)]
+pub struct AssetManifest {
+    dir: PathBuf,
+    url_prefix: String,
+    built: Arc<Mutex<Option<BuiltManifest>>>,
+}
+
+impl AssetManifest {
+    /// Create a manifest of the files in `dir`, served under `url_prefix`.
+    pub fn new(dir: &str, url_prefix: &str) -> Self {
+        Self {
+            dir: PathBuf::from(dir),
+            url_prefix: url_prefix.trim_end_matches('/').to_string(),
+            built: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// # Build
+    ///
+    /// Walks the directory and hashes each file, replacing the previous manifest.
+    ///
+    /// Symlinks to files are followed, symlinks to directories are skipped so a link to a parent can not loop.
+    pub async fn build(&self) -> Result<ManifestData, std::io::Error> {
+        let mut built = self.built.lock().await;
+
+        self.build_into(&mut built).await
+    }
+
+    /// builds the manifest while the lock is held, so builds never run at once.
+    async fn build_into(&self, built: &mut Option<BuiltManifest>) -> Result<ManifestData, std::io::Error> {
+        let mut assets = BTreeMap::new();
+        let mut dirs = vec![self.dir.clone()];
+
+        while let Some(dir) = dirs.pop() {
+            let mut entries = tokio::fs::read_dir(&dir).await?;
+
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                let file_type = entry.file_type().await?;
+
+                if file_type.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+
+                //a broken symlink or one to a directory is not an asset.
+                let Ok(metadata) = tokio::fs::metadata(&path).await else {
+                    continue;
+                };
+
+                if !metadata.is_file() {
+                    continue;
+                }
+
+                let (size, hash) = hash_file(&path).await?;
+                let url = self.url_of(&path);
+
+                let asset = Asset {
+                    size,
+                    etag: file_etag(&metadata),
+                    versioned_url: format!("{url}?v={hash}"),
+                    hash,
+                };
+
+                assets.insert(url, asset);
+            }
+        }
+
+        let mut hasher = Sha256::new();
+        for (url, asset) in &assets {
+            hasher.update(url.as_bytes());
+            hasher.update(asset.hash.as_bytes());
+        }
+
+        let data = ManifestData {
+            version: short_hex(&hasher.finalize()),
+            assets,
+        };
+
+        let json = serde_json::to_string(&data)?;
+
+        *built = Some(BuiltManifest {
+            data: data.clone(),
+            json: Arc::new(json),
+        });
+
+        Ok(data)
+    }
+
+    /// # Data
+    ///
+    /// The current manifest, built if it has not been yet.
+    pub async fn data(&self) -> Result<ManifestData, std::io::Error> {
+        let mut built = self.built.lock().await;
+
+        match built.as_ref() {
+            Some(built) => Ok(built.data.clone()),
+            None => self.build_into(&mut built).await,
+        }
+    }
+
+    /// # Resolution
+    ///
+    /// The manifest as JSON with its ETag, `304` if it matches the `If-None-Match` of the client.
+    ///
+    /// `500` if the manifest could not be built.
+    pub async fn resolution(&self, if_none_match: Option<&str>) -> Box<dyn Resolution + Send> {
+        let mut guard = self.built.lock().await;
+
+        if guard.is_none() && self.build_into(&mut guard).await.is_err() {
+            return EmptyResolution::status(500).resolve();
+        }
+
+        let Some(built) = guard.as_ref() else {
+            return EmptyResolution::status(500).resolve();
+        };
+
+        let etag = format!("\"{}\"", built.data.version);
+
+        ManifestResolution {
+            not_modified: if_none_match.is_some_and(|tags| etag_matches(tags, &etag)),
+            json: built.json.clone(),
+            etag,
+        }
+        .resolve()
+    }
+
+    /// the url of a file inside of the directory.
+    fn url_of(&self, path: &Path) -> String {
+        let relative = path.strip_prefix(&self.dir).unwrap_or(path);
+
+        let parts: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+
+        format!("{}/{}", self.url_prefix, parts.join("/"))
+    }
+}
+
+/// hashes a file in chunks, returning its size and short hash.
+async fn hash_file(path: &Path) -> Result<(u64, String), std::io::Error> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut size = 0;
+
+    loop {
+        let read = file.read(&mut buffer).await?;
+
+        if read == 0 {
+            break;
+        }
+
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+
+    Ok((size, short_hex(&hasher.finalize())))
+}
+
+fn short_hex(digest: &[u8]) -> String {
+    digest[..8].iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// serves the manifest JSON.
+struct ManifestResolution {
+    not_modified: bool,
+    json: Arc<String>,
+    etag: String,
+}
+
+impl Resolution for ManifestResolution {
+    fn get_headers(&self) -> HeaderMap {
+        let mut hmap = HeaderMap::new();
+
+        hmap.set_status(if self.not_modified { 304 } else { 200 });
+        hmap.set_content_type("application/json");
+        hmap.insert("ETag".to_string(), Some(self.etag.clone()));
+        hmap.insert("Cache-Control".to_string(), Some("no-cache".to_string()));
+
+        hmap
+    }
+
+    fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        let json = if self.not_modified {
+            Vec::new()
+        } else {
+            self.json.as_bytes().to_vec()
+        };
+
+        Box::pin(stream::once(async move { json }))
+    }
+
+    fn content_length(&self) -> Option<u64> {
+        match self.not_modified {
+            true => Some(0),
+            false => Some(self.json.len() as u64),
+        }
+    }
+
+    fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+        Box::new(self)
+    }
+}
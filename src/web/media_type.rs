@@ -0,0 +1,145 @@
+use std::fmt;
+
+/// The header naming the media type of a body.
+pub const CONTENT_TYPE_HEADER: &str = "Content-Type";
+
+/// # Media Type
+///
+/// A parsed media type, such as the `Content-Type` of a request, see `Request::content_type`.
+///
+/// The type, subtype, and parameter names are lowercase, parameter values keep their case without their quotes.
+///
+/// ```ignore
+/// let media_type = MediaType::parse("Text/HTML; Charset=\"UTF-8\"").unwrap();
+///
+/// assert_eq!(media_type.essence(), "text/html");
+/// assert_eq!(media_type.charset(), Some("UTF-8"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaType {
+    kind: String,
+    subtype: String,
+    params: Vec<(String, String)>,
+}
+
+impl MediaType {
+    /// # Parse
+    ///
+    /// Parses a media type with its parameters, None if it has no `type/subtype`. Parameters without a value are ignored.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.split(';');
+        let (kind, subtype) = parts.next()?.trim().split_once('/')?;
+
+        let is_token = |s: &str| {
+            !s.is_empty()
+                && s.bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b"!#$&-^_.+".contains(&b))
+        };
+
+        if !is_token(kind) || !is_token(subtype) {
+            return None;
+        }
+
+        let params = parts
+            .filter_map(|param| {
+                let (name, value) = param.split_once('=')?;
+                let value = value.trim();
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|v| v.strip_suffix('"'))
+                    .unwrap_or(value);
+
+                Some((name.trim().to_ascii_lowercase(), value.to_string()))
+            })
+            .collect();
+
+        Some(Self {
+            kind: kind.to_ascii_lowercase(),
+            subtype: subtype.to_ascii_lowercase(),
+            params,
+        })
+    }
+
+    /// The type, `text` of `text/html`.
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    /// The subtype, `html` of `text/html`.
+    pub fn subtype(&self) -> &str {
+        &self.subtype
+    }
+
+    /// The type and subtype without parameters, `text/html`.
+    pub fn essence(&self) -> String {
+        format!("{}/{}", self.kind, self.subtype)
+    }
+
+    /// True if the media type is the essence, `text/html`, compared without case.
+    pub fn is(&self, essence: &str) -> bool {
+        essence.split_once('/').is_some_and(|(kind, subtype)| {
+            self.kind.eq_ignore_ascii_case(kind) && self.subtype.eq_ignore_ascii_case(subtype)
+        })
+    }
+
+    /// The value of a parameter, the name is compared without case.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param, _)| param.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The `charset` parameter, `utf-8` of `text/plain; charset=utf-8`.
+    pub fn charset(&self) -> Option<&str> {
+        self.param("charset")
+    }
+}
+
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.kind, self.subtype)?;
+
+        for (name, value) in &self.params {
+            write!(f, "; {name}={value}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// # Charset
+///
+/// The character encodings a text body is decoded from, see `Request::text`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    /// UTF-8, which US-ASCII is a subset of.
+    Utf8,
+
+    /// ISO-8859-1, each byte is the character of the same code point.
+    Latin1,
+}
+
+impl Charset {
+    /// The charset of a `charset` parameter, None if it is not supported.
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label.trim().to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" | "us-ascii" | "ascii" => Some(Charset::Utf8),
+            "iso-8859-1" | "iso8859-1" | "iso_8859-1" | "latin1" | "latin-1" | "l1" => {
+                Some(Charset::Latin1)
+            }
+            _ => None,
+        }
+    }
+
+    /// Decodes the bytes, None if they are not valid in the charset.
+    pub fn decode(&self, bytes: &[u8]) -> Option<String> {
+        match self {
+            Charset::Utf8 => {
+                let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+                String::from_utf8(bytes.to_vec()).ok()
+            }
+            Charset::Latin1 => Some(bytes.iter().map(|&b| b as char).collect()),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use crate::web::{Request, errors::ExtractError, extract::FromRequest};
+
+/// # Accepts
+///
+/// The media types a client accepts, parsed from the `Accept` header of a request.
+///
+/// A missing header accepts everything, as `*/*`.
+///
+/// ```ignore
+/// let accepts = Accepts::from_request(&req)?;
+///
+/// match accepts.preferred(&["application/json", "text/html"]) {
+///     Some("text/html") => ...,
+///     Some(_) => ...,
+///     None => EmptyResolution::status(406).resolve(),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Accepts {
+    /// The media ranges and their quality, `text/*;q=0.5`.
+    ranges: Vec<(String, f32)>,
+}
+
+impl Accepts {
+    /// # Parse
+    ///
+    /// Parses the value of an `Accept` header, ranges without a valid `q` are given `1`.
+    pub fn parse(header: &str) -> Self {
+        let ranges = header
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let media_range = parts.next()?.trim().to_ascii_lowercase();
+
+                if !media_range.contains('/') {
+                    return None;
+                }
+
+                let quality = parts
+                    .filter_map(|param| param.trim().strip_prefix("q="))
+                    .find_map(|q| q.trim().parse::<f32>().ok())
+                    .unwrap_or(1.0)
+                    .clamp(0.0, 1.0);
+
+                Some((media_range, quality))
+            })
+            .collect();
+
+        Self { ranges }
+    }
+
+    /// Accepts every media type, as `*/*`.
+    pub fn any() -> Self {
+        Self::parse("*/*")
+    }
+
+    /// # Quality
+    ///
+    /// The quality the client gives a media type, from the most specific range that matches it, `0` if none do.
+    ///
+    /// Parameters of the media type, `text/html; charset=utf-8`, are ignored.
+    pub fn quality(&self, media_type: &str) -> f32 {
+        let essence = essence(media_type);
+        let (kind, _) = essence.split_once('/').unwrap_or((&essence, ""));
+
+        self.ranges
+            .iter()
+            .filter_map(|(range, quality)| {
+                let specificity = match range.as_str() {
+                    "*/*" => 0,
+                    range if range == essence => 2,
+                    range if range.strip_suffix("/*") == Some(kind) => 1,
+                    _ => return None,
+                };
+
+                Some((specificity, *quality))
+            })
+            .max_by(|a, b| a.0.cmp(&b.0))
+            .map(|(_, quality)| quality)
+            .unwrap_or(0.0)
+    }
+
+    /// True if the client accepts the media type.
+    pub fn accepts(&self, media_type: &str) -> bool {
+        self.quality(media_type) > 0.0
+    }
+
+    /// # Preferred
+    ///
+    /// The offered media type the client prefers, the first offered wins a tie, None if none are accepted.
+    pub fn preferred<'a>(&self, offered: &[&'a str]) -> Option<&'a str> {
+        let mut preferred: Option<(&str, f32)> = None;
+
+        for &media_type in offered {
+            let quality = self.quality(media_type);
+
+            if quality > 0.0 && preferred.is_none_or(|(_, best)| quality > best) {
+                preferred = Some((media_type, quality));
+            }
+        }
+
+        preferred.map(|(media_type, _)| media_type)
+    }
+}
+
+impl Default for Accepts {
+    fn default() -> Self {
+        Self::any()
+    }
+}
+
+impl FromRequest for Accepts {
+    fn from_request(req: &Request) -> Result<Self, ExtractError> {
+        Ok(req
+            .request_header("Accept")
+            .map(|header| Accepts::parse(header))
+            .unwrap_or_default())
+    }
+}
+
+/// the media type without parameters, lowercase.
+fn essence(media_type: &str) -> String {
+    media_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
@@ -0,0 +1,430 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use schemars::{JsonSchema, Schema, SchemaGenerator, generate::SchemaSettings};
+use serde_json::{Map, Value, json};
+
+use crate::web::{
+    Method, Resolution, StatusCode, banner::RouteSummary, resolution::response::Response,
+};
+
+/// The OpenAPI version of the generated documents.
+pub const OPENAPI_VERSION: &str = "3.1.0";
+
+/// the Swagger UI release the docs page loads.
+const SWAGGER_UI_ASSETS: &str = "https://unpkg.com/swagger-ui-dist@5";
+
+/// Idiomatic type alias for a function producing the schema of a type, a `$ref` for named types.
+type SchemaFn = fn(&mut SchemaGenerator) -> Schema;
+
+/// # OpenApi
+///
+/// The details of the API an OpenAPI document is generated with, see `App::openapi` and `App::serve_openapi`.
+///
+/// ```ignore
+/// let api = OpenApi::new("Tasks", "1.0.0")
+///     .description("Create and track tasks.")
+///     .server("https://tasks.example.com");
+///
+/// app.serve_openapi("/docs", api).await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct OpenApi {
+    title: String,
+    version: String,
+    description: Option<String>,
+    servers: Vec<String>,
+}
+
+impl OpenApi {
+    /// Create the details with the title and version of the API.
+    pub fn new(title: &str, version: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            version: version.to_string(),
+            description: None,
+            servers: Vec::new(),
+        }
+    }
+
+    /// Sets the description of the API, CommonMark is allowed.
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Adds the URL of a server of the API.
+    pub fn server(mut self, url: &str) -> Self {
+        self.servers.push(url.to_string());
+        self
+    }
+
+    /// # Document
+    ///
+    /// Generates the OpenAPI 3.1 document of the routes, with the metadata of the documented routes.
+    ///
+    /// Every route and method is listed, path variables (`{id}`) become path parameters and a wildcard `{*}` becomes the parameter `path`. Methods OpenAPI does not know are left out.
+    pub fn document(&self, routes: &[RouteSummary], docs: &RouteDocs) -> Value {
+        let settings = SchemaSettings::draft2020_12().with(|settings| {
+            settings.definitions_path = "/components/schemas".into();
+            settings.meta_schema = None;
+        });
+        let mut generator = settings.into_generator();
+
+        let mut paths = Map::new();
+
+        for route in routes {
+            let Some(method) = operation_method(&route.method) else {
+                continue;
+            };
+
+            let doc = docs.get(&route.pattern, &route.method).unwrap_or_default();
+
+            if doc.hidden {
+                continue;
+            }
+
+            let (path, parameters) = path_parameters(&route.pattern);
+            let operation = doc.operation(parameters, &mut generator);
+
+            if let Value::Object(operations) = paths.entry(path).or_insert_with(|| json!({})) {
+                operations.insert(method.to_string(), operation);
+            }
+        }
+
+        let mut info = json!({ "title": self.title, "version": self.version });
+
+        if let Some(description) = &self.description {
+            info["description"] = json!(description);
+        }
+
+        let mut document = json!({
+            "openapi": OPENAPI_VERSION,
+            "info": info,
+            "paths": paths,
+        });
+
+        if !self.servers.is_empty() {
+            let servers: Vec<Value> = self
+                .servers
+                .iter()
+                .map(|url| json!({ "url": url }))
+                .collect();
+            document["servers"] = json!(servers);
+        }
+
+        let schemas = generator.take_definitions(true);
+
+        if !schemas.is_empty() {
+            document["components"] = json!({ "schemas": schemas });
+        }
+
+        document
+    }
+}
+
+/// # Route Doc
+///
+/// The OpenAPI metadata of a route and method, see `App::document_route`.
+///
+/// Request and response schemas are generated from types implementing `schemars::JsonSchema`, named types are placed in `components/schemas` and referenced.
+///
+/// ```ignore
+/// #[derive(Serialize, Deserialize, JsonSchema)]
+/// struct Task { name: String, done: bool }
+///
+/// app.document_route("/tasks/{id}", Method::PUT, RouteDoc::new()
+///     .summary("Replace a task")
+///     .tag("tasks")
+///     .request::<Task>()
+///     .response::<Task>(200, "The replaced task")
+///     .empty_response(404, "No task has the id"))
+///     .await?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RouteDoc {
+    summary: Option<String>,
+    description: Option<String>,
+    operation_id: Option<String>,
+    tags: Vec<String>,
+    deprecated: bool,
+    hidden: bool,
+    request: Option<SchemaFn>,
+    responses: Vec<(u16, String, Option<SchemaFn>)>,
+}
+
+impl RouteDoc {
+    /// Create metadata without any detail, the route is listed with a `200` response.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the short summary of what the route does.
+    pub fn summary(mut self, summary: &str) -> Self {
+        self.summary = Some(summary.to_string());
+        self
+    }
+
+    /// Sets the longer description of the route, CommonMark is allowed.
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Sets the unique id of the operation, used by code generators to name functions.
+    pub fn operation_id(mut self, operation_id: &str) -> Self {
+        self.operation_id = Some(operation_id.to_string());
+        self
+    }
+
+    /// Adds a tag the route is grouped under.
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    /// Marks the route as deprecated.
+    pub fn deprecated(mut self) -> Self {
+        self.deprecated = true;
+        self
+    }
+
+    /// Leaves the route out of the document.
+    pub fn hidden(mut self) -> Self {
+        self.hidden = true;
+        self
+    }
+
+    /// Sets the JSON body the route takes.
+    pub fn request<T: JsonSchema>(mut self) -> Self {
+        self.request = Some(subschema::<T>);
+        self
+    }
+
+    /// Adds a response with a JSON body.
+    pub fn response<T: JsonSchema>(
+        mut self,
+        status: impl Into<StatusCode>,
+        description: &str,
+    ) -> Self {
+        self.responses.push((
+            status.into().as_u16(),
+            description.to_string(),
+            Some(subschema::<T>),
+        ));
+        self
+    }
+
+    /// Adds a response without a body.
+    pub fn empty_response(mut self, status: impl Into<StatusCode>, description: &str) -> Self {
+        self.responses
+            .push((status.into().as_u16(), description.to_string(), None));
+        self
+    }
+
+    /// the operation object of the route.
+    fn operation(&self, parameters: Vec<Value>, generator: &mut SchemaGenerator) -> Value {
+        let mut operation = Map::new();
+
+        if let Some(summary) = &self.summary {
+            operation.insert("summary".to_string(), json!(summary));
+        }
+
+        if let Some(description) = &self.description {
+            operation.insert("description".to_string(), json!(description));
+        }
+
+        if let Some(operation_id) = &self.operation_id {
+            operation.insert("operationId".to_string(), json!(operation_id));
+        }
+
+        if !self.tags.is_empty() {
+            operation.insert("tags".to_string(), json!(self.tags));
+        }
+
+        if self.deprecated {
+            operation.insert("deprecated".to_string(), json!(true));
+        }
+
+        if !parameters.is_empty() {
+            operation.insert("parameters".to_string(), json!(parameters));
+        }
+
+        if let Some(schema) = self.request {
+            operation.insert(
+                "requestBody".to_string(),
+                json!({ "required": true, "content": json_content(schema(generator)) }),
+            );
+        }
+
+        let mut responses = Map::new();
+
+        for (status, description, schema) in &self.responses {
+            let mut response = json!({ "description": description });
+
+            if let Some(schema) = schema {
+                response["content"] = json_content(schema(generator));
+            }
+
+            responses.insert(status.to_string(), response);
+        }
+
+        if responses.is_empty() {
+            responses.insert("200".to_string(), json!({ "description": "OK" }));
+        }
+
+        operation.insert("responses".to_string(), Value::Object(responses));
+
+        Value::Object(operation)
+    }
+}
+
+/// # Route Docs
+///
+/// The metadata of the documented routes of an app, by pattern and method.
+///
+/// Cloning shares the same metadata.
+#[derive(Debug, Clone, Default)]
+pub struct RouteDocs {
+    docs: Arc<RwLock<HashMap<(String, Method), RouteDoc>>>,
+}
+
+impl RouteDocs {
+    /// Create metadata without any route.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the metadata of the route pattern and method, replacing any it had.
+    pub fn insert(&self, pattern: &str, method: Method, doc: RouteDoc) {
+        self.docs
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert((pattern.to_string(), method), doc);
+    }
+
+    /// The metadata of the route pattern and method, None if it is not documented.
+    pub fn get(&self, pattern: &str, method: &Method) -> Option<RouteDoc> {
+        self.docs
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&(pattern.to_string(), method.clone()))
+            .cloned()
+    }
+}
+
+/// # Swagger Ui
+///
+/// A page of Swagger UI showing the OpenAPI document at the URL, see `App::serve_openapi`.
+///
+/// The scripts and styles of Swagger UI are loaded by the browser from unpkg.
+pub fn swagger_ui(document_url: &str, title: &str) -> Box<dyn Resolution + Send> {
+    let html = format!(
+        r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title}</title>
+<link rel="stylesheet" href="{SWAGGER_UI_ASSETS}/swagger-ui.css">
+</head>
+<body>
+<div id="swagger-ui"></div>
+<script src="{SWAGGER_UI_ASSETS}/swagger-ui-bundle.js" crossorigin></script>
+<script>
+window.onload = () => {{
+  window.ui = SwaggerUIBundle({{ url: {document_url}, dom_id: "#swagger-ui" }});
+}};
+</script>
+</body>
+</html>
+"##,
+        title = escape_html(title),
+        document_url = json!(document_url).to_string().replace("</", "<\\/"),
+    );
+
+    Response::builder()
+        .header("Content-Type", "text/html; charset=utf-8")
+        .text(&html)
+        .resolve()
+}
+
+/// the schema of the type, a `$ref` into the components for named types.
+fn subschema<T: JsonSchema>(generator: &mut SchemaGenerator) -> Schema {
+    generator.subschema_for::<T>()
+}
+
+/// a JSON content object with the schema.
+fn json_content(schema: Schema) -> Value {
+    json!({ "application/json": { "schema": schema } })
+}
+
+/// the name of the method in a path item, None for methods OpenAPI does not know.
+fn operation_method(method: &Method) -> Option<&'static str> {
+    let method = match method {
+        Method::GET => "get",
+        Method::POST => "post",
+        Method::PUT => "put",
+        Method::DELETE => "delete",
+        Method::PATCH => "patch",
+        Method::Other(other) => match other.to_ascii_uppercase().as_str() {
+            "HEAD" => "head",
+            "OPTIONS" => "options",
+            "TRACE" => "trace",
+            _ => return None,
+        },
+    };
+
+    Some(method)
+}
+
+/// the OpenAPI path of a route pattern and its path parameters.
+fn path_parameters(pattern: &str) -> (String, Vec<Value>) {
+    let mut path = String::new();
+    let mut parameters = Vec::new();
+
+    for segment in pattern.split('/').filter(|s| !s.is_empty()) {
+        path.push('/');
+
+        let Some(variable) = segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) else {
+            path.push_str(segment);
+            continue;
+        };
+
+        let (name, description) = match variable {
+            "*" => ("path", Some("The rest of the path.")),
+            variable => (variable, None),
+        };
+
+        path.push_str(&format!("{{{name}}}"));
+
+        let mut parameter = json!({
+            "name": name,
+            "in": "path",
+            "required": true,
+            "schema": { "type": "string" },
+        });
+
+        if let Some(description) = description {
+            parameter["description"] = json!(description);
+        }
+
+        parameters.push(parameter);
+    }
+
+    if path.is_empty() {
+        path.push('/');
+    }
+
+    (path, parameters)
+}
+
+/// escapes text placed in HTML.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,116 @@
+use std::pin::Pin;
+
+use futures::{Stream, StreamExt, stream};
+
+/// # Byte Range
+///
+/// An inclusive range of bytes of the content, `start..=end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ByteRange {
+    /// The amount of bytes in the range.
+    pub fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    /// Always false, a range holds at least one byte.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// The value of the `Content-Range` header for content of `total` bytes.
+    pub fn content_range(&self, total: u64) -> String {
+        format!("bytes {}-{}/{total}", self.start, self.end)
+    }
+}
+
+/// # Range Request
+///
+/// How a `Range` header applies to content of a known length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeRequest {
+    /// The whole content is served, the header is missing, malformed, or asks for several ranges.
+    Full,
+    /// A single range is served with `206`.
+    Partial(ByteRange),
+    /// The range starts past the content, answered with `416`.
+    Unsatisfiable,
+}
+
+/// # Parse Range
+///
+/// Parses a `Range` header, `bytes=0-99`, `bytes=100-`, or `bytes=-100`, for content of `total` bytes.
+///
+/// Several ranges (`bytes=0-1,5-9`) are not supported and the whole content is served instead, as allowed by RFC 9110.
+pub fn parse_range(header: &str, total: u64) -> RangeRequest {
+    let Some(spec) = header.trim().strip_prefix("bytes=") else {
+        return RangeRequest::Full;
+    };
+
+    if spec.contains(',') {
+        return RangeRequest::Full;
+    }
+
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return RangeRequest::Full;
+    };
+
+    let (start, end) = (start.trim(), end.trim());
+
+    let range = match (start.parse::<u64>(), end.parse::<u64>()) {
+        //bytes=-100, the last 100 bytes.
+        (Err(_), Ok(suffix)) if start.is_empty() => {
+            if suffix == 0 || total == 0 {
+                return RangeRequest::Unsatisfiable;
+            }
+
+            ByteRange {
+                start: total.saturating_sub(suffix),
+                end: total - 1,
+            }
+        }
+        //bytes=100-
+        (Ok(start), Err(_)) if end.is_empty() => ByteRange {
+            start,
+            end: total.saturating_sub(1),
+        },
+        (Ok(start), Ok(end)) if start <= end => ByteRange {
+            start,
+            end: end.min(total.saturating_sub(1)),
+        },
+        _ => return RangeRequest::Full,
+    };
+
+    if range.start >= total {
+        return RangeRequest::Unsatisfiable;
+    }
+
+    RangeRequest::Partial(range)
+}
+
+/// # Take Bytes
+///
+/// Limits a content stream to its first `limit` bytes.
+pub fn take_bytes(
+    content: Pin<Box<dyn Stream<Item = Vec<u8>> + Send>>,
+    limit: u64,
+) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+    Box::pin(
+        stream::unfold((content, limit), |(mut content, remaining)| async move {
+            if remaining == 0 {
+                return None;
+            }
+
+            let mut chunk = content.next().await?;
+            chunk.truncate(remaining.min(chunk.len() as u64) as usize);
+
+            let remaining = remaining - chunk.len() as u64;
+
+            Some((chunk, (content, remaining)))
+        }),
+    )
+}
@@ -0,0 +1,86 @@
+use std::{collections::HashMap, net::IpAddr, sync::Arc, time::Duration};
+
+use tokio::{sync::Mutex, time::Instant};
+
+use crate::web::{Middleware, StatusCode, middleware_fn, routing::middleware::MiddlewareClosure};
+
+/// amount of clients tracked before expired windows are forgotten.
+const MAX_TRACKED_CLIENTS: usize = 4096;
+
+/// the start and count of the window of each client and route.
+type Windows = HashMap<(IpAddr, String), (Instant, u32)>;
+
+/// # Rate Limit
+///
+/// Middleware that allows each client (by IP) `requests` requests per `window`, rejecting the rest with `429` and a `Retry-After` header.
+///
+/// Each call creates its own counters, so a limit added to one route does not count requests of another.
+///
+/// ```ignore
+/// app.add_or_panic("/login", Method::POST, middleware!(rate_limit(5, Duration::from_secs(60))), login).await;
+/// ```
+pub fn rate_limit(requests: u32, window: Duration) -> MiddlewareClosure {
+    limit_by(requests, window, false)
+}
+
+/// # Rate Limit Per Route
+///
+/// Like [`rate_limit`], but counts each client separately for every matched route pattern, so it may be added once as global middleware.
+///
+/// Requests to `/users/1` and `/users/2` count towards the same `/users/{id}` limit.
+///
+/// ```ignore
+/// app.use_middleware(rate_limit_per_route(100, Duration::from_secs(60))).await;
+/// ```
+pub fn rate_limit_per_route(requests: u32, window: Duration) -> MiddlewareClosure {
+    limit_by(requests, window, true)
+}
+
+/// fixed window limiter keyed by the client and, when `per_route`, the route pattern.
+fn limit_by(requests: u32, window: Duration, per_route: bool) -> MiddlewareClosure {
+    let clients: Arc<Mutex<Windows>> = Arc::new(Mutex::new(HashMap::new()));
+
+    middleware_fn(move |req| {
+        let clients = clients.clone();
+
+        Box::pin(async move {
+            let mut clients = clients.lock().await;
+            let now = Instant::now();
+
+            if clients.len() > MAX_TRACKED_CLIENTS {
+                clients.retain(|_, (start, _)| now.duration_since(*start) < window);
+            }
+
+            let route = if per_route {
+                req.route_pattern().to_string()
+            } else {
+                String::new()
+            };
+
+            let (start, count) = clients
+                .entry((req.client_ip(), route))
+                .or_insert((now, 0));
+
+            //a new window starts.
+            if now.duration_since(*start) >= window {
+                *start = now;
+                *count = 0;
+            }
+
+            *count += 1;
+
+            if *count > requests {
+                let retry_after = window.saturating_sub(now.duration_since(*start));
+
+                req.add_header(
+                    "Retry-After".to_string(),
+                    Some(retry_after.as_secs().max(1).to_string()),
+                );
+
+                return Middleware::InvalidEmpty(StatusCode::TOO_MANY_REQUESTS);
+            }
+
+            Middleware::Next
+        })
+    })
+}
@@ -0,0 +1,69 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+
+use serde::Serialize;
+
+/// # Rejections
+///
+/// Counts the connections the app closed to protect itself, so operators can detect slow or oversized requests, see `App::rejections`.
+///
+/// Cloning shares the same counts.
+#[derive(Debug, Clone, Default)]
+pub struct Rejections {
+    counts: Arc<Counts>,
+}
+
+#[derive(Debug, Default)]
+struct Counts {
+    slow_head: AtomicU64,
+    large_head: AtomicU64,
+    request_limit: AtomicU64,
+}
+
+/// # Rejection Stats
+///
+/// A snapshot of the [`Rejections`] of an app.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RejectionStats {
+    /// Connections closed because a line of the head was not sent in time, see `HeadLimits::line_timeout`.
+    pub slow_head: u64,
+
+    /// Connections answered with `431` because the head was too large, see `HeadLimits::max_size`.
+    pub large_head: u64,
+
+    /// Connections closed after making the most requests a connection may make, see `App::set_max_requests_per_connection`.
+    pub request_limit: u64,
+}
+
+impl Rejections {
+    /// Create counts starting at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The counts so far.
+    pub fn stats(&self) -> RejectionStats {
+        RejectionStats {
+            slow_head: self.counts.slow_head.load(Ordering::Relaxed),
+            large_head: self.counts.large_head.load(Ordering::Relaxed),
+            request_limit: self.counts.request_limit.load(Ordering::Relaxed),
+        }
+    }
+
+    /// counts a connection whose head was sent too slowly.
+    pub(crate) fn slow_head(&self) {
+        self.counts.slow_head.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// counts a connection whose head was too large.
+    pub(crate) fn large_head(&self) {
+        self.counts.large_head.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// counts a connection closed by the request limit.
+    pub(crate) fn request_limit(&self) {
+        self.counts.request_limit.fetch_add(1, Ordering::Relaxed);
+    }
+}
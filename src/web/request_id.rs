@@ -0,0 +1,74 @@
+use crate::web::{Middleware, middleware_fn, routing::middleware::MiddlewareClosure};
+
+/// The header the request id is read from and echoed in.
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// the longest incoming id that is honored.
+const MAX_ID_LENGTH: usize = 128;
+
+/// # Request Id
+///
+/// The id of a request, placed in the request extensions by the [`request_id`] middleware.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    /// Create a new random (UUID v4) id.
+    pub fn generate() -> Self {
+        Self(uuid::Uuid::new_v4().to_string())
+    }
+
+    /// Uses an id sent by a client or proxy, None if it is empty, too long, or contains anything but visible ASCII.
+    pub fn from_incoming(id: &str) -> Option<Self> {
+        let id = id.trim();
+
+        let valid = !id.is_empty()
+            && id.len() <= MAX_ID_LENGTH
+            && id.bytes().all(|b| b.is_ascii_graphic());
+
+        valid.then(|| Self(id.to_string()))
+    }
+
+    /// The id as a str.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// # Request Id
+///
+/// Middleware that assigns each request an id, so logs from middleware, handlers, and errors can be correlated.
+///
+/// An incoming `X-Request-Id` is honored, otherwise a UUID is generated. The id is stored in the request extensions (see `Request::request_id`) and echoed in the `X-Request-Id` response header, including for responses rejected by later middleware.
+///
+/// Add it with `App::use_middleware` before any other middleware.
+///
+/// ```ignore
+/// app.use_middleware(request_id()).await;
+///
+/// app.add_or_panic("/", Method::GET, None, |req| async move {
+///     let id = req.lock().await.request_id().unwrap_or_default().to_string();
+///     println!("[{id}] handling /");
+///     // --snip--
+/// })
+/// .await;
+/// ```
+pub fn request_id() -> MiddlewareClosure {
+    middleware_fn(|req| Box::pin(async move {
+        let id = req
+            .request_header(REQUEST_ID_HEADER)
+            .and_then(|id| RequestId::from_incoming(id))
+            .unwrap_or_else(RequestId::generate);
+
+        req.add_header(REQUEST_ID_HEADER.to_string(), Some(id.0.clone()));
+        req.extensions.insert(id);
+
+        Middleware::Next
+    }))
+}
@@ -1,14 +1,40 @@
 use futures::Stream;
-use linked_hash_map::LinkedHashMap;
-use std::pin::Pin;
+use std::{path::PathBuf, pin::Pin};
 
+use crate::web::{
+    StatusCode,
+    headers::HeaderMap,
+    resolution::{
+        empty_resolution::EmptyResolution,
+        json_resolution::JsonResolution,
+        pass_resolution::PassResolution,
+        text_resolution::{StatusResolution, TextResolution},
+    },
+};
 
+
+pub mod archive_resolution;
+pub mod attachment_resolution;
+pub mod blocking_resolution;
+pub mod early_hints;
 pub mod empty_resolution;
 pub mod error_resolution;
 pub mod file_resolution;
+#[cfg(feature = "image")]
+pub mod image_resolution;
 pub mod json_resolution;
 pub mod merged_resolution;
+pub mod negotiated_resolution;
+pub mod pass_resolution;
+pub mod problem_details;
+#[cfg(feature = "proxy")]
+pub mod proxy_resolution;
 pub mod redirect;
+pub mod response;
+pub mod semantic;
+pub mod text_resolution;
+
+pub use response::{Response, ResponseBuilder};
 
 /// # Resolution
 ///
@@ -17,9 +43,9 @@ pub mod redirect;
 pub trait Resolution: Send + 'static {
     /// # Get headers
     ///
-    /// Return a collection of Header keys and values.
+    /// Return the headers of the response, including its status, see `HeaderMap::set_status`.
     ///
-    fn get_headers(&self) -> LinkedHashMap<String, Option<String>>;
+    fn get_headers(&self) -> HeaderMap;
 
     /// # Get Content
     ///
@@ -27,6 +53,17 @@ pub trait Resolution: Send + 'static {
     ///
     fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>>;
 
+    /// # Content Length
+    ///
+    /// The exact length of the content in bytes, None if the length is not known until it is streamed.
+    ///
+    /// Content of a known length is sent with a `Content-Length`, the rest is chunked. A `Content-Length` in the headers is used instead, and the app never writes more than the length it sent.
+    ///
+    /// Seekable resolutions know their length, so it is the `seekable_len` unless implemented.
+    fn content_length(&self) -> Option<u64> {
+        self.seekable_len()
+    }
+
     /// # resolve
     ///
     /// Converts the T type into a Box<dyn Resolution ...
@@ -40,6 +77,137 @@ pub trait Resolution: Send + 'static {
     /// }
     /// ```
     fn resolve(self) -> Box<dyn Resolution + Send + 'static>;
+
+    /// # Seekable Length
+    ///
+    /// The total length of the content if it can be served from an offset with `seek`, None if it can not.
+    ///
+    /// Seekable resolutions answer `Range` requests, the app parses the header and sends `206` with `Content-Range`.
+    fn seekable_len(&self) -> Option<u64> {
+        None
+    }
+
+    /// # Seek
+    ///
+    /// The content starting `offset` bytes in, only called when `seekable_len` is Some. The app stops reading once the range is served.
+    fn seek(&self, offset: u64) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        let _ = offset;
+        self.get_content()
+    }
+
+    /// # Source File
+    ///
+    /// The file on disk the content is read from as is, None if the content is not a file.
+    ///
+    /// Large files are copied from the file to the client by the app, instead of being read into a chunk of the content stream at a time.
+    fn source_file(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// # Informational
+    ///
+    /// The `1xx` responses sent before the response, each a header map with its status, such as the `103 Early Hints` of `EarlyHints`.
+    ///
+    /// Statuses other than `1xx`, and `101` which changes the protocol, are not sent.
+    fn informational(&self) -> Vec<HeaderMap> {
+        Vec::new()
+    }
+
+    /// # Is Pass
+    ///
+    /// True if the resolution could not handle the request and the next resolution of the endpoint should be tried, see `PassResolution`.
+    fn is_pass(&self) -> bool {
+        false
+    }
+}
+
+/// # Into Resolution
+///
+/// A value a handler may return, converted into a resolution by the app.
+///
+/// Allows simple handlers to return `"hello"` or `(404, "not found")` instead of building a resolution.
+///
+/// | Type | Response |
+/// | --- | --- |
+/// | any `Resolution` | itself |
+/// | `String`, `&'static str` | `200`, `text/plain; charset=utf-8` |
+/// | `Vec<u8>` | `200`, `application/octet-stream` |
+/// | `serde_json::Value` | `200`, `application/json` |
+/// | `()` | an empty `200` |
+/// | `(i32, T)`, `(StatusCode, T)` | `T` with the status |
+/// | `Option<T>` | `T`, or a pass to the next resolution of the chain when None |
+pub trait IntoResolution {
+    fn into_resolution(self) -> Box<dyn Resolution + Send + 'static>;
+}
+
+impl<R: Resolution> IntoResolution for R {
+    fn into_resolution(self) -> Box<dyn Resolution + Send + 'static> {
+        self.resolve()
+    }
+}
+
+impl IntoResolution for Box<dyn Resolution + Send + 'static> {
+    fn into_resolution(self) -> Box<dyn Resolution + Send + 'static> {
+        self
+    }
+}
+
+impl IntoResolution for String {
+    fn into_resolution(self) -> Box<dyn Resolution + Send + 'static> {
+        TextResolution::new(&self).resolve()
+    }
+}
+
+impl IntoResolution for &'static str {
+    fn into_resolution(self) -> Box<dyn Resolution + Send + 'static> {
+        TextResolution::new(self).resolve()
+    }
+}
+
+impl IntoResolution for Vec<u8> {
+    fn into_resolution(self) -> Box<dyn Resolution + Send + 'static> {
+        TextResolution::bytes(self).resolve()
+    }
+}
+
+impl IntoResolution for serde_json::Value {
+    fn into_resolution(self) -> Box<dyn Resolution + Send + 'static> {
+        match JsonResolution::serialize(self) {
+            Ok(json) => json.resolve(),
+            Err(e) => e.resolve(),
+        }
+    }
+}
+
+impl IntoResolution for () {
+    fn into_resolution(self) -> Box<dyn Resolution + Send + 'static> {
+        EmptyResolution::status(200).resolve()
+    }
+}
+
+impl<T: IntoResolution> IntoResolution for (i32, T) {
+    fn into_resolution(self) -> Box<dyn Resolution + Send + 'static> {
+        let (status, inner) = self;
+
+        StatusResolution::new(status, inner.into_resolution()).resolve()
+    }
+}
+
+impl<T: IntoResolution> IntoResolution for (StatusCode, T) {
+    fn into_resolution(self) -> Box<dyn Resolution + Send + 'static> {
+        let (status, inner) = self;
+
+        StatusResolution::new(status, inner.into_resolution()).resolve()
+    }
+}
+
+impl<T: IntoResolution> IntoResolution for Option<T> {
+    fn into_resolution(self) -> Box<dyn Resolution + Send + 'static> {
+        match self {
+            Some(inner) => inner.into_resolution(),
+            None => PassResolution.resolve(),
+        }
+    }
 }
 
 /// # Get Status
@@ -58,81 +226,11 @@ pub trait Resolution: Send + 'static {
 ///
 /// ```
 pub fn get_status(status_code: &i32) -> &str {
-    match status_code {
-        // 1xx Informational
-        100 => "Continue",
-        101 => "Switching Protocols",
-        102 => "Processing",
-        103 => "Early Hints",
-
-        // 2xx Success
-        200 => "OK",
-        201 => "Created",
-        202 => "Accepted",
-        203 => "Non-Authoritative Information",
-        204 => "No Content",
-        205 => "Reset Content",
-        206 => "Partial Content",
-        207 => "Multi-Status",
-        208 => "Already Reported",
-        226 => "IM Used",
-
-        // 3xx Redirection
-        300 => "Multiple Choices",
-        301 => "Moved Permanently",
-        302 => "Found",
-        303 => "See Other",
-        304 => "Not Modified",
-        305 => "Use Proxy",
-        307 => "Temporary Redirect",
-        308 => "Permanent Redirect",
-
-        // 4xx Client Error
-        400 => "Bad Request",
-        401 => "Unauthorized",
-        402 => "Payment Required",
-        403 => "Forbidden",
-        404 => "Not Found",
-        405 => "Method Not Allowed",
-        406 => "Not Acceptable",
-        407 => "Proxy Authentication Required",
-        408 => "Request Timeout",
-        409 => "Conflict",
-        410 => "Gone",
-        411 => "Length Required",
-        412 => "Precondition Failed",
-        413 => "Payload Too Large",
-        414 => "URI Too Long",
-        415 => "Unsupported Media Type",
-        416 => "Range Not Satisfiable",
-        417 => "Expectation Failed",
-        418 => "I'm a Teapot",
-        421 => "Misdirected Request",
-        422 => "Unprocessable Entity",
-        423 => "Locked",
-        424 => "Failed Dependency",
-        425 => "Too Early",
-        426 => "Upgrade Required",
-        428 => "Precondition Required",
-        429 => "Too Many Requests",
-        431 => "Request Header Fields Too Large",
-        451 => "Unavailable For Legal Reasons",
-
-        // 5xx Server Error
-        500 => "Internal Server Error",
-        501 => "Not Implemented",
-        502 => "Bad Gateway",
-        503 => "Service Unavailable",
-        504 => "Gateway Timeout",
-        505 => "HTTP Version Not Supported",
-        506 => "Variant Also Negotiates",
-        507 => "Insufficient Storage",
-        508 => "Loop Detected",
-        510 => "Not Extended",
-        511 => "Network Authentication Required",
-
-        _ => "Unknown Status Code",
-    }
+    u16::try_from(*status_code)
+        .ok()
+        .and_then(|code| StatusCode::new(code).ok())
+        .and_then(|status| status.reason())
+        .unwrap_or("Unknown Status Code")
 }
 
 /// Gives you back the appropriate header based on a status code.
@@ -146,10 +244,8 @@ pub fn get_status(status_code: &i32) -> &str {
 /// println!("{header_key} {header_val}");
 ///
 /// ```
-pub fn get_status_header(status_code: i32) -> (String, String) {
-    let status = get_status(&status_code);
-
-    ("HTTP/1.1".to_string(), format!("{status_code} {status}"))
+pub fn get_status_header(status_code: impl Into<StatusCode>) -> (String, String) {
+    ("HTTP/1.1".to_string(), status_code.into().to_string())
 }
 
 /// # Empty Content
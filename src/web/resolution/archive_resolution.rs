@@ -0,0 +1,383 @@
+use std::{io::Write, pin::Pin, sync::Arc};
+
+use async_stream::stream;
+use flate2::{Compression, write::GzEncoder};
+use futures::{Stream, StreamExt};
+
+use crate::web::{Resolution, headers::HeaderMap, range::take_bytes, streams::stream_file};
+
+/// # Archive Format
+///
+/// The format an [`ArchiveResolution`] is streamed as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    /// A zip archive, entries are stored (not compressed).
+    Zip,
+    /// An uncompressed ustar archive.
+    Tar,
+    /// A ustar archive compressed with gzip.
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// The content type header value for the format.
+    fn content_type(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "application/zip",
+            ArchiveFormat::Tar => "application/x-tar",
+            ArchiveFormat::TarGz => "application/gzip",
+        }
+    }
+}
+
+/// # Archive Entry
+///
+/// A single item that is placed into the archive.
+#[derive(Clone)]
+pub enum ArchiveEntry {
+    /// A file on disk, read and streamed when the archive is served.
+    File { name: String, path: String },
+
+    /// An in-memory buffer.
+    Memory { name: String, data: Arc<Vec<u8>> },
+}
+
+impl ArchiveEntry {
+    fn name(&self) -> &str {
+        match self {
+            ArchiveEntry::File { name, .. } => name,
+            ArchiveEntry::Memory { name, .. } => name,
+        }
+    }
+
+    /// Streams the content of the entry.
+    fn content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        match self {
+            ArchiveEntry::File { path, .. } => Box::pin(stream_file(path.clone())),
+            ArchiveEntry::Memory { data, .. } => {
+                let data = data.clone();
+                Box::pin(futures::stream::once(async move { data.to_vec() }))
+            }
+        }
+    }
+
+    /// The size of the entry, None if the file could not be found.
+    async fn size(&self) -> Option<u64> {
+        match self {
+            ArchiveEntry::File { path, .. } => tokio::fs::metadata(path)
+                .await
+                .ok()
+                .filter(|m| m.is_file())
+                .map(|m| m.len()),
+            ArchiveEntry::Memory { data, .. } => Some(data.len() as u64),
+        }
+    }
+}
+
+/// # Archive Resolution
+///
+/// Streams a zip or tar(.gz) archive that is built on the fly from files on disk and in-memory buffers.
+///
+/// The archive is never fully buffered, each entry is read and written to the client chunk by chunk.
+///
+/// ## Example
+///
+/// ```ignore
+/// let mut archive = ArchiveResolution::new("reports.zip", ArchiveFormat::Zip);
+///
+/// archive.add_file("january.csv", "reports/january.csv");
+/// archive.add_bytes("README.txt", b"generated reports".to_vec());
+///
+/// archive.resolve()
+/// ```
+///
+/// Files that do not exist when the archive is served are skipped, as are entries that would take a zip past 4 GiB (or 65535 entries), the limits of zip without zip64.
+///
+/// Each entry is its size when the archive is served, a file that grows while it is read is cut to that size.
+pub struct ArchiveResolution {
+    file_name: String,
+    format: ArchiveFormat,
+    entries: Vec<ArchiveEntry>,
+}
+
+impl ArchiveResolution {
+    /// Create a new empty archive that is downloaded as `file_name`.
+    pub fn new(file_name: &str, format: ArchiveFormat) -> Self {
+        Self {
+            file_name: file_name.to_string(),
+            format,
+            entries: Vec::new(),
+        }
+    }
+
+    /// # Add File
+    ///
+    /// Adds a file from disk (`path`) into the archive as `name`.
+    pub fn add_file(&mut self, name: &str, path: &str) {
+        self.entries.push(ArchiveEntry::File {
+            name: name.to_string(),
+            path: path.to_string(),
+        });
+    }
+
+    /// # Add Bytes
+    ///
+    /// Adds an in-memory buffer into the archive as `name`.
+    pub fn add_bytes(&mut self, name: &str, data: Vec<u8>) {
+        self.entries.push(ArchiveEntry::Memory {
+            name: name.to_string(),
+            data: Arc::new(data),
+        });
+    }
+
+    /// Returns the entries of the archive.
+    pub fn entries(&self) -> &Vec<ArchiveEntry> {
+        &self.entries
+    }
+}
+
+impl Resolution for ArchiveResolution {
+    fn get_headers(&self) -> HeaderMap {
+        let mut hmap = HeaderMap::new();
+
+        hmap.set_status(200);
+        hmap.set_content_type(self.format.content_type());
+        hmap.insert(
+            "Content-Disposition".to_string(),
+            Some(format!(
+                "attachment; filename=\"{}\"",
+                self.file_name.replace('"', "")
+            )),
+        );
+
+        hmap
+    }
+
+    fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        let entries = self.entries.clone();
+
+        match self.format {
+            ArchiveFormat::Zip => Box::pin(zip_stream(entries)),
+            ArchiveFormat::Tar => Box::pin(tar_stream(entries)),
+            ArchiveFormat::TarGz => Box::pin(gzip_stream(tar_stream(entries))),
+        }
+    }
+
+    fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+        Box::new(self)
+    }
+}
+
+// ms-dos date of 1980-01-01, the earliest date that can be stored in a zip.
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+// the largest offset or size of a zip without zip64.
+const ZIP_MAX: u64 = u32::MAX as u64;
+
+/// Streams the entries as a zip archive.
+///
+/// Each entry is stored with a data descriptor so the crc and sizes can be written after the content.
+fn zip_stream(entries: Vec<ArchiveEntry>) -> impl Stream<Item = Vec<u8>> + Send {
+    stream! {
+        //the central directory built as we stream each entry.
+        let mut central = Vec::new();
+        let mut offset: u64 = 0;
+        let mut count: u16 = 0;
+
+        for entry in entries {
+            let Some(size) = entry.size().await else {
+                continue;
+            };
+
+            let name = entry.name().as_bytes().to_vec();
+
+            //the header, content, descriptor, and central record of the entry must all fit in the offsets of a zip.
+            let needed = 30 + 2 * name.len() as u64 + size + 16 + 46;
+
+            if count == u16::MAX
+                || name.len() > u16::MAX as usize
+                || offset + central.len() as u64 + needed > ZIP_MAX
+            {
+                continue;
+            }
+
+            //local file header, bit 3 (data descriptor) and bit 11 (utf-8 names)
+            let mut header = Vec::with_capacity(30 + name.len());
+            header.extend_from_slice(&0x04034b50u32.to_le_bytes());
+            header.extend_from_slice(&20u16.to_le_bytes());
+            header.extend_from_slice(&0x0808u16.to_le_bytes());
+            header.extend_from_slice(&0u16.to_le_bytes());
+            header.extend_from_slice(&0u16.to_le_bytes());
+            header.extend_from_slice(&DOS_DATE.to_le_bytes());
+            header.extend_from_slice(&[0u8; 12]);
+            header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            header.extend_from_slice(&0u16.to_le_bytes());
+            header.extend_from_slice(&name);
+
+            let header_offset = offset as u32;
+            offset += header.len() as u64;
+            yield header;
+
+            //stream the content while computing the crc, never more than the size it was given room for.
+            let mut hasher = crc32fast::Hasher::new();
+            let mut written: u64 = 0;
+            let mut content = take_bytes(entry.content(), size);
+
+            while let Some(chunk) = content.next().await {
+                hasher.update(&chunk);
+                written += chunk.len() as u64;
+                yield chunk;
+            }
+
+            let crc = hasher.finalize();
+            let size = written as u32;
+            offset += written;
+
+            //data descriptor
+            let mut descriptor = Vec::with_capacity(16);
+            descriptor.extend_from_slice(&0x08074b50u32.to_le_bytes());
+            descriptor.extend_from_slice(&crc.to_le_bytes());
+            descriptor.extend_from_slice(&size.to_le_bytes());
+            descriptor.extend_from_slice(&size.to_le_bytes());
+
+            offset += descriptor.len() as u64;
+            yield descriptor;
+
+            //central directory record for the entry.
+            central.extend_from_slice(&0x02014b50u32.to_le_bytes());
+            central.extend_from_slice(&20u16.to_le_bytes());
+            central.extend_from_slice(&20u16.to_le_bytes());
+            central.extend_from_slice(&0x0808u16.to_le_bytes());
+            central.extend_from_slice(&0u16.to_le_bytes());
+            central.extend_from_slice(&0u16.to_le_bytes());
+            central.extend_from_slice(&DOS_DATE.to_le_bytes());
+            central.extend_from_slice(&crc.to_le_bytes());
+            central.extend_from_slice(&size.to_le_bytes());
+            central.extend_from_slice(&size.to_le_bytes());
+            central.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            central.extend_from_slice(&[0u8; 12]);
+            central.extend_from_slice(&header_offset.to_le_bytes());
+            central.extend_from_slice(&name);
+
+            count += 1;
+        }
+
+        //end of central directory record
+        let mut end = Vec::with_capacity(22);
+        end.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        end.extend_from_slice(&[0u8; 4]);
+        end.extend_from_slice(&count.to_le_bytes());
+        end.extend_from_slice(&count.to_le_bytes());
+        end.extend_from_slice(&(central.len() as u32).to_le_bytes());
+        end.extend_from_slice(&(offset as u32).to_le_bytes());
+        end.extend_from_slice(&0u16.to_le_bytes());
+
+        central.extend_from_slice(&end);
+        yield central;
+    }
+}
+
+/// Streams the entries as a ustar archive.
+fn tar_stream(entries: Vec<ArchiveEntry>) -> impl Stream<Item = Vec<u8>> + Send {
+    stream! {
+        for entry in entries {
+            let size = match entry.size().await {
+                Some(size) => size,
+                None => continue,
+            };
+
+            yield tar_header(entry.name(), size);
+
+            //a file that grew while being read is cut to the size in the header.
+            let mut written: u64 = 0;
+            let mut content = take_bytes(entry.content(), size);
+
+            while let Some(chunk) = content.next().await {
+                written += chunk.len() as u64;
+                yield chunk;
+            }
+
+            //the file shrunk while being read, pad so the archive stays valid.
+            if written < size {
+                yield vec![0u8; (size - written) as usize];
+            }
+
+            //pad the entry to the next block.
+            let remainder = (size % 512) as usize;
+            if remainder != 0 {
+                yield vec![0u8; 512 - remainder];
+            }
+        }
+
+        //two empty blocks mark the end of the archive.
+        yield vec![0u8; 1024];
+    }
+}
+
+/// Creates the 512 byte ustar header for an entry.
+fn tar_header(name: &str, size: u64) -> Vec<u8> {
+    let mut header = vec![0u8; 512];
+
+    //names longer than 100 bytes are split into the prefix field, or cut at a character when they can not be split.
+    let (prefix, name) = match name.len() {
+        0..=100 => ("", name),
+        _ => name
+            .char_indices()
+            .filter(|(i, c)| *c == '/' && *i <= 155 && name.len() - i - 1 <= 100)
+            .map(|(i, _)| (&name[..i], &name[i + 1..]))
+            .next()
+            .unwrap_or_else(|| {
+                let end = (0..=100).rev().find(|i| name.is_char_boundary(*i)).unwrap_or(0);
+                ("", &name[..end])
+            }),
+    };
+
+    let mut write_field = |start: usize, len: usize, value: &[u8]| {
+        let len = value.len().min(len);
+        header[start..start + len].copy_from_slice(&value[..len]);
+    };
+
+    write_field(0, 100, name.as_bytes());
+    write_field(100, 8, b"0000644\0");
+    write_field(108, 8, b"0000000\0");
+    write_field(116, 8, b"0000000\0");
+    write_field(124, 12, format!("{size:011o}\0").as_bytes());
+    write_field(136, 12, b"00000000000\0");
+    write_field(148, 8, b"        ");
+    write_field(156, 1, b"0");
+    write_field(257, 6, b"ustar\0");
+    write_field(263, 2, b"00");
+    write_field(345, 155, prefix.as_bytes());
+
+    let checksum: u32 = header.iter().map(|b| *b as u32).sum();
+    header[148..156].copy_from_slice(format!("{checksum:06o}\0 ").as_bytes());
+
+    header
+}
+
+/// Compresses a stream with gzip, yielding compressed data as it becomes available.
+fn gzip_stream<S>(inner: S) -> impl Stream<Item = Vec<u8>> + Send
+where
+    S: Stream<Item = Vec<u8>> + Send + 'static,
+{
+    stream! {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        let mut inner = Box::pin(inner);
+
+        while let Some(chunk) = inner.next().await {
+            if encoder.write_all(&chunk).is_err() {
+                return;
+            }
+
+            let compressed = std::mem::take(encoder.get_mut());
+
+            if !compressed.is_empty() {
+                yield compressed;
+            }
+        }
+
+        if let Ok(rest) = encoder.finish() {
+            yield rest;
+        }
+    }
+}
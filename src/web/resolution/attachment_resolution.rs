@@ -0,0 +1,111 @@
+use std::{path::PathBuf, pin::Pin};
+
+use futures::Stream;
+
+use crate::web::{Resolution, headers::HeaderMap};
+
+/// ## Attachment Resolution
+///
+/// Implementation of the Resolution trait.
+///
+/// Sends another resolution as a download, browsers save it as `filename` instead of displaying it.
+///
+/// ## Example
+///
+/// ```ignore
+/// let report = FileResolution::new("reports/2026-10.csv").resolve();
+///
+/// return AttachmentResolution::new("Rapport d’octobre.csv", report).resolve();
+/// ```
+pub struct AttachmentResolution {
+    filename: String,
+    inner: Box<dyn Resolution + Send + 'static>,
+}
+
+impl AttachmentResolution {
+    /// Sends the resolution as a download named `filename`.
+    pub fn new(filename: &str, inner: Box<dyn Resolution + Send + 'static>) -> Self {
+        Self {
+            filename: filename.to_string(),
+            inner,
+        }
+    }
+}
+
+impl Resolution for AttachmentResolution {
+    fn get_headers(&self) -> HeaderMap {
+        let mut hmap = self.inner.get_headers();
+
+        hmap.insert(
+            "Content-Disposition",
+            Some(content_disposition(&self.filename)),
+        );
+
+        hmap
+    }
+
+    fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        self.inner.get_content()
+    }
+
+    fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+        Box::new(self)
+    }
+
+    fn seekable_len(&self) -> Option<u64> {
+        self.inner.seekable_len()
+    }
+
+    fn seek(&self, offset: u64) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        self.inner.seek(offset)
+    }
+
+    fn source_file(&self) -> Option<PathBuf> {
+        self.inner.source_file()
+    }
+}
+
+/// # Content Disposition
+///
+/// The `Content-Disposition` of a download named `filename`, `attachment; filename="report.csv"`.
+///
+/// Names that are not plain ASCII are also given as `filename*` in UTF-8 (RFC 5987), with an ASCII `filename` for older clients. Path separators are replaced, so only the name is used.
+pub fn content_disposition(filename: &str) -> String {
+    let filename = filename.replace(['/', '\\'], "_");
+
+    let fallback: String = filename
+        .chars()
+        .map(|c| match c {
+            ' '..='~' if c != '"' => c,
+            _ => '_',
+        })
+        .collect();
+
+    if fallback == filename {
+        return format!("attachment; filename=\"{filename}\"");
+    }
+
+    let encoded: String = filename
+        .bytes()
+        .map(|b| match b {
+            b'a'..=b'z'
+            | b'A'..=b'Z'
+            | b'0'..=b'9'
+            | b'!'
+            | b'#'
+            | b'$'
+            | b'&'
+            | b'+'
+            | b'-'
+            | b'.'
+            | b'^'
+            | b'_'
+            | b'`'
+            | b'|'
+            | b'~' => (b as char).to_string(),
+            _ => format!("%{b:02X}"),
+        })
+        .collect();
+
+    format!("attachment; filename=\"{fallback}\"; filename*=UTF-8''{encoded}")
+}
@@ -0,0 +1,92 @@
+use std::{panic::AssertUnwindSafe, path::PathBuf, pin::Pin};
+
+use futures::Stream;
+
+use crate::web::{
+    Resolution, StatusCode,
+    headers::HeaderMap,
+    resolution::{IntoResolution, empty_resolution::EmptyResolution},
+};
+
+/// # Blocking Resolution
+///
+/// Builds a resolution on the blocking pool of tokio, for handlers doing CPU heavy or blocking work, such as running a model, which would otherwise starve the workers serving IO.
+///
+/// A panic of the work is sent as an empty `500`.
+///
+/// ## Example
+///
+/// ```ignore
+/// app.add_or_change_route("/embed", Method::POST, None, move |req| {
+///     let model = model.clone();
+///
+///     async move {
+///         let body = req.lock().await.body.clone().unwrap_or_default();
+///
+///         BlockingResolution::run(move || model.embed(&body)).await.resolve()
+///     }
+/// }).await?;
+/// ```
+pub struct BlockingResolution {
+    inner: Box<dyn Resolution + Send + 'static>,
+}
+
+impl BlockingResolution {
+    /// Runs the work on the blocking pool and waits for its resolution.
+    pub async fn run<F, T>(work: F) -> Self
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: IntoResolution + 'static,
+    {
+        //the resolution is built on the blocking pool as well, it may be as heavy as the work.
+        let result = tokio::task::spawn_blocking(move || {
+            std::panic::catch_unwind(AssertUnwindSafe(|| work().into_resolution()))
+        })
+        .await;
+
+        let inner = match result {
+            Ok(Ok(inner)) => inner,
+            _ => EmptyResolution::status(StatusCode::INTERNAL_SERVER_ERROR).resolve(),
+        };
+
+        Self { inner }
+    }
+}
+
+impl Resolution for BlockingResolution {
+    fn get_headers(&self) -> HeaderMap {
+        self.inner.get_headers()
+    }
+
+    fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        self.inner.get_content()
+    }
+
+    fn content_length(&self) -> Option<u64> {
+        self.inner.content_length()
+    }
+
+    fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+        self.inner
+    }
+
+    fn seekable_len(&self) -> Option<u64> {
+        self.inner.seekable_len()
+    }
+
+    fn seek(&self, offset: u64) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        self.inner.seek(offset)
+    }
+
+    fn source_file(&self) -> Option<PathBuf> {
+        self.inner.source_file()
+    }
+
+    fn informational(&self) -> Vec<HeaderMap> {
+        self.inner.informational()
+    }
+
+    fn is_pass(&self) -> bool {
+        self.inner.is_pass()
+    }
+}
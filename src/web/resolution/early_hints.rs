@@ -0,0 +1,101 @@
+use std::{path::PathBuf, pin::Pin};
+
+use futures::Stream;
+
+use crate::web::{Resolution, StatusCode, headers::HeaderMap};
+
+/// ## Early Hints
+///
+/// Implementation of the Resolution trait.
+///
+/// Sends `103 Early Hints` with `Link` headers before another resolution, so browsers may preload the stylesheets and scripts of a page while its body is sent.
+///
+/// The links are also sent with the response, as clients may ignore `1xx` responses.
+///
+/// ## Example
+///
+/// ```ignore
+/// return EarlyHints::new(FileResolution::new("index.html").resolve())
+///     .preload("/style.css", "style")
+///     .preload("/app.js", "script")
+///     .resolve();
+/// ```
+pub struct EarlyHints {
+    links: Vec<String>,
+    inner: Box<dyn Resolution + Send + 'static>,
+}
+
+impl EarlyHints {
+    /// Sends hints before the resolution, add them with `link` or `preload`.
+    pub fn new(inner: Box<dyn Resolution + Send + 'static>) -> Self {
+        Self {
+            links: Vec::new(),
+            inner,
+        }
+    }
+
+    /// Adds a `Link` header, `</style.css>; rel=preload; as=style`.
+    pub fn link(mut self, link: &str) -> Self {
+        self.links.push(link.to_string());
+        self
+    }
+
+    /// Adds a preload `Link` to the url, `as` is the kind of resource, `style`, `script`, `font`, or `image`.
+    pub fn preload(self, url: &str, as_: &str) -> Self {
+        self.link(&format!("<{url}>; rel=preload; as={as_}"))
+    }
+}
+
+impl Resolution for EarlyHints {
+    fn get_headers(&self) -> HeaderMap {
+        let mut hmap = self.inner.get_headers();
+
+        for link in &self.links {
+            hmap.append("Link", Some(link.clone()));
+        }
+
+        hmap
+    }
+
+    fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        self.inner.get_content()
+    }
+
+    fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+        Box::new(self)
+    }
+
+    fn content_length(&self) -> Option<u64> {
+        self.inner.content_length()
+    }
+
+    fn seekable_len(&self) -> Option<u64> {
+        self.inner.seekable_len()
+    }
+
+    fn seek(&self, offset: u64) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        self.inner.seek(offset)
+    }
+
+    fn source_file(&self) -> Option<PathBuf> {
+        self.inner.source_file()
+    }
+
+    fn informational(&self) -> Vec<HeaderMap> {
+        let mut hints = self.inner.informational();
+
+        if !self.links.is_empty() {
+            let mut hmap = HeaderMap::new();
+
+            hmap.set_status(StatusCode::EARLY_HINTS);
+
+            for link in &self.links {
+                hmap.append("Link", Some(link.clone()));
+            }
+
+            hints.push(hmap);
+        }
+
+        hints
+    }
+}
@@ -1,9 +1,9 @@
 use futures::{Stream, stream};
-use linked_hash_map::LinkedHashMap;
 
 use crate::{ web::{
-    Resolution,
-    resolution::{empty_content, get_status_header},
+    Resolution, StatusCode,
+    headers::HeaderMap,
+    resolution::empty_content,
 }};
 
 /// ## Empty Resolution
@@ -12,23 +12,21 @@ use crate::{ web::{
 ///
 /// Simply creates an empty respond to send to the client with a status code you can set.
 pub struct EmptyResolution {
-    status_code: i32,
+    status_code: StatusCode,
 }
 
 impl EmptyResolution {
     /// Create a new boxed Empty Resolution
-    pub fn status(code: i32) -> Self {
-        Self { status_code: code }
+    pub fn status(code: impl Into<StatusCode>) -> Self {
+        Self { status_code: code.into() }
     }
 }
 
 impl Resolution for EmptyResolution {
-    fn get_headers(&self) -> LinkedHashMap<String, Option<String>> {
-        let mut hmap = LinkedHashMap::new();
+    fn get_headers(&self) -> HeaderMap {
+        let mut hmap = HeaderMap::new();
 
-        let header = get_status_header(self.status_code);
-
-        hmap.insert(header.0, Some(header.1));
+        hmap.set_status(self.status_code);
 
         hmap
     }
@@ -37,6 +35,10 @@ impl Resolution for EmptyResolution {
         Box::pin(stream::once(async move { empty_content() }))
     }
 
+    fn content_length(&self) -> Option<u64> {
+        Some(0)
+    }
+
     fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
         Box::new(self)
     }
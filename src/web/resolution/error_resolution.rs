@@ -1,10 +1,14 @@
-use std::{ fmt::Debug, panic};
+use std::{ backtrace::Backtrace, fmt::Debug, panic};
 
 use futures::stream;
-use linked_hash_map::LinkedHashMap;
 use serde::Serialize;
 
-use crate::{web::{Resolution, resolution::get_status_header}};
+use crate::{web::{
+    Resolution, StatusCode,
+    error_map::ErrorMap,
+    headers::HeaderMap,
+    resolution::problem_details::ProblemDetails,
+}};
 
 /// Idiomatic type alias for converting an Error to a string.
 pub type ErrorFormatter = dyn Fn(&Box<dyn std::error::Error + Send>) -> String + Send;
@@ -18,8 +22,13 @@ pub enum Configured {
     /// Plain Text
     PlainText,
     /// Output is JSON
+    ///
+    /// Sent as `application/problem+json` instead when the app uses problem details, see `App::set_problem_details`.
     Json,
 
+    /// Output is `application/problem+json` (RFC 9457), see `ProblemDetails`.
+    Problem,
+
     /// Custom
     ///
     /// Allows for you to emit a String based on the error received. See ErrorFormatter for the closure.
@@ -43,6 +52,7 @@ impl std::fmt::Debug for Configured {
         match self {
             Configured::PlainText => write!(f, "PlainText"),
             Configured::Json => write!(f, "Json"),
+            Configured::Problem => write!(f, "Problem"),
             Configured::Custom(_) => write!(f, "Custom(...)"),
         }
     }
@@ -85,6 +95,8 @@ impl std::fmt::Debug for Configured {
 ///     }));
 ///     
 /// ```
+///
+/// The status is 500, unless the type of the error is mapped to another status with `App::map_error`, or it is set with `with_status`.
 #[derive(Debug)]
 pub struct ErrorResolution {
     error: Box<dyn std::error::Error + Send + 'static>,
@@ -92,8 +104,11 @@ pub struct ErrorResolution {
 
     /// The error code
     /// 
-    /// Set to 500 initially, you can change this however.
-    pub code: i32
+    /// Set to 500 initially, or the status the type of the error is mapped to, you can change this however.
+    pub code: StatusCode,
+
+    //the detail included in JSON when the error map is in debug.
+    detail: Option<ErrorDetail>,
 }
 
 impl ErrorResolution {
@@ -116,7 +131,7 @@ impl ErrorResolution {
     /// ```
     pub fn from_error<T>(error: T, config: impl Into<Option<Configured>>) -> Self
     where
-        T: std::error::Error + 'static,
+        T: std::error::Error + Send + 'static,
     {
         let error = Box::new(error);
 
@@ -141,36 +156,66 @@ impl ErrorResolution {
     ///      });
     /// ```
     pub fn from_boxed(
-        error: Box<dyn std::error::Error>,
+        error: Box<dyn std::error::Error + Send + 'static>,
         config: impl Into<Option<Configured>>,
     ) -> Self {
+        let (code, detail) = mapped(&*error);
+
         Self {
-            error: InnerError::new_box(error),
-            config: config.into().unwrap_or(Configured::PlainText),
-            code: 500
+            error,
+            config: configured(config.into()),
+            code,
+            detail,
         }
     }
-}
 
-impl Resolution for ErrorResolution {
-    //outputs 500 header
-    fn get_headers(&self) -> LinkedHashMap<String, Option<String>> {
-        let mut hmap = LinkedHashMap::new();
-
-        let header = get_status_header(self.code);
+    /// # from_non_send
+    ///
+    /// Converts an error that can not be sent between tasks, such as one holding an `Rc`, into a `ErrorResolution` resolution.
+    ///
+    /// The error is formatted immediately and dropped, a `Custom` formatter is given the formatted message.
+    ///
+    /// ## Example
+    ///
+    /// ```ignore
+    /// let result = parse_with_rc_state(&body)
+    ///     .map_err(|e| ErrorResolution::from_non_send(e, Configured::Json));
+    /// ```
+    pub fn from_non_send(
+        error: impl Into<Box<dyn std::error::Error>>,
+        config: impl Into<Option<Configured>>,
+    ) -> Self {
+        let error: Box<dyn std::error::Error> = error.into();
+        let (code, detail) = mapped(&*error);
 
-        hmap.insert(header.0, Some(header.1));
+        Self {
+            error: Box::new(FormattedError::new(&*error)),
+            config: configured(config.into()),
+            code,
+            detail,
+        }
+    }
 
-        hmap
+    /// # With Status
+    ///
+    /// Sends the error with the status, replacing the mapped status.
+    ///
+    /// ```ignore
+    /// return ErrorResolution::from_error(e, Configured::Json).with_status(422).resolve();
+    /// ```
+    pub fn with_status(mut self, code: impl Into<StatusCode>) -> Self {
+        self.code = code.into();
+        self
     }
 
-    /// returns an outputted content
-    fn get_content(&self) -> std::pin::Pin<Box<dyn futures::Stream<Item = Vec<u8>> + Send>> {
-        let error_bytes = match &self.config {
+    /// the error formatted as configured.
+    fn body(&self) -> Vec<u8> {
+        match &self.config {
             Configured::Json => {
                 let error = CaptureJsonErr {
-                    code: self.code,
+                    code: self.code.as_u16(),
                     message: self.error.to_string(),
+                    detail: self.detail.as_ref(),
                 };
 
                 let json = serde_json::to_string(&error)
@@ -179,66 +224,152 @@ impl Resolution for ErrorResolution {
 
                 json
             }
+            Configured::Problem => {
+                let mut problem = ProblemDetails::new(self.code).detail(&self.error.to_string());
+
+                if let Some(detail) = &self.detail {
+                    problem = problem
+                        .extension("debug", &detail.debug)
+                        .extension("sources", &detail.sources)
+                        .extension("backtrace", &detail.backtrace);
+                }
+
+                problem.to_json()
+            }
             Configured::PlainText => self.error.to_string(),
             Configured::Custom(func) => {
                 let result = func(&self.error);
                 result
             }
         }
-        .into_bytes();
+        .into_bytes()
+    }
+}
+
+impl Resolution for ErrorResolution {
+    //outputs 500 header
+    fn get_headers(&self) -> HeaderMap {
+        let mut hmap = HeaderMap::new();
+
+        hmap.set_status(self.code);
+
+        match self.config {
+            Configured::Json => hmap.set_content_type("application/json"),
+            Configured::Problem => hmap.set_content_type("application/problem+json"),
+            Configured::PlainText => hmap.set_content_type("text/plain; charset=utf-8"),
+            Configured::Custom(_) => {}
+        }
+
+        hmap
+    }
+
+    /// returns an outputted content
+    fn get_content(&self) -> std::pin::Pin<Box<dyn futures::Stream<Item = Vec<u8>> + Send>> {
+        let error_bytes = self.body();
 
         Box::pin(stream::once(async move { error_bytes }))
     }
 
+    fn content_length(&self) -> Option<u64> {
+        Some(self.body().len() as u64)
+    }
+
     fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
         Box::new(self)
     }
 }
 
-/// # Inner Error
-///
-/// The inner error works as a container, it holds the Boxed error that is non-thread safe.
-///
-/// The Inner Error then implments the following traits
-///
-/// * std::error::Error
-/// * std::fmt::Display
-/// * core::marker::Send
-///
-/// For formatting, it relies on the format of the inner boxed error. So it uses the exact output as the error passed through.
+/// the status of the error from the error map of the request, and its detail while debugging.
+fn mapped(error: &(dyn std::error::Error + 'static)) -> (StatusCode, Option<ErrorDetail>) {
+    let error_map = ErrorMap::current();
+
+    let code = error_map
+        .as_ref()
+        .and_then(|map| map.status_of(error))
+        .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+
+    let detail = error_map
+        .filter(|map| map.debug())
+        .map(|_| ErrorDetail::capture(error));
+
+    (code, detail)
+}
+
+/// the output of the error, JSON is sent as problem details when the error map of the request uses them.
+fn configured(config: Option<Configured>) -> Configured {
+    match config.unwrap_or(Configured::PlainText) {
+        Configured::Json if ErrorMap::current().is_some_and(|map| map.problem_details()) => {
+            Configured::Problem
+        }
+        config => config,
+    }
+}
+
+/// # Formatted Error
 ///
-/// This essentially makes a Box<std::error::Error> thread safe.
+/// The display and debug output of an error that is not Send, formatted when it was converted so only strings are sent between tasks.
 ///
-#[derive(Debug)]
-struct InnerError {
-    error: Box<dyn std::error::Error>,
+/// See `ErrorResolution::from_non_send`.
+#[derive(Clone)]
+struct FormattedError {
+    message: String,
+    debug: String,
 }
 
-impl InnerError {
-    /// Create a new InnerError from a Boxed error.
-    fn new_box(contain: Box<dyn std::error::Error>) -> Box<dyn std::error::Error + Send + 'static> {
-        let inner = Self { error: contain };
-
-        Box::new(inner)
+impl FormattedError {
+    fn new(error: &dyn std::error::Error) -> Self {
+        Self {
+            message: error.to_string(),
+            debug: format!("{error:?}"),
+        }
     }
 }
 
-// impl simple Display based on the error string
-impl std::fmt::Display for InnerError {
+impl std::fmt::Display for FormattedError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        std::fmt::Display::fmt(&(*self.error), f)
+        f.write_str(&self.message)
     }
 }
 
-// error for idiomatic returns
-impl std::error::Error for InnerError {}
+impl std::fmt::Debug for FormattedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.debug)
+    }
+}
 
-//impl send for this, for sending between async operations
-unsafe impl Send for InnerError {}
+impl std::error::Error for FormattedError {}
 
 /// stores the code and message from the error to be serialized if the config of [`ErrorResolution`] is Json
 #[derive(Serialize)]
-struct CaptureJsonErr {
-    code: i32,
+struct CaptureJsonErr<'a> {
+    code: u16,
     message: String,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    detail: Option<&'a ErrorDetail>,
+}
+
+/// the debug output, sources, and backtrace of an error, included in JSON while debugging.
+#[derive(Debug, Serialize)]
+struct ErrorDetail {
+    debug: String,
+    sources: Vec<String>,
+    backtrace: String,
+}
+
+impl ErrorDetail {
+    fn capture(error: &(dyn std::error::Error + 'static)) -> Self {
+        let mut sources = Vec::new();
+        let mut source = error.source();
+
+        while let Some(e) = source {
+            sources.push(e.to_string());
+            source = e.source();
+        }
+
+        Self {
+            debug: format!("{error:?}"),
+            sources,
+            backtrace: Backtrace::force_capture().to_string(),
+        }
+    }
 }
@@ -1,11 +1,10 @@
 use futures::Stream;
-use linked_hash_map::LinkedHashMap;
 
-use crate::{ web::{Resolution, resolution::get_status_header, streams::stream_file}};
+use crate::{ web::{Resolution, StatusCode, headers::HeaderMap, streams::{stream_file, stream_file_from}}};
 
 /// # File Resolution
 ///
-/// Resolution that gives you the ability to serve files as an array of bytes.
+/// Resolution that streams a file from disk in chunks, the bytes are sent as they are so binary files are served correctly.
 ///
 /// This is useful for a content folder where you need to serve non-text based files.
 ///
@@ -48,57 +47,7 @@ impl FileResolution {
     /// ```
     ///
     fn get_file_type_header(&self) -> String {
-        // extract extension (lowercased)
-        let ext = match std::path::Path::new(&self.file_path)
-            .extension()
-            .and_then(|e| e.to_str())
-        {
-            Some(e) => e.to_lowercase(),
-            None => return "application/octet-stream".to_string(),
-        };
-
-        match ext.as_str() {
-            // text types
-            "html" | "htm" => "text/html",
-            "css" => "text/css",
-            "js" => "application/javascript",
-            "json" => "application/json",
-            "txt" => "text/plain",
-            "csv" => "text/csv",
-            "xml" => "application/xml",
-
-            // images
-            "jpg" | "jpeg" => "image/jpeg",
-            "png" => "image/png",
-            "gif" => "image/gif",
-            "bmp" => "image/bmp",
-            "webp" => "image/webp",
-            "svg" => "image/svg+xml",
-            "ico" => "image/x-icon",
-
-            // audio / video
-            "mp3" => "audio/mpeg",
-            "wav" => "audio/wav",
-            "ogg" => "audio/ogg",
-            "mp4" => "video/mp4",
-            "webm" => "video/webm",
-
-            // fonts
-            "woff" => "font/woff",
-            "woff2" => "font/woff2",
-            "ttf" => "font/ttf",
-            "otf" => "font/otf",
-
-            // documents / archives
-            "pdf" => "application/pdf",
-            "zip" => "application/zip",
-            "tar" => "application/x-tar",
-            "gz" => "application/gzip",
-
-            // fallback
-            _ => "application/octet-stream",
-        }
-        .to_string()
+        content_type_of(&self.file_path).to_string()
     }
 
     /// # Get Status
@@ -108,11 +57,11 @@ impl FileResolution {
     ///  `200` -> File exist
     ///
     ///  `404` -> File does not exist
-    fn get_status(&self) -> i32 {
+    fn get_status(&self) -> StatusCode {
         if std::path::Path::new(&self.file_path).exists() {
-            200
+            StatusCode::OK
         } else {
-            404
+            StatusCode::NOT_FOUND
         }
     }
 }
@@ -120,15 +69,14 @@ impl FileResolution {
 impl Resolution for FileResolution {
     /// # get headers
     ///
-    /// For this implementation the headers are the status of the file 200/404 and the file type header, see the get_file_type_header function
-    fn get_headers(&self) -> LinkedHashMap<String, Option<String>> {
-        let mut hmap = LinkedHashMap::new();
-
-        let header = get_status_header(self.get_status());
-        let file_type_header = self.get_file_type_header();
+    /// For this implementation the headers are the status of the file 200/404 and the file type header, see the get_file_type_header function.
+    ///
+    /// The length of the file is known from its metadata, so the file is not chunked.
+    fn get_headers(&self) -> HeaderMap {
+        let mut hmap = HeaderMap::new();
 
-        hmap.insert(header.0, Some(header.1));
-        hmap.insert("Content-Type".to_string(), Some(file_type_header));
+        hmap.set_status(self.get_status());
+        hmap.set_content_type(&self.get_file_type_header());
 
         hmap
     }
@@ -145,4 +93,81 @@ impl Resolution for FileResolution {
     fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
         Box::new(self)
     }
+
+    /// files are seekable, so ranges of them may be requested.
+    fn seekable_len(&self) -> Option<u64> {
+        std::fs::metadata(&self.file_path)
+            .ok()
+            .filter(|m| m.is_file())
+            .map(|m| m.len())
+    }
+
+    fn seek(&self, offset: u64) -> std::pin::Pin<Box<dyn Stream<Item = Vec<u8>> + Send + 'static>> {
+        Box::pin(stream_file_from(self.file_path.clone(), offset))
+    }
+
+    /// the content is the file as is, so large files are copied to the client.
+    fn source_file(&self) -> Option<std::path::PathBuf> {
+        Some(std::path::PathBuf::from(&self.file_path))
+    }
+}
+
+/// # Content Type Of
+///
+/// The MIME type of a file based on its extension, `application/octet-stream` when it is not known.
+pub fn content_type_of(file_path: &str) -> &'static str {
+    // extract extension (lowercased)
+    let ext = match std::path::Path::new(file_path)
+        .extension()
+        .and_then(|e| e.to_str())
+    {
+        Some(e) => e.to_lowercase(),
+        None => return "application/octet-stream",
+    };
+
+    match ext.as_str() {
+        // text types
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "js" | "mjs" => "application/javascript",
+        "json" | "map" => "application/json",
+        "webmanifest" => "application/manifest+json",
+        "txt" => "text/plain; charset=utf-8",
+        "md" => "text/markdown",
+        "csv" => "text/csv",
+        "xml" => "application/xml",
+
+        // images
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "avif" => "image/avif",
+
+        // audio / video
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "ogg" => "audio/ogg",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+
+        // fonts
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+
+        // documents / archives
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "tar" => "application/x-tar",
+        "gz" => "application/gzip",
+        "wasm" => "application/wasm",
+
+        // fallback
+        _ => "application/octet-stream",
+    }
 }
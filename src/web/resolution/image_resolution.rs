@@ -0,0 +1,309 @@
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use futures::{Stream, stream};
+use image::{ImageFormat, codecs::jpeg::JpegEncoder, imageops::FilterType};
+use sha2::{Digest, Sha256};
+
+use crate::web::{
+    Resolution, Route, StatusCode,
+    headers::HeaderMap,
+    resolution::empty_content,
+    streams::stream_file,
+};
+
+/// The largest width or height an image may be resized to.
+pub const MAX_DIMENSION: u32 = 4096;
+
+/// # Image Options
+///
+/// The transformation applied to an image by [`ImageResolution`].
+///
+/// Usually read from the query of the route, for example `/media/cat.png?w=200&h=200&format=webp&quality=80`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ImageOptions {
+    /// The max width of the output, the aspect ratio is kept.
+    pub width: Option<u32>,
+    /// The max height of the output, the aspect ratio is kept.
+    pub height: Option<u32>,
+    /// The format to transcode into, by default the format of the source.
+    pub format: Option<String>,
+    /// Quality from 1-100, only used for jpeg.
+    pub quality: Option<u8>,
+}
+
+impl ImageOptions {
+    /// # From Route
+    ///
+    /// Reads the `w`, `h`, `format`, and `quality` params from a route.
+    ///
+    /// Invalid values are ignored, dimensions are capped at [`MAX_DIMENSION`].
+    pub fn from_route(route: &Route) -> Self {
+        let dimension = |key: &str| {
+            route
+                .get_param(key)
+                .and_then(|v| v.parse::<u32>().ok())
+                .filter(|v| *v > 0)
+                .map(|v| v.min(MAX_DIMENSION))
+        };
+
+        Self {
+            width: dimension("w"),
+            height: dimension("h"),
+            format: route.get_param("format").map(|f| f.to_lowercase()),
+            quality: route
+                .get_param("quality")
+                .and_then(|q| q.parse::<u8>().ok())
+                .map(|q| q.clamp(1, 100)),
+        }
+    }
+
+    /// Returns true if the options do not change the image.
+    pub fn is_identity(&self) -> bool {
+        self.width.is_none() && self.height.is_none() && self.format.is_none()
+    }
+}
+
+/// # Image Resolution
+///
+/// Resizes and transcodes an image file with the `image` crate.
+///
+/// Requires the `image` feature.
+///
+/// If a cache directory is given the transformed image is written to disk and reused for the same file and options.
+///
+/// ## Example
+///
+/// ```ignore
+/// app.add_or_panic("/media/{*}", Method::GET, None, |req| async move {
+///     let guard = req.lock().await;
+///
+///     let path = format!("media/{}", guard.variables.get("*").unwrap());
+///     let options = ImageOptions::from_route(&guard.route);
+///
+///     ImageResolution::transform(&path, options, Some("cache/media"))
+///         .await
+///         .resolve()
+/// })
+/// .await;
+/// ```
+pub struct ImageResolution {
+    status_code: StatusCode,
+    content_type: &'static str,
+    content: ImageContent,
+}
+
+/// where the content of the transformed image lives.
+enum ImageContent {
+    Empty,
+    Memory(Arc<Vec<u8>>),
+    File(PathBuf),
+}
+
+impl ImageResolution {
+    /// # Transform
+    ///
+    /// Transforms the image at `source` with the given options.
+    ///
+    /// The decoding and encoding is done on the blocking pool.
+    ///
+    /// The status of the resolution is:
+    ///
+    /// `404` -> The source does not exist
+    ///
+    /// `415` -> The source could not be decoded or the format is not supported
+    ///
+    /// `200` -> The image was transformed
+    pub async fn transform(source: &str, options: ImageOptions, cache_dir: Option<&str>) -> Self {
+        let source = PathBuf::from(source);
+
+        let modified = match tokio::fs::metadata(&source).await {
+            Ok(meta) if meta.is_file() => meta.modified().ok(),
+            _ => return Self::status(StatusCode::NOT_FOUND),
+        };
+
+        let input_format = match ImageFormat::from_path(&source) {
+            Ok(format) => format,
+            Err(_) => return Self::status(StatusCode::UNSUPPORTED_MEDIA_TYPE),
+        };
+
+        let output_format = match &options.format {
+            None => input_format,
+            Some(ext) => match ImageFormat::from_extension(ext) {
+                Some(format) => format,
+                None => return Self::status(StatusCode::UNSUPPORTED_MEDIA_TYPE),
+            },
+        };
+
+        let content_type = output_format.to_mime_type();
+
+        //nothing to transform, serve the source as is.
+        if options.is_identity() {
+            return Self {
+                status_code: StatusCode::OK,
+                content_type,
+                content: ImageContent::File(source),
+            };
+        }
+
+        //the cached file is keyed by the source, when it was modified, and the options.
+        let cached = cache_dir.map(|dir| {
+            let ext = output_format.extensions_str().first().unwrap_or(&"img");
+
+            Path::new(dir).join(format!("{}.{ext}", cache_key(&source, modified, &options)))
+        });
+
+        if let Some(cached) = &cached
+            && tokio::fs::try_exists(cached).await.unwrap_or(false)
+        {
+            return Self {
+                status_code: StatusCode::OK,
+                content_type,
+                content: ImageContent::File(cached.clone()),
+            };
+        }
+
+        let encoded = tokio::task::spawn_blocking(move || {
+            encode(&source, input_format, output_format, &options)
+        })
+        .await;
+
+        let bytes = match encoded {
+            Ok(Some(bytes)) => bytes,
+            _ => return Self::status(StatusCode::UNSUPPORTED_MEDIA_TYPE),
+        };
+
+        //failing to cache is not fatal, the image is still served.
+        if let Some(cached) = cached {
+            if let Some(parent) = cached.parent() {
+                let _ = tokio::fs::create_dir_all(parent).await;
+            }
+
+            write_atomic(&cached, &bytes).await;
+        }
+
+        Self {
+            status_code: StatusCode::OK,
+            content_type,
+            content: ImageContent::Memory(Arc::new(bytes)),
+        }
+    }
+
+    /// an empty image resolution with a status.
+    fn status(code: StatusCode) -> Self {
+        Self {
+            status_code: code,
+            content_type: "text/plain",
+            content: ImageContent::Empty,
+        }
+    }
+}
+
+/// the hex sha256 of the source, when it was modified, and the options.
+///
+/// every field is length prefixed so that no two inputs share a key.
+fn cache_key(source: &Path, modified: Option<SystemTime>, options: &ImageOptions) -> String {
+    let mut hasher = Sha256::new();
+
+    let mut field = |bytes: &[u8]| {
+        hasher.update((bytes.len() as u64).to_be_bytes());
+        hasher.update(bytes);
+    };
+
+    field(source.to_string_lossy().as_bytes());
+
+    let modified = modified
+        .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos().to_be_bytes().to_vec())
+        .unwrap_or_default();
+    field(&modified);
+
+    let dimension = |v: Option<u32>| v.map(|v| v.to_be_bytes().to_vec()).unwrap_or_default();
+    field(&dimension(options.width));
+    field(&dimension(options.height));
+    field(options.format.as_deref().unwrap_or_default().as_bytes());
+    field(&options.quality.map(|q| vec![q]).unwrap_or_default());
+
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// writes the bytes to a temp file next to `path` and renames it into place,
+/// so a concurrent request never sees a partially written image.
+async fn write_atomic(path: &Path, bytes: &[u8]) {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.tmp", uuid::Uuid::new_v4()));
+    let temp = path.with_file_name(name);
+
+    let written = tokio::fs::write(&temp, bytes).await.is_ok()
+        && tokio::fs::rename(&temp, path).await.is_ok();
+
+    if !written {
+        let _ = tokio::fs::remove_file(&temp).await;
+    }
+}
+
+/// Decodes, resizes, and encodes the source image.
+fn encode(
+    source: &Path,
+    input_format: ImageFormat,
+    output_format: ImageFormat,
+    options: &ImageOptions,
+) -> Option<Vec<u8>> {
+    let reader = std::io::BufReader::new(std::fs::File::open(source).ok()?);
+    let mut img = image::load(reader, input_format).ok()?;
+
+    if options.width.is_some() || options.height.is_some() {
+        let width = options.width.unwrap_or(MAX_DIMENSION);
+        let height = options.height.unwrap_or(MAX_DIMENSION);
+
+        img = img.resize(width, height, FilterType::Triangle);
+    }
+
+    let mut output = Cursor::new(Vec::new());
+
+    match (output_format, options.quality) {
+        (ImageFormat::Jpeg, Some(quality)) => {
+            let encoder = JpegEncoder::new_with_quality(&mut output, quality);
+            img.to_rgb8().write_with_encoder(encoder).ok()?;
+        }
+        (ImageFormat::Jpeg, None) => img.to_rgb8().write_to(&mut output, output_format).ok()?,
+        _ => img.write_to(&mut output, output_format).ok()?,
+    }
+
+    Some(output.into_inner())
+}
+
+impl Resolution for ImageResolution {
+    fn get_headers(&self) -> HeaderMap {
+        let mut hmap = HeaderMap::new();
+
+        hmap.set_status(self.status_code);
+        hmap.set_content_type(self.content_type);
+
+        hmap
+    }
+
+    fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        match &self.content {
+            ImageContent::Empty => Box::pin(stream::once(async move { empty_content() })),
+            ImageContent::Memory(bytes) => {
+                let bytes = bytes.clone();
+                Box::pin(stream::once(async move { bytes.to_vec() }))
+            }
+            ImageContent::File(path) => Box::pin(stream_file(path.to_string_lossy().to_string())),
+        }
+    }
+
+    fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+        Box::new(self)
+    }
+}
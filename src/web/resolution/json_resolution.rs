@@ -1,14 +1,14 @@
 use std::pin::Pin;
 
 use futures::{Stream, stream};
-use linked_hash_map::LinkedHashMap;
 use serde::Serialize;
 use serde_json::{Value, json};
 
 use crate::{
     web::{
-        Resolution,
-        resolution::{error_resolution::ErrorResolution, get_status_header},
+        Resolution, StatusCode,
+        headers::HeaderMap,
+        resolution::error_resolution::ErrorResolution,
     },
 };
 
@@ -37,7 +37,7 @@ use crate::{
 /// ```
 pub struct JsonResolution {
     json_value: String,
-    status_code: i32,
+    status_code: StatusCode,
 }
 
 impl JsonResolution {
@@ -70,14 +70,14 @@ impl JsonResolution {
         serde_json::to_string(&value)
             .map(|json| Self {
                 json_value: json,
-                status_code: 200,
+                status_code: StatusCode::OK,
             })
             .map_err(|e| ErrorResolution::from_error(e, super::error_resolution::Configured::Json))
     }
 
     /// Set the status code of the resolution.
-    pub fn set_status(&mut self, status_code: i32) -> () {
-        self.status_code = status_code
+    pub fn set_status(&mut self, status_code: impl Into<StatusCode>) {
+        self.status_code = status_code.into()
     }
 
     /// Convert string based json value back to a serde::Value
@@ -91,16 +91,11 @@ impl Resolution for JsonResolution {
         Box::new(self)
     }
 
-    fn get_headers(&self) -> LinkedHashMap<String, Option<String>> {
-        let mut hmap = LinkedHashMap::new();
+    fn get_headers(&self) -> HeaderMap {
+        let mut hmap = HeaderMap::new();
 
-        let header = get_status_header(self.status_code);
-
-        hmap.insert(header.0, Some(header.1));
-        hmap.insert(
-            "Content-Type".to_string(),
-            Some("application/json".to_string()),
-        );
+        hmap.set_status(self.status_code);
+        hmap.set_content_type("application/json");
 
         hmap
     }
@@ -110,4 +105,8 @@ impl Resolution for JsonResolution {
 
         Box::pin(stream::once(async move { json_value.into_bytes() }))
     }
+
+    fn content_length(&self) -> Option<u64> {
+        Some(self.json_value.len() as u64)
+    }
 }
@@ -1,44 +1,46 @@
-use std::{cell::RefCell, pin::Pin};
+use std::{pin::Pin, sync::Mutex};
 
-use async_stream::stream;
-use futures::{Stream, stream::once};
-use linked_hash_map::LinkedHashMap;
-use tokio_stream::StreamExt;
+use futures::{Stream, StreamExt, stream};
 
-use crate::web::{Resolution, resolution::empty_content};
+use crate::web::{Resolution, headers::HeaderMap};
 
-//represents a struct that holds the merged struct.
-struct MergedResolution {
-    headers: RefCell<Option<LinkedHashMap<String, Option<String>>>>,
-    stream: RefCell<Option<Pin<Box<dyn Stream<Item = Vec<u8>> + Send>>>>,
+/// Idiomatic type alias for the merged content, taken once by `get_content`.
+type ContentStream = Pin<Box<dyn Stream<Item = Vec<u8>> + Send>>;
+
+/// ## Merged Resolution
+///
+/// Implementation of the Resolution trait.
+///
+/// Several resolutions sent as one, see `and` and `chain`. The content of each is sent after the content of the one before it, never interleaved.
+///
+/// The content is streamed once, later calls of `get_content` are empty.
+pub struct MergedResolution {
+    headers: HeaderMap,
+    content_length: Option<u64>,
+    stream: Mutex<Option<ContentStream>>,
+}
+
+impl MergedResolution {
+    /// Sends the content of the resolution after the merged content, the merged headers take precedence.
+    pub fn and<R: Resolution>(self, next: R) -> Self {
+        chain([self.resolve(), next.resolve()])
+    }
 }
 
 impl Resolution for MergedResolution {
-    fn get_headers(&self) -> LinkedHashMap<String, Option<String>> {
-        //borrow the header mutability
-        let mut ref_headers = self.headers.borrow_mut();
-        //take the headers, none if nothing anyhow
-        let taken_headers = ref_headers.take();
-
-        //return the headers only once, if none return none
-        if let Some(headers) = taken_headers {
-            headers
-        } else {
-            LinkedHashMap::new()
-        }
+    fn get_headers(&self) -> HeaderMap {
+        self.headers.clone()
     }
 
     fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
-        let stream = {
-            //borrow this as mut, takes ONCE.
-            let mut opt_stream = self.stream.borrow_mut();
-            let s = opt_stream.take();
+        //taken ONCE, no content is left to serve after.
+        let stream = self.stream.lock().unwrap_or_else(|e| e.into_inner()).take();
 
-            //no content left to serve, this should never serve content again.
-            s.unwrap_or_else(|| Box::pin(once(async move { empty_content() })))
-        };
+        stream.unwrap_or_else(|| Box::pin(stream::empty()))
+    }
 
-        stream
+    fn content_length(&self) -> Option<u64> {
+        self.content_length
     }
 
     fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
@@ -48,36 +50,59 @@ impl Resolution for MergedResolution {
 
 /// # and
 ///
-/// Combines the Left and Right resolution into a Merged Resolution.
+/// Combines the Left and Right resolution into a Merged Resolution, the content of the left is sent and then the content of the right.
 ///
 /// It is important to note that the left and right headers become merged (as to avoid resolution conflict).
 ///
 /// The left headers take precedent over the right side headers however.
-pub fn and<L, R>(left: L, right: R) -> impl Resolution
+pub fn and<L, R>(left: L, right: R) -> MergedResolution
 where
     L: Resolution,
     R: Resolution,
 {
-    //get the leftside headers, then the rightside headers
-    let left_headers = left.get_headers();
-    let mut combined_headers = right.get_headers();
+    chain([left.resolve(), right.resolve()])
+}
+
+/// # chain
+///
+/// Combines the resolutions in order into a Merged Resolution, the content of each is sent after the content of the one before it.
+///
+/// Headers of earlier resolutions take precedent over the headers of later ones. The `Content-Length` is the sum of the lengths, or the content is chunked if any length is not known.
+///
+/// ```ignore
+/// let page = chain([
+///     FileResolution::new("header.html").resolve(),
+///     TextResolution::new(body).resolve(),
+///     FileResolution::new("footer.html").resolve(),
+/// ]);
+/// ```
+pub fn chain<I>(resolutions: I) -> MergedResolution
+where
+    I: IntoIterator<Item = Box<dyn Resolution + Send + 'static>>,
+{
+    let mut headers = HeaderMap::new();
+    let mut content_length = Some(0);
+    let mut streams = Vec::new();
+
+    for resolution in resolutions {
+        //place the earlier headers on top of this table.
+        let mut next_headers = resolution.get_headers();
+        next_headers.merge(headers);
+        headers = next_headers;
+
+        content_length = content_length
+            .zip(resolution.content_length())
+            .map(|(total, length)| total + length);
 
-    //place the left hand side on top of the right table
-    for (key, value) in left_headers {
-        combined_headers.insert(key, value);
+        streams.push(resolution.get_content());
     }
 
-    //combine the streams to do one after another, create a new stream that is the merged.
-    let mut merged = left.get_content().merge(right.get_content());
-    let content_stream = stream! {
-        while let Some(content) = merged.next().await {
-            yield content;
-        }
-    };
+    //the lengths of the parts are not the length of the whole.
+    headers.remove("Content-Length");
 
     MergedResolution {
-        headers: RefCell::new(Some(combined_headers)),
-        //refcell, some, pin box
-        stream: RefCell::new(Some(Box::pin(content_stream))),
+        headers,
+        content_length,
+        stream: Mutex::new(Some(Box::pin(stream::iter(streams).flatten()))),
     }
 }
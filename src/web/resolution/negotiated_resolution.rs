@@ -0,0 +1,134 @@
+use std::pin::Pin;
+
+use futures::Stream;
+use serde::Serialize;
+
+use crate::web::{
+    Resolution, StatusCode,
+    headers::HeaderMap,
+    negotiate::Accepts,
+    resolution::{
+        empty_resolution::EmptyResolution, json_resolution::JsonResolution,
+        text_resolution::TextResolution,
+    },
+};
+
+/// ## Negotiated Resolution
+///
+/// Implementation of the Resolution trait.
+///
+/// Holds several representations of the same data and sends the one the `Accept` header of the request prefers, or an empty `406` if the client accepts none of them.
+///
+/// When the client prefers none over another, the first registered representation is sent. Responses carry `Vary: Accept`.
+///
+/// ## Example
+///
+/// ```ignore
+/// let accepts = Accepts::from_request(&*req.lock().await)?;
+///
+/// return NegotiatedResolution::new(accepts)
+///     .json(&user)
+///     .html(&format!("<h1>{}</h1>", user.name))
+///     .text(&user.name)
+///     .resolve();
+/// ```
+pub struct NegotiatedResolution {
+    accepts: Accepts,
+    representations: Vec<(String, Box<dyn Resolution + Send + 'static>)>,
+    not_acceptable: EmptyResolution,
+}
+
+impl NegotiatedResolution {
+    /// Create a negotiated resolution without representations.
+    pub fn new(accepts: Accepts) -> Self {
+        Self {
+            accepts,
+            representations: Vec::new(),
+            not_acceptable: EmptyResolution::status(StatusCode::NOT_ACCEPTABLE),
+        }
+    }
+
+    /// # Representation
+    ///
+    /// Registers a resolution sent when the client prefers `media_type`.
+    pub fn representation(
+        mut self,
+        media_type: &str,
+        resolution: Box<dyn Resolution + Send + 'static>,
+    ) -> Self {
+        self.representations.push((media_type.to_string(), resolution));
+        self
+    }
+
+    /// Registers `application/json`, a value that fails to serialize is sent as its error.
+    pub fn json<T: Serialize>(self, value: &T) -> Self {
+        let resolution = match JsonResolution::serialize(value) {
+            Ok(json) => json.resolve(),
+            Err(e) => e.resolve(),
+        };
+
+        self.representation("application/json", resolution)
+    }
+
+    /// Registers `text/html`.
+    pub fn html(self, html: &str) -> Self {
+        let mut resolution = TextResolution::new(html);
+        resolution.set_content_type("text/html; charset=utf-8");
+
+        self.representation("text/html", resolution.resolve())
+    }
+
+    /// Registers `text/plain`.
+    pub fn text(self, text: &str) -> Self {
+        self.representation("text/plain", TextResolution::new(text).resolve())
+    }
+
+    /// The media type that is sent, None if the client accepts none of the representations.
+    pub fn selected_type(&self) -> Option<&str> {
+        let offered: Vec<&str> = self
+            .representations
+            .iter()
+            .map(|(media_type, _)| media_type.as_str())
+            .collect();
+
+        self.accepts.preferred(&offered)
+    }
+
+    fn selected(&self) -> &dyn Resolution {
+        let Some(media_type) = self.selected_type() else {
+            return &self.not_acceptable;
+        };
+
+        self.representations
+            .iter()
+            .find(|(offered, _)| offered == media_type)
+            .map(|(_, resolution)| resolution.as_ref() as &dyn Resolution)
+            .unwrap_or(&self.not_acceptable)
+    }
+}
+
+impl Resolution for NegotiatedResolution {
+    fn get_headers(&self) -> HeaderMap {
+        let mut hmap = self.selected().get_headers();
+
+        hmap.insert("Vary", Some("Accept".to_string()));
+
+        hmap
+    }
+
+    fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        self.selected().get_content()
+    }
+
+    fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+        Box::new(self)
+    }
+
+    fn seekable_len(&self) -> Option<u64> {
+        self.selected().seekable_len()
+    }
+
+    fn seek(&self, offset: u64) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        self.selected().seek(offset)
+    }
+}
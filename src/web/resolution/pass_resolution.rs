@@ -0,0 +1,36 @@
+use futures::{Stream, stream};
+
+use crate::web::{
+    Resolution,
+    headers::HeaderMap,
+    resolution::empty_content,
+};
+
+/// ## Pass Resolution
+///
+/// Signals that a resolution of a chain could not handle the request, so the next resolution of the endpoint is tried, see `App::add_route_chain`.
+///
+/// When the last resolution of a chain passes, or a resolution passes outside of a chain, the client receives an empty `404`.
+pub struct PassResolution;
+
+impl Resolution for PassResolution {
+    fn get_headers(&self) -> HeaderMap {
+        let mut hmap = HeaderMap::new();
+
+        hmap.set_status(404);
+
+        hmap
+    }
+
+    fn get_content(&self) -> std::pin::Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        Box::pin(stream::once(async move { empty_content() }))
+    }
+
+    fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+        Box::new(self)
+    }
+
+    fn is_pass(&self) -> bool {
+        true
+    }
+}
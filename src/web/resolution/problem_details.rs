@@ -0,0 +1,133 @@
+use std::pin::Pin;
+
+use futures::{Stream, stream};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::web::{Resolution, StatusCode, headers::HeaderMap};
+
+/// ## Problem Details
+///
+/// Implementation of the Resolution trait.
+///
+/// An error sent as `application/problem+json` (RFC 9457), so clients of an API can read errors the same way across services.
+///
+/// The type is `about:blank` and the title is the reason of the status unless they are set.
+///
+/// ## Example
+///
+/// ```ignore
+/// return ProblemDetails::new(403)
+///     .with_type("https://example.com/probs/out-of-credit")
+///     .title("You do not have enough credit.")
+///     .detail("Your current balance is 30, but that costs 50.")
+///     .instance(&format!("/account/{id}/msgs/abc"))
+///     .extension("balance", 30)
+///     .resolve();
+/// ```
+#[derive(Debug, Clone, Serialize)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    type_uri: String,
+    title: String,
+    status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instance: Option<String>,
+    #[serde(flatten)]
+    extensions: Map<String, Value>,
+    #[serde(skip)]
+    status_code: StatusCode,
+}
+
+impl ProblemDetails {
+    /// Create a problem of the status, titled with the reason of the status.
+    pub fn new(status_code: impl Into<StatusCode>) -> Self {
+        let status_code = status_code.into();
+
+        Self {
+            type_uri: "about:blank".to_string(),
+            title: status_code.reason().unwrap_or("Unknown Status Code").to_string(),
+            status: status_code.as_u16(),
+            detail: None,
+            instance: None,
+            extensions: Map::new(),
+            status_code,
+        }
+    }
+
+    /// A URI identifying the type of problem.
+    pub fn with_type(mut self, type_uri: &str) -> Self {
+        self.type_uri = type_uri.to_string();
+        self
+    }
+
+    /// A short summary of the type of problem.
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    /// An explanation of this occurrence of the problem.
+    pub fn detail(mut self, detail: &str) -> Self {
+        self.detail = Some(detail.to_string());
+        self
+    }
+
+    /// A URI identifying this occurrence of the problem.
+    pub fn instance(mut self, instance: &str) -> Self {
+        self.instance = Some(instance.to_string());
+        self
+    }
+
+    /// # Extension
+    ///
+    /// Adds a member to the problem, such as the `balance` of an out of credit problem. A value that fails to serialize is sent as null.
+    ///
+    /// The members `type`, `title`, `status`, `detail`, and `instance` are ignored.
+    pub fn extension(mut self, name: &str, value: impl Serialize) -> Self {
+        if !matches!(name, "type" | "title" | "status" | "detail" | "instance") {
+            let value = serde_json::to_value(value).unwrap_or(Value::Null);
+            self.extensions.insert(name.to_string(), value);
+        }
+
+        self
+    }
+
+    /// The status code of the problem.
+    pub fn status_code(&self) -> StatusCode {
+        self.status_code
+    }
+
+    /// The problem as JSON.
+    pub fn to_json(&self) -> String {
+        //only strings and json values are serialized.
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+impl Resolution for ProblemDetails {
+    fn get_headers(&self) -> HeaderMap {
+        let mut hmap = HeaderMap::new();
+
+        hmap.set_status(self.status_code);
+        hmap.set_content_type("application/problem+json");
+
+        hmap
+    }
+
+    fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        let json = self.to_json().into_bytes();
+
+        Box::pin(stream::once(async move { json }))
+    }
+
+    fn content_length(&self) -> Option<u64> {
+        Some(self.to_json().len() as u64)
+    }
+
+    fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+        Box::new(self)
+    }
+}
@@ -0,0 +1,454 @@
+use std::{pin::Pin, sync::Mutex, time::Duration};
+
+use async_stream::stream;
+use futures::{Stream, stream};
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+
+use crate::web::{
+    Request, Resolution, StatusCode, errors::ConfigError, forwarded::TrustedProxies,
+    headers::HeaderMap,
+    trace_context::{TRACEPARENT_HEADER, TRACESTATE_HEADER, TraceContext},
+};
+
+/// headers of a single connection, never forwarded.
+const HOP_BY_HOP: [&str; 9] = [
+    "Connection",
+    "Keep-Alive",
+    "Proxy-Authenticate",
+    "Proxy-Authorization",
+    "Proxy-Connection",
+    "TE",
+    "Trailer",
+    "Transfer-Encoding",
+    "Upgrade",
+];
+
+/// Idiomatic type alias for the body streamed from the upstream.
+type BodyStream = Pin<Box<dyn Stream<Item = Vec<u8>> + Send>>;
+
+/// the longest line of an upstream response head.
+const MAX_LINE: usize = 8 * 1024;
+
+/// # Upstream
+///
+/// The server a [`ProxyResolution`] forwards to, an `http://` base URL.
+///
+/// The request target is appended to the path of the URL, `http://10.0.0.2:9000/v2` forwards `/users?page=2` to `/v2/users?page=2`.
+///
+/// ```ignore
+/// let upstream = Upstream::new("http://10.0.0.2:9000")?.strip_prefix("/api");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Upstream {
+    authority: String,
+    base_path: String,
+    strip_prefix: Option<String>,
+    proto: String,
+    timeout: Duration,
+}
+
+impl Upstream {
+    /// # New
+    ///
+    /// Parses the base URL, only `http://` upstreams are supported.
+    pub fn new(url: &str) -> Result<Self, ConfigError> {
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or(ConfigError::Parse(format!("'{url}' is not an http:// URL")))?;
+
+        let (authority, path) = match rest.find('/') {
+            Some(at) => rest.split_at(at),
+            None => (rest, ""),
+        };
+
+        if authority.is_empty() {
+            return Err(ConfigError::Parse(format!("'{url}' has no host")));
+        }
+
+        //the port is optional in the URL, not when connecting.
+        let authority = match authority.rsplit_once(':') {
+            Some((_, port)) if !port.contains(']') => authority.to_string(),
+            _ => format!("{authority}:80"),
+        };
+
+        Ok(Self {
+            authority,
+            base_path: path.trim_end_matches('/').to_string(),
+            strip_prefix: None,
+            proto: "http".to_string(),
+            timeout: Duration::from_secs(30),
+        })
+    }
+
+    /// Removes the prefix from the request target before it is appended, `/api/users` is forwarded as `/users`.
+    pub fn strip_prefix(mut self, prefix: &str) -> Self {
+        self.strip_prefix = Some(prefix.trim_end_matches('/').to_string());
+        self
+    }
+
+    /// The `X-Forwarded-Proto` sent upstream, `https` when the app serves TLS.
+    ///
+    /// By default (http)
+    pub fn proto(mut self, proto: &str) -> Self {
+        self.proto = proto.to_string();
+        self
+    }
+
+    /// How long connecting and receiving the response head may take, the client receives `504` after.
+    ///
+    /// By default (30 seconds)
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// the target of the request on the upstream.
+    fn target(&self, request_target: &str) -> String {
+        let target = self
+            .strip_prefix
+            .as_deref()
+            .and_then(|prefix| request_target.strip_prefix(prefix))
+            .unwrap_or(request_target);
+
+        match target.starts_with('/') {
+            true => format!("{}{target}", self.base_path),
+            false => format!("{}/{target}", self.base_path),
+        }
+    }
+}
+
+/// ## Proxy Resolution
+///
+/// Implementation of the Resolution trait.
+///
+/// Forwards the request to an [`Upstream`] and streams its response back, so the app may act as a lightweight gateway.
+///
+/// * hop-by-hop headers, and headers named by `Connection`, are not forwarded in either direction
+/// * `X-Forwarded-For`, `X-Forwarded-Host`, and `X-Forwarded-Proto` are sent upstream, the incoming values are only kept from proxies trusted with `App::set_trusted_proxies`
+/// * the client receives `502` if the upstream can not be reached or answers with an invalid response, and `504` when it times out
+///
+/// Requires the `proxy` feature.
+///
+/// ## Example
+///
+/// ```ignore
+/// let upstream = Upstream::new("http://10.0.0.2:9000")?.strip_prefix("/api");
+///
+/// app.add_or_panic("/api/{*}", Method::GET, None, move |req| {
+///     let upstream = upstream.clone();
+///     async move { ProxyResolution::forward(&upstream, &*req.lock().await).await.resolve() }
+/// })
+/// .await;
+/// ```
+pub struct ProxyResolution {
+    headers: HeaderMap,
+    content_length: Option<u64>,
+    stream: Mutex<Option<BodyStream>>,
+}
+
+impl ProxyResolution {
+    /// # Forward
+    ///
+    /// Sends the request upstream and waits for the head of its response, the body is streamed when the resolution is sent.
+    pub async fn forward(upstream: &Upstream, req: &Request) -> Self {
+        let (head, body) = (request_head(upstream, req), req.body.clone());
+
+        match tokio::time::timeout(upstream.timeout, exchange(upstream, head, body)).await {
+            Ok(Some(resolution)) => resolution,
+            Ok(None) => Self::failed(StatusCode::BAD_GATEWAY),
+            Err(_) => Self::failed(StatusCode::GATEWAY_TIMEOUT),
+        }
+    }
+
+    /// a response without content, when the upstream did not answer.
+    fn failed(status: StatusCode) -> Self {
+        let mut headers = HeaderMap::new();
+        headers.set_status(status);
+
+        Self {
+            headers,
+            content_length: Some(0),
+            stream: Mutex::new(None),
+        }
+    }
+}
+
+impl Resolution for ProxyResolution {
+    fn get_headers(&self) -> HeaderMap {
+        self.headers.clone()
+    }
+
+    fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        //taken ONCE, the upstream connection is read as it is sent.
+        let stream = self.stream.lock().unwrap_or_else(|e| e.into_inner()).take();
+
+        stream.unwrap_or_else(|| Box::pin(stream::empty()))
+    }
+
+    fn content_length(&self) -> Option<u64> {
+        self.content_length
+    }
+
+    fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+        Box::new(self)
+    }
+}
+
+/// true if the header is not forwarded, being hop-by-hop or named by `Connection`.
+fn is_hop_by_hop(name: &str, connection: &[String]) -> bool {
+    HOP_BY_HOP.iter().any(|hop| hop.eq_ignore_ascii_case(name))
+        || connection
+            .iter()
+            .any(|listed| listed.eq_ignore_ascii_case(name))
+}
+
+/// the tokens of a `Connection` header.
+fn connection_tokens(connection: Option<&str>) -> Vec<String> {
+    connection
+        .map(|value| value.split(',').map(|t| t.trim().to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// the head of the request sent upstream.
+fn request_head(upstream: &Upstream, req: &Request) -> String {
+    let trusted = req
+        .extensions
+        .get::<TrustedProxies>()
+        .is_some_and(|proxies| proxies.contains(req.client_socket.ip()));
+
+    let connection = connection_tokens(req.request_header("Connection").map(String::as_str));
+
+    let mut head = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\n",
+        req.method,
+        upstream.target(&req.route.init_route),
+        upstream.authority
+    );
+
+    let trace = req.extensions.get::<TraceContext>();
+
+    for (name, value) in &req.headers {
+        let replaced = ["Host", "Content-Length"]
+            .iter()
+            .chain(
+                [TRACEPARENT_HEADER, TRACESTATE_HEADER]
+                    .iter()
+                    .filter(|_| trace.is_some()),
+            )
+            .chain(
+                ["X-Forwarded-For", "X-Forwarded-Host", "X-Forwarded-Proto"]
+                    .iter()
+                    .filter(|_| !trusted),
+            )
+            .any(|n| n.eq_ignore_ascii_case(name));
+
+        if !replaced && !is_hop_by_hop(name, &connection) {
+            head.push_str(&format!("{name}: {value}\r\n"));
+        }
+    }
+
+    let forwarded_for = match (trusted, req.request_header("X-Forwarded-For")) {
+        (true, Some(chain)) => format!("{chain}, {}", req.client_socket.ip()),
+        _ => req.client_socket.ip().to_string(),
+    };
+
+    head.push_str(&format!("X-Forwarded-For: {forwarded_for}\r\n"));
+
+    if !(trusted && req.request_header("X-Forwarded-Host").is_some())
+        && let Some(host) = req.request_header("Host")
+    {
+        head.push_str(&format!("X-Forwarded-Host: {host}\r\n"));
+    }
+
+    if !(trusted && req.request_header("X-Forwarded-Proto").is_some()) {
+        head.push_str(&format!("X-Forwarded-Proto: {}\r\n", upstream.proto));
+    }
+
+    if let Some(trace) = trace {
+        head.push_str(&format!("{TRACEPARENT_HEADER}: {}\r\n", trace.traceparent()));
+
+        if let Some(state) = &trace.state {
+            head.push_str(&format!("{TRACESTATE_HEADER}: {state}\r\n"));
+        }
+    }
+
+    let length = req.body.as_ref().map_or(0, |body| body.len());
+
+    if length > 0 {
+        head.push_str(&format!("Content-Length: {length}\r\n"));
+    }
+
+    head.push_str("Connection: close\r\n\r\n");
+    head
+}
+
+/// sends the request and reads the response head, None if the upstream failed.
+async fn exchange(
+    upstream: &Upstream,
+    head: String,
+    body: Option<Vec<u8>>,
+) -> Option<ProxyResolution> {
+    let mut stream = TcpStream::connect(&upstream.authority).await.ok()?;
+
+    stream.write_all(head.as_bytes()).await.ok()?;
+
+    if let Some(body) = body {
+        stream.write_all(&body).await.ok()?;
+    }
+
+    let mut reader = BufReader::new(stream);
+
+    //interim responses, `100 Continue`, are skipped.
+    let (status, reason, fields) = loop {
+        let status_line = read_line(&mut reader).await?;
+        let mut parts = status_line.splitn(3, ' ');
+
+        let version = parts.next()?;
+        let status = parts.next()?.parse::<u16>().ok()?;
+        let reason = parts.next().unwrap_or_default().to_string();
+
+        if !version.starts_with("HTTP/1.") {
+            return None;
+        }
+
+        let mut fields = Vec::new();
+
+        loop {
+            let line = read_line(&mut reader).await?;
+
+            if line.is_empty() {
+                break;
+            }
+
+            let (name, value) = line.split_once(':')?;
+            fields.push((name.trim().to_string(), value.trim().to_string()));
+        }
+
+        if !(100..200).contains(&status) {
+            break (StatusCode::new(status).ok()?, reason, fields);
+        }
+    };
+
+    let field = |wanted: &str| {
+        fields
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(wanted))
+            .map(|(_, value)| value.as_str())
+    };
+
+    let chunked =
+        field("Transfer-Encoding").is_some_and(|te| te.to_ascii_lowercase().contains("chunked"));
+    let content_length = match chunked {
+        true => None,
+        false => field("Content-Length").and_then(|length| length.parse::<u64>().ok()),
+    };
+
+    let connection = connection_tokens(field("Connection"));
+
+    let mut headers = HeaderMap::new();
+    headers.set_status_reason(status, &reason);
+
+    for (name, value) in &fields {
+        if !name.eq_ignore_ascii_case("Content-Length") && !is_hop_by_hop(name, &connection) {
+            headers.append(name.clone(), Some(value.clone()));
+        }
+    }
+
+    let body: BodyStream = match chunked {
+        true => Box::pin(chunked_body(reader)),
+        false => Box::pin(raw_body(reader, content_length)),
+    };
+
+    Some(ProxyResolution {
+        headers,
+        content_length,
+        stream: Mutex::new(Some(body)),
+    })
+}
+
+/// reads a line of the response head without its line ending.
+async fn read_line<R: AsyncBufRead + Unpin>(reader: &mut R) -> Option<String> {
+    let mut line = Vec::new();
+    let read = (&mut *reader)
+        .take(MAX_LINE as u64)
+        .read_until(b'\n', &mut line)
+        .await
+        .ok()?;
+
+    if read == 0 || !line.ends_with(b"\n") {
+        return None;
+    }
+
+    let line = String::from_utf8(line).ok()?;
+    Some(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// the body of a response with a length, or until the upstream closes.
+fn raw_body<R>(mut reader: R, length: Option<u64>) -> impl Stream<Item = Vec<u8>> + Send
+where
+    R: AsyncBufRead + Unpin + Send + 'static,
+{
+    stream! {
+        let mut remaining = length.unwrap_or(u64::MAX);
+
+        while remaining > 0 {
+            let mut buffer = vec![0u8; 16 * 1024];
+            let limit = buffer.len().min(usize::try_from(remaining).unwrap_or(usize::MAX));
+
+            let read = match reader.read(&mut buffer[..limit]).await {
+                Ok(0) | Err(_) => break,
+                Ok(read) => read,
+            };
+
+            remaining -= read as u64;
+            buffer.truncate(read);
+            yield buffer;
+        }
+    }
+}
+
+/// the decoded body of a chunked response, the app chunks it again for the client.
+fn chunked_body<R>(mut reader: R) -> impl Stream<Item = Vec<u8>> + Send
+where
+    R: AsyncBufRead + Unpin + Send + 'static,
+{
+    stream! {
+        loop {
+            let Some(size_line) = read_line(&mut reader).await else {
+                break;
+            };
+
+            let size = size_line.split(';').next().unwrap_or_default().trim();
+
+            let Ok(size) = u64::from_str_radix(size, 16) else {
+                break;
+            };
+
+            if size == 0 {
+                break;
+            }
+
+            //large chunks are read in parts, not allocated at once.
+            let mut remaining = size;
+
+            while remaining > 0 {
+                let mut part = vec![0u8; (16 * 1024).min(usize::try_from(remaining).unwrap_or(usize::MAX))];
+
+                if reader.read_exact(&mut part).await.is_err() {
+                    return;
+                }
+
+                remaining -= part.len() as u64;
+                yield part;
+            }
+
+            //the line ending after the chunk.
+            if read_line(&mut reader).await.is_none() {
+                break;
+            }
+        }
+    }
+}
@@ -1,11 +1,13 @@
-use linked_hash_map::LinkedHashMap;
+use std::borrow::Cow;
 
 use crate::web::{
-    Resolution,
-    resolution::{empty_content, get_status_header},
+    Resolution, StatusCode,
+    headers::HeaderMap,
+    resolution::empty_content,
 };
 
-pub type Location = &'static str;
+/// The url of a redirect, a `&'static str` or a `String` built at runtime such as `format!("/users/{id}")`.
+pub type Location = Cow<'static, str>;
 
 /// Redirect Types
 ///
@@ -16,8 +18,8 @@ pub enum RedirectType {
     ///
     /// See: https://developer.mozilla.org/en-US/docs/Web/HTTP/Reference/Status/300
     ///
-    /// # NOT YET IMPLEMENTED
-    MultipleChoices, //TODO: This needs a specific struct to specify other locations. This may not even be implemented since it is 'rare' according to mozilla.
+    /// The alternates are sent as a `text/uri-list` body, the first is the preferred choice and is also sent as the `Location`.
+    MultipleChoices(Vec<Location>),
 
     /// The resource has been moved permanently.
     ///
@@ -56,15 +58,15 @@ pub enum RedirectType {
 
 impl RedirectType {
     /// the status of the redirection type 300, etc...
-    fn status(&self) -> i32 {
+    fn status(&self) -> StatusCode {
         match self {
-            RedirectType::MultipleChoices => 300,
-            RedirectType::MovedPermanently(_) => 301,
-            RedirectType::Found(_) => 302,
-            RedirectType::SeeOther(_) => 303,
-            RedirectType::NotModified => 304,
-            RedirectType::TemporaryRedirect(_) => 307,
-            RedirectType::PermanentRedirect(_) => 308,
+            RedirectType::MultipleChoices(_) => StatusCode::MULTIPLE_CHOICES,
+            RedirectType::MovedPermanently(_) => StatusCode::MOVED_PERMANENTLY,
+            RedirectType::Found(_) => StatusCode::FOUND,
+            RedirectType::SeeOther(_) => StatusCode::SEE_OTHER,
+            RedirectType::NotModified => StatusCode::NOT_MODIFIED,
+            RedirectType::TemporaryRedirect(_) => StatusCode::TEMPORARY_REDIRECT,
+            RedirectType::PermanentRedirect(_) => StatusCode::PERMANENT_REDIRECT,
         }
     }
 
@@ -74,8 +76,8 @@ impl RedirectType {
         match self {
             RedirectType::NotModified => 0, //this is more for caching, just letting the browser something has not modified since XYZ
 
-            //TODO implement the multiple choices.
-            RedirectType::MultipleChoices => todo!(),
+            //the preferred location and the content type of the alternates.
+            RedirectType::MultipleChoices(_) => 2,
 
             //the rest of the current implement the Location: header.
             _ => 1,
@@ -94,34 +96,84 @@ impl Redirect {
             redirect_header_type: redirect_type,
         }
     }
+
+    /// `301`, the resource has moved permanently.
+    pub fn moved_permanently(url: impl Into<Location>) -> Self {
+        Self::new(RedirectType::MovedPermanently(url.into()))
+    }
+
+    /// `302`, the resource is temporarily at another url.
+    pub fn found(url: impl Into<Location>) -> Self {
+        Self::new(RedirectType::Found(url.into()))
+    }
+
+    /// # See Other
+    ///
+    /// `303`, the client should GET another url, such as the resource created by a form.
+    ///
+    /// ```ignore
+    /// return Redirect::see_other(format!("/users/{id}")).resolve();
+    /// ```
+    pub fn see_other(url: impl Into<Location>) -> Self {
+        Self::new(RedirectType::SeeOther(url.into()))
+    }
+
+    /// `307`, the resource is temporarily at another url, the method and body are kept.
+    pub fn temporary(url: impl Into<Location>) -> Self {
+        Self::new(RedirectType::TemporaryRedirect(url.into()))
+    }
+
+    /// `308`, the resource has moved permanently, the method and body are kept.
+    pub fn permanent(url: impl Into<Location>) -> Self {
+        Self::new(RedirectType::PermanentRedirect(url.into()))
+    }
+
+    /// `300`, the resource is available at several urls, the first is preferred.
+    pub fn multiple_choices<L: Into<Location>>(alternates: impl IntoIterator<Item = L>) -> Self {
+        Self::new(RedirectType::MultipleChoices(
+            alternates.into_iter().map(Into::into).collect(),
+        ))
+    }
+
+    /// the alternates of multiple choices, one url per line, nothing for other redirects.
+    fn body(&self) -> Vec<u8> {
+        match &self.redirect_header_type {
+            RedirectType::MultipleChoices(alternates) => alternates
+                .iter()
+                .map(|url| format!("{url}\r\n"))
+                .collect::<String>()
+                .into_bytes(),
+            _ => empty_content(),
+        }
+    }
 }
 
 //formats the url into a Location: Url header.
-fn location_header(url: Location) -> (String, String) {
+fn location_header(url: &Location) -> (String, String) {
     ("Location".to_string(), url.to_string())
 }
 
 impl Resolution for Redirect {
     //sets the header for the redirection!
-    fn get_headers(&self) -> LinkedHashMap<String, Option<String>> {
-        let mut hmap = LinkedHashMap::<String, Option<String>>::with_capacity(
-            1 + self.redirect_header_type.size(),
-        );
+    fn get_headers(&self) -> HeaderMap {
+        let mut hmap = HeaderMap::with_capacity(1 + self.redirect_header_type.size());
 
-        let (n, v) = get_status_header(self.redirect_header_type.status());
-        hmap.insert(n, Some(v));
+        hmap.set_status(self.redirect_header_type.status());
 
         //subject to change
-        let redir_headers: Option<(String, String)> = match self.redirect_header_type {
+        let redir_headers: Option<(String, String)> = match &self.redirect_header_type {
             //just use the location header.
             RedirectType::MovedPermanently(url) => Some(location_header(url)),
             RedirectType::Found(url) => Some(location_header(url)),
-            RedirectType::SeeOther(url) => Some(location_header(url).into()),
+            RedirectType::SeeOther(url) => Some(location_header(url)),
             RedirectType::PermanentRedirect(url) => Some(location_header(url)),
             RedirectType::TemporaryRedirect(url) => Some(location_header(url)),
 
-            //TODO: Implement the multiple choices.
-            RedirectType::MultipleChoices => todo!(),
+            //the alternates are the body, the first is preferred.
+            RedirectType::MultipleChoices(alternates) => {
+                hmap.set_content_type("text/uri-list; charset=utf-8");
+                alternates.first().map(location_header)
+            }
             RedirectType::NotModified => None,
         };
 
@@ -134,7 +186,11 @@ impl Resolution for Redirect {
     }
 
     fn get_content(&self) -> std::pin::Pin<Box<dyn futures::Stream<Item = Vec<u8>> + Send>> {
-        Box::pin(tokio_stream::once(empty_content()))
+        Box::pin(tokio_stream::once(self.body()))
+    }
+
+    fn content_length(&self) -> Option<u64> {
+        Some(self.body().len() as u64)
     }
 
     fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
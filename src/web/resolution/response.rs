@@ -0,0 +1,173 @@
+use std::pin::Pin;
+
+use futures::{Stream, stream};
+use serde::Serialize;
+
+use crate::web::{
+    Resolution, StatusCode, headers::HeaderMap,
+    resolution::attachment_resolution::content_disposition,
+};
+
+/// ## Response
+///
+/// Implementation of the Resolution trait.
+///
+/// A response with any status, headers, and body held in memory, built with [`Response::builder`] when none of the other resolutions fit.
+///
+/// ## Example
+///
+/// ```ignore
+/// return Response::builder()
+///     .status(201)
+///     .header("Location", &format!("/users/{id}"))
+///     .json(&user)
+///     .resolve();
+/// ```
+pub struct Response {
+    status_code: StatusCode,
+    reason: Option<String>,
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+impl Response {
+    /// Start building a `200` response without headers.
+    pub fn builder() -> ResponseBuilder {
+        ResponseBuilder {
+            status_code: StatusCode::OK,
+            reason: None,
+            headers: HeaderMap::new(),
+        }
+    }
+
+    /// The status code of the response.
+    pub fn status_code(&self) -> StatusCode {
+        self.status_code
+    }
+
+    /// The body of the response.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+}
+
+/// # Response Builder
+///
+/// Sets the status and headers of a [`Response`], finished by giving it a body with `text`, `bytes`, `json`, or `empty`.
+///
+/// The body sets the `Content-Type` unless a `Content-Type` header was given.
+pub struct ResponseBuilder {
+    status_code: StatusCode,
+    reason: Option<String>,
+    headers: HeaderMap,
+}
+
+impl ResponseBuilder {
+    /// Sets the status code.
+    pub fn status(mut self, status_code: impl Into<StatusCode>) -> Self {
+        self.status_code = status_code.into();
+        self
+    }
+
+    /// Sends a custom reason phrase with the status, see `StatusCode::with_reason`.
+    pub fn reason(mut self, reason: &str) -> Self {
+        self.reason = Some(reason.to_string());
+        self
+    }
+
+    /// Sets a header, replacing a header of the same name.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(name, Some(value.to_string()));
+        self
+    }
+
+    /// Adds a value to a header, keeping its other values, such as several `Set-Cookie`.
+    pub fn append_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.append(name, Some(value.to_string()));
+        self
+    }
+
+    /// Sends the response as a download named `filename`, see `attachment_resolution::content_disposition`.
+    pub fn attachment(self, filename: &str) -> Self {
+        self.header("Content-Disposition", &content_disposition(filename))
+    }
+
+    /// Finish with a text body, sent as `text/plain; charset=utf-8`.
+    pub fn text(self, text: &str) -> Response {
+        self.finish("text/plain; charset=utf-8", text.as_bytes().to_vec())
+    }
+
+    /// Finish with a body of bytes, sent as `application/octet-stream`.
+    pub fn bytes(self, body: Vec<u8>) -> Response {
+        self.finish("application/octet-stream", body)
+    }
+
+    /// # Json
+    ///
+    /// Finish with a body serialized to JSON, sent as `application/json`.
+    ///
+    /// A value that fails to serialize gives a `500` whose body is the error.
+    pub fn json<T: Serialize + ?Sized>(self, body: &T) -> Response {
+        match serde_json::to_vec(body) {
+            Ok(body) => self.finish("application/json", body),
+            Err(e) => Response::builder().status(StatusCode::INTERNAL_SERVER_ERROR).text(&e.to_string()),
+        }
+    }
+
+    /// Finish without a body.
+    pub fn empty(self) -> Response {
+        Response {
+            status_code: self.status_code,
+            reason: self.reason,
+            headers: self.headers,
+            body: Vec::new(),
+        }
+    }
+
+    fn finish(mut self, content_type: &str, body: Vec<u8>) -> Response {
+        if !self.headers.contains("Content-Type") {
+            self.headers.set_content_type(content_type);
+        }
+
+        Response {
+            status_code: self.status_code,
+            reason: self.reason,
+            headers: self.headers,
+            body,
+        }
+    }
+}
+
+impl Resolution for Response {
+    fn get_headers(&self) -> HeaderMap {
+        let mut hmap = self.headers.clone();
+
+        match &self.reason {
+            Some(reason) => hmap.set_status_reason(self.status_code, reason),
+            None => hmap.set_status(self.status_code),
+        }
+
+        hmap
+    }
+
+    fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send + 'static>> {
+        let body = self.body.clone();
+
+        Box::pin(stream::once(async move { body }))
+    }
+
+    fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+        Box::new(self)
+    }
+
+    //held in memory, ranges are cheap.
+    fn seekable_len(&self) -> Option<u64> {
+        Some(self.body.len() as u64)
+    }
+
+    fn seek(&self, offset: u64) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        let body = self.body[(offset as usize).min(self.body.len())..].to_vec();
+
+        Box::pin(stream::once(async move { body }))
+    }
+}
@@ -0,0 +1,158 @@
+use std::pin::Pin;
+
+use futures::Stream;
+
+use crate::web::{
+    Resolution, StatusCode,
+    headers::HeaderMap,
+    resolution::{empty_resolution::EmptyResolution, redirect::Location},
+};
+
+/// ## Created
+///
+/// Implementation of the Resolution trait.
+///
+/// `201 Created` with the `Location` of the new resource, and optionally a body describing it.
+///
+/// ## Example
+///
+/// ```ignore
+/// return Created::at(format!("/users/{id}"))
+///     .with_body(JsonResolution::serialize(&user)?.resolve())
+///     .resolve();
+/// ```
+pub struct Created {
+    location: Location,
+    body: Box<dyn Resolution + Send + 'static>,
+}
+
+impl Created {
+    /// `201` with the `Location` of the new resource, without a body.
+    pub fn at(location: impl Into<Location>) -> Self {
+        Self {
+            location: location.into(),
+            body: EmptyResolution::status(StatusCode::CREATED).resolve(),
+        }
+    }
+
+    /// Sends the headers and content of the resolution as the body, with `201` as the status.
+    pub fn with_body(mut self, body: Box<dyn Resolution + Send + 'static>) -> Self {
+        self.body = body;
+        self
+    }
+}
+
+impl Resolution for Created {
+    fn get_headers(&self) -> HeaderMap {
+        let mut hmap = self.body.get_headers();
+
+        hmap.set_status(StatusCode::CREATED);
+        hmap.set_location(&self.location);
+
+        hmap
+    }
+
+    fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        self.body.get_content()
+    }
+
+    fn content_length(&self) -> Option<u64> {
+        self.body.content_length()
+    }
+
+    fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+        Box::new(self)
+    }
+}
+
+/// ## No Content
+///
+/// Implementation of the Resolution trait.
+///
+/// `204 No Content`, the request succeeded and there is nothing to send. The app writes neither a body nor its length.
+pub struct NoContent;
+
+impl Resolution for NoContent {
+    fn get_headers(&self) -> HeaderMap {
+        let mut hmap = HeaderMap::new();
+
+        hmap.set_status(StatusCode::NO_CONTENT);
+
+        hmap
+    }
+
+    fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        Box::pin(futures::stream::empty())
+    }
+
+    fn content_length(&self) -> Option<u64> {
+        Some(0)
+    }
+
+    fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+        Box::new(self)
+    }
+}
+
+/// ## Accepted
+///
+/// Implementation of the Resolution trait.
+///
+/// `202 Accepted`, the request was queued to be processed later, such as a job.
+///
+/// ## Example
+///
+/// ```ignore
+/// let job = jobs.enqueue(body).await;
+///
+/// return Accepted::with_status_url(format!("/jobs/{}", job.id)).resolve();
+/// ```
+pub struct Accepted {
+    status_url: Option<Location>,
+}
+
+impl Accepted {
+    /// `202` without a way to follow the request.
+    pub fn new() -> Self {
+        Self { status_url: None }
+    }
+
+    /// `202` with the url the client may poll for the status of the request, sent as the `Location`.
+    pub fn with_status_url(status_url: impl Into<Location>) -> Self {
+        Self {
+            status_url: Some(status_url.into()),
+        }
+    }
+}
+
+impl Default for Accepted {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resolution for Accepted {
+    fn get_headers(&self) -> HeaderMap {
+        let mut hmap = HeaderMap::new();
+
+        hmap.set_status(StatusCode::ACCEPTED);
+
+        if let Some(status_url) = &self.status_url {
+            hmap.set_location(status_url);
+        }
+
+        hmap
+    }
+
+    fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        Box::pin(futures::stream::empty())
+    }
+
+    fn content_length(&self) -> Option<u64> {
+        Some(0)
+    }
+
+    fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+        Box::new(self)
+    }
+}
@@ -0,0 +1,131 @@
+use std::pin::Pin;
+
+use futures::{Stream, stream};
+
+use crate::web::{Resolution, StatusCode, headers::HeaderMap};
+
+/// ## Text Resolution
+///
+/// Implementation of the Resolution trait.
+///
+/// Sends a body held in memory with a status and content type, text is sent as `text/plain; charset=utf-8` and bytes as `application/octet-stream`.
+///
+/// ## Example
+///
+/// ```ignore
+/// let mut text = TextResolution::new("hello");
+/// text.set_status(202);
+///
+/// return text.resolve();
+/// ```
+pub struct TextResolution {
+    body: Vec<u8>,
+    content_type: String,
+    status_code: StatusCode,
+}
+
+impl TextResolution {
+    /// Create a `200` text resolution.
+    pub fn new(text: &str) -> Self {
+        Self {
+            body: text.as_bytes().to_vec(),
+            content_type: "text/plain; charset=utf-8".to_string(),
+            status_code: StatusCode::OK,
+        }
+    }
+
+    /// Create a `200` resolution of bytes.
+    pub fn bytes(body: Vec<u8>) -> Self {
+        Self {
+            body,
+            content_type: "application/octet-stream".to_string(),
+            status_code: StatusCode::OK,
+        }
+    }
+
+    /// Set the status code of the resolution.
+    pub fn set_status(&mut self, status_code: impl Into<StatusCode>) {
+        self.status_code = status_code.into();
+    }
+
+    /// Set the `Content-Type` of the resolution.
+    pub fn set_content_type(&mut self, content_type: &str) {
+        self.content_type = content_type.to_string();
+    }
+}
+
+impl Resolution for TextResolution {
+    fn get_headers(&self) -> HeaderMap {
+        let mut hmap = HeaderMap::new();
+
+        hmap.set_status(self.status_code);
+        hmap.set_content_type(&self.content_type);
+
+        hmap
+    }
+
+    fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send + 'static>> {
+        let body = self.body.clone();
+
+        Box::pin(stream::once(async move { body }))
+    }
+
+    fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+        Box::new(self)
+    }
+
+    //held in memory, ranges are cheap.
+    fn seekable_len(&self) -> Option<u64> {
+        Some(self.body.len() as u64)
+    }
+
+    fn seek(&self, offset: u64) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        let body = self.body[(offset as usize).min(self.body.len())..].to_vec();
+
+        Box::pin(stream::once(async move { body }))
+    }
+}
+
+/// # Status Resolution
+///
+/// Replaces the status of another resolution, see the `(i32, T)` implementation of `IntoResolution`.
+pub struct StatusResolution {
+    status_code: StatusCode,
+    inner: Box<dyn Resolution + Send + 'static>,
+}
+
+impl StatusResolution {
+    /// Sends the resolution with the status instead of its own.
+    pub fn new(status_code: impl Into<StatusCode>, inner: Box<dyn Resolution + Send + 'static>) -> Self {
+        Self {
+            status_code: status_code.into(),
+            inner,
+        }
+    }
+}
+
+impl Resolution for StatusResolution {
+    fn get_headers(&self) -> HeaderMap {
+        let mut hmap = self.inner.get_headers();
+
+        hmap.set_status(self.status_code);
+
+        hmap
+    }
+
+    fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send + 'static>> {
+        self.inner.get_content()
+    }
+
+    fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+        Box::new(self)
+    }
+
+    fn seekable_len(&self) -> Option<u64> {
+        self.inner.seekable_len()
+    }
+
+    fn seek(&self, offset: u64) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        self.inner.seek(offset)
+    }
+}
@@ -0,0 +1,236 @@
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::{Stream, stream};
+use tokio::time::Instant;
+
+use crate::web::{
+    Request, Resolution,
+    headers::{HeaderMap, STATUS_KEY},
+};
+
+/// # Cached Response
+///
+/// A response kept by a [`ResponseCache`], sent with its `Age` in seconds.
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    status: String,
+    headers: HeaderMap,
+    body: Arc<Vec<u8>>,
+    stored: Instant,
+    expires: Instant,
+}
+
+impl Resolution for CachedResponse {
+    fn get_headers(&self) -> HeaderMap {
+        let mut headers = self.headers.clone();
+
+        headers.insert(STATUS_KEY, Some(self.status.clone()));
+        headers.insert("Age", Some(self.stored.elapsed().as_secs().to_string()));
+
+        headers
+    }
+
+    fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send + 'static>> {
+        let body = self.body.to_vec();
+
+        Box::pin(stream::once(async move { body }))
+    }
+
+    fn content_length(&self) -> Option<u64> {
+        Some(self.body.len() as u64)
+    }
+
+    fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+        Box::new(self)
+    }
+}
+
+/// # Response Cache
+///
+/// An in-memory cache of the responses of a route, for expensive endpoints such as image captioning, see `App::cache_route`.
+///
+/// Fresh responses are sent before middleware and the handler are called, so only cache routes whose middleware may be skipped, such as public content.
+///
+/// * only `GET` responses with `200` are kept, responses setting cookies or with `Cache-Control: no-store` or `private` are not
+/// * responses are keyed by the method, the path with its query, and the `vary` headers of the request
+/// * responses are kept for `ttl`, at most `max_entries` are kept, and bodies larger than `max_body` are not kept
+///
+/// Cloning shares the same cache.
+///
+/// ```ignore
+/// let cache = ResponseCache::new(Duration::from_secs(300))
+///     .max_entries(500)
+///     .vary(&["Accept-Language"]);
+///
+/// app.cache_route("/alt", Method::GET, cache).await?;
+/// ```
+#[derive(Clone)]
+pub struct ResponseCache {
+    ttl: Duration,
+    max_entries: usize,
+    max_body: usize,
+    vary: Arc<Vec<String>>,
+    entries: Arc<Mutex<HashMap<String, CachedResponse>>>,
+}
+
+impl ResponseCache {
+    /// Create a cache keeping responses for the ttl, at most 1,000 responses of at most 1 MiB each.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            max_entries: 1000,
+            max_body: 1024 * 1024,
+            vary: Arc::new(Vec::new()),
+            entries: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// The most responses that are kept.
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// The largest body that is kept, in bytes.
+    pub fn max_body(mut self, max_body: usize) -> Self {
+        self.max_body = max_body;
+        self
+    }
+
+    /// The request headers that select different responses, such as `Accept-Encoding`.
+    pub fn vary(mut self, headers: &[&str]) -> Self {
+        self.vary = Arc::new(headers.iter().map(|h| h.to_string()).collect());
+        self
+    }
+
+    /// The amount of responses kept, including expired responses that were not dropped yet.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// True if no responses are kept.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops every response.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    /// # Key
+    ///
+    /// The key of the response of a request, its method, path and query, and the values of the `vary` headers.
+    pub fn key(&self, request: &Request) -> String {
+        let mut key = format!("{} {}", request.method, request.route.init_route);
+
+        for header in self.vary.iter() {
+            key.push('\0');
+            key.push_str(request.request_header(header).map(String::as_str).unwrap_or_default());
+        }
+
+        key
+    }
+
+    /// The fresh response of the key, None if it is missing or expired.
+    pub fn get(&self, key: &str) -> Option<CachedResponse> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+
+        match entries.get(key) {
+            Some(response) if response.expires > Instant::now() => Some(response.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// # Claim
+    ///
+    /// A claim on the key, completed by the app with the response of the request.
+    pub fn claim(&self, key: String) -> ResponseCacheClaim {
+        ResponseCacheClaim {
+            cache: self.clone(),
+            key,
+        }
+    }
+
+    fn insert(&self, key: String, response: CachedResponse) {
+        if self.max_entries == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+
+        if entries.len() >= self.max_entries && !entries.contains_key(&key) {
+            entries.retain(|_, response| response.expires > now);
+        }
+
+        if entries.len() >= self.max_entries && !entries.contains_key(&key) {
+            let soonest = entries
+                .iter()
+                .min_by_key(|(_, response)| response.expires)
+                .map(|(k, _)| k.clone());
+
+            if let Some(soonest) = soonest {
+                entries.remove(&soonest);
+            }
+        }
+
+        entries.insert(key, response);
+    }
+}
+
+/// # Response Cache Claim
+///
+/// The key a response is kept under, placed in the request extensions by the app when a cached route misses.
+pub struct ResponseCacheClaim {
+    cache: ResponseCache,
+    key: String,
+}
+
+impl ResponseCacheClaim {
+    /// The largest body that is kept.
+    pub fn max_body(&self) -> usize {
+        self.cache.max_body
+    }
+
+    /// # Complete
+    ///
+    /// Keeps the response, unless it is not a `200`, sets cookies, is not to be stored, or its body was over the limit.
+    pub fn complete(self, status: &str, headers: HeaderMap, body: Option<Vec<u8>>) {
+        let Some(body) = body else {
+            return;
+        };
+
+        let cache_control = headers.get("Cache-Control").unwrap_or_default();
+
+        if !status.starts_with("200")
+            || headers.contains("Set-Cookie")
+            || cache_control.contains("no-store")
+            || cache_control.contains("private")
+        {
+            return;
+        }
+
+        let now = Instant::now();
+
+        self.cache.insert(
+            self.key,
+            CachedResponse {
+                status: status.to_string(),
+                headers,
+                body: Arc::new(body),
+                stored: now,
+                expires: now + self.cache.ttl,
+            },
+        );
+    }
+}
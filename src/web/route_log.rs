@@ -0,0 +1,153 @@
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use tokio::sync::Mutex;
+
+use crate::web::{Method, Resolution, resolution::json_resolution::JsonResolution};
+
+tokio::task_local! {
+    static ACTOR: String;
+}
+
+/// the actor of changes made outside of `as_actor`.
+const DEFAULT_ACTOR: &str = "app";
+
+/// # Route Change Kind
+///
+/// How the routes were changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum RouteChangeKind {
+    /// A single route was added or replaced.
+    Route,
+    /// A route table was loaded, see `App::load_route_table`.
+    Table,
+    /// Every route was replaced, see `App::swap_router`.
+    Swap,
+}
+
+/// # Route Change
+///
+/// A single change of the routes of an app, each route is written as `METHOD /pattern`.
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteChange {
+    /// Seconds since the unix epoch.
+    pub timestamp: u64,
+    /// Who made the change, see [`RouteChangeLog::as_actor`].
+    pub actor: String,
+    /// What made the change, a single route, a loaded table, or a swapped router.
+    pub kind: RouteChangeKind,
+    /// Routes that did not exist before.
+    pub added: Vec<String>,
+    /// Routes whose resolution or settings were replaced.
+    pub replaced: Vec<String>,
+    /// Routes that no longer exist.
+    pub removed: Vec<String>,
+}
+
+/// # Route Change Log
+///
+/// An audit trail of the changes to the routes of an app, so dynamic gateways can explain their current routing state.
+///
+/// The app records every change, keeping the last `max_changes`. Retrieve it with `App::route_changes` or serve it with `App::serve_route_changes`.
+///
+/// Cloning shares the same log.
+#[derive(Clone)]
+pub struct RouteChangeLog {
+    max_changes: usize,
+    changes: Arc<Mutex<VecDeque<RouteChange>>>,
+}
+
+impl RouteChangeLog {
+    /// Create a log keeping the last `max_changes` changes.
+    pub fn new(max_changes: usize) -> Self {
+        Self {
+            max_changes,
+            changes: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// # As Actor
+    ///
+    /// Attributes route changes made by the future to the actor, for example the user of an admin API or a deploy.
+    ///
+    /// ```ignore
+    /// RouteChangeLog::as_actor("deploy 4f2a1c", async {
+    ///     app.load_route_table(&table, &registry).await
+    /// })
+    /// .await?;
+    /// ```
+    pub async fn as_actor<F: Future>(actor: &str, future: F) -> F::Output {
+        ACTOR.scope(actor.to_string(), future).await
+    }
+
+    /// # Record
+    ///
+    /// Records a change, attributed to the current actor.
+    pub async fn record(
+        &self,
+        kind: RouteChangeKind,
+        added: Vec<String>,
+        replaced: Vec<String>,
+        removed: Vec<String>,
+    ) {
+        if self.max_changes == 0 || (added.is_empty() && replaced.is_empty() && removed.is_empty())
+        {
+            return;
+        }
+
+        let change = RouteChange {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            actor: ACTOR
+                .try_with(|actor| actor.clone())
+                .unwrap_or_else(|_| DEFAULT_ACTOR.to_string()),
+            kind,
+            added,
+            replaced,
+            removed,
+        };
+
+        let mut changes = self.changes.lock().await;
+
+        if changes.len() >= self.max_changes {
+            changes.pop_front();
+        }
+
+        changes.push_back(change);
+    }
+
+    /// The recorded changes, oldest first.
+    pub async fn changes(&self) -> Vec<RouteChange> {
+        self.changes.lock().await.iter().cloned().collect()
+    }
+
+    /// # Resolution
+    ///
+    /// A JSON resolution of the changes.
+    pub async fn resolution(&self) -> Box<dyn Resolution + Send + 'static> {
+        match JsonResolution::serialize(self.changes().await) {
+            Ok(json) => json.resolve(),
+            Err(e) => e.resolve(),
+        }
+    }
+}
+
+impl Default for RouteChangeLog {
+    /// A log keeping the last 1,000 changes.
+    fn default() -> Self {
+        Self::new(1000)
+    }
+}
+
+/// # Describe Route
+///
+/// Writes a route as `METHOD /pattern`.
+pub fn describe_route(method: &Method, route: &str) -> String {
+    format!("{method} {route}")
+}
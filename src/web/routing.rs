@@ -1,20 +1,25 @@
+pub mod extensions;
 pub mod method;
 pub mod middleware;
 pub mod request;
 pub mod route;
+pub mod route_names;
 pub mod router;
+pub mod scope;
 
 pub use super::resolution::Resolution;
+pub use extensions::Extensions;
 pub use method::Method;
 pub use middleware::Middleware;
 pub use request::Request;
-pub use route::Route;
+pub use route::{MatchedRoute, Route};
+pub use scope::Scope;
 
 use std::{pin::Pin, sync::Arc};
 
 use tokio::sync::Mutex;
 
-use crate::web::{routing::router::route_node::RouteNode};
+use crate::web::{resolution::IntoResolution, routing::router::route_node::RouteNode};
 
 //highest
 /// # Resolution Future
@@ -29,23 +34,32 @@ use crate::web::{routing::router::route_node::RouteNode};
 /// ```
 pub type ResolutionFuture = dyn Future<Output = Box<dyn Resolution + Send + 'static>> + Send;
 
+/// # Request Future
+///
+/// A boxed future that may borrow the request it was given, see `ResolutionFn` and `MiddlewareRequest`.
+pub type RequestFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
 /// # Resolution Function (FN)
-/// 
+///
+/// The request is lent to the resolution for as long as it runs, each request is handled by one task so no lock is needed.
+///
 /// ```
 /// //although this is not valid code...
-/// let res_fut: ResolutionFn = |req: Arc<Mutex<Request>>| {
+/// let res_fut: ResolutionFn = |req: &mut Request| {
 ///     Box::pin(async move {
 ///         EmptyResolution::status(200).resolve()
 ///     })
 /// };
 /// ```
-pub type ResolutionFn =
-    dyn Fn(Arc<Mutex<Request>>) -> Pin<Box<ResolutionFuture>> + Send + Sync + 'static;
+pub type ResolutionFn = dyn for<'a> Fn(&'a mut Request) -> RequestFuture<'a, Box<dyn Resolution + Send + 'static>>
+    + Send
+    + Sync
+    + 'static;
 
 /// # Resolution Function (FN) Ref
 /// 
 /// ```
-/// let res_fut: ResolutionFnRef = Arc::new(|req: Arc<Mutex<Request>>| {
+/// let res_fut: ResolutionFnRef = resolution_fn(|req| {
 ///     Box::pin(async move {
 ///         EmptyResolution::status(200).resolve()
 ///     })
@@ -54,4 +68,45 @@ pub type ResolutionFn =
 /// ```
 pub type ResolutionFnRef = Arc<ResolutionFn>;
 
+/// # Resolution Fn
+///
+/// Converts a handler borrowing the request, whose output is any `IntoResolution`, into a `ResolutionFnRef`.
+///
+/// ```ignore
+/// let hello = resolution_fn(|req| Box::pin(async move {
+///     req.add_header("X-Hello".to_string(), Some("world".to_string()));
+///     status(200).resolve()
+/// }));
+/// ```
+pub fn resolution_fn<F, R>(handler: F) -> ResolutionFnRef
+where
+    F: for<'a> Fn(&'a mut Request) -> RequestFuture<'a, R> + Send + Sync + 'static,
+    R: IntoResolution + 'static,
+{
+    Arc::new(move |req: &mut Request| {
+        let future = handler(req);
+
+        Box::pin(async move { future.await.into_resolution() })
+    })
+}
+
+/// # Resolution Fn Ref
+///
+/// Converts a handler taking a shared request, whose output is any `IntoResolution`, into a `ResolutionFnRef`.
+///
+/// Kept for handlers written against `Arc<Mutex<Request>>`, see `Request::shared`, prefer `resolution_fn`.
+pub fn resolution_fn_ref<F, Fut>(handler: F) -> ResolutionFnRef
+where
+    F: Fn(Arc<Mutex<Request>>) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output: IntoResolution> + Send + 'static,
+{
+    let handler = Arc::new(handler);
+
+    Arc::new(move |req: &mut Request| {
+        let handler = handler.clone();
+
+        Box::pin(async move { req.shared(|req| async move { handler(req).await.into_resolution() }).await })
+    })
+}
+
 pub type RouteNodeRef = Arc<Mutex<RouteNode>>;
@@ -0,0 +1,70 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+};
+
+/// # Extensions
+///
+/// A type map attached to each request, allowing middleware to hand typed values to handlers.
+///
+/// Only one value may exist per type, wrapping values in a newtype is recommended.
+///
+/// ## Example
+///
+/// ```ignore
+/// struct UserId(u64);
+///
+/// //in middleware
+/// req.lock().await.extensions.insert(UserId(42));
+///
+/// //in the handler
+/// let id = req.lock().await.extensions.get::<UserId>().map(|u| u.0);
+/// ```
+#[derive(Default)]
+pub struct Extensions {
+    map: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl Extensions {
+    /// Create an empty extension map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Insert
+    ///
+    /// Inserts a value, returning the previous value of the same type if one existed.
+    pub fn insert<T: Send + Sync + 'static>(&mut self, value: T) -> Option<T> {
+        self.map
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|prev| prev.downcast::<T>().ok())
+            .map(|prev| *prev)
+    }
+
+    /// Get a reference to the value of type T.
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.map
+            .get(&TypeId::of::<T>())
+            .and_then(|v| v.downcast_ref::<T>())
+    }
+
+    /// Get a mutable reference to the value of type T.
+    pub fn get_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut T> {
+        self.map
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|v| v.downcast_mut::<T>())
+    }
+
+    /// Remove and return the value of type T.
+    pub fn remove<T: Send + Sync + 'static>(&mut self) -> Option<T> {
+        self.map
+            .remove(&TypeId::of::<T>())
+            .and_then(|v| v.downcast::<T>().ok())
+            .map(|v| *v)
+    }
+
+    /// Returns true if a value of type T exist.
+    pub fn contains<T: Send + Sync + 'static>(&self) -> bool {
+        self.map.contains_key(&TypeId::of::<T>())
+    }
+}
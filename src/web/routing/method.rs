@@ -38,3 +38,31 @@ impl std::fmt::Display for Method {
         write!(f, "{m}")
     }
 }
+
+impl Method {
+    /// The name of the method as it is sent in a request line, `OPTIONS` for `Other("OPTIONS")`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::GET => "GET",
+            Self::POST => "POST",
+            Self::PUT => "PUT",
+            Self::DELETE => "DELETE",
+            Self::PATCH => "PATCH",
+            Self::Other(name) => name,
+        }
+    }
+}
+
+impl From<&str> for Method {
+    /// Parses a method name, unknown methods are parsed as Other.
+    fn from(value: &str) -> Self {
+        match value.to_uppercase().as_str() {
+            "GET" => Self::GET,
+            "PUT" => Self::PUT,
+            "POST" => Self::POST,
+            "DELETE" => Self::DELETE,
+            "PATCH" => Self::PATCH,
+            other => Self::Other(other.to_string()),
+        }
+    }
+}
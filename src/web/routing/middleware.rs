@@ -1,13 +1,12 @@
-use tokio::sync::Mutex;
+use crate::web::{Request, Resolution, StatusCode, routing::RequestFuture};
 
-use crate::web::{Request, Resolution};
-
-use std::{pin::Pin, sync::Arc};
+use std::{collections::HashMap, sync::Arc};
 
 pub type MiddlewareFuture = dyn Future<Output = Middleware> + Send;
 
+/// The request is lent to the middleware for as long as it runs.
 pub type MiddlewareRequest =
-    dyn Fn(Arc<Mutex<Request>>) -> Pin<Box<MiddlewareFuture>> + Send + Sync + 'static;
+    dyn for<'a> Fn(&'a mut Request) -> RequestFuture<'a, Middleware> + Send + Sync + 'static;
 
 /// Describes an async function that takes in a request and gives back the Resolution trait.
 pub type MiddlewareClosure = Arc<MiddlewareRequest>;
@@ -25,14 +24,14 @@ pub type MiddlewareCollection = Vec<MiddlewareClosure>;
 /// ### Example
 /// 
 /// ```
-/// let is_admin: MiddlewareClosure = Arc::new(|req: Arc<Mutex< Request>>| Box::pin(async move { 
+/// let is_admin: MiddlewareClosure = middleware_fn(|req| Box::pin(async move { 
 ///
 ///        //snip
 ///
 ///        if is_admin {
 ///            //or pass any type of resolution
 ///            //return Middleware::Invalid(EmptyResolution::new(200))
-///            return Middleware::InvalidEmpty(403);
+///            return Middleware::InvalidEmpty(StatusCode::FORBIDDEN);
 ///        }
 ///        Middleware::Next
 ///    
@@ -53,8 +52,56 @@ pub enum Middleware {
     ///Represents that the middleware failed and cannot move forward towards the resolution.
     ///
     /// Filled with a status code
-    InvalidEmpty(i32),
+    InvalidEmpty(StatusCode),
 
     /// The middleware was a success, move forward towards the request.
     Next,
 }
+
+
+/// ## Priority
+///
+/// The order registered middleware is called in, lower priorities are called first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Priority(pub i32);
+
+/// ## Middleware Registry
+///
+/// Middleware registered by name, so routes can share middleware without cloning it into each `MiddlewareCollection`.
+///
+/// See `App::register_middleware`.
+#[derive(Default, Clone)]
+pub struct MiddlewareRegistry {
+    entries: HashMap<String, (Priority, MiddlewareClosure)>,
+}
+
+impl MiddlewareRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers middleware under the name, replacing middleware with the same name.
+    pub fn register(&mut self, name: &str, closure: MiddlewareClosure, priority: Priority) {
+        self.entries.insert(name.to_string(), (priority, closure));
+    }
+
+    /// Returns true if middleware with the name is registered.
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    /// # resolve
+    ///
+    /// The middleware of the names ordered by priority, names with the same priority keep their order.
+    ///
+    /// Names that are not registered are skipped.
+    pub fn resolve(&self, names: &[String]) -> MiddlewareCollection {
+        let mut found: Vec<&(Priority, MiddlewareClosure)> =
+            names.iter().filter_map(|name| self.entries.get(name)).collect();
+
+        found.sort_by_key(|(priority, _)| *priority);
+
+        found.into_iter().map(|(_, closure)| closure.clone()).collect()
+    }
+}
@@ -1,12 +1,137 @@
-use std::{collections::HashMap, net::SocketAddr};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
 
-use linked_hash_map::LinkedHashMap;
+use serde::de::DeserializeOwned;
 use tokio::{
-    io::{AsyncBufReadExt, AsyncReadExt, BufReader},
-    net::TcpStream,
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader},
+    sync::Mutex,
 };
 
-use crate::{web::{Method, Route}};
+use crate::{factory::{CancellationToken, WaitForCancellationFutureOwned}, web::{Method, Route, headers::HeaderMap, routing::MatchedRoute, background::Background, cache::Cache, cookies::{Cookie, Keyring, is_token, parse_cookies}, errors::{CookieError, ExtractError, RoutingError, WorkerError}, forwarded::TrustedProxies, locale::{ACCEPT_LANGUAGE_HEADER, Locale, negotiate_language, parse_accept_language}, media_type::{CONTENT_TYPE_HEADER, Charset, MediaType}, request_id::RequestId, routing::{Extensions, route::normalize_path, route_names::RouteNames}, security::CspNonce, throttle::Throttle, trace_context::TraceContext}};
+
+/// # Body Limits
+///
+/// Limits on the body of a request, bodies larger than `max_size` are not read and the request is answered with `413`.
+///
+/// Up to `max_drain` bytes of a rejected body are read and discarded so the client receives the response, the connection is then closed.
+#[derive(Debug, Clone, Copy)]
+pub struct BodyLimits {
+    /// The largest body that is read.
+    ///
+    /// By default (16 MiB)
+    pub max_size: usize,
+
+    /// The most bytes of a rejected body that are discarded before the connection is closed.
+    ///
+    /// By default (64 KiB)
+    pub max_drain: usize,
+
+    /// How long discarding a rejected body may take.
+    ///
+    /// By default (1 second)
+    pub drain_timeout: Duration,
+}
+
+impl Default for BodyLimits {
+    fn default() -> Self {
+        Self {
+            max_size: 16 * 1024 * 1024,
+            max_drain: 64 * 1024,
+            drain_timeout: Duration::from_secs(1),
+        }
+    }
+}
+
+/// # Head Limits
+///
+/// Limits on the request line and headers of a request, so clients sending them slowly or without end do not hold a connection.
+///
+/// A line sent too slowly fails with `TimedOut`, a head larger than `max_size` is answered by the app with `431`.
+///
+/// The request target is validated before it is routed, a target with control characters, too many segments, or a form that is not allowed is answered by the app with `400`. The `.` and `..` segments of its path are removed, see `route::normalize_path`.
+#[derive(Debug, Clone, Copy)]
+pub struct HeadLimits {
+    /// How long the client may take to send each line of the head, and the body or each chunk of a chunked body.
+    ///
+    /// By default (10 seconds)
+    pub line_timeout: Duration,
+
+    /// The most bytes of the request line and headers, up to the blank line.
+    ///
+    /// By default (64 KiB)
+    pub max_size: usize,
+
+    /// The most segments of the path of the request target.
+    ///
+    /// By default (128)
+    pub max_segments: usize,
+
+    /// Accepts targets in absolute-form, `GET http://example.com/tasks`, as sent to proxies. The path is routed and the authority replaces the `Host` header.
+    ///
+    /// By default (false)
+    pub allow_absolute_form: bool,
+
+    /// Accepts `CONNECT` requests, whose target is the `host:port` to tunnel to. The target is routed as is.
+    ///
+    /// By default (false)
+    pub allow_connect: bool,
+}
+
+impl Default for HeadLimits {
+    fn default() -> Self {
+        Self {
+            line_timeout: Duration::from_secs(10),
+            max_size: 64 * 1024,
+            max_segments: 128,
+            allow_absolute_form: false,
+            allow_connect: false,
+        }
+    }
+}
+
+/// the error of a request whose body is sent with a transfer coding other than chunked, the app answers it with `501`.
+#[derive(Debug)]
+pub(crate) struct UnsupportedTransferEncoding(String);
+
+impl std::fmt::Display for UnsupportedTransferEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the transfer encoding '{}' is not supported", self.0)
+    }
+}
+
+impl std::error::Error for UnsupportedTransferEncoding {}
+
+impl UnsupportedTransferEncoding {
+    /// true if the error is a transfer encoding that is not supported.
+    pub(crate) fn is(error: &std::io::Error) -> bool {
+        error
+            .get_ref()
+            .is_some_and(|e| e.is::<UnsupportedTransferEncoding>())
+    }
+}
+
+/// the error of a head larger than the `HeadLimits`.
+#[derive(Debug)]
+pub(crate) struct HeadTooLarge(usize);
+
+impl std::fmt::Display for HeadTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the head of the request is larger than {} bytes", self.0)
+    }
+}
+
+impl std::error::Error for HeadTooLarge {}
+
+impl HeadTooLarge {
+    /// true if the error is a head larger than the limits.
+    pub(crate) fn is(error: &std::io::Error) -> bool {
+        error.get_ref().is_some_and(|e| e.is::<HeadTooLarge>())
+    }
+}
 
 /// # Request
 ///
@@ -44,38 +169,107 @@ pub struct Request {
     /// The connected socket of the client
     pub client_socket: SocketAddr,
 
-    additional_headers: Option<LinkedHashMap<String, Option<String>>>,
+    /// Typed values attached to the request by middleware, such as the authenticated principal.
+    pub extensions: Extensions,
+
+    /// The route of the router the request matched.
+    ///
+    /// Set by the app before middleware is called, None for a request that was not routed.
+    pub matched: Option<MatchedRoute>,
+
+    additional_headers: Option<HeaderMap>,
+
+    throttle: Option<Throttle>,
+
+    cookies: Vec<Cookie>,
+
+    body_too_large: bool,
+
+    secure: bool,
+
+    version: String,
 }
 
 impl Request {
+    /// # builder
+    ///
+    /// Start building a `GET /` request from `127.0.0.1`, so middleware and handlers can be called in unit tests without a client, see [`RequestBuilder`].
+    pub fn builder() -> RequestBuilder {
+        RequestBuilder {
+            method: Method::GET,
+            path: "/".to_string(),
+            headers: HashMap::new(),
+            body: None,
+            client_socket: SocketAddr::from(([127, 0, 0, 1], 40000)),
+            variables: HashMap::new(),
+            extensions: Extensions::new(),
+            secure: false,
+        }
+    }
+
     /// # from_stream
     ///
-    /// Takes a mutable reference to the TcpStream (client), reading each line of the stream.
+    /// Takes a mutable reference to the stream of the client (TcpStream or TLS stream), reading each line of the stream.
     ///
     /// Each line is individually parsed to create a Request.
     ///
     /// The client's socket is stored in the Request.
-    pub async fn from_stream(
-        stream: &mut TcpStream,
+    pub async fn from_stream<S>(
+        stream: &mut S,
+        client_socket: SocketAddr,
+    ) -> Result<Self, std::io::Error>
+    where
+        S: AsyncRead + Unpin,
+    {
+        Self::from_stream_limited(stream, client_socket, BodyLimits::default()).await
+    }
+
+    /// # from_stream_limited
+    ///
+    /// Same as `from_stream`, but a body larger than the limit is discarded instead of read, see [`BodyLimits`] and `body_too_large`.
+    pub async fn from_stream_limited<S>(
+        stream: &mut S,
         client_socket: SocketAddr,
-    ) -> Result<Self, std::io::Error> {
+        limits: BodyLimits,
+    ) -> Result<Self, std::io::Error>
+    where
+        S: AsyncRead + Unpin,
+    {
         //create a buffer that will read each line
         let mut reader = BufReader::new(stream);
 
+        Self::from_reader(&mut reader, client_socket, limits, HeadLimits::default()).await
+    }
+
+    /// # from_reader
+    ///
+    /// Same as `from_stream_limited`, reading from a buffered reader, so bytes of the next request of a keep-alive connection stay in its buffer.
+    ///
+    /// The head is read within the [`HeadLimits`].
+    pub async fn from_reader<R>(
+        reader: &mut R,
+        client_socket: SocketAddr,
+        limits: BodyLimits,
+        head: HeadLimits,
+    ) -> Result<Self, std::io::Error>
+    where
+        R: AsyncBufRead + Unpin,
+    {
         let mut request_line = String::new();
+        let mut head_left = head.max_size;
 
         //the first line should be parsed independently
-        reader.read_line(&mut request_line).await?;
+        read_head_line(reader, &mut request_line, &head, &mut head_left).await?;
 
         if request_line.is_empty() {
             //no data
             return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
+                std::io::ErrorKind::UnexpectedEof,
                 "parse request failed due to no data being provided",
             ));
         }
 
-        let mut request_header = request_line.split(" ");
+        let mut request_header = request_line.trim_end().split(" ");
 
         let method = request_header
             .next()
@@ -94,72 +288,187 @@ impl Request {
                 "missing header for method",
             )))?;
 
-        let route = request_header
+        let (target, authority) = request_header
             .next()
-            .map(|header_value| Ok(Route::parse_route(header_value.to_string())))
+            .map(|header_value| request_target(&method, header_value, &head))
             .unwrap_or(Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
                 "missing header for request",
             )))?;
 
+        let route = Route::parse_route(target);
+
+        let version = request_header.next().unwrap_or("HTTP/1.0").trim().to_string();
+
         //all other headers beside the first
-        let mut headers = HashMap::new();
+        let mut headers: HashMap<String, String> = HashMap::new();
+
+        //insert all headers, each line is read into the same buffer.
+        let mut line = request_line;
+
+        //the framing of the body, a request smuggled behind an ambiguous length must not be read as the next request.
+        let mut content_length: Option<usize> = None;
+        let mut transfer_encoding: Option<String> = None;
 
-        //insert all headers
         loop {
-            let mut read_header = String::new();
+            line.clear();
 
-            reader.read_line(&mut read_header).await?;
+            read_head_line(reader, &mut line, &head, &mut head_left).await?;
 
-            let read_header = read_header.trim_end();
+            let read_header = line.trim_end();
 
             //no more headers.
             if read_header.is_empty() {
                 break;
             }
 
-            let split_header = read_header.split_once(":");
+            //a line without a name, or a name with whitespace before the colon, is read differently by each server it passes.
+            let (header_key, header_val) = read_header
+                .split_once(':')
+                .filter(|(key, _)| !key.is_empty() && key.bytes().all(is_token))
+                .ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "the request has a header line without a valid name",
+                    )
+                })?;
+            let header_val = header_val.trim();
+
+            if header_key.eq_ignore_ascii_case("Host")
+                && headers.keys().any(|key| key.eq_ignore_ascii_case("Host"))
+            {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "the request has more than one Host header",
+                ));
+            }
+
+            //header names are not case sensitive, hyper and HTTP/2 proxies send them in lowercase.
+            if header_key.eq_ignore_ascii_case("Content-Length") {
+                let length = parse_content_length(header_val)?;
+
+                if content_length.is_some_and(|sent| sent != length) {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "the request has conflicting Content-Length headers",
+                    ));
+                }
 
-            if split_header.is_none() {
-                continue;
+                content_length = Some(length);
+            } else if header_key.eq_ignore_ascii_case("Transfer-Encoding") {
+                let encodings = match transfer_encoding.take() {
+                    Some(sent) => format!("{sent}, {header_val}"),
+                    None => header_val.to_string(),
+                };
+
+                transfer_encoding = Some(encodings);
             }
 
-            //unwrap the known some value and insert into the headers.
-            let (header_key, header_val) = split_header.unwrap();
-            headers.insert(String::from(header_key), String::from(header_val.trim()));
+            headers.insert(String::from(header_key), String::from(header_val));
+        }
+
+        //the authority of an absolute-form target replaces the host.
+        if let Some(authority) = authority {
+            headers.retain(|key, _| !key.eq_ignore_ascii_case("Host"));
+            headers.insert("Host".to_string(), authority);
         }
 
-        let content_length = headers
-            .get("Content-Length")
-            .and_then(|v| v.parse::<usize>().ok())
-            .unwrap_or(0);
+        if let Some(encodings) = transfer_encoding {
+            //a body framed two ways is read differently by each server it passes.
+            if content_length.is_some() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "the request has both Content-Length and Transfer-Encoding",
+                ));
+            }
+
+            if !encodings.trim().eq_ignore_ascii_case("chunked") {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    UnsupportedTransferEncoding(encodings),
+                ));
+            }
+
+            let body = read_chunked(reader, &limits, &head).await?;
+            let body_too_large = body.is_none();
+
+            //the decoded body is given the length it would have been sent with.
+            headers.retain(|key, _| !key.eq_ignore_ascii_case("Transfer-Encoding"));
+
+            if let Some(body) = &body {
+                headers.insert("Content-Length".to_string(), body.len().to_string());
+            }
+
+            let body = body.filter(|body| !body.is_empty());
+
+            return Ok(Self::parsed(
+                method,
+                route,
+                headers,
+                body,
+                client_socket,
+                body_too_large,
+                version,
+            ));
+        }
 
-        let body = if content_length > 0 {
+        let content_length = content_length.unwrap_or(0);
+        let body_too_large = content_length > limits.max_size;
+
+        let body = if body_too_large {
+            //discard what can be drained quickly, the rest is dropped when the connection closes.
+            let mut discard = (&mut *reader).take(content_length.min(limits.max_drain) as u64);
+            let _ = tokio::time::timeout(
+                limits.drain_timeout,
+                tokio::io::copy(&mut discard, &mut tokio::io::sink()),
+            )
+            .await;
+
+            None
+        } else if content_length > 0 {
             //read the body from the content length.
             let mut body = vec![0u8; content_length];
-            reader.read_exact(&mut body).await?;
+            read_body(reader, &mut body, &head).await?;
             Some(body)
         } else {
             //no body was provided.
             None
         };
 
-        Ok(Self {
+        Ok(Self::parsed(method, route, headers, body, client_socket, body_too_large, version))
+    }
+
+    /// a request read from a client, before the app adds to it.
+    fn parsed(
+        method: Method,
+        route: Route,
+        headers: HashMap<String, String>,
+        body: Option<Vec<u8>>,
+        client_socket: SocketAddr,
+        body_too_large: bool,
+        version: String,
+    ) -> Self {
+        Self {
             method,
             route,
             headers,
             body,
             variables: HashMap::new(),
             client_socket,
-            additional_headers: Some(LinkedHashMap::new()),
-        })
+            extensions: Extensions::new(),
+            matched: None,
+            additional_headers: Some(HeaderMap::new()),
+            throttle: None,
+            cookies: Vec::new(),
+            body_too_large,
+            secure: false,
+            version,
+        }
     }
 
     /// # add header
     ///
-    /// Adds the header to the additional headers map.
-    ///
-    /// `Note: If the value already exist, the previous is returned as Some(previous_value)`
+    /// Adds the header to the additional headers map, replacing a header of the same name.
     ///
     /// This is useful for middleware, if in a middleware resolution you need to add some header that may do caching.
     pub fn add_header(
@@ -172,20 +481,36 @@ impl Request {
         }
     }
 
+    /// # append header
+    ///
+    /// Adds a value to a header of the additional headers map, keeping its other values, for headers that may repeat such as `Link`.
+    pub fn append_header(&mut self, header_name: String, header_value: Option<String>) {
+        if let Some(m) = self.additional_headers.as_mut() {
+            m.append(header_name, header_value);
+        }
+    }
+
+    /// # request header
+    ///
+    /// Retrieves a header sent by the client, the header name is not case sensitive.
+    pub fn request_header(&self, header_name: &str) -> Option<&String> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(header_name))
+            .map(|(_, value)| value)
+    }
+
     /// # get header
     /// 
-    /// Retrieves a header by the header_name.
-    /// 
-    /// If the header exist, a reference to the &Option<String> is returned.
+    /// Retrieves the first value of an additional header by the header_name, the name is not case sensitive.
     /// 
     /// `Note: Sometimes, a header may have a name, but no value.`
     /// 
-    /// If the header does not exist, None is returned.
-    pub fn get_header(&self, header_name: &str) -> Option<&String> {
+    /// If the header does not exist or has no value, None is returned.
+    pub fn get_header(&self, header_name: &str) -> Option<&str> {
         self.additional_headers
             .as_ref()
-            .and_then(|m| m.get(&header_name.to_string()))
-            .and_then(|v| v.as_ref())
+            .and_then(|m| m.get(header_name))
     }
 
     /// # take headers
@@ -195,11 +520,809 @@ impl Request {
     /// If the function returns None, it has already been taken.
     /// 
     /// If the function returns Some(HeadMap), you now have control.
-    pub fn take_headers(&mut self) -> Option<LinkedHashMap<String, Option<String>>> {
+    pub fn take_headers(&mut self) -> Option<HeaderMap> {
         if let None = self.additional_headers {
             return None;
         }
 
         self.additional_headers.take()
     }
+
+    /// # set throttle
+    ///
+    /// Caps the rate at which the response for this request is written to the client.
+    ///
+    /// See `web::throttle` for ready made middleware.
+    pub fn set_throttle(&mut self, throttle: Throttle) {
+        self.throttle = Some(throttle);
+    }
+
+    /// # throttle
+    ///
+    /// The throttle applied to the response, if any.
+    pub fn throttle(&self) -> Option<&Throttle> {
+        self.throttle.as_ref()
+    }
+
+    /// # cookie
+    ///
+    /// Retrieves the value of a cookie sent by the client.
+    pub fn cookie(&self, name: &str) -> Option<String> {
+        let header = self.request_header("Cookie")?;
+
+        parse_cookies(header)
+            .into_iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+    }
+
+    /// # signed cookie
+    ///
+    /// Retrieves the value of a cookie signed with `Keyring::sign`, None if it is missing or was changed by the client.
+    pub fn signed_cookie(&self, name: &str, keyring: &Keyring) -> Option<String> {
+        keyring.verify(name, &self.cookie(name)?)
+    }
+
+    /// # private cookie
+    ///
+    /// Retrieves the value of a cookie encrypted with `Keyring::encrypt`, None if it is missing or was changed by the client.
+    pub fn private_cookie(&self, name: &str, keyring: &Keyring) -> Option<String> {
+        keyring.decrypt(name, &self.cookie(name)?)
+    }
+
+    /// # set cookie
+    ///
+    /// Sends the cookie to the client with the response, each cookie is written as its own `Set-Cookie` header.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CookieError` and does not send the cookie if it can not be written safely, see `Cookie::header_value`.
+    pub fn set_cookie(&mut self, cookie: Cookie) -> Result<(), CookieError> {
+        cookie.header_value()?;
+        self.cookies.push(cookie);
+
+        Ok(())
+    }
+
+    /// # take cookies
+    ///
+    /// Takes the cookies that were set for the response.
+    pub fn take_cookies(&mut self) -> Vec<Cookie> {
+        std::mem::take(&mut self.cookies)
+    }
+
+    /// # cache
+    ///
+    /// The cache of the app, see `App::set_cache`.
+    ///
+    /// A request that was not created by the app receives a new, unshared cache.
+    pub fn cache(&self) -> Cache {
+        self.extensions.get::<Cache>().cloned().unwrap_or_default()
+    }
+
+    /// # cancel token
+    ///
+    /// Cancelled when the client disconnects before or while the response is sent, see `App::set_cancel_on_disconnect`. Check it between the steps of long work, or pass it on to work that can be cancelled.
+    ///
+    /// A request that was not created by the app receives a new token that is never cancelled.
+    pub fn cancel_token(&self) -> CancellationToken {
+        self.extensions.get::<CancellationToken>().cloned().unwrap_or_default()
+    }
+
+    /// # on disconnect
+    ///
+    /// Completes when the client disconnects before or while the response is sent, for handlers and the tasks they spawn to select on.
+    ///
+    /// ```ignore
+    /// let disconnected = req.lock().await.on_disconnect();
+    ///
+    /// tokio::spawn(async move {
+    ///     tokio::select! {
+    ///         _ = generate(prompt, sender) => {}
+    ///         _ = disconnected => {} //the client left, stop generating.
+    ///     }
+    /// });
+    /// ```
+    pub fn on_disconnect(&self) -> WaitForCancellationFutureOwned {
+        self.cancel_token().cancelled_owned()
+    }
+
+    /// # request id
+    ///
+    /// The id assigned by the `web::request_id::request_id` middleware, None if the middleware did not run.
+    pub fn request_id(&self) -> Option<&str> {
+        self.extensions.get::<RequestId>().map(|id| id.as_str())
+    }
+
+    /// # trace context
+    ///
+    /// The W3C trace context set by the `web::trace_context::trace_context` middleware, None if the middleware did not run.
+    pub fn trace_context(&self) -> Option<&TraceContext> {
+        self.extensions.get::<TraceContext>()
+    }
+
+    /// # accept language
+    ///
+    /// The language ranges of the `Accept-Language` header, most preferred first, empty if the header is missing, see `web::locale::parse_accept_language`.
+    pub fn accept_language(&self) -> Vec<String> {
+        self.request_header(ACCEPT_LANGUAGE_HEADER)
+            .map(|header| parse_accept_language(header))
+            .unwrap_or_default()
+    }
+
+    /// # negotiate language
+    ///
+    /// The supported language the client prefers the most, None if it accepts none of them, see `web::locale::negotiate_language`.
+    pub fn negotiate_language<'a>(&self, supported: &[&'a str]) -> Option<&'a str> {
+        negotiate_language(&self.accept_language(), supported)
+    }
+
+    /// # locale
+    ///
+    /// The language chosen by the `web::locale::locale` middleware, None if the middleware did not run.
+    pub fn locale(&self) -> Option<&str> {
+        self.extensions.get::<Locale>().map(|locale| locale.as_str())
+    }
+
+    /// # body too large
+    ///
+    /// True if the body was larger than the `BodyLimits` and was discarded, the app answers such requests with `413` without calling middleware or the handler.
+    pub fn body_too_large(&self) -> bool {
+        self.body_too_large
+    }
+
+    /// # version
+    ///
+    /// The HTTP version of the request line, `HTTP/1.1`.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// # keep alive
+    ///
+    /// True if the client allows the connection to be reused for another request, `HTTP/1.1` unless it sent `Connection: close`, `HTTP/1.0` only with `Connection: keep-alive`.
+    pub fn keep_alive(&self) -> bool {
+        let connection = |token: &str| {
+            self.request_header("Connection")
+                .is_some_and(|value| value.split(',').any(|t| t.trim().eq_ignore_ascii_case(token)))
+        };
+
+        match self.version.as_str() {
+            "HTTP/1.0" => connection("keep-alive"),
+            _ => !connection("close"),
+        }
+    }
+
+    /// # route pattern
+    ///
+    /// The pattern of the matched route, `/users/{id}`, or the cleaned route when the request was not routed.
+    ///
+    /// Use this to group requests by route in rate limiters and metrics.
+    pub fn route_pattern(&self) -> &str {
+        self.matched
+            .as_ref()
+            .map(|matched| matched.pattern.as_str())
+            .unwrap_or(&self.route.cleaned_route)
+    }
+
+    /// # client ip
+    ///
+    /// The address of the client, read from the `Forwarded` or `X-Forwarded-For` header when the connection is from a proxy trusted with `App::set_trusted_proxies`, see `web::forwarded::TrustedProxies`.
+    ///
+    /// Without trusted proxies this is the address of `client_socket`.
+    pub fn client_ip(&self) -> IpAddr {
+        match self.extensions.get::<TrustedProxies>() {
+            Some(proxies) => proxies.client_ip(
+                self.client_socket,
+                self.request_header("Forwarded").map(String::as_str),
+                self.request_header("X-Forwarded-For").map(String::as_str),
+            ),
+            None => self.client_socket.ip(),
+        }
+    }
+
+    /// # scheme
+    ///
+    /// The scheme the client used, `http` or `https`, read from the `Forwarded` or `X-Forwarded-Proto` header when the connection is from a trusted proxy, see `web::forwarded::TrustedProxies`.
+    ///
+    /// Otherwise `https` when the request came over TLS.
+    pub fn scheme(&self) -> &str {
+        let forwarded = self.extensions.get::<TrustedProxies>().and_then(|proxies| {
+            proxies.forwarded_proto(
+                self.client_socket,
+                self.request_header("Forwarded").map(String::as_str),
+                self.request_header("X-Forwarded-Proto").map(String::as_str),
+            )
+        });
+
+        match forwarded.as_deref() {
+            Some("https") => "https",
+            Some(_) => "http",
+            None if self.secure => "https",
+            None => "http",
+        }
+    }
+
+    /// # host
+    ///
+    /// The host the client requested, read from the `Forwarded` or `X-Forwarded-Host` header when the connection is from a trusted proxy, otherwise the `Host` header.
+    ///
+    /// None if the client sent no host.
+    pub fn host(&self) -> Option<String> {
+        self.extensions
+            .get::<TrustedProxies>()
+            .and_then(|proxies| {
+                proxies.forwarded_host(
+                    self.client_socket,
+                    self.request_header("Forwarded").map(String::as_str),
+                    self.request_header("X-Forwarded-Host").map(String::as_str),
+                )
+            })
+            .or_else(|| self.request_header("Host").cloned())
+    }
+
+    /// # full url
+    ///
+    /// The absolute URL the client requested, `https://example.com/tasks?page=2`, from `scheme`, `host`, and the route with its query.
+    ///
+    /// Use this to build redirects and absolute links that stay correct behind a load balancer. None if the client sent no host.
+    pub fn full_url(&self) -> Option<String> {
+        let host = self.host()?;
+
+        Some(format!("{}://{host}{}", self.scheme(), self.route.init_route))
+    }
+
+    /// # content type
+    ///
+    /// The media type of the body from the `Content-Type` header, None if it was not sent or can not be parsed.
+    pub fn content_type(&self) -> Option<MediaType> {
+        self.request_header(CONTENT_TYPE_HEADER)
+            .and_then(|value| MediaType::parse(value))
+    }
+
+    /// # text
+    ///
+    /// The body decoded as text in the charset of its `Content-Type`, an empty string if there is no body.
+    ///
+    /// UTF-8 and ISO-8859-1 are supported. A body without a charset is read as UTF-8, and as ISO-8859-1 if it is not valid UTF-8, where every byte is a character.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ExtractError::UnsupportedCharset` for other charsets (`415`), and `ExtractError::Text` if the body is not valid in the charset it declared (`400`).
+    pub fn text(&self) -> Result<String, ExtractError> {
+        let body = self.body.as_deref().unwrap_or_default();
+
+        let Some(label) = self.content_type().and_then(|t| t.charset().map(str::to_string)) else {
+            return Ok(Charset::Utf8
+                .decode(body)
+                .or_else(|| Charset::Latin1.decode(body))
+                .unwrap_or_default());
+        };
+
+        Charset::from_label(&label)
+            .ok_or_else(|| ExtractError::UnsupportedCharset(label.clone()))?
+            .decode(body)
+            .ok_or(ExtractError::Text(label))
+    }
+
+    /// # query
+    ///
+    /// Deserializes the query of the route into a type, see `Route::query`. Handlers may take the [`crate::web::extract::Query`] extractor instead.
+    ///
+    /// ```ignore
+    /// let pagination: Pagination = match req.lock().await.query() {
+    ///     Ok(pagination) => pagination,
+    ///     Err(e) => return rejection(e),
+    /// };
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `ExtractError::Query` if the query does not fit the type.
+    pub fn query<T: DeserializeOwned>(&self) -> Result<T, ExtractError> {
+        self.route.query()
+    }
+
+    /// # url for
+    ///
+    /// Builds the path of a route named with `AddedRoute::name` or `App::name_route`, see `web::routing::route_names::RouteNames::url_for`.
+    ///
+    /// ```ignore
+    /// let location = req.lock().await.url_for("user_detail", &[("id", &id)])?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `RoutingError::UnknownName` if no route has the name, or the request was not made to an app.
+    pub fn url_for(&self, name: &str, params: &[(&str, &str)]) -> Result<String, RoutingError> {
+        self.extensions
+            .get::<RouteNames>()
+            .ok_or_else(|| RoutingError::UnknownName(name.to_string()))?
+            .url_for(name, params)
+    }
+
+    /// sets if the request came over TLS.
+    #[cfg(feature = "tls")]
+    pub(crate) fn set_secure(&mut self, secure: bool) {
+        self.secure = secure;
+    }
+
+    /// # csp nonce
+    ///
+    /// The Content-Security-Policy nonce of the request, set by the `web::security::security_headers` middleware, None if it did not run.
+    pub fn csp_nonce(&self) -> Option<&str> {
+        self.extensions.get::<CspNonce>().map(|nonce| nonce.as_str())
+    }
+
+    /// # spawn
+    ///
+    /// Spawns fire-and-forget work that outlives the response, carrying the request id and route of this request (see `web::background::current`).
+    ///
+    /// The task is cancelled when the app closes, in which case its output is None, and `App::close` waits for it.
+    ///
+    /// With the `tracing` feature the task runs in the span of the request.
+    ///
+    /// # Errors
+    ///
+    /// `WorkerError::NoBackground` if the request was not handled by an app, such as one made with `Request::builder`.
+    ///
+    /// ```ignore
+    /// req.lock().await.spawn(async move {
+    ///     let ctx = background::current().unwrap();
+    ///     send_welcome_email(&user).await;
+    ///     println!("[{:?}] email sent", ctx.request_id);
+    /// })?;
+    /// ```
+    pub fn spawn<F>(&self, future: F) -> Result<tokio::task::JoinHandle<Option<F::Output>>, WorkerError>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let background = self.extensions.get::<Background>().ok_or(WorkerError::NoBackground)?;
+
+        Ok(background.spawn(
+            self.request_id().map(|id| id.to_string()),
+            self.route.cleaned_route.clone(),
+            future,
+        ))
+    }
+
+    /// # Shared
+    ///
+    /// Lends the request to `f` as an `Arc<Mutex<Request>>`, for handlers and middleware written against the shared request.
+    ///
+    /// The request is taken back once `f` completes, is dropped, or panics. A clone of the shared request kept past `f` is left with an empty request.
+    ///
+    /// When `f` completes while a clone is locked, for example in a spawned task, the request is taken back once it is unlocked.
+    /// When `f` is dropped or panics while a clone is locked the request can not be waited for, it is lost and the error is logged.
+    ///
+    /// ```ignore
+    /// let principal = req.shared(|shared| async move {
+    ///     legacy_lookup(shared).await
+    /// }).await;
+    /// ```
+    pub async fn shared<F, Fut>(&mut self, f: F) -> Fut::Output
+    where
+        F: FnOnce(Arc<Mutex<Request>>) -> Fut,
+        Fut: Future,
+    {
+        let placeholder = Self::placeholder(self.client_socket);
+        let shared = Arc::new(Mutex::new(std::mem::replace(self, placeholder)));
+
+        let restore = Restore {
+            request: Some(self),
+            shared: shared.clone(),
+        };
+
+        let output = f(shared).await;
+
+        restore.finish().await;
+
+        output
+    }
+
+    /// an empty request that holds the place of a lent request.
+    fn placeholder(client_socket: SocketAddr) -> Self {
+        Self {
+            method: Method::GET,
+            route: Route::parse_route("/".to_string()),
+            headers: HashMap::new(),
+            body: None,
+            variables: HashMap::new(),
+            client_socket,
+            extensions: Extensions::new(),
+            matched: None,
+            additional_headers: None,
+            throttle: None,
+            cookies: Vec::new(),
+            body_too_large: false,
+            secure: false,
+            version: String::new(),
+        }
+    }
+}
+
+/// # Request Builder
+///
+/// Sets the parts of a [`Request`] that is not read from a client, finished with `build`.
+///
+/// The request is the same as one parsed from a client, middleware and handlers can be called with it directly.
+///
+/// ```ignore
+/// let mut req = Request::builder()
+///     .method(Method::POST)
+///     .path("/tasks/7?notify=true")
+///     .header("Authorization", "Bearer token")
+///     .variable("id", "7")
+///     .json(&task)?
+///     .build();
+///
+/// let outcome = auth_middleware(&mut req).await;
+/// ```
+pub struct RequestBuilder {
+    method: Method,
+    path: String,
+    headers: HashMap<String, String>,
+    body: Option<Vec<u8>>,
+    client_socket: SocketAddr,
+    variables: HashMap<String, String>,
+    extensions: Extensions,
+    secure: bool,
+}
+
+impl RequestBuilder {
+    /// Sets the method.
+    pub fn method(mut self, method: Method) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Sets the path the client requested, it may carry a query.
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = path.to_string();
+        self
+    }
+
+    /// Sets a header sent by the client, replacing a header of the same name.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.retain(|key, _| !key.eq_ignore_ascii_case(name));
+        self.headers.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Sets the body and its `Content-Length`.
+    pub fn body(self, body: impl Into<Vec<u8>>) -> Self {
+        let body = body.into();
+        let mut builder = self.header("Content-Length", &body.len().to_string());
+
+        builder.body = (!body.is_empty()).then_some(body);
+        builder
+    }
+
+    /// Sets the body to the value as JSON, with the `application/json` content type.
+    pub fn json<T: serde::Serialize + ?Sized>(self, value: &T) -> Result<Self, serde_json::Error> {
+        let body = serde_json::to_vec(value)?;
+
+        Ok(self.header("Content-Type", "application/json").body(body))
+    }
+
+    /// Sets the address of the client.
+    ///
+    /// By default (127.0.0.1:40000)
+    pub fn client_socket(mut self, client_socket: SocketAddr) -> Self {
+        self.client_socket = client_socket;
+        self
+    }
+
+    /// Sets if the request came over TLS, see `Request::scheme`.
+    ///
+    /// By default (false)
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Sets a variable of the route, as the app does for a pattern such as `/tasks/{id}`.
+    pub fn variable(mut self, name: &str, value: &str) -> Self {
+        self.variables.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Inserts a value into the extensions, as earlier middleware would.
+    pub fn extension<T: Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.extensions.insert(value);
+        self
+    }
+
+    /// Finish the request.
+    pub fn build(self) -> Request {
+        Request {
+            method: self.method,
+            route: Route::parse_route(self.path),
+            headers: self.headers,
+            variables: self.variables,
+            body: self.body,
+            client_socket: self.client_socket,
+            extensions: self.extensions,
+            matched: None,
+            additional_headers: Some(HeaderMap::new()),
+            throttle: None,
+            cookies: Vec::new(),
+            body_too_large: false,
+            secure: self.secure,
+            version: "HTTP/1.1".to_string(),
+        }
+    }
+}
+
+/// reads one line of the head, failing when the client takes longer than the line timeout or the head passes its size.
+async fn read_head_line<R>(
+    reader: &mut R,
+    line: &mut String,
+    head: &HeadLimits,
+    head_left: &mut usize,
+) -> Result<(), std::io::Error>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut limited = (&mut *reader).take(*head_left as u64);
+
+    let read = tokio::time::timeout(head.line_timeout, limited.read_line(line))
+        .await
+        .map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "a line of the head of the request was not sent in time",
+            )
+        })??;
+
+    *head_left -= read;
+
+    //the line was cut by the size left, it is not the end of the head.
+    if *head_left == 0 && !line.ends_with('\n') {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            HeadTooLarge(head.max_size),
+        ));
+    }
+
+    Ok(())
+}
+
+/// the length of a `Content-Length` header, only digits are allowed.
+fn parse_content_length(value: &str) -> Result<usize, std::io::Error> {
+    value
+        .bytes()
+        .all(|b| b.is_ascii_digit())
+        .then(|| value.parse::<usize>().ok())
+        .flatten()
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("the Content-Length '{value}' is not a length"),
+            )
+        })
+}
+
+/// reads a chunked body and its trailers, None if it is larger than the limits, the rest is left unread.
+async fn read_chunked<R>(
+    reader: &mut R,
+    limits: &BodyLimits,
+    head: &HeadLimits,
+) -> Result<Option<Vec<u8>>, std::io::Error>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let invalid = |reason: &str| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("the chunked body {reason}"),
+        )
+    };
+
+    let mut body = Vec::new();
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        read_chunk_line(reader, &mut line, head).await?;
+
+        //extensions after the size are ignored.
+        let size = line.split(';').next().unwrap_or_default().trim();
+
+        if size.is_empty() || !size.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(invalid("has a chunk without a size"));
+        }
+
+        let size = usize::from_str_radix(size, 16).map_err(|_| invalid("has a chunk too large"))?;
+
+        if size == 0 {
+            break;
+        }
+
+        if body.len().saturating_add(size) > limits.max_size {
+            return Ok(None);
+        }
+
+        let start = body.len();
+        body.resize(start + size, 0);
+        read_body(reader, &mut body[start..], head).await?;
+
+        line.clear();
+        read_chunk_line(reader, &mut line, head).await?;
+
+        if !line.trim_end_matches(['\r', '\n']).is_empty() {
+            return Err(invalid("has a chunk longer than its size"));
+        }
+    }
+
+    //the trailers are discarded.
+    loop {
+        line.clear();
+        read_chunk_line(reader, &mut line, head).await?;
+
+        if line.trim_end().is_empty() {
+            return Ok(Some(body));
+        }
+    }
+}
+
+/// fills the buffer with the body, or a chunk of a chunked body, within the line timeout of the head.
+async fn read_body<R>(reader: &mut R, body: &mut [u8], head: &HeadLimits) -> Result<(), std::io::Error>
+where
+    R: AsyncBufRead + Unpin,
+{
+    tokio::time::timeout(head.line_timeout, reader.read_exact(body))
+        .await
+        .map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "the body of the request was not sent in time",
+            )
+        })??;
+
+    Ok(())
+}
+
+/// reads a line of the framing of a chunked body, no longer than the head of a request.
+async fn read_chunk_line<R>(
+    reader: &mut R,
+    line: &mut String,
+    head: &HeadLimits,
+) -> Result<(), std::io::Error>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut limited = (&mut *reader).take(head.max_size as u64);
+
+    tokio::time::timeout(head.line_timeout, limited.read_line(line))
+        .await
+        .map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "a line of the chunked body was not sent in time",
+            )
+        })??;
+
+    if !line.ends_with('\n') {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "a line of the chunked body was cut off",
+        ));
+    }
+
+    Ok(())
+}
+
+/// validates the target of the request line against the limits, the normalized target and the authority of an absolute-form target.
+fn request_target(
+    method: &Method,
+    target: &str,
+    head: &HeadLimits,
+) -> Result<(String, Option<String>), std::io::Error> {
+    let invalid = |reason: &str| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("the request target {reason}"),
+        )
+    };
+
+    if target.is_empty() {
+        return Err(invalid("is empty"));
+    }
+
+    if target.bytes().any(|b| b.is_ascii_control() || b == b' ') {
+        return Err(invalid("has control characters"));
+    }
+
+    //the target of CONNECT is the host:port to tunnel to.
+    if matches!(method, Method::Other(m) if m == "CONNECT") {
+        return match head.allow_connect {
+            true => Ok((target.to_string(), None)),
+            false => Err(invalid("of CONNECT is not allowed")),
+        };
+    }
+
+    if target == "*" {
+        return match method {
+            Method::Other(m) if m == "OPTIONS" => Ok((target.to_string(), None)),
+            _ => Err(invalid("* is only allowed for OPTIONS")),
+        };
+    }
+
+    let (target, authority) = match target.starts_with('/') {
+        true => (target, None),
+        false => {
+            let rest = ["http://", "https://"]
+                .iter()
+                .find_map(|scheme| {
+                    target
+                        .get(..scheme.len())
+                        .filter(|prefix| prefix.eq_ignore_ascii_case(scheme))
+                        .map(|_| &target[scheme.len()..])
+                })
+                .ok_or_else(|| invalid("is not a path"))?;
+
+            if !head.allow_absolute_form {
+                return Err(invalid("in absolute-form is not allowed"));
+            }
+
+            let (authority, path) = rest.split_at(rest.find(['/', '?']).unwrap_or(rest.len()));
+
+            if authority.is_empty() || authority.contains('@') {
+                return Err(invalid("has no host"));
+            }
+
+            (path, Some(authority.to_string()))
+        }
+    };
+
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (target, None),
+    };
+
+    if path.split('/').filter(|s| !s.is_empty()).count() > head.max_segments {
+        return Err(invalid("has too many segments"));
+    }
+
+    let mut normalized = normalize_path(path);
+
+    if let Some(query) = query {
+        normalized.push('?');
+        normalized.push_str(query);
+    }
+
+    Ok((normalized, authority))
+}
+
+/// puts a lent request back, waiting for the lock with `finish`, or without waiting when dropped.
+struct Restore<'a> {
+    request: Option<&'a mut Request>,
+    shared: Arc<Mutex<Request>>,
+}
+
+impl Restore<'_> {
+    /// takes the request back once no clone holds the lock.
+    async fn finish(mut self) {
+        if let Some(request) = self.request.take() {
+            std::mem::swap(request, &mut *self.shared.lock().await);
+        }
+    }
+}
+
+impl Drop for Restore<'_> {
+    fn drop(&mut self) {
+        let Some(request) = self.request.take() else {
+            return;
+        };
+
+        //a cancelled or panicking lender can not wait, the lock is only free if no clone holds it.
+        match self.shared.try_lock() {
+            Ok(mut shared) => std::mem::swap(request, &mut shared),
+            Err(_) => crate::web::telemetry::error(
+                "the request lent by `Request::shared` was locked by a clone when it was dropped, its headers, body, and extensions were lost",
+            ),
+        }
+    }
 }
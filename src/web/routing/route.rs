@@ -1,5 +1,9 @@
 use std::collections::HashMap;
 
+use serde::de::DeserializeOwned;
+
+use crate::web::{Method, errors::ExtractError};
+
 /// ## Route
 /// 
 /// A client provided browser url. Created by parsing the route and then can be used to get the parameters sent by the user and the true URL the user was meaning to fetch.
@@ -27,6 +31,39 @@ pub struct Route {
     params: HashMap<String, String>,
 }
 
+/// ## Normalize Path
+///
+/// Removes the `.` and `..` segments of a path, `/a/./b/../c` is `/a/c`.
+///
+/// A `..` never climbs above the root, and a `%2e` is treated as a dot so encoded dot segments are removed as well. Other segments are kept as sent.
+pub fn normalize_path(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    let mut ends_in_dot = false;
+
+    for segment in path.split('/').skip(1) {
+        let decoded = segment.to_ascii_lowercase().replace("%2e", ".");
+
+        ends_in_dot = matches!(decoded.as_str(), "." | "..");
+
+        match decoded.as_str() {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+
+    let mut normalized = format!("/{}", segments.join("/"));
+
+    //a path ending in a dot segment names a directory.
+    if ends_in_dot && !normalized.ends_with('/') {
+        normalized.push('/');
+    }
+
+    normalized
+}
+
 impl std::fmt::Display for Route {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.init_route)
@@ -102,4 +139,53 @@ impl Route {
     pub fn get_params(&self) -> &HashMap<String, String> {
         &self.params
     }
+
+    /// The query of the route, the part after the `?`, empty if there is none.
+    pub fn query_string(&self) -> &str {
+        self.init_route
+            .split_once('?')
+            .map(|(_, query)| query)
+            .unwrap_or_default()
+    }
+
+    /// ## Query
+    ///
+    /// Deserializes the query into a type, `?page=2&per_page=50&sort=desc` into a struct with those fields.
+    ///
+    /// Values are percent-decoded and `+` is a space. Fields the client may leave out take `#[serde(default)]`, or are an `Option`.
+    ///
+    /// ```ignore
+    /// #[derive(Deserialize)]
+    /// #[serde(default)]
+    /// struct Pagination { page: u32, per_page: u32, sort: Sort }
+    ///
+    /// let pagination: Pagination = route.query()?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `ExtractError::Query` if the query does not fit the type, which is sent as `400`.
+    pub fn query<T: DeserializeOwned>(&self) -> Result<T, ExtractError> {
+        serde_urlencoded::from_str(self.query_string()).map_err(|e| ExtractError::Query(e.to_string()))
+    }
+}
+
+/// ## Matched Route
+///
+/// The route of the router that a request matched, set on the request before any middleware is called.
+///
+/// Middleware may group by the pattern, `/users/{id}`, instead of the raw url, `/users/42`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchedRoute {
+    /// The pattern the route was added with, for example `/users/{id}`.
+    pub pattern: String,
+
+    /// The method of the matched endpoint.
+    pub method: Method,
+
+    /// Names of the registered middleware of the endpoint, see `App::add_route_named`.
+    pub named_middleware: Vec<String>,
+
+    /// True when no route matched and the missing route (404) endpoint is used.
+    pub fallback: bool,
 }
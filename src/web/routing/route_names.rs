@@ -0,0 +1,181 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    sync::{Arc, RwLock},
+};
+
+use crate::web::errors::RoutingError;
+
+/// # Route Names
+///
+/// Names given to route patterns, so paths are built from the router with `url_for` instead of hardcoded strings, see `App::url_for` and `Request::url_for`.
+///
+/// Cloning shares the same names.
+#[derive(Debug, Clone, Default)]
+pub struct RouteNames {
+    names: Arc<RwLock<HashMap<String, String>>>,
+}
+
+impl RouteNames {
+    /// Create names without any route.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Insert
+    ///
+    /// Names the route pattern, `/users/{id}`. Naming the same pattern again is allowed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RoutingError::NameTaken` if the name is given to another pattern.
+    pub fn insert(&self, name: &str, pattern: &str) -> Result<(), RoutingError> {
+        let mut names = self.names.write().unwrap_or_else(|e| e.into_inner());
+
+        match names.get(name) {
+            Some(named) if named != pattern => Err(RoutingError::NameTaken(name.to_string())),
+            _ => {
+                names.insert(name.to_string(), pattern.to_string());
+                Ok(())
+            }
+        }
+    }
+
+    /// The pattern of the name, None if no route has the name.
+    pub fn pattern(&self, name: &str) -> Option<String> {
+        self.names
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(name)
+            .cloned()
+    }
+
+    /// # Url For
+    ///
+    /// Builds the path of the named route, each `{variable}` of the pattern is replaced with the param of the same name and `{*}` with the param `*`.
+    ///
+    /// Params the pattern does not use are sent as the query. Values are percent-encoded, a `{*}` value keeps its `/`.
+    ///
+    /// ```ignore
+    /// names.insert("user_detail", "/users/{id}")?;
+    ///
+    /// assert_eq!(names.url_for("user_detail", &[("id", "42"), ("tab", "posts")])?, "/users/42?tab=posts");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `RoutingError::UnknownName` if no route has the name and `RoutingError::MissingVariable` if a variable of the pattern has no param.
+    pub fn url_for(&self, name: &str, params: &[(&str, &str)]) -> Result<String, RoutingError> {
+        let pattern = self
+            .pattern(name)
+            .ok_or_else(|| RoutingError::UnknownName(name.to_string()))?;
+
+        let mut used = Vec::new();
+        let mut path = String::new();
+
+        for segment in pattern.split('/').filter(|s| !s.is_empty()) {
+            path.push('/');
+
+            let Some(variable) = segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) else {
+                path.push_str(segment);
+                continue;
+            };
+
+            let (_, value) = params
+                .iter()
+                .find(|(param, _)| *param == variable)
+                .ok_or_else(|| RoutingError::MissingVariable(variable.to_string()))?;
+
+            used.push(variable);
+
+            match variable {
+                "*" => {
+                    let segments: Vec<String> = value
+                        .trim_start_matches('/')
+                        .split('/')
+                        .map(encode)
+                        .collect();
+
+                    path.push_str(&segments.join("/"));
+                }
+                _ => path.push_str(&encode(value)),
+            }
+        }
+
+        if path.is_empty() {
+            path.push('/');
+        }
+
+        let query: Vec<String> = params
+            .iter()
+            .filter(|(param, _)| !used.contains(param))
+            .map(|(param, value)| format!("{}={}", encode(param), encode(value)))
+            .collect();
+
+        if !query.is_empty() {
+            path.push('?');
+            path.push_str(&query.join("&"));
+        }
+
+        Ok(path)
+    }
+}
+
+/// # Added Route
+///
+/// A route that was just added, returned by `App::add_route` and the other functions that add routes so it may be named.
+///
+/// ```ignore
+/// app.add_route("/users/{id}", Method::GET, None, user_detail)
+///     .await?
+///     .name("user_detail")?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct AddedRoute {
+    names: RouteNames,
+    pattern: String,
+}
+
+impl AddedRoute {
+    /// a route of the pattern, named in the names.
+    pub(crate) fn new(names: RouteNames, pattern: &str) -> Self {
+        Self {
+            names,
+            pattern: pattern.to_string(),
+        }
+    }
+
+    /// The pattern the route was added with.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// # Name
+    ///
+    /// Names the route, so its path is built with `url_for`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RoutingError::NameTaken` if the name is given to another route.
+    pub fn name(self, name: &str) -> crate::Result<()> {
+        Ok(self.names.insert(name, &self.pattern)?)
+    }
+}
+
+/// percent-encodes everything but the unreserved characters of RFC 3986.
+fn encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => {
+                let _ = write!(encoded, "%{byte:02X}");
+            }
+        }
+    }
+
+    encoded
+}
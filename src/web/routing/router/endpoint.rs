@@ -1,4 +1,11 @@
-use crate::web::routing::{ResolutionFnRef, middleware::MiddlewareCollection};
+use std::time::Duration;
+
+use crate::web::{
+    StatusCode,
+    guards::RouteGuards,
+    response_cache::ResponseCache,
+    routing::{ResolutionFnRef, middleware::MiddlewareCollection},
+};
 
 
 /// ## End Point
@@ -10,19 +17,80 @@ use crate::web::routing::{ResolutionFnRef, middleware::MiddlewareCollection};
 /// 
 /// A collection of middleware that is checked.
 /// 
+/// #### Named middleware
+/// 
+/// Names of middleware registered on the app with `App::register_middleware`, called in order of their priority.
+/// 
 /// #### A resolution
 /// 
 /// The resolution that is called once the middleware has completed.
+/// 
+/// #### Fallbacks
+/// 
+/// Resolutions tried in order when the previous one passes, see `PassResolution`.
+/// 
+/// #### Response cache (optional)
+/// 
+/// Fresh responses are sent from the cache before the middleware is checked, see `App::cache_route`.
+/// 
+/// #### Guards (optional)
+/// 
+/// Checks on the body and headers of requests made before the middleware is checked, see `App::guard_route`.
+/// 
+/// #### Timeout (optional)
+/// 
+/// The time the middleware and the resolution have together before the status is served instead, see `App::timeout_route`.
+#[derive(Clone)]
 pub struct EndPoint {
     pub middleware: Option<MiddlewareCollection>,
-    pub resolution: ResolutionFnRef
+    pub named_middleware: Vec<String>,
+    pub resolution: ResolutionFnRef,
+    pub fallbacks: Vec<ResolutionFnRef>,
+    pub response_cache: Option<ResponseCache>,
+    pub guards: Option<RouteGuards>,
+    pub timeout: Option<(Duration, StatusCode)>,
 }
 
 impl EndPoint {
     pub fn new(resolution: ResolutionFnRef, middleware: Option<MiddlewareCollection>) -> Self {
         Self {
             middleware,
-            resolution
+            named_middleware: Vec::new(),
+            resolution,
+            fallbacks: Vec::new(),
+            response_cache: None,
+            guards: None,
+            timeout: None,
         }
     }
+
+    /// Sets the names of the registered middleware called for this endpoint.
+    pub fn with_named_middleware(mut self, names: Vec<String>) -> Self {
+        self.named_middleware = names;
+        self
+    }
+
+    /// Sets the resolutions tried in order when the resolution passes, for example a disk cache, then a generator, then an upstream proxy.
+    pub fn with_fallbacks(mut self, fallbacks: Vec<ResolutionFnRef>) -> Self {
+        self.fallbacks = fallbacks;
+        self
+    }
+
+    /// Sends fresh responses of the endpoint from the cache.
+    pub fn with_response_cache(mut self, cache: ResponseCache) -> Self {
+        self.response_cache = Some(cache);
+        self
+    }
+
+    /// Checks requests of the endpoint with the guards before the middleware.
+    pub fn with_guards(mut self, guards: RouteGuards) -> Self {
+        self.guards = Some(guards);
+        self
+    }
+
+    /// Serves `status` when the middleware and the resolution of the endpoint do not complete within `duration`.
+    pub fn with_timeout(mut self, duration: Duration, status: StatusCode) -> Self {
+        self.timeout = Some((duration, status));
+        self
+    }
 }
\ No newline at end of file
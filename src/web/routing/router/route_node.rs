@@ -92,6 +92,111 @@ impl RouteNode {
         self.resolutions.insert(method, Arc::new(endpoint));
     }
 
+    /// # Pattern
+    ///
+    /// The route pattern of the node, found by walking its parents, for example `/users/{id}`.
+    pub async fn pattern(node_ref: &RouteNodeRef) -> String {
+        let mut ids = Vec::new();
+        let mut current = Some(node_ref.clone());
+
+        while let Some(node) = current {
+            let guard = node.lock().await;
+
+            //the root node has no parent and is the leading "/"
+            if guard.parent.is_some() {
+                ids.push(guard.id.clone());
+            }
+
+            current = guard.parent.clone();
+        }
+
+        ids.reverse();
+
+        format!("/{}", ids.join("/"))
+    }
+
+    /// # Variables
+    ///
+    /// The values of the variables of the node's pattern in a cleaned route it matched, a wildcard `{*}` takes the rest of the route.
+    pub async fn variables(node_ref: &RouteNodeRef, route: &str) -> HashMap<String, String> {
+        let mut variables = HashMap::new();
+
+        //the given route by the user, cleaned.
+        let mut given_route_parts: Vec<&str> = route.split('/').collect();
+
+        let mut current_ref = Some(node_ref.clone());
+
+        let wild_card_skip = {
+            let mut current = Some(node_ref.clone());
+            let mut wild_skip = 0;
+
+            while let Some(node) = current {
+                let guard = node.lock().await;
+                current = guard.parent.clone();
+                wild_skip += 1;
+            }
+
+            //skip for the WILDCARD {*} and SKIP for the beginning "/" route.
+            wild_skip - 1
+        };
+
+        while let Some(c_ref) = current_ref {
+            //pop a route part
+            let route_part = given_route_parts.pop();
+
+            //if none, something is wrong, break out
+            if route_part.is_none() {
+                break;
+            }
+
+            //unwrap the route part
+            let route_part = route_part.unwrap();
+
+            //check if the route part is empty, we are allowed to continue from this
+            if route_part.is_empty() {
+                //since we own c_ref and have not locked, we can just reuse.
+                //we need to pass into some for ownership
+                current_ref = Some(c_ref);
+                continue;
+            }
+
+            //lock for checks
+            let c_ref_lock = c_ref.lock().await;
+
+            if c_ref_lock.is_var {
+                //clean the ID from {name} -> name
+                let mut id = c_ref_lock.id.clone();
+                id.remove(0);
+                id.remove(id.len() - 1);
+
+                let is_wild = id.eq("*");
+
+                let value = if is_wild {
+                    given_route_parts.push(route_part);
+
+                    given_route_parts
+                        .iter()
+                        .skip(wild_card_skip)
+                        .copied()
+                        .collect::<Vec<&str>>()
+                        .join("/")
+                } else {
+                    route_part.to_string()
+                };
+
+                variables.insert(id, value);
+
+                if is_wild {
+                    break;
+                }
+            }
+
+            current_ref = c_ref_lock.parent.clone();
+        }
+
+        variables
+    }
+
     /// # Add Child
     /// 
     /// Takes the parent reference node, has an ID for the route name, and an optional endpoint.
@@ -1,12 +1,51 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use tokio::sync::Mutex;
 
-use crate::web::{EndPoint, Method, errors::RoutingError};
+use crate::web::{EndPoint, Method, Route, errors::RoutingError};
 
 use crate::web::routing::RouteNodeRef;
 use crate::web::routing::router::route_node::RouteNode;
 
+/// # Match Kind
+///
+/// How a path was matched by `RouteTree::match_route`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchKind {
+    /// A route matched each part of the path, through its literal or variable parts.
+    Route,
+
+    /// A wildcard `{*}` route took the rest of the path.
+    Wildcard,
+
+    /// No route matched, the missing route answers the request.
+    MissingRoute,
+
+    /// No route matched and there is no missing route.
+    None,
+}
+
+/// # Match Result
+///
+/// What the router would do with a request, reported by `RouteTree::match_route` without calling middleware or a handler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchResult {
+    /// How the path was matched.
+    pub kind: MatchKind,
+
+    /// The pattern of the matched route, for example `/users/{id}`, None if no route matched.
+    pub pattern: Option<String>,
+
+    /// The variables taken from the path, as handlers see them in `Request::variables`.
+    pub variables: HashMap<String, String>,
+
+    /// The methods the matched route (or missing route) answers, sorted by name.
+    pub methods: Vec<Method>,
+
+    /// True if a handler would be called for the method, the missing route answers every method with its `GET` handler.
+    pub handled: bool,
+}
+
 /// # Route tree
 ///
 /// Trie based tree that separates a given route into nodes and contains information about their nodes such as:
@@ -121,35 +160,32 @@ impl RouteTree {
             //checks if this the last element in the iteration
             let is_last = route_parts.peek().is_none();
 
-            //checks if the node has a child for the rte_part
-            let has_child = {
+            //checks if the node has a child for the rte_part, a variable child is shared by routes with the same variable.
+            let existing = {
                 let node_lock = node.lock().await;
-                node_lock.children.contains_key(rte_part)
+
+                match node_lock.brw_child(rte_part) {
+                    Some(child) => Some(child),
+                    None => match node_lock.var_child.clone() {
+                        Some(var_child) if var_child.lock().await.id == rte_part => Some(var_child),
+                        _ => None,
+                    },
+                }
             };
 
             //check if the child on this route exist.
-            if has_child {
-                //clone the nnode values
-                let node_clone = node.clone();
-                let brw_node = node_clone.lock().await;
-
+            if let Some(child) = existing {
                 //omsert the endpoint to the route, then return ok(), since this is the last item
                 if is_last {
                     //check if there is an endpoint to add
                     if let Some((m, r)) = end_point {
-                        brw_node
-                            .brw_child(rte_part)
-                            .unwrap()
-                            .lock()
-                            .await
-                            .insert_resolution(m, r);
+                        child.lock().await.insert_resolution(m, r);
                     }
                     return Ok(());
                 }
 
-                //if not the last, brw the child and clone for next iteration
-                let child = brw_node.brw_child(rte_part).unwrap();
-                node = child.clone();
+                //if not the last, use the child for next iteration
+                node = child;
 
                 continue;
             }
@@ -257,4 +293,66 @@ impl RouteTree {
 
         return current_node;
     }
+
+    /// # Match Route
+    ///
+    /// Reports which route a request for the path and method would be routed to, and the variables it would carry, without calling anything.
+    ///
+    /// The path is cleaned as the path of a request is, it may carry a query.
+    ///
+    /// ```ignore
+    /// let result = app.get_router().await.match_route("/users/7?full=true", &Method::GET).await;
+    ///
+    /// assert_eq!(result.pattern.as_deref(), Some("/users/{id}"));
+    /// assert_eq!(result.variables["id"], "7");
+    /// ```
+    pub async fn match_route(&self, path: &str, method: &Method) -> MatchResult {
+        let cleaned_route = Route::parse_route(path.to_string()).cleaned_route;
+
+        let Some(node_ref) = self.get_route(&cleaned_route).await else {
+            let methods = self
+                .missing_route
+                .as_ref()
+                .map(methods)
+                .unwrap_or_default();
+
+            let kind = match self.missing_route {
+                Some(_) => MatchKind::MissingRoute,
+                None => MatchKind::None,
+            };
+
+            return MatchResult {
+                kind,
+                pattern: None,
+                variables: HashMap::new(),
+                handled: methods.contains(&Method::GET),
+                methods,
+            };
+        };
+
+        let pattern = RouteNode::pattern(&node_ref).await;
+        let variables = RouteNode::variables(&node_ref, &cleaned_route).await;
+
+        let node = node_ref.lock().await;
+
+        let kind = match node.id == "{*}" {
+            true => MatchKind::Wildcard,
+            false => MatchKind::Route,
+        };
+
+        MatchResult {
+            kind,
+            pattern: Some(pattern),
+            variables,
+            handled: node.brw_resolution(method).is_some(),
+            methods: methods(&node),
+        }
+    }
+}
+
+/// the methods a node answers, sorted by name.
+fn methods(node: &RouteNode) -> Vec<Method> {
+    let mut methods: Vec<Method> = node.resolutions.keys().cloned().collect();
+    methods.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    methods
 }
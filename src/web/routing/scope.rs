@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use crate::web::{Method, Middleware, Request, routing::middleware::MiddlewareClosure};
+
+/// # Scope
+///
+/// Limits which requests a middleware is called for, by path and method.
+///
+/// Path patterns use the route syntax:
+///
+/// * `/health` matches only `/health`
+/// * `/users/{id}` or `/users/*` match one segment
+/// * `/public/{*}` or `/public/**` match everything under `/public`
+///
+/// A request is in scope if it matches any include (or there are none), matches no exclude, and its method is allowed (or no methods are set).
+///
+/// ```ignore
+/// let scope = Scope::new().exclude("/health").exclude("/public/{*}");
+///
+/// app.use_middleware_scoped(bearer(tokens), scope).await;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Scope {
+    include: Vec<String>,
+    exclude: Vec<String>,
+    methods: Vec<Method>,
+}
+
+impl Scope {
+    /// Create a scope that contains every request.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only requests matching one of the included patterns are in scope.
+    pub fn include(mut self, pattern: &str) -> Self {
+        self.include.push(pattern.to_string());
+        self
+    }
+
+    /// Requests matching the pattern are not in scope.
+    pub fn exclude(mut self, pattern: &str) -> Self {
+        self.exclude.push(pattern.to_string());
+        self
+    }
+
+    /// Only requests with one of the methods are in scope.
+    pub fn method(mut self, method: Method) -> Self {
+        self.methods.push(method);
+        self
+    }
+
+    /// # Contains
+    ///
+    /// Returns true if a request with the path and method is in scope.
+    pub fn contains(&self, path: &str, method: &Method) -> bool {
+        let included =
+            self.include.is_empty() || self.include.iter().any(|p| pattern_matches(p, path));
+        let excluded = self.exclude.iter().any(|p| pattern_matches(p, path));
+        let method_allowed = self.methods.is_empty() || self.methods.contains(method);
+
+        included && !excluded && method_allowed
+    }
+}
+
+/// matches a path against a route pattern, segment by segment.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let mut pattern = pattern.split('/').filter(|s| !s.is_empty());
+    let mut path = path.split('/').filter(|s| !s.is_empty());
+
+    loop {
+        match (pattern.next(), path.next()) {
+            (None, None) => return true,
+            (Some("{*}") | Some("**"), _) => return true,
+            (Some(p), Some(s)) => {
+                let wildcard = p == "*" || (p.starts_with('{') && p.ends_with('}'));
+
+                if !wildcard && p != s {
+                    return false;
+                }
+            }
+            _ => return false,
+        }
+    }
+}
+
+/// # Scoped
+///
+/// Wraps middleware so it is only called for requests in the scope, requests outside of it continue with `Middleware::Next`.
+pub fn scoped(closure: MiddlewareClosure, scope: Scope) -> MiddlewareClosure {
+    //built by hand, out of scope requests skip the wrapped middleware without calling it.
+    Arc::new(move |req: &mut Request| {
+        if !scope.contains(&req.route.cleaned_route, &req.method) {
+            return Box::pin(async { Middleware::Next });
+        }
+
+        closure(req)
+    })
+}
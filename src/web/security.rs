@@ -0,0 +1,138 @@
+use aes_gcm::aead::{OsRng, rand_core::RngCore};
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+use crate::web::{Middleware, middleware_fn, routing::middleware::MiddlewareClosure};
+
+/// The placeholder in a Content-Security-Policy replaced with the nonce of the request.
+pub const NONCE_PLACEHOLDER: &str = "{nonce}";
+
+/// # Csp Nonce
+///
+/// A random, single use nonce placed in the request extensions by the [`security_headers`] middleware.
+///
+/// Templates add it to inline scripts and styles, `<script nonce="...">`, so they are allowed by a strict policy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CspNonce(pub String);
+
+impl CspNonce {
+    /// Create a new nonce from 16 random bytes, base64 encoded.
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; 16];
+        OsRng.fill_bytes(&mut bytes);
+
+        Self(STANDARD.encode(bytes))
+    }
+
+    /// The nonce as a str.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for CspNonce {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// # Security Headers
+///
+/// The headers set by the [`security_headers`] middleware, None leaves a header unset.
+#[derive(Debug, Clone)]
+pub struct SecurityHeaders {
+    /// The `Content-Security-Policy`, each `{nonce}` is replaced with the nonce of the request.
+    ///
+    /// By default (`default-src 'self'; script-src 'self' 'nonce-{nonce}'; style-src 'self' 'nonce-{nonce}'; object-src 'none'; base-uri 'self'; frame-ancestors 'none'`)
+    pub content_security_policy: Option<String>,
+    /// By default (`nosniff`)
+    pub content_type_options: Option<String>,
+    /// By default (`DENY`)
+    pub frame_options: Option<String>,
+    /// By default (`strict-origin-when-cross-origin`)
+    pub referrer_policy: Option<String>,
+    /// The `Strict-Transport-Security` header, only set it when served over TLS.
+    ///
+    /// By default (None)
+    pub strict_transport_security: Option<String>,
+}
+
+impl Default for SecurityHeaders {
+    fn default() -> Self {
+        Self {
+            content_security_policy: Some(
+                "default-src 'self'; script-src 'self' 'nonce-{nonce}'; style-src 'self' 'nonce-{nonce}'; object-src 'none'; base-uri 'self'; frame-ancestors 'none'"
+                    .to_string(),
+            ),
+            content_type_options: Some("nosniff".to_string()),
+            frame_options: Some("DENY".to_string()),
+            referrer_policy: Some("strict-origin-when-cross-origin".to_string()),
+            strict_transport_security: None,
+        }
+    }
+}
+
+impl SecurityHeaders {
+    /// Sets the `Content-Security-Policy`, use `{nonce}` where the nonce of the request belongs.
+    pub fn content_security_policy(mut self, policy: &str) -> Self {
+        self.content_security_policy = Some(policy.to_string());
+        self
+    }
+
+    /// Sets `Strict-Transport-Security` to `max-age` seconds, including subdomains.
+    pub fn hsts(mut self, max_age: u64) -> Self {
+        self.strict_transport_security = Some(format!("max-age={max_age}; includeSubDomains"));
+        self
+    }
+
+    /// The `Content-Security-Policy` with the nonce filled in, None if no policy is set.
+    pub fn policy_with_nonce(&self, nonce: &CspNonce) -> Option<String> {
+        self.content_security_policy
+            .as_ref()
+            .map(|policy| policy.replace(NONCE_PLACEHOLDER, nonce.as_str()))
+    }
+}
+
+/// # Security Headers
+///
+/// Middleware that sets the security headers of responses, generating a [`CspNonce`] for each request and reflecting it in the `Content-Security-Policy`.
+///
+/// The nonce is read in handlers with `Request::csp_nonce`.
+///
+/// ```ignore
+/// app.use_middleware(security_headers(SecurityHeaders::default())).await;
+///
+/// app.add_or_panic("/", Method::GET, None, |req| async move {
+///     let nonce = req.lock().await.csp_nonce().unwrap_or_default().to_string();
+///
+///     //the template writes <script nonce="{{ nonce }}">
+///     IndexPage { nonce }.resolve()
+/// })
+/// .await;
+/// ```
+pub fn security_headers(headers: SecurityHeaders) -> MiddlewareClosure {
+    middleware_fn(move |req| {
+        let headers = headers.clone();
+
+        Box::pin(async move {
+            let nonce = CspNonce::generate();
+
+            let values = [
+                ("Content-Security-Policy", headers.policy_with_nonce(&nonce)),
+                ("X-Content-Type-Options", headers.content_type_options),
+                ("X-Frame-Options", headers.frame_options),
+                ("Referrer-Policy", headers.referrer_policy),
+                ("Strict-Transport-Security", headers.strict_transport_security),
+            ];
+
+            for (name, value) in values {
+                if value.is_some() {
+                    req.add_header(name.to_string(), value);
+                }
+            }
+
+            req.extensions.insert(nonce);
+
+            Middleware::Next
+        })
+    })
+}
@@ -0,0 +1,180 @@
+use std::{io, net::SocketAddr, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use socket2::{Domain, Protocol, SockRef, Socket, TcpKeepalive, Type};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+/// # Socket Options
+///
+/// The options of the sockets of an app, see `App::bind_with`.
+///
+/// `reuse_address`, `reuse_port`, and `backlog` are set on each listener when it is bound, `nodelay` and the keepalive options on each accepted connection.
+///
+/// ```toml
+/// [socket]
+/// reuse_port = true
+/// nodelay = true
+/// keepalive = 60
+/// backlog = 4096
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SocketOptions {
+    /// Sets `SO_REUSEADDR`, so the port may be bound again while old connections close.
+    ///
+    /// By default (true), except on Windows where it allows stealing the port.
+    pub reuse_address: bool,
+
+    /// Sets `SO_REUSEPORT`, so several processes may bind the same port and the system balances connections between them.
+    ///
+    /// Only supported on Unix, binding fails elsewhere.
+    ///
+    /// By default (false)
+    pub reuse_port: bool,
+
+    /// Sets `TCP_NODELAY`, sending small responses without waiting to coalesce them.
+    ///
+    /// By default (false)
+    pub nodelay: bool,
+
+    /// Sends keepalive probes once a connection idles for this many seconds, closing dead peers.
+    ///
+    /// By default (None) no probes are sent.
+    pub keepalive: Option<u64>,
+
+    /// The seconds between keepalive probes, ignored on platforms that can not set it.
+    ///
+    /// By default (None) the system default.
+    pub keepalive_interval: Option<u64>,
+
+    /// The unanswered keepalive probes before the connection is dropped, ignored on platforms that can not set it.
+    ///
+    /// By default (None) the system default.
+    pub keepalive_retries: Option<u32>,
+
+    /// The connections the system queues before they are accepted.
+    ///
+    /// By default (1024)
+    pub backlog: i32,
+}
+
+impl Default for SocketOptions {
+    fn default() -> Self {
+        Self {
+            reuse_address: cfg!(not(windows)),
+            reuse_port: false,
+            nodelay: false,
+            keepalive: None,
+            keepalive_interval: None,
+            keepalive_retries: None,
+            backlog: 1024,
+        }
+    }
+}
+
+impl SocketOptions {
+    /// True if any option is set on accepted connections.
+    pub fn applies_to_connections(&self) -> bool {
+        self.nodelay || self.keepalive.is_some()
+    }
+
+    /// # Bind
+    ///
+    /// Binds a listener with the options to the first address that binds, like `TcpListener::bind`.
+    pub async fn bind<A>(&self, addr: A) -> Result<TcpListener, io::Error>
+    where
+        A: ToSocketAddrs,
+    {
+        let mut last_error = None;
+
+        for addr in tokio::net::lookup_host(addr).await? {
+            match self.listen(addr) {
+                Ok(listener) => return Ok(listener),
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        Err(last_error.unwrap_or(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "could not resolve to any address",
+        )))
+    }
+
+    /// # Listen
+    ///
+    /// Binds a listener to the address with the options, an IPv6 address only accepts IPv6.
+    pub fn listen(&self, addr: SocketAddr) -> Result<TcpListener, io::Error> {
+        let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+
+        if addr.is_ipv6() {
+            socket.set_only_v6(true)?;
+        }
+
+        socket.set_reuse_address(self.reuse_address)?;
+
+        if self.reuse_port {
+            #[cfg(all(
+                unix,
+                not(any(target_os = "solaris", target_os = "illumos", target_os = "cygwin"))
+            ))]
+            socket.set_reuse_port(true)?;
+
+            #[cfg(not(all(
+                unix,
+                not(any(target_os = "solaris", target_os = "illumos", target_os = "cygwin"))
+            )))]
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "SO_REUSEPORT is not supported on this platform",
+            ));
+        }
+
+        socket.bind(&addr.into())?;
+        socket.listen(self.backlog)?;
+        socket.set_nonblocking(true)?;
+
+        TcpListener::from_std(socket.into())
+    }
+
+    /// # Apply
+    ///
+    /// Sets the connection options on an accepted stream.
+    pub fn apply(&self, stream: &TcpStream) -> Result<(), io::Error> {
+        if self.nodelay {
+            stream.set_nodelay(true)?;
+        }
+
+        if let Some(idle) = self.keepalive {
+            SockRef::from(stream).set_tcp_keepalive(&self.tcp_keepalive(idle))?;
+        }
+
+        Ok(())
+    }
+
+    /// the keepalive of the options, with the interval and retries where the platform sets them.
+    #[allow(unused_mut)]
+    fn tcp_keepalive(&self, idle: u64) -> TcpKeepalive {
+        let mut keepalive = TcpKeepalive::new().with_time(Duration::from_secs(idle));
+
+        #[cfg(any(
+            target_os = "android",
+            target_os = "freebsd",
+            target_os = "ios",
+            target_os = "linux",
+            target_os = "macos",
+            target_os = "netbsd",
+            target_os = "windows",
+        ))]
+        {
+            if let Some(interval) = self.keepalive_interval {
+                keepalive = keepalive.with_interval(Duration::from_secs(interval));
+            }
+
+            if let Some(retries) = self.keepalive_retries {
+                keepalive = keepalive.with_retries(retries);
+            }
+        }
+
+        keepalive
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    path::{Path, PathBuf},
+    pin::Pin,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use futures::Stream;
+
+use crate::web::{
+    Resolution, StatusCode,
+    headers::HeaderMap,
+    resolution::{
+        empty_resolution::EmptyResolution, file_resolution::FileResolution,
+        text_resolution::TextResolution,
+    },
+};
+
+/// # Static Files
+///
+/// Serves the files of a directory, see `App::serve_static`.
+///
+/// * paths containing `..`, `.` segments, backslashes, or leaving the root through a symlink are rejected with `400`
+/// * a directory is served by its index file, `index.html`, or a listing when listings are on
+/// * files are sent with their MIME type, an ETag of their size and modification time, and `Cache-Control`, a matching `If-None-Match` receives `304`
+///
+/// ```ignore
+/// let files = StaticFiles::new("./public")
+///     .listing(true)
+///     .max_age(Duration::from_secs(3600));
+///
+/// app.serve_static_with("/assets", files).await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct StaticFiles {
+    root: PathBuf,
+    index: Option<String>,
+    listing: bool,
+    max_age: Option<Duration>,
+}
+
+impl StaticFiles {
+    /// Serves the directory with `index.html` as the index, without listings, revalidating every request.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            index: Some("index.html".to_string()),
+            listing: false,
+            max_age: None,
+        }
+    }
+
+    /// The file served for a directory, None serves no index.
+    pub fn index(mut self, index: Option<&str>) -> Self {
+        self.index = index.map(str::to_string);
+        self
+    }
+
+    /// Lists the files of directories without an index.
+    pub fn listing(mut self, listing: bool) -> Self {
+        self.listing = listing;
+        self
+    }
+
+    /// How long clients may cache files without revalidating, sent as `Cache-Control: public, max-age`.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// # Resolution
+    ///
+    /// The file at `path` below the root, `path` is the wildcard of the route, `css/site.css`.
+    ///
+    /// `url` is the path the client requested, used for the links of a listing.
+    pub async fn resolution(
+        &self,
+        path: &str,
+        url: &str,
+        if_none_match: Option<&str>,
+    ) -> Box<dyn Resolution + Send + 'static> {
+        let Some(relative) = safe_path(path) else {
+            return EmptyResolution::status(StatusCode::BAD_REQUEST).resolve();
+        };
+
+        //symlinks may point outside of the root.
+        let (Ok(root), Ok(mut full)) = (
+            tokio::fs::canonicalize(&self.root).await,
+            tokio::fs::canonicalize(self.root.join(&relative)).await,
+        ) else {
+            return EmptyResolution::status(StatusCode::NOT_FOUND).resolve();
+        };
+
+        if !full.starts_with(&root) {
+            return EmptyResolution::status(StatusCode::BAD_REQUEST).resolve();
+        }
+
+        if full.is_dir() {
+            let index = self
+                .index
+                .as_ref()
+                .map(|index| full.join(index))
+                .filter(|index| index.is_file());
+
+            match index {
+                Some(index) => full = index,
+                None if self.listing => return listing(&full, url).await,
+                None => return EmptyResolution::status(StatusCode::NOT_FOUND).resolve(),
+            }
+        }
+
+        let Ok(metadata) = tokio::fs::metadata(&full).await else {
+            return EmptyResolution::status(StatusCode::NOT_FOUND).resolve();
+        };
+
+        let etag = file_etag(&metadata);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("ETag", Some(etag.clone()));
+        headers.insert(
+            "Cache-Control",
+            Some(match self.max_age {
+                Some(max_age) => format!("public, max-age={}", max_age.as_secs()),
+                None => "no-cache".to_string(),
+            }),
+        );
+
+        if if_none_match.is_some_and(|tags| etag_matches(tags, &etag)) {
+            return StaticFile {
+                file: None,
+                headers,
+            }
+            .resolve();
+        }
+
+        StaticFile {
+            file: Some(FileResolution::new(&full.to_string_lossy())),
+            headers,
+        }
+        .resolve()
+    }
+}
+
+/// # File ETag
+///
+/// The weak ETag of a file from its size and modification time, `W/"{size:x}-{mtime:x}"`, also used by `web::manifest::AssetManifest`.
+pub fn file_etag(metadata: &std::fs::Metadata) -> String {
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    format!("W/\"{:x}-{modified:x}\"", metadata.len())
+}
+
+/// # ETag Matches
+///
+/// True if the value of an `If-None-Match` header, a list of tags or `*`, matches the ETag. Tags are compared weakly, ignoring `W/`.
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = opaque(etag);
+
+    if_none_match
+        .split(',')
+        .any(|tag| tag.trim() == "*" || opaque(tag) == etag)
+}
+
+/// the path below the root, None if it is unsafe.
+fn safe_path(path: &str) -> Option<PathBuf> {
+    let mut relative = PathBuf::new();
+
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        if segment == ".." || segment == "." || segment.contains(['\\', '\0', ':']) {
+            return None;
+        }
+
+        relative.push(segment);
+    }
+
+    Some(relative)
+}
+
+/// an html list of the entries of a directory.
+async fn listing(dir: &Path, url: &str) -> Box<dyn Resolution + Send + 'static> {
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return EmptyResolution::status(StatusCode::NOT_FOUND).resolve();
+    };
+
+    let mut names = Vec::new();
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let is_dir = entry.file_type().await.is_ok_and(|t| t.is_dir());
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        names.push(if is_dir { format!("{name}/") } else { name });
+    }
+
+    names.sort();
+
+    let base = url.trim_end_matches('/');
+    let title = escape(if base.is_empty() { "/" } else { base });
+
+    let mut html = format!("<!DOCTYPE html><html><head><title>{title}</title></head><body><h1>{title}</h1><ul>");
+
+    for name in names {
+        html.push_str(&format!(
+            "<li><a href=\"{}/{}\">{}</a></li>",
+            escape(base),
+            escape(&name),
+            escape(&name)
+        ));
+    }
+
+    html.push_str("</ul></body></html>");
+
+    let mut resolution = TextResolution::new(&html);
+    resolution.set_content_type("text/html; charset=utf-8");
+
+    resolution.resolve()
+}
+
+/// escapes text placed in html.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// a file with its cache headers, `304` without a file.
+struct StaticFile {
+    file: Option<FileResolution>,
+    headers: HeaderMap,
+}
+
+impl Resolution for StaticFile {
+    fn get_headers(&self) -> HeaderMap {
+        let mut hmap = match &self.file {
+            Some(file) => file.get_headers(),
+            None => EmptyResolution::status(StatusCode::NOT_MODIFIED).get_headers(),
+        };
+
+        hmap.merge(self.headers.clone());
+
+        hmap
+    }
+
+    fn get_content(&self) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        match &self.file {
+            Some(file) => file.get_content(),
+            None => EmptyResolution::status(StatusCode::NOT_MODIFIED).get_content(),
+        }
+    }
+
+    fn resolve(self) -> Box<dyn Resolution + Send + 'static> {
+        Box::new(self)
+    }
+
+    fn seekable_len(&self) -> Option<u64> {
+        self.file.as_ref().and_then(|file| file.seekable_len())
+    }
+
+    fn seek(&self, offset: u64) -> Pin<Box<dyn Stream<Item = Vec<u8>> + Send>> {
+        match &self.file {
+            Some(file) => file.seek(offset),
+            None => self.get_content(),
+        }
+    }
+
+    fn source_file(&self) -> Option<PathBuf> {
+        self.file.as_ref().and_then(|file| file.source_file())
+    }
+}
@@ -0,0 +1,199 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use tokio::sync::Mutex;
+
+use crate::web::{Resolution, resolution::json_resolution::JsonResolution};
+
+/// the route of requests that did not match any route.
+pub const UNMATCHED_ROUTE: &str = "(unmatched)";
+
+/// # Last Error
+///
+/// The most recent server error of a route.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LastError {
+    /// The panic message, or the status line of the response.
+    pub message: String,
+    /// Seconds since the unix epoch.
+    pub timestamp: u64,
+}
+
+/// # Route Snapshot
+///
+/// The counters of a single route pattern, for example `/users/{id}`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RouteSnapshot {
+    pub route: String,
+    pub requests: u64,
+    /// `2xx` responses.
+    pub success: u64,
+    /// `3xx` responses.
+    pub redirects: u64,
+    /// `4xx` responses.
+    pub client_errors: u64,
+    /// `5xx` responses.
+    pub server_errors: u64,
+    /// Median latency of the recent requests, in milliseconds.
+    pub p50_ms: f64,
+    /// 95th percentile latency of the recent requests, in milliseconds.
+    pub p95_ms: f64,
+    pub last_error: Option<LastError>,
+}
+
+/// counters of a single route.
+#[derive(Default)]
+struct RouteCounters {
+    success: u64,
+    redirects: u64,
+    client_errors: u64,
+    server_errors: u64,
+    other: u64,
+    latencies: VecDeque<Duration>,
+    last_error: Option<LastError>,
+}
+
+impl RouteCounters {
+    fn snapshot(&self, route: &str) -> RouteSnapshot {
+        let mut latencies: Vec<Duration> = self.latencies.iter().copied().collect();
+        latencies.sort();
+
+        RouteSnapshot {
+            route: route.to_string(),
+            requests: self.success
+                + self.redirects
+                + self.client_errors
+                + self.server_errors
+                + self.other,
+            success: self.success,
+            redirects: self.redirects,
+            client_errors: self.client_errors,
+            server_errors: self.server_errors,
+            p50_ms: percentile(&latencies, 50),
+            p95_ms: percentile(&latencies, 95),
+            last_error: self.last_error.clone(),
+        }
+    }
+}
+
+/// the nearest-rank percentile of sorted latencies, in milliseconds.
+fn percentile(sorted: &[Duration], percent: usize) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+
+    let rank = (sorted.len() * percent).div_ceil(100).max(1);
+
+    sorted[rank - 1].as_secs_f64() * 1000.0
+}
+
+/// # Route Stats
+///
+/// Rolling counters of each route pattern, so a failing endpoint can be spotted without reading logs.
+///
+/// Status counts are kept for the life of the app, latency percentiles are over the last `window` requests of the route.
+///
+/// The app records every request, see `App::route_stats` and `App::serve_route_stats`.
+///
+/// Cloning shares the same counters.
+#[derive(Clone)]
+pub struct RouteStats {
+    window: usize,
+    routes: Arc<Mutex<HashMap<String, RouteCounters>>>,
+}
+
+impl RouteStats {
+    /// Create stats keeping the latency of the last `window` requests of each route.
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(1),
+            routes: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// # Record
+    ///
+    /// Records a response of the route, `error` is kept as the last error of a `5xx` response (for example a panic message), otherwise the status line is.
+    pub async fn record(&self, route: &str, status: &str, latency: Duration, error: Option<String>) {
+        let code: u16 = status
+            .split_whitespace()
+            .next()
+            .and_then(|code| code.parse().ok())
+            .unwrap_or(0);
+
+        let mut routes = self.routes.lock().await;
+        let counters = routes.entry(route.to_string()).or_default();
+
+        match code {
+            200..=299 => counters.success += 1,
+            300..=399 => counters.redirects += 1,
+            400..=499 => counters.client_errors += 1,
+            500..=599 => counters.server_errors += 1,
+            _ => counters.other += 1,
+        }
+
+        if code >= 500 {
+            counters.last_error = Some(LastError {
+                message: error.unwrap_or_else(|| status.to_string()),
+                timestamp: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0),
+            });
+        }
+
+        if counters.latencies.len() >= self.window {
+            counters.latencies.pop_front();
+        }
+
+        counters.latencies.push_back(latency);
+    }
+
+    /// The counters of a route pattern, None if it has no requests.
+    pub async fn route(&self, route: &str) -> Option<RouteSnapshot> {
+        self.routes
+            .lock()
+            .await
+            .get(route)
+            .map(|counters| counters.snapshot(route))
+    }
+
+    /// The counters of every route, ordered by route.
+    pub async fn snapshot(&self) -> Vec<RouteSnapshot> {
+        let routes = self.routes.lock().await;
+
+        let mut snapshot: Vec<RouteSnapshot> = routes
+            .iter()
+            .map(|(route, counters)| counters.snapshot(route))
+            .collect();
+
+        snapshot.sort_by(|a, b| a.route.cmp(&b.route));
+        snapshot
+    }
+
+    /// Forgets the counters of every route.
+    pub async fn reset(&self) {
+        self.routes.lock().await.clear();
+    }
+
+    /// # Resolution
+    ///
+    /// A JSON resolution of the snapshot.
+    pub async fn resolution(&self) -> Box<dyn Resolution + Send + 'static> {
+        match JsonResolution::serialize(self.snapshot().await) {
+            Ok(json) => json.resolve(),
+            Err(e) => e.resolve(),
+        }
+    }
+}
+
+impl Default for RouteStats {
+    /// Stats keeping the latency of the last 1,024 requests of each route.
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
@@ -1,3 +1,3 @@
 pub mod streamed_file;
 
-pub use streamed_file::stream_file;
\ No newline at end of file
+pub use streamed_file::{stream_file, stream_file_from};
\ No newline at end of file
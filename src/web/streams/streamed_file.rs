@@ -1,6 +1,8 @@
+use std::io::SeekFrom;
+
 use async_stream::stream;
 use futures::{Stream, StreamExt};
-use tokio::fs::File;
+use tokio::{fs::File, io::AsyncSeekExt};
 use tokio_util::io::ReaderStream;
 
 /// # Stream File
@@ -9,6 +11,13 @@ use tokio_util::io::ReaderStream;
 /// 
 /// Turns a file path into a stream.
 pub fn stream_file(file_path: String) -> impl Stream<Item = Vec<u8>> {
+    stream_file_from(file_path, 0)
+}
+
+/// # Stream File From
+///
+/// Same as `stream_file`, but the stream starts `offset` bytes into the file.
+pub fn stream_file_from(file_path: String, offset: u64) -> impl Stream<Item = Vec<u8>> {
     stream! {
     let f = File::open(file_path).await;
 
@@ -16,7 +25,12 @@ pub fn stream_file(file_path: String) -> impl Stream<Item = Vec<u8>> {
                 return ;
             }
 
-            let f = f.unwrap();
+            let mut f = f.unwrap();
+
+            //start from the offset
+            if offset > 0 && f.seek(SeekFrom::Start(offset)).await.is_err() {
+                return;
+            }
 
             //make streamed reader from file
             let mut reader = ReaderStream::new(f);
@@ -0,0 +1,104 @@
+use std::{net::SocketAddr, time::Duration};
+
+use crate::web::Method;
+
+/// # Span
+///
+/// The span of a connection or of a request, with the `tracing` feature.
+///
+/// Without the feature spans do nothing, so the app does not depend on `tracing` to be built.
+#[derive(Clone)]
+pub(crate) struct Span {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl Span {
+    /// the span of a connection, carrying the address of the client.
+    pub(crate) fn connection(client: SocketAddr) -> Self {
+        #[cfg(not(feature = "tracing"))]
+        let _ = client;
+
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::info_span!("connection", client = %client),
+        }
+    }
+
+    /// the span of a request, its status and latency are recorded once it is answered, see `finish`.
+    pub(crate) fn request(method: &Method, path: &str) -> Self {
+        #[cfg(not(feature = "tracing"))]
+        let _ = (method, path);
+
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::info_span!(
+                "request",
+                method = %method,
+                path = path,
+                otel.kind = "server",
+                trace_id = tracing::field::Empty,
+                status = tracing::field::Empty,
+                latency_ms = tracing::field::Empty,
+            ),
+        }
+    }
+
+    /// the span being entered, so work started from a request stays in its span.
+    pub(crate) fn current() -> Self {
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::Span::current(),
+        }
+    }
+
+    /// runs the future in the span.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn instrument<F: Future>(&self, future: F) -> impl Future<Output = F::Output> + use<F> {
+        tracing::Instrument::instrument(future, self.span.clone())
+    }
+
+    /// runs the future in the span.
+    #[cfg(not(feature = "tracing"))]
+    pub(crate) fn instrument<F: Future>(&self, future: F) -> impl Future<Output = F::Output> + use<F> {
+        future
+    }
+
+    /// records the status and latency of the request, with an event of the request being answered.
+    pub(crate) fn finish(&self, status: &str, latency: Duration) {
+        #[cfg(not(feature = "tracing"))]
+        let _ = (status, latency);
+
+        #[cfg(feature = "tracing")]
+        {
+            let latency_ms = latency.as_secs_f64() * 1000.0;
+
+            self.span.record("status", status);
+            self.span.record("latency_ms", latency_ms);
+
+            tracing::info!(parent: &self.span, status, latency_ms, "answered");
+        }
+    }
+}
+
+/// # Error
+///
+/// Writes an error of the app, as a `tracing` event with the feature, to stderr without it.
+pub(crate) fn error(message: &str) {
+    #[cfg(feature = "tracing")]
+    tracing::error!("{message}");
+
+    #[cfg(not(feature = "tracing"))]
+    eprintln!("{message}");
+}
+
+/// # Info
+///
+/// Writes a message of the app, as a `tracing` event with the feature, to stdout without it.
+pub(crate) fn info(message: &str) {
+    #[cfg(feature = "tracing")]
+    tracing::info!("{message}");
+
+    #[cfg(not(feature = "tracing"))]
+    println!("{message}");
+}
@@ -0,0 +1,294 @@
+use std::net::SocketAddr;
+
+use serde::{Serialize, de::DeserializeOwned};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::web::{
+    HeaderMap, Method, StatusCode,
+    app::{AppContext, serve_stream},
+};
+
+/// the size of the in memory connection, larger requests and responses are written as the other side reads them.
+const CONNECTION_BUFFER: usize = 64 * 1024;
+
+/// # Test Client
+///
+/// Sends requests through the routing, middleware, and resolutions of an app over an in memory connection, so tests do not bind a port or need an HTTP client, see `App::test_client`.
+///
+/// Each request is read and answered by the same code as requests of real clients, the connection hooks (see `App::on_connection`) are not run.
+///
+/// ```ignore
+/// let client = app.test_client();
+///
+/// let response = client
+///     .post("/tasks")
+///     .header("Authorization", "Bearer token")
+///     .json(&Task { name: "write tests".to_string() })?
+///     .send()
+///     .await?;
+///
+/// assert_eq!(response.status(), StatusCode::CREATED);
+/// let task: Task = response.json()?;
+/// ```
+#[derive(Clone)]
+pub struct TestClient {
+    context: AppContext,
+    client_socket: SocketAddr,
+}
+
+impl TestClient {
+    /// creates a client of the app with the context.
+    pub(crate) fn new(context: AppContext) -> Self {
+        Self {
+            context,
+            client_socket: SocketAddr::from(([127, 0, 0, 1], 40000)),
+        }
+    }
+
+    /// # With Client Socket
+    ///
+    /// Sets the address requests appear to come from.
+    ///
+    /// By default (127.0.0.1:40000)
+    pub fn with_client_socket(mut self, client_socket: SocketAddr) -> Self {
+        self.client_socket = client_socket;
+        self
+    }
+
+    /// Start a request with the method and path, the path may carry a query.
+    pub fn request(&self, method: Method, path: &str) -> TestRequest<'_> {
+        TestRequest {
+            client: self,
+            method,
+            path: path.to_string(),
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Start a `GET` request.
+    pub fn get(&self, path: &str) -> TestRequest<'_> {
+        self.request(Method::GET, path)
+    }
+
+    /// Start a `POST` request.
+    pub fn post(&self, path: &str) -> TestRequest<'_> {
+        self.request(Method::POST, path)
+    }
+
+    /// Start a `PUT` request.
+    pub fn put(&self, path: &str) -> TestRequest<'_> {
+        self.request(Method::PUT, path)
+    }
+
+    /// Start a `PATCH` request.
+    pub fn patch(&self, path: &str) -> TestRequest<'_> {
+        self.request(Method::PATCH, path)
+    }
+
+    /// Start a `DELETE` request.
+    pub fn delete(&self, path: &str) -> TestRequest<'_> {
+        self.request(Method::DELETE, path)
+    }
+}
+
+/// # Test Request
+///
+/// A request of a [`TestClient`], sent with `send`.
+pub struct TestRequest<'a> {
+    client: &'a TestClient,
+    method: Method,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl TestRequest<'_> {
+    /// Adds a header, `Host`, `Content-Length`, and `Connection` are set by the client unless they are added.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Sets the body.
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Sets the body to the value as JSON, with the `application/json` content type.
+    pub fn json<T: Serialize>(self, value: &T) -> Result<Self, serde_json::Error> {
+        let body = serde_json::to_vec(value)?;
+
+        Ok(self.header("Content-Type", "application/json").body(body))
+    }
+
+    /// # Send
+    ///
+    /// Sends the request and reads the response, an Err if the app closed the connection without a valid response.
+    pub async fn send(self) -> Result<TestResponse, std::io::Error> {
+        let head = self.head();
+        let (client, server) = tokio::io::duplex(CONNECTION_BUFFER);
+        let (mut reader, mut writer) = tokio::io::split(client);
+
+        let serve = serve_stream(
+            server,
+            self.client.client_socket,
+            self.client.context.clone(),
+        );
+
+        //the request is written while the app reads it, the writer is held until the response is read so the app does not see the client close.
+        let write = async {
+            writer.write_all(head.as_bytes()).await?;
+            writer.write_all(&self.body).await?;
+            writer.flush().await
+        };
+
+        let mut response = Vec::new();
+        let read = reader.read_to_end(&mut response);
+
+        let (served, _, read) = tokio::join!(serve, write, read);
+        read?;
+
+        match (TestResponse::parse(&response), served) {
+            (Some(response), _) => Ok(response),
+            (None, Err(e)) => Err(std::io::Error::other(e.to_string())),
+            (None, Ok(())) => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "the app did not send a valid response",
+            )),
+        }
+    }
+
+    /// the request line and headers.
+    fn head(&self) -> String {
+        let mut head = format!("{} {} HTTP/1.1\r\n", self.method.as_str(), self.path);
+
+        let has = |name: &str| {
+            self.headers
+                .iter()
+                .any(|(n, _)| n.eq_ignore_ascii_case(name))
+        };
+
+        if !has("Host") {
+            head.push_str("Host: localhost\r\n");
+        }
+
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{name}: {value}\r\n"));
+        }
+
+        if !has("Content-Length") && !self.body.is_empty() {
+            head.push_str(&format!("Content-Length: {}\r\n", self.body.len()));
+        }
+
+        if !has("Connection") {
+            head.push_str("Connection: close\r\n");
+        }
+
+        head.push_str("\r\n");
+        head
+    }
+}
+
+/// # Test Response
+///
+/// The response to a [`TestRequest`], informational (1xx) responses sent before it are skipped.
+#[derive(Debug, Clone)]
+pub struct TestResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+impl TestResponse {
+    /// The status of the response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// The headers of the response.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// The first value of a header, None if it is missing.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)
+    }
+
+    /// The body, without any chunked encoding.
+    pub fn bytes(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// The body as text, invalid UTF-8 is replaced.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).to_string()
+    }
+
+    /// Deserializes the body as JSON.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_slice(&self.body)
+    }
+
+    /// parses the bytes the app wrote, None if they are not a response.
+    fn parse(mut raw: &[u8]) -> Option<Self> {
+        loop {
+            let end = raw.windows(4).position(|w| w == b"\r\n\r\n")?;
+            let head = std::str::from_utf8(&raw[..end]).ok()?;
+            raw = &raw[end + 4..];
+
+            let mut lines = head.split("\r\n");
+            let status = lines.next()?.split(' ').nth(1)?.parse::<u16>().ok()?;
+            let status = StatusCode::new(status).ok()?;
+
+            if status.is_informational() {
+                continue;
+            }
+
+            let headers: HeaderMap = lines
+                .filter_map(|line| line.split_once(':'))
+                .map(|(name, value)| (name.trim(), Some(value.trim().to_string())))
+                .collect();
+
+            let chunked = headers
+                .get("Transfer-Encoding")
+                .is_some_and(|encoding| encoding.eq_ignore_ascii_case("chunked"));
+
+            let body = match headers
+                .get("Content-Length")
+                .and_then(|length| length.parse().ok())
+            {
+                _ if chunked => dechunk(raw)?,
+                Some(length) => raw[..raw.len().min(length)].to_vec(),
+                None => raw.to_vec(),
+            };
+
+            return Some(Self {
+                status,
+                headers,
+                body,
+            });
+        }
+    }
+}
+
+/// decodes a chunked body, trailers are ignored.
+fn dechunk(mut raw: &[u8]) -> Option<Vec<u8>> {
+    let mut body = Vec::new();
+
+    loop {
+        let line = raw.windows(2).position(|w| w == b"\r\n")?;
+        let size = std::str::from_utf8(&raw[..line]).ok()?;
+        let size = usize::from_str_radix(size.split(';').next()?.trim(), 16).ok()?;
+        raw = &raw[line + 2..];
+
+        if size == 0 {
+            return Some(body);
+        }
+
+        body.extend_from_slice(raw.get(..size)?);
+        raw = raw.get(size + 2..)?;
+    }
+}
@@ -0,0 +1,149 @@
+use std::{collections::HashMap, net::IpAddr, sync::Arc, time::Duration};
+
+use tokio::{sync::Mutex, time::Instant};
+
+use crate::web::{Middleware, middleware_fn, routing::middleware::MiddlewareClosure};
+
+/// the state of a token bucket.
+struct Bucket {
+    /// max amount of tokens (bytes) that can be spent at once.
+    capacity: f64,
+    /// tokens currently available.
+    tokens: f64,
+    /// tokens refilled each second.
+    rate: f64,
+    /// last time the bucket was refilled.
+    last: Instant,
+}
+
+/// # Throttle
+///
+/// A token bucket that caps the amount of bytes written to a client per second.
+///
+/// Cloning the throttle shares the same bucket, this allows for a single limit to span many responses (for example per client).
+///
+/// The throttle is applied to a request via `Request::set_throttle`, usually from middleware, see [`throttle`] and [`throttle_per_client`].
+#[derive(Clone)]
+pub struct Throttle {
+    bucket: Arc<Mutex<Bucket>>,
+    capacity: usize,
+}
+
+impl Throttle {
+    /// # New
+    ///
+    /// Creates a new throttle that allows `bytes_per_sec` with bursts of up to `burst` bytes.
+    ///
+    /// Both values are at minimum 1.
+    pub fn new(bytes_per_sec: usize, burst: usize) -> Self {
+        let capacity = burst.max(1);
+
+        Self {
+            bucket: Arc::new(Mutex::new(Bucket {
+                capacity: capacity as f64,
+                tokens: capacity as f64,
+                rate: bytes_per_sec.max(1) as f64,
+                last: Instant::now(),
+            })),
+            capacity,
+        }
+    }
+
+    /// # Per Second
+    ///
+    /// Creates a throttle that allows `bytes_per_sec` with a burst of a quarter second.
+    pub fn per_second(bytes_per_sec: usize) -> Self {
+        Self::new(bytes_per_sec, bytes_per_sec / 4)
+    }
+
+    /// The largest amount of bytes that can be consumed at once.
+    ///
+    /// Writes should be split into pieces no larger than this.
+    pub fn chunk_size(&self) -> usize {
+        self.capacity
+    }
+
+    /// # Consume
+    ///
+    /// Waits until `amount` bytes may be written.
+    ///
+    /// `amount` is capped to the chunk size of the throttle.
+    pub async fn consume(&self, amount: usize) {
+        let amount = amount.min(self.capacity) as f64;
+
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().await;
+
+                //refill the bucket based on the time since the last refill.
+                let now = Instant::now();
+                let elapsed = now.duration_since(bucket.last).as_secs_f64();
+                bucket.tokens = (bucket.tokens + elapsed * bucket.rate).min(bucket.capacity);
+                bucket.last = now;
+
+                if bucket.tokens >= amount {
+                    bucket.tokens -= amount;
+                    return;
+                }
+
+                (amount - bucket.tokens) / bucket.rate
+            };
+
+            tokio::time::sleep(Duration::from_secs_f64(wait)).await;
+        }
+    }
+}
+
+/// amount of clients tracked before idle clients are forgotten.
+const MAX_IDLE_CLIENTS: usize = 1024;
+
+/// # Throttle
+///
+/// Middleware that caps each response to `bytes_per_sec`.
+///
+/// Each request receives its own bucket, useful for capping large downloads on a route.
+///
+/// ```ignore
+/// app.add_or_panic("/download", Method::GET, middleware!(throttle(512 * 1024)), |req| async move {
+///     FileResolution::new("big.zip").resolve()
+/// })
+/// .await;
+/// ```
+pub fn throttle(bytes_per_sec: usize) -> MiddlewareClosure {
+    middleware_fn(move |req| Box::pin(async move {
+        req.set_throttle(Throttle::per_second(bytes_per_sec));
+
+        Middleware::Next
+    }))
+}
+
+/// # Throttle Per Client
+///
+/// Middleware that caps the bandwidth of each client (by IP) to `bytes_per_sec`, shared across all of their responses.
+///
+/// This prevents a single client from saturating the uplink with many parallel downloads.
+pub fn throttle_per_client(bytes_per_sec: usize) -> MiddlewareClosure {
+    let clients: Arc<Mutex<HashMap<IpAddr, Throttle>>> = Arc::new(Mutex::new(HashMap::new()));
+
+    middleware_fn(move |req| {
+        let clients = clients.clone();
+
+        Box::pin(async move {
+            let mut clients = clients.lock().await;
+
+            //forget clients that no longer have a response in flight.
+            if clients.len() > MAX_IDLE_CLIENTS {
+                clients.retain(|_, t| Arc::strong_count(&t.bucket) > 1);
+            }
+
+            let throttle = clients
+                .entry(req.client_ip())
+                .or_insert_with(|| Throttle::per_second(bytes_per_sec))
+                .clone();
+
+            req.set_throttle(throttle);
+
+            Middleware::Next
+        })
+    })
+}
@@ -0,0 +1,242 @@
+use std::{
+    collections::HashMap,
+    io::BufReader,
+    sync::{Arc, RwLock},
+};
+
+use rustls::{
+    ServerConfig,
+    crypto::ring::{default_provider, sign::any_supported_type},
+    server::ClientHello,
+    sign::CertifiedKey,
+};
+use tokio_rustls::TlsAcceptor;
+
+use crate::web::errors::TlsError;
+
+pub use rustls::server::ResolvesServerCert;
+
+/// # Certified Key
+///
+/// Parses a PEM certificate chain and PEM private key into a key that can be served.
+pub fn certified_key(cert_pem: &[u8], key_pem: &[u8]) -> Result<CertifiedKey, TlsError> {
+    let certs =
+        rustls_pemfile::certs(&mut BufReader::new(cert_pem)).collect::<Result<Vec<_>, _>>()?;
+
+    if certs.is_empty() {
+        return Err(TlsError::NoCertificates);
+    }
+
+    let key =
+        rustls_pemfile::private_key(&mut BufReader::new(key_pem))?.ok_or(TlsError::NoPrivateKey)?;
+
+    let signing_key = any_supported_type(&key).map_err(|e| TlsError::InvalidKey(e.to_string()))?;
+
+    Ok(CertifiedKey::new(certs, signing_key))
+}
+
+/// # Certificate Store
+///
+/// Holds the certificate served by the TLS listener.
+///
+/// The certificate may be replaced at any time with `load_pem`, new connections use the new certificate without a restart.
+///
+/// ```ignore
+/// let store = Arc::new(CertificateStore::from_files("cert.pem", "key.pem")?);
+///
+/// app.use_tls(store.clone());
+///
+/// //later, after renewing
+/// store.load_pem(&new_cert, &new_key)?;
+/// ```
+#[derive(Debug, Default)]
+pub struct CertificateStore {
+    current: RwLock<Option<Arc<CertifiedKey>>>,
+}
+
+impl CertificateStore {
+    /// Creates an empty store, no connections can be accepted until a certificate is loaded.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a store from a PEM certificate chain and private key.
+    pub fn from_pem(cert_pem: &[u8], key_pem: &[u8]) -> Result<Self, TlsError> {
+        let store = Self::new();
+        store.load_pem(cert_pem, key_pem)?;
+
+        Ok(store)
+    }
+
+    /// Creates a store from PEM files on disk.
+    pub fn from_files(cert_path: &str, key_path: &str) -> Result<Self, TlsError> {
+        let cert = std::fs::read(cert_path)?;
+        let key = std::fs::read(key_path)?;
+
+        Self::from_pem(&cert, &key)
+    }
+
+    /// # Load PEM
+    ///
+    /// Replaces the served certificate.
+    ///
+    /// If the certificate or key is invalid the previous certificate is kept.
+    pub fn load_pem(&self, cert_pem: &[u8], key_pem: &[u8]) -> Result<(), TlsError> {
+        let key = certified_key(cert_pem, key_pem)?;
+        self.set(Arc::new(key));
+
+        Ok(())
+    }
+
+    /// Replaces the served certificate with an already parsed key.
+    pub fn set(&self, key: Arc<CertifiedKey>) {
+        if let Ok(mut current) = self.current.write() {
+            *current = Some(key);
+        }
+    }
+
+    /// Returns true if a certificate is loaded.
+    pub fn is_loaded(&self) -> bool {
+        self.current.read().map(|c| c.is_some()).unwrap_or(false)
+    }
+}
+
+impl ResolvesServerCert for CertificateStore {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        self.current.read().ok()?.clone()
+    }
+}
+
+/// Idiomatic type alias for a closure that selects a certificate from the SNI hostname.
+pub type SniCallback = dyn Fn(&str) -> Option<Arc<CertifiedKey>> + Send + Sync;
+
+/// # SNI Resolver
+///
+/// Selects the certificate of a connection by the hostname the client requested (SNI), allowing many domains to be served from one TLS listener.
+///
+/// Certificates are looked up in order:
+///
+/// i. an exact host match, `api.example.com`
+///
+/// ii. a wildcard host match, `*.example.com`
+///
+/// iii. the callback, for hosts that are only known at runtime
+///
+/// iv. the default certificate, also used when the client sends no SNI
+///
+/// ```ignore
+/// let mut sni = SniResolver::new();
+///
+/// sni.add_host("example.com", Arc::new(CertificateStore::from_files("example.pem", "example.key")?));
+/// sni.add_host("*.example.org", Arc::new(CertificateStore::from_files("org.pem", "org.key")?));
+/// sni.set_callback(|host| tenants.certificate_for(host));
+///
+/// app.use_tls(Arc::new(sni));
+/// ```
+#[derive(Default)]
+pub struct SniResolver {
+    hosts: RwLock<HashMap<String, Arc<CertificateStore>>>,
+    callback: Option<Arc<SniCallback>>,
+    default: Option<Arc<CertificateStore>>,
+}
+
+impl SniResolver {
+    /// Create a resolver with no hosts.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # Add Host
+    ///
+    /// Serves the store's certificate for the host, the host may start with `*.` to match any subdomain.
+    ///
+    /// Hosts can be added while the app is running.
+    pub fn add_host(&self, host: &str, store: Arc<CertificateStore>) {
+        if let Ok(mut hosts) = self.hosts.write() {
+            hosts.insert(host.to_lowercase(), store);
+        }
+    }
+
+    /// Stops serving a host, returning its store.
+    pub fn remove_host(&self, host: &str) -> Option<Arc<CertificateStore>> {
+        self.hosts.write().ok()?.remove(&host.to_lowercase())
+    }
+
+    /// Sets the callback used for hosts that are not added.
+    pub fn set_callback(
+        &mut self,
+        callback: impl Fn(&str) -> Option<Arc<CertifiedKey>> + Send + Sync + 'static,
+    ) {
+        self.callback = Some(Arc::new(callback));
+    }
+
+    /// Sets the certificate used when no host matched.
+    pub fn set_default(&mut self, store: Arc<CertificateStore>) {
+        self.default = Some(store);
+    }
+
+    /// # Resolve Host
+    ///
+    /// Selects the certificate for a hostname, None selects the default.
+    ///
+    /// Stores without a certificate loaded are skipped, as if the host was not added.
+    pub fn resolve_host(&self, host: Option<&str>) -> Option<Arc<CertifiedKey>> {
+        let from_store = |store: &Arc<CertificateStore>| store.current.read().ok()?.clone();
+
+        if let Some(host) = host.map(|h| h.to_lowercase()) {
+            //a host whose store has no certificate loaded yet falls through to the next match.
+            let found = self.hosts.read().ok().and_then(|hosts| {
+                let wildcard = || {
+                    host.split_once('.')
+                        .and_then(|(_, parent)| hosts.get(&format!("*.{parent}")))
+                        .and_then(from_store)
+                };
+
+                hosts.get(&host).and_then(from_store).or_else(wildcard)
+            });
+
+            if let Some(key) = found.or_else(|| self.callback.as_ref().and_then(|cb| cb(&host))) {
+                return Some(key);
+            }
+        }
+
+        self.default.as_ref().and_then(from_store)
+    }
+}
+
+impl std::fmt::Debug for SniResolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hosts: Vec<String> = self
+            .hosts
+            .read()
+            .map(|h| h.keys().cloned().collect())
+            .unwrap_or_default();
+
+        f.debug_struct("SniResolver")
+            .field("hosts", &hosts)
+            .field("callback", &self.callback.is_some())
+            .field("default", &self.default.is_some())
+            .finish()
+    }
+}
+
+impl ResolvesServerCert for SniResolver {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        self.resolve_host(client_hello.server_name())
+    }
+}
+
+/// # Acceptor
+///
+/// Creates the TLS acceptor used by the app, selecting certificates with the `resolver`.
+pub fn acceptor(resolver: Arc<dyn ResolvesServerCert>) -> TlsAcceptor {
+    let mut config = ServerConfig::builder_with_provider(Arc::new(default_provider()))
+        .with_safe_default_protocol_versions()
+        .expect("the ring provider supports the default protocol versions")
+        .with_no_client_auth()
+        .with_cert_resolver(resolver);
+
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    TlsAcceptor::from(Arc::new(config))
+}
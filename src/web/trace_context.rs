@@ -0,0 +1,232 @@
+use crate::web::{Middleware, middleware_fn, routing::middleware::MiddlewareClosure};
+
+/// The W3C header carrying the trace id, the id of the caller's span, and the trace flags.
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// The W3C header carrying vendor specific trace state, passed on unchanged.
+pub const TRACESTATE_HEADER: &str = "tracestate";
+
+/// the longest incoming tracestate that is passed on, the W3C limit is 32 members.
+const MAX_STATE_LENGTH: usize = 512;
+
+/// the trace flag marking a sampled trace.
+const SAMPLED: u8 = 0x01;
+
+/// # Trace Context
+///
+/// The W3C trace context of a request, placed in the request extensions by the [`trace_context`] middleware.
+///
+/// `span_id` is the id of the span of this server, it is the parent of any request made upstream (see `ProxyResolution`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    /// The id of the whole trace.
+    pub trace_id: [u8; 16],
+
+    /// The id of the span of the caller, None if the trace started here.
+    pub parent_id: Option<[u8; 8]>,
+
+    /// The id of the span of this server.
+    pub span_id: [u8; 8],
+
+    /// The trace flags, `0x01` if the trace is sampled.
+    pub flags: u8,
+
+    /// The `tracestate` sent by the caller.
+    pub state: Option<String>,
+}
+
+impl TraceContext {
+    /// Start a new sampled trace, for requests that did not send a `traceparent`.
+    pub fn start() -> Self {
+        Self {
+            trace_id: *uuid::Uuid::new_v4().as_bytes(),
+            parent_id: None,
+            span_id: span_id(),
+            flags: SAMPLED,
+            state: None,
+        }
+    }
+
+    /// Continues the trace of a caller, None if the `traceparent` is malformed.
+    ///
+    /// A `tracestate` is only kept alongside a valid `traceparent`, as the W3C spec requires.
+    pub fn from_incoming(traceparent: &str, tracestate: Option<&str>) -> Option<Self> {
+        let mut parts = traceparent.trim().split('-');
+
+        let version = hex::<1>(parts.next()?)?[0];
+        let trace_id = hex::<16>(parts.next()?)?;
+        let parent_id = hex::<8>(parts.next()?)?;
+        let flags = hex::<1>(parts.next()?)?[0];
+
+        // version 00 has exactly four fields, later versions may append more.
+        let valid = version != 0xff
+            && (version != 0 || parts.next().is_none())
+            && trace_id != [0; 16]
+            && parent_id != [0; 8];
+
+        let state = tracestate
+            .map(str::trim)
+            .filter(|state| {
+                !state.is_empty()
+                    && state.len() <= MAX_STATE_LENGTH
+                    && state.bytes().all(|b| b.is_ascii_graphic() || b == b' ')
+            })
+            .map(str::to_string);
+
+        valid.then(|| Self {
+            trace_id,
+            parent_id: Some(parent_id),
+            span_id: span_id(),
+            flags,
+            state,
+        })
+    }
+
+    /// True if the caller sampled the trace.
+    pub fn sampled(&self) -> bool {
+        self.flags & SAMPLED != 0
+    }
+
+    /// The trace id as lowercase hex, as it appears in logs and tracing backends.
+    pub fn trace_id_hex(&self) -> String {
+        to_hex(&self.trace_id)
+    }
+
+    /// The `traceparent` to send upstream, naming the span of this server as the parent.
+    ///
+    /// With the `otel` feature and an OpenTelemetry layer installed, the id of the OpenTelemetry span of the request is used instead of `span_id`.
+    pub fn traceparent(&self) -> String {
+        #[cfg(feature = "otel")]
+        if let Some((trace_id, span_id, flags)) = otel::current() {
+            return traceparent(&trace_id, &span_id, flags);
+        }
+
+        traceparent(&self.trace_id, &self.span_id, self.flags)
+    }
+}
+
+/// # Trace Context
+///
+/// Middleware that continues the W3C trace of a caller, so this server shows up as a span of the caller's trace.
+///
+/// An incoming `traceparent` (and `tracestate`) is honored, otherwise a new trace is started. The context is stored in the request extensions (see `Request::trace_context`), and `ProxyResolution` sends it upstream so the trace carries on past this server.
+///
+/// With the `tracing` feature the trace id is recorded on the request span, and with the `otel` feature the request span is made a child of the caller's span, for a `tracing-opentelemetry` layer to export.
+///
+/// Add it with `App::use_middleware` before any other middleware.
+///
+/// ```ignore
+/// app.use_middleware(trace_context()).await;
+///
+/// app.add_or_panic("/", Method::GET, None, |req| async move {
+///     let trace = req.lock().await.trace_context().map(|trace| trace.trace_id_hex());
+///     println!("[{trace:?}] handling /");
+///     // --snip--
+/// })
+/// .await;
+/// ```
+pub fn trace_context() -> MiddlewareClosure {
+    middleware_fn(|req| Box::pin(async move {
+        let context = req
+            .request_header(TRACEPARENT_HEADER)
+            .and_then(|parent| {
+                TraceContext::from_incoming(
+                    parent,
+                    req.request_header(TRACESTATE_HEADER).map(String::as_str),
+                )
+            })
+            .unwrap_or_else(TraceContext::start);
+
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("trace_id", context.trace_id_hex());
+
+        #[cfg(feature = "otel")]
+        otel::set_parent(&context);
+
+        req.extensions.insert(context);
+
+        Middleware::Next
+    }))
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use opentelemetry::{
+        Context,
+        trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState},
+    };
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    use super::TraceContext;
+
+    /// makes the caller's span the parent of the current (request) span.
+    pub(super) fn set_parent(context: &TraceContext) {
+        let Some(parent_id) = context.parent_id else {
+            return;
+        };
+
+        let state = context
+            .state
+            .as_deref()
+            .and_then(|state| state.parse::<TraceState>().ok())
+            .unwrap_or_default();
+
+        let parent = SpanContext::new(
+            TraceId::from_bytes(context.trace_id),
+            SpanId::from_bytes(parent_id),
+            TraceFlags::new(context.flags),
+            true,
+            state,
+        );
+
+        // fails only when no OpenTelemetry layer is installed, the trace is then carried by the extension alone.
+        let _ = tracing::Span::current().set_parent(Context::new().with_remote_span_context(parent));
+    }
+
+    /// the trace id, span id, and flags of the OpenTelemetry span of the current span, None without an OpenTelemetry layer.
+    pub(super) fn current() -> Option<([u8; 16], [u8; 8], u8)> {
+        let context = tracing::Span::current().context();
+        let span = context.span();
+        let span = span.span_context();
+
+        span.is_valid().then(|| {
+            (
+                span.trace_id().to_bytes(),
+                span.span_id().to_bytes(),
+                span.trace_flags().to_u8(),
+            )
+        })
+    }
+}
+
+/// a new random span id.
+fn span_id() -> [u8; 8] {
+    let mut id = [0; 8];
+    id.copy_from_slice(&uuid::Uuid::new_v4().as_bytes()[..8]);
+    id
+}
+
+/// parses exactly N bytes of lowercase hex.
+fn hex<const N: usize>(text: &str) -> Option<[u8; N]> {
+    if text.len() != N * 2 || !text.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+        return None;
+    }
+
+    let mut bytes = [0; N];
+
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&text[i * 2..i * 2 + 2], 16).ok()?;
+    }
+
+    Some(bytes)
+}
+
+/// formats a version 00 traceparent.
+fn traceparent(trace_id: &[u8; 16], span_id: &[u8; 8], flags: u8) -> String {
+    format!("00-{}-{}-{flags:02x}", to_hex(trace_id), to_hex(span_id))
+}
+
+/// formats bytes as lowercase hex.
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
@@ -0,0 +1,271 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use socket2::SockRef;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt, Interest},
+    net::TcpStream,
+    sync::Mutex,
+    task::JoinHandle,
+};
+
+use crate::{
+    factory::{WorkManager, WorkerHealth},
+    web::{Resolution, resolution::json_resolution::JsonResolution},
+};
+
+/// Idiomatic type alias for a closure that is called when the workers become wedged.
+pub type WedgedCallback = dyn Fn(&WorkerHealth) + Send + Sync;
+
+/// # Watchdog Config
+///
+/// Settings of the watchdog that detects when every worker is stuck, for example on a deadlocked handler.
+///
+/// Set on the app with `App::set_watchdog`.
+#[derive(Clone)]
+pub struct WatchdogConfig {
+    /// How often the workers are checked.
+    ///
+    /// By default (1 second)
+    pub check_every: Duration,
+
+    /// How long a worker may work on one request before it is counted as stalled.
+    ///
+    /// By default (30 seconds)
+    pub stall_after: Duration,
+
+    /// The max amount of emergency workers added while the workers are wedged, 0 adds none.
+    ///
+    /// They are stopped once they are idle and the workers are no longer wedged.
+    ///
+    /// By default (0)
+    pub max_overflow_workers: usize,
+
+    /// The queue growing on this many checks in a row without any work finishing counts as wedged, 0 disables it.
+    ///
+    /// Catches workers that are stuck for less than `stall_after` each, but never catch up.
+    ///
+    /// By default (3)
+    pub queue_growth_checks: usize,
+
+    /// Called once each time the workers become wedged.
+    pub on_wedged: Option<Arc<WedgedCallback>>,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            check_every: Duration::from_secs(1),
+            stall_after: Duration::from_secs(30),
+            max_overflow_workers: 0,
+            queue_growth_checks: 3,
+            on_wedged: None,
+        }
+    }
+}
+
+/// # Spawn
+///
+/// Spawns the watchdog task, writing each health snapshot into `health`.
+///
+/// While the workers are wedged, overflow workers are added up to `max_overflow_workers`, and retired once they are not.
+pub(crate) fn spawn(
+    work_manager: Arc<Mutex<WorkManager<()>>>,
+    config: WatchdogConfig,
+    health: Arc<Mutex<WorkerHealth>>,
+) -> JoinHandle<()> {
+    tokio::task::spawn(async move {
+        let mut was_wedged = false;
+
+        //how many checks in a row the queue grew without work finishing.
+        let mut growth = 0;
+        let mut last_queued = 0;
+        let mut last_completed = 0;
+
+        loop {
+            tokio::time::sleep(config.check_every).await;
+
+            let mut manager = work_manager.lock().await;
+
+            //workers that stopped are replaced before the snapshot.
+            manager.respawn_dead_workers().await;
+
+            let mut snapshot = manager.health(config.stall_after).await;
+
+            let completed = manager.supervisor().completed();
+            growth = match snapshot.queued > last_queued && completed == last_completed {
+                true => growth + 1,
+                false => 0,
+            };
+            last_queued = snapshot.queued;
+            last_completed = completed;
+
+            if config.queue_growth_checks > 0 && growth >= config.queue_growth_checks {
+                snapshot.wedged = true;
+            }
+
+            if snapshot.wedged {
+                if !was_wedged && let Some(on_wedged) = &config.on_wedged {
+                    on_wedged(&snapshot);
+                }
+
+                //add as many workers as are stalled, at least one for a growing queue, up to the cap.
+                let remaining = config
+                    .max_overflow_workers
+                    .saturating_sub(snapshot.overflow_workers);
+
+                if remaining > 0 {
+                    let added = manager
+                        .add_overflow_workers(snapshot.stalled.max(1).min(remaining))
+                        .await;
+
+                    snapshot.workers += added;
+                    snapshot.overflow_workers += added;
+                }
+            } else if snapshot.overflow_workers > 0 && snapshot.stalled == 0 && snapshot.queued == 0 {
+                //the emergency is over, idle overflow workers are stopped.
+                let retired = manager.retire_overflow_workers().await;
+
+                snapshot.workers -= retired;
+                snapshot.overflow_workers -= retired;
+            }
+
+            drop(manager);
+
+            was_wedged = snapshot.wedged;
+            *health.lock().await = snapshot;
+        }
+    })
+}
+
+/// # Readiness
+///
+/// The resolution of the readiness endpoint, `200` when workers can take new work and `503` when they are wedged.
+///
+/// The body is the [`WorkerHealth`] as JSON.
+pub fn readiness(health: &WorkerHealth) -> Box<dyn Resolution + Send + 'static> {
+    match JsonResolution::serialize(health) {
+        Ok(mut json) => {
+            json.set_status(if health.wedged { 503 } else { 200 });
+            json.resolve()
+        }
+        Err(e) => e.resolve(),
+    }
+}
+
+/// how long a connection is peeked for the request line of a readiness probe.
+const PROBE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// # Readiness Probe
+///
+/// Answers the readiness endpoint outside of the workers while they are wedged, since no worker is free to answer it.
+///
+/// TLS connections can not be peeked, they are always answered by the workers.
+#[derive(Clone)]
+pub(crate) struct ReadinessProbe {
+    path: Arc<RwLock<Option<String>>>,
+    health: Arc<Mutex<WorkerHealth>>,
+}
+
+impl ReadinessProbe {
+    pub(crate) fn new(health: Arc<Mutex<WorkerHealth>>) -> Self {
+        Self {
+            path: Arc::new(RwLock::new(None)),
+            health,
+        }
+    }
+
+    /// the path of the readiness endpoint, see `App::serve_readiness`.
+    pub(crate) fn set_path(&self, path: &str) {
+        *self.path.write().unwrap_or_else(|e| e.into_inner()) = Some(path.to_string());
+    }
+
+    /// true if connections should be peeked for the readiness endpoint, while it is served and the workers are wedged.
+    pub(crate) async fn probes(&self) -> bool {
+        self.path.read().unwrap_or_else(|e| e.into_inner()).is_some() && self.health.lock().await.wedged
+    }
+
+    /// # Intercept
+    ///
+    /// Answers the client with `503` if the workers are wedged and it asks for readiness, waiting up to `PROBE_TIMEOUT` for its request line.
+    ///
+    /// Returns the stream when it was not answered, so it is queued for the workers.
+    pub(crate) async fn intercept(&self, stream: TcpStream) -> Option<TcpStream> {
+        let Some(path) = self.path.read().unwrap_or_else(|e| e.into_inner()).clone() else {
+            return Some(stream);
+        };
+
+        let health = self.health.lock().await.clone();
+
+        if !health.wedged {
+            return Some(stream);
+        }
+
+        let request_line = format!("GET {path} ");
+
+        if !peek_prefix(&stream, request_line.as_bytes()).await {
+            return Some(stream);
+        }
+
+        let body = serde_json::to_vec(&health).unwrap_or_default();
+        let mut stream = stream;
+
+        let _ = tokio::time::timeout(Duration::from_secs(1), async {
+            let head = format!(
+                "HTTP/1.1 503 Service Unavailable\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+
+            stream.write_all(head.as_bytes()).await?;
+            stream.write_all(&body).await?;
+            stream.shutdown().await?;
+
+            //closing with the unread request of the client resets the connection and loses the response.
+            let mut discard = [0u8; 1024];
+            while stream.read(&mut discard).await? > 0 {}
+
+            Ok::<(), std::io::Error>(())
+        })
+        .await;
+
+        None
+    }
+}
+
+/// true if the first bytes the client sent are the prefix, waiting at most `PROBE_TIMEOUT` for them.
+async fn peek_prefix(stream: &TcpStream, prefix: &[u8]) -> bool {
+    //a handle of the same socket, tokio only peeks once data is ready and not once more of it arrived.
+    let Ok(peeker) = SockRef::from(stream).try_clone().map(std::net::TcpStream::from) else {
+        return false;
+    };
+
+    let mut buf = vec![0u8; prefix.len()];
+
+    tokio::time::timeout(PROBE_TIMEOUT, async {
+        loop {
+            if stream.readable().await.is_err() {
+                return false;
+            }
+
+            //a part of the prefix clears the readiness, so the next wait ends once more bytes arrive.
+            let peeked = stream.try_io(Interest::READABLE, || {
+                let read = peeker.peek(&mut buf)?;
+
+                match read > 0 && read < prefix.len() && buf[..read] == prefix[..read] {
+                    true => Err(std::io::ErrorKind::WouldBlock.into()),
+                    false => Ok(read),
+                }
+            });
+
+            match peeked {
+                Ok(read) => return read == prefix.len() && buf == prefix,
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+                Err(_) => return false,
+            }
+        }
+    })
+    .await
+    .unwrap_or(false)
+}